@@ -0,0 +1,33 @@
+use crate::Component;
+use std::any::{Any, TypeId};
+
+/// 一组组件，用于`ECS::spawn_batch`
+pub trait ComponentBundle: 'static {
+    /// 组件的类型，顺序与`for_each_component`一致
+    fn type_ids() -> Vec<TypeId>;
+
+    fn for_each_component<F: FnMut(usize, Box<dyn Any>)>(self, f: F);
+}
+
+macro_rules! impl_bundle_tuple {
+    ($($name:ident $idx:tt),*) => {
+        impl<$($name: Component),*> ComponentBundle for ($($name,)*) {
+            fn type_ids() -> Vec<TypeId> {
+                vec![$(TypeId::of::<$name>()),*]
+            }
+
+            fn for_each_component<Func: FnMut(usize, Box<dyn Any>)>(self, mut f: Func) {
+                $(f($idx, Box::new(self.$idx));)*
+            }
+        }
+    };
+}
+
+impl_bundle_tuple!(A 0);
+impl_bundle_tuple!(A 0, B 1);
+impl_bundle_tuple!(A 0, B 1, C 2);
+impl_bundle_tuple!(A 0, B 1, C 2, D 3);
+impl_bundle_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_bundle_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_bundle_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_bundle_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
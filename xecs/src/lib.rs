@@ -1,11 +1,49 @@
+mod bundle;
+mod query;
+
+pub use bundle::ComponentBundle;
+pub use query::{EntityFilter, Or, With, Without};
 use slab::Slab;
 use std::any::{Any, TypeId};
-use std::collections::HashMap;
-use std::time::Duration;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Hash, Ord, PartialOrd)]
 pub struct EntityId(usize);
 
+impl EntityId {
+    /// 实体在容器中的下标，实体删除后下标会被新的实体重用
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// 实体的弱引用，实体删除后即使id被重用也不会再指向新的实体
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash, Ord, PartialOrd)]
+pub struct WeakEntity {
+    id: EntityId,
+    generation: u32,
+}
+
+impl WeakEntity {
+    pub fn id(&self) -> EntityId {
+        self.id
+    }
+
+    /// 把下标和代数编码成一个整数，用于脚本等只能保存整数的地方，通过`from_bits`还原
+    pub fn to_bits(self) -> u64 {
+        ((self.generation as u64) << 32) | self.id.0 as u64
+    }
+
+    /// 还原`to_bits`编码的弱引用，需要通过`ECS::upgrade`检查实体是否还存在
+    pub fn from_bits(bits: u64) -> WeakEntity {
+        WeakEntity {
+            id: EntityId((bits & 0xFFFF_FFFF) as usize),
+            generation: (bits >> 32) as u32,
+        }
+    }
+}
+
 #[derive(Default)]
 struct ComponentRegistry(HashMap<TypeId, u8>);
 
@@ -19,7 +57,10 @@ impl ComponentRegistry {
     }
 
     fn get_or_create_idx<C: Component>(&mut self) -> u8 {
-        let tid = TypeId::of::<C>();
+        self.get_or_create_idx_by_tid(TypeId::of::<C>())
+    }
+
+    fn get_or_create_idx_by_tid(&mut self, tid: TypeId) -> u8 {
         self.0.get(&tid).map(|idx| *idx).unwrap_or_else(move || {
             let count = self.0.len();
             let idx = count as u8;
@@ -29,8 +70,107 @@ impl ComponentRegistry {
     }
 }
 
+/// 运行时通过名字注册的动态组件
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash, Ord, PartialOrd)]
+pub struct DynamicComponentId(usize);
+
+#[derive(Default)]
+struct DynamicComponentRegistry {
+    names: Vec<String>,
+    ids: HashMap<String, DynamicComponentId>,
+}
+
+impl DynamicComponentRegistry {
+    fn get_or_create(&mut self, name: &str) -> DynamicComponentId {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = DynamicComponentId(self.names.len());
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        id
+    }
+}
+
 struct Entity {
     components: Vec<Option<Box<dyn Any>>>,
+    changed: Vec<u64>,
+    dynamic_components: HashMap<DynamicComponentId, Vec<u8>>,
+}
+
+impl Entity {
+    fn new(capacity: usize) -> Entity {
+        Entity {
+            components: Vec::with_capacity(capacity),
+            changed: Vec::with_capacity(capacity),
+            dynamic_components: Default::default(),
+        }
+    }
+
+    fn set_component(&mut self, idx: u8, c: Box<dyn Any>, tick: u64) {
+        let idx = idx as usize;
+        if self.components.len() <= idx {
+            self.components.resize_with(idx + 1, || None);
+            self.changed.resize(idx + 1, 0);
+        }
+        self.components[idx] = Some(c);
+        self.changed[idx] = tick;
+    }
+}
+
+type RemovedComponents = HashMap<TypeId, Vec<(EntityId, Box<dyn Any>)>>;
+
+type ComponentHook = Box<dyn FnMut(EntityId, &mut dyn Any)>;
+
+/// 复制组件，通过`ECS::register_cloneable`注册
+type ComponentCloner = fn(&dyn Any) -> Box<dyn Any>;
+
+#[derive(Default)]
+struct ComponentHooks {
+    on_add: HashMap<TypeId, Vec<ComponentHook>>,
+    on_remove: HashMap<TypeId, Vec<ComponentHook>>,
+}
+
+impl ComponentHooks {
+    fn call(hooks: &mut HashMap<TypeId, Vec<ComponentHook>>, id: EntityId, c: &mut dyn Any) {
+        if let Some(hooks) = hooks.get_mut(&Any::type_id(c)) {
+            for hook in hooks {
+                hook(id, c);
+            }
+        }
+    }
+
+    fn call_on_add(&mut self, id: EntityId, c: &mut dyn Any) {
+        Self::call(&mut self.on_add, id, c);
+    }
+
+    fn call_on_remove(&mut self, id: EntityId, c: &mut dyn Any) {
+        Self::call(&mut self.on_remove, id, c);
+    }
+
+    fn call_on_add_all(&mut self, id: EntityId, entity: &mut Entity) {
+        for c in entity.components.iter_mut().flatten() {
+            self.call_on_add(id, c.as_mut());
+        }
+    }
+
+    fn call_on_remove_all(&mut self, id: EntityId, entity: &mut Entity) {
+        for c in entity.components.iter_mut().flatten() {
+            self.call_on_remove(id, c.as_mut());
+        }
+    }
+}
+
+fn clone_component<C: Component + Clone>(c: &dyn Any) -> Box<dyn Any> {
+    Box::new(c.downcast_ref::<C>().unwrap().clone())
+}
+
+fn box_hook<C, F>(mut f: F) -> ComponentHook
+where
+    C: Component,
+    F: FnMut(EntityId, &mut C) + 'static,
+{
+    Box::new(move |id, c| f(id, c.downcast_mut::<C>().unwrap()))
 }
 
 pub struct EntityRef<'a> {
@@ -67,12 +207,44 @@ impl<'a> EntityRef<'a> {
             _ => None,
         }
     }
+
+    /// 组件最后一次被添加或者通过`get_mut`获取的时间，参考`ECS::change_tick`
+    pub fn changed_tick<C: Component>(&self) -> Option<u64> {
+        let entity = &self.entity;
+        match self
+            .components_registry
+            .get_idx::<C>()
+            .map(|idx| idx as usize)
+            .and_then(|idx| Some((entity.components.get(idx)?, entity.changed.get(idx)?)))
+        {
+            Some((Some(_), tick)) => Some(*tick),
+            _ => None,
+        }
+    }
+
+    /// 组件是否在`tick`之后被修改过
+    pub fn is_changed_since<C: Component>(&self, tick: u64) -> bool {
+        self.changed_tick::<C>()
+            .map(|changed| changed > tick)
+            .unwrap_or_default()
+    }
+
+    pub fn contains_dynamic(&self, id: DynamicComponentId) -> bool {
+        self.entity.dynamic_components.contains_key(&id)
+    }
+
+    pub fn get_dynamic(&self, id: DynamicComponentId) -> Option<&[u8]> {
+        self.entity.dynamic_components.get(&id).map(Vec::as_slice)
+    }
 }
 
 pub struct EntityMut<'a> {
     id: EntityId,
     components_registry: &'a mut ComponentRegistry,
     events: &'a mut Vec<Event>,
+    removed: &'a mut RemovedComponents,
+    hooks: &'a mut ComponentHooks,
+    change_tick: &'a mut u64,
     entity: &'a mut Entity,
 }
 
@@ -93,18 +265,43 @@ impl<'a> EntityMut<'a> {
         }
     }
 
+    /// 添加组件，已有的同类型组件会像`remove`一样先被删除
     pub fn add<C: Component>(&mut self, c: C) {
         let tid = TypeId::of::<C>();
         let idx = self.components_registry.get_or_create_idx::<C>();
-        self.entity.components[idx as usize] = Some(Box::new(c));
+        if let Some(mut old) = self
+            .entity
+            .components
+            .get_mut(idx as usize)
+            .and_then(Option::take)
+        {
+            self.hooks.call_on_remove(self.id, old.as_mut());
+            self.removed.entry(tid).or_default().push((self.id, old));
+            self.events.push(Event::RemoveComponent(self.id, tid));
+        }
+        *self.change_tick += 1;
+        self.entity
+            .set_component(idx, Box::new(c), *self.change_tick);
+        if let Some(Some(c)) = self.entity.components.get_mut(idx as usize) {
+            self.hooks.call_on_add(self.id, c.as_mut());
+        }
         self.events.push(Event::CreateComponent(self.id, tid));
     }
 
+    /// 删除组件，被删除的组件可以在处理事件时通过`ECS::removed`获取
     pub fn remove<C: Component>(&mut self) {
         let tid = TypeId::of::<C>();
-        let idx = self.components_registry.get_or_create_idx::<C>();
-        self.entity.components[idx as usize] = None;
-        self.events.push(Event::RemoveComponent(self.id, tid));
+        let entity = &mut self.entity;
+        if let Some(mut c) = self
+            .components_registry
+            .get_idx::<C>()
+            .and_then(|idx| entity.components.get_mut(idx as usize))
+            .and_then(Option::take)
+        {
+            self.hooks.call_on_remove(self.id, c.as_mut());
+            self.removed.entry(tid).or_default().push((self.id, c));
+            self.events.push(Event::RemoveComponent(self.id, tid));
+        }
     }
 
     pub fn get<C: Component>(&self) -> Option<&C> {
@@ -119,17 +316,45 @@ impl<'a> EntityMut<'a> {
         }
     }
 
+    /// 获取可变引用时会把组件标记为已修改
     pub fn get_mut<C: Component>(&mut self) -> Option<&mut C> {
         let entity = &mut self.entity;
-        match self
-            .components_registry
-            .get_idx::<C>()
-            .and_then(move |idx| entity.components.get_mut(idx as usize))
-        {
-            Some(Some(ref mut c)) => Some(c.downcast_mut::<C>().unwrap()),
+        let idx = self.components_registry.get_idx::<C>()? as usize;
+        match entity.components.get_mut(idx) {
+            Some(Some(ref mut c)) => {
+                *self.change_tick += 1;
+                entity.changed[idx] = *self.change_tick;
+                Some(c.downcast_mut::<C>().unwrap())
+            }
             _ => None,
         }
     }
+
+    pub fn contains_dynamic(&self, id: DynamicComponentId) -> bool {
+        self.entity.dynamic_components.contains_key(&id)
+    }
+
+    /// 添加动态组件，数据的编码格式由使用者决定
+    pub fn add_dynamic(&mut self, id: DynamicComponentId, data: Vec<u8>) {
+        self.entity.dynamic_components.insert(id, data);
+        self.events.push(Event::CreateDynamicComponent(self.id, id));
+    }
+
+    pub fn remove_dynamic(&mut self, id: DynamicComponentId) -> Option<Vec<u8>> {
+        let data = self.entity.dynamic_components.remove(&id);
+        if data.is_some() {
+            self.events.push(Event::RemoveDynamicComponent(self.id, id));
+        }
+        data
+    }
+
+    pub fn get_dynamic(&self, id: DynamicComponentId) -> Option<&[u8]> {
+        self.entity.dynamic_components.get(&id).map(Vec::as_slice)
+    }
+
+    pub fn get_dynamic_mut(&mut self, id: DynamicComponentId) -> Option<&mut Vec<u8>> {
+        self.entity.dynamic_components.get_mut(&id)
+    }
 }
 
 pub trait System {
@@ -142,14 +367,138 @@ pub enum Event {
     RemoveEntity(EntityId),
     CreateComponent(EntityId, TypeId),
     RemoveComponent(EntityId, TypeId),
+    CreateDynamicComponent(EntityId, DynamicComponentId),
+    RemoveDynamicComponent(EntityId, DynamicComponentId),
     Custom(Box<dyn Any>),
 }
 
+impl Event {
+    /// 如果是指定类型的自定义事件，返回事件的引用
+    pub fn downcast_ref<E: 'static>(&self) -> Option<&E> {
+        match self {
+            Event::Custom(event) => event.downcast_ref::<E>(),
+            _ => None,
+        }
+    }
+}
+
+/// 系统的执行时机
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Schedule {
+    /// 每帧执行一次，参数是帧间隔
+    Update,
+
+    /// 按固定时间步长执行，每帧可能执行0次或者多次，参数是固定时间步长
+    FixedUpdate,
+}
+
+type RunCondition = Box<dyn Fn(&ECS) -> bool>;
+
+/// 保留系统的具体类型，取出系统时可以安全的向下转型
+trait AnySystem: System {
+    fn as_system_mut(&mut self) -> &mut dyn System;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<S: System + 'static> AnySystem for S {
+    fn as_system_mut(&mut self) -> &mut dyn System {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+struct SystemInfo {
+    name: &'static str,
+    order: usize,
+    schedule: Schedule,
+    enabled: bool,
+    time: Duration,
+    run_condition: Option<RunCondition>,
+    system: Box<dyn AnySystem>,
+}
+
+/// 系统在上一帧的耗时，包括所有的固定步长和处理事件的时间
+#[derive(Debug, Clone)]
+pub struct SystemTiming {
+    pub name: &'static str,
+    pub schedule: Schedule,
+    pub order: usize,
+    pub time: Duration,
+}
+
+/// 添加系统后返回，用于进一步配置系统
+pub struct SystemConfig<'a> {
+    info: &'a mut SystemInfo,
+}
+
+impl<'a> SystemConfig<'a> {
+    /// 只有条件满足时才执行系统的`update`，`handle_event`不受影响
+    pub fn run_if<F>(self, condition: F) -> Self
+    where
+        F: Fn(&ECS) -> bool + 'static,
+    {
+        self.info.run_condition = Some(Box::new(condition));
+        self
+    }
+}
+
 struct ECSInner {
     entities: Slab<Entity>,
+    generations: Vec<u32>,
     components_registry: ComponentRegistry,
-    systems: HashMap<TypeId, (usize, Box<dyn System>)>,
+    dynamic_components_registry: DynamicComponentRegistry,
+    systems: HashMap<TypeId, SystemInfo>,
+
+    /// 正在执行的系统
+    running_systems: Vec<TypeId>,
+
+    /// 执行期间删除了自己的系统，执行结束后不再放回容器
+    pending_removals: HashSet<TypeId>,
+
     events: Vec<Event>,
+    removed: RemovedComponents,
+    dispatching_removed: RemovedComponents,
+    hooks: ComponentHooks,
+    cloners: HashMap<TypeId, ComponentCloner>,
+    fixed_timestep: Duration,
+    fixed_accumulator: Duration,
+    max_fixed_steps: usize,
+    change_tick: u64,
+    resources: HashMap<TypeId, Box<dyn Any>>,
+    timings: Vec<SystemTiming>,
+}
+
+impl ECSInner {
+    fn next_change_tick(&mut self) -> u64 {
+        self.change_tick += 1;
+        self.change_tick
+    }
+
+    fn generation(&self, id: EntityId) -> u32 {
+        self.generations.get(id.0).copied().unwrap_or_default()
+    }
+
+    fn remove_entity(&mut self, id: EntityId) -> Entity {
+        let entity = self.entities.remove(id.0);
+        if self.generations.len() <= id.0 {
+            self.generations.resize(id.0 + 1, 0);
+        }
+        self.generations[id.0] = self.generations[id.0].wrapping_add(1);
+        entity
+    }
 }
 
 pub struct EntityBuilder<'a> {
@@ -160,12 +509,21 @@ pub struct EntityBuilder<'a> {
 impl<'a> EntityBuilder<'a> {
     pub fn component<C: Component>(mut self, c: C) -> Self {
         let idx = self.ecs_inner.components_registry.get_or_create_idx::<C>();
-        self.entity.components[idx as usize] = Some(Box::new(c));
+        let tick = self.ecs_inner.next_change_tick();
+        self.entity.set_component(idx, Box::new(c), tick);
+        self
+    }
+
+    pub fn dynamic_component(mut self, id: DynamicComponentId, data: Vec<u8>) -> Self {
+        self.entity.dynamic_components.insert(id, data);
         self
     }
 
     pub fn finish(self) -> EntityId {
         let id = EntityId(self.ecs_inner.entities.insert(self.entity));
+        self.ecs_inner
+            .hooks
+            .call_on_add_all(id, &mut self.ecs_inner.entities[id.0]);
         self.ecs_inner.events.push(Event::CreateEntity(id));
         id
     }
@@ -180,9 +538,23 @@ impl ECS {
         ECS {
             inner: ECSInner {
                 entities: Default::default(),
+                generations: Default::default(),
                 components_registry: Default::default(),
+                dynamic_components_registry: Default::default(),
                 systems: Default::default(),
+                running_systems: Default::default(),
+                pending_removals: Default::default(),
                 events: Default::default(),
+                removed: Default::default(),
+                dispatching_removed: Default::default(),
+                hooks: Default::default(),
+                cloners: Default::default(),
+                fixed_timestep: Duration::from_secs(1) / 60,
+                fixed_accumulator: Duration::default(),
+                max_fixed_steps: 8,
+                change_tick: 0,
+                resources: Default::default(),
+                timings: Default::default(),
             },
         }
     }
@@ -190,17 +562,170 @@ impl ECS {
     pub fn create_entity(&mut self) -> EntityBuilder {
         EntityBuilder {
             ecs_inner: &mut self.inner,
-            entity: Entity {
-                components: Vec::with_capacity(32),
-            },
+            entity: Entity::new(32),
+        }
+    }
+
+    /// 批量创建实体，比逐个使用`create_entity`快
+    pub fn spawn_batch<B, I>(&mut self, iter: I) -> Vec<EntityId>
+    where
+        B: ComponentBundle,
+        I: IntoIterator<Item = B>,
+    {
+        let inner = &mut self.inner;
+        let indices = B::type_ids()
+            .into_iter()
+            .map(|tid| inner.components_registry.get_or_create_idx_by_tid(tid))
+            .collect::<Vec<_>>();
+        let components_len = indices
+            .iter()
+            .max()
+            .map(|idx| *idx as usize + 1)
+            .unwrap_or(0);
+
+        let iter = iter.into_iter();
+        let (count, _) = iter.size_hint();
+        inner.entities.reserve(count);
+        inner.events.reserve(count);
+
+        let mut ids = Vec::with_capacity(count);
+        for bundle in iter {
+            let mut entity = Entity::new(components_len);
+            let tick = inner.next_change_tick();
+            bundle.for_each_component(|i, c| entity.set_component(indices[i], c, tick));
+
+            let id = EntityId(inner.entities.insert(entity));
+            inner.hooks.call_on_add_all(id, &mut inner.entities[id.0]);
+            inner.events.push(Event::CreateEntity(id));
+            ids.push(id);
         }
+        ids
+    }
+
+    /// 注册可以被`clone_entity`复制的组件类型
+    pub fn register_cloneable<C: Component + Clone>(&mut self) {
+        self.inner
+            .cloners
+            .insert(TypeId::of::<C>(), clone_component::<C>);
+    }
+
+    /// 复制实体，只复制注册过的组件类型和所有的动态组件
+    pub fn clone_entity(&mut self, id: EntityId) -> Option<EntityId> {
+        let inner = &mut self.inner;
+        let src = inner.entities.get(id.0)?;
+        let mut entity = Entity::new(src.components.len());
+        entity.dynamic_components = src.dynamic_components.clone();
+        let tick = inner.change_tick + 1;
+        inner.change_tick = tick;
+        for (idx, c) in src.components.iter().enumerate() {
+            if let Some(c) = c {
+                if let Some(cloner) = inner.cloners.get(&c.as_ref().type_id()) {
+                    entity.set_component(idx as u8, cloner(c.as_ref()), tick);
+                }
+            }
+        }
+
+        let new_id = EntityId(inner.entities.insert(entity));
+        inner
+            .hooks
+            .call_on_add_all(new_id, &mut inner.entities[new_id.0]);
+        inner.events.push(Event::CreateEntity(new_id));
+        Some(new_id)
     }
 
     pub fn remove_entity(&mut self, id: EntityId) {
-        self.inner.entities.remove(id.0);
+        let mut entity = self.inner.remove_entity(id);
+        self.inner.hooks.call_on_remove_all(id, &mut entity);
+        for c in entity.components.into_iter().flatten() {
+            self.inner
+                .removed
+                .entry(c.as_ref().type_id())
+                .or_default()
+                .push((id, c));
+        }
         self.inner.events.push(Event::RemoveEntity(id));
     }
 
+    /// 把实体连同所有组件从`src`移动到`dst`，返回在`dst`中的新id
+    ///
+    /// `src`会产生`Event::RemoveEntity`事件，`dst`会产生`Event::CreateEntity`事件，
+    /// 被移动的组件不会出现在`src`的`removed`中，但会触发`src`的`on_remove`和`dst`的`on_add`
+    pub fn migrate_entity(src: &mut ECS, dst: &mut ECS, id: EntityId) -> Option<EntityId> {
+        if !src.contains(id) {
+            return None;
+        }
+
+        let mut entity = src.inner.remove_entity(id);
+        src.inner.hooks.call_on_remove_all(id, &mut entity);
+        src.inner.events.push(Event::RemoveEntity(id));
+
+        let mut new_entity = Entity::new(entity.components.len());
+        let tick = dst.inner.next_change_tick();
+        for c in entity.components.into_iter().flatten() {
+            let idx = dst
+                .inner
+                .components_registry
+                .get_or_create_idx_by_tid(c.as_ref().type_id());
+            new_entity.set_component(idx, c, tick);
+        }
+        for (id, data) in entity.dynamic_components {
+            // 动态组件的id在不同的世界中可能不同，按名字重新注册
+            let name = &src.inner.dynamic_components_registry.names[id.0];
+            let new_id = dst.inner.dynamic_components_registry.get_or_create(name);
+            new_entity.dynamic_components.insert(new_id, data);
+        }
+
+        let new_id = EntityId(dst.inner.entities.insert(new_entity));
+        dst.inner
+            .hooks
+            .call_on_add_all(new_id, &mut dst.inner.entities[new_id.0]);
+        dst.inner.events.push(Event::CreateEntity(new_id));
+        Some(new_id)
+    }
+
+    /// 组件被添加到实体时立即调用，可以修改组件
+    pub fn on_add<C, F>(&mut self, f: F)
+    where
+        C: Component,
+        F: FnMut(EntityId, &mut C) + 'static,
+    {
+        self.inner
+            .hooks
+            .on_add
+            .entry(TypeId::of::<C>())
+            .or_default()
+            .push(box_hook(f));
+    }
+
+    /// 组件从实体删除时立即调用，包括随实体一起删除的组件
+    pub fn on_remove<C, F>(&mut self, f: F)
+    where
+        C: Component,
+        F: FnMut(EntityId, &mut C) + 'static,
+    {
+        self.inner
+            .hooks
+            .on_remove
+            .entry(TypeId::of::<C>())
+            .or_default()
+            .push(box_hook(f));
+    }
+
+    /// 发送自定义事件，所有系统都会在`handle_event`中收到`Event::Custom`
+    pub fn send_event<E: 'static>(&mut self, event: E) {
+        self.inner.events.push(Event::Custom(Box::new(event)));
+    }
+
+    /// 在处理事件期间获取被删除的组件，包括随实体一起删除的组件
+    pub fn removed<C: Component>(&self) -> impl Iterator<Item = (EntityId, &C)> {
+        self.inner
+            .dispatching_removed
+            .get(&TypeId::of::<C>())
+            .into_iter()
+            .flatten()
+            .map(|(id, c)| (*id, c.downcast_ref::<C>().unwrap()))
+    }
+
     pub fn entity(&self, id: EntityId) -> Option<EntityRef> {
         let components_registry = &self.inner.components_registry;
         self.inner.entities.get(id.0).map(|entity| EntityRef {
@@ -210,7 +735,78 @@ impl ECS {
         })
     }
 
-    pub fn entities(&self) -> impl Iterator<Item = EntityRef> {
+    /// 按名字注册动态组件，重复注册返回相同的id
+    pub fn register_dynamic_component(&mut self, name: &str) -> DynamicComponentId {
+        self.inner.dynamic_components_registry.get_or_create(name)
+    }
+
+    pub fn dynamic_component_id(&self, name: &str) -> Option<DynamicComponentId> {
+        self.inner
+            .dynamic_components_registry
+            .ids
+            .get(name)
+            .copied()
+    }
+
+    pub fn dynamic_component_name(&self, id: DynamicComponentId) -> Option<&str> {
+        self.inner
+            .dynamic_components_registry
+            .names
+            .get(id.0)
+            .map(String::as_str)
+    }
+
+    /// 所有存活的实体id
+    pub fn entities(&self) -> impl Iterator<Item = EntityId> + '_ {
+        self.inner.entities.iter().map(|(id, _)| EntityId(id))
+    }
+
+    pub fn contains(&self, id: EntityId) -> bool {
+        self.inner.entities.contains(id.0)
+    }
+
+    /// 通过`EntityId::index`查找存活的实体，下标被重用后返回的是新的实体
+    pub fn entity_id_at(&self, index: usize) -> Option<EntityId> {
+        if self.inner.entities.contains(index) {
+            Some(EntityId(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn weak(&self, id: EntityId) -> Option<WeakEntity> {
+        if self.contains(id) {
+            Some(WeakEntity {
+                id,
+                generation: self.inner.generation(id),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// 弱引用指向的实体是否还存在
+    pub fn is_alive(&self, weak: WeakEntity) -> bool {
+        self.contains(weak.id) && self.inner.generation(weak.id) == weak.generation
+    }
+
+    pub fn upgrade(&self, weak: WeakEntity) -> Option<EntityId> {
+        if self.is_alive(weak) {
+            Some(weak.id)
+        } else {
+            None
+        }
+    }
+
+    /// 删除所有的实体，每个实体都会产生`Event::RemoveEntity`事件
+    pub fn clear(&mut self) {
+        let ids = self.entities().collect::<Vec<_>>();
+        for id in ids {
+            self.remove_entity(id);
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = EntityRef> {
         let components_registry = &self.inner.components_registry;
         self.inner
             .entities
@@ -222,8 +818,22 @@ impl ECS {
             })
     }
 
+    /// 遍历满足过滤条件的实体
+    ///
+    /// ```ignore
+    /// for entity in ecs.query::<(With<ComponentTransform>, Without<Dead>)>() {
+    ///     ...
+    /// }
+    /// ```
+    pub fn query<F: EntityFilter>(&self) -> impl Iterator<Item = EntityRef<'_>> {
+        self.iter().filter(|entity| F::matches(entity))
+    }
+
     pub fn entity_mut(&mut self, id: EntityId) -> Option<EntityMut> {
         let events = &mut self.inner.events;
+        let removed = &mut self.inner.removed;
+        let hooks = &mut self.inner.hooks;
+        let change_tick = &mut self.inner.change_tick;
         let components_registry = &mut self.inner.components_registry;
         self.inner
             .entities
@@ -232,52 +842,638 @@ impl ECS {
                 id,
                 components_registry,
                 events,
+                removed,
+                hooks,
+                change_tick,
                 entity,
             })
     }
 
+    /// 当前的修改计数，之后添加或者通过`get_mut`获取的组件的`changed_tick`都会比它大
+    pub fn change_tick(&self) -> u64 {
+        self.inner.change_tick
+    }
+
     pub fn entities_count(&self) -> usize {
         self.inner.entities.len()
     }
 
-    pub fn system<T: 'static>(&self) -> Option<&T> {
-        unsafe {
-            self.inner
-                .systems
-                .get(&TypeId::of::<T>())
-                .map(|item| (&*(item.1.as_ref() as *const dyn System as *const T)))
+    /// 添加系统，`order`越小越先执行，同类型的系统会被替换
+    pub fn add_system<S: System + 'static>(&mut self, order: usize, system: S) -> SystemConfig<'_> {
+        self.add_system_with_schedule(Schedule::Update, order, system)
+    }
+
+    /// 添加按固定时间步长执行的系统
+    pub fn add_fixed_system<S: System + 'static>(
+        &mut self,
+        order: usize,
+        system: S,
+    ) -> SystemConfig<'_> {
+        self.add_system_with_schedule(Schedule::FixedUpdate, order, system)
+    }
+
+    pub fn add_system_with_schedule<S: System + 'static>(
+        &mut self,
+        schedule: Schedule,
+        order: usize,
+        system: S,
+    ) -> SystemConfig<'_> {
+        let tid = TypeId::of::<S>();
+        self.inner.systems.insert(
+            tid,
+            SystemInfo {
+                name: std::any::type_name::<S>(),
+                order,
+                schedule,
+                enabled: true,
+                time: Duration::default(),
+                run_condition: None,
+                system: Box::new(system),
+            },
+        );
+        SystemConfig {
+            info: self.inner.systems.get_mut(&tid).unwrap(),
+        }
+    }
+
+    /// 删除系统，返回被删除的系统
+    ///
+    /// 系统在自己的`update`或者`handle_event`中删除自己时返回`None`，执行结束后系统会被丢弃
+    pub fn remove_system<S: System + 'static>(&mut self) -> Option<S> {
+        let tid = TypeId::of::<S>();
+        match self.inner.systems.remove(&tid) {
+            Some(info) => info
+                .system
+                .into_any()
+                .downcast::<S>()
+                .ok()
+                .map(|system| *system),
+            None => {
+                if self.inner.running_systems.contains(&tid) {
+                    self.inner.pending_removals.insert(tid);
+                }
+                None
+            }
+        }
+    }
+
+    /// 启用或者禁用系统，被禁用的系统不会执行update和handle_event
+    pub fn set_system_enabled<S: System + 'static>(&mut self, enabled: bool) {
+        if let Some(info) = self.inner.systems.get_mut(&TypeId::of::<S>()) {
+            info.enabled = enabled;
         }
     }
 
+    pub fn is_system_enabled<S: System + 'static>(&self) -> bool {
+        self.inner
+            .systems
+            .get(&TypeId::of::<S>())
+            .map(|info| info.enabled)
+            .unwrap_or_default()
+    }
+
+    /// 固定时间步长，默认是1/60秒
+    pub fn fixed_timestep(&self) -> Duration {
+        self.inner.fixed_timestep
+    }
+
+    pub fn set_fixed_timestep(&mut self, timestep: Duration) {
+        assert!(timestep > Duration::default());
+        self.inner.fixed_timestep = timestep;
+    }
+
+    /// 每帧最多执行多少次固定步长，防止帧率过低时越积越多
+    pub fn max_fixed_steps(&self) -> usize {
+        self.inner.max_fixed_steps
+    }
+
+    pub fn set_max_fixed_steps(&mut self, steps: usize) {
+        self.inner.max_fixed_steps = steps;
+    }
+
+    /// 累积的剩余时间与固定步长的比值，用于渲染时插值
+    pub fn fixed_alpha(&self) -> f32 {
+        self.inner.fixed_accumulator.as_secs_f32() / self.inner.fixed_timestep.as_secs_f32()
+    }
+
+    /// 添加全局唯一的资源，同类型的资源会被替换
+    pub fn insert_resource<R: 'static>(&mut self, resource: R) {
+        self.inner
+            .resources
+            .insert(TypeId::of::<R>(), Box::new(resource));
+    }
+
+    pub fn remove_resource<R: 'static>(&mut self) -> Option<R> {
+        self.inner
+            .resources
+            .remove(&TypeId::of::<R>())
+            .map(|resource| *resource.downcast::<R>().unwrap())
+    }
+
+    pub fn resource<R: 'static>(&self) -> Option<&R> {
+        self.inner
+            .resources
+            .get(&TypeId::of::<R>())
+            .and_then(|resource| resource.downcast_ref::<R>())
+    }
+
+    pub fn resource_mut<R: 'static>(&mut self) -> Option<&mut R> {
+        self.inner
+            .resources
+            .get_mut(&TypeId::of::<R>())
+            .and_then(|resource| resource.downcast_mut::<R>())
+    }
+
+    pub fn system<T: 'static>(&self) -> Option<&T> {
+        self.inner
+            .systems
+            .get(&TypeId::of::<T>())
+            .and_then(|info| info.system.as_any().downcast_ref::<T>())
+    }
+
     pub fn system_mut<T: 'static>(&mut self) -> Option<&mut T> {
-        unsafe {
-            self.inner
-                .systems
-                .get_mut(&TypeId::of::<T>())
-                .map(|item| (&mut *(item.1.as_mut() as *mut dyn System as *mut T)))
+        self.inner
+            .systems
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|info| info.system.as_any_mut().downcast_mut::<T>())
+    }
+
+    /// 把系统从容器中取出来再执行，执行期间可以通过`ECS`访问其它的系统，
+    /// 但访问不到正在执行的系统本身
+    fn run_system<F>(&mut self, tid: TypeId, check_condition: bool, f: F)
+    where
+        F: FnOnce(&mut dyn System, &mut ECS),
+    {
+        if let Some(mut info) = self.inner.systems.remove(&tid) {
+            let run = !check_condition
+                || info
+                    .run_condition
+                    .as_ref()
+                    .map(|condition| condition(self))
+                    .unwrap_or(true);
+            if run {
+                let start = Instant::now();
+                self.inner.running_systems.push(tid);
+                f(info.system.as_system_mut(), self);
+                self.inner.running_systems.pop();
+                info.time += start.elapsed();
+            }
+            if self.inner.pending_removals.remove(&tid) {
+                return;
+            }
+            // 执行期间如果添加了同类型的系统，以新添加的为准
+            self.inner.systems.entry(tid).or_insert(info);
         }
     }
 
+    /// 上一次`update`里每个系统的耗时，按执行顺序排列
+    pub fn system_timings(&self) -> &[SystemTiming] {
+        &self.inner.timings
+    }
+
     pub fn update(&mut self, delta: Duration) {
-        unsafe {
-            let mut systems = Vec::with_capacity(self.inner.systems.len());
-            for (_, (ord, system)) in &mut self.inner.systems {
-                systems.push((*ord, system.as_mut() as *mut dyn System));
+        for info in self.inner.systems.values_mut() {
+            info.time = Duration::default();
+        }
+        let mut systems = self
+            .inner
+            .systems
+            .iter()
+            .filter(|(_, info)| info.enabled)
+            .map(|(tid, info)| (info.order, info.schedule, *tid))
+            .collect::<Vec<_>>();
+        systems.sort_by(|a, b| a.0.cmp(&b.0));
+
+        // 固定步长
+        let fixed_timestep = self.inner.fixed_timestep;
+        let mut steps = 0;
+        self.inner.fixed_accumulator += delta;
+        while self.inner.fixed_accumulator >= fixed_timestep {
+            if steps >= self.inner.max_fixed_steps {
+                // 丢弃追不上的时间
+                self.inner.fixed_accumulator = Duration::default();
+                break;
             }
-            systems.sort_by(|a, b| a.0.cmp(&b.0));
+            self.inner.fixed_accumulator -= fixed_timestep;
+            for (_, schedule, tid) in &systems {
+                if *schedule == Schedule::FixedUpdate {
+                    self.run_system(*tid, true, |system, ecs| system.update(ecs, fixed_timestep));
+                }
+            }
+            steps += 1;
+        }
 
-            for (_, system) in &systems {
-                system.as_mut().unwrap().update(self, delta);
+        for (_, schedule, tid) in &systems {
+            if *schedule == Schedule::Update {
+                self.run_system(*tid, true, |system, ecs| system.update(ecs, delta));
             }
+        }
 
-            let events = std::mem::replace(&mut self.inner.events, Default::default());
-            for (_, system) in &systems {
+        let events = std::mem::replace(&mut self.inner.events, Default::default());
+        self.inner.dispatching_removed =
+            std::mem::replace(&mut self.inner.removed, Default::default());
+        for (_, _, tid) in &systems {
+            self.run_system(*tid, false, |system, ecs| {
                 for event in &events {
-                    system.as_mut().unwrap().handle_event(self, event);
+                    system.handle_event(ecs, event);
                 }
+            });
+        }
+        self.inner.dispatching_removed.clear();
+
+        let inner = &mut self.inner;
+        inner.timings = systems
+            .iter()
+            .filter_map(|(_, _, tid)| inner.systems.get(tid))
+            .map(|info| SystemTiming {
+                name: info.name,
+                schedule: info.schedule,
+                order: info.order,
+                time: info.time,
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Counter(usize);
+
+    impl System for Counter {
+        fn update(&mut self, _ecs: &mut ECS, _delta: Duration) {
+            self.0 += 1;
+        }
+    }
+
+    #[test]
+    fn test_system_management() {
+        let mut ecs = ECS::new();
+        ecs.add_system(0, Counter(0));
+        ecs.update(Duration::from_millis(16));
+        assert_eq!(ecs.system::<Counter>().unwrap().0, 1);
+
+        ecs.set_system_enabled::<Counter>(false);
+        assert!(!ecs.is_system_enabled::<Counter>());
+        ecs.update(Duration::from_millis(16));
+        assert_eq!(ecs.system::<Counter>().unwrap().0, 1);
+
+        ecs.set_system_enabled::<Counter>(true);
+        ecs.update(Duration::from_millis(16));
+        assert_eq!(ecs.remove_system::<Counter>().unwrap().0, 2);
+        assert!(ecs.system::<Counter>().is_none());
+    }
+
+    struct RemoveInUpdate;
+
+    impl System for RemoveInUpdate {
+        fn update(&mut self, ecs: &mut ECS, _delta: Duration) {
+            assert!(ecs.remove_system::<RemoveInUpdate>().is_none());
+        }
+    }
+
+    struct RemoveInEvent;
+
+    impl System for RemoveInEvent {
+        fn handle_event(&mut self, ecs: &mut ECS, _event: &Event) {
+            ecs.remove_system::<RemoveInEvent>();
+        }
+    }
+
+    #[test]
+    fn test_remove_running_system() {
+        let mut ecs = ECS::new();
+        ecs.add_system(0, RemoveInUpdate);
+        ecs.add_system(1, RemoveInEvent);
+        ecs.create_entity().finish();
+        ecs.update(Duration::from_millis(16));
+        assert!(ecs.system::<RemoveInUpdate>().is_none());
+        assert!(ecs.system::<RemoveInEvent>().is_none());
+
+        // 已经删除的系统可以再次添加
+        ecs.add_system(0, RemoveInUpdate);
+        assert!(ecs.system::<RemoveInUpdate>().is_some());
+        ecs.update(Duration::from_millis(16));
+        assert!(ecs.system::<RemoveInUpdate>().is_none());
+    }
+
+    struct ReadCounter(usize);
+
+    impl System for ReadCounter {
+        fn update(&mut self, ecs: &mut ECS, _delta: Duration) {
+            assert!(ecs.system::<ReadCounter>().is_none());
+            self.0 = ecs.system::<Counter>().unwrap().0;
+        }
+    }
+
+    #[test]
+    fn test_access_other_system() {
+        let mut ecs = ECS::new();
+        ecs.add_system(0, Counter(0));
+        ecs.add_system(1, ReadCounter(0));
+        ecs.update(Duration::from_millis(16));
+        ecs.update(Duration::from_millis(16));
+        assert_eq!(ecs.system::<ReadCounter>().unwrap().0, 2);
+    }
+
+    struct Health(i32);
+
+    impl Component for Health {
+        fn name() -> &'static str {
+            "Health"
+        }
+    }
+
+    #[derive(Default)]
+    struct Cleanup(Vec<(EntityId, i32)>);
+
+    impl System for Cleanup {
+        fn handle_event(&mut self, ecs: &mut ECS, event: &Event) {
+            if let Event::RemoveComponent(_, _) | Event::RemoveEntity(_) = event {
+                self.0.clear();
+                self.0
+                    .extend(ecs.removed::<Health>().map(|(id, health)| (id, health.0)));
             }
+        }
+    }
+
+    #[test]
+    fn test_removed_components() {
+        let mut ecs = ECS::new();
+        ecs.add_system(0, Cleanup::default());
+        let a = ecs.create_entity().component(Health(10)).finish();
+        let b = ecs.create_entity().component(Health(20)).finish();
+        ecs.update(Duration::from_millis(16));
+
+        ecs.entity_mut(a).unwrap().remove::<Health>();
+        assert!(!ecs.entity(a).unwrap().contains::<Health>());
+        ecs.remove_entity(b);
+        ecs.update(Duration::from_millis(16));
+        assert_eq!(ecs.system::<Cleanup>().unwrap().0, vec![(a, 10), (b, 20)]);
+        assert_eq!(ecs.removed::<Health>().count(), 0);
+        let c = ecs.create_entity().component(Health(30)).finish();
+        ecs.update(Duration::from_millis(16));
+        ecs.entity_mut(c).unwrap().add(Health(31));
+        ecs.update(Duration::from_millis(16));
+        assert_eq!(ecs.system::<Cleanup>().unwrap().0, vec![(c, 30)]);
+    }
+
+    #[test]
+    fn test_entities() {
+        let mut ecs = ECS::new();
+        let a = ecs.create_entity().component(Health(1)).finish();
+        let b = ecs.create_entity().finish();
+        assert_eq!(ecs.entities().collect::<Vec<_>>(), vec![a, b]);
+        assert!(ecs.contains(a));
+
+        ecs.remove_entity(a);
+        assert!(!ecs.contains(a));
+        assert_eq!(
+            ecs.iter().map(|entity| entity.id()).collect::<Vec<_>>(),
+            vec![b]
+        );
+
+        ecs.clear();
+        assert_eq!(ecs.entities_count(), 0);
+        assert!(!ecs.contains(b));
+    }
+
+    #[derive(Clone)]
+    struct Name(&'static str);
+
+    impl Component for Name {
+        fn name() -> &'static str {
+            "Name"
+        }
+    }
 
-            self.inner.events.clear();
+    #[test]
+    fn test_migrate_entity() {
+        let mut menu = ECS::new();
+        let mut game = ECS::new();
+        game.create_entity().component(Name("other")).finish();
+
+        let id = menu
+            .create_entity()
+            .component(Health(5))
+            .component(Name("player"))
+            .finish();
+        let new_id = ECS::migrate_entity(&mut menu, &mut game, id).unwrap();
+        assert!(!menu.contains(id));
+        assert_eq!(game.entities_count(), 2);
+
+        let entity = game.entity(new_id).unwrap();
+        assert_eq!(entity.get::<Health>().unwrap().0, 5);
+        assert_eq!(entity.get::<Name>().unwrap().0, "player");
+        assert!(ECS::migrate_entity(&mut menu, &mut game, id).is_none());
+    }
+
+    #[test]
+    fn test_dynamic_components() {
+        let mut ecs = ECS::new();
+        let script_data = ecs.register_dynamic_component("ScriptData");
+        assert_eq!(ecs.register_dynamic_component("ScriptData"), script_data);
+        assert_eq!(ecs.dynamic_component_id("ScriptData"), Some(script_data));
+        assert_eq!(ecs.dynamic_component_name(script_data), Some("ScriptData"));
+
+        let id = ecs
+            .create_entity()
+            .component(Health(1))
+            .dynamic_component(script_data, vec![1, 2, 3])
+            .finish();
+        let entity = ecs.entity(id).unwrap();
+        assert!(entity.contains::<Health>());
+        assert_eq!(entity.get_dynamic(script_data), Some(&[1u8, 2, 3][..]));
+
+        let mut other = ECS::new();
+        other.register_dynamic_component("Other");
+        let new_id = ECS::migrate_entity(&mut ecs, &mut other, id).unwrap();
+        let new_script_data = other.dynamic_component_id("ScriptData").unwrap();
+        let mut entity = other.entity_mut(new_id).unwrap();
+        assert_eq!(entity.remove_dynamic(new_script_data), Some(vec![1, 2, 3]));
+        assert!(!entity.contains_dynamic(new_script_data));
+    }
+
+    #[test]
+    fn test_query() {
+        fn ids<F: EntityFilter>(ecs: &ECS) -> Vec<EntityId> {
+            ecs.query::<F>().map(|entity| entity.id()).collect()
+        }
+
+        let mut ecs = ECS::new();
+        let a = ecs.create_entity().component(Health(1)).finish();
+        let b = ecs
+            .create_entity()
+            .component(Health(2))
+            .component(Name("b"))
+            .finish();
+        let c = ecs.create_entity().component(Name("c")).finish();
+        ecs.create_entity().finish();
+
+        assert_eq!(ids::<With<Health>>(&ecs), vec![a, b]);
+        assert_eq!(ids::<(With<Health>, Without<Name>)>(&ecs), vec![a]);
+        assert_eq!(ids::<Or<(With<Health>, With<Name>)>>(&ecs), vec![a, b, c]);
+        assert_eq!(ids::<()>(&ecs).len(), 4);
+    }
+
+    struct Paused;
+
+    impl Component for Paused {
+        fn name() -> &'static str {
+            "Paused"
         }
     }
+
+    #[test]
+    fn test_run_condition() {
+        let mut ecs = ECS::new();
+        ecs.add_system(0, Counter(0))
+            .run_if(|ecs| ecs.query::<With<Paused>>().next().is_none());
+        ecs.update(Duration::from_millis(16));
+        assert_eq!(ecs.system::<Counter>().unwrap().0, 1);
+
+        let id = ecs.create_entity().component(Paused).finish();
+        ecs.update(Duration::from_millis(16));
+        assert_eq!(ecs.system::<Counter>().unwrap().0, 1);
+
+        ecs.remove_entity(id);
+        ecs.update(Duration::from_millis(16));
+        assert_eq!(ecs.system::<Counter>().unwrap().0, 2);
+    }
+
+    #[test]
+    fn test_component_hooks() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let mut ecs = ECS::new();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        ecs.on_add::<Health, _>(|_, health| health.0 *= 10);
+        ecs.on_remove::<Health, _>({
+            let log = log.clone();
+            move |id, health| log.borrow_mut().push((id, health.0))
+        });
+
+        let a = ecs.create_entity().component(Health(1)).finish();
+        let b = ecs.create_entity().finish();
+        ecs.entity_mut(b).unwrap().add(Health(2));
+        assert_eq!(ecs.entity(a).unwrap().get::<Health>().unwrap().0, 10);
+        assert_eq!(ecs.entity(b).unwrap().get::<Health>().unwrap().0, 20);
+
+        // 替换组件时旧组件也会触发on_remove
+        ecs.entity_mut(a).unwrap().add(Health(3));
+        assert_eq!(*log.borrow(), vec![(a, 10)]);
+        assert_eq!(ecs.entity(a).unwrap().get::<Health>().unwrap().0, 30);
+
+        ecs.entity_mut(a).unwrap().remove::<Health>();
+        ecs.remove_entity(b);
+        assert_eq!(*log.borrow(), vec![(a, 10), (a, 30), (b, 20)]);
+    }
+
+    #[test]
+    fn test_spawn_batch() {
+        let mut ecs = ECS::new();
+        ecs.create_entity().component(Name("first")).finish();
+        let ids = ecs.spawn_batch((0..100).map(|i| (Health(i), Name("bullet"))));
+        assert_eq!(ids.len(), 100);
+        assert_eq!(ecs.entities_count(), 101);
+
+        let entity = ecs.entity(ids[42]).unwrap();
+        assert_eq!(entity.get::<Health>().unwrap().0, 42);
+        assert_eq!(entity.get::<Name>().unwrap().0, "bullet");
+    }
+
+    #[test]
+    fn test_clone_entity() {
+        let mut ecs = ECS::new();
+        ecs.register_cloneable::<Name>();
+        let template = ecs
+            .create_entity()
+            .component(Health(3))
+            .component(Name("bullet"))
+            .finish();
+
+        let id = ecs.clone_entity(template).unwrap();
+        assert_ne!(id, template);
+        let entity = ecs.entity(id).unwrap();
+        assert_eq!(entity.get::<Name>().unwrap().0, "bullet");
+        assert!(!entity.contains::<Health>());
+    }
+
+    #[test]
+    fn test_weak_entity() {
+        let mut ecs = ECS::new();
+        let a = ecs.create_entity().finish();
+        let weak = ecs.weak(a).unwrap();
+        assert!(ecs.is_alive(weak));
+        assert_eq!(ecs.upgrade(weak), Some(a));
+
+        ecs.remove_entity(a);
+        assert!(ecs.weak(a).is_none());
+        let b = ecs.create_entity().finish();
+        assert_eq!(a, b);
+        assert!(!ecs.is_alive(weak));
+        assert!(ecs.upgrade(weak).is_none());
+        assert!(ecs.is_alive(ecs.weak(b).unwrap()));
+
+        // 编码成整数以后仍然能区分重用了下标的实体
+        assert!(ecs.upgrade(WeakEntity::from_bits(weak.to_bits())).is_none());
+        let bits = ecs.weak(b).unwrap().to_bits();
+        assert_eq!(ecs.upgrade(WeakEntity::from_bits(bits)), Some(b));
+        assert_eq!(ecs.entity_id_at(b.index()), Some(b));
+    }
+
+    #[test]
+    fn test_fixed_update() {
+        let mut ecs = ECS::new();
+        ecs.set_fixed_timestep(Duration::from_millis(10));
+        ecs.add_fixed_system(0, Counter(0));
+
+        ecs.update(Duration::from_millis(25));
+        assert_eq!(ecs.system::<Counter>().unwrap().0, 2);
+
+        ecs.update(Duration::from_millis(5));
+        assert_eq!(ecs.system::<Counter>().unwrap().0, 3);
+
+        ecs.update(Duration::from_secs(1));
+        assert_eq!(
+            ecs.system::<Counter>().unwrap().0,
+            3 + ecs.max_fixed_steps()
+        );
+    }
+
+    #[test]
+    fn test_change_detection() {
+        let mut ecs = ECS::new();
+        let id = ecs.create_entity().component(Health(10)).finish();
+        let tick = ecs.change_tick();
+        assert!(!ecs.entity(id).unwrap().is_changed_since::<Health>(tick));
+        assert!(!ecs.entity(id).unwrap().is_changed_since::<Name>(0));
+
+        ecs.entity_mut(id).unwrap().get::<Health>();
+        assert!(!ecs.entity(id).unwrap().is_changed_since::<Health>(tick));
+
+        ecs.entity_mut(id).unwrap().get_mut::<Health>().unwrap().0 = 5;
+        assert!(ecs.entity(id).unwrap().is_changed_since::<Health>(tick));
+
+        let tick = ecs.change_tick();
+        ecs.entity_mut(id).unwrap().add(Name("a"));
+        let entity = ecs.entity(id).unwrap();
+        assert!(!entity.is_changed_since::<Health>(tick));
+        assert!(entity.is_changed_since::<Name>(tick));
+    }
+
+    #[test]
+    fn test_resources() {
+        struct Score(u32);
+
+        let mut ecs = ECS::new();
+        assert!(ecs.resource::<Score>().is_none());
+        ecs.insert_resource(Score(1));
+        ecs.resource_mut::<Score>().unwrap().0 += 1;
+        assert_eq!(ecs.resource::<Score>().unwrap().0, 2);
+        assert_eq!(ecs.remove_resource::<Score>().unwrap().0, 2);
+        assert!(ecs.resource::<Score>().is_none());
+    }
 }
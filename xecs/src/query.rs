@@ -0,0 +1,64 @@
+use crate::{Component, EntityRef};
+use std::marker::PhantomData;
+
+/// 实体过滤器，用于`ECS::query`
+///
+/// 元组表示所有条件都满足，`Or`表示任意一个条件满足
+pub trait EntityFilter {
+    fn matches(entity: &EntityRef) -> bool;
+}
+
+/// 包含组件`C`
+pub struct With<C>(PhantomData<C>);
+
+impl<C: Component> EntityFilter for With<C> {
+    #[inline]
+    fn matches(entity: &EntityRef) -> bool {
+        entity.contains::<C>()
+    }
+}
+
+/// 不包含组件`C`
+pub struct Without<C>(PhantomData<C>);
+
+impl<C: Component> EntityFilter for Without<C> {
+    #[inline]
+    fn matches(entity: &EntityRef) -> bool {
+        !entity.contains::<C>()
+    }
+}
+
+/// 满足任意一个过滤器
+pub struct Or<T>(PhantomData<T>);
+
+impl EntityFilter for () {
+    #[inline]
+    fn matches(_entity: &EntityRef) -> bool {
+        true
+    }
+}
+
+macro_rules! impl_filter_tuple {
+    ($($name:ident),*) => {
+        impl<$($name: EntityFilter),*> EntityFilter for ($($name,)*) {
+            #[inline]
+            fn matches(entity: &EntityRef) -> bool {
+                $($name::matches(entity))&&*
+            }
+        }
+
+        impl<$($name: EntityFilter),*> EntityFilter for Or<($($name,)*)> {
+            #[inline]
+            fn matches(entity: &EntityRef) -> bool {
+                $($name::matches(entity))||*
+            }
+        }
+    };
+}
+
+impl_filter_tuple!(A);
+impl_filter_tuple!(A, B);
+impl_filter_tuple!(A, B, C);
+impl_filter_tuple!(A, B, C, D);
+impl_filter_tuple!(A, B, C, D, E);
+impl_filter_tuple!(A, B, C, D, E, F);
@@ -0,0 +1,129 @@
+use crate::ComponentTransform;
+use std::time::Duration;
+use xecs::{Component, EntityId, System, With, ECS};
+use xmath::{Multiply, Transform, TransposeMultiply, Vector2, AABB};
+
+/// 2D相机，位置和旋转来自实体的`ComponentTransform`
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentCamera {
+    /// 每个世界单位对应的像素数
+    pub zoom: f32,
+
+    /// 视口的像素大小
+    pub viewport: Vector2<f32>,
+
+    /// 同时有多个激活的相机时使用第一个
+    pub active: bool,
+}
+
+impl Default for ComponentCamera {
+    fn default() -> Self {
+        ComponentCamera {
+            zoom: 32.0,
+            viewport: (800.0, 600.0).into(),
+            active: true,
+        }
+    }
+}
+
+impl Component for ComponentCamera {
+    fn name() -> &'static str {
+        "Camera"
+    }
+}
+
+/// 当前激活相机的视图，由`SystemCamera`每帧更新到ECS资源中，渲染和调试绘制共用
+///
+/// 屏幕坐标的原点在视口左上角，y轴向下
+#[derive(Debug, Copy, Clone)]
+pub struct CameraView {
+    pub camera: EntityId,
+    pub transform: Transform<f32>,
+    pub zoom: f32,
+    pub viewport: Vector2<f32>,
+
+    /// 视口在世界坐标中的包围盒，可以用来做剔除
+    pub aabb: AABB<f32>,
+}
+
+impl CameraView {
+    pub fn world_to_screen(&self, point: Vector2<f32>) -> Vector2<f32> {
+        let local = self.transform.transpose_multiply(point) * self.zoom;
+        Vector2::new(local.x, -local.y) + self.viewport * 0.5
+    }
+
+    pub fn screen_to_world(&self, point: Vector2<f32>) -> Vector2<f32> {
+        let local = point - self.viewport * 0.5;
+        self.transform
+            .multiply(Vector2::new(local.x, -local.y) * (1.0 / self.zoom))
+    }
+}
+
+/// 根据激活的相机计算`CameraView`资源，没有激活的相机时删除这个资源
+#[derive(Default)]
+pub struct SystemCamera;
+
+impl System for SystemCamera {
+    fn update(&mut self, ecs: &mut ECS, _delta: Duration) {
+        let view = ecs
+            .query::<(With<ComponentCamera>, With<ComponentTransform>)>()
+            .find(|entity| entity.get::<ComponentCamera>().unwrap().active)
+            .map(|entity| {
+                let camera = entity.get::<ComponentCamera>().unwrap();
+                let transform = entity.get::<ComponentTransform>().unwrap().0;
+                let half = camera.viewport * (0.5 / camera.zoom);
+                let (c, s) = (transform.q.c.abs(), transform.q.s.abs());
+                let extents = Vector2::new(c * half.x + s * half.y, s * half.x + c * half.y);
+                CameraView {
+                    camera: entity.id(),
+                    transform,
+                    zoom: camera.zoom,
+                    viewport: camera.viewport,
+                    aabb: AABB::new_center(transform.p, extents),
+                }
+            });
+
+        match view {
+            Some(view) => ecs.insert_resource(view),
+            None => {
+                ecs.remove_resource::<CameraView>();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xmath::Rotation;
+
+    #[test]
+    fn test_camera_view() {
+        let mut ecs = ECS::new();
+        ecs.add_system(0, SystemCamera);
+        ecs.create_entity()
+            .component(ComponentTransform(Transform::new(
+                (10.0, 5.0).into(),
+                Rotation::new(0.0),
+            )))
+            .component(ComponentCamera {
+                zoom: 10.0,
+                viewport: (200.0, 100.0).into(),
+                active: true,
+            })
+            .finish();
+        ecs.update(Duration::from_secs(1) / 60);
+
+        let view = ecs.resource::<CameraView>().unwrap();
+        assert_eq!(view.aabb.lower_bound, Vector2::new(0.0, 0.0));
+        assert_eq!(view.aabb.upper_bound, Vector2::new(20.0, 10.0));
+        assert_eq!(
+            view.world_to_screen((0.0, 10.0).into()),
+            Vector2::new(0.0, 0.0)
+        );
+        assert_eq!(
+            view.screen_to_world((200.0, 100.0).into()),
+            Vector2::new(20.0, 0.0)
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use crate::ComponentChildren;
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::time::Duration;
+use xecs::{Component, EntityId, Event, System, With, ECS};
+
+/// 父实体，由`SystemHierarchy`维护父实体的`ComponentChildren`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ComponentParent(pub EntityId);
+
+impl Component for ComponentParent {
+    fn name() -> &'static str {
+        "Parent"
+    }
+}
+
+/// 根据`ComponentParent`维护`ComponentChildren`
+///
+/// 添加、删除或者修改`ComponentParent`时更新父实体的子实体列表，父实体被删除时，
+/// 子实体的`ComponentParent`会被删除
+#[derive(Default)]
+pub struct SystemHierarchy {
+    /// 子实体到父实体
+    parents: HashMap<EntityId, EntityId>,
+}
+
+impl SystemHierarchy {
+    fn attach(&mut self, ecs: &mut ECS, child: EntityId, parent: EntityId) {
+        if self.parents.get(&child) == Some(&parent) {
+            return;
+        }
+        self.detach(ecs, child);
+
+        let mut entity = match ecs.entity_mut(parent) {
+            Some(entity) => entity,
+            None => return,
+        };
+        match entity.get_mut::<ComponentChildren>() {
+            Some(children) => {
+                if !children.0.contains(&child) {
+                    children.0.push(child);
+                }
+            }
+            None => entity.add(ComponentChildren(vec![child])),
+        }
+        self.parents.insert(child, parent);
+    }
+
+    fn detach(&mut self, ecs: &mut ECS, child: EntityId) {
+        let parent = match self.parents.remove(&child) {
+            Some(parent) => parent,
+            None => return,
+        };
+        if let Some(children) = ecs
+            .entity_mut(parent)
+            .as_mut()
+            .and_then(|entity| entity.get_mut::<ComponentChildren>())
+        {
+            children.0.retain(|id| *id != child);
+        }
+    }
+
+    fn parent_of(ecs: &ECS, id: EntityId) -> Option<EntityId> {
+        ecs.entity(id)
+            .and_then(|entity| entity.get::<ComponentParent>().map(|parent| parent.0))
+    }
+}
+
+impl System for SystemHierarchy {
+    fn update(&mut self, ecs: &mut ECS, _delta: Duration) {
+        // 处理通过`get_mut`修改的父实体
+        let changed = ecs
+            .query::<With<ComponentParent>>()
+            .map(|entity| (entity.id(), entity.get::<ComponentParent>().unwrap().0))
+            .filter(|(child, parent)| self.parents.get(child) != Some(parent))
+            .collect::<Vec<_>>();
+        for (child, parent) in changed {
+            self.attach(ecs, child, parent);
+        }
+    }
+
+    fn handle_event(&mut self, ecs: &mut ECS, event: &Event) {
+        match event {
+            Event::CreateEntity(id) => {
+                if let Some(parent) = Self::parent_of(ecs, *id) {
+                    self.attach(ecs, *id, parent);
+                }
+            }
+            Event::CreateComponent(id, tid) if *tid == TypeId::of::<ComponentParent>() => {
+                if let Some(parent) = Self::parent_of(ecs, *id) {
+                    self.attach(ecs, *id, parent);
+                }
+            }
+            Event::RemoveComponent(id, tid) if *tid == TypeId::of::<ComponentParent>() => {
+                self.detach(ecs, *id);
+            }
+            Event::RemoveEntity(id) => {
+                self.detach(ecs, *id);
+
+                // 父实体被删除了，子实体变成根实体
+                let children = self
+                    .parents
+                    .iter()
+                    .filter(|(_, parent)| *parent == id)
+                    .map(|(child, _)| *child)
+                    .collect::<Vec<_>>();
+                for child in children {
+                    self.parents.remove(&child);
+                    if let Some(mut entity) = ecs.entity_mut(child) {
+                        entity.remove::<ComponentParent>();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn children(ecs: &ECS, id: EntityId) -> Vec<EntityId> {
+        ecs.entity(id)
+            .unwrap()
+            .get::<ComponentChildren>()
+            .unwrap()
+            .0
+            .clone()
+    }
+
+    #[test]
+    fn test_hierarchy() {
+        let mut ecs = ECS::new();
+        ecs.add_system(0, SystemHierarchy::default());
+        let a = ecs.create_entity().finish();
+        let b = ecs.create_entity().finish();
+        let child = ecs.create_entity().component(ComponentParent(a)).finish();
+        ecs.update(Duration::from_secs(1) / 60);
+        assert_eq!(children(&ecs, a), vec![child]);
+
+        ecs.entity_mut(child)
+            .unwrap()
+            .get_mut::<ComponentParent>()
+            .unwrap()
+            .0 = b;
+        ecs.update(Duration::from_secs(1) / 60);
+        assert!(children(&ecs, a).is_empty());
+        assert_eq!(children(&ecs, b), vec![child]);
+
+        ecs.remove_entity(b);
+        ecs.update(Duration::from_secs(1) / 60);
+        assert!(!ecs.entity(child).unwrap().contains::<ComponentParent>());
+
+        ecs.entity_mut(child).unwrap().add(ComponentParent(a));
+        ecs.update(Duration::from_secs(1) / 60);
+        assert_eq!(children(&ecs, a), vec![child]);
+        ecs.entity_mut(child).unwrap().remove::<ComponentParent>();
+        ecs.update(Duration::from_secs(1) / 60);
+        assert!(children(&ecs, a).is_empty());
+    }
+}
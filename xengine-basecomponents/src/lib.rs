@@ -1,4 +1,28 @@
-use xecs::{EntityId, Component};
+mod camera;
+mod hierarchy;
+mod lifetime;
+mod movement;
+mod name;
+mod scene;
+mod state_machine;
+mod tag;
+mod transform;
+mod tween;
+
+pub use camera::{CameraView, ComponentCamera, SystemCamera};
+pub use hierarchy::{ComponentParent, SystemHierarchy};
+pub use lifetime::{ComponentLifetime, LifetimeExpired, SystemLifetime};
+pub use movement::{ComponentVelocity, SystemSimpleMovement};
+pub use name::{entity_label, find_entities_by_name, find_entity_by_name, ComponentName};
+pub use scene::{despawn_scene, scene_entities, spawn_scene, ComponentSceneMember, Scene, SceneId};
+pub use state_machine::{ComponentStateMachine, StateChanged, SystemStateMachine};
+pub use tag::ComponentTag;
+pub use transform::{
+    compose_scaled, ComponentLocalScale, ComponentLocalTransform, ComponentScale,
+    SystemTransformPropagation,
+};
+pub use tween::{ComponentTween, SystemTween, TweenCompleted, TweenMode};
+use xecs::{Component, EntityId};
 use xmath::Transform;
 
 pub struct ComponentTransform(pub Transform<f32>);
@@ -11,3 +35,20 @@ impl Component for ComponentTransform {
 
 pub struct ComponentChildren(pub Vec<EntityId>);
 
+impl Component for ComponentChildren {
+    fn name() -> &'static str {
+        "Children"
+    }
+}
+
+/// 禁用实体，内置的系统（物理、变换传播、简单运动等）会跳过带有这个组件的实体
+///
+/// 禁用的实体的子实体也不会进行变换传播
+#[derive(Debug, Copy, Clone, Default)]
+pub struct ComponentDisabled;
+
+impl Component for ComponentDisabled {
+    fn name() -> &'static str {
+        "Disabled"
+    }
+}
@@ -0,0 +1,82 @@
+use std::time::Duration;
+use xecs::{Component, EntityId, System, With, ECS};
+
+/// 剩余的存活时间，由`SystemLifetime`每帧减少，到期后删除实体
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentLifetime(pub Duration);
+
+impl Component for ComponentLifetime {
+    fn name() -> &'static str {
+        "Lifetime"
+    }
+}
+
+/// 实体因为存活时间到期被删除，在删除之前通过`Event::Custom`发送
+#[derive(Debug, Copy, Clone)]
+pub struct LifetimeExpired(pub EntityId);
+
+/// 减少`ComponentLifetime`，到期时发送`LifetimeExpired`事件并删除实体
+#[derive(Default)]
+pub struct SystemLifetime;
+
+impl System for SystemLifetime {
+    fn update(&mut self, ecs: &mut ECS, delta: Duration) {
+        let ids = ecs
+            .query::<With<ComponentLifetime>>()
+            .map(|entity| entity.id())
+            .collect::<Vec<_>>();
+
+        for id in ids {
+            let expired = {
+                let mut entity = ecs.entity_mut(id).unwrap();
+                let lifetime = entity.get_mut::<ComponentLifetime>().unwrap();
+                match lifetime.0.checked_sub(delta) {
+                    Some(remaining) if remaining > Duration::default() => {
+                        lifetime.0 = remaining;
+                        false
+                    }
+                    _ => true,
+                }
+            };
+            if expired {
+                ecs.send_event(LifetimeExpired(id));
+                ecs.remove_entity(id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xecs::Event;
+
+    #[derive(Default)]
+    struct Expired(Vec<EntityId>);
+
+    impl System for Expired {
+        fn handle_event(&mut self, _ecs: &mut ECS, event: &Event) {
+            if let Some(event) = event.downcast_ref::<LifetimeExpired>() {
+                self.0.push(event.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lifetime() {
+        let mut ecs = ECS::new();
+        ecs.add_system(0, SystemLifetime);
+        ecs.add_system(1, Expired::default());
+        let bullet = ecs
+            .create_entity()
+            .component(ComponentLifetime(Duration::from_millis(250)))
+            .finish();
+
+        ecs.update(Duration::from_millis(100));
+        ecs.update(Duration::from_millis(100));
+        assert!(ecs.contains(bullet));
+        ecs.update(Duration::from_millis(100));
+        assert!(!ecs.contains(bullet));
+        assert_eq!(ecs.system::<Expired>().unwrap().0, vec![bullet]);
+    }
+}
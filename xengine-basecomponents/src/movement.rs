@@ -0,0 +1,83 @@
+use crate::{ComponentDisabled, ComponentLocalTransform, ComponentTransform};
+use std::time::Duration;
+use xecs::{Component, EntityId, System, With, Without, ECS};
+use xmath::{Rotation, Transform, Vector2};
+
+/// 不需要物理的简单运动，由`SystemSimpleMovement`积分到变换中
+#[derive(Debug, Copy, Clone, Default)]
+pub struct ComponentVelocity {
+    pub linear: Vector2<f32>,
+
+    /// 角速度（弧度每秒）
+    pub angular: f32,
+}
+
+impl Component for ComponentVelocity {
+    fn name() -> &'static str {
+        "Velocity"
+    }
+}
+
+fn integrate(xf: &mut Transform<f32>, velocity: &ComponentVelocity, dt: f32) {
+    xf.p = xf.p + velocity.linear * dt;
+    if velocity.angular != 0.0 {
+        xf.q = Rotation::new(xf.q.angle() + velocity.angular * dt);
+    }
+}
+
+/// 每帧把`ComponentVelocity`积分到变换中，用于UI元素、背景物体等不需要刚体的实体
+///
+/// 有`ComponentLocalTransform`时修改局部变换，否则修改`ComponentTransform`
+#[derive(Default)]
+pub struct SystemSimpleMovement;
+
+impl System for SystemSimpleMovement {
+    fn update(&mut self, ecs: &mut ECS, delta: Duration) {
+        let dt = delta.as_secs_f32();
+        let entities = ecs
+            .query::<(With<ComponentVelocity>, Without<ComponentDisabled>)>()
+            .map(|entity| (entity.id(), *entity.get::<ComponentVelocity>().unwrap()))
+            .filter(|(_, velocity)| velocity.linear != Vector2::zero() || velocity.angular != 0.0)
+            .collect::<Vec<(EntityId, ComponentVelocity)>>();
+
+        for (id, velocity) in entities {
+            let mut entity = ecs.entity_mut(id).unwrap();
+            if let Some(local) = entity.get_mut::<ComponentLocalTransform>() {
+                integrate(&mut local.0, &velocity, dt);
+            } else if let Some(transform) = entity.get_mut::<ComponentTransform>() {
+                integrate(&mut transform.0, &velocity, dt);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simple_movement() {
+        let mut ecs = ECS::new();
+        ecs.add_system(0, SystemSimpleMovement);
+        let id = ecs
+            .create_entity()
+            .component(ComponentTransform(Transform::identity()))
+            .component(ComponentVelocity {
+                linear: (1.0, 2.0).into(),
+                angular: 1.0,
+            })
+            .finish();
+        for _ in 0..10 {
+            ecs.update(Duration::from_millis(100));
+        }
+
+        let xf = ecs
+            .entity(id)
+            .unwrap()
+            .get::<ComponentTransform>()
+            .unwrap()
+            .0;
+        assert!(xf.p.distance(&Vector2::new(1.0, 2.0)) < 0.0001);
+        assert!((xf.q.angle() - 1.0).abs() < 0.0001);
+    }
+}
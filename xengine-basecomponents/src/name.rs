@@ -0,0 +1,69 @@
+use std::fmt::{self, Display, Formatter};
+use xecs::{Component, EntityId, ECS};
+
+/// 实体的名字，用于查找实体和调试输出，不要求唯一
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ComponentName(pub String);
+
+impl ComponentName {
+    pub fn new(name: impl Into<String>) -> ComponentName {
+        ComponentName(name.into())
+    }
+}
+
+impl Component for ComponentName {
+    fn name() -> &'static str {
+        "Name"
+    }
+}
+
+impl Display for ComponentName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// 查找第一个名字为`name`的实体
+pub fn find_entity_by_name(ecs: &ECS, name: &str) -> Option<EntityId> {
+    find_entities_by_name(ecs, name).next()
+}
+
+/// 查找所有名字为`name`的实体
+pub fn find_entities_by_name<'a>(
+    ecs: &'a ECS,
+    name: &'a str,
+) -> impl Iterator<Item = EntityId> + 'a {
+    ecs.iter()
+        .filter(move |entity| entity.get::<ComponentName>().map(|n| n.0.as_str()) == Some(name))
+        .map(|entity| entity.id())
+}
+
+/// 用于日志和调试显示的实体描述，有名字时是`name(EntityId(1))`，否则是`EntityId(1)`
+pub fn entity_label(ecs: &ECS, id: EntityId) -> String {
+    match ecs.entity(id) {
+        Some(entity) if entity.contains::<ComponentName>() => {
+            format!("{}({:?})", entity.get::<ComponentName>().unwrap(), id)
+        }
+        _ => format!("{:?}", id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name() {
+        let mut ecs = ECS::new();
+        let player = ecs
+            .create_entity()
+            .component(ComponentName::new("player"))
+            .finish();
+        let other = ecs.create_entity().finish();
+
+        assert_eq!(find_entity_by_name(&ecs, "player"), Some(player));
+        assert_eq!(find_entity_by_name(&ecs, "enemy"), None);
+        assert_eq!(entity_label(&ecs, player), format!("player({:?})", player));
+        assert_eq!(entity_label(&ecs, other), format!("{:?}", other));
+    }
+}
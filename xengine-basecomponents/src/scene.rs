@@ -0,0 +1,113 @@
+use xecs::{Component, EntityBuilder, EntityId, With, ECS};
+
+/// 场景实例的id，由`spawn_scene`分配
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct SceneId(u32);
+
+/// 实体属于哪个场景实例，`despawn_scene`会删除场景的所有实体
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ComponentSceneMember(pub SceneId);
+
+impl Component for ComponentSceneMember {
+    fn name() -> &'static str {
+        "SceneMember"
+    }
+}
+
+type EntityTemplate = dyn Fn(EntityBuilder) -> EntityBuilder;
+
+/// 一组实体模板，可以多次实例化
+///
+/// ```ignore
+/// let scene = Scene::new()
+///     .entity(|builder| builder.component(ComponentTransform(Transform::identity())))
+///     .entity(|builder| builder.component(ComponentName::new("player")));
+/// let id = spawn_scene(&mut ecs, &scene);
+/// ```
+#[derive(Default)]
+pub struct Scene {
+    templates: Vec<Box<EntityTemplate>>,
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Default::default()
+    }
+
+    /// 添加一个实体模板，`f`给实体添加组件
+    pub fn entity<F>(mut self, f: F) -> Self
+    where
+        F: Fn(EntityBuilder) -> EntityBuilder + 'static,
+    {
+        self.templates.push(Box::new(f));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+}
+
+/// 保存在ECS资源中的下一个场景id
+#[derive(Default)]
+struct NextSceneId(u32);
+
+/// 实例化场景，每个实体都会带有`ComponentSceneMember`
+pub fn spawn_scene(ecs: &mut ECS, scene: &Scene) -> SceneId {
+    if ecs.resource::<NextSceneId>().is_none() {
+        ecs.insert_resource(NextSceneId::default());
+    }
+    let next_id = ecs.resource_mut::<NextSceneId>().unwrap();
+    let id = SceneId(next_id.0);
+    next_id.0 += 1;
+
+    for template in &scene.templates {
+        template(ecs.create_entity())
+            .component(ComponentSceneMember(id))
+            .finish();
+    }
+    id
+}
+
+/// 返回属于场景实例`id`的所有实体
+pub fn scene_entities(ecs: &ECS, id: SceneId) -> Vec<EntityId> {
+    ecs.query::<With<ComponentSceneMember>>()
+        .filter(|entity| entity.get::<ComponentSceneMember>().map(|member| member.0) == Some(id))
+        .map(|entity| entity.id())
+        .collect()
+}
+
+/// 删除属于场景实例`id`的所有实体
+pub fn despawn_scene(ecs: &mut ECS, id: SceneId) {
+    for entity in scene_entities(ecs, id) {
+        ecs.remove_entity(entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ComponentName;
+
+    #[test]
+    fn test_scene() {
+        let scene = Scene::new()
+            .entity(|builder| builder.component(ComponentName::new("player")))
+            .entity(|builder| builder.component(ComponentName::new("enemy")));
+        let mut ecs = ECS::new();
+        let level1 = spawn_scene(&mut ecs, &scene);
+        let level2 = spawn_scene(&mut ecs, &scene);
+        assert_ne!(level1, level2);
+        assert_eq!(scene_entities(&ecs, level1).len(), 2);
+        assert_eq!(ecs.entities_count(), 4);
+
+        despawn_scene(&mut ecs, level1);
+        assert!(scene_entities(&ecs, level1).is_empty());
+        assert_eq!(scene_entities(&ecs, level2).len(), 2);
+        assert_eq!(ecs.entities_count(), 2);
+    }
+}
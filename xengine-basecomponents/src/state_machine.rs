@@ -0,0 +1,365 @@
+use crate::ComponentDisabled;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::time::Duration;
+use xecs::{Component, EntityId, Event, System, With, Without, ECS};
+
+type Hook = Rc<dyn Fn(&mut ECS, EntityId)>;
+type UpdateHook<S> = Rc<dyn Fn(&mut ECS, EntityId, Duration) -> Option<S>>;
+type EventHook<S> = Rc<dyn Fn(&mut ECS, EntityId, &Event) -> Option<S>>;
+
+struct Hooks<S> {
+    enter: Vec<(S, Hook)>,
+    exit: Vec<(S, Hook)>,
+    update: Vec<(S, UpdateHook<S>)>,
+    event: Vec<(S, EventHook<S>)>,
+}
+
+fn find<S: PartialEq, T: Clone>(hooks: &[(S, T)], state: S) -> Option<T> {
+    hooks
+        .iter()
+        .find(|(s, _)| *s == state)
+        .map(|(_, hook)| hook.clone())
+}
+
+/// 有限状态机，由`SystemStateMachine<S>`驱动，每个状态可以有进入、退出、更新和事件回调
+///
+/// 更新和事件回调返回`Some(state)`时切换到新的状态，切换时先调用旧状态的退出回调，再调用新状态的进入回调，
+/// 然后发送`StateChanged<S>`事件。初始状态的进入回调在第一次更新时调用。
+///
+/// ```ignore
+/// ComponentStateMachine::new(Enemy::Idle)
+///     .on_update(Enemy::Idle, |ecs, id, _| if sees_player(ecs, id) { Some(Enemy::Chase) } else { None })
+///     .on_enter(Enemy::Chase, |ecs, id| play_sound(ecs, id))
+///     .on_event::<LifetimeExpired, _>(Enemy::Chase, |_, _, _| Some(Enemy::Idle));
+/// ```
+pub struct ComponentStateMachine<S> {
+    current: S,
+    previous: Option<S>,
+    elapsed: Duration,
+    started: bool,
+    pending: Option<S>,
+    hooks: Rc<Hooks<S>>,
+}
+
+impl<S: Copy + PartialEq + Debug + 'static> ComponentStateMachine<S> {
+    pub fn new(initial: S) -> ComponentStateMachine<S> {
+        ComponentStateMachine {
+            current: initial,
+            previous: None,
+            elapsed: Duration::default(),
+            started: false,
+            pending: None,
+            hooks: Rc::new(Hooks {
+                enter: Vec::new(),
+                exit: Vec::new(),
+                update: Vec::new(),
+                event: Vec::new(),
+            }),
+        }
+    }
+
+    fn hooks_mut(&mut self) -> &mut Hooks<S> {
+        Rc::get_mut(&mut self.hooks).expect("hooks are shared")
+    }
+
+    /// 进入`state`时调用
+    pub fn on_enter<F>(mut self, state: S, f: F) -> Self
+    where
+        F: Fn(&mut ECS, EntityId) + 'static,
+    {
+        self.hooks_mut().enter.push((state, Rc::new(f)));
+        self
+    }
+
+    /// 离开`state`时调用
+    pub fn on_exit<F>(mut self, state: S, f: F) -> Self
+    where
+        F: Fn(&mut ECS, EntityId) + 'static,
+    {
+        self.hooks_mut().exit.push((state, Rc::new(f)));
+        self
+    }
+
+    /// 处于`state`时每帧调用，参数是帧间隔
+    pub fn on_update<F>(mut self, state: S, f: F) -> Self
+    where
+        F: Fn(&mut ECS, EntityId, Duration) -> Option<S> + 'static,
+    {
+        self.hooks_mut().update.push((state, Rc::new(f)));
+        self
+    }
+
+    /// 处于`state`时收到`Event::Custom`发送的事件`E`时调用
+    pub fn on_event<E, F>(mut self, state: S, f: F) -> Self
+    where
+        E: 'static,
+        F: Fn(&mut ECS, EntityId, &E) -> Option<S> + 'static,
+    {
+        let hook: EventHook<S> = Rc::new(move |ecs, id, event| {
+            event
+                .downcast_ref::<E>()
+                .and_then(|event| f(ecs, id, event))
+        });
+        self.hooks_mut().event.push((state, hook));
+        self
+    }
+
+    pub fn current(&self) -> S {
+        self.current
+    }
+
+    /// 上一个状态，还没有切换过时为`None`
+    pub fn previous(&self) -> Option<S> {
+        self.previous
+    }
+
+    /// 在当前状态中经过的时间
+    pub fn time_in_state(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// 请求切换状态，在下一次更新时切换
+    pub fn transition(&mut self, state: S) {
+        self.pending = Some(state);
+    }
+}
+
+impl<S: 'static> Component for ComponentStateMachine<S> {
+    fn name() -> &'static str {
+        "StateMachine"
+    }
+}
+
+/// 状态机切换了状态，通过`Event::Custom`发送
+#[derive(Debug, Copy, Clone)]
+pub struct StateChanged<S> {
+    pub entity: EntityId,
+    pub from: S,
+    pub to: S,
+}
+
+fn with_machine<S: 'static, R>(
+    ecs: &mut ECS,
+    id: EntityId,
+    f: impl FnOnce(&mut ComponentStateMachine<S>) -> R,
+) -> Option<R> {
+    let mut entity = ecs.entity_mut(id)?;
+    entity.get_mut::<ComponentStateMachine<S>>().map(f)
+}
+
+fn enter<S: Copy + PartialEq + Debug + 'static>(ecs: &mut ECS, id: EntityId) {
+    let (state, hooks) = match with_machine(ecs, id, |machine: &mut ComponentStateMachine<S>| {
+        machine.started = true;
+        (machine.current, machine.hooks.clone())
+    }) {
+        Some(res) => res,
+        None => return,
+    };
+    if let Some(hook) = find(&hooks.enter, state) {
+        hook(ecs, id);
+    }
+}
+
+fn transition<S: Copy + PartialEq + Debug + 'static>(ecs: &mut ECS, id: EntityId, to: S) {
+    let (from, hooks) = match with_machine(ecs, id, |machine: &mut ComponentStateMachine<S>| {
+        (machine.current, machine.hooks.clone())
+    }) {
+        Some(res) => res,
+        None => return,
+    };
+    if let Some(hook) = find(&hooks.exit, from) {
+        hook(ecs, id);
+    }
+
+    // 退出回调可能删除了实体或者状态机
+    let changed = with_machine(ecs, id, |machine: &mut ComponentStateMachine<S>| {
+        machine.previous = Some(from);
+        machine.current = to;
+        machine.elapsed = Duration::default();
+        machine.pending = None;
+    });
+    if changed.is_none() {
+        return;
+    }
+    if let Some(hook) = find(&hooks.enter, to) {
+        hook(ecs, id);
+    }
+    ecs.send_event(StateChanged {
+        entity: id,
+        from,
+        to,
+    });
+}
+
+/// 更新`ComponentStateMachine<S>`，每种状态类型都要单独添加一个系统
+pub struct SystemStateMachine<S>(PhantomData<S>);
+
+impl<S> Default for SystemStateMachine<S> {
+    fn default() -> Self {
+        SystemStateMachine(PhantomData)
+    }
+}
+
+impl<S: Copy + PartialEq + Debug + 'static> SystemStateMachine<S> {
+    fn machines(ecs: &ECS) -> Vec<EntityId> {
+        ecs.query::<(With<ComponentStateMachine<S>>, Without<ComponentDisabled>)>()
+            .map(|entity| entity.id())
+            .collect()
+    }
+}
+
+impl<S: Copy + PartialEq + Debug + 'static> System for SystemStateMachine<S> {
+    fn update(&mut self, ecs: &mut ECS, delta: Duration) {
+        for id in Self::machines(ecs) {
+            let (started, pending) =
+                match with_machine(ecs, id, |machine: &mut ComponentStateMachine<S>| {
+                    (machine.started, machine.pending)
+                }) {
+                    Some(res) => res,
+                    None => continue,
+                };
+            if !started {
+                enter::<S>(ecs, id);
+            }
+            if let Some(state) = pending {
+                transition(ecs, id, state);
+            }
+
+            let (state, hooks) =
+                match with_machine(ecs, id, |machine: &mut ComponentStateMachine<S>| {
+                    machine.elapsed += delta;
+                    (machine.current, machine.hooks.clone())
+                }) {
+                    Some(res) => res,
+                    None => continue,
+                };
+            if let Some(hook) = find(&hooks.update, state) {
+                if let Some(to) = hook(ecs, id, delta) {
+                    transition(ecs, id, to);
+                }
+            }
+        }
+    }
+
+    fn handle_event(&mut self, ecs: &mut ECS, event: &Event) {
+        for id in Self::machines(ecs) {
+            let (state, hooks) =
+                match with_machine(ecs, id, |machine: &mut ComponentStateMachine<S>| {
+                    (machine.started, machine.current, machine.hooks.clone())
+                }) {
+                    Some((true, state, hooks)) => (state, hooks),
+                    _ => continue,
+                };
+            for (s, hook) in &hooks.event {
+                if *s == state {
+                    if let Some(to) = hook(ecs, id, event) {
+                        transition(ecs, id, to);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    enum Enemy {
+        Idle,
+        Chase,
+        Dead,
+    }
+
+    struct Hit;
+
+    #[derive(Default)]
+    struct Changes(Vec<(Enemy, Enemy)>);
+
+    impl System for Changes {
+        fn handle_event(&mut self, _ecs: &mut ECS, event: &Event) {
+            if let Some(event) = event.downcast_ref::<StateChanged<Enemy>>() {
+                self.0.push((event.from, event.to));
+            }
+        }
+    }
+
+    #[test]
+    fn test_state_machine() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let push = |message: &'static str| {
+            let log = log.clone();
+            move |_: &mut ECS, _: EntityId| log.borrow_mut().push(message)
+        };
+
+        let mut ecs = ECS::new();
+        ecs.add_system(0, SystemStateMachine::<Enemy>::default());
+        ecs.add_system(1, Changes::default());
+        let enemy = ecs
+            .create_entity()
+            .component(
+                ComponentStateMachine::new(Enemy::Idle)
+                    .on_enter(Enemy::Idle, push("enter idle"))
+                    .on_exit(Enemy::Idle, push("exit idle"))
+                    .on_enter(Enemy::Chase, push("enter chase"))
+                    .on_update(Enemy::Idle, |ecs, id, _| {
+                        let machine = ecs
+                            .entity(id)
+                            .unwrap()
+                            .get::<ComponentStateMachine<Enemy>>()
+                            .unwrap()
+                            .time_in_state();
+                        if machine >= Duration::from_secs(1) {
+                            Some(Enemy::Chase)
+                        } else {
+                            None
+                        }
+                    })
+                    .on_event::<Hit, _>(Enemy::Chase, |_, _, _| Some(Enemy::Dead)),
+            )
+            .finish();
+        let state = |ecs: &ECS| {
+            ecs.entity(enemy)
+                .unwrap()
+                .get::<ComponentStateMachine<Enemy>>()
+                .unwrap()
+                .current()
+        };
+
+        // 初始状态不处理事件
+        ecs.send_event(Hit);
+        ecs.update(Duration::from_millis(600));
+        assert_eq!(state(&ecs), Enemy::Idle);
+        ecs.update(Duration::from_millis(600));
+        assert_eq!(state(&ecs), Enemy::Chase);
+        assert_eq!(
+            *log.borrow(),
+            vec!["enter idle", "exit idle", "enter chase"]
+        );
+
+        ecs.send_event(Hit);
+        ecs.update(Duration::from_millis(10));
+        assert_eq!(state(&ecs), Enemy::Dead);
+
+        ecs.entity_mut(enemy)
+            .unwrap()
+            .get_mut::<ComponentStateMachine<Enemy>>()
+            .unwrap()
+            .transition(Enemy::Idle);
+        ecs.update(Duration::from_millis(10));
+        assert_eq!(state(&ecs), Enemy::Idle);
+        assert_eq!(
+            ecs.system::<Changes>().unwrap().0,
+            vec![
+                (Enemy::Idle, Enemy::Chase),
+                (Enemy::Chase, Enemy::Dead),
+                (Enemy::Dead, Enemy::Idle)
+            ]
+        );
+    }
+}
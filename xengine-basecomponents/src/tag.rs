@@ -0,0 +1,80 @@
+use std::fmt::{self, Debug, Formatter};
+use std::marker::PhantomData;
+use xecs::Component;
+
+/// 泛型的标记组件，不同的`T`是不同的组件类型，`T`只用来区分标记，不需要实现任何trait
+///
+/// ```ignore
+/// struct Player;
+///
+/// ecs.create_entity().component(ComponentTag::<Player>::new()).finish();
+/// for entity in ecs.query::<With<ComponentTag<Player>>>() {
+///     ...
+/// }
+/// ```
+pub struct ComponentTag<T: 'static>(PhantomData<fn() -> T>);
+
+impl<T: 'static> ComponentTag<T> {
+    pub fn new() -> Self {
+        ComponentTag(PhantomData)
+    }
+}
+
+impl<T: 'static> Default for ComponentTag<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: 'static> Clone for ComponentTag<T> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl<T: 'static> Copy for ComponentTag<T> {}
+
+impl<T: 'static> Debug for ComponentTag<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "ComponentTag<{}>", std::any::type_name::<T>())
+    }
+}
+
+impl<T: 'static> Component for ComponentTag<T> {
+    fn name() -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xecs::{With, Without, ECS};
+
+    struct Player;
+    struct Enemy;
+
+    #[test]
+    fn test_tag() {
+        let mut ecs = ECS::new();
+        let player = ecs
+            .create_entity()
+            .component(ComponentTag::<Player>::new())
+            .finish();
+        let enemy = ecs
+            .create_entity()
+            .component(ComponentTag::<Enemy>::new())
+            .finish();
+
+        let players = ecs
+            .query::<With<ComponentTag<Player>>>()
+            .map(|entity| entity.id())
+            .collect::<Vec<_>>();
+        assert_eq!(players, vec![player]);
+        let others = ecs
+            .query::<Without<ComponentTag<Player>>>()
+            .map(|entity| entity.id())
+            .collect::<Vec<_>>();
+        assert_eq!(others, vec![enemy]);
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{ComponentChildren, ComponentDisabled, ComponentTransform};
+use std::collections::HashSet;
+use std::time::Duration;
+use xecs::{Component, EntityId, System, ECS};
+use xmath::{Multiply, Transform, Vector2};
+
+/// 相对父实体的局部变换，由`SystemTransformPropagation`合成世界坐标的`ComponentTransform`
+///
+/// 没有父实体时局部变换就是世界变换
+pub struct ComponentLocalTransform(pub Transform<f32>);
+
+impl Component for ComponentLocalTransform {
+    fn name() -> &'static str {
+        "LocalTransform"
+    }
+}
+
+/// 世界坐标的缩放，没有这个组件的实体缩放为1
+///
+/// `xengine-physics`创建fixture时会按它缩放collider的尺寸
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentScale(pub Vector2<f32>);
+
+impl Default for ComponentScale {
+    fn default() -> Self {
+        ComponentScale((1.0, 1.0).into())
+    }
+}
+
+impl Component for ComponentScale {
+    fn name() -> &'static str {
+        "Scale"
+    }
+}
+
+/// 相对父实体的缩放，由`SystemTransformPropagation`合成世界坐标的`ComponentScale`
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentLocalScale(pub Vector2<f32>);
+
+impl Default for ComponentLocalScale {
+    fn default() -> Self {
+        ComponentLocalScale((1.0, 1.0).into())
+    }
+}
+
+impl Component for ComponentLocalScale {
+    fn name() -> &'static str {
+        "LocalScale"
+    }
+}
+
+fn transform_eq(a: &Transform<f32>, b: &Transform<f32>) -> bool {
+    a.p == b.p && a.q.s == b.q.s && a.q.c == b.q.c
+}
+
+/// 在父实体缩放后的坐标系中应用局部变换
+///
+/// 非均匀缩放和旋转组合时不会产生切变，子实体只有位置受父实体缩放影响
+pub fn compose_scaled(
+    parent: &Transform<f32>,
+    parent_scale: Vector2<f32>,
+    local: &Transform<f32>,
+) -> Transform<f32> {
+    parent.multiply(Transform {
+        p: local.p * parent_scale,
+        q: local.q,
+    })
+}
+
+/// 沿着`ComponentChildren`的层级，把父实体的世界变换和子实体的`ComponentLocalTransform`合成子实体的`ComponentTransform`，
+/// 缩放同理
+///
+/// 没有`ComponentLocalTransform`的实体保持自己的`ComponentTransform`不变，没有`ComponentLocalScale`的实体
+/// 保持自己的`ComponentScale`不变，都没有时继承父实体的缩放
+#[derive(Default)]
+pub struct SystemTransformPropagation;
+
+impl SystemTransformPropagation {
+    fn propagate(ecs: &mut ECS, id: EntityId, parent: Option<(Transform<f32>, Vector2<f32>)>) {
+        let (local, local_scale, children) = match ecs.entity(id) {
+            Some(entity) if entity.contains::<ComponentDisabled>() => return,
+            Some(entity) => (
+                entity.get::<ComponentLocalTransform>().map(|local| local.0),
+                entity.get::<ComponentLocalScale>().map(|scale| scale.0),
+                entity
+                    .get::<ComponentChildren>()
+                    .map(|children| children.0.clone())
+                    .unwrap_or_default(),
+            ),
+            None => return,
+        };
+        let parent_scale = parent
+            .map(|(_, scale)| scale)
+            .unwrap_or_else(|| (1.0, 1.0).into());
+
+        // 只在变化时写入，避免组件被标记为已修改
+        let mut entity = ecs.entity_mut(id).unwrap();
+        let world = match local {
+            Some(local) => {
+                let world = match parent {
+                    Some((parent, _)) => compose_scaled(&parent, parent_scale, &local),
+                    None => local,
+                };
+                match entity.get::<ComponentTransform>() {
+                    Some(transform) if transform_eq(&transform.0, &world) => {}
+                    Some(_) => entity.get_mut::<ComponentTransform>().unwrap().0 = world,
+                    None => entity.add(ComponentTransform(world)),
+                }
+                world
+            }
+            None => entity
+                .get::<ComponentTransform>()
+                .map(|transform| transform.0)
+                .unwrap_or_else(Transform::identity),
+        };
+        let scale = match local_scale {
+            Some(local_scale) => {
+                let scale = parent_scale * local_scale;
+                match entity.get::<ComponentScale>() {
+                    Some(world_scale) if world_scale.0 == scale => {}
+                    Some(_) => entity.get_mut::<ComponentScale>().unwrap().0 = scale,
+                    None => entity.add(ComponentScale(scale)),
+                }
+                scale
+            }
+            None => entity
+                .get::<ComponentScale>()
+                .map(|scale| scale.0)
+                .unwrap_or(parent_scale),
+        };
+
+        for child in children {
+            Self::propagate(ecs, child, Some((world, scale)));
+        }
+    }
+}
+
+impl System for SystemTransformPropagation {
+    fn update(&mut self, ecs: &mut ECS, _delta: Duration) {
+        let children = ecs
+            .iter()
+            .flat_map(|entity| {
+                entity
+                    .get::<ComponentChildren>()
+                    .map(|children| children.0.clone())
+                    .unwrap_or_default()
+            })
+            .collect::<HashSet<_>>();
+        let roots = ecs
+            .entities()
+            .filter(|id| !children.contains(id))
+            .collect::<Vec<_>>();
+        for id in roots {
+            Self::propagate(ecs, id, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xmath::Rotation;
+
+    #[test]
+    fn test_transform_propagation() {
+        let mut ecs = ECS::new();
+        ecs.add_system(0, SystemTransformPropagation);
+        let turret = ecs
+            .create_entity()
+            .component(ComponentLocalTransform(Transform::new(
+                (1.0, 0.0).into(),
+                Rotation::new(0.0),
+            )))
+            .finish();
+        let tank = ecs
+            .create_entity()
+            .component(ComponentLocalTransform(Transform::new(
+                (1.0, 0.0).into(),
+                Rotation::new(std::f32::consts::FRAC_PI_2),
+            )))
+            .component(ComponentChildren(vec![turret]))
+            .finish();
+        ecs.update(Duration::from_secs(1) / 60);
+
+        let p = ecs
+            .entity(turret)
+            .unwrap()
+            .get::<ComponentTransform>()
+            .unwrap()
+            .0
+            .p;
+        assert!(p.distance(&Vector2::new(1.0, 1.0)) < 0.0001);
+
+        ecs.entity_mut(tank)
+            .unwrap()
+            .get_mut::<ComponentLocalTransform>()
+            .unwrap()
+            .0
+            .p = (2.0, 0.0).into();
+        ecs.update(Duration::from_secs(1) / 60);
+        let p = ecs
+            .entity(turret)
+            .unwrap()
+            .get::<ComponentTransform>()
+            .unwrap()
+            .0
+            .p;
+        assert!(p.distance(&Vector2::new(2.0, 1.0)) < 0.0001);
+
+        ecs.entity_mut(tank)
+            .unwrap()
+            .add(ComponentLocalScale((2.0, 3.0).into()));
+        ecs.update(Duration::from_secs(1) / 60);
+        let p = ecs
+            .entity(turret)
+            .unwrap()
+            .get::<ComponentTransform>()
+            .unwrap()
+            .0
+            .p;
+        assert!(p.distance(&Vector2::new(2.0, 2.0)) < 0.0001);
+        let scale = ecs.entity(tank).unwrap().get::<ComponentScale>().unwrap().0;
+        assert_eq!(scale, Vector2::new(2.0, 3.0));
+    }
+}
@@ -0,0 +1,194 @@
+use crate::ComponentDisabled;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::time::Duration;
+use xecs::{Component, EntityId, System, With, Without, ECS};
+use xmath::{Easing, Lerp};
+
+/// 补间播放完以后的行为
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TweenMode {
+    /// 播放一次，结束后删除补间组件
+    Once,
+
+    /// 从头重复播放
+    Repeat,
+
+    /// 来回播放
+    PingPong,
+}
+
+/// 补间动画，由`SystemTween<C>`每帧把插值结果写入同一个实体的组件`C`
+///
+/// 一个实体对每种组件只能有一个补间，同时改变多个字段时在`setter`里一起设置。
+pub struct ComponentTween<C> {
+    duration: Duration,
+    elapsed: Duration,
+    easing: Easing,
+    mode: TweenMode,
+    apply: Rc<dyn Fn(&mut C, f32)>,
+}
+
+impl<C: Component> ComponentTween<C> {
+    /// 在`duration`时间内把值从`from`变化到`to`，通过`setter`写入组件
+    pub fn new<V: Lerp + 'static>(
+        from: V,
+        to: V,
+        duration: Duration,
+        easing: Easing,
+        setter: fn(&mut C, V),
+    ) -> ComponentTween<C> {
+        ComponentTween {
+            duration,
+            elapsed: Duration::default(),
+            easing,
+            mode: TweenMode::Once,
+            apply: Rc::new(move |c, t| setter(c, from.lerp(&to, t))),
+        }
+    }
+
+    pub fn with_mode(self, mode: TweenMode) -> ComponentTween<C> {
+        ComponentTween { mode, ..self }
+    }
+
+    pub fn mode(&self) -> TweenMode {
+        self.mode
+    }
+
+    /// 当前的进度，0到1之间，没有经过缓动曲线
+    pub fn progress(&self) -> f32 {
+        let t = if self.duration == Duration::default() {
+            1.0
+        } else {
+            self.elapsed.as_secs_f32() / self.duration.as_secs_f32()
+        };
+        match self.mode {
+            TweenMode::Once => t.min(1.0),
+            TweenMode::Repeat => t.fract(),
+            TweenMode::PingPong => {
+                let t = t % 2.0;
+                if t > 1.0 {
+                    2.0 - t
+                } else {
+                    t
+                }
+            }
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.mode == TweenMode::Once && self.elapsed >= self.duration
+    }
+}
+
+impl<C: Component> Component for ComponentTween<C> {
+    fn name() -> &'static str {
+        "Tween"
+    }
+}
+
+/// 只播放一次的补间结束，在删除`ComponentTween<C>`之前通过`Event::Custom`发送
+#[derive(Debug, Copy, Clone)]
+pub struct TweenCompleted {
+    pub entity: EntityId,
+
+    /// 被补间的组件的名字
+    pub component: &'static str,
+}
+
+/// 更新`ComponentTween<C>`，每种需要补间的组件都要单独添加一个系统
+pub struct SystemTween<C>(PhantomData<C>);
+
+impl<C> Default for SystemTween<C> {
+    fn default() -> Self {
+        SystemTween(PhantomData)
+    }
+}
+
+impl<C: Component> System for SystemTween<C> {
+    fn update(&mut self, ecs: &mut ECS, delta: Duration) {
+        let ids = ecs
+            .query::<(With<ComponentTween<C>>, With<C>, Without<ComponentDisabled>)>()
+            .map(|entity| entity.id())
+            .collect::<Vec<_>>();
+
+        for id in ids {
+            let mut entity = ecs.entity_mut(id).unwrap();
+            let (t, apply, finished) = {
+                let tween = entity.get_mut::<ComponentTween<C>>().unwrap();
+                tween.elapsed += delta;
+                (
+                    tween.easing.apply(tween.progress()),
+                    tween.apply.clone(),
+                    tween.is_finished(),
+                )
+            };
+            apply(entity.get_mut::<C>().unwrap(), t);
+
+            if finished {
+                entity.remove::<ComponentTween<C>>();
+                ecs.send_event(TweenCompleted {
+                    entity: id,
+                    component: C::name(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ComponentTransform;
+    use xecs::Event;
+    use xmath::{Transform, Vector2};
+
+    #[derive(Default)]
+    struct Completed(Vec<EntityId>);
+
+    impl System for Completed {
+        fn handle_event(&mut self, _ecs: &mut ECS, event: &Event) {
+            if let Some(event) = event.downcast_ref::<TweenCompleted>() {
+                self.0.push(event.entity);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tween() {
+        let mut ecs = ECS::new();
+        ecs.add_system(0, SystemTween::<ComponentTransform>::default());
+        ecs.add_system(1, Completed::default());
+        let id = ecs
+            .create_entity()
+            .component(ComponentTransform(Transform::identity()))
+            .component(ComponentTween::<ComponentTransform>::new(
+                Vector2::new(0.0, 0.0),
+                Vector2::new(4.0, 0.0),
+                Duration::from_secs(1),
+                Easing::Linear,
+                |c, p| c.0.p = p,
+            ))
+            .finish();
+
+        ecs.update(Duration::from_millis(250));
+        let position = |ecs: &ECS| {
+            ecs.entity(id)
+                .unwrap()
+                .get::<ComponentTransform>()
+                .unwrap()
+                .0
+                .p
+        };
+        assert_eq!(position(&ecs), Vector2::new(1.0, 0.0));
+        assert!(ecs.system::<Completed>().unwrap().0.is_empty());
+
+        ecs.update(Duration::from_millis(1000));
+        assert_eq!(position(&ecs), Vector2::new(4.0, 0.0));
+        assert!(!ecs
+            .entity(id)
+            .unwrap()
+            .contains::<ComponentTween<ComponentTransform>>());
+        assert_eq!(ecs.system::<Completed>().unwrap().0, vec![id]);
+    }
+}
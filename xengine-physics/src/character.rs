@@ -0,0 +1,237 @@
+use crate::SystemPhysics;
+use std::time::Duration;
+use xecs::{Component, EntityId, System, With, Without, ECS};
+use xengine_basecomponents::{ComponentDisabled, ComponentTransform};
+use xmath::Vector2;
+
+/// 每条边发射的射线数量
+const RAY_COUNT: usize = 3;
+
+/// 角色控制器，由`SystemCharacterController`通过射线检测移动角色，不需要动态刚体
+///
+/// 角色是一个轴对齐的矩形，重力方向固定向下
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentCharacterController {
+    /// 角色矩形的半宽和半高
+    pub half_size: Vector2<f32>,
+
+    /// 当前速度，gameplay每帧设置水平速度，跳跃时设置竖直速度
+    pub velocity: Vector2<f32>,
+
+    /// 向下的重力加速度
+    pub gravity: f32,
+
+    /// 可以行走的最大坡度（弧度）
+    pub max_slope: f32,
+
+    /// 可以直接走上去的台阶高度
+    pub step_height: f32,
+
+    /// 射线从角色内部这个距离处发出，避免贴着表面时检测不到
+    pub skin_width: f32,
+
+    /// 站在可以行走的地面上
+    pub grounded: bool,
+
+    /// 碰到了天花板
+    pub on_ceiling: bool,
+
+    /// 水平移动被墙挡住
+    pub on_wall: bool,
+}
+
+impl Default for ComponentCharacterController {
+    fn default() -> Self {
+        ComponentCharacterController {
+            half_size: (0.25, 0.5).into(),
+            velocity: Vector2::zero(),
+            gravity: 10.0,
+            max_slope: 45f32.to_radians(),
+            step_height: 0.1,
+            skin_width: 0.02,
+            grounded: false,
+            on_ceiling: false,
+            on_wall: false,
+        }
+    }
+}
+
+impl Component for ComponentCharacterController {
+    fn name() -> &'static str {
+        "CharacterController"
+    }
+}
+
+fn slope_angle(normal: Vector2<f32>) -> f32 {
+    normal.y.max(-1.0).min(1.0).acos()
+}
+
+struct MoveAndSlide<'a> {
+    physics: &'a SystemPhysics,
+    id: EntityId,
+}
+
+impl<'a> MoveAndSlide<'a> {
+    /// 返回击中点的距离和法线，忽略传感器和角色自己
+    fn cast(
+        &self,
+        origin: Vector2<f32>,
+        dir: Vector2<f32>,
+        length: f32,
+    ) -> Option<(f32, Vector2<f32>)> {
+        let id = self.id;
+        let world = &self.physics.world;
+        self.physics
+            .ray_cast_fixtures(origin, origin + dir * length, |fixture| {
+                !fixture.is_sensor()
+                    && fixture.data() != Some(&id)
+                    && world.body(fixture.body()).and_then(|body| body.data()) != Some(&id)
+            })
+            .map(|hit| (hit.point.distance(&origin), hit.normal))
+    }
+
+    fn run(
+        &self,
+        c: &mut ComponentCharacterController,
+        position: Vector2<f32>,
+        dt: f32,
+    ) -> Vector2<f32> {
+        let was_grounded = c.grounded;
+        let half = c.half_size;
+        let skin = c.skin_width;
+        c.velocity.y -= c.gravity * dt;
+        c.grounded = false;
+        c.on_ceiling = false;
+        c.on_wall = false;
+
+        let mut delta = c.velocity * dt;
+        let mut climbed = false;
+        let mut stepped = false;
+
+        // 水平方向
+        if delta.x != 0.0 {
+            let dir = delta.x.signum();
+            let mut length = delta.x.abs() + skin;
+            let feet = position.y - half.y + skin;
+            for i in 0..RAY_COUNT {
+                let y = feet + 2.0 * (half.y - skin) * i as f32 / (RAY_COUNT - 1) as f32;
+                let origin = Vector2::new(position.x + dir * (half.x - skin), y);
+                let (distance, normal) = match self.cast(origin, (dir, 0.0).into(), length) {
+                    Some(hit) => hit,
+                    None => continue,
+                };
+
+                if i == 0 {
+                    let angle = slope_angle(normal);
+                    if angle <= c.max_slope {
+                        // 沿着斜坡向上走
+                        delta.y = delta.y.max(delta.x.abs() * angle.tan());
+                        climbed = true;
+                        continue;
+                    }
+                    let step_origin = Vector2::new(origin.x, feet + c.step_height);
+                    if c.step_height > 0.0
+                        && self.cast(step_origin, (dir, 0.0).into(), length).is_none()
+                    {
+                        // 台阶高度处没有障碍，走上台阶
+                        stepped = true;
+                        continue;
+                    }
+                }
+
+                length = distance;
+                delta.x = (distance - skin) * dir;
+                c.on_wall = true;
+            }
+        }
+        if c.on_wall {
+            c.velocity.x = 0.0;
+        }
+
+        // 竖直方向，走上台阶时先抬高再向下检测
+        let x = position.x + delta.x;
+        let lift = if stepped { c.step_height } else { 0.0 };
+        let dir = if delta.y > 0.0 { 1.0 } else { -1.0 };
+        let base_y = if dir < 0.0 {
+            position.y + lift
+        } else {
+            position.y
+        };
+        let mut length = delta.y.abs() + skin;
+        if dir < 0.0 {
+            length += lift;
+            if was_grounded || climbed {
+                // 下坡时贴着地面
+                length += delta.x.abs() * c.max_slope.tan();
+            }
+        }
+
+        let mut hit = false;
+        for i in 0..RAY_COUNT {
+            let ray_x =
+                x - half.x + skin + 2.0 * (half.x - skin) * i as f32 / (RAY_COUNT - 1) as f32;
+            let origin = Vector2::new(ray_x, base_y + dir * (half.y - skin));
+            if let Some((distance, normal)) = self.cast(origin, (0.0, dir).into(), length) {
+                length = distance;
+                hit = true;
+                if dir > 0.0 {
+                    c.on_ceiling = true;
+                } else if slope_angle(normal) <= c.max_slope {
+                    c.grounded = true;
+                }
+            }
+        }
+        c.grounded |= climbed;
+
+        let y = if hit {
+            base_y + dir * (length - skin)
+        } else {
+            position.y + delta.y
+        };
+        if (c.grounded && c.velocity.y < 0.0) || (c.on_ceiling && c.velocity.y > 0.0) {
+            c.velocity.y = 0.0;
+        }
+        Vector2::new(x, y)
+    }
+}
+
+/// 移动带有`ComponentCharacterController`的实体，并且更新地面、天花板和墙的标记
+///
+/// 通过`SystemPhysics`的射线检测实现，需要和`SystemPhysics`一起添加
+#[derive(Default)]
+pub struct SystemCharacterController;
+
+impl System for SystemCharacterController {
+    fn update(&mut self, ecs: &mut ECS, delta: Duration) {
+        let dt = delta.as_secs_f32();
+        if dt <= 0.0 {
+            return;
+        }
+
+        let ids = ecs
+            .query::<(
+                With<ComponentCharacterController>,
+                With<ComponentTransform>,
+                Without<ComponentDisabled>,
+            )>()
+            .map(|entity| entity.id())
+            .collect::<Vec<_>>();
+        for id in ids {
+            let (mut controller, position) = match ecs.entity(id) {
+                Some(entity) => (
+                    *entity.get::<ComponentCharacterController>().unwrap(),
+                    entity.get::<ComponentTransform>().unwrap().0.p,
+                ),
+                None => continue,
+            };
+            let position = match ecs.system::<SystemPhysics>() {
+                Some(physics) => MoveAndSlide { physics, id }.run(&mut controller, position, dt),
+                None => return,
+            };
+
+            let mut entity = ecs.entity_mut(id).unwrap();
+            *entity.get_mut::<ComponentCharacterController>().unwrap() = controller;
+            entity.get_mut::<ComponentTransform>().unwrap().0.p = position;
+        }
+    }
+}
@@ -2,10 +2,12 @@ use std::collections::hash_map::DefaultHasher;
 use std::hash::Hasher;
 use std::ops::Deref;
 use xecs::Component;
-use xmath::Vector2;
+use xengine_basecomponents::compose_scaled;
+use xmath::{Multiply, Transform, Vector2};
 use xphysics::Filter;
 
-pub struct Fixture {
+/// collider创建fixture时使用的摩擦、弹性、密度和过滤属性
+pub struct ColliderMaterial {
     pub friction: f32,
     pub restitution: f32,
     pub density: f32,
@@ -22,13 +24,73 @@ fn hash_vector2<H: Hasher>(value: Vector2<f32>, state: &mut H) {
     hash_f32(value.y, state);
 }
 
+fn hash_transform<H: Hasher>(xf: &Transform<f32>, state: &mut H) {
+    hash_vector2(xf.p, state);
+    hash_f32(xf.q.s, state);
+    hash_f32(xf.q.c, state);
+}
+
+/// collider所在实体相对刚体的变换和缩放
+#[derive(Copy, Clone)]
+pub(crate) struct ColliderFrame {
+    pub(crate) xf: Transform<f32>,
+    pub(crate) scale: Vector2<f32>,
+}
+
+impl ColliderFrame {
+    pub(crate) fn new(scale: Vector2<f32>) -> ColliderFrame {
+        ColliderFrame {
+            xf: Transform::identity(),
+            scale,
+        }
+    }
+
+    /// 子实体的坐标系，子实体的位置会按父实体的缩放
+    pub(crate) fn child(&self, local: &Transform<f32>, scale: Vector2<f32>) -> ColliderFrame {
+        ColliderFrame {
+            xf: compose_scaled(&self.xf, self.scale, local),
+            scale: self.scale * scale,
+        }
+    }
+
+    /// 把collider局部坐标系中的点转换到刚体坐标系
+    pub(crate) fn point(&self, local: &Transform<f32>, v: Vector2<f32>) -> Vector2<f32> {
+        self.xf.multiply(local.multiply(v) * self.scale)
+    }
+
+    /// 非均匀缩放时只缩放矩形的边长，不会产生切变
+    pub(crate) fn angle(&self, local: &Transform<f32>) -> f32 {
+        self.xf.q.angle() + local.q.angle()
+    }
+
+    pub(crate) fn extent(&self, half_size: Vector2<f32>) -> Vector2<f32> {
+        half_size * self.scale.abs()
+    }
+
+    /// 非均匀缩放时使用较大的缩放
+    pub(crate) fn radius(&self, radius: f32) -> f32 {
+        radius * self.scale.x.abs().max(self.scale.y.abs())
+    }
+
+    /// 把变换和缩放合并到shape hash中
+    pub(crate) fn hash(&self, hash: u64) -> u64 {
+        let mut state = DefaultHasher::new();
+        state.write_u64(hash);
+        hash_transform(&self.xf, &mut state);
+        hash_vector2(self.scale, &mut state);
+        state.finish()
+    }
+}
+
 pub(crate) trait ShapeHash {
     fn hash(&self) -> u64;
 }
 
 pub struct ComponentColliderCircle {
-    pub fixture: Fixture,
-    pub position: Vector2<f32>,
+    pub fixture: ColliderMaterial,
+
+    /// 相对实体的局部变换，圆心在变换的原点
+    pub transform: Transform<f32>,
     pub radius: f32,
 }
 
@@ -39,7 +101,7 @@ impl Component for ComponentColliderCircle {
 }
 
 impl Deref for ComponentColliderCircle {
-    type Target = Fixture;
+    type Target = ColliderMaterial;
 
     fn deref(&self) -> &Self::Target {
         &self.fixture
@@ -49,15 +111,17 @@ impl Deref for ComponentColliderCircle {
 impl ShapeHash for ComponentColliderCircle {
     fn hash(&self) -> u64 {
         let mut state = DefaultHasher::new();
-        hash_vector2(self.position, &mut state);
+        hash_transform(&self.transform, &mut state);
         hash_f32(self.radius, &mut state);
         state.finish()
     }
 }
 
 pub struct ComponentColliderBox {
-    pub fixture: Fixture,
-    pub position: Vector2<f32>,
+    pub fixture: ColliderMaterial,
+
+    /// 相对实体的局部变换，矩形的中心在变换的原点
+    pub transform: Transform<f32>,
     pub half_size: Vector2<f32>,
 }
 
@@ -68,7 +132,7 @@ impl Component for ComponentColliderBox {
 }
 
 impl Deref for ComponentColliderBox {
-    type Target = Fixture;
+    type Target = ColliderMaterial;
 
     fn deref(&self) -> &Self::Target {
         &self.fixture
@@ -78,14 +142,18 @@ impl Deref for ComponentColliderBox {
 impl ShapeHash for ComponentColliderBox {
     fn hash(&self) -> u64 {
         let mut state = DefaultHasher::new();
-        hash_vector2(self.position, &mut state);
+        hash_transform(&self.transform, &mut state);
         hash_vector2(self.half_size, &mut state);
         state.finish()
     }
 }
 
 pub struct ComponentColliderEdge {
-    pub fixture: Fixture,
+    pub fixture: ColliderMaterial,
+
+    /// 相对实体的局部变换，顶点在这个变换的坐标系中
+    pub transform: Transform<f32>,
+
     pub vertex1: Vector2<f32>,
     pub vertex2: Vector2<f32>,
 }
@@ -97,7 +165,7 @@ impl Component for ComponentColliderEdge {
 }
 
 impl Deref for ComponentColliderEdge {
-    type Target = Fixture;
+    type Target = ColliderMaterial;
 
     fn deref(&self) -> &Self::Target {
         &self.fixture
@@ -107,6 +175,7 @@ impl Deref for ComponentColliderEdge {
 impl ShapeHash for ComponentColliderEdge {
     fn hash(&self) -> u64 {
         let mut state = DefaultHasher::new();
+        hash_transform(&self.transform, &mut state);
         hash_vector2(self.vertex1, &mut state);
         hash_vector2(self.vertex2, &mut state);
         state.finish()
@@ -114,7 +183,11 @@ impl ShapeHash for ComponentColliderEdge {
 }
 
 pub struct ComponentColliderChain {
-    pub fixture: Fixture,
+    pub fixture: ColliderMaterial,
+
+    /// 相对实体的局部变换，顶点在这个变换的坐标系中
+    pub transform: Transform<f32>,
+
     pub is_loop: bool,
     pub vertices: Vec<Vector2<f32>>,
 }
@@ -126,7 +199,7 @@ impl Component for ComponentColliderChain {
 }
 
 impl Deref for ComponentColliderChain {
-    type Target = Fixture;
+    type Target = ColliderMaterial;
 
     fn deref(&self) -> &Self::Target {
         &self.fixture
@@ -136,6 +209,7 @@ impl Deref for ComponentColliderChain {
 impl ShapeHash for ComponentColliderChain {
     fn hash(&self) -> u64 {
         let mut state = DefaultHasher::new();
+        hash_transform(&self.transform, &mut state);
         state.write_u8(self.is_loop as u8);
         for v in &self.vertices {
             hash_vector2(*v, &mut state);
@@ -145,7 +219,11 @@ impl ShapeHash for ComponentColliderChain {
 }
 
 pub struct ComponentColliderPolygon {
-    pub fixture: Fixture,
+    pub fixture: ColliderMaterial,
+
+    /// 相对实体的局部变换，顶点在这个变换的坐标系中
+    pub transform: Transform<f32>,
+
     pub vertices: Vec<Vector2<f32>>,
 }
 
@@ -156,7 +234,7 @@ impl Component for ComponentColliderPolygon {
 }
 
 impl Deref for ComponentColliderPolygon {
-    type Target = Fixture;
+    type Target = ColliderMaterial;
 
     fn deref(&self) -> &Self::Target {
         &self.fixture
@@ -166,9 +244,60 @@ impl Deref for ComponentColliderPolygon {
 impl ShapeHash for ComponentColliderPolygon {
     fn hash(&self) -> u64 {
         let mut state = DefaultHasher::new();
+        hash_transform(&self.transform, &mut state);
         for v in &self.vertices {
             hash_vector2(*v, &mut state);
         }
         state.finish()
     }
 }
+
+/// 复合collider里的形状
+pub enum ShapeKind {
+    Circle { radius: f32 },
+    Box { half_size: Vector2<f32> },
+    Polygon { vertices: Vec<Vector2<f32>> },
+}
+
+/// 复合collider里的一个形状
+pub struct ShapeDesc {
+    pub fixture: ColliderMaterial,
+
+    /// 相对实体的局部变换
+    pub transform: Transform<f32>,
+
+    pub kind: ShapeKind,
+}
+
+impl ShapeHash for ShapeDesc {
+    fn hash(&self) -> u64 {
+        let mut state = DefaultHasher::new();
+        hash_transform(&self.transform, &mut state);
+        match &self.kind {
+            ShapeKind::Circle { radius } => {
+                state.write_u8(0);
+                hash_f32(*radius, &mut state);
+            }
+            ShapeKind::Box { half_size } => {
+                state.write_u8(1);
+                hash_vector2(*half_size, &mut state);
+            }
+            ShapeKind::Polygon { vertices } => {
+                state.write_u8(2);
+                for v in vertices {
+                    hash_vector2(*v, &mut state);
+                }
+            }
+        }
+        state.finish()
+    }
+}
+
+/// 复合collider，每个形状都会在同一个刚体上创建一个fixture
+pub struct ComponentColliderCompound(pub Vec<ShapeDesc>);
+
+impl Component for ComponentColliderCompound {
+    fn name() -> &'static str {
+        "ColliderCompound"
+    }
+}
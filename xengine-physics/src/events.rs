@@ -0,0 +1,129 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use xecs::EntityId;
+use xmath::Vector2;
+use xphysics::{ContactImpulse, ContactKey, ContactListener, ContactMut};
+
+/// 两个实体开始碰撞，通过`Event::Custom`发送
+#[derive(Debug, Copy, Clone)]
+pub struct CollisionBegin {
+    pub a: EntityId,
+    pub b: EntityId,
+
+    /// 从`a`指向`b`的碰撞法线
+    pub normal: Vector2<f32>,
+
+    /// 第一次求解时的法向冲量之和
+    pub impulse: f32,
+}
+
+/// 两个实体结束碰撞，通过`Event::Custom`发送
+#[derive(Debug, Copy, Clone)]
+pub struct CollisionEnd {
+    pub a: EntityId,
+    pub b: EntityId,
+}
+
+/// 实体进入传感器，通过`Event::Custom`发送
+#[derive(Debug, Copy, Clone)]
+pub struct TriggerEnter {
+    /// 传感器所在的实体
+    pub sensor: EntityId,
+
+    /// 进入传感器的实体
+    pub other: EntityId,
+}
+
+/// 实体离开传感器，通过`Event::Custom`发送
+#[derive(Debug, Copy, Clone)]
+pub struct TriggerExit {
+    /// 传感器所在的实体
+    pub sensor: EntityId,
+
+    /// 离开传感器的实体
+    pub other: EntityId,
+}
+
+#[derive(Default)]
+pub(crate) struct ContactEvents {
+    pub(crate) begin: Vec<(ContactKey, CollisionBegin)>,
+    pub(crate) end: Vec<CollisionEnd>,
+    pub(crate) trigger_enter: Vec<TriggerEnter>,
+    pub(crate) trigger_exit: Vec<TriggerExit>,
+}
+
+fn contact_entities(contact: &ContactMut<f32, EntityId>) -> Option<(EntityId, EntityId)> {
+    match (contact.fixture_a().data(), contact.fixture_b().data()) {
+        (Some(a), Some(b)) => Some((*a, *b)),
+        _ => None,
+    }
+}
+
+/// 返回(传感器实体, 另一个实体)，如果两个fixture都不是传感器则返回`None`
+fn sensor_entities(contact: &ContactMut<f32, EntityId>) -> Option<(EntityId, EntityId)> {
+    let (a, b) = contact_entities(contact)?;
+    if contact.fixture_a().is_sensor() {
+        Some((a, b))
+    } else if contact.fixture_b().is_sensor() {
+        Some((b, a))
+    } else {
+        None
+    }
+}
+
+/// 在`World::step`期间收集碰撞事件，步进结束后再发送到ECS
+pub(crate) struct ContactEventsListener(pub(crate) Rc<RefCell<ContactEvents>>);
+
+impl ContactListener<f32, EntityId> for ContactEventsListener {
+    fn begin_contact(&self, contact: &mut ContactMut<f32, EntityId>) {
+        if contact.fixture_a().is_sensor() || contact.fixture_b().is_sensor() {
+            if let Some((sensor, other)) = sensor_entities(contact) {
+                self.0
+                    .borrow_mut()
+                    .trigger_enter
+                    .push(TriggerEnter { sensor, other });
+            }
+            return;
+        }
+        if let Some((a, b)) = contact_entities(contact) {
+            let normal = contact.world_manifold().normal;
+            self.0.borrow_mut().begin.push((
+                contact.id(),
+                CollisionBegin {
+                    a,
+                    b,
+                    normal,
+                    impulse: 0.0,
+                },
+            ));
+        }
+    }
+
+    fn end_contact(&self, contact: &mut ContactMut<f32, EntityId>) {
+        if contact.fixture_a().is_sensor() || contact.fixture_b().is_sensor() {
+            if let Some((sensor, other)) = sensor_entities(contact) {
+                self.0
+                    .borrow_mut()
+                    .trigger_exit
+                    .push(TriggerExit { sensor, other });
+            }
+            return;
+        }
+        if let Some((a, b)) = contact_entities(contact) {
+            self.0.borrow_mut().end.push(CollisionEnd { a, b });
+        }
+    }
+
+    fn post_solve(&self, contact: &mut ContactMut<f32, EntityId>, impulse: &ContactImpulse<f32>) {
+        // 事件在每一步结束后发送并清空，同一步中的接触ID不会被复用
+        let id = contact.id();
+        let mut events = self.0.borrow_mut();
+        if let Some((_, event)) = events
+            .begin
+            .iter_mut()
+            .find(|(k, event)| *k == id && event.impulse == 0.0)
+        {
+            event.impulse = impulse.normal_impulses[..impulse.count].iter().sum();
+        }
+    }
+}
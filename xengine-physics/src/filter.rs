@@ -0,0 +1,75 @@
+use crate::SystemPhysics;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use xecs::{EntityId, ECS};
+use xphysics::{ContactFilter, DefaultContactFilter, Fixture};
+
+type ContactFilterFn = dyn Fn(&ECS, EntityId, EntityId) -> bool;
+
+#[derive(Default)]
+pub(crate) struct ContactFilterState {
+    filter: RefCell<Option<Box<ContactFilterFn>>>,
+
+    /// 只在`World::step`期间有效，其它时候为空
+    ecs: Cell<Option<*const ECS>>,
+}
+
+impl ContactFilterState {
+    /// 在`f`执行期间让过滤函数可以访问`ecs`
+    pub(crate) fn with_ecs<R, F: FnOnce() -> R>(&self, ecs: &ECS, f: F) -> R {
+        // `f`panic的时候也要清空指针，避免留下悬垂的`ECS`指针
+        struct ClearEcs<'a>(&'a Cell<Option<*const ECS>>);
+
+        impl<'a> Drop for ClearEcs<'a> {
+            fn drop(&mut self) {
+                self.0.set(None);
+            }
+        }
+
+        self.ecs.set(Some(ecs as *const ECS));
+        let _guard = ClearEcs(&self.ecs);
+        f()
+    }
+}
+
+pub(crate) struct EcsContactFilter(pub(crate) Rc<ContactFilterState>);
+
+impl ContactFilter<f32, EntityId> for EcsContactFilter {
+    fn should_collide(
+        &self,
+        fixture_a: &Fixture<f32, EntityId>,
+        fixture_b: &Fixture<f32, EntityId>,
+    ) -> bool {
+        if !DefaultContactFilter.should_collide(fixture_a, fixture_b) {
+            return false;
+        }
+
+        let filter = self.0.filter.borrow();
+        match (
+            &*filter,
+            self.0.ecs.get(),
+            fixture_a.data(),
+            fixture_b.data(),
+        ) {
+            (Some(filter), Some(ecs), Some(a), Some(b)) => filter(unsafe { &*ecs }, *a, *b),
+            _ => true,
+        }
+    }
+}
+
+impl SystemPhysics {
+    /// 设置碰撞过滤函数，参数是两个fixture所在的实体，返回`false`时不会产生碰撞
+    ///
+    /// 只有通过了`category_bits`和`mask_bits`过滤的fixture才会调用这个函数，
+    /// 已经产生的碰撞不会重新过滤
+    pub fn set_contact_filter<F>(&mut self, filter: F)
+    where
+        F: Fn(&ECS, EntityId, EntityId) -> bool + 'static,
+    {
+        *self.contact_filter.filter.borrow_mut() = Some(Box::new(filter));
+    }
+
+    pub fn clear_contact_filter(&mut self) {
+        *self.contact_filter.filter.borrow_mut() = None;
+    }
+}
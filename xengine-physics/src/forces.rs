@@ -0,0 +1,67 @@
+use xecs::{Component, EntityId, EntityMut, EntityRef};
+use xmath::Vector2;
+use xphysics::Body;
+
+/// 在下一次物理步进时施加一次力，步进结束后自动删除
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentApplyForce {
+    pub force: Vector2<f32>,
+
+    /// 施力点的世界坐标，`None`表示作用在质心
+    pub point: Option<Vector2<f32>>,
+}
+
+impl Component for ComponentApplyForce {
+    fn name() -> &'static str {
+        "ApplyForce"
+    }
+}
+
+/// 在下一次物理步进时施加一次冲量，步进结束后自动删除
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentApplyImpulse {
+    pub impulse: Vector2<f32>,
+
+    /// 作用点的世界坐标，`None`表示作用在质心
+    pub point: Option<Vector2<f32>>,
+}
+
+impl Component for ComponentApplyImpulse {
+    fn name() -> &'static str {
+        "ApplyImpulse"
+    }
+}
+
+/// 在下一次物理步进时施加一次扭矩，步进结束后自动删除
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentApplyTorque(pub f32);
+
+impl Component for ComponentApplyTorque {
+    fn name() -> &'static str {
+        "ApplyTorque"
+    }
+}
+
+pub(crate) fn apply_forces(entity: &EntityRef, body: &mut Body<f32, EntityId>) {
+    if let Some(component) = entity.get::<ComponentApplyForce>() {
+        match component.point {
+            Some(point) => body.apply_force(component.force, point, true),
+            None => body.apply_force_to_center(component.force, true),
+        }
+    }
+    if let Some(component) = entity.get::<ComponentApplyImpulse>() {
+        match component.point {
+            Some(point) => body.apply_linear_impulse(component.impulse, point, true),
+            None => body.apply_linear_impulse_to_center(component.impulse, true),
+        }
+    }
+    if let Some(component) = entity.get::<ComponentApplyTorque>() {
+        body.apply_torque(component.0, true);
+    }
+}
+
+pub(crate) fn clear_forces(entity: &mut EntityMut) {
+    entity.remove::<ComponentApplyForce>();
+    entity.remove::<ComponentApplyImpulse>();
+    entity.remove::<ComponentApplyTorque>();
+}
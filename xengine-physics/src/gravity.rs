@@ -0,0 +1,113 @@
+use crate::SystemPhysics;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use xecs::{Component, EntityId, ECS};
+use xmath::Vector2;
+use xphysics::BodyType;
+
+/// 重力区域对区域内的刚体的作用方式
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GravityMode {
+    /// 在世界重力的基础上叠加区域重力
+    Add,
+
+    /// 用区域重力代替世界重力
+    Replace,
+}
+
+/// 重力区域，区域的形状由同一个实体上的传感器collider决定
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentGravityZone {
+    pub gravity: Vector2<f32>,
+    pub mode: GravityMode,
+
+    /// 多个`Replace`区域重叠时使用优先级最高的，优先级相同时使用实体ID较小的
+    pub priority: i32,
+}
+
+impl Component for ComponentGravityZone {
+    fn name() -> &'static str {
+        "GravityZone"
+    }
+}
+
+impl SystemPhysics {
+    /// 返回和重力区域重叠的所有动态刚体
+    fn gravity_zone_overlaps(&self, zone_id: EntityId) -> Vec<EntityId> {
+        let mut entities = Vec::new();
+        let body = match self
+            .bodies
+            .get(&zone_id)
+            .and_then(|body_info| self.world.body(body_info.body_id))
+        {
+            Some(body) => body,
+            None => return entities,
+        };
+
+        for (_, fixture) in body.fixture_list() {
+            if !fixture.is_sensor() {
+                continue;
+            }
+            let overlaps = self
+                .world
+                .overlap_shape(fixture.shape(), *body.transform(), |other| {
+                    self.world
+                        .body(other.body())
+                        .is_some_and(|body| body.body_type() == BodyType::Dynamic)
+                });
+            for other in overlaps {
+                let other_id = match self.world.body(other.body()).and_then(|body| body.data()) {
+                    Some(id) if *id != zone_id => *id,
+                    _ => continue,
+                };
+                if !entities.contains(&other_id) {
+                    entities.push(other_id);
+                }
+            }
+        }
+        entities
+    }
+
+    pub(crate) fn apply_gravity_zones(&mut self, ecs: &ECS) {
+        // 每个刚体需要额外施加的重力加速度
+        let mut accelerations: HashMap<EntityId, (Vector2<f32>, bool)> = HashMap::new();
+        let world_gravity = self.world.gravity();
+
+        // 按固定的顺序处理区域，结果不受HashMap遍历顺序影响
+        let mut zones = self
+            .bodies
+            .keys()
+            .filter_map(|zone_id| {
+                ecs.entity(*zone_id)
+                    .and_then(|entity| entity.get::<ComponentGravityZone>().copied())
+                    .map(|zone| (*zone_id, zone))
+            })
+            .collect::<Vec<_>>();
+        zones.sort_by_key(|(zone_id, zone)| (Reverse(zone.priority), *zone_id));
+
+        for (zone_id, zone) in zones {
+            for id in self.gravity_zone_overlaps(zone_id) {
+                let (acceleration, replaced) =
+                    accelerations.entry(id).or_insert((Vector2::zero(), false));
+                match zone.mode {
+                    GravityMode::Add => *acceleration += zone.gravity,
+                    GravityMode::Replace if !*replaced => {
+                        *acceleration += zone.gravity - world_gravity;
+                        *replaced = true;
+                    }
+                    GravityMode::Replace => {}
+                }
+            }
+        }
+
+        for (id, (acceleration, _)) in accelerations {
+            let body_id = match self.bodies.get(&id) {
+                Some(body_info) => body_info.body_id,
+                None => continue,
+            };
+            let body = self.world.body_mut(body_id).unwrap();
+            let force = acceleration * (body.mass() * body.gravity_scale());
+            body.apply_force_to_center(force, true);
+        }
+    }
+}
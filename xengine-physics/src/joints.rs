@@ -0,0 +1,366 @@
+use crate::{BodyInfo, SystemPhysics};
+use std::any::TypeId;
+use std::collections::HashMap;
+use xecs::{Component, EntityId, EntityRef, ECS};
+use xmath::Vector2;
+use xphysics::{
+    BodyId, DistanceJointDef, Joint, JointDef, JointId, PrismaticJointDef, RevoluteJointDef, World,
+};
+
+/// 旋转关节，连接所在实体和`target`实体的刚体，可以用来制作门和布娃娃
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentJointRevolute {
+    pub target: EntityId,
+    pub collide_connected: bool,
+    pub local_anchor_a: Vector2<f32>,
+    pub local_anchor_b: Vector2<f32>,
+    pub reference_angle: f32,
+    pub enable_limit: bool,
+    pub lower_angle: f32,
+    pub upper_angle: f32,
+    pub enable_motor: bool,
+    pub motor_speed: f32,
+    pub max_motor_torque: f32,
+}
+
+impl ComponentJointRevolute {
+    pub fn new(target: EntityId) -> ComponentJointRevolute {
+        ComponentJointRevolute {
+            target,
+            collide_connected: false,
+            local_anchor_a: Vector2::zero(),
+            local_anchor_b: Vector2::zero(),
+            reference_angle: 0.0,
+            enable_limit: false,
+            lower_angle: 0.0,
+            upper_angle: 0.0,
+            enable_motor: false,
+            motor_speed: 0.0,
+            max_motor_torque: 0.0,
+        }
+    }
+}
+
+impl Component for ComponentJointRevolute {
+    fn name() -> &'static str {
+        "JointRevolute"
+    }
+}
+
+/// 距离关节，保持所在实体和`target`实体的锚点之间的距离
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentJointDistance {
+    pub target: EntityId,
+    pub collide_connected: bool,
+    pub local_anchor_a: Vector2<f32>,
+    pub local_anchor_b: Vector2<f32>,
+    pub length: f32,
+
+    /// 弹簧的频率，为0时是刚性连接
+    pub frequency_hz: f32,
+    pub damping_ratio: f32,
+}
+
+impl ComponentJointDistance {
+    pub fn new(target: EntityId, length: f32) -> ComponentJointDistance {
+        ComponentJointDistance {
+            target,
+            collide_connected: false,
+            local_anchor_a: Vector2::zero(),
+            local_anchor_b: Vector2::zero(),
+            length,
+            frequency_hz: 0.0,
+            damping_ratio: 0.0,
+        }
+    }
+}
+
+impl Component for ComponentJointDistance {
+    fn name() -> &'static str {
+        "JointDistance"
+    }
+}
+
+/// 平移关节，所在实体和`target`实体的刚体只能沿`local_axis_a`相对移动
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentJointPrismatic {
+    pub target: EntityId,
+    pub collide_connected: bool,
+    pub local_anchor_a: Vector2<f32>,
+    pub local_anchor_b: Vector2<f32>,
+    pub local_axis_a: Vector2<f32>,
+    pub reference_angle: f32,
+    pub enable_limit: bool,
+    pub lower_translation: f32,
+    pub upper_translation: f32,
+    pub enable_motor: bool,
+    pub motor_speed: f32,
+    pub max_motor_force: f32,
+}
+
+impl ComponentJointPrismatic {
+    pub fn new(target: EntityId, local_axis_a: Vector2<f32>) -> ComponentJointPrismatic {
+        ComponentJointPrismatic {
+            target,
+            collide_connected: false,
+            local_anchor_a: Vector2::zero(),
+            local_anchor_b: Vector2::zero(),
+            local_axis_a: local_axis_a.normalize(),
+            reference_angle: 0.0,
+            enable_limit: false,
+            lower_translation: 0.0,
+            upper_translation: 0.0,
+            enable_motor: false,
+            motor_speed: 0.0,
+            max_motor_force: 0.0,
+        }
+    }
+}
+
+impl Component for ComponentJointPrismatic {
+    fn name() -> &'static str {
+        "JointPrismatic"
+    }
+}
+
+/// 可以同步到xphysics关节的组件
+trait JointComponent: Component + Copy {
+    fn target(&self) -> EntityId;
+
+    fn joint_def(&self, body_a: BodyId, body_b: BodyId) -> JointDef<f32>;
+
+    /// 把限位和马达等属性同步到已有的关节，锚点等属性改变了需要重新创建关节时返回`false`
+    fn sync(&self, joint: &mut Joint<f32>) -> bool;
+}
+
+impl JointComponent for ComponentJointRevolute {
+    fn target(&self) -> EntityId {
+        self.target
+    }
+
+    fn joint_def(&self, body_a: BodyId, body_b: BodyId) -> JointDef<f32> {
+        RevoluteJointDef {
+            body_a,
+            body_b,
+            collide_connected: self.collide_connected,
+            local_anchor_a: self.local_anchor_a,
+            local_anchor_b: self.local_anchor_b,
+            reference_angle: self.reference_angle,
+            enable_limit: self.enable_limit,
+            lower_angle: self.lower_angle,
+            upper_angle: self.upper_angle,
+            enable_motor: self.enable_motor,
+            motor_speed: self.motor_speed,
+            max_motor_torque: self.max_motor_torque,
+        }
+        .into()
+    }
+
+    fn sync(&self, joint: &mut Joint<f32>) -> bool {
+        let collide_connected = joint.collide_connected();
+        let joint = match joint {
+            Joint::Revolute(joint) => joint,
+            _ => return false,
+        };
+        if collide_connected != self.collide_connected
+            || *joint.local_anchor_a() != self.local_anchor_a
+            || *joint.local_anchor_b() != self.local_anchor_b
+            || joint.reference_angle() != self.reference_angle
+        {
+            return false;
+        }
+        joint.enable_limit(self.enable_limit);
+        joint.set_limits(self.lower_angle, self.upper_angle);
+        joint.enable_motor(self.enable_motor);
+        joint.set_motor_speed(self.motor_speed);
+        joint.set_max_motor_torque(self.max_motor_torque);
+        true
+    }
+}
+
+impl JointComponent for ComponentJointDistance {
+    fn target(&self) -> EntityId {
+        self.target
+    }
+
+    fn joint_def(&self, body_a: BodyId, body_b: BodyId) -> JointDef<f32> {
+        DistanceJointDef {
+            body_a,
+            body_b,
+            collide_connected: self.collide_connected,
+            local_anchor_a: self.local_anchor_a,
+            local_anchor_b: self.local_anchor_b,
+            length: self.length,
+            frequency_hz: self.frequency_hz,
+            damping_ratio: self.damping_ratio,
+        }
+        .into()
+    }
+
+    fn sync(&self, joint: &mut Joint<f32>) -> bool {
+        let collide_connected = joint.collide_connected();
+        let joint = match joint {
+            Joint::Distance(joint) => joint,
+            _ => return false,
+        };
+        if collide_connected != self.collide_connected
+            || *joint.local_anchor_a() != self.local_anchor_a
+            || *joint.local_anchor_b() != self.local_anchor_b
+        {
+            return false;
+        }
+        joint.set_length(self.length);
+        joint.set_frequency(self.frequency_hz);
+        joint.set_damping_ratio(self.damping_ratio);
+        true
+    }
+}
+
+impl JointComponent for ComponentJointPrismatic {
+    fn target(&self) -> EntityId {
+        self.target
+    }
+
+    fn joint_def(&self, body_a: BodyId, body_b: BodyId) -> JointDef<f32> {
+        PrismaticJointDef {
+            body_a,
+            body_b,
+            collide_connected: self.collide_connected,
+            local_anchor_a: self.local_anchor_a,
+            local_anchor_b: self.local_anchor_b,
+            local_axis_a: self.local_axis_a.normalize(),
+            reference_angle: self.reference_angle,
+            enable_limit: self.enable_limit,
+            lower_translation: self.lower_translation,
+            upper_translation: self.upper_translation,
+            enable_motor: self.enable_motor,
+            max_motor_force: self.max_motor_force,
+            motor_speed: self.motor_speed,
+        }
+        .into()
+    }
+
+    fn sync(&self, joint: &mut Joint<f32>) -> bool {
+        let collide_connected = joint.collide_connected();
+        let joint = match joint {
+            Joint::Prismatic(joint) => joint,
+            _ => return false,
+        };
+        if collide_connected != self.collide_connected
+            || *joint.local_anchor_a() != self.local_anchor_a
+            || *joint.local_anchor_b() != self.local_anchor_b
+            || *joint.local_axis_a() != self.local_axis_a.normalize()
+            || joint.reference_angle() != self.reference_angle
+        {
+            return false;
+        }
+        joint.enable_limit(self.enable_limit);
+        joint.set_limits(self.lower_translation, self.upper_translation);
+        joint.enable_motor(self.enable_motor);
+        joint.set_motor_speed(self.motor_speed);
+        joint.set_max_motor_force(self.max_motor_force);
+        true
+    }
+}
+
+/// 关节所在的实体和关节组件类型
+pub(crate) type JointKey = (EntityId, TypeId);
+
+pub(crate) struct JointInfo {
+    joint_id: JointId,
+    target: EntityId,
+}
+
+struct JointSync<'a> {
+    world: &'a mut World<f32, EntityId>,
+    bodies: &'a HashMap<EntityId, BodyInfo>,
+    joints: &'a mut HashMap<JointKey, JointInfo>,
+    entity: &'a EntityRef<'a>,
+    body_a: BodyId,
+    tick: u64,
+}
+
+impl<'a> JointSync<'a> {
+    fn sync<C: JointComponent>(&mut self) {
+        let entity_id = self.entity.id();
+        let key = (entity_id, TypeId::of::<C>());
+        let component = self.entity.get::<C>().copied();
+        let bodies = self.bodies;
+        let body_b = component
+            .filter(|component| component.target() != entity_id)
+            .and_then(|component| bodies.get(&component.target()))
+            .map(|body_info| body_info.body_id);
+
+        let (component, body_b) = match (component, body_b) {
+            (Some(component), Some(body_b)) => (component, body_b),
+            _ => {
+                if let Some(joint_info) = self.joints.remove(&key) {
+                    self.world.destroy_joint(joint_info.joint_id);
+                }
+                return;
+            }
+        };
+
+        if let Some(joint_info) = self.joints.get(&key) {
+            if joint_info.target == component.target() {
+                if !self.entity.is_changed_since::<C>(self.tick) {
+                    return;
+                }
+                let joint = self.world.joint_mut(joint_info.joint_id).unwrap();
+                if component.sync(joint) {
+                    return;
+                }
+            }
+            self.world.destroy_joint(joint_info.joint_id);
+        }
+
+        let joint_id = self
+            .world
+            .create_joint(component.joint_def(self.body_a, body_b));
+        self.joints.insert(
+            key,
+            JointInfo {
+                joint_id,
+                target: component.target(),
+            },
+        );
+    }
+}
+
+impl SystemPhysics {
+    /// 根据关节组件创建、删除或者同步关节
+    pub(crate) fn sync_joints(&mut self, ecs: &ECS) {
+        let SystemPhysics {
+            world,
+            bodies,
+            joints,
+            ..
+        } = self;
+        for (entity_id, body_info) in bodies.iter() {
+            let entity = match ecs.entity(*entity_id) {
+                Some(entity) => entity,
+                None => continue,
+            };
+            if body_info.disabled {
+                continue;
+            }
+            let mut sync = JointSync {
+                world,
+                bodies,
+                joints,
+                entity: &entity,
+                body_a: body_info.body_id,
+                tick: body_info.synced_tick,
+            };
+            sync.sync::<ComponentJointRevolute>();
+            sync.sync::<ComponentJointDistance>();
+            sync.sync::<ComponentJointPrismatic>();
+        }
+    }
+
+    /// 刚体被销毁时xphysics会一起删除连接它的关节
+    pub(crate) fn forget_joints(&mut self, id: EntityId) {
+        self.joints
+            .retain(|(entity_id, _), joint_info| *entity_id != id && joint_info.target != id);
+    }
+}
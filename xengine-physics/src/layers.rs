@@ -0,0 +1,78 @@
+use xecs::Component;
+
+/// 命名的碰撞层，创建fixture时会编译成`Filter`的`category_bits`和`mask_bits`
+///
+/// 会覆盖同一个实体上所有collider的filter，没有刚体的子实体如果没有这个组件，会使用父实体的碰撞层，
+/// 都没有的collider属于`CollisionLayers::DEFAULT`层
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentCollisionLayer {
+    pub layer: &'static str,
+    pub collides_with: &'static [&'static str],
+}
+
+impl Component for ComponentCollisionLayer {
+    fn name() -> &'static str {
+        "CollisionLayer"
+    }
+}
+
+/// 碰撞层名字到`Filter`位的映射，作为ECS资源使用，没有添加时`SystemPhysics`会创建一个空的
+///
+/// 第0位保留给没有碰撞层的collider（名字是`CollisionLayers::DEFAULT`），命名的层从第1位开始，最多支持15个
+#[derive(Debug, Default, Clone)]
+pub struct CollisionLayers {
+    names: Vec<&'static str>,
+}
+
+impl CollisionLayers {
+    /// 没有碰撞层的collider所在的层，`collides_with`包含它时会和这些collider碰撞
+    pub const DEFAULT: &'static str = "default";
+
+    /// 默认层的位，和`Filter`默认的`category_bits`相同
+    pub const DEFAULT_BITS: u16 = 0x0001;
+
+    /// 注册一个碰撞层并返回对应的位，已经注册过的层直接返回，超过15个命名的层时返回`None`
+    pub fn register(&mut self, name: &'static str) -> Option<u16> {
+        if let Some(bits) = self.bits(name) {
+            return Some(bits);
+        }
+        if self.names.len() >= 15 {
+            return None;
+        }
+        self.names.push(name);
+        Some(1 << self.names.len())
+    }
+
+    pub fn bits(&self, name: &str) -> Option<u16> {
+        if name == Self::DEFAULT {
+            return Some(Self::DEFAULT_BITS);
+        }
+        self.names
+            .iter()
+            .position(|item| *item == name)
+            .map(|index| 1 << (index + 1))
+    }
+
+    /// 注册过的命名的层，不包括默认层
+    pub fn names(&self) -> &[&'static str] {
+        &self.names
+    }
+
+    /// 返回(category_bits, mask_bits)，没有注册的层会自动注册
+    ///
+    /// # Panics
+    ///
+    /// 命名的层超过15个时panic
+    pub(crate) fn compile(&mut self, layer: &ComponentCollisionLayer) -> (u16, u16) {
+        let mut register = |name| match self.register(name) {
+            Some(bits) => bits,
+            None => panic!("too many collision layers, can't register \"{}\"", name),
+        };
+        let category_bits = register(layer.layer);
+        let mask_bits = layer
+            .collides_with
+            .iter()
+            .fold(0, |bits, name| bits | register(name));
+        (category_bits, mask_bits)
+    }
+}
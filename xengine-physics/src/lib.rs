@@ -1,21 +1,51 @@
+mod character;
 mod colliders;
+mod events;
+mod filter;
+mod forces;
+mod gravity;
+mod joints;
+mod layers;
+mod materials;
+mod pathfinding;
+mod query;
+pub mod tiled;
 
-use crate::colliders::ShapeHash;
+pub use crate::character::{ComponentCharacterController, SystemCharacterController};
+use crate::colliders::{ColliderFrame, ShapeHash};
 pub use crate::colliders::{
-    ComponentColliderBox, ComponentColliderChain, ComponentColliderCircle, ComponentColliderEdge,
-    ComponentColliderPolygon,
+    ColliderMaterial, ComponentColliderBox, ComponentColliderChain, ComponentColliderCircle,
+    ComponentColliderCompound, ComponentColliderEdge, ComponentColliderPolygon, ShapeDesc,
+    ShapeKind,
 };
+pub use crate::events::{CollisionBegin, CollisionEnd, TriggerEnter, TriggerExit};
+use crate::events::{ContactEvents, ContactEventsListener};
+use crate::filter::{ContactFilterState, EcsContactFilter};
+pub use crate::forces::{ComponentApplyForce, ComponentApplyImpulse, ComponentApplyTorque};
+pub use crate::gravity::{ComponentGravityZone, GravityMode};
+pub use crate::joints::{ComponentJointDistance, ComponentJointPrismatic, ComponentJointRevolute};
+use crate::joints::{JointInfo, JointKey};
+pub use crate::layers::{CollisionLayers, ComponentCollisionLayer};
+pub use crate::materials::{ComponentPhysicsMaterial, PhysicsMaterial, PhysicsMaterialHandle};
+pub use crate::pathfinding::{NavGrid, PathAlgorithm, PathOptions};
+pub use crate::query::HitInfo;
 use std::any::TypeId;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
+use std::rc::Rc;
 use std::time::Duration;
-use xecs::{Component, EntityId, EntityRef, Event, System, ECS};
-use xengine_basecomponents::ComponentTransform;
-use xmath::Vector2;
+use xecs::{Component, EntityId, EntityMut, EntityRef, Event, System, ECS};
+use xengine_basecomponents::{
+    ComponentChildren, ComponentDisabled, ComponentLocalScale, ComponentLocalTransform,
+    ComponentScale, ComponentTransform,
+};
+use xmath::{Multiply, Transform, Vector2};
 use xphysics::{
-    BodyDef, BodyId, BodyType, FixtureDef, FixtureId, IntoBoxedShape, Shape, ShapeChain,
-    ShapeCircle, ShapeEdge, ShapePolygon, World,
+    Body, BodyDef, BodyId, FixtureDef, FixtureId, IntoBoxedShape, Shape, ShapeChain, ShapeCircle,
+    ShapeEdge, ShapePolygon, World,
 };
+pub use xphysics::{BodyType, DebugDraw, DebugDrawFlags, Profile};
 
 /// 刚体
 pub struct ComponentRigidBody {
@@ -56,6 +86,40 @@ impl Component for ComponentRigidBody {
     }
 }
 
+/// 传感器当前重叠的实体列表，需要的话手动添加到传感器实体上，由`SystemPhysics`维护
+#[derive(Debug, Default, Clone)]
+pub struct ComponentOverlapping(pub Vec<EntityId>);
+
+impl Component for ComponentOverlapping {
+    fn name() -> &'static str {
+        "Overlapping"
+    }
+}
+
+/// 刚体的睡眠状态，需要的话手动添加到刚体实体上，由`SystemPhysics`维护
+///
+/// 渲染或者AI可以根据它跳过睡眠中的物体
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ComponentSleepState {
+    Awake,
+    Sleeping,
+}
+
+impl Default for ComponentSleepState {
+    fn default() -> Self {
+        ComponentSleepState::Awake
+    }
+}
+
+impl Component for ComponentSleepState {
+    fn name() -> &'static str {
+        "SleepState"
+    }
+}
+
+/// 实体、collider组件类型和组件里的形状序号
+type FixtureKey = (EntityId, TypeId, usize);
+
 struct FixtureInfo {
     fixture_id: FixtureId,
     shape_hash: u64,
@@ -63,58 +127,487 @@ struct FixtureInfo {
 
 struct BodyInfo {
     body_id: BodyId,
-    fixtures: HashMap<TypeId, FixtureInfo>,
+    fixtures: HashMap<FixtureKey, FixtureInfo>,
+
+    /// 上一次同步到ComponentTransform的变换，用来检测gameplay的修改
+    transform: Transform<f32>,
+
+    /// 运动学刚体被ComponentTransform驱动时，步进结束后需要恢复的速度
+    restore_velocity: Option<(Vector2<f32>, f32)>,
+
+    /// 上一次同步结束时的`ECS::change_tick`，之后修改过的组件才需要同步
+    synced_tick: u64,
+
+    /// 实体带有`ComponentDisabled`，刚体不参与模拟，也不同步组件
+    disabled: bool,
+
+    /// 同步fixtures时遍历到的没有刚体的子实体
+    children: HashSet<EntityId>,
+}
+
+fn transform_eq(a: &Transform<f32>, b: &Transform<f32>) -> bool {
+    a.p == b.p && a.q.s == b.q.s && a.q.c == b.q.c
+}
+
+/// 把刚体移动到`target`
+///
+/// 运动学刚体会计算这一步需要的速度，这样可以正确的推动动态刚体，其它刚体直接瞬移
+fn move_body(
+    world: &mut World<f32, EntityId>,
+    body_info: &mut BodyInfo,
+    target: &Transform<f32>,
+    dt: f32,
+) {
+    let body = world.body_mut(body_info.body_id).unwrap();
+    if body.body_type() != BodyType::Kinematic || dt <= 0.0 {
+        world.set_body_transform(body_info.body_id, target.p, target.q.angle());
+        return;
+    }
+
+    let center = target.multiply(*body.local_center());
+    let mut angle = target.q.angle() - body.angle();
+    while angle > std::f32::consts::PI {
+        angle -= 2.0 * std::f32::consts::PI;
+    }
+    while angle < -std::f32::consts::PI {
+        angle += 2.0 * std::f32::consts::PI;
+    }
+
+    body_info.restore_velocity = Some((*body.linear_velocity(), body.angular_velocity()));
+    body.set_linear_velocity((center - *body.world_center()) * (1.0 / dt));
+    body.set_angular_velocity(angle / dt);
+}
+
+/// 把模拟之后的速度和唤醒状态写回`ComponentRigidBody`
+///
+/// 组件被修改时会把所有属性同步到刚体，不回写的话修改阻尼等其它属性也会用旧的速度覆盖刚体。
+fn write_back_rigid_body(entity: &mut EntityMut, body: &Body<f32, EntityId>) {
+    if let Some(component_body) = entity.get_mut::<ComponentRigidBody>() {
+        component_body.linear_velocity = *body.linear_velocity();
+        component_body.angular_velocity = body.angular_velocity();
+        component_body.awake = body.is_awake();
+    }
+}
+
+/// 物理系统配置
+pub struct PhysicsConfig {
+    pub gravity: Vector2<f32>,
+    pub velocity_iterations: usize,
+    pub position_iterations: usize,
+    pub sub_stepping: bool,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        PhysicsConfig {
+            gravity: (0.0, -10.0).into(),
+            velocity_iterations: 8,
+            position_iterations: 3,
+            sub_stepping: false,
+        }
+    }
+}
+
+/// 物理世界的设置，作为ECS资源添加后，`SystemPhysics`在每帧开始时读取并应用到世界
+///
+/// 添加这个资源以后，通过`SystemPhysics`的setter做的修改会被覆盖
+#[derive(Debug, Copy, Clone)]
+pub struct PhysicsSettings {
+    pub gravity: Vector2<f32>,
+    pub velocity_iterations: usize,
+    pub position_iterations: usize,
+    pub sub_stepping: bool,
+
+    /// 连续碰撞检测
+    pub continuous_physics: bool,
+
+    /// 固定的步长（秒），为`None`时使用帧间隔
+    pub fixed_dt: Option<f32>,
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        let config = PhysicsConfig::default();
+        PhysicsSettings {
+            gravity: config.gravity,
+            velocity_iterations: config.velocity_iterations,
+            position_iterations: config.position_iterations,
+            sub_stepping: config.sub_stepping,
+            continuous_physics: true,
+            fixed_dt: None,
+        }
+    }
 }
 
 /// 刚体物理系统
 pub struct SystemPhysics {
     world: World<f32, EntityId>,
     bodies: HashMap<EntityId, BodyInfo>,
+    joints: HashMap<JointKey, JointInfo>,
+    velocity_iterations: usize,
+    position_iterations: usize,
+    contact_events: Rc<RefCell<ContactEvents>>,
+    contact_filter: Rc<ContactFilterState>,
+    materials: Vec<PhysicsMaterial>,
+    material_names: HashMap<String, PhysicsMaterialHandle>,
+
+    /// 材质改变了，下一帧需要同步所有的fixtures
+    colliders_dirty: bool,
+
+    /// 子实体的collider被删除了，下一帧需要同步fixtures的刚体
+    dirty_bodies: HashSet<EntityId>,
+}
+
+impl SystemPhysics {
+    pub fn new(config: PhysicsConfig) -> SystemPhysics {
+        let contact_events = Rc::new(RefCell::new(ContactEvents::default()));
+        let mut world = World::new(config.gravity);
+        world.set_sub_stepping(config.sub_stepping);
+        world.set_contact_listener(ContactEventsListener(contact_events.clone()));
+        let contact_filter = Rc::new(ContactFilterState::default());
+        world.set_contact_filter(EcsContactFilter(contact_filter.clone()));
+        SystemPhysics {
+            world,
+            bodies: Default::default(),
+            joints: Default::default(),
+            contact_events,
+            contact_filter,
+            materials: Vec::new(),
+            material_names: HashMap::new(),
+            colliders_dirty: false,
+            dirty_bodies: HashSet::new(),
+            velocity_iterations: config.velocity_iterations,
+            position_iterations: config.position_iterations,
+        }
+    }
+
+    pub fn gravity(&self) -> Vector2<f32> {
+        self.world.gravity()
+    }
+
+    pub fn set_gravity(&mut self, gravity: Vector2<f32>) {
+        self.world.set_gravity(gravity);
+    }
+
+    pub fn velocity_iterations(&self) -> usize {
+        self.velocity_iterations
+    }
+
+    pub fn set_velocity_iterations(&mut self, iterations: usize) {
+        self.velocity_iterations = iterations;
+    }
+
+    pub fn position_iterations(&self) -> usize {
+        self.position_iterations
+    }
+
+    pub fn set_position_iterations(&mut self, iterations: usize) {
+        self.position_iterations = iterations;
+    }
+
+    pub fn sub_stepping(&self) -> bool {
+        self.world.sub_stepping()
+    }
+
+    pub fn set_sub_stepping(&mut self, flag: bool) {
+        self.world.set_sub_stepping(flag);
+    }
+
+    pub fn continuous_physics(&self) -> bool {
+        self.world.continuous_physics()
+    }
+
+    pub fn set_continuous_physics(&mut self, flag: bool) {
+        self.world.set_continuous_physics(flag);
+    }
+
+    /// 上一次步进的各阶段耗时
+    pub fn profile(&self) -> &Profile {
+        self.world.profile()
+    }
+
+    pub fn body_count(&self) -> usize {
+        self.world.body_count()
+    }
+
+    pub fn contact_count(&self) -> usize {
+        self.world.contact_count()
+    }
+
+    /// 设置物理世界的调试绘制，之后调用`draw_debug_data`绘制
+    pub fn set_debug_draw<DD: DebugDraw + 'static>(&mut self, debug_draw: DD, flags: DebugDrawFlags) {
+        self.world.set_debug_draw(debug_draw);
+        self.world.set_debug_draw_flags(flags);
+    }
+
+    pub fn set_debug_draw_flags(&mut self, flags: DebugDrawFlags) {
+        self.world.set_debug_draw_flags(flags);
+    }
+
+    /// 使用`set_debug_draw`设置的调试绘制绘制物理世界
+    pub fn draw_debug_data(&mut self) {
+        self.world.draw_debug_data();
+    }
+
+    fn apply_settings(&mut self, settings: &PhysicsSettings) {
+        self.world.set_gravity(settings.gravity);
+        self.world.set_sub_stepping(settings.sub_stepping);
+        self.world
+            .set_continuous_physics(settings.continuous_physics);
+        self.velocity_iterations = settings.velocity_iterations;
+        self.position_iterations = settings.position_iterations;
+    }
+
+    /// 实体有刚体和变换组件时创建刚体和fixtures
+    fn create_body(&mut self, ecs: &ECS, id: EntityId, collision_layers: &mut CollisionLayers) {
+        let entity = match ecs.entity(id) {
+            Some(entity) => entity,
+            None => return,
+        };
+        if let (Some(component_body), Some(component_transform)) = (
+            entity.get::<ComponentRigidBody>(),
+            entity.get::<ComponentTransform>(),
+        ) {
+            let body_id = self.world.create_body(BodyDef {
+                type_: BodyType::Static,
+                position: component_transform.0.p,
+                angle: component_transform.0.q.angle(),
+                linear_velocity: component_body.linear_velocity,
+                angular_velocity: component_body.angular_velocity,
+                linear_damping: component_body.linear_damping,
+                angular_damping: component_body.angular_damping,
+                allow_sleep: component_body.allow_sleep,
+                awake: component_body.awake,
+                fixed_rotation: component_body.fixed_rotation,
+                bullet: component_body.bullet,
+                active: component_body.active,
+                gravity_scale: component_body.gravity_scale,
+                dominance: 0,
+                data: Some(id),
+            });
+            self.bodies.insert(
+                id,
+                BodyInfo {
+                    body_id,
+                    fixtures: Default::default(),
+                    transform: component_transform.0,
+                    restore_velocity: None,
+                    synced_tick: 0,
+                    disabled: false,
+                    children: HashSet::new(),
+                },
+            );
+
+            sync_colliders(
+                ecs,
+                &entity,
+                &mut self.world,
+                self.bodies.get_mut(&id).unwrap(),
+                &self.materials,
+                collision_layers,
+            );
+        }
+    }
+
+    fn send_contact_events(&mut self, ecs: &mut ECS) {
+        let mut contact_events = self.contact_events.borrow_mut();
+        for (_, event) in contact_events.begin.drain(..) {
+            ecs.send_event(event);
+        }
+        for event in contact_events.end.drain(..) {
+            ecs.send_event(event);
+        }
+        for event in contact_events.trigger_enter.drain(..) {
+            if let Some(overlapping) = ecs
+                .entity_mut(event.sensor)
+                .as_mut()
+                .and_then(|entity| entity.get_mut::<ComponentOverlapping>())
+            {
+                if !overlapping.0.contains(&event.other) {
+                    overlapping.0.push(event.other);
+                }
+            }
+            ecs.send_event(event);
+        }
+        for event in contact_events.trigger_exit.drain(..) {
+            if let Some(overlapping) = ecs
+                .entity_mut(event.sensor)
+                .as_mut()
+                .and_then(|entity| entity.get_mut::<ComponentOverlapping>())
+            {
+                overlapping.0.retain(|id| *id != event.other);
+            }
+            ecs.send_event(event);
+        }
+    }
+
+    /// `id`是某个刚体的子实体时，下一帧重新同步这个刚体的fixtures
+    fn mark_collider_child(&mut self, id: EntityId) {
+        for (body_entity, body_info) in &self.bodies {
+            if body_info.children.contains(&id) {
+                self.dirty_bodies.insert(*body_entity);
+            }
+        }
+    }
+}
+
+impl Default for SystemPhysics {
+    fn default() -> Self {
+        SystemPhysics::new(Default::default())
+    }
 }
 
 impl System for SystemPhysics {
     fn update(&mut self, ecs: &mut ECS, delta: Duration) {
+        let mut dt = delta.as_secs_f32();
+        if let Some(settings) = ecs.resource::<PhysicsSettings>().copied() {
+            self.apply_settings(&settings);
+            dt = settings.fixed_dt.unwrap_or(dt);
+        }
+        let colliders_dirty = std::mem::replace(&mut self.colliders_dirty, false);
+        let dirty_bodies = std::mem::take(&mut self.dirty_bodies);
+        let mut collision_layers = take_collision_layers(ecs);
+
         // 同步component的属性到world
         for (entity_id, body_info) in &mut self.bodies {
-            let entity = ecs.entity(*entity_id).unwrap();
-            let component_body = entity.get::<ComponentRigidBody>().unwrap();
-            let body = self.world.body_mut(body_info.body_id).unwrap();
+            // 实体可能在这一帧被删除了，等处理事件时再销毁刚体
+            let entity = match ecs.entity(*entity_id) {
+                Some(entity) => entity,
+                None => continue,
+            };
+            let component_body = match entity.get::<ComponentRigidBody>() {
+                Some(component_body) => component_body,
+                None => continue,
+            };
+            let body_id = body_info.body_id;
+            let tick = body_info.synced_tick;
+
+            // 禁用的实体保持synced_tick不变，重新启用时同步禁用期间的修改
+            let disabled = entity.contains::<ComponentDisabled>();
+            if disabled != body_info.disabled {
+                body_info.disabled = disabled;
+                self.world
+                    .set_body_active(body_id, !disabled && component_body.active);
+            }
+            if disabled {
+                continue;
+            }
 
             // 同步body属性
-            body.set_body_type(component_body.type_);
-            body.set_linear_velocity(component_body.linear_velocity);
-            body.set_angular_velocity(component_body.angular_velocity);
-            body.set_linear_damping(component_body.linear_damping);
-            body.set_angular_damping(component_body.angular_damping);
-            body.set_sleeping_allowed(component_body.allow_sleep);
-            body.set_awake(component_body.awake);
-            body.set_fixed_rotation(component_body.fixed_rotation);
-            body.set_bullet(component_body.bullet);
-            body.set_active(component_body.active);
-            body.set_gravity_scale(component_body.gravity_scale);
+            if entity.is_changed_since::<ComponentRigidBody>(tick) {
+                self.world.set_body_type(body_id, component_body.type_);
+                self.world.set_body_active(body_id, component_body.active);
+                let body = self.world.body_mut(body_id).unwrap();
+                body.set_linear_velocity(component_body.linear_velocity);
+                body.set_angular_velocity(component_body.angular_velocity);
+                body.set_linear_damping(component_body.linear_damping);
+                body.set_angular_damping(component_body.angular_damping);
+                body.set_sleeping_allowed(component_body.allow_sleep);
+                body.set_awake(component_body.awake);
+                body.set_fixed_rotation(component_body.fixed_rotation);
+                body.set_bullet(component_body.bullet);
+                body.set_gravity_scale(component_body.gravity_scale);
+            }
 
-            // 删除shape已经改变的fixtures
-            check_remove_fixtures(&entity, &mut self.world, body_info);
+            // 同步gameplay对ComponentTransform的修改
+            if let Some(transform) = entity.get::<ComponentTransform>() {
+                if entity.is_changed_since::<ComponentTransform>(tick)
+                    && !transform_eq(&transform.0, &body_info.transform)
+                {
+                    move_body(&mut self.world, body_info, &transform.0, dt);
+                    body_info.transform = transform.0;
+                }
+            }
 
-            // 创建或者删除fixtures
-            create_or_remove_fixtures(&entity, &mut self.world, body_info);
+            // 施加一次性的力和冲量
+            forces::apply_forces(&entity, self.world.body_mut(body_id).unwrap());
 
-            // 通过fixtures的属性
-            sync_fixtures(&entity, &mut self.world, body_info);
+            // 创建、删除或者同步fixtures
+            if colliders_dirty
+                || dirty_bodies.contains(entity_id)
+                || colliders_changed(ecs, &entity, tick)
+            {
+                sync_colliders(
+                    ecs,
+                    &entity,
+                    &mut self.world,
+                    body_info,
+                    &self.materials,
+                    &mut collision_layers,
+                );
+            }
         }
+        ecs.insert_resource(collision_layers);
+
+        // 创建、删除或者同步关节
+        self.sync_joints(ecs);
+
+        // 重力区域
+        self.apply_gravity_zones(ecs);
 
         // 步进世界
-        self.world.step(delta.as_secs_f32(), 8, 3);
+        let world = &mut self.world;
+        let (velocity_iterations, position_iterations) =
+            (self.velocity_iterations, self.position_iterations);
+        self.contact_filter.with_ecs(ecs, || {
+            world.step(dt, velocity_iterations, position_iterations)
+        });
+
+        // 发送碰撞事件
+        self.send_contact_events(ecs);
 
         // 同步body的位置和旋转属性到ComponentTransform
         for (entity_id, body_info) in &mut self.bodies {
-            if let Some(transform) = ecs
-                .entity_mut(*entity_id)
-                .unwrap()
-                .get_mut::<ComponentTransform>()
+            if body_info.disabled {
+                continue;
+            }
+            let mut entity = match ecs.entity_mut(*entity_id) {
+                Some(entity) => entity,
+                None => continue,
+            };
+            forces::clear_forces(&mut entity);
+
+            let body = self.world.body_mut(body_info.body_id).unwrap();
+            if let Some((linear_velocity, angular_velocity)) = body_info.restore_velocity.take() {
+                body.set_linear_velocity(linear_velocity);
+                body.set_angular_velocity(angular_velocity);
+            }
+
+            // 只在状态改变时回写，避免组件每帧都被标记为已修改
+            let sleep_state = match body.is_awake() {
+                true => ComponentSleepState::Awake,
+                false => ComponentSleepState::Sleeping,
+            };
+            if entity
+                .get::<ComponentSleepState>()
+                .map(|state| *state != sleep_state)
+                == Some(true)
             {
-                let body = self.world.body(body_info.body_id).unwrap();
-                transform.0 = *body.transform();
+                *entity.get_mut::<ComponentSleepState>().unwrap() = sleep_state;
+            }
+
+            // 静止的刚体不需要回写，避免组件被标记为已修改
+            let sleeping = !body.is_awake()
+                && entity
+                    .get::<ComponentRigidBody>()
+                    .map(|component_body| !component_body.awake)
+                    .unwrap_or(true);
+            if body.body_type() == BodyType::Static || sleeping {
+                continue;
+            }
+
+            body_info.transform = *body.transform();
+            if let Some(transform) = entity.get_mut::<ComponentTransform>() {
+                transform.0 = body_info.transform;
+            }
+            write_back_rigid_body(&mut entity, body);
+        }
+
+        // 回写产生的修改不需要再同步回world
+        let tick = ecs.change_tick();
+        for body_info in self.bodies.values_mut() {
+            if !body_info.disabled {
+                body_info.synced_tick = tick;
             }
         }
     }
@@ -122,54 +615,38 @@ impl System for SystemPhysics {
     fn handle_event(&mut self, ecs: &mut ECS, event: &Event) {
         match event {
             Event::CreateEntity(id) => {
-                let entity = ecs.entity(*id).unwrap();
-                if let (Some(component_body), Some(component_transform)) = (
-                    entity.get::<ComponentRigidBody>(),
-                    entity.get::<ComponentTransform>(),
-                ) {
-                    let body_id = self.world.create_body(BodyDef {
-                        type_: BodyType::Static,
-                        position: component_transform.0.p,
-                        angle: component_transform.0.q.angle(),
-                        linear_velocity: component_body.linear_velocity,
-                        angular_velocity: component_body.angular_velocity,
-                        linear_damping: component_body.linear_damping,
-                        angular_damping: component_body.angular_damping,
-                        allow_sleep: component_body.allow_sleep,
-                        awake: component_body.awake,
-                        fixed_rotation: component_body.fixed_rotation,
-                        bullet: component_body.bullet,
-                        active: component_body.active,
-                        gravity_scale: component_body.gravity_scale,
-                        data: Some(*id),
-                    });
-                    self.bodies.insert(
-                        *id,
-                        BodyInfo {
-                            body_id,
-                            fixtures: Default::default(),
-                        },
-                    );
-
-                    create_or_remove_fixtures(
-                        &entity,
-                        &mut self.world,
-                        self.bodies.get_mut(id).unwrap(),
-                    );
-                }
+                let mut collision_layers = take_collision_layers(ecs);
+                self.create_body(ecs, *id, &mut collision_layers);
+                ecs.insert_resource(collision_layers);
             }
             Event::RemoveEntity(id) => {
-                if let Some(body_info) = self.bodies.remove(id) {
-                    self.world.destroy_body(body_info.body_id);
+                match self.bodies.remove(id) {
+                    Some(body_info) => {
+                        self.world.destroy_body(body_info.body_id);
+                        self.forget_joints(*id);
+                    }
+                    // 可能是某个刚体的子实体
+                    None => self.mark_collider_child(*id),
                 }
             }
-            Event::CreateComponent(id, _) | Event::RemoveComponent(id, _) => {
+            Event::CreateComponent(id, tid) | Event::RemoveComponent(id, tid) => {
+                if !self.bodies.contains_key(id) && is_collider_component(*tid) {
+                    // 可能是某个刚体的子实体
+                    self.mark_collider_child(*id);
+                }
                 if let Some(body_info) = self.bodies.get_mut(id) {
-                    create_or_remove_fixtures(
-                        &ecs.entity(*id).unwrap(),
-                        &mut self.world,
-                        body_info,
-                    );
+                    let mut collision_layers = take_collision_layers(ecs);
+                    if let Some(entity) = ecs.entity(*id) {
+                        sync_colliders(
+                            ecs,
+                            &entity,
+                            &mut self.world,
+                            body_info,
+                            &self.materials,
+                            &mut collision_layers,
+                        );
+                    }
+                    ecs.insert_resource(collision_layers);
                 }
             }
             _ => {}
@@ -177,160 +654,1329 @@ impl System for SystemPhysics {
     }
 }
 
-fn check_remove_fixture<C>(
-    entity: &EntityRef,
-    world: &mut World<f32, EntityId>,
-    body_info: &mut BodyInfo,
-) where
-    C: Component + ShapeHash,
-{
-    if let Some(collider) = entity.get::<C>() {
-        let remove_fixture_id =
-            if let Some(fixture_info) = body_info.fixtures.get(&TypeId::of::<C>()) {
-                let new_hash = collider.hash();
-                if new_hash != fixture_info.shape_hash {
-                    Some(fixture_info.fixture_id)
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
+/// 会影响fixtures的组件
+fn is_collider_component(tid: TypeId) -> bool {
+    tid == TypeId::of::<ComponentColliderCircle>()
+        || tid == TypeId::of::<ComponentColliderBox>()
+        || tid == TypeId::of::<ComponentColliderEdge>()
+        || tid == TypeId::of::<ComponentColliderChain>()
+        || tid == TypeId::of::<ComponentColliderPolygon>()
+        || tid == TypeId::of::<ComponentColliderCompound>()
+        || tid == TypeId::of::<ComponentPhysicsMaterial>()
+        || tid == TypeId::of::<ComponentCollisionLayer>()
+        || tid == TypeId::of::<ComponentChildren>()
+        || tid == TypeId::of::<ComponentScale>()
+        || tid == TypeId::of::<ComponentLocalScale>()
+        || tid == TypeId::of::<ComponentTransform>()
+        || tid == TypeId::of::<ComponentLocalTransform>()
+}
 
-        if let Some(fixture_id) = remove_fixture_id {
-            // shape改变了，删除这个fixture
-            body_info.fixtures.remove(&TypeId::of::<C>());
-            world
-                .body_mut(body_info.body_id)
-                .unwrap()
-                .destroy_fixture(fixture_id);
-        }
+fn collider_components_changed(entity: &EntityRef, tick: u64) -> bool {
+    entity.is_changed_since::<ComponentColliderCircle>(tick)
+        || entity.is_changed_since::<ComponentColliderBox>(tick)
+        || entity.is_changed_since::<ComponentColliderEdge>(tick)
+        || entity.is_changed_since::<ComponentColliderChain>(tick)
+        || entity.is_changed_since::<ComponentColliderPolygon>(tick)
+        || entity.is_changed_since::<ComponentColliderCompound>(tick)
+        || entity.is_changed_since::<ComponentPhysicsMaterial>(tick)
+        || entity.is_changed_since::<ComponentCollisionLayer>(tick)
+        || entity.is_changed_since::<ComponentChildren>(tick)
+        || entity.is_changed_since::<ComponentScale>(tick)
+}
+
+/// 实体或者没有刚体的子实体上的collider是否在`tick`之后被修改过
+fn colliders_changed(ecs: &ECS, entity: &EntityRef, tick: u64) -> bool {
+    if collider_components_changed(entity, tick) {
+        return true;
     }
+    let children = match entity.get::<ComponentChildren>() {
+        Some(children) => children,
+        None => return false,
+    };
+    children
+        .0
+        .iter()
+        .any(|child_id| match ecs.entity(*child_id) {
+            Some(child) if !child.contains::<ComponentRigidBody>() => {
+                child.is_changed_since::<ComponentTransform>(tick)
+                    || child.is_changed_since::<ComponentLocalTransform>(tick)
+                    || child.is_changed_since::<ComponentLocalScale>(tick)
+                    || colliders_changed(ecs, &child, tick)
+            }
+            _ => false,
+        })
 }
 
-fn check_remove_fixtures(
-    entity: &EntityRef,
-    world: &mut World<f32, EntityId>,
-    body_info: &mut BodyInfo,
-) {
-    check_remove_fixture::<ComponentColliderCircle>(entity, world, body_info);
-    check_remove_fixture::<ComponentColliderBox>(entity, world, body_info);
-    check_remove_fixture::<ComponentColliderEdge>(entity, world, body_info);
-    check_remove_fixture::<ComponentColliderChain>(entity, world, body_info);
-    check_remove_fixture::<ComponentColliderPolygon>(entity, world, body_info);
+/// 根据实体和子实体上的collider创建、删除或者同步fixtures
+struct ColliderSync<'a> {
+    world: &'a mut World<f32, EntityId>,
+    body_info: &'a mut BodyInfo,
+    materials: &'a [PhysicsMaterial],
+    collision_layers: &'a mut CollisionLayers,
+    visited: HashSet<FixtureKey>,
+    children: HashSet<EntityId>,
 }
 
-fn create_or_remove_fixture<C, F, S: Shape<f32> + 'static>(
-    entity: &EntityRef,
-    world: &mut World<f32, EntityId>,
-    body_info: &mut BodyInfo,
-    create_shape: F,
-) where
-    C: Component + Deref<Target = colliders::Fixture> + ShapeHash,
-    F: FnOnce(&C) -> S,
-{
-    if let Some(collider) = entity.get::<C>() {
-        if !body_info.fixtures.contains_key(&TypeId::of::<C>()) {
-            // 创建
-            let fixture_def = FixtureDef {
-                shape: create_shape(collider).into_boxed(),
-                friction: collider.friction,
-                restitution: collider.restitution,
-                density: collider.density,
-                is_sensor: collider.is_sensor,
-                filter: collider.filter,
-                data: Some(entity.id()),
-            };
-            let fixture_id = world
-                .body_mut(body_info.body_id)
-                .unwrap()
-                .create_fixture(fixture_def);
+/// 子实体会继承父实体的材质和碰撞层
+#[derive(Copy, Clone, Default)]
+struct ColliderProps {
+    material: Option<PhysicsMaterialHandle>,
+    layer: Option<(u16, u16)>,
+}
+
+impl<'a> ColliderSync<'a> {
+    fn sync_collider<C, F, S>(
+        &mut self,
+        entity: &EntityRef,
+        frame: &ColliderFrame,
+        props: ColliderProps,
+        create_shape: F,
+    ) where
+        C: Component + Deref<Target = colliders::ColliderMaterial> + ShapeHash,
+        F: FnOnce(&C, &ColliderFrame) -> S,
+        S: Shape<f32> + 'static,
+    {
+        let collider = match entity.get::<C>() {
+            Some(collider) => collider,
+            None => return,
+        };
+        self.sync_fixture(
+            (entity.id(), TypeId::of::<C>(), 0),
+            collider,
+            frame.hash(collider.hash()),
+            props,
+            || create_shape(collider, frame).into_boxed(),
+        );
+    }
 
-            body_info.fixtures.insert(
-                TypeId::of::<C>(),
-                FixtureInfo {
-                    fixture_id,
-                    shape_hash: collider.hash(),
+    fn sync_compound(&mut self, entity: &EntityRef, frame: &ColliderFrame, props: ColliderProps) {
+        let compound = match entity.get::<ComponentColliderCompound>() {
+            Some(compound) => compound,
+            None => return,
+        };
+        for (index, desc) in compound.0.iter().enumerate() {
+            let local = &desc.transform;
+            self.sync_fixture(
+                (
+                    entity.id(),
+                    TypeId::of::<ComponentColliderCompound>(),
+                    index,
+                ),
+                &desc.fixture,
+                frame.hash(desc.hash()),
+                props,
+                || match &desc.kind {
+                    ShapeKind::Circle { radius } => {
+                        ShapeCircle::new(frame.point(local, Vector2::zero()), frame.radius(*radius))
+                            .into_boxed()
+                    }
+                    ShapeKind::Box { half_size } => {
+                        let half_size = frame.extent(*half_size);
+                        ShapePolygon::new_box(
+                            half_size.x,
+                            half_size.y,
+                            frame.point(local, Vector2::zero()),
+                            frame.angle(local),
+                        )
+                        .into_boxed()
+                    }
+                    ShapeKind::Polygon { vertices } => {
+                        ShapePolygon::new(vertices.iter().map(|v| frame.point(local, *v)))
+                            .into_boxed()
+                    }
                 },
             );
         }
-    } else {
-        if let Some(fixture_info) = body_info.fixtures.remove(&TypeId::of::<C>()) {
-            // 删除
-            world
-                .body_mut(body_info.body_id)
-                .unwrap()
-                .destroy_fixture(fixture_info.fixture_id);
+    }
+
+    fn sync_fixture<F>(
+        &mut self,
+        key: FixtureKey,
+        collider: &colliders::ColliderMaterial,
+        shape_hash: u64,
+        props: ColliderProps,
+        create_shape: F,
+    ) where
+        F: FnOnce() -> Box<dyn Shape<f32>>,
+    {
+        self.visited.insert(key);
+
+        let (friction, restitution, density) = match props
+            .material
+            .and_then(|handle| self.materials.get(handle.0))
+        {
+            Some(material) => (material.friction, material.restitution, material.density),
+            None => (collider.friction, collider.restitution, collider.density),
+        };
+        let mut filter = collider.filter;
+        match props.layer {
+            Some((category_bits, mask_bits)) => {
+                filter.category_bits = category_bits;
+                filter.mask_bits = mask_bits;
+            }
+            // 没有碰撞层的collider属于默认层，不会和命名的层共用一位
+            None => filter.category_bits = CollisionLayers::DEFAULT_BITS,
+        }
+        let body_id = self.body_info.body_id;
+
+        if let Some(fixture_info) = self.body_info.fixtures.get(&key) {
+            if fixture_info.shape_hash != shape_hash {
+                // shape改变了，删除这个fixture
+                self.world.destroy_fixture(body_id, fixture_info.fixture_id);
+                self.body_info.fixtures.remove(&key);
+            }
+        }
+
+        match self.body_info.fixtures.get(&key) {
+            Some(fixture_info) => {
+                // 同步fixture属性
+                let fixture_id = fixture_info.fixture_id;
+                let fixture = self.world.fixture_mut(body_id, fixture_id).unwrap();
+                fixture.set_friction(friction);
+                fixture.set_restitution(restitution);
+                fixture.set_density(density);
+                self.world
+                    .set_fixture_sensor(body_id, fixture_id, collider.is_sensor);
+                self.world.set_fixture_filter(body_id, fixture_id, filter);
+            }
+            None => {
+                // 创建
+                let fixture_def = FixtureDef {
+                    shape: create_shape(),
+                    friction,
+                    restitution,
+                    density,
+                    is_sensor: collider.is_sensor,
+                    filter,
+                    one_way_normal: None,
+                    adhesion: None,
+                    data: Some(key.0),
+                };
+                let fixture_id = self.world.create_fixture(body_id, fixture_def);
+                self.body_info.fixtures.insert(
+                    key,
+                    FixtureInfo {
+                        fixture_id,
+                        shape_hash,
+                    },
+                );
+            }
+        }
+    }
+
+    fn sync_entity(&mut self, entity: &EntityRef, frame: &ColliderFrame, props: ColliderProps) {
+        self.sync_collider::<ComponentColliderCircle, _, _>(entity, frame, props, |c, frame| {
+            ShapeCircle::new(
+                frame.point(&c.transform, Vector2::zero()),
+                frame.radius(c.radius),
+            )
+        });
+
+        self.sync_collider::<ComponentColliderBox, _, _>(entity, frame, props, |c, frame| {
+            let half_size = frame.extent(c.half_size);
+            ShapePolygon::new_box(
+                half_size.x,
+                half_size.y,
+                frame.point(&c.transform, Vector2::zero()),
+                frame.angle(&c.transform),
+            )
+        });
+
+        self.sync_collider::<ComponentColliderEdge, _, _>(entity, frame, props, |c, frame| {
+            ShapeEdge::new(
+                frame.point(&c.transform, c.vertex1),
+                frame.point(&c.transform, c.vertex2),
+            )
+        });
+
+        self.sync_collider::<ComponentColliderChain, _, _>(entity, frame, props, |c, frame| {
+            let vertices = c.vertices.iter().map(|v| frame.point(&c.transform, *v));
+            match c.is_loop {
+                true => ShapeChain::create_loop(vertices),
+                false => ShapeChain::create_chain(vertices),
+            }
+        });
+
+        self.sync_collider::<ComponentColliderPolygon, _, _>(entity, frame, props, |c, frame| {
+            ShapePolygon::new(c.vertices.iter().map(|v| frame.point(&c.transform, *v)))
+        });
+
+        self.sync_compound(entity, frame, props);
+    }
+
+    /// 没有刚体的子实体上的collider会合并到父实体的刚体上，子实体有`ComponentLocalTransform`时使用它，
+    /// 否则子实体的`ComponentTransform`是相对父实体的局部变换，缩放同理
+    fn sync_children(
+        &mut self,
+        ecs: &ECS,
+        entity: &EntityRef,
+        frame: &ColliderFrame,
+        props: ColliderProps,
+    ) {
+        let children = match entity.get::<ComponentChildren>() {
+            Some(children) => children,
+            None => return,
+        };
+        for child_id in &children.0 {
+            let child = match ecs.entity(*child_id) {
+                Some(child) => child,
+                None => continue,
+            };
+            if child.contains::<ComponentRigidBody>() {
+                // 子实体有自己的刚体
+                continue;
+            }
+            self.children.insert(*child_id);
+            let local = child
+                .get::<ComponentLocalTransform>()
+                .map(|transform| transform.0)
+                .or_else(|| {
+                    child
+                        .get::<ComponentTransform>()
+                        .map(|transform| transform.0)
+                })
+                .unwrap_or_else(Transform::identity);
+            let scale = child
+                .get::<ComponentLocalScale>()
+                .map(|scale| scale.0)
+                .or_else(|| child.get::<ComponentScale>().map(|scale| scale.0))
+                .unwrap_or_else(|| (1.0, 1.0).into());
+            let frame = frame.child(&local, scale);
+            let props = self.props(&child, props);
+            self.sync_entity(&child, &frame, props);
+            self.sync_children(ecs, &child, &frame, props);
+        }
+    }
+
+    fn props(&mut self, entity: &EntityRef, parent: ColliderProps) -> ColliderProps {
+        ColliderProps {
+            material: entity
+                .get::<ComponentPhysicsMaterial>()
+                .map(|material| material.0)
+                .or(parent.material),
+            layer: entity
+                .get::<ComponentCollisionLayer>()
+                .map(|layer| self.collision_layers.compile(layer))
+                .or(parent.layer),
         }
     }
+
+    fn finish(self) {
+        // 删除collider已经不存在的fixtures
+        let body_id = self.body_info.body_id;
+        let world = self.world;
+        let visited = self.visited;
+        self.body_info.children = self.children;
+        self.body_info.fixtures.retain(|key, fixture_info| {
+            if visited.contains(key) {
+                true
+            } else {
+                world.destroy_fixture(body_id, fixture_info.fixture_id);
+                false
+            }
+        });
+    }
 }
 
-fn create_or_remove_fixtures(
+/// 同步fixtures时可能会注册新的碰撞层，期间先把碰撞层资源从ECS里取出来，用完再放回去
+fn take_collision_layers(ecs: &mut ECS) -> CollisionLayers {
+    ecs.remove_resource::<CollisionLayers>().unwrap_or_default()
+}
+
+fn sync_colliders(
+    ecs: &ECS,
     entity: &EntityRef,
     world: &mut World<f32, EntityId>,
     body_info: &mut BodyInfo,
+    materials: &[PhysicsMaterial],
+    collision_layers: &mut CollisionLayers,
 ) {
-    create_or_remove_fixture::<ComponentColliderCircle, _, _>(
-        &entity,
+    let mut sync = ColliderSync {
         world,
         body_info,
-        |collider| ShapeCircle::new(collider.position, collider.radius),
+        materials,
+        collision_layers,
+        visited: HashSet::new(),
+        children: HashSet::new(),
+    };
+    let frame = ColliderFrame::new(
+        entity
+            .get::<ComponentScale>()
+            .map(|scale| scale.0)
+            .unwrap_or_else(|| (1.0, 1.0).into()),
     );
+    let props = sync.props(entity, ColliderProps::default());
+    sync.sync_entity(entity, &frame, props);
+    sync.sync_children(ecs, entity, &frame, props);
+    sync.finish();
+}
 
-    create_or_remove_fixture::<ComponentColliderBox, _, _>(&entity, world, body_info, |collider| {
-        ShapePolygon::new_box(
-            collider.half_size.x,
-            collider.half_size.y,
-            collider.position,
-            0.0,
-        )
-    });
-
-    create_or_remove_fixture::<ComponentColliderEdge, _, _>(
-        &entity,
-        world,
-        body_info,
-        |collider| ShapeEdge::new(collider.vertex1, collider.vertex2),
-    );
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xmath::{Rotation, Transform, AABB};
 
-    create_or_remove_fixture::<ComponentColliderChain, _, _>(
-        &entity,
-        world,
-        body_info,
-        |collider| match collider.is_loop {
-            true => ShapeChain::create_loop(collider.vertices.clone()),
-            false => ShapeChain::create_chain(collider.vertices.clone()),
-        },
-    );
+    fn fixture() -> colliders::ColliderMaterial {
+        colliders::ColliderMaterial {
+            friction: 0.2,
+            restitution: 0.0,
+            density: 1.0,
+            is_sensor: false,
+            filter: Default::default(),
+        }
+    }
 
-    create_or_remove_fixture::<ComponentColliderPolygon, _, _>(
-        &entity,
-        world,
-        body_info,
-        |collider| ShapePolygon::new(collider.vertices.clone()),
-    );
-}
+    fn create_ball(ecs: &mut ECS, position: Vector2<f32>) -> EntityId {
+        ecs.create_entity()
+            .component(ComponentTransform(Transform::new(
+                position,
+                Rotation::new(0.0),
+            )))
+            .component(ComponentRigidBody {
+                type_: BodyType::Dynamic,
+                ..ComponentRigidBody::default()
+            })
+            .component(ComponentColliderCircle {
+                fixture: fixture(),
+                transform: Transform::identity(),
+                radius: 0.5,
+            })
+            .finish()
+    }
+
+    fn create_ground(ecs: &mut ECS) -> EntityId {
+        ecs.create_entity()
+            .component(ComponentTransform(Transform::new(
+                Vector2::zero(),
+                Rotation::new(0.0),
+            )))
+            .component(ComponentRigidBody::default())
+            .component(ComponentColliderBox {
+                fixture: fixture(),
+                transform: Transform::identity(),
+                half_size: (10.0, 0.5).into(),
+            })
+            .finish()
+    }
 
-fn sync_fixture<C>(entity: &EntityRef, world: &mut World<f32, EntityId>, body_info: &BodyInfo)
-where
-    C: Component + Deref<Target = colliders::Fixture>,
-{
-    if let Some(component) = entity.get::<C>() {
-        let component_fixture = component.deref();
-        let body = world.body_mut(body_info.body_id).unwrap();
-        for (_, fixture) in body.fixture_list_mut() {
-            fixture.set_friction(component_fixture.friction);
-            fixture.set_restitution(component_fixture.restitution);
-            fixture.set_density(component_fixture.density);
-            fixture.set_sensor(component_fixture.is_sensor);
-            fixture.set_filter(component_fixture.filter);
+    fn create_ecs() -> ECS {
+        let mut ecs = ECS::new();
+        ecs.add_system(0, SystemPhysics::new(PhysicsConfig::default()));
+        ecs
+    }
+
+    #[derive(Default)]
+    struct Collisions(Vec<CollisionBegin>);
+
+    impl System for Collisions {
+        fn handle_event(&mut self, _ecs: &mut ECS, event: &Event) {
+            if let Some(event) = event.downcast_ref::<CollisionBegin>() {
+                self.0.push(*event);
+            }
         }
     }
-}
-fn sync_fixtures(entity: &EntityRef, world: &mut World<f32, EntityId>, body_info: &BodyInfo) {
-    sync_fixture::<ComponentColliderCircle>(entity, world, body_info);
-    sync_fixture::<ComponentColliderBox>(entity, world, body_info);
-    sync_fixture::<ComponentColliderEdge>(entity, world, body_info);
-    sync_fixture::<ComponentColliderChain>(entity, world, body_info);
-    sync_fixture::<ComponentColliderPolygon>(entity, world, body_info);
+
+    #[test]
+    fn test_falling_body() {
+        let mut ecs = create_ecs();
+        let id = create_ball(&mut ecs, (0.0, 10.0).into());
+        for _ in 0..60 {
+            ecs.update(Duration::from_secs(1) / 60);
+        }
+        let entity = ecs.entity(id).unwrap();
+        assert!(entity.get::<ComponentTransform>().unwrap().0.p.y < 10.0);
+    }
+
+    #[test]
+    fn test_collision_events() {
+        let mut ecs = create_ecs();
+        ecs.add_system(1, Collisions::default());
+        let ground = create_ground(&mut ecs);
+        let ball = create_ball(&mut ecs, (0.0, 2.0).into());
+        for _ in 0..120 {
+            ecs.update(Duration::from_secs(1) / 60);
+        }
+
+        let collisions = &ecs.system::<Collisions>().unwrap().0;
+        assert_eq!(collisions.len(), 1);
+        let event = collisions[0];
+        assert!((event.a, event.b) == (ground, ball) || (event.a, event.b) == (ball, ground));
+        assert!(event.impulse > 0.0);
+    }
+
+    #[derive(Default)]
+    struct Triggers {
+        enter: Vec<TriggerEnter>,
+        exit: Vec<TriggerExit>,
+    }
+
+    impl System for Triggers {
+        fn handle_event(&mut self, _ecs: &mut ECS, event: &Event) {
+            if let Some(event) = event.downcast_ref::<TriggerEnter>() {
+                self.enter.push(*event);
+            } else if let Some(event) = event.downcast_ref::<TriggerExit>() {
+                self.exit.push(*event);
+            }
+        }
+    }
+
+    #[test]
+    fn test_trigger_events() {
+        let mut ecs = create_ecs();
+        ecs.add_system(1, Triggers::default());
+        let sensor = ecs
+            .create_entity()
+            .component(ComponentTransform(Transform::new(
+                Vector2::zero(),
+                Rotation::new(0.0),
+            )))
+            .component(ComponentRigidBody::default())
+            .component(ComponentColliderBox {
+                fixture: colliders::ColliderMaterial {
+                    is_sensor: true,
+                    ..fixture()
+                },
+                transform: Transform::identity(),
+                half_size: (1.0, 1.0).into(),
+            })
+            .component(ComponentOverlapping::default())
+            .finish();
+        let ball = create_ball(&mut ecs, (0.0, 2.0).into());
+
+        while ecs.system::<Triggers>().unwrap().enter.is_empty() {
+            ecs.update(Duration::from_secs(1) / 60);
+        }
+        let overlapping = ecs.entity(sensor).unwrap();
+        assert_eq!(
+            overlapping.get::<ComponentOverlapping>().unwrap().0,
+            vec![ball]
+        );
+
+        for _ in 0..120 {
+            ecs.update(Duration::from_secs(1) / 60);
+        }
+        let triggers = ecs.system::<Triggers>().unwrap();
+        assert_eq!(triggers.enter.len(), 1);
+        assert_eq!(triggers.exit.len(), 1);
+        assert_eq!(
+            (triggers.exit[0].sensor, triggers.exit[0].other),
+            (sensor, ball)
+        );
+        let overlapping = ecs.entity(sensor).unwrap();
+        assert!(overlapping
+            .get::<ComponentOverlapping>()
+            .unwrap()
+            .0
+            .is_empty());
+    }
+
+    #[test]
+    fn test_queries() {
+        let mut ecs = create_ecs();
+        let ground = create_ground(&mut ecs);
+        let ball = create_ball(&mut ecs, (3.0, 5.0).into());
+        ecs.update(Duration::from_secs(1) / 60);
+        let physics = ecs.system::<SystemPhysics>().unwrap();
+
+        let hit = physics
+            .ray_cast((0.0, 5.0).into(), (0.0, -5.0).into(), |_| true)
+            .unwrap();
+        assert_eq!(hit.entity, ground);
+        assert!((hit.point.y - 0.5).abs() < 0.001);
+        assert!((hit.normal.y - 1.0).abs() < 0.001);
+        assert!(physics
+            .ray_cast((0.0, 5.0).into(), (0.0, -5.0).into(), |id| id != ground)
+            .is_none());
+
+        // 长度为0的射线
+        assert!(physics
+            .ray_cast((0.0, 0.0).into(), (0.0, 0.0).into(), |_| true)
+            .is_none());
+
+        let aabb = AABB::new_center((3.0, 3.0).into(), (1.0, 3.0).into());
+        let mut entities = physics.overlap_aabb(aabb);
+        assert_eq!(entities.len(), 2);
+        assert!(entities.contains(&ground) && entities.contains(&ball));
+
+        assert_eq!(physics.overlap_circle((0.0, 1.0).into(), 0.6), vec![ground]);
+        assert!(physics.overlap_circle((0.0, 1.5).into(), 0.6).is_empty());
+    }
+
+    #[test]
+    fn test_child_colliders() {
+        let mut ecs = create_ecs();
+        let child = ecs
+            .create_entity()
+            .component(ComponentTransform(Transform::new(
+                (3.0, 0.0).into(),
+                Rotation::new(0.0),
+            )))
+            .component(ComponentColliderCircle {
+                fixture: fixture(),
+                transform: Transform::identity(),
+                radius: 0.5,
+            })
+            .finish();
+        ecs.create_entity()
+            .component(ComponentTransform(Transform::new(
+                (1.0, 0.0).into(),
+                Rotation::new(0.0),
+            )))
+            .component(ComponentRigidBody::default())
+            .component(ComponentChildren(vec![child]))
+            .finish();
+        ecs.update(Duration::from_secs(1) / 60);
+
+        let physics = ecs.system::<SystemPhysics>().unwrap();
+        assert_eq!(physics.overlap_circle((4.0, 0.0).into(), 0.1), vec![child]);
+        assert!(physics.overlap_circle((1.0, 0.0).into(), 0.1).is_empty());
+
+        ecs.entity_mut(child)
+            .unwrap()
+            .remove::<ComponentColliderCircle>();
+        ecs.update(Duration::from_secs(1) / 60);
+        let physics = ecs.system::<SystemPhysics>().unwrap();
+        assert!(physics.overlap_circle((4.0, 0.0).into(), 0.1).is_empty());
+    }
+
+    #[test]
+    fn test_child_collider_events() {
+        let mut ecs = create_ecs();
+        let child = ecs
+            .create_entity()
+            .component(ComponentColliderCircle {
+                fixture: fixture(),
+                transform: Transform::identity(),
+                radius: 0.5,
+            })
+            .finish();
+        let parent = ecs
+            .create_entity()
+            .component(ComponentTransform(Transform::identity()))
+            .component(ComponentRigidBody::default())
+            .component(ComponentChildren(vec![child]))
+            .finish();
+        let other = ecs
+            .create_entity()
+            .component(ComponentTransform(Transform::identity()))
+            .finish();
+        ecs.update(Duration::from_secs(1) / 60);
+
+        // 和刚体无关的实体不会触发同步
+        let mut entity = ecs.entity_mut(other).unwrap();
+        entity.add(ComponentScale((2.0, 2.0).into()));
+        entity.remove::<ComponentTransform>();
+        ecs.update(Duration::from_secs(1) / 60);
+        let physics = ecs.system::<SystemPhysics>().unwrap();
+        assert!(!physics.colliders_dirty);
+        assert!(physics.dirty_bodies.is_empty());
+
+        ecs.entity_mut(child)
+            .unwrap()
+            .remove::<ComponentColliderCircle>();
+        ecs.update(Duration::from_secs(1) / 60);
+        let physics = ecs.system::<SystemPhysics>().unwrap();
+        assert!(!physics.colliders_dirty);
+        assert_eq!(physics.dirty_bodies, vec![parent].into_iter().collect());
+
+        ecs.update(Duration::from_secs(1) / 60);
+        let physics = ecs.system::<SystemPhysics>().unwrap();
+        assert!(physics.dirty_bodies.is_empty());
+        assert!(physics.bodies[&parent].fixtures.is_empty());
+    }
+
+    #[test]
+    fn test_physics_material() {
+        let mut ecs = create_ecs();
+        let ice = ecs
+            .system_mut::<SystemPhysics>()
+            .unwrap()
+            .add_material(PhysicsMaterial {
+                friction: 0.05,
+                ..PhysicsMaterial::default()
+            });
+        let ground = create_ground(&mut ecs);
+        ecs.entity_mut(ground)
+            .unwrap()
+            .add(ComponentPhysicsMaterial(ice));
+        ecs.update(Duration::from_secs(1) / 60);
+
+        let friction = |ecs: &ECS| {
+            let physics = ecs.system::<SystemPhysics>().unwrap();
+            let body_id = physics.bodies[&ground].body_id;
+            physics.world.body(body_id).unwrap().fixture_list()[0]
+                .1
+                .friction()
+        };
+        assert_eq!(friction(&ecs), 0.05);
+
+        ecs.system_mut::<SystemPhysics>()
+            .unwrap()
+            .material_mut(ice)
+            .unwrap()
+            .friction = 0.01;
+        ecs.update(Duration::from_secs(1) / 60);
+        assert_eq!(friction(&ecs), 0.01);
+    }
+
+    #[test]
+    fn test_move_by_transform() {
+        let mut ecs = create_ecs();
+        let ball = create_ball(&mut ecs, (0.0, 10.0).into());
+        let platform = ecs
+            .create_entity()
+            .component(ComponentTransform(Transform::new(
+                Vector2::zero(),
+                Rotation::new(0.0),
+            )))
+            .component(ComponentRigidBody {
+                type_: BodyType::Kinematic,
+                ..ComponentRigidBody::default()
+            })
+            .component(ComponentColliderBox {
+                fixture: fixture(),
+                transform: Transform::identity(),
+                half_size: (1.0, 0.1).into(),
+            })
+            .finish();
+        ecs.update(Duration::from_secs(1) / 60);
+
+        for (id, x) in &[(ball, 5.0), (platform, 1.0)] {
+            let mut entity = ecs.entity_mut(*id).unwrap();
+            let transform = entity.get_mut::<ComponentTransform>().unwrap();
+            transform.0.p.x = *x;
+        }
+        ecs.update(Duration::from_secs(1) / 60);
+
+        let entity = ecs.entity(ball).unwrap();
+        assert_eq!(entity.get::<ComponentTransform>().unwrap().0.p.x, 5.0);
+
+        let entity = ecs.entity(platform).unwrap();
+        assert!((entity.get::<ComponentTransform>().unwrap().0.p.x - 1.0).abs() < 0.001);
+        assert_eq!(
+            entity.get::<ComponentRigidBody>().unwrap().linear_velocity,
+            Vector2::zero()
+        );
+    }
+
+    #[test]
+    fn test_apply_impulse() {
+        let mut ecs = create_ecs();
+        let ball = create_ball(&mut ecs, (0.0, 10.0).into());
+        ecs.update(Duration::from_secs(1) / 60);
+
+        ecs.entity_mut(ball).unwrap().add(ComponentApplyImpulse {
+            impulse: (1.0, 0.0).into(),
+            point: None,
+        });
+        ecs.update(Duration::from_secs(1) / 60);
+        let entity = ecs.entity(ball).unwrap();
+        let velocity = entity
+            .get::<ComponentRigidBody>()
+            .unwrap()
+            .linear_velocity
+            .x;
+        assert!(velocity > 0.0);
+        assert!(!entity.contains::<ComponentApplyImpulse>());
+
+        ecs.update(Duration::from_secs(1) / 60);
+        let entity = ecs.entity(ball).unwrap();
+        assert_eq!(
+            entity
+                .get::<ComponentRigidBody>()
+                .unwrap()
+                .linear_velocity
+                .x,
+            velocity
+        );
+    }
+
+    #[test]
+    fn test_velocity_write_back() {
+        let mut ecs = create_ecs();
+        let ball = create_ball(&mut ecs, (0.0, 10.0).into());
+        for _ in 0..10 {
+            ecs.update(Duration::from_secs(1) / 60);
+        }
+        let velocity = ecs
+            .entity(ball)
+            .unwrap()
+            .get::<ComponentRigidBody>()
+            .unwrap()
+            .linear_velocity;
+        assert!(velocity.y < -1.0);
+
+        // 修改其它属性时不会用旧的速度覆盖刚体
+        ecs.entity_mut(ball)
+            .unwrap()
+            .get_mut::<ComponentRigidBody>()
+            .unwrap()
+            .linear_damping = 0.1;
+        ecs.update(Duration::from_secs(1) / 60);
+        let entity = ecs.entity(ball).unwrap();
+        assert!(
+            entity
+                .get::<ComponentRigidBody>()
+                .unwrap()
+                .linear_velocity
+                .y
+                < velocity.y
+        );
+    }
+
+    #[test]
+    fn test_joint_distance() {
+        let mut ecs = create_ecs();
+        let ground = create_ground(&mut ecs);
+        let ball = create_ball(&mut ecs, (3.0, 8.0).into());
+        ecs.entity_mut(ball).unwrap().add(ComponentJointDistance {
+            local_anchor_b: (0.0, 8.0).into(),
+            ..ComponentJointDistance::new(ground, 3.0)
+        });
+        let distance = |ecs: &ECS| {
+            let p = ecs
+                .entity(ball)
+                .unwrap()
+                .get::<ComponentTransform>()
+                .unwrap()
+                .0
+                .p;
+            (p - Vector2::new(0.0, 8.0)).length()
+        };
+
+        for _ in 0..60 {
+            ecs.update(Duration::from_secs(1) / 60);
+        }
+        assert!((distance(&ecs) - 3.0).abs() < 0.05);
+
+        // 修改长度同步到已有的关节
+        ecs.entity_mut(ball)
+            .unwrap()
+            .get_mut::<ComponentJointDistance>()
+            .unwrap()
+            .length = 2.0;
+        for _ in 0..60 {
+            ecs.update(Duration::from_secs(1) / 60);
+        }
+        assert!((distance(&ecs) - 2.0).abs() < 0.05);
+
+        // 删除组件后关节也被删除
+        ecs.entity_mut(ball)
+            .unwrap()
+            .remove::<ComponentJointDistance>();
+        for _ in 0..60 {
+            ecs.update(Duration::from_secs(1) / 60);
+        }
+        assert!(distance(&ecs) > 4.0);
+    }
+
+    fn create_gravity_zone(
+        ecs: &mut ECS,
+        gravity: Vector2<f32>,
+        mode: GravityMode,
+        priority: i32,
+    ) -> EntityId {
+        ecs.create_entity()
+            .component(ComponentTransform(Transform::new(
+                Vector2::zero(),
+                Rotation::new(0.0),
+            )))
+            .component(ComponentRigidBody::default())
+            .component(ComponentColliderBox {
+                fixture: colliders::ColliderMaterial {
+                    is_sensor: true,
+                    ..fixture()
+                },
+                transform: Transform::identity(),
+                half_size: (5.0, 5.0).into(),
+            })
+            .component(ComponentGravityZone {
+                gravity,
+                mode,
+                priority,
+            })
+            .finish()
+    }
+
+    #[test]
+    fn test_gravity_zone() {
+        let mut ecs = create_ecs();
+        create_gravity_zone(&mut ecs, (0.0, 10.0).into(), GravityMode::Replace, 0);
+        let ball = create_ball(&mut ecs, Vector2::zero());
+        for _ in 0..30 {
+            ecs.update(Duration::from_secs(1) / 60);
+        }
+        let entity = ecs.entity(ball).unwrap();
+        assert!(entity.get::<ComponentTransform>().unwrap().0.p.y > 0.0);
+    }
+
+    #[test]
+    fn test_gravity_zone_priority() {
+        let ball_position = |zones: &[(Vector2<f32>, i32)]| {
+            let mut ecs = create_ecs();
+            for (gravity, priority) in zones {
+                create_gravity_zone(&mut ecs, *gravity, GravityMode::Replace, *priority);
+            }
+            let ball = create_ball(&mut ecs, Vector2::zero());
+            for _ in 0..30 {
+                ecs.update(Duration::from_secs(1) / 60);
+            }
+            let entity = ecs.entity(ball).unwrap();
+            entity.get::<ComponentTransform>().unwrap().0.p
+        };
+
+        // 优先级高的区域生效
+        let p = ball_position(&[((0.0, 10.0).into(), 0), ((10.0, 0.0).into(), 1)]);
+        assert!(p.x > 0.5 && p.y.abs() < 0.01, "{:?}", p);
+
+        // 优先级相同时先创建的区域生效
+        let p = ball_position(&[((0.0, 10.0).into(), 0), ((10.0, 0.0).into(), 0)]);
+        assert!(p.y > 0.5 && p.x.abs() < 0.01, "{:?}", p);
+        let p = ball_position(&[((10.0, 0.0).into(), 0), ((0.0, 10.0).into(), 0)]);
+        assert!(p.x > 0.5 && p.y.abs() < 0.01, "{:?}", p);
+    }
+
+    #[test]
+    fn test_collision_layers() {
+        let mut ecs = create_ecs();
+        let ground = create_ground(&mut ecs);
+        ecs.entity_mut(ground)
+            .unwrap()
+            .add(ComponentCollisionLayer {
+                layer: "ground",
+                collides_with: &["player"],
+            });
+        let player = create_ball(&mut ecs, (-2.0, 1.5).into());
+        ecs.entity_mut(player)
+            .unwrap()
+            .add(ComponentCollisionLayer {
+                layer: "player",
+                collides_with: &["ground"],
+            });
+        let debris = create_ball(&mut ecs, (2.0, 1.5).into());
+        ecs.entity_mut(debris)
+            .unwrap()
+            .add(ComponentCollisionLayer {
+                layer: "debris",
+                collides_with: &["ground"],
+            });
+        for _ in 0..120 {
+            ecs.update(Duration::from_secs(1) / 60);
+        }
+
+        let y = |id| {
+            ecs.entity(id)
+                .unwrap()
+                .get::<ComponentTransform>()
+                .unwrap()
+                .0
+                .p
+                .y
+        };
+        assert!(y(player) > 0.5);
+        assert!(y(debris) < 0.0);
+
+        let layers = ecs.resource::<CollisionLayers>().unwrap();
+        assert_eq!(layers.names(), &["ground", "player", "debris"]);
+        assert_eq!(layers.bits("debris"), Some(8));
+        assert_eq!(
+            layers.bits(CollisionLayers::DEFAULT),
+            Some(CollisionLayers::DEFAULT_BITS)
+        );
+    }
+
+    #[test]
+    fn test_static_bodies_not_written_back() {
+        let mut ecs = create_ecs();
+        let ground = create_ground(&mut ecs);
+        let ball = create_ball(&mut ecs, (0.0, 5.0).into());
+        ecs.update(Duration::from_secs(1) / 60);
+
+        let tick = ecs.change_tick();
+        ecs.update(Duration::from_secs(1) / 60);
+        assert!(!ecs
+            .entity(ground)
+            .unwrap()
+            .is_changed_since::<ComponentTransform>(tick));
+        assert!(ecs
+            .entity(ball)
+            .unwrap()
+            .is_changed_since::<ComponentTransform>(tick));
+    }
+
+    #[test]
+    fn test_character_controller() {
+        let mut ecs = create_ecs();
+        ecs.add_system(1, SystemCharacterController);
+        create_ground(&mut ecs);
+        ecs.create_entity()
+            .component(ComponentTransform(Transform::new(
+                (3.0, 1.5).into(),
+                Rotation::new(0.0),
+            )))
+            .component(ComponentRigidBody::default())
+            .component(ComponentColliderBox {
+                fixture: fixture(),
+                transform: Transform::identity(),
+                half_size: (0.5, 1.0).into(),
+            })
+            .finish();
+        let character = ecs
+            .create_entity()
+            .component(ComponentTransform(Transform::new(
+                (0.0, 3.0).into(),
+                Rotation::new(0.0),
+            )))
+            .component(ComponentCharacterController::default())
+            .finish();
+        for _ in 0..120 {
+            ecs.update(Duration::from_secs(1) / 60);
+        }
+
+        let entity = ecs.entity(character).unwrap();
+        let controller = *entity.get::<ComponentCharacterController>().unwrap();
+        assert!(controller.grounded);
+        assert!((entity.get::<ComponentTransform>().unwrap().0.p.y - 1.0).abs() < 0.01);
+
+        for _ in 0..120 {
+            ecs.entity_mut(character)
+                .unwrap()
+                .get_mut::<ComponentCharacterController>()
+                .unwrap()
+                .velocity
+                .x = 2.0;
+            ecs.update(Duration::from_secs(1) / 60);
+        }
+
+        let entity = ecs.entity(character).unwrap();
+        let controller = *entity.get::<ComponentCharacterController>().unwrap();
+        assert!(controller.grounded);
+        assert!(controller.on_wall);
+        assert!((entity.get::<ComponentTransform>().unwrap().0.p.x - 2.25).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_compound_collider() {
+        let mut ecs = create_ecs();
+        create_ground(&mut ecs);
+        let wheel = |x| ShapeDesc {
+            fixture: fixture(),
+            transform: Transform::new((x, -0.25).into(), Rotation::new(0.0)),
+            kind: ShapeKind::Circle { radius: 0.25 },
+        };
+        let car = ecs
+            .create_entity()
+            .component(ComponentTransform(Transform::new(
+                (0.0, 3.0).into(),
+                Rotation::new(0.0),
+            )))
+            .component(ComponentRigidBody {
+                type_: BodyType::Dynamic,
+                ..ComponentRigidBody::default()
+            })
+            .component(ComponentColliderCompound(vec![
+                ShapeDesc {
+                    fixture: fixture(),
+                    transform: Transform::new((0.0, 0.25).into(), Rotation::new(0.0)),
+                    kind: ShapeKind::Box {
+                        half_size: (1.0, 0.25).into(),
+                    },
+                },
+                wheel(-0.75),
+                wheel(0.75),
+            ]))
+            .finish();
+        for _ in 0..120 {
+            ecs.update(Duration::from_secs(1) / 60);
+        }
+
+        let fixtures = |ecs: &ECS| {
+            ecs.system::<SystemPhysics>().unwrap().bodies[&car]
+                .fixtures
+                .len()
+        };
+        assert_eq!(fixtures(&ecs), 3);
+        let y = ecs
+            .entity(car)
+            .unwrap()
+            .get::<ComponentTransform>()
+            .unwrap()
+            .0
+            .p
+            .y;
+        assert!((y - 1.0).abs() < 0.05);
+
+        ecs.entity_mut(car)
+            .unwrap()
+            .get_mut::<ComponentColliderCompound>()
+            .unwrap()
+            .0
+            .pop();
+        ecs.update(Duration::from_secs(1) / 60);
+        assert_eq!(fixtures(&ecs), 2);
+    }
+
+    #[test]
+    fn test_physics_settings() {
+        let mut ecs = create_ecs();
+        ecs.insert_resource(PhysicsSettings {
+            gravity: Vector2::zero(),
+            ..PhysicsSettings::default()
+        });
+        let ball = create_ball(&mut ecs, (0.0, 10.0).into());
+        for _ in 0..60 {
+            ecs.update(Duration::from_secs(1) / 60);
+        }
+        let entity = ecs.entity(ball).unwrap();
+        assert_eq!(entity.get::<ComponentTransform>().unwrap().0.p.y, 10.0);
+        assert_eq!(
+            ecs.system::<SystemPhysics>().unwrap().gravity(),
+            Vector2::zero()
+        );
+    }
+
+    #[test]
+    fn test_sleep_state() {
+        let mut ecs = create_ecs();
+        create_ground(&mut ecs);
+        let ball = create_ball(&mut ecs, (0.0, 2.0).into());
+        ecs.entity_mut(ball)
+            .unwrap()
+            .add(ComponentSleepState::default());
+        ecs.update(Duration::from_secs(1) / 60);
+        assert_eq!(
+            *ecs.entity(ball)
+                .unwrap()
+                .get::<ComponentSleepState>()
+                .unwrap(),
+            ComponentSleepState::Awake
+        );
+
+        for _ in 0..300 {
+            ecs.update(Duration::from_secs(1) / 60);
+        }
+        assert_eq!(
+            *ecs.entity(ball)
+                .unwrap()
+                .get::<ComponentSleepState>()
+                .unwrap(),
+            ComponentSleepState::Sleeping
+        );
+    }
+
+    #[test]
+    fn test_collider_local_transform() {
+        let mut ecs = create_ecs();
+        ecs.create_entity()
+            .component(ComponentTransform(Transform::identity()))
+            .component(ComponentRigidBody::default())
+            .component(ComponentColliderBox {
+                fixture: fixture(),
+                transform: Transform::new(
+                    (0.0, 1.0).into(),
+                    Rotation::new(std::f32::consts::FRAC_PI_2),
+                ),
+                half_size: (0.1, 1.0).into(),
+            })
+            .finish();
+        ecs.update(Duration::from_secs(1) / 60);
+
+        let physics = ecs.system::<SystemPhysics>().unwrap();
+        let hit = physics
+            .ray_cast((0.8, 5.0).into(), (0.8, -5.0).into(), |_| true)
+            .unwrap();
+        assert!((hit.point.y - 1.1).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_entities_in_area() {
+        let mut ecs = create_ecs();
+        let ground = create_ground(&mut ecs);
+        let ball = create_ball(&mut ecs, (0.0, 1.0).into());
+        ecs.entity_mut(ground).unwrap().add(ComponentCollisionLayer {
+            layer: "ground",
+            collides_with: &["enemy"],
+        });
+        ecs.entity_mut(ball).unwrap().add(ComponentCollisionLayer {
+            layer: "enemy",
+            collides_with: &["ground"],
+        });
+        ecs.update(Duration::from_secs(1) / 60);
+        let physics = ecs.system::<SystemPhysics>().unwrap();
+
+        let entities = physics.entities_in_circle(&ecs, (0.0, 0.5).into(), 1.0, None);
+        assert_eq!(entities.len(), 2);
+        assert!(entities.contains(&ground) && entities.contains(&ball));
+        assert_eq!(
+            physics.entities_in_circle(&ecs, (0.0, 0.5).into(), 1.0, Some("enemy")),
+            vec![ball]
+        );
+        assert!(physics
+            .entities_in_circle(&ecs, (0.0, 0.5).into(), 1.0, Some("player"))
+            .is_empty());
+
+        let aabb = AABB::new_center((5.0, 0.0).into(), (1.0, 1.0).into());
+        assert_eq!(physics.entities_in_aabb(&ecs, aabb, None), vec![ground]);
+        assert!(physics
+            .entities_in_aabb(&ecs, aabb, Some("enemy"))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_entities_in_area_unlayered() {
+        let mut ecs = create_ecs();
+        let ground = create_ground(&mut ecs);
+        ecs.entity_mut(ground)
+            .unwrap()
+            .add(ComponentCollisionLayer {
+                layer: "ground",
+                collides_with: &[CollisionLayers::DEFAULT],
+            });
+        let ball = create_ball(&mut ecs, (0.0, 1.0).into());
+        ecs.update(Duration::from_secs(1) / 60);
+        let physics = ecs.system::<SystemPhysics>().unwrap();
+
+        // 没有碰撞层的实体不会被当成第一个命名的层
+        assert_eq!(
+            physics.entities_in_circle(&ecs, (0.0, 0.5).into(), 1.0, Some("ground")),
+            vec![ground]
+        );
+        assert_eq!(
+            physics.entities_in_circle(
+                &ecs,
+                (0.0, 0.5).into(),
+                1.0,
+                Some(CollisionLayers::DEFAULT)
+            ),
+            vec![ball]
+        );
+    }
+
+    #[test]
+    fn test_contact_filter() {
+        struct Ghost;
+
+        impl Component for Ghost {
+            fn name() -> &'static str {
+                "Ghost"
+            }
+        }
+
+        let mut ecs = create_ecs();
+        ecs.system_mut::<SystemPhysics>()
+            .unwrap()
+            .set_contact_filter(|ecs, a, b| {
+                let is_ghost = |id| ecs.entity(id).unwrap().contains::<Ghost>();
+                !is_ghost(a) && !is_ghost(b)
+            });
+        create_ground(&mut ecs);
+        let ball = create_ball(&mut ecs, (-2.0, 2.0).into());
+        let ghost = create_ball(&mut ecs, (2.0, 2.0).into());
+        ecs.entity_mut(ghost).unwrap().add(Ghost);
+        for _ in 0..120 {
+            ecs.update(Duration::from_secs(1) / 60);
+        }
+
+        let y = |id| {
+            ecs.entity(id)
+                .unwrap()
+                .get::<ComponentTransform>()
+                .unwrap()
+                .0
+                .p
+                .y
+        };
+        assert!(y(ball) > 0.5);
+        assert!(y(ghost) < 0.0);
+    }
+
+    #[test]
+    fn test_collider_scale() {
+        let mut ecs = create_ecs();
+        let id = ecs
+            .create_entity()
+            .component(ComponentTransform(Transform::identity()))
+            .component(ComponentScale((2.0, 0.5).into()))
+            .component(ComponentRigidBody::default())
+            .component(ComponentColliderBox {
+                fixture: fixture(),
+                transform: Transform::identity(),
+                half_size: (1.0, 1.0).into(),
+            })
+            .finish();
+        ecs.update(Duration::from_secs(1) / 60);
+
+        let ray_cast = |ecs: &ECS| {
+            ecs.system::<SystemPhysics>()
+                .unwrap()
+                .ray_cast((1.5, 5.0).into(), (1.5, -5.0).into(), |_| true)
+                .map(|hit| hit.point.y)
+        };
+        assert!((ray_cast(&ecs).unwrap() - 0.5).abs() < 0.001);
+
+        ecs.entity_mut(id)
+            .unwrap()
+            .get_mut::<ComponentScale>()
+            .unwrap()
+            .0 = (1.0, 1.0).into();
+        ecs.update(Duration::from_secs(1) / 60);
+        assert!(ray_cast(&ecs).is_none());
+    }
+
+    #[test]
+    fn test_disabled_body() {
+        let mut ecs = create_ecs();
+        let ball = create_ball(&mut ecs, (0.0, 10.0).into());
+        ecs.update(Duration::from_secs(1) / 60);
+        ecs.entity_mut(ball).unwrap().add(ComponentDisabled);
+
+        let y = |ecs: &ECS| {
+            ecs.entity(ball)
+                .unwrap()
+                .get::<ComponentTransform>()
+                .unwrap()
+                .0
+                .p
+                .y
+        };
+        let start = y(&ecs);
+        for _ in 0..30 {
+            ecs.update(Duration::from_secs(1) / 60);
+        }
+        assert_eq!(y(&ecs), start);
+
+        ecs.entity_mut(ball).unwrap().remove::<ComponentDisabled>();
+        for _ in 0..30 {
+            ecs.update(Duration::from_secs(1) / 60);
+        }
+        assert!(y(&ecs) < start);
+    }
+
+    #[test]
+    fn test_nav_grid_from_physics() {
+        let mut ecs = create_ecs();
+        create_ground(&mut ecs);
+        create_ball(&mut ecs, (0.0, 1.5).into());
+        ecs.update(Duration::from_secs(1) / 60);
+        ecs.update(Duration::from_secs(1) / 60);
+        let physics = ecs.system::<SystemPhysics>().unwrap();
+
+        let bounds = AABB::new((-2.0, -1.0).into(), (2.0, 2.0).into());
+        let grid = NavGrid::from_physics(physics, bounds, 0.5);
+        assert_eq!((grid.width(), grid.height()), (8, 6));
+        for y in 0..grid.height() {
+            assert_eq!(grid.is_blocked(0, y), y == 1 || y == 2);
+        }
+        // 动态刚体不会阻挡
+        assert!(!grid.is_blocked(4, 5));
+
+        let options = PathOptions::default();
+        assert!(grid
+            .find_path((-1.5, -0.75).into(), (1.5, 1.5).into(), options)
+            .is_none());
+        let path = grid
+            .find_path((-1.5, 1.5).into(), (1.5, 0.75).into(), options)
+            .unwrap();
+        assert_eq!(path.len(), 2);
+    }
 }
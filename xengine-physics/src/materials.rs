@@ -0,0 +1,90 @@
+use crate::SystemPhysics;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use xecs::Component;
+
+/// 物理材质，多个collider可以通过`ComponentPhysicsMaterial`共享同一个材质
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(default)]
+pub struct PhysicsMaterial {
+    pub friction: f32,
+    pub restitution: f32,
+    pub density: f32,
+
+    /// 滚动阻力，xphysics暂不支持，目前只是保存下来
+    pub rolling_resistance: f32,
+}
+
+impl Default for PhysicsMaterial {
+    fn default() -> Self {
+        PhysicsMaterial {
+            friction: 0.2,
+            restitution: 0.0,
+            density: 1.0,
+            rolling_resistance: 0.0,
+        }
+    }
+}
+
+/// 通过`SystemPhysics::add_material`得到的材质句柄
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct PhysicsMaterialHandle(pub(crate) usize);
+
+/// 实体上的collider使用的材质，会覆盖collider自己的friction、restitution和density
+///
+/// 没有刚体的子实体如果没有这个组件，会使用父实体的材质
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentPhysicsMaterial(pub PhysicsMaterialHandle);
+
+impl Component for ComponentPhysicsMaterial {
+    fn name() -> &'static str {
+        "PhysicsMaterial"
+    }
+}
+
+impl SystemPhysics {
+    pub fn add_material(&mut self, material: PhysicsMaterial) -> PhysicsMaterialHandle {
+        self.materials.push(material);
+        PhysicsMaterialHandle(self.materials.len() - 1)
+    }
+
+    pub fn material(&self, handle: PhysicsMaterialHandle) -> Option<&PhysicsMaterial> {
+        self.materials.get(handle.0)
+    }
+
+    /// 修改材质后，下一次`update`时会同步到所有使用这个材质的fixtures
+    pub fn material_mut(&mut self, handle: PhysicsMaterialHandle) -> Option<&mut PhysicsMaterial> {
+        self.colliders_dirty = true;
+        self.materials.get_mut(handle.0)
+    }
+    /// 读取命名的材质，格式是`{"ice": {"friction": 0.0}}`，缺少的字段使用默认值
+    ///
+    /// 已经读取过的名字会原地更新材质，句柄保持不变，所以可以用来重新加载材质文件
+    pub fn load_materials(
+        &mut self,
+        json: &str,
+    ) -> serde_json::Result<Vec<(String, PhysicsMaterialHandle)>> {
+        let materials: BTreeMap<String, PhysicsMaterial> = serde_json::from_str(json)?;
+        let mut handles = Vec::new();
+        for (name, material) in materials {
+            let handle = match self.material_names.get(&name).copied() {
+                Some(handle) => {
+                    *self.material_mut(handle).unwrap() = material;
+                    handle
+                }
+                None => {
+                    let handle = self.add_material(material);
+                    self.material_names.insert(name.clone(), handle);
+                    handle
+                }
+            };
+            handles.push((name, handle));
+        }
+        Ok(handles)
+    }
+
+    /// 通过`load_materials`读取的材质
+    pub fn material_by_name(&self, name: &str) -> Option<PhysicsMaterialHandle> {
+        self.material_names.get(name).copied()
+    }
+}
@@ -0,0 +1,529 @@
+//! 网格寻路
+//!
+//! `NavGrid`可以从图块数据创建，也可以从物理世界中的静态碰撞体光栅化得到，作为ECS资源给AI系统使用：
+//!
+//! ```ignore
+//! let grid = NavGrid::from_physics(ecs.system::<SystemPhysics>().unwrap(), bounds, 0.5);
+//! ecs.insert_resource(grid);
+//!
+//! let path = ecs.resource::<NavGrid>().unwrap().find_path(from, to, PathOptions::default());
+//! ```
+
+use crate::SystemPhysics;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use xmath::{Rotation, Transform, Vector2, AABB};
+use xphysics::{BodyType, ShapePolygon};
+
+/// 直线和对角线移动一格的代价
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+/// xphysics的多边形半径，重叠测试时两个多边形都会向外扩展这个距离
+const POLYGON_RADIUS: f32 = 0.01;
+
+/// 寻路算法
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PathAlgorithm {
+    AStar,
+
+    /// Jump Point Search，只在允许对角线移动时使用，否则退化为A*
+    JumpPoint,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct PathOptions {
+    pub algorithm: PathAlgorithm,
+
+    /// 是否允许对角线移动，对角线移动不会穿过两个阻挡格子之间的角
+    pub allow_diagonal: bool,
+
+    /// 是否用拉绳算法去掉可以直接看到的中间路点
+    pub smooth: bool,
+}
+
+impl Default for PathOptions {
+    fn default() -> Self {
+        PathOptions {
+            algorithm: PathAlgorithm::JumpPoint,
+            allow_diagonal: true,
+            smooth: true,
+        }
+    }
+}
+
+type Cell = (i32, i32);
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+struct OpenNode {
+    f: u32,
+    g: u32,
+    cell: Cell,
+}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // f相同时优先展开离终点更近的节点
+        (self.f, Reverse(self.g), self.cell).cmp(&(other.f, Reverse(other.g), other.cell))
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn octile(a: Cell, b: Cell) -> u32 {
+    let dx = (a.0 - b.0).abs() as u32;
+    let dy = (a.1 - b.1).abs() as u32;
+    STRAIGHT_COST * dx.max(dy) + (DIAGONAL_COST - STRAIGHT_COST) * dx.min(dy)
+}
+
+/// 寻路用的网格，格子`(0, 0)`的左下角在`origin`
+#[derive(Debug, Clone)]
+pub struct NavGrid {
+    width: usize,
+    height: usize,
+    cell_size: f32,
+    origin: Vector2<f32>,
+    blocked: Vec<bool>,
+}
+
+impl NavGrid {
+    /// 创建所有格子都可以通过的网格
+    pub fn new(width: usize, height: usize, cell_size: f32, origin: Vector2<f32>) -> NavGrid {
+        assert!(cell_size > 0.0);
+        NavGrid {
+            width,
+            height,
+            cell_size,
+            origin,
+            blocked: vec![false; width * height],
+        }
+    }
+
+    /// 从图块数据创建，`tiles`按行从上到下排列（和Tiled相同），非0的图块是阻挡
+    pub fn from_tiles(
+        width: usize,
+        height: usize,
+        cell_size: f32,
+        origin: Vector2<f32>,
+        tiles: &[u32],
+    ) -> NavGrid {
+        assert_eq!(tiles.len(), width * height);
+        let mut grid = NavGrid::new(width, height, cell_size, origin);
+        for (index, tile) in tiles.iter().enumerate() {
+            let (x, y) = (index % width, height - 1 - index / width);
+            grid.set_blocked(x, y, *tile != 0);
+        }
+        grid
+    }
+
+    /// 把`bounds`范围内的静态碰撞体光栅化，和静态的非传感器碰撞体重叠的格子是阻挡
+    pub fn from_physics(physics: &SystemPhysics, bounds: AABB<f32>, cell_size: f32) -> NavGrid {
+        let size = bounds.upper_bound - bounds.lower_bound;
+        let width = (size.x / cell_size).ceil().max(0.0) as usize;
+        let height = (size.y / cell_size).ceil().max(0.0) as usize;
+        let mut grid = NavGrid::new(width, height, cell_size, bounds.lower_bound);
+
+        // 把格子缩小一点，只接触格子边缘的碰撞体不会阻挡格子
+        let half = (cell_size * 0.5 - POLYGON_RADIUS * 2.5).max(cell_size * 0.25);
+        let cell_shape = ShapePolygon::new_box_center(half, half);
+        for y in 0..height {
+            for x in 0..width {
+                let center = grid.cell_center(x, y);
+                let xf = Transform::new(center, Rotation::identity());
+                let blocked = physics
+                    .world
+                    .overlap_shape(&cell_shape, xf, |fixture| !fixture.is_sensor())
+                    .any(|fixture| {
+                        physics
+                            .world
+                            .body(fixture.body())
+                            .is_some_and(|body| body.body_type() == BodyType::Static)
+                    });
+                grid.set_blocked(x, y, blocked);
+            }
+        }
+        grid
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn origin(&self) -> Vector2<f32> {
+        self.origin
+    }
+
+    pub fn set_blocked(&mut self, x: usize, y: usize, blocked: bool) {
+        assert!(x < self.width && y < self.height);
+        self.blocked[y * self.width + x] = blocked;
+    }
+
+    /// 网格外的格子都是阻挡
+    pub fn is_blocked(&self, x: usize, y: usize) -> bool {
+        !self.walkable((x as i32, y as i32))
+    }
+
+    /// 世界坐标所在的格子
+    pub fn cell_at(&self, point: Vector2<f32>) -> Option<(usize, usize)> {
+        let local = (point - self.origin) * (1.0 / self.cell_size);
+        let (x, y) = (local.x.floor(), local.y.floor());
+        if x >= 0.0 && y >= 0.0 && (x as usize) < self.width && (y as usize) < self.height {
+            Some((x as usize, y as usize))
+        } else {
+            None
+        }
+    }
+
+    /// 格子中心的世界坐标
+    pub fn cell_center(&self, x: usize, y: usize) -> Vector2<f32> {
+        self.origin + Vector2::new(x as f32 + 0.5, y as f32 + 0.5) * self.cell_size
+    }
+
+    fn walkable(&self, (x, y): Cell) -> bool {
+        x >= 0
+            && y >= 0
+            && (x as usize) < self.width
+            && (y as usize) < self.height
+            && !self.blocked[y as usize * self.width + x as usize]
+    }
+
+    fn index(&self, (x, y): Cell) -> usize {
+        y as usize * self.width + x as usize
+    }
+
+    /// 从`from`到`to`寻找路径，返回包括起点和终点在内的路点，起点或终点不可到达时返回`None`
+    pub fn find_path(
+        &self,
+        from: Vector2<f32>,
+        to: Vector2<f32>,
+        options: PathOptions,
+    ) -> Option<Vec<Vector2<f32>>> {
+        let start = self.cell_at(from)?;
+        let goal = self.cell_at(to)?;
+        let (start, goal) = (
+            (start.0 as i32, start.1 as i32),
+            (goal.0 as i32, goal.1 as i32),
+        );
+        if !self.walkable(start) || !self.walkable(goal) {
+            return None;
+        }
+
+        let jump = options.algorithm == PathAlgorithm::JumpPoint && options.allow_diagonal;
+        let cells = self.search(start, goal, options.allow_diagonal, jump)?;
+        let mut path = Vec::with_capacity(cells.len());
+        path.push(from);
+        if cells.len() > 2 {
+            path.extend(
+                cells[1..cells.len() - 1]
+                    .iter()
+                    .map(|&(x, y)| self.cell_center(x as usize, y as usize)),
+            );
+        }
+        path.push(to);
+
+        if options.smooth {
+            path = self.smooth_path(&path);
+        }
+        Some(path)
+    }
+
+    /// 拉绳算法，去掉可以从前一个路点直接到达的路点
+    pub fn smooth_path(&self, path: &[Vector2<f32>]) -> Vec<Vector2<f32>> {
+        if path.len() <= 2 {
+            return path.to_vec();
+        }
+        let mut result = vec![path[0]];
+        let mut anchor = 0;
+        while anchor < path.len() - 1 {
+            let mut next = anchor + 1;
+            for i in (anchor + 2..path.len()).rev() {
+                if self.line_of_sight(path[anchor], path[i]) {
+                    next = i;
+                    break;
+                }
+            }
+            result.push(path[next]);
+            anchor = next;
+        }
+        result
+    }
+
+    /// 线段经过的所有格子是否都可以通过
+    pub fn line_of_sight(&self, from: Vector2<f32>, to: Vector2<f32>) -> bool {
+        let a = (from - self.origin) * (1.0 / self.cell_size);
+        let b = (to - self.origin) * (1.0 / self.cell_size);
+        let mut cell = (a.x.floor() as i32, a.y.floor() as i32);
+        let end = (b.x.floor() as i32, b.y.floor() as i32);
+        let d = b - a;
+        let step = (
+            if d.x > 0.0 { 1 } else { -1 },
+            if d.y > 0.0 { 1 } else { -1 },
+        );
+        let delta = (
+            if d.x != 0.0 {
+                (1.0 / d.x).abs()
+            } else {
+                f32::INFINITY
+            },
+            if d.y != 0.0 {
+                (1.0 / d.y).abs()
+            } else {
+                f32::INFINITY
+            },
+        );
+        let boundary = |p: f32, c: i32, s: i32| {
+            if s > 0 {
+                c as f32 + 1.0 - p
+            } else {
+                p - c as f32
+            }
+        };
+        let mut t_max = (
+            boundary(a.x, cell.0, step.0) * delta.0,
+            boundary(a.y, cell.1, step.1) * delta.1,
+        );
+
+        loop {
+            if !self.walkable(cell) {
+                return false;
+            }
+            if cell == end {
+                return true;
+            }
+            if (t_max.0 - t_max.1).abs() < 1e-6 {
+                // 正好经过格子的角，两个相邻的格子都要可以通过
+                if !self.walkable((cell.0 + step.0, cell.1))
+                    || !self.walkable((cell.0, cell.1 + step.1))
+                {
+                    return false;
+                }
+                cell = (cell.0 + step.0, cell.1 + step.1);
+                t_max = (t_max.0 + delta.0, t_max.1 + delta.1);
+            } else if t_max.0 < t_max.1 {
+                cell.0 += step.0;
+                t_max.0 += delta.0;
+            } else {
+                cell.1 += step.1;
+                t_max.1 += delta.1;
+            }
+            if t_max.0.min(t_max.1) > 1.0 + 1e-6 && cell != end {
+                // 浮点误差，已经走过了终点
+                return self.walkable(end);
+            }
+        }
+    }
+
+    fn can_move(&self, cell: Cell, (dx, dy): Cell) -> bool {
+        let next = (cell.0 + dx, cell.1 + dy);
+        if !self.walkable(next) {
+            return false;
+        }
+        dx == 0
+            || dy == 0
+            || (self.walkable((cell.0 + dx, cell.1)) && self.walkable((cell.0, cell.1 + dy)))
+    }
+
+    fn neighbors(&self, cell: Cell, parent: Option<Cell>, diagonal: bool, jump: bool) -> Vec<Cell> {
+        let mut result = Vec::with_capacity(8);
+        let mut push = |d: Cell| {
+            if self.can_move(cell, d) {
+                result.push((cell.0 + d.0, cell.1 + d.1));
+            }
+        };
+
+        match parent {
+            Some(parent) if jump => {
+                let dx = (cell.0 - parent.0).signum();
+                let dy = (cell.1 - parent.1).signum();
+                if dx != 0 && dy != 0 {
+                    push((0, dy));
+                    push((dx, 0));
+                    push((dx, dy));
+                } else if dx != 0 {
+                    push((dx, 0));
+                    push((dx, 1));
+                    push((dx, -1));
+                    push((0, 1));
+                    push((0, -1));
+                } else {
+                    push((0, dy));
+                    push((1, dy));
+                    push((-1, dy));
+                    push((1, 0));
+                    push((-1, 0));
+                }
+            }
+            _ => {
+                for &d in &[(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                    push(d);
+                }
+                if diagonal {
+                    for &d in &[(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                        push(d);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// 从`cell`沿`(dx, dy)`方向跳跃，返回遇到的跳点
+    fn jump(&self, mut cell: Cell, (dx, dy): Cell, goal: Cell) -> Option<Cell> {
+        loop {
+            if !self.walkable(cell) {
+                return None;
+            }
+            if cell == goal {
+                return Some(cell);
+            }
+            let (x, y) = cell;
+            if dx != 0 && dy != 0 {
+                if self.jump((x + dx, y), (dx, 0), goal).is_some()
+                    || self.jump((x, y + dy), (0, dy), goal).is_some()
+                {
+                    return Some(cell);
+                }
+            } else if dx != 0 {
+                if (self.walkable((x, y + 1)) && !self.walkable((x - dx, y + 1)))
+                    || (self.walkable((x, y - 1)) && !self.walkable((x - dx, y - 1)))
+                {
+                    return Some(cell);
+                }
+            } else if (self.walkable((x + 1, y)) && !self.walkable((x + 1, y - dy)))
+                || (self.walkable((x - 1, y)) && !self.walkable((x - 1, y - dy)))
+            {
+                return Some(cell);
+            }
+
+            if !self.can_move(cell, (dx, dy)) {
+                return None;
+            }
+            cell = (x + dx, y + dy);
+        }
+    }
+
+    fn search(&self, start: Cell, goal: Cell, diagonal: bool, jump: bool) -> Option<Vec<Cell>> {
+        let mut g = vec![u32::MAX; self.width * self.height];
+        let mut parent: Vec<Option<Cell>> = vec![None; self.width * self.height];
+        let mut closed = vec![false; self.width * self.height];
+        let mut open = BinaryHeap::new();
+
+        g[self.index(start)] = 0;
+        open.push(Reverse(OpenNode {
+            f: octile(start, goal),
+            g: 0,
+            cell: start,
+        }));
+
+        while let Some(Reverse(node)) = open.pop() {
+            let index = self.index(node.cell);
+            if closed[index] {
+                continue;
+            }
+            closed[index] = true;
+            if node.cell == goal {
+                let mut cells = vec![goal];
+                let mut cell = goal;
+                while let Some(p) = parent[self.index(cell)] {
+                    cells.push(p);
+                    cell = p;
+                }
+                cells.reverse();
+                return Some(cells);
+            }
+
+            for neighbor in self.neighbors(node.cell, parent[index], diagonal, jump) {
+                let next = if jump {
+                    let d = (neighbor.0 - node.cell.0, neighbor.1 - node.cell.1);
+                    match self.jump(neighbor, d, goal) {
+                        Some(cell) => cell,
+                        None => continue,
+                    }
+                } else {
+                    neighbor
+                };
+                let next_index = self.index(next);
+                if closed[next_index] {
+                    continue;
+                }
+                let cost = node.g + octile(node.cell, next);
+                if cost < g[next_index] {
+                    g[next_index] = cost;
+                    parent[next_index] = Some(node.cell);
+                    open.push(Reverse(OpenNode {
+                        f: cost + octile(next, goal),
+                        g: cost,
+                        cell: next,
+                    }));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rustfmt::skip]
+    const TILES: [u32; 25] = [
+        0, 0, 0, 0, 0,
+        0, 1, 1, 1, 0,
+        0, 0, 0, 1, 0,
+        1, 1, 0, 1, 0,
+        0, 0, 0, 1, 0,
+    ];
+
+    #[test]
+    fn test_find_path() {
+        let grid = NavGrid::from_tiles(5, 5, 1.0, Vector2::zero(), &TILES);
+        assert!(grid.is_blocked(0, 1));
+        assert!(!grid.is_blocked(0, 0));
+        let from = Vector2::new(0.5, 0.5);
+        let to = Vector2::new(4.5, 0.5);
+
+        let mut lengths = Vec::new();
+        for &algorithm in &[PathAlgorithm::AStar, PathAlgorithm::JumpPoint] {
+            let options = PathOptions {
+                algorithm,
+                allow_diagonal: true,
+                smooth: false,
+            };
+            let path = grid.find_path(from, to, options).unwrap();
+            assert_eq!(path[0], from);
+            assert_eq!(*path.last().unwrap(), to);
+            for pair in path.windows(2) {
+                assert!(grid.line_of_sight(pair[0], pair[1]));
+            }
+            lengths.push(path.windows(2).map(|p| p[0].distance(&p[1])).sum::<f32>());
+
+            let smoothed = grid.smooth_path(&path);
+            assert!(smoothed.len() <= path.len());
+            for pair in smoothed.windows(2) {
+                assert!(grid.line_of_sight(pair[0], pair[1]));
+            }
+        }
+        assert!((lengths[0] - lengths[1]).abs() < 1e-4);
+
+        // 终点被围住
+        let mut grid = grid;
+        grid.set_blocked(4, 1, true);
+        assert!(grid.find_path(from, to, PathOptions::default()).is_none());
+        assert!(grid
+            .find_path(from, Vector2::new(10.0, 0.5), PathOptions::default())
+            .is_none());
+    }
+}
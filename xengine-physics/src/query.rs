@@ -0,0 +1,152 @@
+use crate::{CollisionLayers, SystemPhysics};
+use xecs::{EntityId, ECS};
+use xmath::{Rotation, Transform, Vector2, AABB};
+use xphysics::{Fixture, ShapeCircle};
+
+/// 射线检测的结果
+#[derive(Debug, Copy, Clone)]
+pub struct HitInfo {
+    /// 被击中的实体
+    pub entity: EntityId,
+
+    /// 击中点的世界坐标
+    pub point: Vector2<f32>,
+
+    /// 击中点的表面法线
+    pub normal: Vector2<f32>,
+}
+
+fn push_entity(entities: &mut Vec<EntityId>, fixture: &Fixture<f32, EntityId>) {
+    if let Some(id) = fixture.data() {
+        if !entities.contains(id) {
+            entities.push(*id);
+        }
+    }
+}
+
+impl SystemPhysics {
+    /// 从`p1`到`p2`做射线检测，返回最近的击中点，`p1`和`p2`相同时返回`None`
+    ///
+    /// `filter`返回`false`的实体会被忽略
+    pub fn ray_cast<F>(&self, p1: Vector2<f32>, p2: Vector2<f32>, mut filter: F) -> Option<HitInfo>
+    where
+        F: FnMut(EntityId) -> bool,
+    {
+        self.ray_cast_fixtures(p1, p2, |fixture| match fixture.data() {
+            Some(entity) => filter(*entity),
+            None => false,
+        })
+    }
+
+    pub(crate) fn ray_cast_fixtures<F>(
+        &self,
+        p1: Vector2<f32>,
+        p2: Vector2<f32>,
+        mut filter: F,
+    ) -> Option<HitInfo>
+    where
+        F: FnMut(&Fixture<f32, EntityId>) -> bool,
+    {
+        // 长度为0的射线没有方向，xphysics会panic
+        if p1 == p2 {
+            return None;
+        }
+        let hit = self.world.ray_cast_closest(p1, p2, |fixture| {
+            fixture.data().is_some() && filter(fixture)
+        })?;
+        Some(HitInfo {
+            entity: *hit.fixture.data().unwrap(),
+            point: hit.point,
+            normal: hit.normal,
+        })
+    }
+
+    /// 返回包围盒与`aabb`重叠的所有实体
+    pub fn overlap_aabb(&self, aabb: AABB<f32>) -> Vec<EntityId> {
+        self.overlap_aabb_fixtures(aabb, |_| true)
+    }
+
+    /// 返回形状与圆重叠的所有实体
+    pub fn overlap_circle(&self, center: Vector2<f32>, radius: f32) -> Vec<EntityId> {
+        self.overlap_circle_fixtures(center, radius, |_| true)
+    }
+
+    /// 返回包围盒与`aabb`重叠并且属于碰撞层`layer`的所有实体，`layer`为`None`时不过滤碰撞层
+    ///
+    /// 碰撞层的名字通过ECS里的`CollisionLayers`资源查找，`CollisionLayers::DEFAULT`表示没有碰撞层的实体
+    pub fn entities_in_aabb(
+        &self,
+        ecs: &ECS,
+        aabb: AABB<f32>,
+        layer: Option<&str>,
+    ) -> Vec<EntityId> {
+        match layer_bits(ecs, layer) {
+            Some(bits) => self
+                .overlap_aabb_fixtures(aabb, |fixture| fixture.filter().category_bits & bits != 0),
+            None => Vec::new(),
+        }
+    }
+
+    /// 返回形状与圆重叠并且属于碰撞层`layer`的所有实体，`layer`为`None`时不过滤碰撞层
+    ///
+    /// 通过broad-phase查询，适合AI感知和范围效果
+    pub fn entities_in_circle(
+        &self,
+        ecs: &ECS,
+        center: Vector2<f32>,
+        radius: f32,
+        layer: Option<&str>,
+    ) -> Vec<EntityId> {
+        match layer_bits(ecs, layer) {
+            Some(bits) => self.overlap_circle_fixtures(center, radius, |fixture| {
+                fixture.filter().category_bits & bits != 0
+            }),
+            None => Vec::new(),
+        }
+    }
+
+    fn overlap_aabb_fixtures<F>(&self, aabb: AABB<f32>, filter: F) -> Vec<EntityId>
+    where
+        F: FnMut(&Fixture<f32, EntityId>) -> bool,
+    {
+        let mut entities = Vec::new();
+        for fixture in self.world.query_aabb_filtered(aabb, filter) {
+            let overlap = (0..fixture.shape().child_count())
+                .any(|child_index| fixture.aabb(child_index).is_overlap(&aabb));
+            if overlap {
+                push_entity(&mut entities, fixture);
+            }
+        }
+        entities
+    }
+
+    fn overlap_circle_fixtures<F>(
+        &self,
+        center: Vector2<f32>,
+        radius: f32,
+        filter: F,
+    ) -> Vec<EntityId>
+    where
+        F: FnMut(&Fixture<f32, EntityId>) -> bool,
+    {
+        let circle = ShapeCircle::new(Vector2::zero(), radius);
+        let xf_circle = Transform::new(center, Rotation::identity());
+
+        let mut entities = Vec::new();
+        for fixture in self.world.overlap_shape(&circle, xf_circle, filter) {
+            push_entity(&mut entities, fixture);
+        }
+        entities
+    }
+}
+
+/// 没有注册的碰撞层返回`None`
+fn layer_bits(ecs: &ECS, layer: Option<&str>) -> Option<u16> {
+    match layer {
+        Some(CollisionLayers::DEFAULT) => Some(CollisionLayers::DEFAULT_BITS),
+        Some(name) => ecs
+            .resource::<CollisionLayers>()
+            .and_then(|layers| layers.bits(name)),
+        None => Some(0xFFFF),
+    }
+}
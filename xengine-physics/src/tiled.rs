@@ -0,0 +1,459 @@
+//! 从Tiled的JSON地图导入碰撞体
+//!
+//! 支持对象层里的矩形、椭圆、多边形和折线，以及嵌入的图块集里每个图块的碰撞形状。
+//! Tiled的坐标以像素为单位并且y轴向下，导入时会按`pixels_per_meter`缩放并翻转y轴，地图的左下角是原点。
+
+use crate::colliders::ColliderMaterial;
+use crate::{
+    ComponentColliderBox, ComponentColliderChain, ComponentColliderCircle,
+    ComponentColliderPolygon, ComponentRigidBody,
+};
+use serde::Deserialize;
+use xecs::{EntityId, ECS};
+use xengine_basecomponents::ComponentTransform;
+use xmath::{Rotation, Transform, Vector2};
+
+/// xphysics的多边形最多支持的顶点数
+const MAX_POLYGON_VERTICES: usize = 8;
+
+/// Tiled的gid高位是翻转标记
+const GID_MASK: u32 = 0x1FFF_FFFF;
+
+/// 相邻顶点之间的最小距离（米），和xphysics的`linear_slop`相同，更近的顶点会让形状无法创建
+const MIN_VERTEX_DISTANCE: f32 = 0.005;
+
+#[derive(Deserialize)]
+struct TiledMap {
+    height: u32,
+    tileheight: u32,
+    tilewidth: u32,
+    #[serde(default)]
+    layers: Vec<TiledLayer>,
+    #[serde(default)]
+    tilesets: Vec<TiledTileset>,
+}
+
+#[derive(Deserialize)]
+struct TiledLayer {
+    #[serde(rename = "type")]
+    type_: String,
+    #[serde(default = "default_true")]
+    visible: bool,
+    #[serde(default)]
+    width: u32,
+    #[serde(default)]
+    data: Vec<u32>,
+    #[serde(default)]
+    objects: Vec<TiledObject>,
+    #[serde(default)]
+    layers: Vec<TiledLayer>,
+}
+
+#[derive(Deserialize)]
+struct TiledTileset {
+    firstgid: u32,
+    #[serde(default)]
+    tiles: Vec<TiledTile>,
+}
+
+#[derive(Deserialize)]
+struct TiledTile {
+    id: u32,
+    objectgroup: Option<TiledObjectGroup>,
+}
+
+#[derive(Deserialize)]
+struct TiledObjectGroup {
+    #[serde(default)]
+    objects: Vec<TiledObject>,
+}
+
+#[derive(Deserialize)]
+struct TiledObject {
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default)]
+    rotation: f32,
+    #[serde(default)]
+    ellipse: bool,
+    #[serde(default)]
+    point: bool,
+    polygon: Option<Vec<TiledPoint>>,
+    polyline: Option<Vec<TiledPoint>>,
+}
+
+#[derive(Deserialize, Copy, Clone)]
+struct TiledPoint {
+    x: f32,
+    y: f32,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn fixture() -> ColliderMaterial {
+    ColliderMaterial {
+        friction: 0.2,
+        restitution: 0.0,
+        density: 0.0,
+        is_sensor: false,
+        filter: Default::default(),
+    }
+}
+
+/// 去掉和前一个顶点距离太近的顶点，`is_loop`时最后一个顶点也不能和第一个顶点太近
+fn clean_vertices(vertices: &[Vector2<f32>], is_loop: bool) -> Vec<Vector2<f32>> {
+    let min_distance_squared = MIN_VERTEX_DISTANCE * MIN_VERTEX_DISTANCE;
+    let mut cleaned: Vec<Vector2<f32>> = Vec::with_capacity(vertices.len());
+    for v in vertices {
+        match cleaned.last() {
+            Some(last) if last.distance_squared(v) <= min_distance_squared => {}
+            _ => cleaned.push(*v),
+        }
+    }
+    if is_loop
+        && cleaned.len() > 1
+        && cleaned[0].distance_squared(&cleaned[cleaned.len() - 1]) <= min_distance_squared
+    {
+        cleaned.pop();
+    }
+    cleaned
+}
+
+/// 至少有3个顶点并且面积不接近0，共线的顶点无法创建多边形
+fn has_area(vertices: &[Vector2<f32>]) -> bool {
+    let n = vertices.len();
+    let twice_area: f32 = (0..n)
+        .map(|i| {
+            let (a, b) = (vertices[i], vertices[(i + 1) % n]);
+            a.x * b.y - a.y * b.x
+        })
+        .sum();
+    n >= 3 && twice_area.abs() * 0.5 > MIN_VERTEX_DISTANCE * MIN_VERTEX_DISTANCE
+}
+
+fn is_convex(vertices: &[Vector2<f32>]) -> bool {
+    let n = vertices.len();
+    let mut sign = 0.0;
+    for i in 0..n {
+        let e1 = vertices[(i + 1) % n] - vertices[i];
+        let e2 = vertices[(i + 2) % n] - vertices[(i + 1) % n];
+        let cross = e1.x * e2.y - e1.y * e2.x;
+        if cross != 0.0 {
+            if sign * cross < 0.0 {
+                return false;
+            }
+            sign = cross;
+        }
+    }
+    true
+}
+
+/// 把相对对象原点的像素偏移转换到实体的局部坐标
+fn local_point(point: TiledPoint, scale: f32) -> Vector2<f32> {
+    Vector2::new(point.x * scale, -point.y * scale)
+}
+
+struct Loader<'a> {
+    ecs: &'a mut ECS,
+    scale: f32,
+    map_height: f32,
+    entities: Vec<EntityId>,
+}
+
+impl<'a> Loader<'a> {
+    /// 把Tiled的像素坐标转换到世界坐标
+    fn world_point(&self, x: f32, y: f32) -> Vector2<f32> {
+        Vector2::new(x * self.scale, (self.map_height - y) * self.scale)
+    }
+
+    fn spawn_object(&mut self, object: &TiledObject, offset: TiledPoint) {
+        if object.point {
+            return;
+        }
+        let scale = self.scale;
+
+        // Tiled的旋转是绕对象原点顺时针的角度
+        let transform = ComponentTransform(Transform::new(
+            self.world_point(offset.x + object.x, offset.y + object.y),
+            Rotation::new(-object.rotation.to_radians()),
+        ));
+        let builder = self
+            .ecs
+            .create_entity()
+            .component(transform)
+            .component(ComponentRigidBody::default());
+
+        let id = if let Some(points) = &object.polygon {
+            let vertices: Vec<_> = points.iter().map(|p| local_point(*p, scale)).collect();
+            let vertices = clean_vertices(&vertices, true);
+            if !has_area(&vertices) {
+                return;
+            }
+            if vertices.len() <= MAX_POLYGON_VERTICES && is_convex(&vertices) {
+                builder
+                    .component(ComponentColliderPolygon {
+                        fixture: fixture(),
+                        transform: Transform::identity(),
+                        vertices,
+                    })
+                    .finish()
+            } else {
+                builder
+                    .component(ComponentColliderChain {
+                        fixture: fixture(),
+                        transform: Transform::identity(),
+                        is_loop: true,
+                        vertices,
+                    })
+                    .finish()
+            }
+        } else if let Some(points) = &object.polyline {
+            let vertices: Vec<_> = points.iter().map(|p| local_point(*p, scale)).collect();
+            let vertices = clean_vertices(&vertices, false);
+            if vertices.len() < 2 {
+                return;
+            }
+            builder
+                .component(ComponentColliderChain {
+                    fixture: fixture(),
+                    transform: Transform::identity(),
+                    is_loop: false,
+                    vertices,
+                })
+                .finish()
+        } else {
+            if object.width <= 0.0 || object.height <= 0.0 {
+                return;
+            }
+            let half_size = Vector2::new(object.width, object.height) * (0.5 * scale);
+            let center = Vector2::new(half_size.x, -half_size.y);
+            if object.ellipse && object.width == object.height {
+                builder
+                    .component(ComponentColliderCircle {
+                        fixture: fixture(),
+                        transform: Transform::new(center, Rotation::new(0.0)),
+                        radius: half_size.x,
+                    })
+                    .finish()
+            } else if object.ellipse {
+                let vertices = (0..MAX_POLYGON_VERTICES)
+                    .map(|i| {
+                        let angle =
+                            i as f32 / MAX_POLYGON_VERTICES as f32 * 2.0 * std::f32::consts::PI;
+                        center + Vector2::new(angle.cos() * half_size.x, angle.sin() * half_size.y)
+                    })
+                    .collect::<Vec<_>>();
+                let vertices = clean_vertices(&vertices, true);
+                if !has_area(&vertices) {
+                    return;
+                }
+                builder
+                    .component(ComponentColliderPolygon {
+                        fixture: fixture(),
+                        transform: Transform::identity(),
+                        vertices,
+                    })
+                    .finish()
+            } else {
+                builder
+                    .component(ComponentColliderBox {
+                        fixture: fixture(),
+                        transform: Transform::new(center, Rotation::new(0.0)),
+                        half_size,
+                    })
+                    .finish()
+            }
+        };
+        self.entities.push(id);
+    }
+
+    fn load_layers(&mut self, map: &TiledMap, layers: &[TiledLayer]) {
+        for layer in layers {
+            if !layer.visible {
+                continue;
+            }
+            match layer.type_.as_str() {
+                "objectgroup" => {
+                    for object in &layer.objects {
+                        self.spawn_object(object, TiledPoint { x: 0.0, y: 0.0 });
+                    }
+                }
+                "tilelayer" => self.load_tiles(map, layer),
+                "group" => self.load_layers(map, &layer.layers),
+                _ => {}
+            }
+        }
+    }
+
+    fn load_tiles(&mut self, map: &TiledMap, layer: &TiledLayer) {
+        if layer.width == 0 {
+            return;
+        }
+        for (index, gid) in layer.data.iter().enumerate() {
+            let gid = gid & GID_MASK;
+            if gid == 0 {
+                continue;
+            }
+            let tileset = match map
+                .tilesets
+                .iter()
+                .filter(|tileset| tileset.firstgid <= gid)
+                .max_by_key(|tileset| tileset.firstgid)
+            {
+                Some(tileset) => tileset,
+                None => continue,
+            };
+            let objects = match tileset
+                .tiles
+                .iter()
+                .find(|tile| tile.id == gid - tileset.firstgid)
+                .and_then(|tile| tile.objectgroup.as_ref())
+            {
+                Some(group) => &group.objects,
+                None => continue,
+            };
+            let offset = TiledPoint {
+                x: (index as u32 % layer.width * map.tilewidth) as f32,
+                y: (index as u32 / layer.width * map.tileheight) as f32,
+            };
+            for object in objects {
+                self.spawn_object(object, offset);
+            }
+        }
+    }
+}
+
+/// 读取Tiled的JSON地图，为每个碰撞形状创建一个带有静态刚体的实体，返回创建的实体
+///
+/// 只支持嵌入的图块集，外部的图块集会被忽略
+pub fn load_tiled_map(
+    ecs: &mut ECS,
+    json: &str,
+    pixels_per_meter: f32,
+) -> serde_json::Result<Vec<EntityId>> {
+    let map: TiledMap = serde_json::from_str(json)?;
+    let mut loader = Loader {
+        ecs,
+        scale: 1.0 / pixels_per_meter,
+        map_height: (map.height * map.tileheight) as f32,
+        entities: Vec::new(),
+    };
+    loader.load_layers(&map, &map.layers);
+    Ok(loader.entities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SystemPhysics;
+
+    const MAP: &str = r#"{
+        "width": 2,
+        "height": 2,
+        "tilewidth": 32,
+        "tileheight": 32,
+        "layers": [
+            {
+                "type": "tilelayer",
+                "width": 2,
+                "height": 2,
+                "data": [0, 0, 0, 1]
+            },
+            {
+                "type": "objectgroup",
+                "objects": [
+                    { "x": 0, "y": 32, "width": 64, "height": 16 },
+                    { "x": 0, "y": 0, "point": true },
+                    { "x": 0, "y": 0, "polyline": [{ "x": 0, "y": 0 }, { "x": 32, "y": 32 }] }
+                ]
+            }
+        ],
+        "tilesets": [
+            {
+                "firstgid": 1,
+                "tiles": [
+                    {
+                        "id": 0,
+                        "objectgroup": {
+                            "objects": [{ "x": 0, "y": 0, "width": 32, "height": 32 }]
+                        }
+                    }
+                ]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_load_tiled_map() {
+        let mut ecs = ECS::new();
+        let entities = load_tiled_map(&mut ecs, MAP, 32.0).unwrap();
+        assert_eq!(entities.len(), 3);
+
+        let tile = ecs.entity(entities[0]).unwrap();
+        assert_eq!(
+            tile.get::<ComponentTransform>().unwrap().0.p,
+            Vector2::new(1.0, 1.0)
+        );
+        let collider = tile.get::<ComponentColliderBox>().unwrap();
+        assert_eq!(collider.transform.p, Vector2::new(0.5, -0.5));
+        assert_eq!(collider.half_size, Vector2::new(0.5, 0.5));
+
+        let platform = ecs.entity(entities[1]).unwrap();
+        assert_eq!(
+            platform.get::<ComponentTransform>().unwrap().0.p,
+            Vector2::new(0.0, 1.0)
+        );
+        assert_eq!(
+            platform.get::<ComponentColliderBox>().unwrap().half_size,
+            Vector2::new(1.0, 0.25)
+        );
+
+        let line = ecs.entity(entities[2]).unwrap();
+        let chain = line.get::<ComponentColliderChain>().unwrap();
+        assert!(!chain.is_loop);
+        assert_eq!(
+            chain.vertices,
+            vec![Vector2::zero(), Vector2::new(1.0, -1.0)]
+        );
+    }
+
+    #[test]
+    fn test_degenerate_shapes() {
+        let map = r#"{
+            "width": 2,
+            "height": 2,
+            "tilewidth": 32,
+            "tileheight": 32,
+            "layers": [
+                {
+                    "type": "objectgroup",
+                    "objects": [
+                        { "x": 0, "y": 0, "polygon": [{ "x": 0, "y": 0 }, { "x": 32, "y": 0 }, { "x": 64, "y": 0 }] },
+                        { "x": 0, "y": 0, "polygon": [{ "x": 0, "y": 0 }, { "x": 0, "y": 0 }, { "x": 32, "y": 0 }] },
+                        { "x": 0, "y": 0, "polyline": [{ "x": 0, "y": 0 }, { "x": 0, "y": 0 }] },
+                        { "x": 0, "y": 0, "polyline": [{ "x": 0, "y": 0 }, { "x": 0, "y": 0 }, { "x": 32, "y": 0 }] },
+                        { "x": 0, "y": 0, "width": 0.001, "height": 0.002, "ellipse": true }
+                    ]
+                }
+            ]
+        }"#;
+        let mut ecs = ECS::new();
+        ecs.add_system(0, SystemPhysics::new(Default::default()));
+        let entities = load_tiled_map(&mut ecs, map, 32.0).unwrap();
+        assert_eq!(entities.len(), 1);
+        let chain = ecs.entity(entities[0]).unwrap();
+        assert_eq!(
+            chain.get::<ComponentColliderChain>().unwrap().vertices,
+            vec![Vector2::zero(), Vector2::new(1.0, 0.0)]
+        );
+
+        // 创建刚体时不会panic
+        ecs.update(std::time::Duration::from_secs(1) / 60);
+    }
+}
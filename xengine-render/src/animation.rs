@@ -0,0 +1,184 @@
+use crate::{ComponentSprite, TextureHandle};
+use std::time::Duration;
+use xecs::{Component, EntityId, System, With, Without, ECS};
+use xengine_basecomponents::ComponentDisabled;
+
+/// 逐帧动画，由`SystemSpriteAnimation`把当前帧的纹理设置到同一个实体的`ComponentSprite`
+#[derive(Debug, Clone)]
+pub struct ComponentSpriteAnimation {
+    /// 按顺序播放的帧
+    pub frames: Vec<TextureHandle>,
+
+    /// 每秒播放的帧数
+    pub fps: f32,
+
+    /// 播放到最后一帧以后从头开始
+    pub looping: bool,
+
+    /// 为`false`时停在当前帧
+    pub playing: bool,
+
+    /// 已经播放的时间（秒）
+    pub time: f32,
+}
+
+impl ComponentSpriteAnimation {
+    pub fn new(frames: Vec<TextureHandle>, fps: f32) -> ComponentSpriteAnimation {
+        ComponentSpriteAnimation {
+            frames,
+            fps,
+            looping: true,
+            playing: true,
+            time: 0.0,
+        }
+    }
+
+    pub fn with_looping(self, looping: bool) -> ComponentSpriteAnimation {
+        ComponentSpriteAnimation { looping, ..self }
+    }
+
+    /// 播放一遍需要的时间（秒）
+    pub fn duration(&self) -> f32 {
+        if self.fps > 0.0 {
+            self.frames.len() as f32 / self.fps
+        } else {
+            0.0
+        }
+    }
+
+    /// 当前帧的下标，没有帧时返回`None`
+    pub fn current_frame(&self) -> Option<usize> {
+        let count = self.frames.len();
+        if count == 0 {
+            return None;
+        }
+        let frame = (self.time * self.fps).max(0.0) as usize;
+        Some(if self.looping {
+            frame % count
+        } else {
+            frame.min(count - 1)
+        })
+    }
+
+    /// 不循环的动画播放到了最后
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.time >= self.duration()
+    }
+
+    /// 从第一帧重新播放
+    pub fn restart(&mut self) {
+        self.time = 0.0;
+        self.playing = true;
+    }
+}
+
+impl Component for ComponentSpriteAnimation {
+    fn name() -> &'static str {
+        "SpriteAnimation"
+    }
+}
+
+/// 不循环的动画播放结束，通过`Event::Custom`发送
+#[derive(Debug, Copy, Clone)]
+pub struct SpriteAnimationFinished(pub EntityId);
+
+/// 推进`ComponentSpriteAnimation`并更新精灵的纹理，需要在`SystemSprites`之前执行
+#[derive(Default)]
+pub struct SystemSpriteAnimation;
+
+impl System for SystemSpriteAnimation {
+    fn update(&mut self, ecs: &mut ECS, delta: Duration) {
+        let ids = ecs
+            .query::<(
+                With<ComponentSpriteAnimation>,
+                With<ComponentSprite>,
+                Without<ComponentDisabled>,
+            )>()
+            .map(|entity| entity.id())
+            .collect::<Vec<_>>();
+
+        for id in ids {
+            let mut entity = ecs.entity_mut(id).unwrap();
+            let (texture, finished) = {
+                let animation = entity.get_mut::<ComponentSpriteAnimation>().unwrap();
+                let mut finished = false;
+                if animation.playing && !animation.is_finished() {
+                    animation.time += delta.as_secs_f32();
+                    if animation.is_finished() {
+                        animation.playing = false;
+                        finished = true;
+                    }
+                }
+                (
+                    animation
+                        .current_frame()
+                        .map(|frame| animation.frames[frame]),
+                    finished,
+                )
+            };
+            if let Some(texture) = texture {
+                let sprite = entity.get_mut::<ComponentSprite>().unwrap();
+                if sprite.texture != Some(texture) {
+                    sprite.texture = Some(texture);
+                }
+            }
+            if finished {
+                ecs.send_event(SpriteAnimationFinished(id));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xecs::Event;
+
+    #[derive(Default)]
+    struct Finished(Vec<EntityId>);
+
+    impl System for Finished {
+        fn handle_event(&mut self, _ecs: &mut ECS, event: &Event) {
+            if let Some(event) = event.downcast_ref::<SpriteAnimationFinished>() {
+                self.0.push(event.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sprite_animation() {
+        let mut ecs = ECS::new();
+        ecs.add_system(0, SystemSpriteAnimation);
+        ecs.add_system(1, Finished::default());
+        let frames = (0..3).map(TextureHandle::from_index).collect::<Vec<_>>();
+        let looping = ecs
+            .create_entity()
+            .component(ComponentSprite::default())
+            .component(ComponentSpriteAnimation::new(frames.clone(), 10.0))
+            .finish();
+        let once = ecs
+            .create_entity()
+            .component(ComponentSprite::default())
+            .component(ComponentSpriteAnimation::new(frames.clone(), 10.0).with_looping(false))
+            .finish();
+        let texture = |ecs: &ECS, id| {
+            ecs.entity(id)
+                .unwrap()
+                .get::<ComponentSprite>()
+                .unwrap()
+                .texture
+        };
+
+        ecs.update(Duration::from_millis(150));
+        assert_eq!(texture(&ecs, looping), Some(frames[1]));
+        assert_eq!(texture(&ecs, once), Some(frames[1]));
+
+        ecs.update(Duration::from_millis(200));
+        assert_eq!(texture(&ecs, looping), Some(frames[0]));
+        assert_eq!(texture(&ecs, once), Some(frames[2]));
+        assert_eq!(ecs.system::<Finished>().unwrap().0, vec![once]);
+
+        ecs.update(Duration::from_millis(100));
+        assert_eq!(ecs.system::<Finished>().unwrap().0, vec![once]);
+    }
+}
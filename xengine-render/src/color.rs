@@ -0,0 +1,49 @@
+use xmath::Lerp;
+
+/// RGBA颜色，每个分量的范围是0到1
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const WHITE: Color = Color::rgb(1.0, 1.0, 1.0);
+    pub const BLACK: Color = Color::rgb(0.0, 0.0, 0.0);
+    pub const RED: Color = Color::rgb(1.0, 0.0, 0.0);
+    pub const GREEN: Color = Color::rgb(0.0, 1.0, 0.0);
+    pub const BLUE: Color = Color::rgb(0.0, 0.0, 1.0);
+
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Color {
+        Color { r, g, b, a: 1.0 }
+    }
+
+    pub const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Color {
+        Color { r, g, b, a }
+    }
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Color::WHITE
+    }
+}
+
+impl From<xphysics::Color> for Color {
+    fn from(color: xphysics::Color) -> Self {
+        Color::rgba(color.r, color.g, color.b, color.a)
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        Color::rgba(
+            self.r.lerp(&to.r, t),
+            self.g.lerp(&to.g, t),
+            self.b.lerp(&to.b, t),
+            self.a.lerp(&to.a, t),
+        )
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{Color, DrawList, SystemSprites};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use xecs::{System, ECS};
+use xengine_physics::{DebugDraw, DebugDrawFlags, SystemPhysics};
+use xmath::{Multiply, Transform, Vector2};
+
+/// 绘制圆时使用的线段数
+const CIRCLE_SEGMENTS: usize = 16;
+
+/// 绘制点时使用的半宽
+const POINT_SIZE: f32 = 0.05;
+
+/// 绘制坐标轴时使用的长度
+const AXIS_LENGTH: f32 = 0.4;
+
+fn fill_color(color: Color) -> Color {
+    Color::rgba(color.r * 0.5, color.g * 0.5, color.b * 0.5, 0.5)
+}
+
+struct Collector(Rc<RefCell<DrawList>>);
+
+impl Collector {
+    fn polygon(&self, vertices: &[Vector2<f32>], color: Color) {
+        let mut list = self.0.borrow_mut();
+        for i in 0..vertices.len() {
+            list.push_line(vertices[i], vertices[(i + 1) % vertices.len()], color);
+        }
+    }
+
+    fn solid_polygon(&self, vertices: &[Vector2<f32>], color: Color) {
+        {
+            let mut list = self.0.borrow_mut();
+            for i in 1..vertices.len().saturating_sub(1) {
+                list.push_triangle(vertices[0], vertices[i], vertices[i + 1], fill_color(color));
+            }
+        }
+        self.polygon(vertices, color);
+    }
+
+    fn circle_vertices(center: Vector2<f32>, radius: f32) -> Vec<Vector2<f32>> {
+        (0..CIRCLE_SEGMENTS)
+            .map(|i| {
+                let angle = i as f32 / CIRCLE_SEGMENTS as f32 * 2.0 * std::f32::consts::PI;
+                center + Vector2::new(angle.cos(), angle.sin()) * radius
+            })
+            .collect()
+    }
+}
+
+impl DebugDraw for Collector {
+    fn draw_polygon(&mut self, vertices: &[Vector2<f32>], color: xphysics::Color) {
+        self.polygon(vertices, color.into());
+    }
+
+    fn draw_solid_polygon(&mut self, vertices: &[Vector2<f32>], color: xphysics::Color) {
+        self.solid_polygon(vertices, color.into());
+    }
+
+    fn draw_circle(&mut self, center: &Vector2<f32>, radius: f32, color: xphysics::Color) {
+        self.polygon(&Self::circle_vertices(*center, radius), color.into());
+    }
+
+    fn draw_solid_circle(
+        &mut self,
+        center: &Vector2<f32>,
+        radius: f32,
+        axis: &Vector2<f32>,
+        color: xphysics::Color,
+    ) {
+        let color = color.into();
+        self.solid_polygon(&Self::circle_vertices(*center, radius), color);
+        self.0
+            .borrow_mut()
+            .push_line(*center, *center + *axis * radius, color);
+    }
+
+    fn draw_segment(&mut self, p1: &Vector2<f32>, p2: &Vector2<f32>, color: xphysics::Color) {
+        self.0.borrow_mut().push_line(*p1, *p2, color.into());
+    }
+
+    fn draw_transform(&mut self, xf: &Transform<f32>) {
+        let mut list = self.0.borrow_mut();
+        list.push_line(
+            xf.p,
+            xf.multiply(Vector2::new(AXIS_LENGTH, 0.0)),
+            Color::RED,
+        );
+        list.push_line(
+            xf.p,
+            xf.multiply(Vector2::new(0.0, AXIS_LENGTH)),
+            Color::GREEN,
+        );
+    }
+
+    fn draw_point(&mut self, p: &Vector2<f32>, color: xphysics::Color) {
+        let d = POINT_SIZE;
+        self.solid_polygon(
+            &[
+                *p + Vector2::new(-d, -d),
+                *p + Vector2::new(d, -d),
+                *p + Vector2::new(d, d),
+                *p + Vector2::new(-d, d),
+            ],
+            color.into(),
+        );
+    }
+}
+
+/// 把物理世界的调试图形添加到`DrawList`资源，和`SystemSprites`一起使用时需要在它之后执行
+pub struct SystemPhysicsDebugDraw {
+    flags: DebugDrawFlags,
+    shapes: Rc<RefCell<DrawList>>,
+    installed: bool,
+}
+
+impl SystemPhysicsDebugDraw {
+    pub fn new(flags: DebugDrawFlags) -> SystemPhysicsDebugDraw {
+        SystemPhysicsDebugDraw {
+            flags,
+            shapes: Default::default(),
+            installed: false,
+        }
+    }
+
+    pub fn flags(&self) -> DebugDrawFlags {
+        self.flags
+    }
+
+    /// 下一帧生效
+    pub fn set_flags(&mut self, flags: DebugDrawFlags) {
+        self.flags = flags;
+        self.installed = false;
+    }
+}
+
+impl Default for SystemPhysicsDebugDraw {
+    fn default() -> Self {
+        SystemPhysicsDebugDraw::new(DebugDrawFlags::SHAPE)
+    }
+}
+
+impl System for SystemPhysicsDebugDraw {
+    fn update(&mut self, ecs: &mut ECS, _delta: Duration) {
+        let physics = match ecs.system_mut::<SystemPhysics>() {
+            Some(physics) => physics,
+            None => return,
+        };
+        if !self.installed {
+            physics.set_debug_draw(Collector(self.shapes.clone()), self.flags);
+            self.installed = true;
+        }
+        physics.draw_debug_data();
+
+        let shapes = std::mem::take(&mut *self.shapes.borrow_mut());
+        let mut draw_list = ecs.remove_resource::<DrawList>().unwrap_or_default();
+        if ecs.system::<SystemSprites>().is_none() {
+            // 没有SystemSprites每帧重新生成时自己清空
+            draw_list.clear();
+        }
+        draw_list.triangles.extend(shapes.triangles);
+        draw_list.lines.extend(shapes.lines);
+        ecs.insert_resource(draw_list);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xengine_basecomponents::ComponentTransform;
+    use xengine_physics::{ColliderMaterial, ComponentColliderBox, ComponentRigidBody};
+    use xmath::Rotation;
+
+    #[test]
+    fn test_physics_debug_draw() {
+        let mut ecs = ECS::new();
+        ecs.add_system(0, SystemPhysics::default());
+        ecs.add_system(1, SystemPhysicsDebugDraw::default());
+        ecs.create_entity()
+            .component(ComponentTransform(Transform::new(
+                (0.0, 0.0).into(),
+                Rotation::new(0.0),
+            )))
+            .component(ComponentRigidBody::default())
+            .component(ComponentColliderBox {
+                fixture: ColliderMaterial {
+                    friction: 0.2,
+                    restitution: 0.0,
+                    density: 1.0,
+                    is_sensor: false,
+                    filter: Default::default(),
+                },
+                transform: Transform::identity(),
+                half_size: (1.0, 0.5).into(),
+            })
+            .finish();
+
+        // 刚体在第一帧的事件里创建，第二帧开始才能绘制，绘制的内容不会在帧之间累积
+        ecs.update(Duration::from_secs(1) / 60);
+        for _ in 0..2 {
+            ecs.update(Duration::from_secs(1) / 60);
+            let draw_list = ecs.resource::<DrawList>().unwrap();
+            assert_eq!(draw_list.triangles.len(), 6);
+            assert_eq!(draw_list.lines.len(), 8);
+            assert_eq!(draw_list.lines[0].position, [-1.0, -0.5]);
+        }
+    }
+}
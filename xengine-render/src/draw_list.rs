@@ -0,0 +1,126 @@
+use crate::{Color, TextureHandle};
+use xmath::{Multiply, Transform, Vector2};
+
+/// 顶点的内存布局和着色器的输入一致
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Vertex {
+    pub position: [f32; 2],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl Vertex {
+    pub fn new(position: Vector2<f32>, uv: Vector2<f32>, color: Color) -> Vertex {
+        Vertex {
+            position: [position.x, position.y],
+            uv: [uv.x, uv.y],
+            color: [color.r, color.g, color.b, color.a],
+        }
+    }
+}
+
+/// 使用同一个纹理的一批三角形，`texture`为`None`时使用白色纹理
+#[derive(Debug, Clone)]
+pub struct SpriteBatch {
+    pub texture: Option<TextureHandle>,
+    pub vertices: Vec<Vertex>,
+}
+
+/// 一帧要绘制的所有图形，作为ECS资源使用
+///
+/// 先按顺序绘制`batches`，然后是没有纹理的`triangles`和`lines`，坐标都是世界坐标，
+/// 最后在所有图形上面绘制`overlay`
+#[derive(Debug, Default, Clone)]
+pub struct DrawList {
+    pub batches: Vec<SpriteBatch>,
+    pub triangles: Vec<Vertex>,
+    pub lines: Vec<Vertex>,
+
+    /// 屏幕坐标的三角形，单位是像素，原点在左上角，y轴向下
+    pub overlay: Vec<Vertex>,
+}
+
+impl DrawList {
+    /// 添加一个以`transform`为中心的矩形，和上一个批次的纹理相同时合并到同一个批次
+    pub fn push_sprite(
+        &mut self,
+        texture: Option<TextureHandle>,
+        transform: &Transform<f32>,
+        size: Vector2<f32>,
+        color: Color,
+    ) {
+        let batch = match self.batches.last_mut() {
+            Some(batch) if batch.texture == texture => batch,
+            _ => {
+                self.batches.push(SpriteBatch {
+                    texture,
+                    vertices: Vec::new(),
+                });
+                self.batches.last_mut().unwrap()
+            }
+        };
+
+        let half = size * 0.5;
+        let corner = |x: f32, y: f32, u: f32, v: f32| {
+            Vertex::new(
+                transform.multiply(Vector2::new(half.x * x, half.y * y)),
+                Vector2::new(u, v),
+                color,
+            )
+        };
+        let bottom_left = corner(-1.0, -1.0, 0.0, 1.0);
+        let bottom_right = corner(1.0, -1.0, 1.0, 1.0);
+        let top_right = corner(1.0, 1.0, 1.0, 0.0);
+        let top_left = corner(-1.0, 1.0, 0.0, 0.0);
+        batch.vertices.extend_from_slice(&[
+            bottom_left,
+            bottom_right,
+            top_right,
+            bottom_left,
+            top_right,
+            top_left,
+        ]);
+    }
+
+    pub fn push_triangle(
+        &mut self,
+        a: Vector2<f32>,
+        b: Vector2<f32>,
+        c: Vector2<f32>,
+        color: Color,
+    ) {
+        for p in &[a, b, c] {
+            self.triangles.push(Vertex::new(*p, Vector2::zero(), color));
+        }
+    }
+
+    pub fn push_line(&mut self, a: Vector2<f32>, b: Vector2<f32>, color: Color) {
+        self.lines.push(Vertex::new(a, Vector2::zero(), color));
+        self.lines.push(Vertex::new(b, Vector2::zero(), color));
+    }
+
+    /// 在屏幕上添加一个矩形，`min`是左上角，`max`是右下角
+    pub fn push_overlay_rect(&mut self, min: Vector2<f32>, max: Vector2<f32>, color: Color) {
+        let corner = |x: f32, y: f32| Vertex::new(Vector2::new(x, y), Vector2::zero(), color);
+        let top_left = corner(min.x, min.y);
+        let top_right = corner(max.x, min.y);
+        let bottom_right = corner(max.x, max.y);
+        let bottom_left = corner(min.x, max.y);
+        self.overlay.extend_from_slice(&[
+            top_left,
+            top_right,
+            bottom_right,
+            top_left,
+            bottom_right,
+            bottom_left,
+        ]);
+    }
+
+    pub fn clear(&mut self) {
+        self.batches.clear();
+        self.triangles.clear();
+        self.lines.clear();
+        self.overlay.clear();
+    }
+}
@@ -0,0 +1,314 @@
+use crate::{DrawList, SpriteBatch, TextureHandle, Textures, Vertex};
+use gl::types::{GLchar, GLenum, GLint, GLsizei, GLsizeiptr, GLuint};
+use std::collections::HashMap;
+use std::ffi::CString;
+use xecs::ECS;
+use xengine_basecomponents::CameraView;
+use xmath::{Transform, Vector2};
+
+/// 没有`CameraView`资源时每个世界单位对应的像素数
+const DEFAULT_ZOOM: f32 = 32.0;
+
+const VERTEX_SHADER: &str = r#"
+#version 150
+uniform mat3 u_view;
+in vec2 a_position;
+in vec2 a_uv;
+in vec4 a_color;
+out vec2 v_uv;
+out vec4 v_color;
+void main() {
+    v_uv = a_uv;
+    v_color = a_color;
+    gl_Position = vec4((u_view * vec3(a_position, 1.0)).xy, 0.0, 1.0);
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"
+#version 150
+uniform sampler2D u_texture;
+in vec2 v_uv;
+in vec4 v_color;
+out vec4 o_color;
+void main() {
+    o_color = texture(u_texture, v_uv) * v_color;
+}
+"#;
+
+unsafe fn compile_shader(kind: GLenum, source: &str) -> GLuint {
+    let shader = gl::CreateShader(kind);
+    let source = CString::new(source).unwrap();
+    gl::ShaderSource(shader, 1, &source.as_ptr(), std::ptr::null());
+    gl::CompileShader(shader);
+
+    let mut status = 0;
+    gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut status);
+    if status == 0 {
+        let mut len = 0;
+        gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
+        let mut log = vec![0u8; len as usize];
+        gl::GetShaderInfoLog(
+            shader,
+            len,
+            std::ptr::null_mut(),
+            log.as_mut_ptr() as *mut GLchar,
+        );
+        panic!(
+            "failed to compile shader: {}",
+            String::from_utf8_lossy(&log)
+        );
+    }
+    shader
+}
+
+unsafe fn create_texture(width: u32, height: u32, pixels: &[u8]) -> GLuint {
+    let mut texture = 0;
+    gl::GenTextures(1, &mut texture);
+    gl::BindTexture(gl::TEXTURE_2D, texture);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+    gl::TexParameteri(
+        gl::TEXTURE_2D,
+        gl::TEXTURE_WRAP_S,
+        gl::CLAMP_TO_EDGE as GLint,
+    );
+    gl::TexParameteri(
+        gl::TEXTURE_2D,
+        gl::TEXTURE_WRAP_T,
+        gl::CLAMP_TO_EDGE as GLint,
+    );
+    gl::TexImage2D(
+        gl::TEXTURE_2D,
+        0,
+        gl::RGBA as GLint,
+        width as GLsizei,
+        height as GLsizei,
+        0,
+        gl::RGBA,
+        gl::UNSIGNED_BYTE,
+        pixels.as_ptr() as *const _,
+    );
+    texture
+}
+
+/// 世界坐标到裁剪坐标的矩阵，按列存储
+fn view_matrix(transform: &Transform<f32>, zoom: f32, viewport: Vector2<f32>) -> [f32; 9] {
+    let sx = 2.0 * zoom / viewport.x;
+    let sy = 2.0 * zoom / viewport.y;
+    let (c, s) = (transform.q.c, transform.q.s);
+    let p = transform.p;
+    // 先平移到相机的位置，再旋转相机角度的逆，最后缩放
+    let tx = -(c * p.x + s * p.y);
+    let ty = -(-s * p.x + c * p.y);
+    [
+        c * sx,
+        -s * sy,
+        0.0,
+        s * sx,
+        c * sy,
+        0.0,
+        tx * sx,
+        ty * sy,
+        1.0,
+    ]
+}
+
+/// 屏幕像素坐标到裁剪坐标的矩阵，原点在左上角
+fn overlay_matrix(viewport: Vector2<f32>) -> [f32; 9] {
+    [
+        2.0 / viewport.x,
+        0.0,
+        0.0,
+        0.0,
+        -2.0 / viewport.y,
+        0.0,
+        -1.0,
+        1.0,
+        1.0,
+    ]
+}
+
+/// 使用OpenGL绘制`DrawList`资源，必须在创建它的OpenGL上下文里使用
+pub struct GlRenderer {
+    program: GLuint,
+    vao: GLuint,
+    vbo: GLuint,
+    u_view: GLint,
+    u_texture: GLint,
+    white: GLuint,
+    textures: HashMap<TextureHandle, GLuint>,
+}
+
+impl GlRenderer {
+    /// 调用之前需要先通过`gl::load_with`加载OpenGL函数
+    pub fn new() -> GlRenderer {
+        unsafe {
+            let vs = compile_shader(gl::VERTEX_SHADER, VERTEX_SHADER);
+            let fs = compile_shader(gl::FRAGMENT_SHADER, FRAGMENT_SHADER);
+            let program = gl::CreateProgram();
+            gl::AttachShader(program, vs);
+            gl::AttachShader(program, fs);
+            for (index, name) in ["a_position", "a_uv", "a_color"].iter().enumerate() {
+                let name = CString::new(*name).unwrap();
+                gl::BindAttribLocation(program, index as GLuint, name.as_ptr());
+            }
+            let name = CString::new("o_color").unwrap();
+            gl::BindFragDataLocation(program, 0, name.as_ptr());
+            gl::LinkProgram(program);
+            gl::DeleteShader(vs);
+            gl::DeleteShader(fs);
+
+            let u_view = gl::GetUniformLocation(program, b"u_view\0".as_ptr() as *const GLchar);
+            let u_texture =
+                gl::GetUniformLocation(program, b"u_texture\0".as_ptr() as *const GLchar);
+
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+            let mut vbo = 0;
+            gl::GenBuffers(1, &mut vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+            let stride = std::mem::size_of::<Vertex>() as GLsizei;
+            let attributes = [(0, 2, 0), (1, 2, 8), (2, 4, 16)];
+            for (index, size, offset) in attributes.iter() {
+                gl::EnableVertexAttribArray(*index);
+                gl::VertexAttribPointer(
+                    *index,
+                    *size,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    stride,
+                    *offset as *const _,
+                );
+            }
+            gl::BindVertexArray(0);
+
+            GlRenderer {
+                program,
+                vao,
+                vbo,
+                u_view,
+                u_texture,
+                white: create_texture(1, 1, &[255; 4]),
+                textures: HashMap::new(),
+            }
+        }
+    }
+
+    fn texture(&mut self, textures: Option<&Textures>, handle: Option<TextureHandle>) -> GLuint {
+        let handle = match handle {
+            Some(handle) => handle,
+            None => return self.white,
+        };
+        if let Some(texture) = self.textures.get(&handle) {
+            return *texture;
+        }
+        match textures.and_then(|textures| textures.get(handle)) {
+            Some(texture) => {
+                let id = unsafe { create_texture(texture.width, texture.height, &texture.pixels) };
+                self.textures.insert(handle, id);
+                id
+            }
+            None => self.white,
+        }
+    }
+
+    unsafe fn draw(&self, mode: GLenum, texture: GLuint, vertices: &[Vertex]) {
+        if vertices.is_empty() {
+            return;
+        }
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+        gl::BufferData(
+            gl::ARRAY_BUFFER,
+            (vertices.len() * std::mem::size_of::<Vertex>()) as GLsizeiptr,
+            vertices.as_ptr() as *const _,
+            gl::STREAM_DRAW,
+        );
+        gl::DrawArrays(mode, 0, vertices.len() as GLsizei);
+    }
+
+    /// 绘制`DrawList`资源，`viewport`是帧缓冲的像素大小
+    ///
+    /// 有`CameraView`资源时使用相机的视图，否则原点在屏幕中心，`overlay`总是使用`viewport`的像素坐标
+    pub fn render(&mut self, ecs: &ECS, viewport: Vector2<f32>) {
+        let draw_list = match ecs.resource::<DrawList>() {
+            Some(draw_list) => draw_list,
+            None => return,
+        };
+        let view = match ecs.resource::<CameraView>() {
+            Some(view) => view_matrix(&view.transform, view.zoom, view.viewport),
+            None => view_matrix(&Transform::identity(), DEFAULT_ZOOM, viewport),
+        };
+
+        let textures = ecs.resource::<Textures>();
+        let batches = draw_list
+            .batches
+            .iter()
+            .map(|SpriteBatch { texture, vertices }| (self.texture(textures, *texture), vertices))
+            .collect::<Vec<_>>();
+
+        unsafe {
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl::UseProgram(self.program);
+            gl::UniformMatrix3fv(self.u_view, 1, gl::FALSE, view.as_ptr());
+            gl::Uniform1i(self.u_texture, 0);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindVertexArray(self.vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+
+            for (texture, vertices) in batches {
+                self.draw(gl::TRIANGLES, texture, vertices);
+            }
+            self.draw(gl::TRIANGLES, self.white, &draw_list.triangles);
+            self.draw(gl::LINES, self.white, &draw_list.lines);
+
+            let overlay = overlay_matrix(viewport);
+            gl::UniformMatrix3fv(self.u_view, 1, gl::FALSE, overlay.as_ptr());
+            self.draw(gl::TRIANGLES, self.white, &draw_list.overlay);
+
+            gl::BindVertexArray(0);
+            gl::UseProgram(0);
+        }
+    }
+}
+
+impl Drop for GlRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            for texture in self.textures.values() {
+                gl::DeleteTextures(1, texture);
+            }
+            gl::DeleteTextures(1, &self.white);
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+            gl::DeleteProgram(self.program);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xmath::{Multiply, Rotation};
+
+    #[test]
+    fn test_view_matrix() {
+        let transform = Transform::new((3.0, 4.0).into(), Rotation::new(0.5));
+        let m = view_matrix(&transform, 10.0, (200.0, 100.0).into());
+        let project = |p: Vector2<f32>| {
+            Vector2::new(
+                m[0] * p.x + m[3] * p.y + m[6],
+                m[1] * p.x + m[4] * p.y + m[7],
+            )
+        };
+
+        let p = project(transform.p);
+        assert!(p.x.abs() < 1e-5 && p.y.abs() < 1e-5);
+
+        // 相机局部坐标系里(10, 5)在视口的右上角
+        let p = project(transform.multiply(Vector2::new(10.0, 5.0)));
+        assert!((p.x - 1.0).abs() < 1e-5 && (p.y - 1.0).abs() < 1e-5);
+    }
+}
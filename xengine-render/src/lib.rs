@@ -0,0 +1,23 @@
+//! 2D渲染，包括精灵、精灵动画、粒子、批处理、物理调试绘制和调试文字
+//!
+//! 系统只负责生成`DrawList`资源，`GlRenderer`把它绘制出来，需要在OpenGL上下文里使用。
+
+mod animation;
+mod color;
+mod debug_draw;
+mod draw_list;
+mod gl_renderer;
+mod particles;
+mod sprite;
+mod text;
+mod texture;
+
+pub use animation::{ComponentSpriteAnimation, SpriteAnimationFinished, SystemSpriteAnimation};
+pub use color::Color;
+pub use debug_draw::SystemPhysicsDebugDraw;
+pub use draw_list::{DrawList, SpriteBatch, Vertex};
+pub use gl_renderer::GlRenderer;
+pub use particles::{ComponentParticleEmitter, Particle, SystemParticles};
+pub use sprite::{ComponentSprite, SystemSprites};
+pub use text::text_size;
+pub use texture::{Texture, TextureHandle, Textures};
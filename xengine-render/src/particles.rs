@@ -0,0 +1,341 @@
+use crate::{Color, ComponentSprite, TextureHandle};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::Duration;
+use xecs::{Component, System, With, Without, ECS};
+use xengine_basecomponents::{ComponentDisabled, ComponentTransform};
+use xengine_physics::SystemPhysics;
+use xmath::{Curve, DotTrait, Rotation, Transform, Vector2};
+
+/// 碰撞后离开表面的距离，避免下一帧的射线从表面内部开始
+const COLLISION_OFFSET: f32 = 0.001;
+
+/// 一个粒子，坐标是世界坐标
+#[derive(Debug, Copy, Clone)]
+pub struct Particle {
+    pub position: Vector2<f32>,
+    pub velocity: Vector2<f32>,
+
+    /// 已经存在的时间（秒）
+    pub age: f32,
+
+    /// 总的存活时间（秒）
+    pub lifetime: f32,
+}
+
+impl Particle {
+    /// 0到1之间的生命周期进度
+    pub fn life(&self) -> f32 {
+        (self.age / self.lifetime).min(1.0)
+    }
+}
+
+/// 粒子发射器，由`SystemParticles`在实体的`ComponentTransform`处发射和更新粒子，
+/// `SystemSprites`把粒子和精灵一起批量绘制
+#[derive(Debug, Clone)]
+pub struct ComponentParticleEmitter {
+    /// 每秒发射的粒子数量
+    pub rate: f32,
+
+    /// 同时存在的最大粒子数量
+    pub max_particles: usize,
+
+    /// 存活时间的范围（秒）
+    pub lifetime: (f32, f32),
+
+    /// 初始速度的范围
+    pub speed: (f32, f32),
+
+    /// 发射方向相对实体朝向的角度（弧度）
+    pub direction: f32,
+
+    /// 发射方向随机偏离的最大角度（弧度）
+    pub spread: f32,
+
+    pub gravity: Vector2<f32>,
+
+    /// 速度随生命周期的倍数
+    pub speed_over_life: Curve<f32>,
+
+    /// 大小随生命周期的变化
+    pub size_over_life: Curve<f32>,
+
+    /// 颜色随生命周期的变化
+    pub color_over_life: Curve<Color>,
+
+    pub texture: Option<TextureHandle>,
+    pub layer: i32,
+
+    /// 不为`None`时粒子会和物理世界碰撞并按这个恢复系数反弹，需要添加`SystemPhysics`
+    pub collision: Option<f32>,
+
+    /// 为`false`时停止发射，已经存在的粒子继续更新
+    pub emitting: bool,
+
+    particles: Vec<Particle>,
+    accumulator: f32,
+}
+
+impl Default for ComponentParticleEmitter {
+    fn default() -> Self {
+        ComponentParticleEmitter {
+            rate: 10.0,
+            max_particles: 100,
+            lifetime: (1.0, 1.0),
+            speed: (1.0, 1.0),
+            direction: std::f32::consts::FRAC_PI_2,
+            spread: 0.0,
+            gravity: Vector2::zero(),
+            speed_over_life: Curve::constant(1.0),
+            size_over_life: Curve::constant(0.1),
+            color_over_life: Curve::constant(Color::WHITE),
+            texture: None,
+            layer: 0,
+            collision: None,
+            emitting: true,
+            particles: Vec::new(),
+            accumulator: 0.0,
+        }
+    }
+}
+
+impl ComponentParticleEmitter {
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// 删除所有粒子
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.accumulator = 0.0;
+    }
+}
+
+impl Component for ComponentParticleEmitter {
+    fn name() -> &'static str {
+        "ParticleEmitter"
+    }
+}
+
+fn random_range(rng: &mut StdRng, (min, max): (f32, f32)) -> f32 {
+    if max > min {
+        rng.gen_range(min, max)
+    } else {
+        min
+    }
+}
+
+/// 发射、移动和删除粒子，需要在`SystemSprites`之前执行
+pub struct SystemParticles {
+    rng: StdRng,
+}
+
+impl Default for SystemParticles {
+    fn default() -> Self {
+        SystemParticles {
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
+}
+
+impl System for SystemParticles {
+    fn update(&mut self, ecs: &mut ECS, delta: Duration) {
+        let dt = delta.as_secs_f32();
+        let ids = ecs
+            .query::<(
+                With<ComponentParticleEmitter>,
+                With<ComponentTransform>,
+                Without<ComponentDisabled>,
+            )>()
+            .map(|entity| entity.id())
+            .collect::<Vec<_>>();
+
+        for id in ids {
+            let transform = ecs
+                .entity(id)
+                .unwrap()
+                .get::<ComponentTransform>()
+                .unwrap()
+                .0;
+            // 粒子单独取出，避免每帧复制
+            let (emitter, mut particles, mut accumulator) = {
+                let mut entity = ecs.entity_mut(id).unwrap();
+                let emitter = entity.get_mut::<ComponentParticleEmitter>().unwrap();
+                let particles = std::mem::take(&mut emitter.particles);
+                (emitter.clone(), particles, emitter.accumulator)
+            };
+
+            // 移动粒子
+            let physics = ecs.system::<SystemPhysics>();
+            particles.retain(|p| p.age + dt < p.lifetime);
+            for p in &mut particles {
+                p.age += dt;
+                p.velocity += emitter.gravity * dt;
+                let next =
+                    p.position + p.velocity * (emitter.speed_over_life.sample(p.life()) * dt);
+                let hit = match (emitter.collision, physics) {
+                    (Some(restitution), Some(physics)) => physics
+                        .ray_cast(p.position, next, |_| true)
+                        .map(|hit| (hit, restitution)),
+                    _ => None,
+                };
+                match hit {
+                    Some((hit, restitution)) => {
+                        let vn = p.velocity.dot(hit.normal);
+                        if vn < 0.0 {
+                            p.velocity -= hit.normal * ((1.0 + restitution) * vn);
+                        }
+                        p.position = hit.point + hit.normal * COLLISION_OFFSET;
+                    }
+                    None => p.position = next,
+                }
+            }
+
+            // 发射新的粒子
+            if emitter.emitting && emitter.rate > 0.0 {
+                accumulator += emitter.rate * dt;
+                while accumulator >= 1.0 {
+                    accumulator -= 1.0;
+                    if particles.len() >= emitter.max_particles {
+                        continue;
+                    }
+                    let angle = transform.q.angle()
+                        + emitter.direction
+                        + random_range(&mut self.rng, (-emitter.spread, emitter.spread));
+                    let speed = random_range(&mut self.rng, emitter.speed);
+                    particles.push(Particle {
+                        position: transform.p,
+                        velocity: Vector2::new(angle.cos(), angle.sin()) * speed,
+                        age: 0.0,
+                        lifetime: random_range(&mut self.rng, emitter.lifetime)
+                            .max(std::f32::EPSILON),
+                    });
+                }
+            } else {
+                accumulator = 0.0;
+            }
+
+            let mut entity = ecs.entity_mut(id).unwrap();
+            let emitter = entity.get_mut::<ComponentParticleEmitter>().unwrap();
+            emitter.particles = particles;
+            emitter.accumulator = accumulator;
+        }
+    }
+}
+
+/// 把所有粒子转换为精灵，由`SystemSprites`和普通的精灵一起排序
+pub(crate) fn particle_sprites(ecs: &ECS) -> Vec<(ComponentSprite, Transform<f32>)> {
+    let mut sprites = Vec::new();
+    for entity in ecs.query::<(With<ComponentParticleEmitter>, Without<ComponentDisabled>)>() {
+        let emitter = entity.get::<ComponentParticleEmitter>().unwrap();
+        for p in &emitter.particles {
+            let life = p.life();
+            let size = emitter.size_over_life.sample(life);
+            sprites.push((
+                ComponentSprite {
+                    texture: emitter.texture,
+                    size: Vector2::new(size, size),
+                    color: emitter.color_over_life.sample(life),
+                    layer: emitter.layer,
+                },
+                Transform::new(p.position, Rotation::identity()),
+            ));
+        }
+    }
+    sprites
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DrawList, SystemSprites};
+    use xengine_physics::{ColliderMaterial, ComponentColliderBox, ComponentRigidBody};
+
+    #[test]
+    fn test_particles() {
+        let mut ecs = ECS::new();
+        ecs.add_system(0, SystemPhysics::default());
+        ecs.add_system(1, SystemParticles::default());
+        ecs.add_system(2, SystemSprites);
+        ecs.create_entity()
+            .component(ComponentTransform(Transform::new(
+                (0.0, -1.0).into(),
+                Rotation::new(0.0),
+            )))
+            .component(ComponentRigidBody::default())
+            .component(ComponentColliderBox {
+                fixture: ColliderMaterial {
+                    friction: 0.2,
+                    restitution: 0.0,
+                    density: 0.0,
+                    is_sensor: false,
+                    filter: Default::default(),
+                },
+                transform: Transform::identity(),
+                half_size: (5.0, 0.5).into(),
+            })
+            .finish();
+        let emitter = ecs
+            .create_entity()
+            .component(ComponentTransform(Transform::identity()))
+            .component(ComponentParticleEmitter {
+                rate: 100.0,
+                max_particles: 5,
+                direction: -std::f32::consts::FRAC_PI_2,
+                speed: (5.0, 5.0),
+                collision: Some(0.0),
+                ..ComponentParticleEmitter::default()
+            })
+            .finish();
+
+        for _ in 0..20 {
+            ecs.update(Duration::from_millis(20));
+        }
+        let particles = ecs
+            .entity(emitter)
+            .unwrap()
+            .get::<ComponentParticleEmitter>()
+            .unwrap()
+            .particles()
+            .to_vec();
+        assert_eq!(particles.len(), 5);
+        for p in &particles {
+            assert!(p.position.y > -0.5 - COLLISION_OFFSET * 2.0);
+        }
+
+        let draw_list = ecs.resource::<DrawList>().unwrap();
+        assert_eq!(draw_list.batches.len(), 1);
+        assert_eq!(draw_list.batches[0].vertices.len(), 5 * 6);
+    }
+
+    #[test]
+    fn test_particles_at_rest() {
+        let mut ecs = ECS::new();
+        ecs.add_system(0, SystemPhysics::default());
+        ecs.add_system(1, SystemParticles::default());
+        let emitter = ecs
+            .create_entity()
+            .component(ComponentTransform(Transform::identity()))
+            .component(ComponentParticleEmitter {
+                rate: 100.0,
+                max_particles: 5,
+                speed: (0.0, 0.0),
+                gravity: Vector2::zero(),
+                collision: Some(0.0),
+                ..ComponentParticleEmitter::default()
+            })
+            .finish();
+
+        // 不动的粒子做长度为0的射线检测
+        for _ in 0..5 {
+            ecs.update(Duration::from_millis(20));
+        }
+        let emitter = ecs.entity(emitter).unwrap();
+        let particles = emitter
+            .get::<ComponentParticleEmitter>()
+            .unwrap()
+            .particles();
+        assert!(!particles.is_empty());
+        assert!(particles.iter().all(|p| p.position == Vector2::zero()));
+    }
+}
@@ -0,0 +1,129 @@
+use crate::particles::particle_sprites;
+use crate::{Color, DrawList, TextureHandle};
+use std::time::Duration;
+use xecs::{Component, System, With, Without, ECS};
+use xengine_basecomponents::{CameraView, ComponentDisabled, ComponentTransform};
+use xmath::{Vector2, AABB};
+
+/// 以实体的`ComponentTransform`为中心绘制的矩形精灵
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentSprite {
+    /// 为`None`时只使用颜色
+    pub texture: Option<TextureHandle>,
+
+    /// 世界坐标中的宽和高
+    pub size: Vector2<f32>,
+
+    /// 和纹理的颜色相乘
+    pub color: Color,
+
+    /// 越小越先绘制
+    pub layer: i32,
+}
+
+impl Default for ComponentSprite {
+    fn default() -> Self {
+        ComponentSprite {
+            texture: None,
+            size: (1.0, 1.0).into(),
+            color: Color::WHITE,
+            layer: 0,
+        }
+    }
+}
+
+impl Component for ComponentSprite {
+    fn name() -> &'static str {
+        "Sprite"
+    }
+}
+
+/// 每帧根据所有的精灵重新生成`DrawList`资源
+///
+/// 精灵按`layer`排序，同一层里纹理相同的精灵合并成一个批次，粒子发射器的粒子也作为精灵一起绘制。
+/// 有`CameraView`资源时跳过视口外的精灵。
+#[derive(Default)]
+pub struct SystemSprites;
+
+impl System for SystemSprites {
+    fn update(&mut self, ecs: &mut ECS, _delta: Duration) {
+        let view = ecs.resource::<CameraView>().map(|view| view.aabb);
+        let mut sprites = ecs
+            .query::<(
+                With<ComponentSprite>,
+                With<ComponentTransform>,
+                Without<ComponentDisabled>,
+            )>()
+            .map(|entity| {
+                (
+                    *entity.get::<ComponentSprite>().unwrap(),
+                    entity.get::<ComponentTransform>().unwrap().0,
+                )
+            })
+            .collect::<Vec<_>>();
+        sprites.extend(particle_sprites(ecs));
+        if let Some(view) = &view {
+            sprites.retain(|(sprite, transform)| {
+                let radius = sprite.size.length() * 0.5;
+                view.is_overlap(&AABB::new_center(transform.p, (radius, radius).into()))
+            });
+        }
+        sprites.sort_by_key(|(sprite, _)| (sprite.layer, sprite.texture));
+
+        let mut draw_list = ecs.remove_resource::<DrawList>().unwrap_or_default();
+        draw_list.clear();
+        for (sprite, transform) in &sprites {
+            draw_list.push_sprite(sprite.texture, transform, sprite.size, sprite.color);
+        }
+        ecs.insert_resource(draw_list);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Texture, Textures};
+    use xmath::{Rotation, Transform};
+
+    fn spawn(ecs: &mut ECS, x: f32, texture: Option<TextureHandle>, layer: i32) {
+        ecs.create_entity()
+            .component(ComponentTransform(Transform::new(
+                (x, 0.0).into(),
+                Rotation::new(0.0),
+            )))
+            .component(ComponentSprite {
+                texture,
+                layer,
+                ..ComponentSprite::default()
+            })
+            .finish();
+    }
+
+    #[test]
+    fn test_sprites() {
+        let mut textures = Textures::default();
+        let a = textures.add(Texture::new(1, 1, vec![255; 4]));
+        let b = textures.add(Texture::new(1, 1, vec![255; 4]));
+
+        let mut ecs = ECS::new();
+        ecs.add_system(0, SystemSprites);
+        spawn(&mut ecs, 0.0, Some(a), 1);
+        spawn(&mut ecs, 1.0, Some(b), 0);
+        spawn(&mut ecs, 2.0, Some(a), 1);
+        spawn(&mut ecs, 3.0, Some(b), 0);
+        spawn(&mut ecs, 4.0, None, 2);
+        ecs.update(Duration::from_secs(1) / 60);
+
+        let draw_list = ecs.resource::<DrawList>().unwrap();
+        let batches = draw_list
+            .batches
+            .iter()
+            .map(|batch| (batch.texture, batch.vertices.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(batches, vec![(Some(b), 12), (Some(a), 12), (None, 6)]);
+
+        let vertex = draw_list.batches[2].vertices[0];
+        assert_eq!(vertex.position, [3.5, -0.5]);
+        assert_eq!(vertex.color, [1.0, 1.0, 1.0, 1.0]);
+    }
+}
@@ -0,0 +1,130 @@
+//! 内置的3x5像素字体，用于调试信息，只包含数字、大写字母和常用的符号，小写字母按大写绘制
+
+use crate::{Color, DrawList};
+use xmath::Vector2;
+
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+
+/// 字符之间和行之间的间隔（字体像素）
+const SPACING: usize = 1;
+
+/// 每个字形从上到下5行，每行的低3位从左到右
+const GLYPHS: &[(char, [u8; GLYPH_HEIGHT])] = &[
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b001, 0b001, 0b001]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('C', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('G', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('K', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('Q', [0b010, 0b101, 0b101, 0b110, 0b011]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('U', [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('V', [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', [0b111, 0b001, 0b010, 0b100, 0b111]),
+    (':', [0b000, 0b010, 0b000, 0b010, 0b000]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    (',', [0b000, 0b000, 0b000, 0b010, 0b100]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    ('+', [0b000, 0b010, 0b111, 0b010, 0b000]),
+    ('=', [0b000, 0b111, 0b000, 0b111, 0b000]),
+    ('/', [0b001, 0b001, 0b010, 0b100, 0b100]),
+    ('%', [0b101, 0b001, 0b010, 0b100, 0b101]),
+    ('(', [0b010, 0b100, 0b100, 0b100, 0b010]),
+    (')', [0b010, 0b001, 0b001, 0b001, 0b010]),
+    ('[', [0b110, 0b100, 0b100, 0b100, 0b110]),
+    (']', [0b011, 0b001, 0b001, 0b001, 0b011]),
+    ('<', [0b001, 0b010, 0b100, 0b010, 0b001]),
+    ('>', [0b100, 0b010, 0b001, 0b010, 0b100]),
+    ('_', [0b000, 0b000, 0b000, 0b000, 0b111]),
+    ('#', [0b101, 0b111, 0b101, 0b111, 0b101]),
+    ('!', [0b010, 0b010, 0b010, 0b000, 0b010]),
+    ('?', [0b111, 0b001, 0b010, 0b000, 0b010]),
+    ('*', [0b101, 0b010, 0b101, 0b000, 0b000]),
+    ('\'', [0b010, 0b010, 0b000, 0b000, 0b000]),
+    ('"', [0b101, 0b101, 0b000, 0b000, 0b000]),
+];
+
+fn glyph(c: char) -> Option<&'static [u8; GLYPH_HEIGHT]> {
+    let c = c.to_ascii_uppercase();
+    GLYPHS
+        .iter()
+        .find(|(ch, _)| *ch == c)
+        .map(|(_, rows)| rows)
+        .or_else(|| if c.is_whitespace() { None } else { glyph('?') })
+}
+
+/// 文字占用的像素大小，`scale`是每个字体像素对应的屏幕像素数
+pub fn text_size(text: &str, scale: f32) -> Vector2<f32> {
+    let columns = text
+        .lines()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    let rows = text.lines().count();
+    Vector2::new(
+        (columns * (GLYPH_WIDTH + SPACING)) as f32 * scale,
+        (rows * (GLYPH_HEIGHT + SPACING)) as f32 * scale,
+    )
+}
+
+impl DrawList {
+    /// 在屏幕上绘制文字，`position`是左上角，`scale`是每个字体像素对应的屏幕像素数，支持多行
+    pub fn push_text(&mut self, position: Vector2<f32>, text: &str, scale: f32, color: Color) {
+        for (row, line) in text.lines().enumerate() {
+            let y = position.y + (row * (GLYPH_HEIGHT + SPACING)) as f32 * scale;
+            for (column, c) in line.chars().enumerate() {
+                let x = position.x + (column * (GLYPH_WIDTH + SPACING)) as f32 * scale;
+                let rows = match glyph(c) {
+                    Some(rows) => rows,
+                    None => continue,
+                };
+                for (i, bits) in rows.iter().enumerate() {
+                    // 同一行连续的像素合并成一个矩形
+                    let mut start = None;
+                    for j in 0..=GLYPH_WIDTH {
+                        let set = j < GLYPH_WIDTH && bits & (1 << (GLYPH_WIDTH - 1 - j)) != 0;
+                        match (set, start) {
+                            (true, None) => start = Some(j),
+                            (false, Some(s)) => {
+                                let top = y + i as f32 * scale;
+                                self.push_overlay_rect(
+                                    Vector2::new(x + s as f32 * scale, top),
+                                    Vector2::new(x + j as f32 * scale, top + scale),
+                                    color,
+                                );
+                                start = None;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
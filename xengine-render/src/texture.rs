@@ -0,0 +1,51 @@
+/// 通过`Textures::add`得到的纹理句柄
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct TextureHandle(pub(crate) usize);
+
+impl TextureHandle {
+    /// 纹理在`Textures`中的下标，按添加的顺序从0开始
+    pub fn index(self) -> usize {
+        self.0
+    }
+
+    /// 通过下标创建句柄，不存在的纹理渲染为白色
+    pub fn from_index(index: usize) -> TextureHandle {
+        TextureHandle(index)
+    }
+}
+
+/// RGBA8格式的纹理数据，第一行是图片的顶部
+#[derive(Debug, Clone)]
+pub struct Texture {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Texture {
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Texture {
+        assert_eq!(pixels.len(), (width * height * 4) as usize);
+        Texture {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+/// 所有的纹理，作为ECS资源使用，渲染器在第一次用到时上传到显卡
+#[derive(Debug, Default)]
+pub struct Textures {
+    textures: Vec<Texture>,
+}
+
+impl Textures {
+    pub fn add(&mut self, texture: Texture) -> TextureHandle {
+        self.textures.push(texture);
+        TextureHandle(self.textures.len() - 1)
+    }
+
+    pub fn get(&self, handle: TextureHandle) -> Option<&Texture> {
+        self.textures.get(handle.0)
+    }
+}
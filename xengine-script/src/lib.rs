@@ -0,0 +1,642 @@
+//! 使用rhai编写游戏逻辑，修改脚本不需要重新编译
+//!
+//! 脚本里的实体是整数，组件是`SceneRegistry`里注册的组件，数据格式和场景文件相同：
+//!
+//! ```rhai
+//! let player = find("player");
+//!
+//! fn update(dt) {
+//!     let t = get(player, "Transform");
+//!     t.position[0] += dt;
+//!     set(player, "Transform", t);
+//! }
+//!
+//! fn on_hit(event) {
+//!     despawn_entity(event.b);
+//! }
+//!
+//! subscribe("CollisionBegin", "on_hit");
+//! ```
+//!
+//! 可以使用的函数：
+//!
+//! - `spawn_entity()`、`despawn_entity(e)`、`exists(e)`、`find(name)`
+//! - `get(e, component)`、`set(e, component, value)`、`has(e, component)`、`remove(e, component)`
+//! - `ray_cast(from, to)`、`overlap_circle(center, radius)`、`overlap_aabb(min, max)`，坐标是`[x, y]`
+//! - `subscribe(event, function)`、`emit(event, value)`
+
+use rhai::{
+    Array, CallFnOptions, Dynamic, Engine, EvalAltResult, Map, ParseError, Scope, AST, INT,
+};
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+use xecs::{EntityId, Event, System, WeakEntity, ECS};
+use xengine::scene_file::{LoadContext, SaveContext, SceneRegistry};
+use xengine_basecomponents::{find_entity_by_name, LifetimeExpired};
+use xengine_physics::{CollisionBegin, CollisionEnd, SystemPhysics, TriggerEnter, TriggerExit};
+use xmath::{Vector2, AABB};
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// 脚本通过`emit`发送的事件，通过`Event::Custom`发送，脚本可以通过`subscribe`接收
+#[derive(Debug, Clone)]
+pub struct ScriptEvent {
+    pub name: String,
+    pub value: Dynamic,
+}
+
+#[derive(Debug)]
+pub enum ScriptError {
+    Parse(String, ParseError),
+    Runtime(String, Box<EvalAltResult>),
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Parse(name, err) => write!(f, "{}: parse error: {}", name, err),
+            ScriptError::Runtime(name, err) => write!(f, "{}: runtime error: {}", name, err),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+struct LoadedScript {
+    name: String,
+    ast: AST,
+    scope: Scope<'static>,
+    started: bool,
+    has_update: bool,
+}
+
+struct Subscription {
+    event: String,
+    script: usize,
+    function: String,
+}
+
+/// 注册函数共享的状态
+#[derive(Default)]
+struct Shared {
+    /// 只在执行脚本期间有效
+    ecs: Cell<Option<*mut ECS>>,
+
+    /// 正在执行的脚本，用于`subscribe`
+    current: Cell<usize>,
+    subscriptions: RefCell<Vec<Subscription>>,
+}
+
+impl Shared {
+    fn ecs(&self) -> ScriptResult<&mut ECS> {
+        match self.ecs.get() {
+            // 指针只在`SystemScripts::with_ecs`期间设置，这期间没有其它对ECS的引用
+            Some(ecs) => Ok(unsafe { &mut *ecs }),
+            None => Err("ECS is not available outside of SystemScripts".into()),
+        }
+    }
+}
+
+type EventConverter = Box<dyn Fn(&ECS, &Event) -> Option<(String, Dynamic)>>;
+
+/// 脚本里的实体是`WeakEntity::to_bits`编码的整数，实体删除后下标被重用也不会指向新的实体，
+/// 已经删除的实体转换为`()`
+fn entity_to_script(ecs: &ECS, id: EntityId) -> Dynamic {
+    match ecs.weak(id) {
+        Some(weak) => Dynamic::from(weak.to_bits() as INT),
+        None => Dynamic::UNIT,
+    }
+}
+
+fn entity_from_script(ecs: &ECS, e: INT) -> ScriptResult<EntityId> {
+    match ecs.upgrade(WeakEntity::from_bits(e as u64)) {
+        Some(id) => Ok(id),
+        None => Err(format!("entity {} does not exist", e).into()),
+    }
+}
+
+/// 脚本里的`1`是整数，`1.0`才是浮点数，两种都接受
+fn number_from_script(value: &Dynamic) -> ScriptResult<f32> {
+    value
+        .as_float()
+        .or_else(|_| value.as_int().map(|i| i as f32))
+        .map_err(|_| "expected a number".into())
+}
+
+fn vector_from_script(value: &Array) -> ScriptResult<Vector2<f32>> {
+    match value.as_slice() {
+        [x, y] => Ok(Vector2::new(number_from_script(x)?, number_from_script(y)?)),
+        _ => Err("expected [x, y]".into()),
+    }
+}
+
+fn vector_to_script(v: Vector2<f32>) -> Dynamic {
+    Dynamic::from_array(vec![v.x.into(), v.y.into()])
+}
+
+fn entities_to_script(ecs: &ECS, entities: Vec<EntityId>) -> Dynamic {
+    Dynamic::from_array(
+        entities
+            .into_iter()
+            .map(|id| entity_to_script(ecs, id))
+            .collect(),
+    )
+}
+
+fn map(fields: Vec<(&str, Dynamic)>) -> Dynamic {
+    let mut map = Map::new();
+    for (name, value) in fields {
+        map.insert(name.into(), value);
+    }
+    Dynamic::from_map(map)
+}
+
+fn physics(ecs: &ECS) -> ScriptResult<&SystemPhysics> {
+    ecs.system::<SystemPhysics>()
+        .ok_or_else(|| "SystemPhysics is not added".into())
+}
+
+fn register_api(engine: &mut Engine, shared: &Rc<Shared>) {
+    let s = shared.clone();
+    engine.register_fn("spawn_entity", move || -> ScriptResult<Dynamic> {
+        let ecs = s.ecs()?;
+        let id = ecs.create_entity().finish();
+        Ok(entity_to_script(ecs, id))
+    });
+
+    let s = shared.clone();
+    engine.register_fn("despawn_entity", move |e: INT| -> ScriptResult<()> {
+        let ecs = s.ecs()?;
+        let id = entity_from_script(ecs, e)?;
+        ecs.remove_entity(id);
+        Ok(())
+    });
+
+    let s = shared.clone();
+    engine.register_fn("exists", move |e: INT| -> ScriptResult<bool> {
+        Ok(entity_from_script(s.ecs()?, e).is_ok())
+    });
+
+    let s = shared.clone();
+    engine.register_fn("find", move |name: &str| -> ScriptResult<Dynamic> {
+        let ecs = s.ecs()?;
+        Ok(find_entity_by_name(ecs, name)
+            .map(|id| entity_to_script(ecs, id))
+            .unwrap_or(Dynamic::UNIT))
+    });
+
+    let s = shared.clone();
+    engine.register_fn(
+        "get",
+        move |e: INT, component: &str| -> ScriptResult<Dynamic> {
+            let ecs = s.ecs()?;
+            let id = entity_from_script(ecs, e)?;
+            match SceneRegistry::from_ecs(ecs).component_to_json(
+                ecs,
+                id,
+                component,
+                &SaveContext::handles(ecs),
+            ) {
+                Some(value) => {
+                    let value = value.map_err(|err| err.to_string())?;
+                    rhai::serde::to_dynamic(value)
+                }
+                None => Ok(Dynamic::UNIT),
+            }
+        },
+    );
+
+    let s = shared.clone();
+    engine.register_fn(
+        "set",
+        move |e: INT, component: &str, value: Dynamic| -> ScriptResult<()> {
+            let ecs = s.ecs()?;
+            let id = entity_from_script(ecs, e)?;
+            let value: serde_json::Value = rhai::serde::from_dynamic(&value)?;
+            let registered = SceneRegistry::from_ecs(ecs)
+                .component_from_json(ecs, id, component, value, &LoadContext::handles(ecs))
+                .map_err(|err| format!("invalid {}: {}", component, err))?;
+            if registered {
+                Ok(())
+            } else {
+                Err(format!("component {} is not registered", component).into())
+            }
+        },
+    );
+
+    let s = shared.clone();
+    engine.register_fn(
+        "has",
+        move |e: INT, component: &str| -> ScriptResult<bool> {
+            let ecs = s.ecs()?;
+            let id = entity_from_script(ecs, e)?;
+            Ok(SceneRegistry::from_ecs(ecs)
+                .component_to_json(ecs, id, component, &SaveContext::handles(ecs))
+                .is_some())
+        },
+    );
+
+    let s = shared.clone();
+    engine.register_fn(
+        "remove",
+        move |e: INT, component: &str| -> ScriptResult<()> {
+            let ecs = s.ecs()?;
+            let id = entity_from_script(ecs, e)?;
+            SceneRegistry::from_ecs(ecs).remove_component(ecs, id, component);
+            Ok(())
+        },
+    );
+
+    let s = shared.clone();
+    engine.register_fn(
+        "ray_cast",
+        move |from: Array, to: Array| -> ScriptResult<Dynamic> {
+            let ecs = s.ecs()?;
+            let hit = physics(ecs)?.ray_cast(
+                vector_from_script(&from)?,
+                vector_from_script(&to)?,
+                |_| true,
+            );
+            Ok(match hit {
+                Some(hit) => map(vec![
+                    ("entity", entity_to_script(ecs, hit.entity)),
+                    ("point", vector_to_script(hit.point)),
+                    ("normal", vector_to_script(hit.normal)),
+                ]),
+                None => Dynamic::UNIT,
+            })
+        },
+    );
+
+    let s = shared.clone();
+    let overlap_circle = move |center: Array, radius: f32| -> ScriptResult<Dynamic> {
+        let ecs = s.ecs()?;
+        let center = vector_from_script(&center)?;
+        Ok(entities_to_script(
+            ecs,
+            physics(ecs)?.overlap_circle(center, radius),
+        ))
+    };
+    let f = overlap_circle.clone();
+    engine.register_fn(
+        "overlap_circle",
+        move |center: Array, radius: rhai::FLOAT| f(center, radius),
+    );
+    engine.register_fn("overlap_circle", move |center: Array, radius: INT| {
+        overlap_circle(center, radius as f32)
+    });
+
+    let s = shared.clone();
+    engine.register_fn(
+        "overlap_aabb",
+        move |min: Array, max: Array| -> ScriptResult<Dynamic> {
+            let ecs = s.ecs()?;
+            let aabb = AABB::new(vector_from_script(&min)?, vector_from_script(&max)?);
+            Ok(entities_to_script(ecs, physics(ecs)?.overlap_aabb(aabb)))
+        },
+    );
+
+    let s = shared.clone();
+    engine.register_fn("subscribe", move |event: &str, function: &str| {
+        s.subscriptions.borrow_mut().push(Subscription {
+            event: event.to_string(),
+            script: s.current.get(),
+            function: function.to_string(),
+        });
+    });
+
+    let s = shared.clone();
+    engine.register_fn(
+        "emit",
+        move |event: &str, value: Dynamic| -> ScriptResult<()> {
+            s.ecs()?.send_event(ScriptEvent {
+                name: event.to_string(),
+                value,
+            });
+            Ok(())
+        },
+    );
+}
+
+fn builtin_events() -> Vec<EventConverter> {
+    vec![
+        Box::new(|ecs, event| {
+            event.downcast_ref::<CollisionBegin>().map(|e| {
+                let value = map(vec![
+                    ("a", entity_to_script(ecs, e.a)),
+                    ("b", entity_to_script(ecs, e.b)),
+                    ("normal", vector_to_script(e.normal)),
+                    ("impulse", e.impulse.into()),
+                ]);
+                ("CollisionBegin".to_string(), value)
+            })
+        }),
+        Box::new(|ecs, event| {
+            event.downcast_ref::<CollisionEnd>().map(|e| {
+                let value = map(vec![
+                    ("a", entity_to_script(ecs, e.a)),
+                    ("b", entity_to_script(ecs, e.b)),
+                ]);
+                ("CollisionEnd".to_string(), value)
+            })
+        }),
+        Box::new(|ecs, event| {
+            event.downcast_ref::<TriggerEnter>().map(|e| {
+                let value = map(vec![
+                    ("sensor", entity_to_script(ecs, e.sensor)),
+                    ("other", entity_to_script(ecs, e.other)),
+                ]);
+                ("TriggerEnter".to_string(), value)
+            })
+        }),
+        Box::new(|ecs, event| {
+            event.downcast_ref::<TriggerExit>().map(|e| {
+                let value = map(vec![
+                    ("sensor", entity_to_script(ecs, e.sensor)),
+                    ("other", entity_to_script(ecs, e.other)),
+                ]);
+                ("TriggerExit".to_string(), value)
+            })
+        }),
+        Box::new(|ecs, event| {
+            event
+                .downcast_ref::<LifetimeExpired>()
+                .map(|e| ("LifetimeExpired".to_string(), entity_to_script(ecs, e.0)))
+        }),
+        Box::new(|_, event| {
+            event
+                .downcast_ref::<ScriptEvent>()
+                .map(|e| (e.name.clone(), e.value.clone()))
+        }),
+    ]
+}
+
+/// 加载和执行rhai脚本
+///
+/// 脚本的顶层代码在加载后的第一次`update`时执行一次，之后每帧调用脚本里的`update(dt)`函数。
+/// 脚本的错误不会中断游戏，可以通过`take_errors`获取。
+pub struct SystemScripts {
+    engine: Engine,
+    shared: Rc<Shared>,
+    scripts: Vec<LoadedScript>,
+    events: Vec<EventConverter>,
+    errors: Vec<ScriptError>,
+}
+
+impl Default for SystemScripts {
+    fn default() -> Self {
+        let shared = Rc::new(Shared::default());
+        let mut engine = Engine::new();
+        register_api(&mut engine, &shared);
+        SystemScripts {
+            engine,
+            shared,
+            scripts: Vec::new(),
+            events: builtin_events(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+impl SystemScripts {
+    /// 可以注册自定义的函数和类型
+    pub fn engine_mut(&mut self) -> &mut Engine {
+        &mut self.engine
+    }
+
+    /// 让脚本可以通过`subscribe(name, ...)`接收自定义的事件
+    pub fn register_event<E, F>(&mut self, name: &str, f: F)
+    where
+        E: 'static,
+        F: Fn(&E) -> Dynamic + 'static,
+    {
+        let name = name.to_string();
+        self.events.push(Box::new(move |_, event| {
+            event
+                .downcast_ref::<E>()
+                .map(|event| (name.clone(), f(event)))
+        }));
+    }
+
+    /// 加载脚本，同名的脚本会被替换，替换时会清除它的变量和订阅
+    pub fn load(&mut self, name: &str, source: &str) -> Result<(), ScriptError> {
+        let ast = self
+            .engine
+            .compile(source)
+            .map_err(|err| ScriptError::Parse(name.to_string(), err))?;
+        let has_update = ast
+            .iter_functions()
+            .any(|f| f.name == "update" && f.params.len() == 1);
+        let script = LoadedScript {
+            name: name.to_string(),
+            ast,
+            scope: Scope::new(),
+            started: false,
+            has_update,
+        };
+        match self.scripts.iter().position(|s| s.name == name) {
+            Some(index) => {
+                self.shared
+                    .subscriptions
+                    .borrow_mut()
+                    .retain(|s| s.script != index);
+                self.scripts[index] = script;
+            }
+            None => self.scripts.push(script),
+        }
+        Ok(())
+    }
+
+    /// 读取文件并加载，脚本的名字是文件的路径
+    pub fn load_file(&mut self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)?;
+        self.load(&path.to_string_lossy(), &source)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err.to_string()))
+    }
+
+    pub fn script_names(&self) -> impl Iterator<Item = &str> {
+        self.scripts.iter().map(|script| script.name.as_str())
+    }
+
+    /// 取出脚本执行时产生的错误
+    pub fn take_errors(&mut self) -> Vec<ScriptError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// 执行脚本的顶层代码或者调用脚本里的函数，执行期间注册的函数可以访问ECS
+    fn run(&mut self, ecs: &mut ECS, index: usize, function: Option<(&str, Dynamic)>) {
+        let script = &mut self.scripts[index];
+        self.shared.current.set(index);
+        self.shared.ecs.set(Some(ecs as *mut ECS));
+        let result = match function {
+            Some((name, arg)) => self
+                .engine
+                .call_fn_with_options::<Dynamic>(
+                    CallFnOptions::new().eval_ast(false),
+                    &mut script.scope,
+                    &script.ast,
+                    name,
+                    (arg,),
+                )
+                .map(|_| ()),
+            None => self
+                .engine
+                .run_ast_with_scope(&mut script.scope, &script.ast),
+        };
+        self.shared.ecs.set(None);
+        if let Err(err) = result {
+            self.errors
+                .push(ScriptError::Runtime(script.name.clone(), err));
+        }
+    }
+}
+
+impl System for SystemScripts {
+    fn update(&mut self, ecs: &mut ECS, delta: Duration) {
+        for index in 0..self.scripts.len() {
+            if !self.scripts[index].started {
+                self.scripts[index].started = true;
+                self.run(ecs, index, None);
+            }
+        }
+
+        let dt = Dynamic::from(delta.as_secs_f32());
+        for index in 0..self.scripts.len() {
+            if self.scripts[index].has_update {
+                self.run(ecs, index, Some(("update", dt.clone())));
+            }
+        }
+    }
+
+    fn handle_event(&mut self, ecs: &mut ECS, event: &Event) {
+        let (name, value) = match self.events.iter().find_map(|convert| convert(ecs, event)) {
+            Some(event) => event,
+            None => return,
+        };
+        let handlers = self
+            .shared
+            .subscriptions
+            .borrow()
+            .iter()
+            .filter(|s| s.event == name)
+            .map(|s| (s.script, s.function.clone()))
+            .collect::<Vec<_>>();
+        for (script, function) in handlers {
+            self.run(ecs, script, Some((&function, value.clone())));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xengine_basecomponents::{ComponentName, ComponentTransform};
+    use xmath::Transform;
+
+    const SCRIPT: &str = r#"
+        let player = find("player");
+        let hits = 0;
+
+        fn update(dt) {
+            let t = get(player, "Transform");
+            t.position[0] += dt;
+            set(player, "Transform", t);
+        }
+
+        fn on_ping(value) {
+            hits += value;
+            let e = spawn_entity();
+            set(e, "Name", "pong" + hits);
+        }
+
+        subscribe("ping", "on_ping");
+        emit("ping", 2);
+    "#;
+
+    #[test]
+    fn test_scripts() {
+        let mut ecs = ECS::new();
+        let player = ecs
+            .create_entity()
+            .component(ComponentName::new("player"))
+            .component(ComponentTransform(Transform::identity()))
+            .finish();
+        let mut scripts = SystemScripts::default();
+        scripts.load("test", SCRIPT).unwrap();
+        ecs.add_system(0, scripts);
+
+        ecs.update(Duration::from_millis(500));
+        ecs.update(Duration::from_millis(500));
+        let errors = ecs.system_mut::<SystemScripts>().unwrap().take_errors();
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let position = ecs
+            .entity(player)
+            .unwrap()
+            .get::<ComponentTransform>()
+            .unwrap()
+            .0
+            .p;
+        assert_eq!(position, Vector2::new(1.0, 0.0));
+        assert!(find_entity_by_name(&ecs, "pong2").is_some());
+
+        let scripts = ecs.system_mut::<SystemScripts>().unwrap();
+        assert!(scripts.load("broken", "fn (").is_err());
+        scripts.load("bad", "get(12345, \"Transform\");").unwrap();
+        ecs.update(Duration::from_millis(16));
+        let errors = ecs.system_mut::<SystemScripts>().unwrap().take_errors();
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_stale_entity_handle() {
+        let mut ecs = ECS::new();
+        let mut scripts = SystemScripts::default();
+        scripts
+            .load(
+                "test",
+                r#"
+                    let a = spawn_entity();
+                    despawn_entity(a);
+                    let b = spawn_entity();
+                    if exists(a) || !exists(b) {
+                        throw "stale entity handle";
+                    }
+                "#,
+            )
+            .unwrap();
+        ecs.add_system(0, scripts);
+
+        ecs.update(Duration::from_millis(16));
+        let errors = ecs.system_mut::<SystemScripts>().unwrap().take_errors();
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(ecs.entities().count(), 1);
+    }
+
+    #[test]
+    fn test_integer_arguments() {
+        let mut ecs = ECS::new();
+        ecs.add_system(0, SystemPhysics::default());
+        let mut scripts = SystemScripts::default();
+        scripts
+            .load(
+                "test",
+                r#"
+                    ray_cast([0, 0], [10, 0]);
+                    ray_cast([0.5, 0], [10, 0.5]);
+                    overlap_circle([0, 0], 1);
+                    overlap_circle([0, 0.5], 1.5);
+                    overlap_aabb([-1, -1], [1, 1]);
+                "#,
+            )
+            .unwrap();
+        ecs.add_system(1, scripts);
+
+        ecs.update(Duration::from_millis(16));
+        let errors = ecs.system_mut::<SystemScripts>().unwrap().take_errors();
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+}
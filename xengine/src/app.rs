@@ -0,0 +1,308 @@
+use crate::{Diagnostics, Input, Scheduler, SystemDiagnostics, SystemScheduler, Time};
+use std::time::Duration;
+use xecs::{System, ECS};
+use xengine_basecomponents::{CameraView, SystemHierarchy, SystemTransformPropagation};
+use xengine_physics::{PhysicsConfig, SystemPhysics};
+use xengine_render::{SystemParticles, SystemSpriteAnimation, SystemSprites};
+
+type StartupCallback = Box<dyn FnOnce(&mut ECS)>;
+type FixedUpdateCallback = Box<dyn FnMut(&mut ECS, Duration)>;
+type RenderCallback = Box<dyn FnMut(&mut ECS, f32)>;
+
+/// 默认系统的执行顺序，用户的系统可以插在它们之间
+const ORDER_FIXED_UPDATE: usize = 100;
+const ORDER_SCHEDULER: usize = 50;
+const ORDER_PHYSICS: usize = 200;
+const ORDER_HIERARCHY: usize = 100;
+const ORDER_TRANSFORM_PROPAGATION: usize = 200;
+const ORDER_SPRITE_ANIMATION: usize = 250;
+const ORDER_PARTICLES: usize = 260;
+const ORDER_SPRITES: usize = 300;
+const ORDER_DIAGNOSTICS: usize = 400;
+
+/// 在固定步长里按添加的顺序调用`on_fixed_update`注册的回调
+struct FixedUpdateCallbacks(Vec<FixedUpdateCallback>);
+
+impl System for FixedUpdateCallbacks {
+    fn update(&mut self, ecs: &mut ECS, delta: Duration) {
+        for callback in &mut self.0 {
+            callback(ecs, delta);
+        }
+    }
+}
+
+/// 用于创建`App`
+///
+/// 默认会添加定时器、层级、变换传播、物理和精灵系统，物理和`on_fixed_update`的回调按固定步长执行，
+/// 回调在物理之前执行
+pub struct AppBuilder {
+    ecs: ECS,
+    default_systems: bool,
+    physics_config: PhysicsConfig,
+    startup: Vec<StartupCallback>,
+    fixed_update: Vec<FixedUpdateCallback>,
+    render: Vec<RenderCallback>,
+    #[cfg(feature = "window")]
+    pub(crate) window: crate::WindowConfig,
+}
+
+impl Default for AppBuilder {
+    fn default() -> Self {
+        AppBuilder {
+            ecs: ECS::new(),
+            default_systems: true,
+            physics_config: PhysicsConfig::default(),
+            startup: Vec::new(),
+            fixed_update: Vec::new(),
+            render: Vec::new(),
+            #[cfg(feature = "window")]
+            window: Default::default(),
+        }
+    }
+}
+
+impl AppBuilder {
+    pub fn new() -> AppBuilder {
+        Default::default()
+    }
+
+    /// 固定步长，默认是1/60秒
+    pub fn fixed_timestep(mut self, timestep: Duration) -> Self {
+        self.ecs.set_fixed_timestep(timestep);
+        self
+    }
+
+    /// 每帧最多执行的固定步长次数，追不上的时间会被丢弃
+    pub fn max_fixed_steps(mut self, steps: usize) -> Self {
+        self.ecs.set_max_fixed_steps(steps);
+        self
+    }
+
+    /// 不添加默认的系统
+    pub fn without_default_systems(mut self) -> Self {
+        self.default_systems = false;
+        self
+    }
+
+    /// 创建默认物理系统使用的配置
+    pub fn physics_config(mut self, config: PhysicsConfig) -> Self {
+        self.physics_config = config;
+        self
+    }
+
+    /// 添加每帧执行的系统
+    pub fn system<S: System + 'static>(mut self, order: usize, system: S) -> Self {
+        self.ecs.add_system(order, system);
+        self
+    }
+
+    /// 添加按固定步长执行的系统
+    pub fn fixed_system<S: System + 'static>(mut self, order: usize, system: S) -> Self {
+        self.ecs.add_fixed_system(order, system);
+        self
+    }
+
+    /// 添加资源
+    pub fn resource<R: 'static>(mut self, resource: R) -> Self {
+        self.ecs.insert_resource(resource);
+        self
+    }
+
+    /// 在`build`时调用一次，一般用于创建初始的实体
+    pub fn startup<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut ECS) + 'static,
+    {
+        self.startup.push(Box::new(f));
+        self
+    }
+
+    /// 每个固定步长调用一次，参数是固定步长
+    pub fn on_fixed_update<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&mut ECS, Duration) + 'static,
+    {
+        self.fixed_update.push(Box::new(f));
+        self
+    }
+
+    /// 每帧在所有系统执行完后调用，参数是`ECS::fixed_alpha`，用于插值
+    pub fn on_render<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&mut ECS, f32) + 'static,
+    {
+        self.render.push(Box::new(f));
+        self
+    }
+
+    pub fn build(self) -> App {
+        let mut ecs = self.ecs;
+        ecs.insert_resource(Input::default());
+        ecs.insert_resource(Time::default());
+        ecs.insert_resource(Diagnostics::default());
+        ecs.insert_resource(Scheduler::default());
+        if self.default_systems {
+            ecs.add_system(ORDER_SCHEDULER, SystemScheduler);
+            ecs.add_system(ORDER_HIERARCHY, SystemHierarchy::default());
+            ecs.add_system(ORDER_TRANSFORM_PROPAGATION, SystemTransformPropagation);
+            ecs.add_fixed_system(ORDER_PHYSICS, SystemPhysics::new(self.physics_config));
+            ecs.add_system(ORDER_SPRITE_ANIMATION, SystemSpriteAnimation);
+            ecs.add_system(ORDER_PARTICLES, SystemParticles::default());
+            ecs.add_system(ORDER_SPRITES, SystemSprites);
+            ecs.add_system(ORDER_DIAGNOSTICS, SystemDiagnostics::default());
+        }
+        if !self.fixed_update.is_empty() {
+            ecs.add_fixed_system(ORDER_FIXED_UPDATE, FixedUpdateCallbacks(self.fixed_update));
+        }
+        for f in self.startup {
+            f(&mut ecs);
+        }
+        App {
+            ecs,
+            render: self.render,
+            #[cfg(feature = "window")]
+            window: self.window,
+        }
+    }
+}
+
+/// 拥有ECS并驱动每一帧
+pub struct App {
+    ecs: ECS,
+    render: Vec<RenderCallback>,
+    #[cfg(feature = "window")]
+    pub(crate) window: crate::WindowConfig,
+}
+
+impl App {
+    pub fn builder() -> AppBuilder {
+        AppBuilder::new()
+    }
+
+    pub fn ecs(&self) -> &ECS {
+        &self.ecs
+    }
+
+    pub fn ecs_mut(&mut self) -> &mut ECS {
+        &mut self.ecs
+    }
+
+    /// 推进一帧，先执行所有系统，然后调用渲染回调，最后清除`Input`里只在这一帧有效的状态
+    ///
+    /// 系统收到的帧间隔会按`Time`资源的`time_scale`缩放，暂停时为0
+    ///
+    /// 没有窗口时可以自己驱动主循环，比如服务器或者测试
+    pub fn update(&mut self, delta: Duration) {
+        let delta = self.begin_frame(delta);
+        self.ecs.update(delta);
+        self.call_render();
+        self.end_frame();
+    }
+
+    /// 更新`Time`和`Input`，返回按`Time`缩放后的帧间隔
+    pub(crate) fn begin_frame(&mut self, delta: Duration) -> Duration {
+        let fixed_delta = self.ecs.fixed_timestep();
+        let delta = match self.ecs.resource_mut::<Time>() {
+            Some(time) => time.advance(delta, fixed_delta),
+            None => delta,
+        };
+
+        let view = self.ecs.resource::<CameraView>().copied();
+        if let Some(input) = self.ecs.resource_mut::<Input>() {
+            input.begin_frame(view.as_ref());
+        }
+        delta
+    }
+
+    pub(crate) fn end_frame(&mut self) {
+        if let Some(input) = self.ecs.resource_mut::<Input>() {
+            input.end_frame();
+        }
+    }
+
+    pub(crate) fn call_render(&mut self) {
+        let alpha = self.ecs.fixed_alpha();
+        for render in &mut self.render {
+            render(&mut self.ecs, alpha);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use xengine_basecomponents::ComponentTransform;
+    use xengine_physics::{
+        BodyType, ColliderMaterial, ComponentColliderCircle, ComponentRigidBody,
+    };
+    use xmath::{Rotation, Transform, Vector2};
+
+    #[test]
+    fn test_app() {
+        let fixed_steps = Rc::new(RefCell::new(0));
+        let alphas = Rc::new(RefCell::new(Vec::new()));
+
+        let mut app = App::builder()
+            .fixed_timestep(Duration::from_millis(10))
+            .startup(|ecs| {
+                ecs.create_entity()
+                    .component(ComponentTransform(Transform::new(
+                        Vector2::new(0.0, 10.0),
+                        Rotation::new(0.0),
+                    )))
+                    .component(ComponentRigidBody {
+                        type_: BodyType::Dynamic,
+                        ..ComponentRigidBody::default()
+                    })
+                    .component(ComponentColliderCircle {
+                        fixture: ColliderMaterial {
+                            friction: 0.2,
+                            restitution: 0.0,
+                            density: 1.0,
+                            is_sensor: false,
+                            filter: Default::default(),
+                        },
+                        transform: Transform::identity(),
+                        radius: 0.5,
+                    })
+                    .finish();
+            })
+            .on_fixed_update({
+                let fixed_steps = fixed_steps.clone();
+                move |_, delta| {
+                    assert_eq!(delta, Duration::from_millis(10));
+                    *fixed_steps.borrow_mut() += 1;
+                }
+            })
+            .on_render({
+                let alphas = alphas.clone();
+                move |_, alpha| alphas.borrow_mut().push(alpha)
+            })
+            .build();
+        assert_eq!(app.ecs().entities_count(), 1);
+
+        app.update(Duration::from_millis(25));
+        assert_eq!(*fixed_steps.borrow(), 2);
+        assert_eq!(alphas.borrow().len(), 1);
+        assert!((alphas.borrow()[0] - 0.5).abs() < 1e-4);
+
+        for _ in 0..10 {
+            app.update(Duration::from_millis(10));
+        }
+        assert_eq!(*fixed_steps.borrow(), 12);
+
+        let id = app.ecs().entities().next().unwrap();
+        let y = app
+            .ecs()
+            .entity(id)
+            .unwrap()
+            .get::<ComponentTransform>()
+            .unwrap()
+            .0
+            .p
+            .y;
+        assert!(y < 10.0);
+    }
+}
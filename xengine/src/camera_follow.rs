@@ -0,0 +1,301 @@
+use std::time::Duration;
+use xecs::{Component, EntityId, Event, System, With, ECS};
+use xengine_basecomponents::{ComponentCamera, ComponentTransform};
+use xengine_physics::CollisionBegin;
+use xmath::{Rotation, Vector2, AABB};
+
+/// 相机跟随目标实体，并且根据创伤值（trauma）抖动
+///
+/// 和`ComponentCamera`放在同一个实体上，由`SystemCameraFollow`更新相机的`ComponentTransform`
+#[derive(Debug, Copy, Clone)]
+pub struct ComponentCameraFollow {
+    /// 跟随的实体，为`None`时相机停在原地，仍然会抖动
+    pub target: Option<EntityId>,
+
+    /// 相机中心相对目标的偏移
+    pub offset: Vector2<f32>,
+
+    /// 死区的半宽和半高，目标在死区内移动时相机不动
+    pub dead_zone: Vector2<f32>,
+
+    /// 平滑速度，越大跟得越紧，为0时不做平滑
+    pub smoothing: f32,
+
+    /// 世界的边界，相机的视口不会超出边界，边界比视口小时相机停在边界的中心
+    pub bounds: Option<AABB<f32>>,
+
+    /// 当前的创伤值，范围是0到1，抖动的幅度和它的平方成正比
+    pub trauma: f32,
+
+    /// 创伤值每秒减少的量
+    pub trauma_decay: f32,
+
+    /// 创伤值为1时的最大位移（世界单位）
+    pub max_shake_offset: f32,
+
+    /// 创伤值为1时的最大旋转（弧度）
+    pub max_shake_angle: f32,
+
+    /// 抖动的频率
+    pub shake_frequency: f32,
+
+    /// 每单位碰撞冲量增加的创伤值，为0时不响应碰撞
+    pub impulse_trauma: f32,
+
+    /// 小于这个冲量的碰撞不会引起抖动
+    pub min_impulse: f32,
+
+    /// 没有抖动时相机的位置和角度
+    focus: Option<(Vector2<f32>, f32)>,
+    time: f32,
+}
+
+impl Default for ComponentCameraFollow {
+    fn default() -> Self {
+        ComponentCameraFollow {
+            target: None,
+            offset: Vector2::zero(),
+            dead_zone: Vector2::zero(),
+            smoothing: 5.0,
+            bounds: None,
+            trauma: 0.0,
+            trauma_decay: 1.0,
+            max_shake_offset: 0.5,
+            max_shake_angle: 0.05,
+            shake_frequency: 15.0,
+            impulse_trauma: 0.0,
+            min_impulse: 0.0,
+            focus: None,
+            time: 0.0,
+        }
+    }
+}
+
+impl Component for ComponentCameraFollow {
+    fn name() -> &'static str {
+        "CameraFollow"
+    }
+}
+
+impl ComponentCameraFollow {
+    pub fn new(target: EntityId) -> ComponentCameraFollow {
+        ComponentCameraFollow {
+            target: Some(target),
+            ..Default::default()
+        }
+    }
+
+    /// 增加创伤值，最大为1
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).max(0.0).min(1.0);
+    }
+
+    /// 没有抖动时相机的位置
+    pub fn focus(&self) -> Option<Vector2<f32>> {
+        self.focus.map(|(p, _)| p)
+    }
+}
+
+/// 平滑的伪随机噪声，范围是-1到1
+fn noise(t: f32, seed: f32) -> f32 {
+    (t + seed * 17.0).sin() * 0.6
+        + (t * 2.7 + seed * 31.0).sin() * 0.3
+        + (t * 5.3 + seed * 7.0).sin() * 0.1
+}
+
+/// 把目标点移出死区的最小移动
+fn escape_dead_zone(focus: f32, goal: f32, dead_zone: f32) -> f32 {
+    let d = goal - focus;
+    if d > dead_zone {
+        focus + d - dead_zone
+    } else if d < -dead_zone {
+        focus + d + dead_zone
+    } else {
+        focus
+    }
+}
+
+fn clamp_axis(value: f32, lower: f32, upper: f32, half: f32) -> f32 {
+    if upper - lower <= half * 2.0 {
+        (lower + upper) * 0.5
+    } else {
+        value.max(lower + half).min(upper - half)
+    }
+}
+
+/// 更新带有`ComponentCameraFollow`的相机位置
+///
+/// 需要在变换传播之后、`SystemCamera`之前执行。碰撞的冲量来自`SystemPhysics`发送的`CollisionBegin`事件。
+#[derive(Default)]
+pub struct SystemCameraFollow;
+
+impl System for SystemCameraFollow {
+    fn update(&mut self, ecs: &mut ECS, delta: Duration) {
+        let dt = delta.as_secs_f32();
+        let ids = ecs
+            .query::<(With<ComponentCameraFollow>, With<ComponentTransform>)>()
+            .map(|entity| entity.id())
+            .collect::<Vec<_>>();
+
+        for id in ids {
+            let (mut follow, transform, half) = {
+                let entity = ecs.entity(id).unwrap();
+                let half = entity
+                    .get::<ComponentCamera>()
+                    .map(|camera| camera.viewport * (0.5 / camera.zoom))
+                    .unwrap_or_else(Vector2::zero);
+                (
+                    *entity.get::<ComponentCameraFollow>().unwrap(),
+                    entity.get::<ComponentTransform>().unwrap().0,
+                    half,
+                )
+            };
+            let goal = follow
+                .target
+                .and_then(|target| ecs.entity(target))
+                .and_then(|target| target.get::<ComponentTransform>().map(|t| t.0.p))
+                .map(|p| p + follow.offset);
+
+            let (mut focus, angle) = match (follow.focus, goal) {
+                (Some(focus), Some(goal)) => {
+                    let desired = Vector2::new(
+                        escape_dead_zone(focus.0.x, goal.x, follow.dead_zone.x),
+                        escape_dead_zone(focus.0.y, goal.y, follow.dead_zone.y),
+                    );
+                    let t = if follow.smoothing > 0.0 {
+                        1.0 - (-follow.smoothing * dt).exp()
+                    } else {
+                        1.0
+                    };
+                    (focus.0 + (desired - focus.0) * t, focus.1)
+                }
+                (Some(focus), None) => focus,
+                (None, goal) => (goal.unwrap_or(transform.p), transform.q.angle()),
+            };
+            if let Some(bounds) = follow.bounds {
+                focus.x = clamp_axis(focus.x, bounds.lower_bound.x, bounds.upper_bound.x, half.x);
+                focus.y = clamp_axis(focus.y, bounds.lower_bound.y, bounds.upper_bound.y, half.y);
+            }
+            follow.focus = Some((focus, angle));
+
+            follow.time += dt;
+            let shake = follow.trauma * follow.trauma;
+            let t = follow.time * follow.shake_frequency;
+            let offset =
+                Vector2::new(noise(t, 0.0), noise(t, 1.0)) * (follow.max_shake_offset * shake);
+            let shake_angle = noise(t, 2.0) * follow.max_shake_angle * shake;
+            follow.trauma = (follow.trauma - follow.trauma_decay * dt).max(0.0);
+
+            let mut entity = ecs.entity_mut(id).unwrap();
+            *entity.get_mut::<ComponentCameraFollow>().unwrap() = follow;
+            let transform = &mut entity.get_mut::<ComponentTransform>().unwrap().0;
+            transform.p = focus + offset;
+            transform.q = Rotation::new(angle + shake_angle);
+        }
+    }
+
+    fn handle_event(&mut self, ecs: &mut ECS, event: &Event) {
+        let impulse = match event.downcast_ref::<CollisionBegin>() {
+            Some(event) => event.impulse,
+            None => return,
+        };
+        let ids = ecs
+            .query::<With<ComponentCameraFollow>>()
+            .map(|entity| entity.id())
+            .collect::<Vec<_>>();
+        for id in ids {
+            let mut entity = ecs.entity_mut(id).unwrap();
+            let follow = entity.get_mut::<ComponentCameraFollow>().unwrap();
+            if follow.impulse_trauma > 0.0 && impulse >= follow.min_impulse {
+                follow.add_trauma(impulse * follow.impulse_trauma);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xmath::Transform;
+
+    #[test]
+    fn test_camera_follow() {
+        let mut ecs = ECS::new();
+        ecs.add_system(0, SystemCameraFollow);
+        let player = ecs
+            .create_entity()
+            .component(ComponentTransform(Transform::identity()))
+            .finish();
+        let camera = ecs
+            .create_entity()
+            .component(ComponentTransform(Transform::identity()))
+            .component(ComponentCamera {
+                zoom: 10.0,
+                viewport: (200.0, 100.0).into(),
+                active: true,
+            })
+            .component(ComponentCameraFollow {
+                dead_zone: (1.0, 1.0).into(),
+                smoothing: 0.0,
+                bounds: Some(AABB::new((-20.0, -20.0).into(), (20.0, 20.0).into())),
+                impulse_trauma: 0.1,
+                min_impulse: 1.0,
+                ..ComponentCameraFollow::new(player)
+            })
+            .finish();
+        let camera_position = |ecs: &ECS| {
+            ecs.entity(camera)
+                .unwrap()
+                .get::<ComponentTransform>()
+                .unwrap()
+                .0
+                .p
+        };
+        let move_player = |ecs: &mut ECS, x: f32, y: f32| {
+            let mut entity = ecs.entity_mut(player).unwrap();
+            entity.get_mut::<ComponentTransform>().unwrap().0.p = (x, y).into();
+        };
+
+        ecs.update(Duration::from_millis(100));
+        assert_eq!(camera_position(&ecs), Vector2::zero());
+
+        // 在死区内移动
+        move_player(&mut ecs, 0.5, -0.5);
+        ecs.update(Duration::from_millis(100));
+        assert_eq!(camera_position(&ecs), Vector2::zero());
+
+        // 离开死区，并且被边界限制
+        move_player(&mut ecs, 3.0, -30.0);
+        ecs.update(Duration::from_millis(100));
+        assert_eq!(camera_position(&ecs), Vector2::new(2.0, -15.0));
+
+        // 冲量太小的碰撞被忽略
+        let collision = |impulse| CollisionBegin {
+            a: player,
+            b: player,
+            normal: Vector2::new(0.0, 1.0),
+            impulse,
+        };
+        ecs.send_event(collision(0.5));
+        ecs.update(Duration::from_millis(100));
+        assert_eq!(camera_position(&ecs), Vector2::new(2.0, -15.0));
+
+        // 事件在系统更新之后分发，下一帧才开始抖动
+        ecs.send_event(collision(5.0));
+        ecs.update(Duration::from_millis(100));
+        ecs.update(Duration::from_millis(100));
+        let follow = *ecs
+            .entity(camera)
+            .unwrap()
+            .get::<ComponentCameraFollow>()
+            .unwrap();
+        assert!(follow.trauma > 0.0 && follow.trauma < 0.5);
+        assert_ne!(camera_position(&ecs), Vector2::new(2.0, -15.0));
+        assert_eq!(follow.focus(), Some(Vector2::new(2.0, -15.0)));
+
+        for _ in 0..10 {
+            ecs.update(Duration::from_millis(100));
+        }
+        assert_eq!(camera_position(&ecs), Vector2::new(2.0, -15.0));
+    }
+}
@@ -0,0 +1,315 @@
+//! 诊断信息，统计帧时间、每个系统的耗时、物理的耗时、实体数量和内存分配，并在屏幕上显示
+//!
+//! 内存分配的统计需要把`CountingAllocator`设置为全局分配器：
+//!
+//! ```ignore
+//! #[global_allocator]
+//! static ALLOCATOR: xengine::CountingAllocator = xengine::CountingAllocator;
+//! ```
+
+use crate::{Input, Key, Time};
+use std::alloc::{GlobalAlloc, Layout, System as SystemAllocator};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use xecs::{System, SystemTiming, ECS};
+use xengine_physics::{Profile, SystemPhysics};
+use xengine_render::{text_size, Color, DrawList};
+use xmath::Vector2;
+
+/// 帧时间的平滑系数，越小越平滑
+const SMOOTHING: f32 = 0.1;
+
+/// 每个字体像素对应的屏幕像素数
+const TEXT_SCALE: f32 = 2.0;
+
+/// 文字离屏幕边缘和背景边缘的距离（像素）
+const MARGIN: f32 = 8.0;
+
+/// 最多显示的系统数量，按耗时从大到小
+const MAX_SYSTEMS: usize = 12;
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static DEALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// 统计内存分配的全局分配器，实际的分配交给系统分配器
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        INSTALLED.store(true, Ordering::Relaxed);
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        LIVE_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        SystemAllocator.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        SystemAllocator.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        LIVE_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        SystemAllocator.realloc(ptr, layout, new_size)
+    }
+}
+
+/// 从程序启动开始的内存分配统计
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct AllocationStats {
+    pub allocations: usize,
+    pub deallocations: usize,
+
+    /// 当前还没有释放的字节数
+    pub live_bytes: usize,
+}
+
+impl AllocationStats {
+    /// 没有使用`CountingAllocator`时返回`None`
+    pub fn current() -> Option<AllocationStats> {
+        if !INSTALLED.load(Ordering::Relaxed) {
+            return None;
+        }
+        Some(AllocationStats {
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            deallocations: DEALLOCATIONS.load(Ordering::Relaxed),
+            live_bytes: LIVE_BYTES.load(Ordering::Relaxed),
+        })
+    }
+}
+
+/// 诊断信息，作为ECS资源使用，由`SystemDiagnostics`每帧更新
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+    /// 是否在屏幕上显示
+    pub visible: bool,
+
+    /// 切换显示的按键
+    pub toggle_key: Key,
+
+    frame_time: Duration,
+    entity_count: usize,
+    body_count: usize,
+    contact_count: usize,
+    physics: Option<Profile>,
+    systems: Vec<SystemTiming>,
+    allocations: Option<AllocationStats>,
+    frame_allocations: usize,
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Diagnostics {
+            visible: false,
+            toggle_key: Key::F3,
+            frame_time: Duration::default(),
+            entity_count: 0,
+            body_count: 0,
+            contact_count: 0,
+            physics: None,
+            systems: Vec::new(),
+            allocations: None,
+            frame_allocations: 0,
+        }
+    }
+}
+
+impl Diagnostics {
+    /// 平滑后的帧时间，不受时间缩放的影响
+    pub fn frame_time(&self) -> Duration {
+        self.frame_time
+    }
+
+    pub fn fps(&self) -> f32 {
+        let secs = self.frame_time.as_secs_f32();
+        if secs > 0.0 {
+            1.0 / secs
+        } else {
+            0.0
+        }
+    }
+
+    pub fn entity_count(&self) -> usize {
+        self.entity_count
+    }
+
+    pub fn body_count(&self) -> usize {
+        self.body_count
+    }
+
+    pub fn contact_count(&self) -> usize {
+        self.contact_count
+    }
+
+    /// 没有`SystemPhysics`时返回`None`
+    pub fn physics_profile(&self) -> Option<&Profile> {
+        self.physics.as_ref()
+    }
+
+    /// 上一帧每个系统的耗时
+    pub fn system_timings(&self) -> &[SystemTiming] {
+        &self.systems
+    }
+
+    /// 没有使用`CountingAllocator`时返回`None`
+    pub fn allocations(&self) -> Option<&AllocationStats> {
+        self.allocations.as_ref()
+    }
+
+    /// 上一帧的内存分配次数
+    pub fn frame_allocations(&self) -> usize {
+        self.frame_allocations
+    }
+
+    fn text(&self) -> String {
+        let ms = |d: Duration| d.as_secs_f32() * 1000.0;
+        let mut text = format!(
+            "FPS {:.1}  FRAME {:.2}MS\nENTITIES {}  BODIES {}  CONTACTS {}\n",
+            self.fps(),
+            ms(self.frame_time),
+            self.entity_count,
+            self.body_count,
+            self.contact_count
+        );
+        if let Some(profile) = &self.physics {
+            text += &format!(
+                "PHYSICS {:.2}MS  COLLIDE {:.2}  SOLVE {:.2}  TOI {:.2}\n",
+                ms(profile.step),
+                ms(profile.collide),
+                ms(profile.solve),
+                ms(profile.solve_toi)
+            );
+        }
+        if let Some(stats) = &self.allocations {
+            text += &format!(
+                "ALLOC {}/FRAME  LIVE {:.2}MB\n",
+                self.frame_allocations,
+                stats.live_bytes as f32 / (1024.0 * 1024.0)
+            );
+        }
+
+        let mut systems = self.systems.iter().collect::<Vec<_>>();
+        systems.sort_by(|a, b| b.time.cmp(&a.time));
+        for timing in systems.into_iter().take(MAX_SYSTEMS) {
+            text += &format!("{:6.2}MS {}\n", ms(timing.time), short_name(timing.name));
+        }
+        text
+    }
+}
+
+/// 去掉类型名里的模块路径
+fn short_name(name: &str) -> String {
+    let mut output = String::new();
+    let mut segment = String::new();
+    for c in name.chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            segment.push(c);
+        } else {
+            output += segment.rsplit("::").next().unwrap_or_default();
+            segment.clear();
+            output.push(c);
+        }
+    }
+    output += segment.rsplit("::").next().unwrap_or_default();
+    output
+}
+
+/// 更新`Diagnostics`资源，显示时把诊断信息绘制到`DrawList`的`overlay`，需要在`SystemSprites`之后执行
+#[derive(Default)]
+pub struct SystemDiagnostics {
+    last_allocations: usize,
+}
+
+impl System for SystemDiagnostics {
+    fn update(&mut self, ecs: &mut ECS, delta: Duration) {
+        let mut diagnostics = ecs.remove_resource::<Diagnostics>().unwrap_or_default();
+        if let Some(input) = ecs.resource::<Input>() {
+            if input.keyboard.just_pressed(diagnostics.toggle_key) {
+                diagnostics.visible = !diagnostics.visible;
+            }
+        }
+
+        let delta = ecs
+            .resource::<Time>()
+            .map(|time| time.real_delta())
+            .unwrap_or(delta);
+        diagnostics.frame_time = if diagnostics.frame_time == Duration::default() {
+            delta
+        } else {
+            diagnostics.frame_time.mul_f32(1.0 - SMOOTHING) + delta.mul_f32(SMOOTHING)
+        };
+        diagnostics.entity_count = ecs.entities_count();
+        diagnostics.systems = ecs.system_timings().to_vec();
+        match ecs.system::<SystemPhysics>() {
+            Some(physics) => {
+                diagnostics.body_count = physics.body_count();
+                diagnostics.contact_count = physics.contact_count();
+                diagnostics.physics = Some(*physics.profile());
+            }
+            None => {
+                diagnostics.body_count = 0;
+                diagnostics.contact_count = 0;
+                diagnostics.physics = None;
+            }
+        }
+        diagnostics.allocations = AllocationStats::current();
+        if let Some(stats) = &diagnostics.allocations {
+            diagnostics.frame_allocations = stats.allocations - self.last_allocations;
+            self.last_allocations = stats.allocations;
+        }
+
+        if diagnostics.visible {
+            if let Some(draw_list) = ecs.resource_mut::<DrawList>() {
+                let text = diagnostics.text();
+                let size = text_size(&text, TEXT_SCALE);
+                let margin = Vector2::new(MARGIN, MARGIN);
+                draw_list.push_overlay_rect(
+                    margin * 0.5,
+                    margin * 1.5 + size,
+                    Color::rgba(0.0, 0.0, 0.0, 0.6),
+                );
+                draw_list.push_text(margin, &text, TEXT_SCALE, Color::WHITE);
+            }
+        }
+        ecs.insert_resource(diagnostics);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostics() {
+        assert_eq!(
+            short_name("a::b::SystemTween<a::c::ComponentTransform>"),
+            "SystemTween<ComponentTransform>"
+        );
+
+        let mut ecs = ECS::new();
+        ecs.add_system(0, xengine_render::SystemSprites);
+        ecs.add_system(1, SystemDiagnostics::default());
+        ecs.create_entity().finish();
+        ecs.update(Duration::from_millis(20));
+        ecs.update(Duration::from_millis(20));
+
+        let diagnostics = ecs.resource::<Diagnostics>().unwrap();
+        assert!((diagnostics.fps() - 50.0).abs() < 1e-3);
+        assert_eq!(diagnostics.entity_count(), 1);
+        assert!(diagnostics.physics_profile().is_none());
+        assert!(diagnostics
+            .system_timings()
+            .iter()
+            .any(|timing| timing.name.ends_with("SystemSprites")));
+        assert!(ecs.resource::<DrawList>().unwrap().overlay.is_empty());
+
+        ecs.resource_mut::<Diagnostics>().unwrap().visible = true;
+        ecs.update(Duration::from_millis(20));
+        assert!(!ecs.resource::<DrawList>().unwrap().overlay.is_empty());
+    }
+}
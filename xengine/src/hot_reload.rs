@@ -0,0 +1,396 @@
+//! 资源热重载，文件在磁盘上改变后重新读取并原地更新受影响的实体
+//!
+//! 通过定时检查文件的修改时间和大小实现，不依赖平台的文件通知：
+//!
+//! ```ignore
+//! ecs.insert_resource(HotReload::default());
+//! ecs.add_system(0, SystemHotReload);
+//!
+//! let level = ecs.resource_mut::<HotReload>().unwrap().watch_scene("assets/level.json");
+//! ```
+
+use crate::scene_file::{patch_scene, scene_from_str, SceneFileError};
+use crate::Time;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use xecs::{EntityId, System, WeakEntity, ECS};
+use xengine_physics::tiled::load_tiled_map;
+use xengine_physics::SystemPhysics;
+
+/// 被监视的文件，由`HotReload`的`watch_*`方法返回
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct WatchId(usize);
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum AssetKind {
+    Scene,
+    TiledMap { pixels_per_meter: f32 },
+    Materials,
+}
+
+struct Watch {
+    id: WatchId,
+    path: PathBuf,
+    kind: AssetKind,
+
+    /// 上一次读取时文件的修改时间和大小
+    stamp: Option<(SystemTime, u64)>,
+
+    /// 上一次成功读取的内容，用于比较场景的改变
+    contents: Option<String>,
+
+    /// 运行时被删除的实体的id可能被别的实体重用，所以保存弱引用
+    entities: Vec<WeakEntity>,
+}
+
+/// 文件读取或者解析失败
+#[derive(Debug)]
+pub struct HotReloadError {
+    pub path: PathBuf,
+    pub error: SceneFileError,
+}
+
+/// 文件被读取或者重新读取，通过`Event::Custom`发送
+#[derive(Debug, Clone)]
+pub struct AssetReloaded {
+    pub id: WatchId,
+    pub path: PathBuf,
+
+    /// 第一次读取时为`false`
+    pub reload: bool,
+}
+
+/// 需要热重载的文件，作为ECS资源使用，由`SystemHotReload`检查和读取
+///
+/// 文件在添加后的第一次检查时读取，之后每次改变都会重新读取：
+///
+/// - 场景文件只替换改变了的组件，见`patch_scene`
+/// - Tiled地图删除之前创建的实体后重新创建
+/// - 材质文件原地更新同名的材质，使用这些材质的collider在下一次物理更新时同步
+pub struct HotReload {
+    watches: Vec<Watch>,
+    next_id: usize,
+    interval: Duration,
+    elapsed: Duration,
+    errors: Vec<HotReloadError>,
+}
+
+impl Default for HotReload {
+    fn default() -> Self {
+        HotReload {
+            watches: Vec::new(),
+            next_id: 0,
+            interval: Duration::from_millis(500),
+            elapsed: Duration::default(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+impl HotReload {
+    fn watch(&mut self, path: impl AsRef<Path>, kind: AssetKind) -> WatchId {
+        let id = WatchId(self.next_id);
+        self.next_id += 1;
+        self.watches.push(Watch {
+            id,
+            path: path.as_ref().to_path_buf(),
+            kind,
+            stamp: None,
+            contents: None,
+            entities: Vec::new(),
+        });
+        // 让新添加的文件在下一次更新时立即读取
+        self.elapsed = self.interval;
+        id
+    }
+
+    /// 监视`scene_file`格式的场景文件
+    pub fn watch_scene(&mut self, path: impl AsRef<Path>) -> WatchId {
+        self.watch(path, AssetKind::Scene)
+    }
+
+    /// 监视Tiled的JSON地图，见`xengine_physics::tiled::load_tiled_map`
+    pub fn watch_tiled_map(&mut self, path: impl AsRef<Path>, pixels_per_meter: f32) -> WatchId {
+        self.watch(path, AssetKind::TiledMap { pixels_per_meter })
+    }
+
+    /// 监视材质文件，见`SystemPhysics::load_materials`
+    pub fn watch_materials(&mut self, path: impl AsRef<Path>) -> WatchId {
+        self.watch(path, AssetKind::Materials)
+    }
+
+    /// 停止监视，已经创建的实体不会被删除
+    pub fn unwatch(&mut self, id: WatchId) {
+        self.watches.retain(|watch| watch.id != id);
+    }
+
+    /// 文件创建的实体，运行时可能已经被删除，需要通过`ECS::upgrade`检查
+    pub fn entities(&self, id: WatchId) -> &[WeakEntity] {
+        self.watches
+            .iter()
+            .find(|watch| watch.id == id)
+            .map(|watch| watch.entities.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// 检查文件的间隔，按真实时间计算，暂停时也会检查
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    /// 取出读取失败的错误，失败后保留之前的状态，文件再次改变时会重试
+    pub fn take_errors(&mut self) -> Vec<HotReloadError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// 返回需要读取的文件
+    fn poll(&mut self) -> Vec<WatchId> {
+        let mut changed = Vec::new();
+        for watch in &mut self.watches {
+            let stamp = match std::fs::metadata(&watch.path)
+                .and_then(|metadata| Ok((metadata.modified()?, metadata.len())))
+            {
+                Ok(stamp) => stamp,
+                // 编辑器保存时文件可能暂时不存在
+                Err(_) => continue,
+            };
+            if watch.stamp != Some(stamp) {
+                watch.stamp = Some(stamp);
+                changed.push(watch.id);
+            }
+        }
+        changed
+    }
+}
+
+fn load(
+    ecs: &mut ECS,
+    kind: AssetKind,
+    contents: &str,
+    previous: Option<&str>,
+    entities: &[WeakEntity],
+) -> Result<Vec<EntityId>, SceneFileError> {
+    match kind {
+        AssetKind::Scene => match previous {
+            Some(previous) => Ok(patch_scene(ecs, entities, previous, contents)?),
+            None => Ok(scene_from_str(ecs, contents)?),
+        },
+        AssetKind::TiledMap { pixels_per_meter } => {
+            // 先解析，失败时保留之前的实体
+            let mut tmp = ECS::new();
+            load_tiled_map(&mut tmp, contents, pixels_per_meter)?;
+            for weak in entities {
+                if let Some(id) = ecs.upgrade(*weak) {
+                    ecs.remove_entity(id);
+                }
+            }
+            Ok(load_tiled_map(ecs, contents, pixels_per_meter)?)
+        }
+        AssetKind::Materials => {
+            if let Some(physics) = ecs.system_mut::<SystemPhysics>() {
+                physics.load_materials(contents)?;
+            }
+            Ok(Vec::new())
+        }
+    }
+}
+
+/// 检查`HotReload`资源中的文件，读取改变了的文件
+#[derive(Default)]
+pub struct SystemHotReload;
+
+impl System for SystemHotReload {
+    fn update(&mut self, ecs: &mut ECS, delta: Duration) {
+        let delta = ecs
+            .resource::<Time>()
+            .map(|time| time.real_delta())
+            .unwrap_or(delta);
+        let changed = match ecs.resource_mut::<HotReload>() {
+            Some(hot_reload) => {
+                hot_reload.elapsed += delta;
+                if hot_reload.elapsed < hot_reload.interval {
+                    return;
+                }
+                hot_reload.elapsed = Duration::default();
+                hot_reload.poll()
+            }
+            None => return,
+        };
+
+        for id in changed {
+            let (path, kind, previous, entities) = {
+                let hot_reload = ecs.resource_mut::<HotReload>().unwrap();
+                let watch = hot_reload.watches.iter_mut().find(|w| w.id == id).unwrap();
+                (
+                    watch.path.clone(),
+                    watch.kind,
+                    watch.contents.take(),
+                    std::mem::take(&mut watch.entities),
+                )
+            };
+            let result = std::fs::read_to_string(&path)
+                .map_err(SceneFileError::from)
+                .and_then(|contents| {
+                    load(ecs, kind, &contents, previous.as_deref(), &entities)
+                        .map(|entities| (contents, entities))
+                })
+                .map(|(contents, entities)| {
+                    let entities = entities
+                        .into_iter()
+                        .filter_map(|id| ecs.weak(id))
+                        .collect::<Vec<_>>();
+                    (contents, entities)
+                });
+
+            let hot_reload = ecs.resource_mut::<HotReload>().unwrap();
+            let watch = hot_reload.watches.iter_mut().find(|w| w.id == id).unwrap();
+            let reload = previous.is_some();
+            match result {
+                Ok((contents, entities)) => {
+                    watch.contents = Some(contents);
+                    watch.entities = entities;
+                    ecs.send_event(AssetReloaded { id, path, reload });
+                }
+                Err(error) => {
+                    watch.contents = previous;
+                    watch.entities = entities;
+                    hot_reload.errors.push(HotReloadError { path, error });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene_file::scene_to_string;
+    use xengine_basecomponents::{ComponentName, ComponentTransform, ComponentVelocity};
+    use xmath::{Transform, Vector2};
+
+    #[test]
+    fn test_hot_reload_scene() {
+        let mut ecs = ECS::new();
+        ecs.create_entity()
+            .component(ComponentName::new("player"))
+            .component(ComponentTransform(Transform::identity()))
+            .finish();
+        ecs.create_entity()
+            .component(ComponentName::new("enemy"))
+            .finish();
+        let json = scene_to_string(&ecs).unwrap();
+
+        let path =
+            std::env::temp_dir().join(format!("xengine-hot-reload-{}.json", std::process::id()));
+        std::fs::write(&path, &json).unwrap();
+
+        let mut ecs = ECS::new();
+        ecs.insert_resource(HotReload::default());
+        ecs.add_system(0, SystemHotReload);
+        let id = ecs.resource_mut::<HotReload>().unwrap().watch_scene(&path);
+        ecs.update(Duration::from_millis(16));
+        let entities = ecs
+            .resource::<HotReload>()
+            .unwrap()
+            .entities(id)
+            .iter()
+            .map(|weak| ecs.upgrade(*weak).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(entities.len(), 2);
+
+        // 运行时的状态
+        let player = entities[0];
+        ecs.entity_mut(player)
+            .unwrap()
+            .get_mut::<ComponentTransform>()
+            .unwrap()
+            .0
+            .p = Vector2::new(5.0, 0.0);
+        ecs.entity_mut(player).unwrap().add(ComponentVelocity {
+            linear: Vector2::new(1.0, 0.0),
+            angular: 0.0,
+        });
+
+        // 修改名字，删除第二个实体
+        let new_json = json
+            .replace("\"player\"", "\"hero\"")
+            .replace(",\n    {\n      \"Name\": \"enemy\"\n    }", "");
+        assert_ne!(new_json.len(), json.len());
+        std::fs::write(&path, &new_json).unwrap();
+        ecs.update(Duration::from_millis(600));
+        std::fs::remove_file(&path).unwrap();
+
+        let errors = ecs.resource_mut::<HotReload>().unwrap().take_errors();
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            ecs.resource::<HotReload>().unwrap().entities(id),
+            &[ecs.weak(player).unwrap()]
+        );
+        assert!(!ecs.contains(entities[1]));
+        let entity = ecs.entity(player).unwrap();
+        assert_eq!(entity.get::<ComponentName>().unwrap().0, "hero");
+        assert_eq!(
+            entity.get::<ComponentTransform>().unwrap().0.p,
+            Vector2::new(5.0, 0.0)
+        );
+        assert!(entity.contains::<ComponentVelocity>());
+    }
+
+    #[test]
+    fn test_hot_reload_tiled_map_despawned_entity() {
+        let map = r#"{
+            "width": 2,
+            "height": 1,
+            "tilewidth": 32,
+            "tileheight": 32,
+            "layers": [
+                {
+                    "type": "objectgroup",
+                    "objects": [
+                        { "x": 0, "y": 0, "width": 32, "height": 32 },
+                        { "x": 32, "y": 0, "width": 32, "height": 32 }
+                    ]
+                }
+            ],
+            "tilesets": []
+        }"#;
+        let path =
+            std::env::temp_dir().join(format!("xengine-hot-reload-{}.tmj", std::process::id()));
+        std::fs::write(&path, map).unwrap();
+
+        let mut ecs = ECS::new();
+        ecs.insert_resource(HotReload::default());
+        ecs.add_system(0, SystemHotReload);
+        let id = ecs
+            .resource_mut::<HotReload>()
+            .unwrap()
+            .watch_tiled_map(&path, 32.0);
+        ecs.update(Duration::from_millis(16));
+        let weak = ecs.resource::<HotReload>().unwrap().entities(id)[1];
+        let despawned = ecs.upgrade(weak).unwrap();
+
+        // 运行时删除的实体的id被无关的实体重用
+        ecs.remove_entity(despawned);
+        let unrelated = ecs
+            .create_entity()
+            .component(ComponentName::new("unrelated"))
+            .finish();
+        assert_eq!(unrelated, despawned);
+
+        std::fs::write(&path, map.replace("\"x\": 32", "\"x\": 64")).unwrap();
+        ecs.update(Duration::from_millis(600));
+        std::fs::remove_file(&path).unwrap();
+
+        let errors = ecs.resource_mut::<HotReload>().unwrap().take_errors();
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(ecs.resource::<HotReload>().unwrap().entities(id).len(), 2);
+        assert_eq!(
+            ecs.entity(unrelated)
+                .unwrap()
+                .get::<ComponentName>()
+                .unwrap()
+                .0,
+            "unrelated"
+        );
+    }
+}
@@ -0,0 +1,346 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use xengine_basecomponents::CameraView;
+use xmath::Vector2;
+
+/// 键盘按键，名字和glutin的`VirtualKeyCode`一致
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Key {
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    Key0,
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    Escape,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    Insert,
+    Home,
+    Delete,
+    End,
+    PageDown,
+    PageUp,
+    Left,
+    Up,
+    Right,
+    Down,
+    Back,
+    Return,
+    Space,
+    Tab,
+    LShift,
+    RShift,
+    LControl,
+    RControl,
+    LAlt,
+    RAlt,
+    Comma,
+    Period,
+    Minus,
+    Equals,
+    Slash,
+    Backslash,
+    Semicolon,
+    Apostrophe,
+    LBracket,
+    RBracket,
+    Grave,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Other(u8),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+/// 一组按钮的状态
+///
+/// `just_pressed`和`just_released`只在按下或者松开后的那一帧有效
+#[derive(Debug, Clone)]
+pub struct Buttons<T> {
+    pressed: HashSet<T>,
+    just_pressed: HashSet<T>,
+    just_released: HashSet<T>,
+}
+
+impl<T> Default for Buttons<T> {
+    fn default() -> Self {
+        Buttons {
+            pressed: HashSet::new(),
+            just_pressed: HashSet::new(),
+            just_released: HashSet::new(),
+        }
+    }
+}
+
+impl<T: Copy + Eq + Hash> Buttons<T> {
+    pub fn press(&mut self, button: T) {
+        if self.pressed.insert(button) {
+            self.just_pressed.insert(button);
+        }
+    }
+
+    pub fn release(&mut self, button: T) {
+        if self.pressed.remove(&button) {
+            self.just_released.insert(button);
+        }
+    }
+
+    /// 松开所有按下的按钮，比如窗口失去焦点的时候
+    pub fn release_all(&mut self) {
+        self.just_released.extend(self.pressed.drain());
+    }
+
+    pub fn pressed(&self, button: T) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    pub fn just_pressed(&self, button: T) -> bool {
+        self.just_pressed.contains(&button)
+    }
+
+    pub fn just_released(&self, button: T) -> bool {
+        self.just_released.contains(&button)
+    }
+
+    pub fn iter_pressed(&self) -> impl Iterator<Item = T> + '_ {
+        self.pressed.iter().copied()
+    }
+
+    fn end_frame(&mut self) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+    }
+}
+
+/// 手柄的按钮和摇杆
+#[derive(Debug, Clone, Default)]
+pub struct Gamepad {
+    pub buttons: Buttons<GamepadButton>,
+    axes: HashMap<GamepadAxis, f32>,
+}
+
+impl Gamepad {
+    /// 摇杆的范围是-1到1，扳机的范围是0到1
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or_default()
+    }
+
+    pub fn set_axis(&mut self, axis: GamepadAxis, value: f32) {
+        self.axes.insert(axis, value);
+    }
+}
+
+/// 键盘、鼠标和手柄的输入状态，作为ECS资源使用
+///
+/// `App::run`会根据窗口事件更新键盘和鼠标，glutin不支持手柄，需要通过`gamepad_mut`自己更新。
+/// 没有窗口时也可以直接修改这个资源来模拟输入。
+#[derive(Debug, Clone, Default)]
+pub struct Input {
+    pub keyboard: Buttons<Key>,
+    pub mouse: Buttons<MouseButton>,
+    mouse_position: Vector2<f32>,
+    mouse_world_position: Option<Vector2<f32>>,
+    mouse_wheel: Vector2<f32>,
+    gamepads: HashMap<usize, Gamepad>,
+}
+
+impl Input {
+    /// 鼠标的屏幕坐标，原点在窗口左上角，y轴向下，和`CameraView`的屏幕坐标一致
+    pub fn mouse_position(&self) -> Vector2<f32> {
+        self.mouse_position
+    }
+
+    pub fn set_mouse_position(&mut self, position: Vector2<f32>) {
+        self.mouse_position = position;
+    }
+
+    /// 每帧开始时通过`CameraView`计算的鼠标世界坐标，没有相机时为`None`
+    pub fn mouse_world_position(&self) -> Option<Vector2<f32>> {
+        self.mouse_world_position
+    }
+
+    /// 这一帧滚轮的滚动量
+    pub fn mouse_wheel(&self) -> Vector2<f32> {
+        self.mouse_wheel
+    }
+
+    pub fn add_mouse_wheel(&mut self, delta: Vector2<f32>) {
+        self.mouse_wheel = self.mouse_wheel + delta;
+    }
+
+    pub fn gamepad(&self, id: usize) -> Option<&Gamepad> {
+        self.gamepads.get(&id)
+    }
+
+    /// 返回指定的手柄，不存在时会创建
+    pub fn gamepad_mut(&mut self, id: usize) -> &mut Gamepad {
+        self.gamepads.entry(id).or_default()
+    }
+
+    pub fn remove_gamepad(&mut self, id: usize) -> Option<Gamepad> {
+        self.gamepads.remove(&id)
+    }
+
+    pub fn gamepads(&self) -> impl Iterator<Item = (usize, &Gamepad)> {
+        self.gamepads.iter().map(|(id, gamepad)| (*id, gamepad))
+    }
+
+    pub(crate) fn begin_frame(&mut self, view: Option<&CameraView>) {
+        self.mouse_world_position = view.map(|view| view.screen_to_world(self.mouse_position));
+    }
+
+    pub(crate) fn end_frame(&mut self) {
+        self.keyboard.end_frame();
+        self.mouse.end_frame();
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.buttons.end_frame();
+        }
+        self.mouse_wheel = Vector2::zero();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::App;
+    use std::time::Duration;
+    use xecs::{System, ECS};
+    use xengine_basecomponents::{ComponentCamera, ComponentTransform, SystemCamera};
+    use xmath::{Rotation, Transform};
+
+    #[derive(Default)]
+    struct Jumps(usize);
+
+    impl System for Jumps {
+        fn update(&mut self, ecs: &mut ECS, _delta: Duration) {
+            if ecs
+                .resource::<Input>()
+                .unwrap()
+                .keyboard
+                .just_pressed(Key::Space)
+            {
+                self.0 += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_input() {
+        let mut app = App::builder()
+            .without_default_systems()
+            .system(0, SystemCamera)
+            .system(1, Jumps::default())
+            .startup(|ecs| {
+                ecs.create_entity()
+                    .component(ComponentTransform(Transform::new(
+                        (10.0, 0.0).into(),
+                        Rotation::new(0.0),
+                    )))
+                    .component(ComponentCamera {
+                        zoom: 10.0,
+                        viewport: (100.0, 100.0).into(),
+                        active: true,
+                    })
+                    .finish();
+            })
+            .build();
+        let frame = Duration::from_secs(1) / 60;
+
+        let input = app.ecs_mut().resource_mut::<Input>().unwrap();
+        input.keyboard.press(Key::Space);
+        input.set_mouse_position((100.0, 0.0).into());
+        app.update(frame);
+        app.update(frame);
+        assert_eq!(app.ecs().system::<Jumps>().unwrap().0, 1);
+
+        let input = app.ecs().resource::<Input>().unwrap();
+        assert!(input.keyboard.pressed(Key::Space));
+        assert!(!input.keyboard.just_pressed(Key::Space));
+        assert_eq!(input.mouse_world_position(), Some((15.0, 5.0).into()));
+
+        let input = app.ecs_mut().resource_mut::<Input>().unwrap();
+        input.keyboard.release(Key::Space);
+        assert!(input.keyboard.just_released(Key::Space));
+        app.update(frame);
+        assert!(!app
+            .ecs()
+            .resource::<Input>()
+            .unwrap()
+            .keyboard
+            .just_released(Key::Space));
+    }
+}
@@ -0,0 +1,36 @@
+//! 把ECS、基础组件、物理和渲染组合起来的引擎入口
+//!
+//! 通过`AppBuilder`创建`App`，开启`window`特性后可以用`App::run`创建窗口并运行主循环。
+
+mod app;
+mod camera_follow;
+mod diagnostics;
+mod hot_reload;
+mod input;
+mod lockstep;
+pub mod reflect;
+pub mod scene_file;
+mod scheduler;
+mod time;
+#[cfg(feature = "window")]
+mod window;
+
+pub use app::{App, AppBuilder};
+pub use camera_follow::{ComponentCameraFollow, SystemCameraFollow};
+pub use diagnostics::{AllocationStats, CountingAllocator, Diagnostics, SystemDiagnostics};
+pub use hot_reload::{AssetReloaded, HotReload, HotReloadError, SystemHotReload, WatchId};
+pub use input::{Buttons, Gamepad, GamepadAxis, GamepadButton, Input, Key, MouseButton};
+pub use lockstep::{
+    lockstep_checksum, transform_checksum, Desync, Lockstep, LockstepInputs, LockstepPhysics,
+    StateHasher,
+};
+pub use scheduler::{Scheduler, SystemScheduler, TimerId};
+pub use time::Time;
+#[cfg(feature = "window")]
+pub use window::{WindowConfig, WindowSize};
+
+pub use xecs;
+pub use xengine_basecomponents as basecomponents;
+pub use xengine_physics as physics;
+pub use xengine_render as render;
+pub use xmath;
@@ -0,0 +1,522 @@
+//! 确定性的帧同步
+//!
+//! 每个tick需要所有玩家的输入都到齐以后才执行，执行时把输入作为`LockstepInputs`资源插入ECS，
+//! 然后执行一次固定步长。每个tick结束后计算状态的校验和，和其它客户端的校验和比较来检测不同步。
+//!
+//! 游戏逻辑需要放在固定步长的系统里，并且只依赖`LockstepInputs`，帧同步期间不要再调用`App::update`。
+//!
+//! `SystemPhysics`使用f32，只能保证同一个二进制在同一种平台上的结果一致。不同平台之间帧同步时，
+//! 需要把刚体放到`LockstepPhysics`的定点数物理世界里，`Lockstep::step`会在每个tick步进它。
+
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use xecs::{EntityId, With, ECS};
+use xengine_basecomponents::ComponentTransform;
+use xfixedpoint::FP;
+use xmath::{RealConverter, Rotation, Transform, Vector2};
+use xphysics::World;
+
+/// 保留的校验和数量
+const CHECKSUM_HISTORY: usize = 256;
+
+/// FNV-1a哈希，结果不依赖平台和Rust版本，用于计算状态的校验和
+pub struct StateHasher(u64);
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        StateHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl StateHasher {
+    pub fn write_f32(&mut self, value: f32) {
+        self.write_u32(value.to_bits());
+    }
+}
+
+impl Hasher for StateHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    // 整数按小端写入，保证不同平台的结果一致
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+
+    fn write_i64(&mut self, value: i64) {
+        self.write_u64(value as u64);
+    }
+}
+
+impl StateHasher {
+    pub fn write_fp(&mut self, value: FP) {
+        self.write_i64(value.to_bits());
+    }
+}
+
+/// 所有实体的`ComponentTransform`的校验和
+pub fn transform_checksum(ecs: &ECS) -> u64 {
+    let mut hasher = StateHasher::default();
+    hash_transforms(ecs, &mut hasher);
+    hasher.finish()
+}
+
+/// 默认的校验和，包括所有实体的`ComponentTransform`和`LockstepPhysics`里所有刚体的状态
+pub fn lockstep_checksum(ecs: &ECS) -> u64 {
+    let mut hasher = StateHasher::default();
+    hash_transforms(ecs, &mut hasher);
+    if let Some(physics) = ecs.resource::<LockstepPhysics>() {
+        physics.hash_state(&mut hasher);
+    }
+    hasher.finish()
+}
+
+fn hash_transforms(ecs: &ECS, hasher: &mut StateHasher) {
+    for entity in ecs.query::<With<ComponentTransform>>() {
+        let transform = entity.get::<ComponentTransform>().unwrap().0;
+        entity.id().hash(hasher);
+        hasher.write_f32(transform.p.x);
+        hasher.write_f32(transform.p.y);
+        hasher.write_f32(transform.q.s);
+        hasher.write_f32(transform.q.c);
+    }
+}
+
+/// 帧同步用的定点数物理世界，作为资源添加到ECS
+///
+/// 刚体直接在`world`里创建，`data`是对应的实体。`Lockstep::step`在固定步长的系统执行完以后步进一次，
+/// 然后把刚体的位置转换成f32写回实体的`ComponentTransform`，写回的值只应该用于渲染。
+pub struct LockstepPhysics {
+    pub world: World<FP, EntityId>,
+    pub velocity_iterations: usize,
+    pub position_iterations: usize,
+}
+
+impl LockstepPhysics {
+    pub fn new(gravity: Vector2<FP>) -> LockstepPhysics {
+        LockstepPhysics {
+            world: World::new(gravity),
+            velocity_iterations: 8,
+            position_iterations: 3,
+        }
+    }
+
+    fn step(&mut self, dt: FP) {
+        self.world
+            .step(dt, self.velocity_iterations, self.position_iterations);
+    }
+
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        for body in self.world.bodies() {
+            body.data().hash(hasher);
+            let transform = body.transform();
+            hasher.write_fp(transform.p.x);
+            hasher.write_fp(transform.p.y);
+            hasher.write_fp(transform.q.s);
+            hasher.write_fp(transform.q.c);
+            hasher.write_fp(body.linear_velocity().x);
+            hasher.write_fp(body.linear_velocity().y);
+            hasher.write_fp(body.angular_velocity());
+        }
+    }
+
+    fn write_transforms(&self, ecs: &mut ECS) {
+        for body in self.world.bodies() {
+            let mut entity = match body.data().and_then(|id| ecs.entity_mut(*id)) {
+                Some(entity) => entity,
+                None => continue,
+            };
+            if let Some(transform) = entity.get_mut::<ComponentTransform>() {
+                let t = body.transform();
+                transform.0 = Transform {
+                    p: Vector2::new(t.p.x.to_f32(), t.p.y.to_f32()),
+                    q: Rotation {
+                        s: t.q.s.to_f32(),
+                        c: t.q.c.to_f32(),
+                    },
+                };
+            }
+        }
+    }
+}
+
+/// 当前tick所有玩家的输入，按玩家的序号排列
+#[derive(Debug, Clone)]
+pub struct LockstepInputs<I> {
+    pub tick: u64,
+    pub inputs: Vec<I>,
+}
+
+/// 检测到的不同步
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Desync {
+    pub tick: u64,
+
+    /// 校验和不一致的玩家
+    pub player: usize,
+    pub local: u64,
+    pub remote: u64,
+}
+
+type ChecksumFn = Box<dyn Fn(&ECS) -> u64>;
+type DesyncHook = Box<dyn FnMut(&Desync)>;
+
+/// 帧同步的状态，负责收集输入、驱动ECS的固定步长和比较校验和
+pub struct Lockstep<I> {
+    players: usize,
+    input_delay: u64,
+    tick: u64,
+    inputs: BTreeMap<u64, Vec<Option<I>>>,
+    checksums: BTreeMap<u64, u64>,
+    remote_checksums: BTreeMap<u64, Vec<(usize, u64)>>,
+    checksum: ChecksumFn,
+    desync_hooks: Vec<DesyncHook>,
+    desyncs: Vec<Desync>,
+}
+
+impl<I: Clone + 'static> Lockstep<I> {
+    pub fn new(players: usize) -> Lockstep<I> {
+        assert!(players > 0);
+        Lockstep {
+            players,
+            input_delay: 0,
+            tick: 0,
+            inputs: BTreeMap::new(),
+            checksums: BTreeMap::new(),
+            remote_checksums: BTreeMap::new(),
+            checksum: Box::new(lockstep_checksum),
+            desync_hooks: Vec::new(),
+            desyncs: Vec::new(),
+        }
+    }
+
+    /// 本地输入延迟的tick数，用来掩盖网络延迟
+    pub fn with_input_delay(self, ticks: u64) -> Lockstep<I> {
+        Lockstep {
+            input_delay: ticks,
+            ..self
+        }
+    }
+
+    /// 替换计算校验和的函数，默认是`lockstep_checksum`
+    pub fn with_checksum<F>(self, f: F) -> Lockstep<I>
+    where
+        F: Fn(&ECS) -> u64 + 'static,
+    {
+        Lockstep {
+            checksum: Box::new(f),
+            ..self
+        }
+    }
+
+    /// 检测到不同步时调用
+    pub fn on_desync<F>(&mut self, f: F)
+    where
+        F: FnMut(&Desync) + 'static,
+    {
+        self.desync_hooks.push(Box::new(f));
+    }
+
+    pub fn players(&self) -> usize {
+        self.players
+    }
+
+    /// 下一个要执行的tick
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// 本地现在产生的输入应该在哪个tick生效
+    pub fn local_input_tick(&self) -> u64 {
+        self.tick + self.input_delay
+    }
+
+    /// 添加玩家在某个tick的输入，已经执行过的tick返回`false`
+    pub fn add_input(&mut self, tick: u64, player: usize, input: I) -> bool {
+        assert!(player < self.players);
+        if tick < self.tick {
+            return false;
+        }
+        let players = self.players;
+        self.inputs
+            .entry(tick)
+            .or_insert_with(|| vec![None; players])[player] = Some(input);
+        true
+    }
+
+    /// 下一个tick的输入已经到齐
+    pub fn is_ready(&self) -> bool {
+        self.inputs
+            .get(&self.tick)
+            .map(|inputs| inputs.iter().all(Option::is_some))
+            .unwrap_or(false)
+    }
+
+    /// 输入到齐时执行一个tick，返回这个tick的校验和
+    pub fn step(&mut self, ecs: &mut ECS) -> Option<u64> {
+        if !self.is_ready() {
+            return None;
+        }
+        let tick = self.tick;
+        let inputs = self
+            .inputs
+            .remove(&tick)
+            .unwrap()
+            .into_iter()
+            .map(Option::unwrap)
+            .collect();
+        ecs.insert_resource(LockstepInputs { tick, inputs });
+        ecs.update(ecs.fixed_timestep());
+        ecs.remove_resource::<LockstepInputs<I>>();
+        if let Some(mut physics) = ecs.remove_resource::<LockstepPhysics>() {
+            // 步长按微秒换算成定点数，不经过浮点运算
+            let micros = ecs.fixed_timestep().as_micros() as i32;
+            physics.step(FP::i32(micros) / FP::i32(1_000_000));
+            physics.write_transforms(ecs);
+            ecs.insert_resource(physics);
+        }
+
+        let checksum = (self.checksum)(ecs);
+        self.checksums.insert(tick, checksum);
+        self.tick += 1;
+        if let Some(remote) = self.remote_checksums.remove(&tick) {
+            for (player, value) in remote {
+                self.compare(tick, player, value);
+            }
+        }
+        while self.checksums.len() > CHECKSUM_HISTORY {
+            let oldest = *self.checksums.keys().next().unwrap();
+            self.checksums.remove(&oldest);
+        }
+        Some(checksum)
+    }
+
+    /// 执行所有输入已经到齐的tick，最多`max_ticks`个，返回执行的数量
+    pub fn run(&mut self, ecs: &mut ECS, max_ticks: usize) -> usize {
+        let mut count = 0;
+        while count < max_ticks && self.step(ecs).is_some() {
+            count += 1;
+        }
+        count
+    }
+
+    /// 本地在某个tick的校验和，只保留最近的一部分
+    pub fn checksum(&self, tick: u64) -> Option<u64> {
+        self.checksums.get(&tick).copied()
+    }
+
+    /// 添加其它玩家发来的校验和，本地还没有执行到这个tick时会等到执行以后再比较
+    pub fn add_remote_checksum(&mut self, tick: u64, player: usize, checksum: u64) {
+        if tick < self.tick {
+            self.compare(tick, player, checksum);
+        } else {
+            self.remote_checksums
+                .entry(tick)
+                .or_default()
+                .push((player, checksum));
+        }
+    }
+
+    /// 所有检测到的不同步
+    pub fn desyncs(&self) -> &[Desync] {
+        &self.desyncs
+    }
+
+    fn compare(&mut self, tick: u64, player: usize, remote: u64) {
+        let local = match self.checksums.get(&tick) {
+            Some(local) => *local,
+            None => return,
+        };
+        if local != remote {
+            let desync = Desync {
+                tick,
+                player,
+                local,
+                remote,
+            };
+            for hook in &mut self.desync_hooks {
+                hook(&desync);
+            }
+            self.desyncs.push(desync);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use xecs::System;
+    use xmath::RealConstants;
+    use xphysics::{
+        BodyDef, BodyId, BodyType, FixtureDef, IntoBoxedShape, ShapeCircle, ShapePolygon,
+    };
+
+    struct Move;
+
+    impl System for Move {
+        fn update(&mut self, ecs: &mut ECS, _delta: Duration) {
+            let inputs = match ecs.resource::<LockstepInputs<Vector2<f32>>>() {
+                Some(inputs) => inputs.inputs.clone(),
+                None => return,
+            };
+            let ids = ecs
+                .query::<With<ComponentTransform>>()
+                .map(|entity| entity.id())
+                .collect::<Vec<_>>();
+            for (id, input) in ids.into_iter().zip(inputs) {
+                let mut entity = ecs.entity_mut(id).unwrap();
+                entity.get_mut::<ComponentTransform>().unwrap().0.p += input;
+            }
+        }
+    }
+
+    fn create() -> ECS {
+        let mut ecs = ECS::new();
+        ecs.add_fixed_system(0, Move);
+        for _ in 0..2 {
+            ecs.create_entity()
+                .component(ComponentTransform(Transform::identity()))
+                .finish();
+        }
+        ecs
+    }
+
+    struct Push(BodyId);
+
+    impl System for Push {
+        fn update(&mut self, ecs: &mut ECS, _delta: Duration) {
+            let inputs = match ecs.resource::<LockstepInputs<i32>>() {
+                Some(inputs) => inputs.inputs.clone(),
+                None => return,
+            };
+            let physics = ecs.resource_mut::<LockstepPhysics>().unwrap();
+            let body = physics.world.body_mut(self.0).unwrap();
+            for input in inputs {
+                body.apply_linear_impulse_to_center(Vector2::new(FP::i32(input), FP::zero()), true);
+            }
+        }
+    }
+
+    fn create_physics() -> ECS {
+        let mut ecs = ECS::new();
+        let ball = ecs
+            .create_entity()
+            .component(ComponentTransform(Transform::identity()))
+            .finish();
+        let mut physics = LockstepPhysics::new(Vector2::new(FP::zero(), FP::i32(-10)));
+        physics.world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(
+                ShapePolygon::new_box_center(FP::i32(20), FP::one()).into_boxed(),
+                FP::zero(),
+            ),
+        );
+        let ball_id = physics.world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: Vector2::new(FP::zero(), FP::i32(5)),
+                data: Some(ball),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(
+                ShapeCircle::new(Vector2::zero(), FP::one()).into_boxed(),
+                FP::one(),
+            ),
+        );
+        ecs.insert_resource(physics);
+        ecs.add_fixed_system(0, Push(ball_id));
+        ecs
+    }
+
+    #[test]
+    fn test_lockstep_physics() {
+        let mut worlds = [create_physics(), create_physics(), create_physics()];
+        let mut locksteps = [Lockstep::new(1), Lockstep::new(1), Lockstep::new(1)];
+        for tick in 0..60 {
+            locksteps[0].add_input(tick, 0, 1);
+            locksteps[1].add_input(tick, 0, 1);
+            locksteps[2].add_input(tick, 0, if tick == 30 { 2 } else { 1 });
+        }
+        for (lockstep, ecs) in locksteps.iter_mut().zip(worlds.iter_mut()) {
+            assert_eq!(lockstep.run(ecs, 100), 60);
+        }
+        for tick in 0..60 {
+            let checksum = locksteps[1].checksum(tick).unwrap();
+            locksteps[0].add_remote_checksum(tick, 0, checksum);
+            let checksum = locksteps[2].checksum(tick).unwrap();
+            locksteps[1].add_remote_checksum(tick, 0, checksum);
+        }
+        assert!(locksteps[0].desyncs().is_empty());
+        assert_eq!(locksteps[1].desyncs()[0].tick, 30);
+
+        // 刚体的位置写回了实体的变换
+        let ecs = &worlds[0];
+        let physics = ecs.resource::<LockstepPhysics>().unwrap();
+        let body = physics.world.bodies().nth(1).unwrap();
+        let id = *body.data().unwrap();
+        let transform = ecs
+            .entity(id)
+            .unwrap()
+            .get::<ComponentTransform>()
+            .unwrap()
+            .0;
+        assert!(transform.p.x > 0.0 && transform.p.y < 5.0);
+        assert_eq!(transform.p.x, body.position().x.to_f32());
+    }
+
+    #[test]
+    fn test_lockstep() {
+        let mut a = create();
+        let mut b = create();
+        let mut la = Lockstep::new(2).with_input_delay(2);
+        let mut lb = Lockstep::new(2).with_input_delay(2);
+        assert_eq!(la.local_input_tick(), 2);
+
+        for tick in 0..4 {
+            let input = Vector2::new(tick as f32, 1.0);
+            for lockstep in [&mut la, &mut lb].iter_mut() {
+                lockstep.add_input(tick, 0, input);
+            }
+        }
+        for tick in 0..3 {
+            la.add_input(tick, 1, Vector2::new(0.0, -1.0));
+            lb.add_input(tick, 1, Vector2::new(0.0, -1.0));
+        }
+        // 客户端b在tick 3收到了错误的输入
+        la.add_input(3, 1, Vector2::new(0.0, -1.0));
+        lb.add_input(3, 1, Vector2::new(0.0, 1.0));
+
+        assert_eq!(la.run(&mut a, 2), 2);
+        assert_eq!(la.run(&mut a, 10), 2);
+        assert!(!la.is_ready());
+        assert_eq!(la.step(&mut a), None);
+        assert_eq!(lb.run(&mut b, 10), 4);
+
+        let desyncs = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let hook_desyncs = desyncs.clone();
+        la.on_desync(move |desync| hook_desyncs.borrow_mut().push(desync.tick));
+        for tick in 0..4 {
+            la.add_remote_checksum(tick, 1, lb.checksum(tick).unwrap());
+        }
+        assert_eq!(*desyncs.borrow(), vec![3]);
+        assert_eq!(la.desyncs()[0].player, 1);
+        assert!(!la.add_input(0, 0, Vector2::zero()));
+    }
+}
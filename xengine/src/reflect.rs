@@ -0,0 +1,862 @@
+//! 组件反射，按名字读取和修改组件的字段，用于运行时检查器和编辑器
+//!
+//! 组件实现`Reflect`并注册到`ReflectRegistry`以后，可以通过组件名和字段名访问：
+//!
+//! ```ignore
+//! let registry = ReflectRegistry::from_ecs(&ecs);
+//! registry.set_str(&mut ecs, id, "Transform", "position", "(1, 2)")?;
+//! for component in registry.inspect(&ecs, id) {
+//!     println!("{}", component);
+//! }
+//! ```
+
+use serde_json::{Map, Value};
+use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
+use xecs::{Component, EntityId, EntityRef, ECS};
+use xengine_basecomponents::{
+    ComponentCamera, ComponentLifetime, ComponentLocalScale, ComponentLocalTransform,
+    ComponentName, ComponentParent, ComponentScale, ComponentTransform, ComponentVelocity,
+};
+use xengine_physics::{BodyType, ComponentRigidBody};
+use xengine_render::{Color, ComponentSprite};
+use xmath::{Rotation, Vector2};
+
+/// 字段的类型
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FieldType {
+    Bool,
+    Int,
+    Float,
+    Vector2,
+    Color,
+    String,
+    Entity,
+}
+
+/// 字段的值
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Bool(bool),
+    Int(i64),
+    Float(f32),
+    Vector2(Vector2<f32>),
+    Color(Color),
+    String(String),
+    Entity(Option<EntityId>),
+}
+
+fn parse_floats(s: &str) -> Option<Vec<f32>> {
+    s.trim()
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .split(',')
+        .map(|item| item.trim().parse().ok())
+        .collect()
+}
+
+impl FieldValue {
+    pub fn type_(&self) -> FieldType {
+        match self {
+            FieldValue::Bool(_) => FieldType::Bool,
+            FieldValue::Int(_) => FieldType::Int,
+            FieldValue::Float(_) => FieldType::Float,
+            FieldValue::Vector2(_) => FieldType::Vector2,
+            FieldValue::Color(_) => FieldType::Color,
+            FieldValue::String(_) => FieldType::String,
+            FieldValue::Entity(_) => FieldType::Entity,
+        }
+    }
+
+    /// 按照`Display`输出的格式解析，向量和颜色也可以省略括号，实体必须是存活的
+    pub fn parse(ecs: &ECS, type_: FieldType, s: &str) -> Option<FieldValue> {
+        let s = s.trim();
+        match type_ {
+            FieldType::Bool => s.parse().ok().map(FieldValue::Bool),
+            FieldType::Int => s.parse().ok().map(FieldValue::Int),
+            FieldType::Float => s.parse().ok().map(FieldValue::Float),
+            FieldType::Vector2 => match parse_floats(s)?.as_slice() {
+                [x, y] => Some(FieldValue::Vector2(Vector2::new(*x, *y))),
+                _ => None,
+            },
+            FieldType::Color => match parse_floats(s)?.as_slice() {
+                [r, g, b] => Some(FieldValue::Color(Color::rgb(*r, *g, *b))),
+                [r, g, b, a] => Some(FieldValue::Color(Color::rgba(*r, *g, *b, *a))),
+                _ => None,
+            },
+            FieldType::String => Some(FieldValue::String(s.to_string())),
+            FieldType::Entity if s == "none" => Some(FieldValue::Entity(None)),
+            FieldType::Entity => s
+                .trim_start_matches('#')
+                .parse()
+                .ok()
+                .and_then(|index| ecs.entity_id_at(index))
+                .map(|id| FieldValue::Entity(Some(id))),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        match self {
+            FieldValue::Bool(value) => Value::from(*value),
+            FieldValue::Int(value) => Value::from(*value),
+            FieldValue::Float(value) => Value::from(*value),
+            FieldValue::Vector2(v) => Value::from(vec![v.x, v.y]),
+            FieldValue::Color(c) => Value::from(vec![c.r, c.g, c.b, c.a]),
+            FieldValue::String(value) => Value::from(value.as_str()),
+            FieldValue::Entity(id) => id.map(|id| Value::from(id.index())).unwrap_or(Value::Null),
+        }
+    }
+
+    pub fn from_json(ecs: &ECS, type_: FieldType, value: &Value) -> Option<FieldValue> {
+        let floats = || -> Option<Vec<f32>> {
+            value
+                .as_array()?
+                .iter()
+                .map(|item| item.as_f64().map(|f| f as f32))
+                .collect()
+        };
+        match type_ {
+            FieldType::Bool => value.as_bool().map(FieldValue::Bool),
+            FieldType::Int => value.as_i64().map(FieldValue::Int),
+            FieldType::Float => value.as_f64().map(|f| FieldValue::Float(f as f32)),
+            FieldType::Vector2 => match floats()?.as_slice() {
+                [x, y] => Some(FieldValue::Vector2(Vector2::new(*x, *y))),
+                _ => None,
+            },
+            FieldType::Color => match floats()?.as_slice() {
+                [r, g, b, a] => Some(FieldValue::Color(Color::rgba(*r, *g, *b, *a))),
+                _ => None,
+            },
+            FieldType::String => value.as_str().map(|s| FieldValue::String(s.to_string())),
+            FieldType::Entity if value.is_null() => Some(FieldValue::Entity(None)),
+            FieldType::Entity => value
+                .as_u64()
+                .and_then(|index| ecs.entity_id_at(index as usize))
+                .map(|id| FieldValue::Entity(Some(id))),
+        }
+    }
+}
+
+impl Display for FieldValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::Bool(value) => write!(f, "{}", value),
+            FieldValue::Int(value) => write!(f, "{}", value),
+            FieldValue::Float(value) => write!(f, "{}", value),
+            FieldValue::Vector2(v) => write!(f, "({}, {})", v.x, v.y),
+            FieldValue::Color(c) => write!(f, "({}, {}, {}, {})", c.r, c.g, c.b, c.a),
+            FieldValue::String(value) => f.write_str(value),
+            FieldValue::Entity(Some(id)) => write!(f, "#{}", id.index()),
+            FieldValue::Entity(None) => f.write_str("none"),
+        }
+    }
+}
+
+/// 可以作为反射字段的类型
+pub trait FieldData: Sized {
+    const TYPE: FieldType;
+
+    fn to_value(&self) -> FieldValue;
+
+    fn from_value(value: FieldValue) -> Option<Self>;
+}
+
+impl FieldData for bool {
+    const TYPE: FieldType = FieldType::Bool;
+
+    fn to_value(&self) -> FieldValue {
+        FieldValue::Bool(*self)
+    }
+
+    fn from_value(value: FieldValue) -> Option<Self> {
+        match value {
+            FieldValue::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl FieldData for i32 {
+    const TYPE: FieldType = FieldType::Int;
+
+    fn to_value(&self) -> FieldValue {
+        FieldValue::Int(*self as i64)
+    }
+
+    fn from_value(value: FieldValue) -> Option<Self> {
+        match value {
+            FieldValue::Int(value) if value >= i32::MIN as i64 && value <= i32::MAX as i64 => {
+                Some(value as i32)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl FieldData for f32 {
+    const TYPE: FieldType = FieldType::Float;
+
+    fn to_value(&self) -> FieldValue {
+        FieldValue::Float(*self)
+    }
+
+    fn from_value(value: FieldValue) -> Option<Self> {
+        match value {
+            FieldValue::Float(value) => Some(value),
+            FieldValue::Int(value) => Some(value as f32),
+            _ => None,
+        }
+    }
+}
+
+impl FieldData for Vector2<f32> {
+    const TYPE: FieldType = FieldType::Vector2;
+
+    fn to_value(&self) -> FieldValue {
+        FieldValue::Vector2(*self)
+    }
+
+    fn from_value(value: FieldValue) -> Option<Self> {
+        match value {
+            FieldValue::Vector2(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl FieldData for Color {
+    const TYPE: FieldType = FieldType::Color;
+
+    fn to_value(&self) -> FieldValue {
+        FieldValue::Color(*self)
+    }
+
+    fn from_value(value: FieldValue) -> Option<Self> {
+        match value {
+            FieldValue::Color(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl FieldData for String {
+    const TYPE: FieldType = FieldType::String;
+
+    fn to_value(&self) -> FieldValue {
+        FieldValue::String(self.clone())
+    }
+
+    fn from_value(value: FieldValue) -> Option<Self> {
+        match value {
+            FieldValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl FieldData for EntityId {
+    const TYPE: FieldType = FieldType::Entity;
+
+    fn to_value(&self) -> FieldValue {
+        FieldValue::Entity(Some(*self))
+    }
+
+    fn from_value(value: FieldValue) -> Option<Self> {
+        match value {
+            FieldValue::Entity(value) => value,
+            _ => None,
+        }
+    }
+}
+
+impl FieldData for Option<EntityId> {
+    const TYPE: FieldType = FieldType::Entity;
+
+    fn to_value(&self) -> FieldValue {
+        FieldValue::Entity(*self)
+    }
+
+    fn from_value(value: FieldValue) -> Option<Self> {
+        match value {
+            FieldValue::Entity(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// 组件`C`的一个字段
+pub struct Field<C> {
+    name: &'static str,
+    type_: FieldType,
+    get: Box<dyn Fn(&C) -> FieldValue>,
+    set: Box<dyn Fn(&mut C, FieldValue) -> bool>,
+}
+
+impl<C: 'static> Field<C> {
+    /// 直接访问组件的成员
+    pub fn new<V: FieldData + 'static>(
+        name: &'static str,
+        get: fn(&C) -> &V,
+        get_mut: fn(&mut C) -> &mut V,
+    ) -> Field<C> {
+        Field {
+            name,
+            type_: V::TYPE,
+            get: Box::new(move |c| get(c).to_value()),
+            set: Box::new(move |c, value| match V::from_value(value) {
+                Some(value) => {
+                    *get_mut(c) = value;
+                    true
+                }
+                None => false,
+            }),
+        }
+    }
+
+    /// 通过getter和setter访问，用于不是直接存储在组件里的值，比如变换的角度
+    pub fn computed<V: FieldData + 'static>(
+        name: &'static str,
+        get: fn(&C) -> V,
+        set: fn(&mut C, V),
+    ) -> Field<C> {
+        Field {
+            name,
+            type_: V::TYPE,
+            get: Box::new(move |c| get(c).to_value()),
+            set: Box::new(move |c, value| match V::from_value(value) {
+                Some(value) => {
+                    set(c, value);
+                    true
+                }
+                None => false,
+            }),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn type_(&self) -> FieldType {
+        self.type_
+    }
+
+    pub fn get(&self, c: &C) -> FieldValue {
+        (self.get)(c)
+    }
+
+    /// 类型不匹配时返回`false`
+    pub fn set(&self, c: &mut C, value: FieldValue) -> bool {
+        (self.set)(c, value)
+    }
+}
+
+/// 可以反射的组件
+pub trait Reflect: Component + Sized {
+    fn fields() -> Vec<Field<Self>>;
+}
+
+#[derive(Debug)]
+pub enum ReflectError {
+    UnknownComponent(String),
+    UnknownField(String),
+    EntityNotFound,
+
+    /// 实体没有这个组件
+    MissingComponent(&'static str),
+
+    /// 值的类型和字段不匹配，或者字符串无法解析
+    InvalidValue {
+        field: String,
+        expected: FieldType,
+    },
+}
+
+impl Display for ReflectError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ReflectError::UnknownComponent(name) => write!(f, "unknown component: {}", name),
+            ReflectError::UnknownField(name) => write!(f, "unknown field: {}", name),
+            ReflectError::EntityNotFound => f.write_str("entity not found"),
+            ReflectError::MissingComponent(name) => write!(f, "missing component: {}", name),
+            ReflectError::InvalidValue { field, expected } => {
+                write!(f, "invalid value for {}, expected {:?}", field, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReflectError {}
+
+/// 字段的名字和类型
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FieldInfo {
+    pub name: &'static str,
+    pub type_: FieldType,
+}
+
+/// 一个组件所有字段的值，`Display`每行输出一个字段
+#[derive(Debug, Clone)]
+pub struct ComponentValues {
+    pub name: &'static str,
+    pub fields: Vec<(&'static str, FieldValue)>,
+}
+
+impl Display for ComponentValues {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        for (name, value) in &self.fields {
+            write!(f, "\n    {}: {}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+type GetFieldsFn = fn(&EntityRef) -> Option<Vec<(&'static str, FieldValue)>>;
+type SetFieldFn = fn(&mut ECS, EntityId, &str, FieldValue) -> Result<(), ReflectError>;
+
+#[derive(Clone)]
+struct ReflectEntry {
+    name: &'static str,
+    fields: Vec<FieldInfo>,
+    get: GetFieldsFn,
+    set: SetFieldFn,
+}
+
+fn get_fields<C: Reflect>(entity: &EntityRef) -> Option<Vec<(&'static str, FieldValue)>> {
+    let c = entity.get::<C>()?;
+    Some(
+        C::fields()
+            .iter()
+            .map(|field| (field.name, field.get(c)))
+            .collect(),
+    )
+}
+
+fn set_field<C: Reflect>(
+    ecs: &mut ECS,
+    id: EntityId,
+    name: &str,
+    value: FieldValue,
+) -> Result<(), ReflectError> {
+    let field = C::fields()
+        .into_iter()
+        .find(|field| field.name == name)
+        .ok_or_else(|| ReflectError::UnknownField(name.to_string()))?;
+    let mut entity = ecs.entity_mut(id).ok_or(ReflectError::EntityNotFound)?;
+    let c = entity
+        .get_mut::<C>()
+        .ok_or_else(|| ReflectError::MissingComponent(C::name()))?;
+    if field.set(c, value) {
+        Ok(())
+    } else {
+        Err(ReflectError::InvalidValue {
+            field: name.to_string(),
+            expected: field.type_,
+        })
+    }
+}
+
+/// 可以反射的组件，作为ECS资源使用，没有这个资源时使用默认注册的组件
+///
+/// 默认注册了基础组件、刚体和精灵
+#[derive(Clone)]
+pub struct ReflectRegistry {
+    components: Vec<ReflectEntry>,
+}
+
+impl Default for ReflectRegistry {
+    fn default() -> Self {
+        let mut registry = ReflectRegistry::empty();
+        registry.register::<ComponentTransform>();
+        registry.register::<ComponentLocalTransform>();
+        registry.register::<ComponentScale>();
+        registry.register::<ComponentLocalScale>();
+        registry.register::<ComponentParent>();
+        registry.register::<ComponentName>();
+        registry.register::<ComponentVelocity>();
+        registry.register::<ComponentLifetime>();
+        registry.register::<ComponentCamera>();
+        registry.register::<ComponentRigidBody>();
+        registry.register::<ComponentSprite>();
+        registry
+    }
+}
+
+impl ReflectRegistry {
+    /// 没有注册任何组件
+    pub fn empty() -> ReflectRegistry {
+        ReflectRegistry {
+            components: Vec::new(),
+        }
+    }
+
+    pub fn register<C: Reflect>(&mut self) {
+        self.components.retain(|entry| entry.name != C::name());
+        self.components.push(ReflectEntry {
+            name: C::name(),
+            fields: C::fields()
+                .iter()
+                .map(|field| FieldInfo {
+                    name: field.name,
+                    type_: field.type_,
+                })
+                .collect(),
+            get: get_fields::<C>,
+            set: set_field::<C>,
+        });
+    }
+
+    /// ECS里的`ReflectRegistry`资源，没有时返回默认注册的组件
+    pub fn from_ecs(ecs: &ECS) -> ReflectRegistry {
+        ecs.resource::<ReflectRegistry>()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn entry(&self, component: &str) -> Result<&ReflectEntry, ReflectError> {
+        self.components
+            .iter()
+            .find(|entry| entry.name == component)
+            .ok_or_else(|| ReflectError::UnknownComponent(component.to_string()))
+    }
+
+    pub fn component_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.components.iter().map(|entry| entry.name)
+    }
+
+    /// 组件的所有字段，组件没有注册时返回`None`
+    pub fn fields(&self, component: &str) -> Option<&[FieldInfo]> {
+        self.entry(component)
+            .ok()
+            .map(|entry| entry.fields.as_slice())
+    }
+
+    /// 实体所有注册过的组件的字段值
+    pub fn inspect(&self, ecs: &ECS, id: EntityId) -> Vec<ComponentValues> {
+        let entity = match ecs.entity(id) {
+            Some(entity) => entity,
+            None => return Vec::new(),
+        };
+        self.components
+            .iter()
+            .filter_map(|entry| {
+                (entry.get)(&entity).map(|fields| ComponentValues {
+                    name: entry.name,
+                    fields,
+                })
+            })
+            .collect()
+    }
+
+    pub fn get(
+        &self,
+        ecs: &ECS,
+        id: EntityId,
+        component: &str,
+        field: &str,
+    ) -> Result<FieldValue, ReflectError> {
+        let entry = self.entry(component)?;
+        let entity = ecs.entity(id).ok_or(ReflectError::EntityNotFound)?;
+        let fields = (entry.get)(&entity).ok_or(ReflectError::MissingComponent(entry.name))?;
+        fields
+            .into_iter()
+            .find(|(name, _)| *name == field)
+            .map(|(_, value)| value)
+            .ok_or_else(|| ReflectError::UnknownField(field.to_string()))
+    }
+
+    pub fn set(
+        &self,
+        ecs: &mut ECS,
+        id: EntityId,
+        component: &str,
+        field: &str,
+        value: FieldValue,
+    ) -> Result<(), ReflectError> {
+        (self.entry(component)?.set)(ecs, id, field, value)
+    }
+
+    /// 按照字段的类型解析字符串并设置，用于控制台和检查器的输入
+    pub fn set_str(
+        &self,
+        ecs: &mut ECS,
+        id: EntityId,
+        component: &str,
+        field: &str,
+        value: &str,
+    ) -> Result<(), ReflectError> {
+        let entry = self.entry(component)?;
+        let info = entry
+            .fields
+            .iter()
+            .find(|info| info.name == field)
+            .ok_or_else(|| ReflectError::UnknownField(field.to_string()))?;
+        let value =
+            FieldValue::parse(ecs, info.type_, value).ok_or(ReflectError::InvalidValue {
+                field: field.to_string(),
+                expected: info.type_,
+            })?;
+        (entry.set)(ecs, id, field, value)
+    }
+
+    /// 把实体所有注册过的组件转换为`{组件: {字段: 值}}`
+    pub fn to_json(&self, ecs: &ECS, id: EntityId) -> Value {
+        let mut components = Map::new();
+        for component in self.inspect(ecs, id) {
+            let fields = component
+                .fields
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_json()))
+                .collect();
+            components.insert(component.name.to_string(), Value::Object(fields));
+        }
+        Value::Object(components)
+    }
+
+    /// 把`to_json`的结果写回实体已有的组件，不会添加组件，缺少的字段保持不变
+    pub fn apply_json(
+        &self,
+        ecs: &mut ECS,
+        id: EntityId,
+        value: &Value,
+    ) -> Result<(), ReflectError> {
+        let components = match value.as_object() {
+            Some(components) => components,
+            None => return Ok(()),
+        };
+        for (component, fields) in components {
+            let entry = self.entry(component)?;
+            for (field, value) in fields.as_object().into_iter().flatten() {
+                let info = entry
+                    .fields
+                    .iter()
+                    .find(|info| info.name == field)
+                    .ok_or_else(|| ReflectError::UnknownField(field.clone()))?;
+                let value = FieldValue::from_json(ecs, info.type_, value).ok_or_else(|| {
+                    ReflectError::InvalidValue {
+                        field: field.clone(),
+                        expected: info.type_,
+                    }
+                })?;
+                (entry.set)(ecs, id, field, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn angle(transform: &xmath::Transform<f32>) -> f32 {
+    transform.q.angle()
+}
+
+impl Reflect for ComponentTransform {
+    fn fields() -> Vec<Field<Self>> {
+        vec![
+            Field::new("position", |c| &c.0.p, |c| &mut c.0.p),
+            Field::computed("angle", |c| angle(&c.0), |c, a| c.0.q = Rotation::new(a)),
+        ]
+    }
+}
+
+impl Reflect for ComponentLocalTransform {
+    fn fields() -> Vec<Field<Self>> {
+        vec![
+            Field::new("position", |c| &c.0.p, |c| &mut c.0.p),
+            Field::computed("angle", |c| angle(&c.0), |c, a| c.0.q = Rotation::new(a)),
+        ]
+    }
+}
+
+impl Reflect for ComponentScale {
+    fn fields() -> Vec<Field<Self>> {
+        vec![Field::new("scale", |c| &c.0, |c| &mut c.0)]
+    }
+}
+
+impl Reflect for ComponentLocalScale {
+    fn fields() -> Vec<Field<Self>> {
+        vec![Field::new("scale", |c| &c.0, |c| &mut c.0)]
+    }
+}
+
+impl Reflect for ComponentParent {
+    fn fields() -> Vec<Field<Self>> {
+        vec![Field::new("parent", |c| &c.0, |c| &mut c.0)]
+    }
+}
+
+impl Reflect for ComponentName {
+    fn fields() -> Vec<Field<Self>> {
+        vec![Field::new("name", |c| &c.0, |c| &mut c.0)]
+    }
+}
+
+impl Reflect for ComponentVelocity {
+    fn fields() -> Vec<Field<Self>> {
+        vec![
+            Field::new("linear", |c| &c.linear, |c| &mut c.linear),
+            Field::new("angular", |c| &c.angular, |c| &mut c.angular),
+        ]
+    }
+}
+
+impl Reflect for ComponentLifetime {
+    fn fields() -> Vec<Field<Self>> {
+        vec![Field::computed(
+            "seconds",
+            |c| c.0.as_secs_f32(),
+            |c, seconds| c.0 = Duration::from_secs_f32(seconds.max(0.0)),
+        )]
+    }
+}
+
+impl Reflect for ComponentCamera {
+    fn fields() -> Vec<Field<Self>> {
+        vec![
+            Field::new("zoom", |c| &c.zoom, |c| &mut c.zoom),
+            Field::new("viewport", |c| &c.viewport, |c| &mut c.viewport),
+            Field::new("active", |c| &c.active, |c| &mut c.active),
+        ]
+    }
+}
+
+fn body_type_name(type_: BodyType) -> String {
+    match type_ {
+        BodyType::Static => "static",
+        BodyType::Kinematic => "kinematic",
+        BodyType::Dynamic => "dynamic",
+    }
+    .to_string()
+}
+
+fn set_body_type(c: &mut ComponentRigidBody, name: String) {
+    match name.as_str() {
+        "static" => c.type_ = BodyType::Static,
+        "kinematic" => c.type_ = BodyType::Kinematic,
+        "dynamic" => c.type_ = BodyType::Dynamic,
+        _ => {}
+    }
+}
+
+impl Reflect for ComponentRigidBody {
+    fn fields() -> Vec<Field<Self>> {
+        vec![
+            Field::computed("type", |c| body_type_name(c.type_), set_body_type),
+            Field::new(
+                "linear_velocity",
+                |c| &c.linear_velocity,
+                |c| &mut c.linear_velocity,
+            ),
+            Field::new(
+                "angular_velocity",
+                |c| &c.angular_velocity,
+                |c| &mut c.angular_velocity,
+            ),
+            Field::new(
+                "linear_damping",
+                |c| &c.linear_damping,
+                |c| &mut c.linear_damping,
+            ),
+            Field::new(
+                "angular_damping",
+                |c| &c.angular_damping,
+                |c| &mut c.angular_damping,
+            ),
+            Field::new(
+                "gravity_scale",
+                |c| &c.gravity_scale,
+                |c| &mut c.gravity_scale,
+            ),
+            Field::new("allow_sleep", |c| &c.allow_sleep, |c| &mut c.allow_sleep),
+            Field::new("awake", |c| &c.awake, |c| &mut c.awake),
+            Field::new(
+                "fixed_rotation",
+                |c| &c.fixed_rotation,
+                |c| &mut c.fixed_rotation,
+            ),
+            Field::new("bullet", |c| &c.bullet, |c| &mut c.bullet),
+            Field::new("active", |c| &c.active, |c| &mut c.active),
+        ]
+    }
+}
+
+impl Reflect for ComponentSprite {
+    fn fields() -> Vec<Field<Self>> {
+        vec![
+            Field::new("size", |c| &c.size, |c| &mut c.size),
+            Field::new("color", |c| &c.color, |c| &mut c.color),
+            Field::new("layer", |c| &c.layer, |c| &mut c.layer),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xmath::Transform;
+
+    #[test]
+    fn test_reflect() {
+        let mut ecs = ECS::new();
+        let id = ecs
+            .create_entity()
+            .component(ComponentTransform(Transform::identity()))
+            .component(ComponentName::new("player"))
+            .finish();
+        let registry = ReflectRegistry::from_ecs(&ecs);
+
+        let fields = registry.fields("Transform").unwrap();
+        assert_eq!(fields[0].name, "position");
+        assert_eq!(fields[1].type_, FieldType::Float);
+
+        registry
+            .set_str(&mut ecs, id, "Transform", "position", "(1, 2)")
+            .unwrap();
+        registry
+            .set(&mut ecs, id, "Transform", "angle", FieldValue::Float(0.5))
+            .unwrap();
+        let transform = ecs
+            .entity(id)
+            .unwrap()
+            .get::<ComponentTransform>()
+            .unwrap()
+            .0;
+        assert_eq!(transform.p, Vector2::new(1.0, 2.0));
+        assert!((transform.q.angle() - 0.5).abs() < 1e-6);
+        assert_eq!(
+            registry.get(&ecs, id, "Name", "name").unwrap(),
+            FieldValue::String("player".to_string())
+        );
+
+        assert!(matches!(
+            registry.set_str(&mut ecs, id, "Transform", "position", "abc"),
+            Err(ReflectError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            registry.set(&mut ecs, id, "Transform", "angle", FieldValue::Bool(true)),
+            Err(ReflectError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            registry.get(&ecs, id, "Camera", "zoom"),
+            Err(ReflectError::MissingComponent("Camera"))
+        ));
+
+        let values = registry.inspect(&ecs, id);
+        assert_eq!(values.len(), 2);
+        assert_eq!(
+            values[0].to_string(),
+            "Transform\n    position: (1, 2)\n    angle: 0.5"
+        );
+
+        let json = registry.to_json(&ecs, id);
+        registry
+            .set_str(&mut ecs, id, "Name", "name", "enemy")
+            .unwrap();
+        registry.apply_json(&mut ecs, id, &json).unwrap();
+        assert_eq!(
+            ecs.entity(id).unwrap().get::<ComponentName>().unwrap().0,
+            "player"
+        );
+    }
+}
@@ -0,0 +1,1172 @@
+//! 场景文件，把实体、注册过的组件和资源保存为JSON
+//!
+//! 文件的格式如下，每个实体是组件名到组件数据的映射，实体之间的引用使用实体在数组中的下标：
+//!
+//! ```json
+//! {
+//!     "entities": [
+//!         { "Transform": { "position": [0, 1], "angle": 0 }, "Name": "player" },
+//!         { "Transform": { "position": [1, 1], "angle": 0 }, "Parent": 0 }
+//!     ],
+//!     "resources": { "PhysicsSettings": { ... } }
+//! }
+//! ```
+//!
+//! 没有注册的组件和资源不会被保存，读取时会忽略不认识的名字。
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+use xecs::{Component, EntityId, EntityRef, WeakEntity, ECS};
+use xengine_basecomponents::{
+    ComponentCamera, ComponentChildren, ComponentDisabled, ComponentLifetime, ComponentLocalScale,
+    ComponentLocalTransform, ComponentName, ComponentParent, ComponentScale, ComponentTransform,
+    ComponentVelocity,
+};
+use xengine_physics::{
+    BodyType, ColliderMaterial, ComponentColliderBox, ComponentColliderChain,
+    ComponentColliderCircle, ComponentColliderCompound, ComponentColliderEdge,
+    ComponentColliderPolygon, ComponentRigidBody, PhysicsSettings, ShapeDesc, ShapeKind,
+};
+use xengine_render::{Color, ComponentSprite, TextureHandle};
+use xmath::{Rotation, Transform, Vector2};
+use xphysics::Filter;
+
+/// 保存时把实体转换为文件中的下标
+pub struct SaveContext {
+    indices: HashMap<EntityId, usize>,
+}
+
+impl SaveContext {
+    /// 使用`WeakEntity::to_bits`作为下标，用于脚本等不经过场景文件的转换，和`LoadContext::handles`对应
+    pub fn handles(ecs: &ECS) -> SaveContext {
+        SaveContext {
+            indices: ecs
+                .entities()
+                .filter_map(|id| ecs.weak(id))
+                .map(|weak| (weak.id(), weak.to_bits() as usize))
+                .collect(),
+        }
+    }
+
+    pub fn entity_index(&self, id: EntityId) -> Option<usize> {
+        self.indices.get(&id).copied()
+    }
+}
+
+/// 读取时把文件中的下标转换为新创建的实体
+pub struct LoadContext {
+    entities: HashMap<usize, EntityId>,
+}
+
+impl LoadContext {
+    /// 通过`WeakEntity::from_bits`转换下标，已经删除的实体会被忽略，和`SaveContext::handles`对应
+    pub fn handles(ecs: &ECS) -> LoadContext {
+        LoadContext {
+            entities: ecs
+                .entities()
+                .filter_map(|id| ecs.weak(id))
+                .map(|weak| (weak.to_bits() as usize, weak.id()))
+                .collect(),
+        }
+    }
+
+    pub fn entity(&self, index: usize) -> Option<EntityId> {
+        self.entities.get(&index).copied()
+    }
+}
+
+/// 可以保存到场景文件的组件，文件中使用`Component::name`作为名字
+pub trait SceneComponent: Component + Sized {
+    type Data: Serialize + DeserializeOwned;
+
+    fn to_data(&self, ctx: &SaveContext) -> Self::Data;
+
+    /// 返回`None`时不添加这个组件，比如引用的实体不存在
+    fn from_data(data: Self::Data, ctx: &LoadContext) -> Option<Self>;
+}
+
+/// 可以保存到场景文件的资源
+pub trait SceneResource: Sized + 'static {
+    type Data: Serialize + DeserializeOwned;
+
+    const NAME: &'static str;
+
+    fn to_data(&self) -> Self::Data;
+
+    fn from_data(data: Self::Data) -> Self;
+}
+
+type SaveComponentFn = fn(&EntityRef, &SaveContext) -> Option<serde_json::Result<Value>>;
+type LoadComponentFn = fn(&mut ECS, EntityId, Value, &LoadContext) -> serde_json::Result<()>;
+type RemoveComponentFn = fn(&mut ECS, EntityId);
+type SaveResourceFn = fn(&ECS) -> Option<serde_json::Result<Value>>;
+type LoadResourceFn = fn(&mut ECS, Value) -> serde_json::Result<()>;
+
+fn save_component<C: SceneComponent>(
+    entity: &EntityRef,
+    ctx: &SaveContext,
+) -> Option<serde_json::Result<Value>> {
+    entity
+        .get::<C>()
+        .map(|c| serde_json::to_value(c.to_data(ctx)))
+}
+
+fn load_component<C: SceneComponent>(
+    ecs: &mut ECS,
+    id: EntityId,
+    value: Value,
+    ctx: &LoadContext,
+) -> serde_json::Result<()> {
+    if let Some(c) = C::from_data(serde_json::from_value(value)?, ctx) {
+        ecs.entity_mut(id).unwrap().add(c);
+    }
+    Ok(())
+}
+
+fn remove_component<C: Component>(ecs: &mut ECS, id: EntityId) {
+    if let Some(mut entity) = ecs.entity_mut(id) {
+        entity.remove::<C>();
+    }
+}
+
+fn save_resource<R: SceneResource>(ecs: &ECS) -> Option<serde_json::Result<Value>> {
+    ecs.resource::<R>()
+        .map(|resource| serde_json::to_value(resource.to_data()))
+}
+
+fn load_resource<R: SceneResource>(ecs: &mut ECS, value: Value) -> serde_json::Result<()> {
+    ecs.insert_resource(R::from_data(serde_json::from_value(value)?));
+    Ok(())
+}
+
+/// 场景文件支持的组件和资源，作为ECS资源使用，没有这个资源时使用默认注册的组件和资源
+///
+/// 默认注册了基础组件、刚体、collider、精灵和`PhysicsSettings`
+#[derive(Clone)]
+pub struct SceneRegistry {
+    components: Vec<ComponentEntry>,
+    resources: Vec<(&'static str, SaveResourceFn, LoadResourceFn)>,
+}
+
+#[derive(Clone)]
+struct ComponentEntry {
+    name: &'static str,
+    save: SaveComponentFn,
+    load: LoadComponentFn,
+    remove: RemoveComponentFn,
+}
+
+impl SceneRegistry {
+    /// 没有注册任何组件和资源
+    pub fn empty() -> SceneRegistry {
+        SceneRegistry {
+            components: Vec::new(),
+            resources: Vec::new(),
+        }
+    }
+
+    pub fn register_component<C: SceneComponent>(&mut self) {
+        self.components.retain(|entry| entry.name != C::name());
+        self.components.push(ComponentEntry {
+            name: C::name(),
+            save: save_component::<C>,
+            load: load_component::<C>,
+            remove: remove_component::<C>,
+        });
+    }
+
+    pub fn register_resource<R: SceneResource>(&mut self) {
+        self.resources.retain(|(name, _, _)| *name != R::NAME);
+        self.resources
+            .push((R::NAME, save_resource::<R>, load_resource::<R>));
+    }
+
+    /// ECS里的`SceneRegistry`资源，没有时返回默认注册的组件和资源
+    pub fn from_ecs(ecs: &ECS) -> SceneRegistry {
+        ecs.resource::<SceneRegistry>().cloned().unwrap_or_default()
+    }
+
+    fn component(&self, name: &str) -> Option<&ComponentEntry> {
+        self.components.iter().find(|entry| entry.name == name)
+    }
+
+    /// 所有注册的组件的名字
+    pub fn component_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.components.iter().map(|entry| entry.name)
+    }
+
+    /// 把实体的组件转换为JSON，组件没有注册或者实体没有这个组件时返回`None`
+    pub fn component_to_json(
+        &self,
+        ecs: &ECS,
+        id: EntityId,
+        name: &str,
+        ctx: &SaveContext,
+    ) -> Option<serde_json::Result<Value>> {
+        let entity = ecs.entity(id)?;
+        (self.component(name)?.save)(&entity, ctx)
+    }
+
+    /// 通过JSON添加或者替换实体的组件，组件没有注册或者实体不存在时返回`false`
+    pub fn component_from_json(
+        &self,
+        ecs: &mut ECS,
+        id: EntityId,
+        name: &str,
+        value: Value,
+        ctx: &LoadContext,
+    ) -> serde_json::Result<bool> {
+        match self.component(name) {
+            Some(entry) if ecs.entity(id).is_some() => {
+                (entry.load)(ecs, id, value, ctx)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// 删除实体的组件，组件没有注册时返回`false`
+    pub fn remove_component(&self, ecs: &mut ECS, id: EntityId, name: &str) -> bool {
+        match self.component(name) {
+            Some(entry) => {
+                (entry.remove)(ecs, id);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Default for SceneRegistry {
+    fn default() -> Self {
+        let mut registry = SceneRegistry::empty();
+        registry.register_component::<ComponentTransform>();
+        registry.register_component::<ComponentLocalTransform>();
+        registry.register_component::<ComponentScale>();
+        registry.register_component::<ComponentLocalScale>();
+        registry.register_component::<ComponentParent>();
+        registry.register_component::<ComponentChildren>();
+        registry.register_component::<ComponentName>();
+        registry.register_component::<ComponentDisabled>();
+        registry.register_component::<ComponentVelocity>();
+        registry.register_component::<ComponentLifetime>();
+        registry.register_component::<ComponentCamera>();
+        registry.register_component::<ComponentRigidBody>();
+        registry.register_component::<ComponentColliderCircle>();
+        registry.register_component::<ComponentColliderBox>();
+        registry.register_component::<ComponentColliderPolygon>();
+        registry.register_component::<ComponentColliderEdge>();
+        registry.register_component::<ComponentColliderChain>();
+        registry.register_component::<ComponentColliderCompound>();
+        registry.register_component::<ComponentSprite>();
+        registry.register_resource::<PhysicsSettings>();
+        registry
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct SceneFile {
+    #[serde(default)]
+    entities: Vec<BTreeMap<String, Value>>,
+    #[serde(default)]
+    resources: BTreeMap<String, Value>,
+}
+
+/// 把所有实体和注册过的资源保存为JSON字符串
+pub fn scene_to_string(ecs: &ECS) -> serde_json::Result<String> {
+    let registry = SceneRegistry::from_ecs(ecs);
+    let ids = ecs.entities().collect::<Vec<_>>();
+    let ctx = SaveContext {
+        indices: ids.iter().enumerate().map(|(i, id)| (*id, i)).collect(),
+    };
+
+    let mut file = SceneFile::default();
+    for id in ids {
+        let entity = ecs.entity(id).unwrap();
+        let mut components = BTreeMap::new();
+        for entry in &registry.components {
+            if let Some(value) = (entry.save)(&entity, &ctx) {
+                components.insert(entry.name.to_string(), value?);
+            }
+        }
+        file.entities.push(components);
+    }
+    for (name, save, _) in &registry.resources {
+        if let Some(value) = save(ecs) {
+            file.resources.insert(name.to_string(), value?);
+        }
+    }
+    serde_json::to_string_pretty(&file)
+}
+
+/// 读取`scene_to_string`保存的场景，创建所有实体并替换文件中的资源，返回创建的实体
+///
+/// 不会删除已经存在的实体
+pub fn scene_from_str(ecs: &mut ECS, json: &str) -> serde_json::Result<Vec<EntityId>> {
+    let registry = SceneRegistry::from_ecs(ecs);
+    let file: SceneFile = serde_json::from_str(json)?;
+
+    // 先创建所有实体，组件里才能引用后面的实体
+    let entities = file
+        .entities
+        .iter()
+        .map(|_| ecs.create_entity().finish())
+        .collect::<Vec<_>>();
+    let ctx = LoadContext {
+        entities: entities.iter().copied().enumerate().collect(),
+    };
+    for (id, components) in entities.iter().zip(file.entities) {
+        for (name, value) in components {
+            if let Some(entry) = registry.component(&name) {
+                (entry.load)(ecs, *id, value, &ctx)?;
+            }
+        }
+    }
+    for (name, value) in file.resources {
+        if let Some((_, _, load)) = registry.resources.iter().find(|(n, _, _)| *n == name) {
+            load(ecs, value)?;
+        }
+    }
+    Ok(entities)
+}
+
+/// 把场景从`old_json`更新到`new_json`，`entities`是读取`old_json`时创建的实体的弱引用，返回更新后的实体
+///
+/// 按实体在文件中的下标对应，只有文件中改变了的组件和资源会被替换，没有改变的组件和不在文件中的组件保持运行时的状态。
+/// 文件中新增的实体会被创建，删除的实体会被删除，运行时被删除的实体会按照新的文件重新创建，
+/// 即使它的id已经被别的实体重用。
+pub fn patch_scene(
+    ecs: &mut ECS,
+    entities: &[WeakEntity],
+    old_json: &str,
+    new_json: &str,
+) -> serde_json::Result<Vec<EntityId>> {
+    let registry = SceneRegistry::from_ecs(ecs);
+    let old: SceneFile = serde_json::from_str(old_json)?;
+    let new: SceneFile = serde_json::from_str(new_json)?;
+
+    for weak in entities.iter().skip(new.entities.len()) {
+        if let Some(id) = ecs.upgrade(*weak) {
+            ecs.remove_entity(id);
+        }
+    }
+
+    // 运行时被删除的实体和文件中新增的实体一样重新创建，需要读取所有的组件
+    let mut created = vec![false; new.entities.len()];
+    let entities = (0..new.entities.len())
+        .map(|index| {
+            let weak = entities.get(index);
+            match weak.and_then(|weak| ecs.upgrade(*weak)) {
+                Some(id) => id,
+                None => {
+                    created[index] = true;
+                    ecs.create_entity().finish()
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    let ctx = LoadContext {
+        entities: entities.iter().copied().enumerate().collect(),
+    };
+
+    let empty = BTreeMap::new();
+    for (index, components) in new.entities.into_iter().enumerate() {
+        let id = entities[index];
+        let old_components = match created[index] {
+            true => &empty,
+            false => old.entities.get(index).unwrap_or(&empty),
+        };
+        for name in old_components.keys() {
+            if !components.contains_key(name) {
+                registry.remove_component(ecs, id, name);
+            }
+        }
+        for (name, value) in components {
+            if old_components.get(&name) == Some(&value) {
+                continue;
+            }
+            if let Some(entry) = registry.component(&name) {
+                (entry.load)(ecs, id, value, &ctx)?;
+            }
+        }
+    }
+    for (name, value) in new.resources {
+        if old.resources.get(&name) == Some(&value) {
+            continue;
+        }
+        if let Some((_, _, load)) = registry.resources.iter().find(|(n, _, _)| *n == name) {
+            load(ecs, value)?;
+        }
+    }
+    Ok(entities)
+}
+
+#[derive(Debug)]
+pub enum SceneFileError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for SceneFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneFileError::Io(err) => write!(f, "io error: {}", err),
+            SceneFileError::Json(err) => write!(f, "json error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SceneFileError {}
+
+impl From<std::io::Error> for SceneFileError {
+    fn from(err: std::io::Error) -> Self {
+        SceneFileError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SceneFileError {
+    fn from(err: serde_json::Error) -> Self {
+        SceneFileError::Json(err)
+    }
+}
+
+pub fn save_scene(ecs: &ECS, path: impl AsRef<Path>) -> Result<(), SceneFileError> {
+    std::fs::write(path, scene_to_string(ecs)?)?;
+    Ok(())
+}
+
+pub fn load_scene(ecs: &mut ECS, path: impl AsRef<Path>) -> Result<Vec<EntityId>, SceneFileError> {
+    let json = std::fs::read_to_string(path)?;
+    Ok(scene_from_str(ecs, &json)?)
+}
+
+type Vec2 = [f32; 2];
+
+fn to_vec2(v: Vector2<f32>) -> Vec2 {
+    [v.x, v.y]
+}
+
+fn from_vec2(v: Vec2) -> Vector2<f32> {
+    Vector2::new(v[0], v[1])
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TransformData {
+    position: Vec2,
+    angle: f32,
+}
+
+impl TransformData {
+    fn new(transform: &Transform<f32>) -> TransformData {
+        TransformData {
+            position: to_vec2(transform.p),
+            angle: transform.q.angle(),
+        }
+    }
+
+    fn transform(&self) -> Transform<f32> {
+        Transform::new(from_vec2(self.position), Rotation::new(self.angle))
+    }
+}
+
+impl SceneComponent for ComponentTransform {
+    type Data = TransformData;
+
+    fn to_data(&self, _ctx: &SaveContext) -> Self::Data {
+        TransformData::new(&self.0)
+    }
+
+    fn from_data(data: Self::Data, _ctx: &LoadContext) -> Option<Self> {
+        Some(ComponentTransform(data.transform()))
+    }
+}
+
+impl SceneComponent for ComponentLocalTransform {
+    type Data = TransformData;
+
+    fn to_data(&self, _ctx: &SaveContext) -> Self::Data {
+        TransformData::new(&self.0)
+    }
+
+    fn from_data(data: Self::Data, _ctx: &LoadContext) -> Option<Self> {
+        Some(ComponentLocalTransform(data.transform()))
+    }
+}
+
+impl SceneComponent for ComponentScale {
+    type Data = Vec2;
+
+    fn to_data(&self, _ctx: &SaveContext) -> Self::Data {
+        to_vec2(self.0)
+    }
+
+    fn from_data(data: Self::Data, _ctx: &LoadContext) -> Option<Self> {
+        Some(ComponentScale(from_vec2(data)))
+    }
+}
+
+impl SceneComponent for ComponentLocalScale {
+    type Data = Vec2;
+
+    fn to_data(&self, _ctx: &SaveContext) -> Self::Data {
+        to_vec2(self.0)
+    }
+
+    fn from_data(data: Self::Data, _ctx: &LoadContext) -> Option<Self> {
+        Some(ComponentLocalScale(from_vec2(data)))
+    }
+}
+
+impl SceneComponent for ComponentParent {
+    type Data = Option<usize>;
+
+    fn to_data(&self, ctx: &SaveContext) -> Self::Data {
+        ctx.entity_index(self.0)
+    }
+
+    fn from_data(data: Self::Data, ctx: &LoadContext) -> Option<Self> {
+        data.and_then(|index| ctx.entity(index))
+            .map(ComponentParent)
+    }
+}
+
+impl SceneComponent for ComponentChildren {
+    type Data = Vec<usize>;
+
+    fn to_data(&self, ctx: &SaveContext) -> Self::Data {
+        self.0
+            .iter()
+            .filter_map(|id| ctx.entity_index(*id))
+            .collect()
+    }
+
+    fn from_data(data: Self::Data, ctx: &LoadContext) -> Option<Self> {
+        Some(ComponentChildren(
+            data.into_iter()
+                .filter_map(|index| ctx.entity(index))
+                .collect(),
+        ))
+    }
+}
+
+impl SceneComponent for ComponentName {
+    type Data = String;
+
+    fn to_data(&self, _ctx: &SaveContext) -> Self::Data {
+        self.0.clone()
+    }
+
+    fn from_data(data: Self::Data, _ctx: &LoadContext) -> Option<Self> {
+        Some(ComponentName(data))
+    }
+}
+
+impl SceneComponent for ComponentDisabled {
+    type Data = ();
+
+    fn to_data(&self, _ctx: &SaveContext) -> Self::Data {}
+
+    fn from_data(_data: Self::Data, _ctx: &LoadContext) -> Option<Self> {
+        Some(ComponentDisabled)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct VelocityData {
+    linear: Vec2,
+    angular: f32,
+}
+
+impl SceneComponent for ComponentVelocity {
+    type Data = VelocityData;
+
+    fn to_data(&self, _ctx: &SaveContext) -> Self::Data {
+        VelocityData {
+            linear: to_vec2(self.linear),
+            angular: self.angular,
+        }
+    }
+
+    fn from_data(data: Self::Data, _ctx: &LoadContext) -> Option<Self> {
+        Some(ComponentVelocity {
+            linear: from_vec2(data.linear),
+            angular: data.angular,
+        })
+    }
+}
+
+impl SceneComponent for ComponentLifetime {
+    /// 剩余的秒数
+    type Data = f32;
+
+    fn to_data(&self, _ctx: &SaveContext) -> Self::Data {
+        self.0.as_secs_f32()
+    }
+
+    fn from_data(data: Self::Data, _ctx: &LoadContext) -> Option<Self> {
+        Some(ComponentLifetime(Duration::from_secs_f32(data.max(0.0))))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CameraData {
+    zoom: f32,
+    viewport: Vec2,
+    active: bool,
+}
+
+impl SceneComponent for ComponentCamera {
+    type Data = CameraData;
+
+    fn to_data(&self, _ctx: &SaveContext) -> Self::Data {
+        CameraData {
+            zoom: self.zoom,
+            viewport: to_vec2(self.viewport),
+            active: self.active,
+        }
+    }
+
+    fn from_data(data: Self::Data, _ctx: &LoadContext) -> Option<Self> {
+        Some(ComponentCamera {
+            zoom: data.zoom,
+            viewport: from_vec2(data.viewport),
+            active: data.active,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyTypeData {
+    Static,
+    Kinematic,
+    Dynamic,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RigidBodyData {
+    #[serde(rename = "type")]
+    type_: BodyTypeData,
+    linear_velocity: Vec2,
+    angular_velocity: f32,
+    linear_damping: f32,
+    angular_damping: f32,
+    allow_sleep: bool,
+    awake: bool,
+    fixed_rotation: bool,
+    bullet: bool,
+    active: bool,
+    gravity_scale: f32,
+}
+
+impl SceneComponent for ComponentRigidBody {
+    type Data = RigidBodyData;
+
+    fn to_data(&self, _ctx: &SaveContext) -> Self::Data {
+        RigidBodyData {
+            type_: match self.type_ {
+                BodyType::Static => BodyTypeData::Static,
+                BodyType::Kinematic => BodyTypeData::Kinematic,
+                BodyType::Dynamic => BodyTypeData::Dynamic,
+            },
+            linear_velocity: to_vec2(self.linear_velocity),
+            angular_velocity: self.angular_velocity,
+            linear_damping: self.linear_damping,
+            angular_damping: self.angular_damping,
+            allow_sleep: self.allow_sleep,
+            awake: self.awake,
+            fixed_rotation: self.fixed_rotation,
+            bullet: self.bullet,
+            active: self.active,
+            gravity_scale: self.gravity_scale,
+        }
+    }
+
+    fn from_data(data: Self::Data, _ctx: &LoadContext) -> Option<Self> {
+        Some(ComponentRigidBody {
+            type_: match data.type_ {
+                BodyTypeData::Static => BodyType::Static,
+                BodyTypeData::Kinematic => BodyType::Kinematic,
+                BodyTypeData::Dynamic => BodyType::Dynamic,
+            },
+            linear_velocity: from_vec2(data.linear_velocity),
+            angular_velocity: data.angular_velocity,
+            linear_damping: data.linear_damping,
+            angular_damping: data.angular_damping,
+            allow_sleep: data.allow_sleep,
+            awake: data.awake,
+            fixed_rotation: data.fixed_rotation,
+            bullet: data.bullet,
+            active: data.active,
+            gravity_scale: data.gravity_scale,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FixtureData {
+    friction: f32,
+    restitution: f32,
+    density: f32,
+    is_sensor: bool,
+    category_bits: u16,
+    mask_bits: u16,
+    group_index: i16,
+}
+
+impl FixtureData {
+    fn new(fixture: &ColliderMaterial) -> FixtureData {
+        FixtureData {
+            friction: fixture.friction,
+            restitution: fixture.restitution,
+            density: fixture.density,
+            is_sensor: fixture.is_sensor,
+            category_bits: fixture.filter.category_bits,
+            mask_bits: fixture.filter.mask_bits,
+            group_index: fixture.filter.group_index,
+        }
+    }
+
+    fn fixture(&self) -> ColliderMaterial {
+        ColliderMaterial {
+            friction: self.friction,
+            restitution: self.restitution,
+            density: self.density,
+            is_sensor: self.is_sensor,
+            filter: Filter {
+                category_bits: self.category_bits,
+                mask_bits: self.mask_bits,
+                group_index: self.group_index,
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ShapeData {
+    Circle { radius: f32 },
+    Box { half_size: Vec2 },
+    Polygon { vertices: Vec<Vec2> },
+    Edge { vertex1: Vec2, vertex2: Vec2 },
+    Chain { is_loop: bool, vertices: Vec<Vec2> },
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ColliderData {
+    fixture: FixtureData,
+    transform: TransformData,
+    #[serde(flatten)]
+    shape: ShapeData,
+}
+
+impl ColliderData {
+    fn new(
+        fixture: &ColliderMaterial,
+        transform: &Transform<f32>,
+        shape: ShapeData,
+    ) -> ColliderData {
+        ColliderData {
+            fixture: FixtureData::new(fixture),
+            transform: TransformData::new(transform),
+            shape,
+        }
+    }
+}
+
+fn to_vertices(vertices: &[Vector2<f32>]) -> Vec<Vec2> {
+    vertices.iter().map(|v| to_vec2(*v)).collect()
+}
+
+fn from_vertices(vertices: Vec<Vec2>) -> Vec<Vector2<f32>> {
+    vertices.into_iter().map(from_vec2).collect()
+}
+
+impl SceneComponent for ComponentColliderCircle {
+    type Data = ColliderData;
+
+    fn to_data(&self, _ctx: &SaveContext) -> Self::Data {
+        let shape = ShapeData::Circle {
+            radius: self.radius,
+        };
+        ColliderData::new(&self.fixture, &self.transform, shape)
+    }
+
+    fn from_data(data: Self::Data, _ctx: &LoadContext) -> Option<Self> {
+        match data.shape {
+            ShapeData::Circle { radius } => Some(ComponentColliderCircle {
+                fixture: data.fixture.fixture(),
+                transform: data.transform.transform(),
+                radius,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl SceneComponent for ComponentColliderBox {
+    type Data = ColliderData;
+
+    fn to_data(&self, _ctx: &SaveContext) -> Self::Data {
+        let shape = ShapeData::Box {
+            half_size: to_vec2(self.half_size),
+        };
+        ColliderData::new(&self.fixture, &self.transform, shape)
+    }
+
+    fn from_data(data: Self::Data, _ctx: &LoadContext) -> Option<Self> {
+        match data.shape {
+            ShapeData::Box { half_size } => Some(ComponentColliderBox {
+                fixture: data.fixture.fixture(),
+                transform: data.transform.transform(),
+                half_size: from_vec2(half_size),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl SceneComponent for ComponentColliderPolygon {
+    type Data = ColliderData;
+
+    fn to_data(&self, _ctx: &SaveContext) -> Self::Data {
+        let shape = ShapeData::Polygon {
+            vertices: to_vertices(&self.vertices),
+        };
+        ColliderData::new(&self.fixture, &self.transform, shape)
+    }
+
+    fn from_data(data: Self::Data, _ctx: &LoadContext) -> Option<Self> {
+        match data.shape {
+            ShapeData::Polygon { vertices } => Some(ComponentColliderPolygon {
+                fixture: data.fixture.fixture(),
+                transform: data.transform.transform(),
+                vertices: from_vertices(vertices),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl SceneComponent for ComponentColliderEdge {
+    type Data = ColliderData;
+
+    fn to_data(&self, _ctx: &SaveContext) -> Self::Data {
+        let shape = ShapeData::Edge {
+            vertex1: to_vec2(self.vertex1),
+            vertex2: to_vec2(self.vertex2),
+        };
+        ColliderData::new(&self.fixture, &self.transform, shape)
+    }
+
+    fn from_data(data: Self::Data, _ctx: &LoadContext) -> Option<Self> {
+        match data.shape {
+            ShapeData::Edge { vertex1, vertex2 } => Some(ComponentColliderEdge {
+                fixture: data.fixture.fixture(),
+                transform: data.transform.transform(),
+                vertex1: from_vec2(vertex1),
+                vertex2: from_vec2(vertex2),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl SceneComponent for ComponentColliderChain {
+    type Data = ColliderData;
+
+    fn to_data(&self, _ctx: &SaveContext) -> Self::Data {
+        let shape = ShapeData::Chain {
+            is_loop: self.is_loop,
+            vertices: to_vertices(&self.vertices),
+        };
+        ColliderData::new(&self.fixture, &self.transform, shape)
+    }
+
+    fn from_data(data: Self::Data, _ctx: &LoadContext) -> Option<Self> {
+        match data.shape {
+            ShapeData::Chain { is_loop, vertices } => Some(ComponentColliderChain {
+                fixture: data.fixture.fixture(),
+                transform: data.transform.transform(),
+                is_loop,
+                vertices: from_vertices(vertices),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl SceneComponent for ComponentColliderCompound {
+    type Data = Vec<ColliderData>;
+
+    fn to_data(&self, _ctx: &SaveContext) -> Self::Data {
+        self.0
+            .iter()
+            .map(|desc| {
+                let shape = match &desc.kind {
+                    ShapeKind::Circle { radius } => ShapeData::Circle { radius: *radius },
+                    ShapeKind::Box { half_size } => ShapeData::Box {
+                        half_size: to_vec2(*half_size),
+                    },
+                    ShapeKind::Polygon { vertices } => ShapeData::Polygon {
+                        vertices: to_vertices(vertices),
+                    },
+                };
+                ColliderData::new(&desc.fixture, &desc.transform, shape)
+            })
+            .collect()
+    }
+
+    fn from_data(data: Self::Data, _ctx: &LoadContext) -> Option<Self> {
+        let shapes = data
+            .into_iter()
+            .filter_map(|data| {
+                let kind = match data.shape {
+                    ShapeData::Circle { radius } => ShapeKind::Circle { radius },
+                    ShapeData::Box { half_size } => ShapeKind::Box {
+                        half_size: from_vec2(half_size),
+                    },
+                    ShapeData::Polygon { vertices } => ShapeKind::Polygon {
+                        vertices: from_vertices(vertices),
+                    },
+                    // 组合碰撞体不支持边和链
+                    _ => return None,
+                };
+                Some(ShapeDesc {
+                    fixture: data.fixture.fixture(),
+                    transform: data.transform.transform(),
+                    kind,
+                })
+            })
+            .collect();
+        Some(ComponentColliderCompound(shapes))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SpriteData {
+    /// 纹理在`Textures`中的下标，读取场景前需要按同样的顺序添加纹理
+    texture: Option<usize>,
+    size: Vec2,
+    color: [f32; 4],
+    layer: i32,
+}
+
+impl SceneComponent for ComponentSprite {
+    type Data = SpriteData;
+
+    fn to_data(&self, _ctx: &SaveContext) -> Self::Data {
+        SpriteData {
+            texture: self.texture.map(TextureHandle::index),
+            size: to_vec2(self.size),
+            color: [self.color.r, self.color.g, self.color.b, self.color.a],
+            layer: self.layer,
+        }
+    }
+
+    fn from_data(data: Self::Data, _ctx: &LoadContext) -> Option<Self> {
+        let [r, g, b, a] = data.color;
+        Some(ComponentSprite {
+            texture: data.texture.map(TextureHandle::from_index),
+            size: from_vec2(data.size),
+            color: Color::rgba(r, g, b, a),
+            layer: data.layer,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PhysicsSettingsData {
+    gravity: Vec2,
+    velocity_iterations: usize,
+    position_iterations: usize,
+    sub_stepping: bool,
+    continuous_physics: bool,
+    fixed_dt: Option<f32>,
+}
+
+impl SceneResource for PhysicsSettings {
+    type Data = PhysicsSettingsData;
+
+    const NAME: &'static str = "PhysicsSettings";
+
+    fn to_data(&self) -> Self::Data {
+        PhysicsSettingsData {
+            gravity: to_vec2(self.gravity),
+            velocity_iterations: self.velocity_iterations,
+            position_iterations: self.position_iterations,
+            sub_stepping: self.sub_stepping,
+            continuous_physics: self.continuous_physics,
+            fixed_dt: self.fixed_dt,
+        }
+    }
+
+    fn from_data(data: Self::Data) -> Self {
+        PhysicsSettings {
+            gravity: from_vec2(data.gravity),
+            velocity_iterations: data.velocity_iterations,
+            position_iterations: data.position_iterations,
+            sub_stepping: data.sub_stepping,
+            continuous_physics: data.continuous_physics,
+            fixed_dt: data.fixed_dt,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scene_round_trip() {
+        let mut ecs = ECS::new();
+        let parent = ecs
+            .create_entity()
+            .component(ComponentName::new("parent"))
+            .component(ComponentTransform(Transform::new(
+                Vector2::new(1.0, 2.0),
+                Rotation::new(0.5),
+            )))
+            .component(ComponentRigidBody {
+                type_: BodyType::Dynamic,
+                ..ComponentRigidBody::default()
+            })
+            .component(ComponentColliderCircle {
+                fixture: ColliderMaterial {
+                    friction: 0.3,
+                    restitution: 0.1,
+                    density: 2.0,
+                    is_sensor: false,
+                    filter: Filter::default(),
+                },
+                transform: Transform::identity(),
+                radius: 0.5,
+            })
+            .finish();
+        ecs.create_entity()
+            .component(ComponentParent(parent))
+            .component(ComponentSprite {
+                texture: Some(TextureHandle::from_index(2)),
+                size: Vector2::new(1.0, 1.0),
+                color: Color::rgba(1.0, 0.5, 0.0, 1.0),
+                layer: 3,
+            })
+            .finish();
+        ecs.insert_resource(PhysicsSettings {
+            gravity: Vector2::new(0.0, -5.0),
+            ..PhysicsSettings::default()
+        });
+
+        let json = scene_to_string(&ecs).unwrap();
+        let mut loaded = ECS::new();
+        let entities = scene_from_str(&mut loaded, &json).unwrap();
+        assert_eq!(entities.len(), 2);
+
+        let parent = loaded.entity(entities[0]).unwrap();
+        assert_eq!(parent.get::<ComponentName>().unwrap().0, "parent");
+        let transform = parent.get::<ComponentTransform>().unwrap().0;
+        assert_eq!(transform.p, Vector2::new(1.0, 2.0));
+        assert!((transform.q.angle() - 0.5).abs() < 1e-5);
+        assert_eq!(
+            parent.get::<ComponentRigidBody>().unwrap().type_,
+            BodyType::Dynamic
+        );
+        let circle = parent.get::<ComponentColliderCircle>().unwrap();
+        assert_eq!(circle.radius, 0.5);
+        assert_eq!(circle.fixture.density, 2.0);
+
+        let child = loaded.entity(entities[1]).unwrap();
+        assert_eq!(child.get::<ComponentParent>().unwrap().0, entities[0]);
+        let sprite = child.get::<ComponentSprite>().unwrap();
+        assert_eq!(sprite.texture, Some(TextureHandle::from_index(2)));
+        assert_eq!(sprite.layer, 3);
+
+        assert_eq!(
+            loaded.resource::<PhysicsSettings>().unwrap().gravity,
+            Vector2::new(0.0, -5.0)
+        );
+    }
+
+    #[test]
+    fn test_patch_scene_despawned_entity() {
+        let mut ecs = ECS::new();
+        ecs.create_entity()
+            .component(ComponentName::new("player"))
+            .finish();
+        ecs.create_entity()
+            .component(ComponentName::new("enemy"))
+            .component(ComponentTransform(Transform::identity()))
+            .finish();
+        let json = scene_to_string(&ecs).unwrap();
+
+        let mut ecs = ECS::new();
+        let entities = scene_from_str(&mut ecs, &json).unwrap();
+        let weak = entities
+            .iter()
+            .map(|id| ecs.weak(*id).unwrap())
+            .collect::<Vec<_>>();
+        ecs.remove_entity(entities[1]);
+
+        // 被删除的实体的id被无关的实体重用
+        let unrelated = ecs
+            .create_entity()
+            .component(ComponentName::new("unrelated"))
+            .finish();
+        assert_eq!(unrelated, entities[1]);
+
+        // 只有第一个实体改变了，被删除的实体也要读取所有的组件
+        let new_json = json.replace("\"player\"", "\"hero\"");
+        let entities = patch_scene(&mut ecs, &weak, &json, &new_json).unwrap();
+        assert_ne!(entities[1], unrelated);
+        let unrelated = ecs.entity(unrelated).unwrap();
+        assert_eq!(unrelated.get::<ComponentName>().unwrap().0, "unrelated");
+        assert!(!unrelated.contains::<ComponentTransform>());
+        let enemy = ecs.entity(entities[1]).unwrap();
+        assert_eq!(enemy.get::<ComponentName>().unwrap().0, "enemy");
+        assert!(enemy.contains::<ComponentTransform>());
+        assert_eq!(
+            ecs.entity(entities[0])
+                .unwrap()
+                .get::<ComponentName>()
+                .unwrap()
+                .0,
+            "hero"
+        );
+    }
+
+    #[test]
+    fn test_patch_scene_remove_despawned_entity() {
+        let mut ecs = ECS::new();
+        ecs.create_entity()
+            .component(ComponentName::new("player"))
+            .finish();
+        ecs.create_entity()
+            .component(ComponentName::new("enemy"))
+            .finish();
+        let json = scene_to_string(&ecs).unwrap();
+
+        let mut ecs = ECS::new();
+        let entities = scene_from_str(&mut ecs, &json).unwrap();
+        let weak = entities
+            .iter()
+            .map(|id| ecs.weak(*id).unwrap())
+            .collect::<Vec<_>>();
+        ecs.remove_entity(entities[1]);
+
+        // 文件中也删除了运行时已经删除的实体
+        let mut new_scene = ECS::new();
+        new_scene
+            .create_entity()
+            .component(ComponentName::new("player"))
+            .finish();
+        let new_json = scene_to_string(&new_scene).unwrap();
+        let entities = patch_scene(&mut ecs, &weak, &json, &new_json).unwrap();
+        assert_eq!(entities.len(), 1);
+        assert_eq!(ecs.entities().count(), 1);
+    }
+}
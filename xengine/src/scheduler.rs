@@ -0,0 +1,213 @@
+use crate::Time;
+use std::time::Duration;
+use xecs::{System, ECS};
+
+/// `Scheduler`返回的定时器标识，用于取消定时器
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct TimerId(u64);
+
+enum Callback {
+    Once(Box<dyn FnOnce(&mut ECS)>),
+    Repeat(Box<dyn FnMut(&mut ECS)>),
+}
+
+struct Timer {
+    id: TimerId,
+    due: Duration,
+    interval: Duration,
+    callback: Callback,
+}
+
+/// 延时和重复执行的回调，作为ECS资源使用，由`SystemScheduler`按`Time`资源的时间驱动
+///
+/// 时间是缩放后的游戏时间，暂停时定时器也会暂停。回调里可以通过ECS资源继续添加或者取消定时器。
+///
+/// ```ignore
+/// let scheduler = ecs.resource_mut::<Scheduler>().unwrap();
+/// scheduler.after(2.0, |ecs| spawn_boss(ecs));
+/// scheduler.every(0.5, |ecs| spawn_enemy(ecs));
+/// ```
+#[derive(Default)]
+pub struct Scheduler {
+    now: Duration,
+    next_id: u64,
+    timers: Vec<Timer>,
+
+    /// 正在执行的定时器，以及它是否在回调中被取消
+    running: Option<(TimerId, bool)>,
+}
+
+impl Scheduler {
+    fn add(&mut self, delay: Duration, interval: Duration, callback: Callback) -> TimerId {
+        let id = TimerId(self.next_id);
+        self.next_id += 1;
+        self.timers.push(Timer {
+            id,
+            due: self.now + delay,
+            interval,
+            callback,
+        });
+        id
+    }
+
+    /// `seconds`秒后执行一次`f`
+    pub fn after<F>(&mut self, seconds: f32, f: F) -> TimerId
+    where
+        F: FnOnce(&mut ECS) + 'static,
+    {
+        let delay = Duration::from_secs_f32(seconds.max(0.0));
+        self.add(delay, Duration::default(), Callback::Once(Box::new(f)))
+    }
+
+    /// 每隔`seconds`秒执行一次`f`，直到被取消
+    ///
+    /// 一帧的时间超过间隔时会在这一帧执行多次
+    pub fn every<F>(&mut self, seconds: f32, f: F) -> TimerId
+    where
+        F: FnMut(&mut ECS) + 'static,
+    {
+        assert!(seconds > 0.0, "the interval must be greater than 0");
+        let interval = Duration::from_secs_f32(seconds);
+        self.add(interval, interval, Callback::Repeat(Box::new(f)))
+    }
+
+    /// 取消定时器，定时器不存在或者已经执行完时返回`false`
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        match &mut self.running {
+            Some((running, cancelled)) if *running == id => {
+                let was_cancelled = *cancelled;
+                *cancelled = true;
+                return !was_cancelled;
+            }
+            _ => {}
+        }
+        match self.timers.iter().position(|timer| timer.id == id) {
+            Some(index) => {
+                self.timers.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 定时器是否还在等待执行
+    pub fn contains(&self, id: TimerId) -> bool {
+        match self.running {
+            Some((running, cancelled)) if running == id => !cancelled,
+            _ => self.timers.iter().any(|timer| timer.id == id),
+        }
+    }
+
+    /// 取消所有的定时器
+    pub fn clear(&mut self) {
+        self.timers.clear();
+        if let Some((_, cancelled)) = &mut self.running {
+            *cancelled = true;
+        }
+    }
+
+    /// 取出最早到期的定时器，同时到期时按添加的顺序
+    fn pop_due(&mut self) -> Option<Timer> {
+        let now = self.now;
+        let index = self
+            .timers
+            .iter()
+            .enumerate()
+            .filter(|(_, timer)| timer.due <= now)
+            .min_by_key(|(_, timer)| (timer.due, timer.id))
+            .map(|(index, _)| index)?;
+        let timer = self.timers.remove(index);
+        self.running = Some((timer.id, false));
+        Some(timer)
+    }
+}
+
+/// 执行`Scheduler`资源中到期的定时器
+#[derive(Default)]
+pub struct SystemScheduler;
+
+impl System for SystemScheduler {
+    fn update(&mut self, ecs: &mut ECS, delta: Duration) {
+        let now = ecs.resource::<Time>().map(|time| time.elapsed());
+        match ecs.resource_mut::<Scheduler>() {
+            Some(scheduler) => scheduler.now = now.unwrap_or(scheduler.now + delta),
+            None => return,
+        }
+
+        loop {
+            let mut timer = match ecs.resource_mut::<Scheduler>().and_then(Scheduler::pop_due) {
+                Some(timer) => timer,
+                None => break,
+            };
+            let repeat = match timer.callback {
+                Callback::Once(f) => {
+                    f(ecs);
+                    None
+                }
+                Callback::Repeat(mut f) => {
+                    f(ecs);
+                    timer.due += timer.interval;
+                    Some(Callback::Repeat(f))
+                }
+            };
+
+            // 回调可能删除了`Scheduler`资源
+            if let Some(scheduler) = ecs.resource_mut::<Scheduler>() {
+                let cancelled = matches!(scheduler.running.take(), Some((_, true)));
+                if let (Some(callback), false) = (repeat, cancelled) {
+                    scheduler.timers.push(Timer { callback, ..timer });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::App;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_scheduler() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut app = App::builder().build();
+        let scheduler = app.ecs_mut().resource_mut::<Scheduler>().unwrap();
+        let every = scheduler.every(0.1, {
+            let log = log.clone();
+            move |_| log.borrow_mut().push("every")
+        });
+        scheduler.after(0.25, {
+            let log = log.clone();
+            move |ecs| {
+                log.borrow_mut().push("after");
+                let scheduler = ecs.resource_mut::<Scheduler>().unwrap();
+                assert!(scheduler.cancel(every));
+            }
+        });
+        let cancelled = scheduler.after(0.05, |_| panic!("cancelled"));
+        assert!(scheduler.cancel(cancelled));
+        assert!(!scheduler.contains(cancelled));
+
+        app.update(Duration::from_millis(150));
+        assert_eq!(*log.borrow(), vec!["every"]);
+
+        // 暂停时不执行
+        app.ecs_mut()
+            .resource_mut::<Time>()
+            .unwrap()
+            .set_paused(true);
+        app.update(Duration::from_millis(500));
+        assert_eq!(*log.borrow(), vec!["every"]);
+        app.ecs_mut()
+            .resource_mut::<Time>()
+            .unwrap()
+            .set_paused(false);
+
+        app.update(Duration::from_millis(150));
+        assert_eq!(*log.borrow(), vec!["every", "every", "after"]);
+        app.update(Duration::from_millis(500));
+        assert_eq!(*log.borrow(), vec!["every", "every", "after"]);
+    }
+}
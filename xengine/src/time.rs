@@ -0,0 +1,146 @@
+use std::time::Duration;
+
+/// 时间信息，作为ECS资源使用，由`App`每帧更新
+///
+/// `App`按`time_scale`缩放帧间隔后再执行系统，暂停时帧间隔为0，
+/// 所以系统收到的`delta`、固定步长的次数和这里的`delta`始终一致。
+#[derive(Debug, Copy, Clone)]
+pub struct Time {
+    delta: Duration,
+    real_delta: Duration,
+    fixed_delta: Duration,
+    elapsed: Duration,
+    real_elapsed: Duration,
+    frame_count: u64,
+    time_scale: f32,
+    paused: bool,
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Time {
+            delta: Duration::default(),
+            real_delta: Duration::default(),
+            fixed_delta: Duration::default(),
+            elapsed: Duration::default(),
+            real_elapsed: Duration::default(),
+            frame_count: 0,
+            time_scale: 1.0,
+            paused: false,
+        }
+    }
+}
+
+impl Time {
+    /// 这一帧缩放后的帧间隔
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /// 这一帧没有缩放的帧间隔
+    pub fn real_delta(&self) -> Duration {
+        self.real_delta
+    }
+
+    /// 固定步长
+    pub fn fixed_delta(&self) -> Duration {
+        self.fixed_delta
+    }
+
+    /// 缩放后的累计时间，暂停时不增加
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// 没有缩放的累计时间
+    pub fn real_elapsed(&self) -> Duration {
+        self.real_elapsed
+    }
+
+    /// 已经执行的帧数，包括暂停的帧
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// 小于1时是慢动作，必须是不小于0的有限值
+    pub fn set_time_scale(&mut self, scale: f32) {
+        assert!(scale.is_finite() && scale >= 0.0);
+        self.time_scale = scale;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// 开始新的一帧，返回缩放后的帧间隔
+    pub(crate) fn advance(&mut self, real_delta: Duration, fixed_delta: Duration) -> Duration {
+        self.real_delta = real_delta;
+        self.delta = if self.paused {
+            Duration::default()
+        } else {
+            real_delta.mul_f32(self.time_scale)
+        };
+        self.fixed_delta = fixed_delta;
+        self.elapsed += self.delta;
+        self.real_elapsed += real_delta;
+        self.frame_count += 1;
+        self.delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::App;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_time() {
+        let fixed_steps = Rc::new(Cell::new(0));
+        let mut app = App::builder()
+            .without_default_systems()
+            .fixed_timestep(Duration::from_millis(10))
+            .on_fixed_update({
+                let fixed_steps = fixed_steps.clone();
+                move |_, _| fixed_steps.set(fixed_steps.get() + 1)
+            })
+            .build();
+
+        app.ecs_mut()
+            .resource_mut::<Time>()
+            .unwrap()
+            .set_time_scale(0.5);
+        for _ in 0..4 {
+            app.update(Duration::from_millis(10));
+        }
+        assert_eq!(fixed_steps.get(), 2);
+
+        app.ecs_mut()
+            .resource_mut::<Time>()
+            .unwrap()
+            .set_paused(true);
+        app.update(Duration::from_millis(10));
+        assert_eq!(fixed_steps.get(), 2);
+
+        let time = app.ecs().resource::<Time>().unwrap();
+        assert_eq!(time.delta(), Duration::default());
+        assert_eq!(time.real_delta(), Duration::from_millis(10));
+        assert_eq!(time.fixed_delta(), Duration::from_millis(10));
+        assert_eq!(time.elapsed(), Duration::from_millis(20));
+        assert_eq!(time.real_elapsed(), Duration::from_millis(50));
+        assert_eq!(time.frame_count(), 5);
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{App, Input, Key, MouseButton};
+use glutin::dpi::{LogicalSize, PhysicalSize};
+use glutin::event::{
+    ElementState, Event, KeyboardInput, MouseScrollDelta, VirtualKeyCode, WindowEvent,
+};
+use glutin::event_loop::{ControlFlow, EventLoop};
+use glutin::window::WindowBuilder;
+use glutin::ContextBuilder;
+use std::time::Instant;
+use xengine_render::GlRenderer;
+use xmath::Vector2;
+
+/// 窗口的配置
+#[derive(Debug, Clone)]
+pub struct WindowConfig {
+    pub title: String,
+    pub width: f64,
+    pub height: f64,
+    pub vsync: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            title: "xengine".to_string(),
+            width: 1024.0,
+            height: 768.0,
+            vsync: true,
+        }
+    }
+}
+
+/// 窗口的逻辑大小和缩放比例，`App::run`每帧更新这个资源
+#[derive(Debug, Copy, Clone)]
+pub struct WindowSize {
+    pub width: f32,
+    pub height: f32,
+    pub scale_factor: f32,
+}
+
+macro_rules! convert_key {
+    ($key:expr, $($name:ident),*) => {
+        match $key {
+            $(VirtualKeyCode::$name => Some(Key::$name),)*
+            _ => None,
+        }
+    };
+}
+
+fn convert_key(key: VirtualKeyCode) -> Option<Key> {
+    convert_key!(
+        key, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0, A, B, C, D, E, F, G, H, I,
+        J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, Escape, F1, F2, F3, F4, F5, F6, F7, F8,
+        F9, F10, F11, F12, Insert, Home, Delete, End, PageDown, PageUp, Left, Up, Right, Down,
+        Back, Return, Space, Tab, LShift, RShift, LControl, RControl, LAlt, RAlt, Comma, Period,
+        Minus, Equals, Slash, Backslash, Semicolon, Apostrophe, LBracket, RBracket, Grave
+    )
+}
+
+fn convert_mouse_button(button: glutin::event::MouseButton) -> MouseButton {
+    match button {
+        glutin::event::MouseButton::Left => MouseButton::Left,
+        glutin::event::MouseButton::Right => MouseButton::Right,
+        glutin::event::MouseButton::Middle => MouseButton::Middle,
+        glutin::event::MouseButton::Other(n) => MouseButton::Other(n),
+    }
+}
+
+/// 每行滚动对应的像素数，用于统一两种滚动量
+const PIXELS_PER_LINE: f32 = 16.0;
+
+fn handle_input(input: &mut Input, event: WindowEvent) {
+    match event {
+        WindowEvent::KeyboardInput {
+            input:
+                KeyboardInput {
+                    state,
+                    virtual_keycode: Some(key),
+                    ..
+                },
+            ..
+        } => {
+            if let Some(key) = convert_key(key) {
+                match state {
+                    ElementState::Pressed => input.keyboard.press(key),
+                    ElementState::Released => input.keyboard.release(key),
+                }
+            }
+        }
+        WindowEvent::MouseInput { state, button, .. } => {
+            let button = convert_mouse_button(button);
+            match state {
+                ElementState::Pressed => input.mouse.press(button),
+                ElementState::Released => input.mouse.release(button),
+            }
+        }
+        WindowEvent::CursorMoved { position, .. } => {
+            input.set_mouse_position(Vector2::new(position.x as f32, position.y as f32));
+        }
+        WindowEvent::MouseWheel { delta, .. } => match delta {
+            MouseScrollDelta::LineDelta(x, y) => {
+                input.add_mouse_wheel(Vector2::new(x, y) * PIXELS_PER_LINE)
+            }
+            MouseScrollDelta::PixelDelta(delta) => {
+                input.add_mouse_wheel(Vector2::new(delta.x as f32, delta.y as f32))
+            }
+        },
+        WindowEvent::Focused(false) => {
+            input.keyboard.release_all();
+            input.mouse.release_all();
+        }
+        _ => {}
+    }
+}
+
+impl crate::AppBuilder {
+    /// 窗口的配置
+    pub fn window(mut self, config: WindowConfig) -> Self {
+        self.window = config;
+        self
+    }
+}
+
+impl App {
+    /// 创建窗口和OpenGL上下文并运行主循环，关闭窗口时退出进程
+    ///
+    /// 每帧清空屏幕并执行所有系统，用`GlRenderer`绘制`DrawList`后调用渲染回调，渲染回调里可以直接使用OpenGL。
+    /// 窗口的键盘和鼠标事件会更新到`Input`资源
+    pub fn run(mut self) -> ! {
+        let el = EventLoop::new();
+        let wb = WindowBuilder::new()
+            .with_title(self.window.title.clone())
+            .with_inner_size(LogicalSize::new(self.window.width, self.window.height));
+        let windowed_context = ContextBuilder::new()
+            .with_vsync(self.window.vsync)
+            .build_windowed(wb, &el)
+            .unwrap();
+        let windowed_context = unsafe { windowed_context.make_current().unwrap() };
+        gl::load_with(|p| windowed_context.get_proc_address(p) as *const _);
+        let mut renderer = GlRenderer::new();
+
+        let mut last_frame = Instant::now();
+        el.run(move |event, _, control_flow| {
+            let size = windowed_context.window().inner_size();
+            let scale_factor = windowed_context.window().hidpi_factor() as f32;
+            self.ecs_mut().insert_resource(WindowSize {
+                width: size.width as f32,
+                height: size.height as f32,
+                scale_factor,
+            });
+
+            unsafe {
+                gl::Viewport(
+                    0,
+                    0,
+                    (size.width as f32 * scale_factor) as i32,
+                    (size.height as f32 * scale_factor) as i32,
+                );
+                gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT);
+            }
+
+            let now = Instant::now();
+            let delta = self.begin_frame(now - last_frame);
+            self.ecs_mut().update(delta);
+            renderer.render(
+                self.ecs(),
+                Vector2::new(size.width as f32, size.height as f32) * scale_factor,
+            );
+            self.call_render();
+            self.end_frame();
+            last_frame = now;
+            windowed_context.swap_buffers().unwrap();
+
+            if let Event::WindowEvent { event, .. } = event {
+                match event {
+                    WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+                    WindowEvent::Resized(sz) => windowed_context.resize(PhysicalSize {
+                        width: sz.width,
+                        height: sz.height,
+                    }),
+                    event => {
+                        if let Some(input) = self.ecs_mut().resource_mut::<Input>() {
+                            handle_input(input, event);
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,80 @@
+//! 场景稳定之后物理世界的步进不应该再分配内存
+//!
+//! 全局分配器对整个进程生效，所以这个测试单独放在一个测试程序中。
+
+use xengine::{AllocationStats, CountingAllocator};
+use xmath::{Vector2, AABB};
+use xphysics::{
+    BodyDef, BodyType, FixtureDef, IntoBoxedShape, RevoluteJointDef, ShapeCircle, ShapeEdge,
+    ShapePolygon, World,
+};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    AllocationStats::current().unwrap().allocations
+}
+
+#[test]
+fn test_step_does_not_allocate() {
+    let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+    world.set_allow_sleeping(false);
+    let ground = world.create_body_with_fixture(
+        BodyDef::default(),
+        FixtureDef::new(ShapeEdge::new((-20.0, 0.0), (20.0, 0.0)).into_boxed(), 0.0),
+    );
+
+    // 十列箱子堆
+    for i in 0..40 {
+        world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: ((i % 10) as f32 - 5.0, 0.5 + (i / 10) as f32).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapePolygon::new_box_center(0.45, 0.45).into_boxed(), 1.0),
+        );
+    }
+
+    // 停在地面上的子弹，让连续碰撞也参与步进
+    world.create_body_with_fixture(
+        BodyDef {
+            type_: BodyType::Dynamic,
+            position: (10.0, 0.25).into(),
+            bullet: true,
+            ..BodyDef::default()
+        },
+        FixtureDef::new(ShapeCircle::new((0.0, 0.0), 0.25).into_boxed(), 1.0),
+    );
+
+    // 挂在地面上的摆
+    let pendulum = world.create_body_with_fixture(
+        BodyDef {
+            type_: BodyType::Dynamic,
+            position: (-10.0, 5.0).into(),
+            ..BodyDef::default()
+        },
+        FixtureDef::new(ShapeCircle::new((0.0, 0.0), 0.25).into_boxed(), 1.0),
+    );
+    let def = RevoluteJointDef::with_anchor(&world, ground, pendulum, (-12.0, 5.0).into());
+    world.create_joint(def);
+
+    for _ in 0..120 {
+        world.step(1.0 / 60.0, 8, 3);
+    }
+    assert!(world.contact_count() > 0);
+
+    let before = allocations();
+    for _ in 0..60 {
+        world.step(1.0 / 60.0, 8, 3);
+
+        // 游戏每帧都会做的查询
+        let aabb = AABB::new((-6.0, 0.0).into(), (6.0, 4.0).into());
+        assert!(world.query_aabb(aabb).count() > 0);
+        assert!(world
+            .ray_cast_closest((-20.0, 2.0).into(), (20.0, 2.0).into(), |_| true)
+            .is_some());
+    }
+    assert_eq!(allocations() - before, 0);
+}
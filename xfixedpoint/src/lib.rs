@@ -34,7 +34,7 @@ pub mod consts {
     use super::FP;
 
     pub const MAX_VALUE: FP = FP(super::MAX_VALUE - 1);
-    pub const MIN_VALUE: FP = FP(super::MAX_VALUE - 1);
+    pub const MIN_VALUE: FP = FP(super::MIN_VALUE + 2);
     pub const MIN_POSITIVE_VALUE: FP = FP(super::MIN_POSITIVE_VALUE);
     pub const ONE: FP = FP(super::ONE);
     pub const TWO: FP = FP(super::TWO);
@@ -72,6 +72,17 @@ pub mod consts {
     pub const LOG2MIN: FP = FP(-0x2000000000);
 }
 
+impl FP {
+    /// 32.32定点数的原始表示，用于校验和以及网络同步
+    pub const fn to_bits(self) -> i64 {
+        self.0
+    }
+
+    pub const fn from_bits(bits: i64) -> FP {
+        FP(bits)
+    }
+}
+
 impl Real for FP {
     fn is_valid(&self) -> bool {
         self.0 > MIN_VALUE + 1 && self.0 < MAX_VALUE
@@ -224,17 +235,19 @@ impl Real for FP {
 
         let raw_index = clamped * LUT_INTERVAL;
         let rounded_index = raw_index.round();
-        let index_error = consts::ZERO;
+        let index_error = raw_index - rounded_index;
 
+        let index = rounded_index.to_i32();
+        let next_index = index + lut_step(index_error);
         let nearest_value = FP(SIN_LUT[if flip_horizontal {
-            SIN_LUT.len() - 1 - rounded_index.to_i32() as usize
+            SIN_LUT.len() - 1 - index as usize
         } else {
-            rounded_index.to_i32() as usize
+            index as usize
         }]);
         let second_nearest_value = FP(SIN_LUT[if flip_horizontal {
-            SIN_LUT.len() - 1 - (rounded_index.to_i32() - index_error.signum().to_i32()) as usize
+            SIN_LUT.len() - 1 - next_index as usize
         } else {
-            (rounded_index.to_i32() + index_error.signum().to_i32()) as usize
+            next_index as usize
         }]);
 
         let delta = (index_error * (nearest_value - second_nearest_value).abs()).0;
@@ -273,7 +286,7 @@ impl Real for FP {
 
         let nearest_value = FP(TAN_LUT[rounded_index.to_i32() as usize]);
         let second_nearest_value =
-            FP(TAN_LUT[(rounded_index + FP::i32(index_error.signum().to_i32())).to_i32() as usize]);
+            FP(TAN_LUT[(rounded_index.to_i32() + lut_step(index_error)) as usize]);
 
         let delta = (index_error * (nearest_value - second_nearest_value).abs()).0;
         let interpolated_value = nearest_value.0 + delta;
@@ -311,7 +324,7 @@ impl Real for FP {
         let index_error = raw_index - rounded_index;
         let nearest_value = FP(ACOS_LUT[rounded_index.to_i32() as usize]);
 
-        let mut next_index = rounded_index.to_i32() + index_error.signum().to_i32();
+        let mut next_index = rounded_index.to_i32() + lut_step(index_error);
         if next_index >= LUT_SIZE as i32 {
             next_index = LUT_SIZE as i32 - 1;
         }
@@ -405,7 +418,7 @@ impl Add for FP {
     type Output = FP;
 
     fn add(self, rhs: Self) -> Self::Output {
-        FP(self.0 + rhs.0)
+        FP(self.0.saturating_add(rhs.0))
     }
 }
 
@@ -413,34 +426,23 @@ impl Sub for FP {
     type Output = FP;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        FP(self.0 - rhs.0)
+        FP(self.0.saturating_sub(rhs.0))
     }
 }
 
 impl Mul for FP {
     type Output = FP;
 
+    /// 溢出时取最大或最小值，保证debug和release下结果一致
     fn mul(self, rhs: Self) -> Self::Output {
-        let xl = self.0;
-        let yl = rhs.0;
-
-        let xlo = (xl & 0x00000000FFFFFFFF) as u64;
-        let xhi = xl >> FRACTIONAL_PLACES;
-        let ylo = (yl & 0x00000000FFFFFFFF) as u64;
-        let yhi = yl >> FRACTIONAL_PLACES;
-
-        let lolo = xlo * ylo;
-        let lohi = xlo as i64 * yhi;
-        let hilo = xhi * (ylo as i64);
-        let hihi = xhi * yhi;
-
-        let lo_result = lolo >> FRACTIONAL_PLACES;
-        let mid_result1 = lohi;
-        let mid_result2 = hilo;
-        let hi_result = hihi << FRACTIONAL_PLACES;
-
-        let sum = (lo_result as i64) + mid_result1 + mid_result2 + hi_result;
-        FP(sum)
+        let product = (self.0 as i128 * rhs.0 as i128) >> FRACTIONAL_PLACES;
+        if product > MAX_VALUE as i128 {
+            consts::MAX_VALUE
+        } else if product < MIN_VALUE as i128 {
+            consts::MIN_VALUE
+        } else {
+            FP(product as i64)
+        }
     }
 }
 
@@ -787,6 +789,15 @@ impl FP {
     }
 }
 
+/// 查表插值时下一个表项的方向，误差为0时不需要插值
+fn lut_step(index_error: FP) -> i32 {
+    match index_error.0.cmp(&0) {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    }
+}
+
 fn clamp_sin_value(angle: i64) -> (i64, bool, bool) {
     let mut clamped2pi = angle % PI_TIMES_2;
     if angle < 0 {
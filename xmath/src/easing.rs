@@ -0,0 +1,189 @@
+use crate::{Real, Rotation, Transform, Vector2};
+use std::f32::consts::PI;
+
+/// 可以线性插值的值，`t`为0时返回自己，为1时返回`to`
+pub trait Lerp {
+    fn lerp(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl<T: Real> Lerp for Vector2<T> {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        *self + (*to - *self) * T::f32(t)
+    }
+}
+
+/// 沿着较小的角度插值
+impl<T: Real> Lerp for Rotation<T> {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        let from = self.angle();
+        let mut delta = to.angle() - from;
+        if delta > T::pi() {
+            delta -= T::pi_times_2();
+        } else if delta < -T::pi() {
+            delta += T::pi_times_2();
+        }
+        Rotation::new(from + delta * T::f32(t))
+    }
+}
+
+impl<T: Real> Lerp for Transform<T> {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        Transform::new(self.p.lerp(&to.p, t), self.q.lerp(&to.q, t))
+    }
+}
+
+/// 缓动曲线，把0到1的进度映射为插值系数
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+
+    /// 先向反方向移动一点
+    BackIn,
+
+    /// 超过终点再回来
+    BackOut,
+    ElasticOut,
+    BounceOut,
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984_375
+    }
+}
+
+impl Easing {
+    /// 进度会被限制在0到1之间，NaN当作0，返回值在0和1时分别是0和1，中间可能超出这个范围
+    pub fn apply(self, t: f32) -> f32 {
+        const BACK: f32 = 1.701_58;
+
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::SineIn => 1.0 - (t * PI / 2.0).cos(),
+            Easing::SineOut => (t * PI / 2.0).sin(),
+            Easing::SineInOut => -((t * PI).cos() - 1.0) / 2.0,
+            Easing::ExpoIn => {
+                if t == 0.0 {
+                    0.0
+                } else {
+                    2f32.powf(10.0 * t - 10.0)
+                }
+            }
+            Easing::ExpoOut => {
+                if t == 1.0 {
+                    1.0
+                } else {
+                    1.0 - 2f32.powf(-10.0 * t)
+                }
+            }
+            Easing::BackIn => (BACK + 1.0) * t * t * t - BACK * t * t,
+            Easing::BackOut => {
+                let t = t - 1.0;
+                1.0 + (BACK + 1.0) * t * t * t + BACK * t * t
+            }
+            Easing::ElasticOut => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+                }
+            }
+            Easing::BounceOut => bounce_out(t),
+        }
+    }
+}
+
+/// 由关键帧组成的曲线，关键帧之间线性插值，常用于随生命周期变化的值
+#[derive(Debug, Clone)]
+pub struct Curve<T> {
+    keys: Vec<(f32, T)>,
+}
+
+impl<T: Lerp + Clone> Curve<T> {
+    pub fn constant(value: T) -> Curve<T> {
+        Curve {
+            keys: vec![(0.0, value)],
+        }
+    }
+
+    /// 从0时的`from`线性变化到1时的`to`
+    pub fn new(from: T, to: T) -> Curve<T> {
+        Curve {
+            keys: vec![(0.0, from), (1.0, to)],
+        }
+    }
+
+    /// 添加一个关键帧，时间相同时替换原来的值
+    pub fn with_key(mut self, time: f32, value: T) -> Curve<T> {
+        match self
+            .keys
+            .binary_search_by(|(t, _)| t.partial_cmp(&time).unwrap())
+        {
+            Ok(index) => self.keys[index].1 = value,
+            Err(index) => self.keys.insert(index, (time, value)),
+        }
+        self
+    }
+
+    /// 在第一个关键帧之前和最后一个关键帧之后保持不变
+    pub fn sample(&self, time: f32) -> T {
+        let index = self.keys.iter().position(|(t, _)| *t > time);
+        match index {
+            Some(0) => self.keys[0].1.clone(),
+            Some(index) => {
+                let (t0, v0) = &self.keys[index - 1];
+                let (t1, v1) = &self.keys[index];
+                v0.lerp(v1, (time - t0) / (t1 - t0))
+            }
+            None => self.keys.last().unwrap().1.clone(),
+        }
+    }
+}
@@ -1,5 +1,6 @@
 mod aabb;
 mod affine_transform;
+mod easing;
 mod float32;
 mod real;
 mod rotation;
@@ -9,6 +10,7 @@ mod vector3;
 
 pub use aabb::*;
 pub use affine_transform::*;
+pub use easing::*;
 pub use real::*;
 pub use rotation::*;
 pub use transform::*;
@@ -1,12 +1,33 @@
-use crate::test::{Test, TestSetting};
-use crate::tests::CharacterCollision;
+use crate::test::{Test, TestImpl, TestSetting};
+use crate::tests::{BulletTest, Car, Chain, CharacterCollision, Pyramid, Tumbler, VerticalStack};
 
 mod camera;
 mod debug_draw;
 mod test;
 mod tests;
 
+/// 通过第一个命令行参数选择场景
 fn main() {
+    let name = std::env::args().nth(1);
+    match name.as_deref().unwrap_or("character_collision") {
+        "character_collision" => run::<CharacterCollision>(),
+        "pyramid" => run::<Pyramid>(),
+        "tumbler" => run::<Tumbler>(),
+        "vertical_stack" => run::<VerticalStack>(),
+        "bullet_test" => run::<BulletTest>(),
+        "chain" => run::<Chain>(),
+        "car" => run::<Car>(),
+        name => {
+            eprintln!("unknown test: {}", name);
+            eprintln!(
+                "available tests: character_collision, pyramid, tumbler, vertical_stack, bullet_test, \
+                 chain, car"
+            );
+        }
+    }
+}
+
+fn run<I: TestImpl<f32> + 'static>() {
     let el = glutin::event_loop::EventLoop::new();
     let wb = glutin::window::WindowBuilder::new()
         .with_inner_size(glutin::dpi::LogicalSize::new(1024.0, 768.0));
@@ -16,7 +37,7 @@ fn main() {
     let windowed_context = unsafe { windowed_context.make_current().unwrap() };
     gl::load_with(|p| windowed_context.get_proc_address(p) as *const _);
 
-    let mut test = Test::<f32, CharacterCollision>::new();
+    let mut test = Test::<f32, I>::new();
     let mut settings = TestSetting::default();
 
     el.run(move |event, _, control_flow| {
@@ -47,6 +68,18 @@ fn main() {
                 glutin::event::WindowEvent::CloseRequested => {
                     *control_flow = glutin::event_loop::ControlFlow::Exit;
                 }
+                glutin::event::WindowEvent::KeyboardInput {
+                    input:
+                        glutin::event::KeyboardInput {
+                            state: glutin::event::ElementState::Pressed,
+                            virtual_keycode: Some(key),
+                            ..
+                        },
+                    ..
+                } => {
+                    settings.handle_key(key);
+                    test.keyboard(key);
+                }
                 glutin::event::WindowEvent::Resized(sz) => {
                     windowed_context.resize(glutin::dpi::PhysicalSize {
                         width: sz.width,
@@ -1,10 +1,18 @@
 use crate::camera::Camera;
 use crate::debug_draw::NvgDebugDraw;
+use glutin::event::VirtualKeyCode;
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::Duration;
 use xmath::{Real, Vector2};
 use xphysics::{DebugDrawFlags, World};
 
+/// 慢动作时的时间缩放
+const SLOW_MOTION_SCALE: f32 = 0.25;
+
+/// 统计信息使用的字体，可以通过`XPHYSICS_TESTBED_FONT`环境变量指定
+const DEFAULT_FONT: &str = "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf";
+
 pub struct TestSetting<T> {
     hz: T,
     velocity_iterations: usize,
@@ -16,6 +24,7 @@ pub struct TestSetting<T> {
     draw_contact_impulse: bool,
     draw_friction_impulse: bool,
     draw_center_of_mass: bool,
+    draw_joints: bool,
     draw_stats: bool,
     enable_warm_starting: bool,
     enable_continuous: bool,
@@ -23,6 +32,7 @@ pub struct TestSetting<T> {
     enable_sleep: bool,
     pause: bool,
     single_step: bool,
+    slow_motion: bool,
 }
 
 impl<T: Real> Default for TestSetting<T> {
@@ -38,6 +48,7 @@ impl<T: Real> Default for TestSetting<T> {
             draw_contact_impulse: false,
             draw_friction_impulse: false,
             draw_center_of_mass: false,
+            draw_joints: true,
             draw_stats: false,
             enable_warm_starting: true,
             enable_continuous: true,
@@ -45,6 +56,23 @@ impl<T: Real> Default for TestSetting<T> {
             enable_sleep: true,
             pause: false,
             single_step: false,
+            slow_motion: false,
+        }
+    }
+}
+
+impl<T> TestSetting<T> {
+    /// P暂停/继续，O暂停并单步执行一次，M切换慢动作，F1切换统计信息
+    pub fn handle_key(&mut self, key: VirtualKeyCode) {
+        match key {
+            VirtualKeyCode::P => self.pause = !self.pause,
+            VirtualKeyCode::O => {
+                self.pause = true;
+                self.single_step = true;
+            }
+            VirtualKeyCode::M => self.slow_motion = !self.slow_motion,
+            VirtualKeyCode::F1 => self.draw_stats = !self.draw_stats,
+            _ => {}
         }
     }
 }
@@ -53,7 +81,7 @@ pub trait TestImpl<T: Real> {
     fn new(world: &mut World<T, ()>) -> Self;
 
     fn step(
-        &self,
+        &mut self,
         world: &mut World<T, ()>,
         time_step: T,
         velocity_iterations: usize,
@@ -61,11 +89,14 @@ pub trait TestImpl<T: Real> {
     ) {
         world.step(time_step, velocity_iterations, position_iterations);
     }
+
+    fn keyboard(&mut self, _world: &mut World<T, ()>, _key: VirtualKeyCode) {}
 }
 
 pub struct Test<T, I> {
     ctx: Rc<RefCell<nvg::Context<nvg_gl::Renderer>>>,
     camera: Rc<RefCell<Camera>>,
+    font: Option<nvg::FontId>,
     test_impl: I,
     pub world: World<T, ()>,
 }
@@ -78,6 +109,12 @@ impl<T: Real, I: TestImpl<T>> Test<T, I> {
             nvg::Context::create(nvg_gl::Renderer::create().unwrap()).unwrap(),
         ));
         let camera = Rc::new(RefCell::new(Camera::default()));
+        let font_path =
+            std::env::var("XPHYSICS_TESTBED_FONT").unwrap_or_else(|_| DEFAULT_FONT.to_string());
+        let font = ctx
+            .borrow_mut()
+            .create_font_from_file("sans", font_path)
+            .ok();
         world.set_debug_draw(NvgDebugDraw {
             ctx: ctx.clone(),
             camera: camera.clone(),
@@ -85,11 +122,16 @@ impl<T: Real, I: TestImpl<T>> Test<T, I> {
         Test {
             ctx,
             camera,
+            font,
             test_impl,
             world,
         }
     }
 
+    pub fn keyboard(&mut self, key: VirtualKeyCode) {
+        self.test_impl.keyboard(&mut self.world, key);
+    }
+
     pub fn step(
         &mut self,
         settings: &mut TestSetting<T>,
@@ -109,6 +151,10 @@ impl<T: Real, I: TestImpl<T>> Test<T, I> {
             T::zero()
         };
 
+        if settings.slow_motion {
+            time_step = time_step * T::f32(SLOW_MOTION_SCALE);
+        }
+
         if settings.pause {
             if settings.single_step {
                 settings.single_step = false;
@@ -122,6 +168,20 @@ impl<T: Real, I: TestImpl<T>> Test<T, I> {
             flags.set(DebugDrawFlags::SHAPE, settings.draw_shapes);
             flags.set(DebugDrawFlags::AABB, settings.draw_aabbs);
             flags.set(DebugDrawFlags::CENTER_OF_MASS, settings.draw_center_of_mass);
+            flags.set(DebugDrawFlags::JOINT, settings.draw_joints);
+            flags.set(DebugDrawFlags::CONTACT_POINT, settings.draw_contact_points);
+            flags.set(
+                DebugDrawFlags::CONTACT_NORMAL,
+                settings.draw_contact_normals,
+            );
+            flags.set(
+                DebugDrawFlags::CONTACT_IMPULSE,
+                settings.draw_contact_impulse,
+            );
+            flags.set(
+                DebugDrawFlags::FRICTION_IMPULSE,
+                settings.draw_friction_impulse,
+            );
             flags
         });
 
@@ -138,7 +198,64 @@ impl<T: Real, I: TestImpl<T>> Test<T, I> {
             settings.position_iterations,
         );
         self.world.draw_debug_data();
+        self.draw_overlay(settings);
 
         self.ctx.borrow_mut().end_frame().unwrap();
     }
+
+    fn draw_overlay(&self, settings: &TestSetting<T>) {
+        let font = match self.font {
+            Some(font) => font,
+            None => return,
+        };
+
+        let mut lines = Vec::new();
+        if settings.pause {
+            lines.push("****PAUSED****".to_string());
+        }
+        if settings.slow_motion {
+            lines.push(format!("slow motion x{}", SLOW_MOTION_SCALE));
+        }
+        if settings.draw_stats {
+            let ms = |d: Duration| d.as_secs_f32() * 1000.0;
+            let profile = self.world.profile();
+            lines.push(format!(
+                "bodies/contacts/proxies = {}/{}/{}",
+                self.world.body_count(),
+                self.world.contact_count(),
+                self.world.proxy_count()
+            ));
+            lines.push(format!("tree height = {}", self.world.tree_height()));
+            lines.push(format!(
+                "solved bodies/points/TOI/queries = {}/{}/{}/{}",
+                profile.body_count,
+                profile.contact_point_count,
+                profile.toi_count,
+                profile.tree_query_count
+            ));
+            lines.push(format!("step [ms] = {:.2}", ms(profile.step)));
+            lines.push(format!("collide [ms] = {:.2}", ms(profile.collide)));
+            lines.push(format!("solve [ms] = {:.2}", ms(profile.solve)));
+            lines.push(format!("solve init [ms] = {:.2}", ms(profile.solve_init)));
+            lines.push(format!(
+                "solve velocity [ms] = {:.2}",
+                ms(profile.solve_velocity)
+            ));
+            lines.push(format!(
+                "solve position [ms] = {:.2}",
+                ms(profile.solve_position)
+            ));
+            lines.push(format!("solve TOI [ms] = {:.2}", ms(profile.solve_toi)));
+            lines.push(format!("broad-phase [ms] = {:.2}", ms(profile.broad_phase)));
+        }
+
+        let mut ctx = self.ctx.borrow_mut();
+        ctx.font(font);
+        ctx.font_size(14.0);
+        ctx.fill_paint(nvg::Color::rgb(0.9, 0.6, 0.6));
+        ctx.text_align(nvg::Align::LEFT | nvg::Align::TOP);
+        for (i, line) in lines.iter().enumerate() {
+            ctx.text((5.0, 5.0 + i as f32 * 16.0), line).unwrap();
+        }
+    }
 }
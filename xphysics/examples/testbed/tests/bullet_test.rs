@@ -0,0 +1,115 @@
+use crate::test::TestImpl;
+use xmath::{Real, Vector2};
+use xphysics::{
+    BodyDef, BodyId, BodyType, FixtureDef, IntoBoxedShape, ShapeEdge, ShapePolygon, World,
+};
+
+/// 每隔这么多步重新发射一次
+const LAUNCH_INTERVAL: usize = 60;
+
+/// 每次发射的水平位置，轮流使用
+const LAUNCH_X: [f32; 4] = [0.20352793, -0.7, 0.45, -0.15];
+
+pub struct BulletTest {
+    body: BodyId,
+    bullet: BodyId,
+    step_count: usize,
+    launch_count: usize,
+}
+
+impl BulletTest {
+    fn launch<T: Real>(&mut self, world: &mut World<T, ()>) {
+        world.set_body_transform(self.body, Vector2::new(T::zero(), T::f32(4.0)), T::zero());
+        let body = world.body_mut(self.body).unwrap();
+        body.set_linear_velocity(Vector2::zero());
+        body.set_angular_velocity(T::zero());
+
+        let x = LAUNCH_X[self.launch_count % LAUNCH_X.len()];
+        self.launch_count += 1;
+
+        world.set_body_transform(
+            self.bullet,
+            Vector2::new(T::f32(x), T::f32(10.0)),
+            T::zero(),
+        );
+        let bullet = world.body_mut(self.bullet).unwrap();
+        bullet.set_linear_velocity(Vector2::new(T::zero(), T::f32(-50.0)));
+        bullet.set_angular_velocity(T::zero());
+    }
+}
+
+impl<T: Real> TestImpl<T> for BulletTest {
+    fn new(world: &mut World<T, ()>) -> BulletTest {
+        // Ground body
+        world.create_body_with_fixtures(
+            BodyDef::default(),
+            vec![
+                FixtureDef::new(
+                    ShapeEdge::new((T::f32(-10.0), T::f32(0.0)), (T::f32(10.0), T::f32(0.0)))
+                        .into_boxed(),
+                    T::zero(),
+                ),
+                FixtureDef::new(
+                    ShapePolygon::new_box(
+                        T::f32(0.2),
+                        T::f32(1.0),
+                        (T::f32(0.5), T::f32(1.0)),
+                        T::zero(),
+                    )
+                    .into_boxed(),
+                    T::zero(),
+                ),
+            ],
+        );
+
+        let body = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (T::zero(), T::f32(4.0)).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(
+                ShapePolygon::new_box_center(T::f32(2.0), T::f32(0.1)).into_boxed(),
+                T::f32(1.0),
+            ),
+        );
+
+        let bullet = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                bullet: true,
+                position: (T::f32(LAUNCH_X[0]), T::f32(10.0)).into(),
+                linear_velocity: Vector2::new(T::zero(), T::f32(-50.0)),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(
+                ShapePolygon::new_box_center(T::f32(0.25), T::f32(0.25)).into_boxed(),
+                T::f32(100.0),
+            ),
+        );
+
+        BulletTest {
+            body,
+            bullet,
+            step_count: 0,
+            launch_count: 1,
+        }
+    }
+
+    fn step(
+        &mut self,
+        world: &mut World<T, ()>,
+        time_step: T,
+        velocity_iterations: usize,
+        position_iterations: usize,
+    ) {
+        world.step(time_step, velocity_iterations, position_iterations);
+
+        if time_step > T::zero() {
+            self.step_count += 1;
+            if self.step_count % LAUNCH_INTERVAL == 0 {
+                self.launch(world);
+            }
+        }
+    }
+}
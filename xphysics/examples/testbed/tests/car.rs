@@ -0,0 +1,220 @@
+use crate::test::TestImpl;
+use glutin::event::VirtualKeyCode;
+use xmath::{Real, Vector2};
+use xphysics::{
+    BodyDef, BodyType, FixtureDef, IntoBoxedShape, Joint, JointId, RevoluteJointDef, ShapeCircle,
+    ShapeEdge, ShapePolygon, WheelJointDef, World,
+};
+
+/// 后轮马达的转速
+const MOTOR_SPEED: f32 = 50.0;
+
+/// 起伏的地面、跷跷板、吊桥和一堆箱子，A/S/D控制小车后退、停止和前进，Q/E调低和调高悬挂弹簧的频率
+pub struct Car {
+    rear_wheel: JointId,
+    front_wheel: JointId,
+    frequency_hz: f32,
+}
+
+fn edge<T: Real>(x1: f32, y1: f32, x2: f32, y2: f32) -> FixtureDef<T, ()> {
+    FixtureDef {
+        friction: T::f32(0.6),
+        ..FixtureDef::new(
+            ShapeEdge::new((T::f32(x1), T::f32(y1)), (T::f32(x2), T::f32(y2))).into_boxed(),
+            T::zero(),
+        )
+    }
+}
+
+impl<T: Real> TestImpl<T> for Car {
+    fn new(world: &mut World<T, ()>) -> Car {
+        let mut edges = vec![edge(-20.0, 0.0, 20.0, 0.0)];
+        let hs = [0.25, 1.0, 4.0, 0.0, 0.0, -1.0, -2.0, -2.0, -1.25, 0.0];
+        let mut x = 20.0;
+        let mut y1 = 0.0;
+        let dx = 5.0;
+        for _ in 0..2 {
+            for y2 in hs.iter() {
+                edges.push(edge(x, y1, x + dx, *y2));
+                y1 = *y2;
+                x += dx;
+            }
+        }
+        edges.push(edge(x, 0.0, x + 40.0, 0.0));
+        x += 80.0;
+        edges.push(edge(x, 0.0, x + 40.0, 0.0));
+        x += 40.0;
+        edges.push(edge(x, 0.0, x + 10.0, 5.0));
+        x += 20.0;
+        edges.push(edge(x, 0.0, x + 40.0, 0.0));
+        x += 40.0;
+        edges.push(edge(x, 0.0, x, 20.0));
+        let ground = world.create_body_with_fixtures(BodyDef::default(), edges);
+
+        // 跷跷板
+        let teeter = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (T::f32(140.0), T::f32(1.0)).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(
+                ShapePolygon::new_box_center(T::f32(10.0), T::f32(0.25)).into_boxed(),
+                T::one(),
+            ),
+        );
+        world.create_joint(RevoluteJointDef {
+            enable_limit: true,
+            lower_angle: T::f32(-8.0f32.to_radians()),
+            upper_angle: T::f32(8.0f32.to_radians()),
+            ..RevoluteJointDef::with_anchor(
+                world,
+                ground,
+                teeter,
+                Vector2::new(T::f32(140.0), T::f32(1.0)),
+            )
+        });
+        world
+            .body_mut(teeter)
+            .unwrap()
+            .apply_angular_impulse(T::f32(100.0), true);
+
+        // 吊桥
+        let mut prev = ground;
+        for i in 0..20 {
+            let body = world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: (T::f32(161.0 + 2.0 * i as f32), T::f32(-0.125)).into(),
+                    ..BodyDef::default()
+                },
+                FixtureDef {
+                    friction: T::f32(0.6),
+                    ..FixtureDef::new(
+                        ShapePolygon::new_box_center(T::one(), T::f32(0.125)).into_boxed(),
+                        T::one(),
+                    )
+                },
+            );
+            world.create_joint(RevoluteJointDef::with_anchor(
+                world,
+                prev,
+                body,
+                Vector2::new(T::f32(160.0 + 2.0 * i as f32), T::f32(-0.125)),
+            ));
+            prev = body;
+        }
+        world.create_joint(RevoluteJointDef::with_anchor(
+            world,
+            prev,
+            ground,
+            Vector2::new(T::f32(200.0), T::f32(-0.125)),
+        ));
+
+        for i in 0..5 {
+            world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: (T::f32(230.0), T::f32(0.5 + i as f32)).into(),
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(
+                    ShapePolygon::new_box_center(T::f32(0.5), T::f32(0.5)).into_boxed(),
+                    T::f32(0.5),
+                ),
+            );
+        }
+
+        let chassis = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (T::zero(), T::one()).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(
+                ShapePolygon::new(vec![
+                    (T::f32(-1.5), T::f32(-0.5)),
+                    (T::f32(1.5), T::f32(-0.5)),
+                    (T::f32(1.5), T::zero()),
+                    (T::zero(), T::f32(0.9)),
+                    (T::f32(-1.15), T::f32(0.9)),
+                    (T::f32(-1.5), T::f32(0.2)),
+                ])
+                .into_boxed(),
+                T::one(),
+            ),
+        );
+        let frequency_hz = 4.0;
+        let mut wheel = |x: f32, y: f32| {
+            let position = Vector2::new(T::f32(x), T::f32(y));
+            let body = world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position,
+                    ..BodyDef::default()
+                },
+                FixtureDef {
+                    friction: T::f32(0.9),
+                    ..FixtureDef::new(
+                        ShapeCircle::new(Vector2::zero(), T::f32(0.4)).into_boxed(),
+                        T::one(),
+                    )
+                },
+            );
+            WheelJointDef {
+                frequency_hz: T::f32(frequency_hz),
+                damping_ratio: T::f32(0.7),
+                ..WheelJointDef::with_anchor(
+                    world,
+                    chassis,
+                    body,
+                    position,
+                    Vector2::new(T::zero(), T::one()),
+                )
+            }
+        };
+        let rear_wheel = wheel(-1.0, 0.35);
+        let front_wheel = wheel(1.0, 0.4);
+
+        let rear_wheel = world.create_joint(WheelJointDef {
+            enable_motor: true,
+            max_motor_torque: T::f32(20.0),
+            ..rear_wheel
+        });
+        let front_wheel = world.create_joint(WheelJointDef {
+            max_motor_torque: T::f32(10.0),
+            ..front_wheel
+        });
+
+        Car {
+            rear_wheel,
+            front_wheel,
+            frequency_hz,
+        }
+    }
+
+    fn keyboard(&mut self, world: &mut World<T, ()>, key: VirtualKeyCode) {
+        let speed = match key {
+            VirtualKeyCode::A => MOTOR_SPEED,
+            VirtualKeyCode::S => 0.0,
+            VirtualKeyCode::D => -MOTOR_SPEED,
+            VirtualKeyCode::Q | VirtualKeyCode::E => {
+                self.frequency_hz = if key == VirtualKeyCode::Q {
+                    (self.frequency_hz - 1.0).max(0.0)
+                } else {
+                    self.frequency_hz + 1.0
+                };
+                for id in [self.rear_wheel, self.front_wheel].iter() {
+                    if let Some(Joint::Wheel(joint)) = world.joint_mut(*id) {
+                        joint.set_frequency(T::f32(self.frequency_hz));
+                    }
+                }
+                return;
+            }
+            _ => return,
+        };
+        if let Some(Joint::Wheel(joint)) = world.joint_mut(self.rear_wheel) {
+            joint.set_motor_speed(T::f32(speed));
+        }
+    }
+}
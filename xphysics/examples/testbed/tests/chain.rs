@@ -0,0 +1,51 @@
+use crate::test::TestImpl;
+use xmath::{Real, Vector2};
+use xphysics::{
+    BodyDef, BodyType, FixtureDef, IntoBoxedShape, RevoluteJointDef, ShapeEdge, ShapePolygon, World,
+};
+
+const COUNT: usize = 30;
+
+/// 用旋转关节连接起来的链条，一端固定在地面上
+pub struct Chain;
+
+impl<T: Real> TestImpl<T> for Chain {
+    fn new(world: &mut World<T, ()>) -> Chain {
+        let ground = world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(
+                ShapeEdge::new((T::f32(-40.0), T::f32(0.0)), (T::f32(40.0), T::f32(0.0)))
+                    .into_boxed(),
+                T::zero(),
+            ),
+        );
+
+        let y = 25.0;
+        let mut prev = ground;
+        for i in 0..COUNT {
+            let body = world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: (T::f32(0.5 + i as f32), T::f32(y)).into(),
+                    ..BodyDef::default()
+                },
+                FixtureDef {
+                    friction: T::f32(0.2),
+                    ..FixtureDef::new(
+                        ShapePolygon::new_box_center(T::f32(0.6), T::f32(0.125)).into_boxed(),
+                        T::f32(20.0),
+                    )
+                },
+            );
+            world.create_joint(RevoluteJointDef::with_anchor(
+                world,
+                prev,
+                body,
+                Vector2::new(T::f32(i as f32), T::f32(y)),
+            ));
+            prev = body;
+        }
+
+        Chain
+    }
+}
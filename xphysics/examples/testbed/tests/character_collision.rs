@@ -236,7 +236,7 @@ impl<T: Real> TestImpl<T> for CharacterCollision {
     }
 
     fn step(
-        &self,
+        &mut self,
         world: &mut World<T, ()>,
         time_step: T,
         velocity_iterations: usize,
@@ -1,3 +1,15 @@
+mod bullet_test;
+mod car;
+mod chain;
 mod character_collision;
+mod pyramid;
+mod tumbler;
+mod vertical_stack;
 
-pub use character_collision::CharacterCollision;
\ No newline at end of file
+pub use bullet_test::BulletTest;
+pub use car::Car;
+pub use chain::Chain;
+pub use character_collision::CharacterCollision;
+pub use pyramid::Pyramid;
+pub use tumbler::Tumbler;
+pub use vertical_stack::VerticalStack;
@@ -0,0 +1,47 @@
+use crate::test::TestImpl;
+use xmath::{Real, Vector2};
+use xphysics::{BodyDef, BodyType, FixtureDef, IntoBoxedShape, ShapeEdge, ShapePolygon, World};
+
+const COUNT: usize = 20;
+
+pub struct Pyramid;
+
+impl<T: Real> TestImpl<T> for Pyramid {
+    fn new(world: &mut World<T, ()>) -> Pyramid {
+        // Ground body
+        world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(
+                ShapeEdge::new((T::f32(-40.0), T::f32(0.0)), (T::f32(40.0), T::f32(0.0)))
+                    .into_boxed(),
+                T::zero(),
+            ),
+        );
+
+        let a = 0.5;
+        let mut x = Vector2::new(-7.0, 0.75);
+        let delta_x = Vector2::new(0.5625, 1.25);
+        let delta_y = Vector2::new(1.125, 0.0);
+
+        for i in 0..COUNT {
+            let mut y = x;
+            for _ in i..COUNT {
+                world.create_body_with_fixture(
+                    BodyDef {
+                        type_: BodyType::Dynamic,
+                        position: (T::f32(y.x), T::f32(y.y)).into(),
+                        ..BodyDef::default()
+                    },
+                    FixtureDef::new(
+                        ShapePolygon::new_box_center(T::f32(a), T::f32(a)).into_boxed(),
+                        T::f32(5.0),
+                    ),
+                );
+                y += delta_y;
+            }
+            x += delta_x;
+        }
+
+        Pyramid
+    }
+}
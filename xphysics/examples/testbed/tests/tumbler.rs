@@ -0,0 +1,75 @@
+use crate::test::TestImpl;
+use xmath::Real;
+use xphysics::{
+    BodyDef, BodyType, FixtureDef, IntoBoxedShape, RevoluteJointDef, ShapePolygon, World,
+};
+
+const MAX_COUNT: usize = 800;
+
+/// 不断往旋转的箱子里添加小方块，箱子由带马达的旋转关节驱动
+pub struct Tumbler {
+    count: usize,
+}
+
+impl<T: Real> TestImpl<T> for Tumbler {
+    fn new(world: &mut World<T, ()>) -> Tumbler {
+        let wall = |hx: f32, hy: f32, x: f32, y: f32| {
+            FixtureDef::new(
+                ShapePolygon::new_box(T::f32(hx), T::f32(hy), (T::f32(x), T::f32(y)), T::zero())
+                    .into_boxed(),
+                T::f32(5.0),
+            )
+        };
+
+        let ground = world.create_body(BodyDef::default());
+        let body = world.create_body_with_fixtures(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (T::zero(), T::f32(10.0)).into(),
+                allow_sleep: false,
+                ..BodyDef::default()
+            },
+            vec![
+                wall(0.5, 10.0, 10.0, 0.0),
+                wall(0.5, 10.0, -10.0, 0.0),
+                wall(10.0, 0.5, 0.0, 10.0),
+                wall(10.0, 0.5, 0.0, -10.0),
+            ],
+        );
+
+        world.create_joint(RevoluteJointDef {
+            local_anchor_a: (T::zero(), T::f32(10.0)).into(),
+            enable_motor: true,
+            motor_speed: T::f32(0.05) * T::pi(),
+            max_motor_torque: T::f32(1e8),
+            ..RevoluteJointDef::new(ground, body)
+        });
+
+        Tumbler { count: 0 }
+    }
+
+    fn step(
+        &mut self,
+        world: &mut World<T, ()>,
+        time_step: T,
+        velocity_iterations: usize,
+        position_iterations: usize,
+    ) {
+        world.step(time_step, velocity_iterations, position_iterations);
+
+        if time_step > T::zero() && self.count < MAX_COUNT {
+            world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: (T::zero(), T::f32(10.0)).into(),
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(
+                    ShapePolygon::new_box_center(T::f32(0.125), T::f32(0.125)).into_boxed(),
+                    T::f32(1.0),
+                ),
+            );
+            self.count += 1;
+        }
+    }
+}
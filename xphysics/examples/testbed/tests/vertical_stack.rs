@@ -0,0 +1,95 @@
+use crate::test::TestImpl;
+use glutin::event::VirtualKeyCode;
+use xmath::{Real, Vector2};
+use xphysics::{
+    BodyDef, BodyId, BodyType, FixtureDef, IntoBoxedShape, ShapeCircle, ShapeEdge, ShapePolygon,
+    World,
+};
+
+const COLUMN_COUNT: usize = 5;
+const ROW_COUNT: usize = 10;
+
+/// 按`,`发射一颗子弹
+pub struct VerticalStack {
+    bullet: Option<BodyId>,
+}
+
+impl<T: Real> TestImpl<T> for VerticalStack {
+    fn new(world: &mut World<T, ()>) -> VerticalStack {
+        // Ground body
+        world.create_body_with_fixtures(
+            BodyDef::default(),
+            vec![
+                FixtureDef::new(
+                    ShapeEdge::new((T::f32(-40.0), T::f32(0.0)), (T::f32(40.0), T::f32(0.0)))
+                        .into_boxed(),
+                    T::zero(),
+                ),
+                FixtureDef::new(
+                    ShapeEdge::new((T::f32(20.0), T::f32(0.0)), (T::f32(20.0), T::f32(20.0)))
+                        .into_boxed(),
+                    T::zero(),
+                ),
+            ],
+        );
+
+        let xs = [0.0, -10.0, -5.0, 5.0, 10.0];
+        for (j, x) in xs.iter().enumerate().take(COLUMN_COUNT) {
+            for i in 0..ROW_COUNT {
+                // 稍微错开一点，避免完全对齐的堆叠
+                let offset = if j % 2 == 0 { 0.01 } else { -0.01 } * i as f32;
+                world.create_body_with_fixture(
+                    BodyDef {
+                        type_: BodyType::Dynamic,
+                        position: (T::f32(x + offset), T::f32(0.55 + 1.1 * i as f32)).into(),
+                        ..BodyDef::default()
+                    },
+                    FixtureDef {
+                        friction: T::f32(0.3),
+                        ..FixtureDef::new(
+                            ShapePolygon::new_box_center(T::f32(0.5), T::f32(0.5)).into_boxed(),
+                            T::f32(1.0),
+                        )
+                    },
+                );
+            }
+        }
+
+        VerticalStack { bullet: None }
+    }
+
+    fn keyboard(&mut self, world: &mut World<T, ()>, key: VirtualKeyCode) {
+        if key != VirtualKeyCode::Comma {
+            return;
+        }
+
+        let position = Vector2::new(T::f32(-31.0), T::f32(5.0));
+        let velocity = Vector2::new(T::f32(400.0), T::zero());
+
+        // 已经有子弹时重新发射
+        if let Some(id) = self.bullet.filter(|id| world.body(*id).is_some()) {
+            world.set_body_transform(id, position, T::zero());
+            let bullet = world.body_mut(id).unwrap();
+            bullet.set_linear_velocity(velocity);
+            bullet.set_angular_velocity(T::zero());
+            return;
+        }
+
+        self.bullet = Some(world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                bullet: true,
+                position,
+                linear_velocity: velocity,
+                ..BodyDef::default()
+            },
+            FixtureDef {
+                restitution: T::f32(0.05),
+                ..FixtureDef::new(
+                    ShapeCircle::new_with_radius(T::f32(0.25)).into_boxed(),
+                    T::f32(20.0),
+                )
+            },
+        ));
+    }
+}
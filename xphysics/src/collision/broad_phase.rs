@@ -11,6 +11,8 @@ pub(crate) struct BroadPhase<T, D> {
     pub(crate) tree: DynamicTree<T, D>,
     move_buffer: Vec<Option<usize>>,
     pair_buffer: Vec<Pair>,
+    query_stack: Vec<usize>,
+    pub(crate) query_count: usize,
 }
 
 impl<T: Real, D> BroadPhase<T, D> {
@@ -19,9 +21,17 @@ impl<T: Real, D> BroadPhase<T, D> {
             tree: DynamicTree::new(),
             move_buffer: Default::default(),
             pair_buffer: Default::default(),
+            query_stack: Default::default(),
+            query_count: 0,
         }
     }
 
+    pub fn clear(&mut self) {
+        self.tree.clear();
+        self.move_buffer.clear();
+        self.pair_buffer.clear();
+    }
+
     pub fn create_proxy(&mut self, aabb: AABB<T>, data: D) -> usize {
         let proxy_id = self.tree.create_proxy(aabb, data);
         self.buffer_move(proxy_id);
@@ -52,21 +62,19 @@ impl<T: Real, D> BroadPhase<T, D> {
     pub fn update_pairs<F: FnMut(&D, &D)>(&mut self, mut cb: F) {
         self.pair_buffer.clear();
 
-        for id in &self.move_buffer {
-            if let Some(id) = id {
-                let fat_aabb = self.tree.get_fat_aabb(*id);
-                for other_id in self
-                    .tree
-                    .query(*fat_aabb)
-                    .map(|item| item.0)
-                    .filter(|other_id| *other_id != *id)
-                {
-                    self.pair_buffer.push(Pair {
-                        proxy_id_a: other_id.min(*id),
-                        proxy_id_b: other_id.max(*id),
+        let tree = &self.tree;
+        let pair_buffer = &mut self.pair_buffer;
+        for &id in self.move_buffer.iter().flatten() {
+            let fat_aabb = tree.get_fat_aabb(id);
+            self.query_count += 1;
+            tree.query_with(fat_aabb, &mut self.query_stack, |other_id| {
+                if other_id != id {
+                    pair_buffer.push(Pair {
+                        proxy_id_a: other_id.min(id),
+                        proxy_id_b: other_id.max(id),
                     });
                 }
-            }
+            });
         }
 
         self.move_buffer.clear();
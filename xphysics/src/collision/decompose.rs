@@ -0,0 +1,183 @@
+use crate::{settings, ShapePolygon};
+use xmath::{CrossTrait, Real, Vector2};
+
+/// 把任意简单多边形（可以是凹的）分解成若干个凸多边形
+///
+/// 先用耳切法三角化，再合并相邻的三角形（Hertel-Mehlhorn），合并后的顶点数不超过`MAX_POLYGON_VERTICES`。
+/// 轮廓的顶点可以是顺时针或逆时针，不能自相交；重合和共线的顶点会被忽略。
+pub fn decompose_polygon<T: Real>(outline: &[Vector2<T>]) -> Vec<ShapePolygon<T>> {
+    let points = clean_outline(outline);
+    if points.len() < 3 {
+        return Vec::new();
+    }
+
+    let mut pieces = triangulate(&points);
+    merge_pieces(&points, &mut pieces);
+
+    pieces
+        .into_iter()
+        .map(|piece| ShapePolygon::new(piece.into_iter().map(|i| points[i])))
+        .collect()
+}
+
+fn cross3<T: Real>(a: Vector2<T>, b: Vector2<T>, c: Vector2<T>) -> T {
+    (b - a).cross(c - b)
+}
+
+/// 去掉重合和共线的顶点，并把顶点调整为逆时针顺序
+fn clean_outline<T: Real>(outline: &[Vector2<T>]) -> Vec<Vector2<T>> {
+    let slop = T::half() * settings::linear_slop();
+    let mut points: Vec<Vector2<T>> = Vec::with_capacity(outline.len());
+    for p in outline {
+        if let Some(last) = points.last() {
+            if last.distance_squared(p) < slop * slop {
+                continue;
+            }
+        }
+        points.push(*p);
+    }
+    while points.len() > 1 && points[0].distance_squared(points.last().unwrap()) < slop * slop {
+        points.pop();
+    }
+
+    let mut i = 0;
+    while points.len() >= 3 && i < points.len() {
+        let n = points.len();
+        let prev = points[(i + n - 1) % n];
+        let next = points[(i + 1) % n];
+        if cross3(prev, points[i], next) == T::zero() {
+            points.remove(i);
+            i = i.saturating_sub(1);
+        } else {
+            i += 1;
+        }
+    }
+
+    let mut area = T::zero();
+    for i in 0..points.len() {
+        area += points[i].cross(points[(i + 1) % points.len()]);
+    }
+    if area < T::zero() {
+        points.reverse();
+    }
+    points
+}
+
+fn in_triangle<T: Real>(p: Vector2<T>, a: Vector2<T>, b: Vector2<T>, c: Vector2<T>) -> bool {
+    (b - a).cross(p - a) >= T::zero()
+        && (c - b).cross(p - b) >= T::zero()
+        && (a - c).cross(p - c) >= T::zero()
+}
+
+/// 耳切法三角化，返回顶点索引
+fn triangulate<T: Real>(points: &[Vector2<T>]) -> Vec<Vec<usize>> {
+    let mut remaining = (0..points.len()).collect::<Vec<_>>();
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let mut clipped = false;
+
+        for i in 0..n {
+            let (ia, ib, ic) = (
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            );
+            let (a, b, c) = (points[ia], points[ib], points[ic]);
+            let turn = cross3(a, b, c);
+
+            // 剪耳后产生的共线顶点直接去掉
+            if turn == T::zero() {
+                remaining.remove(i);
+                clipped = true;
+                break;
+            }
+            if turn < T::zero() {
+                continue;
+            }
+
+            let is_ear = remaining.iter().all(|&j| {
+                j == ia || j == ib || j == ic || {
+                    let p = points[j];
+                    p == a || p == b || p == c || !in_triangle(p, a, b, c)
+                }
+            });
+            if is_ear {
+                triangles.push(vec![ia, ib, ic]);
+                remaining.remove(i);
+                clipped = true;
+                break;
+            }
+        }
+
+        // 轮廓自相交时找不到耳朵
+        if !clipped {
+            break;
+        }
+    }
+
+    if remaining.len() == 3
+        && cross3(
+            points[remaining[0]],
+            points[remaining[1]],
+            points[remaining[2]],
+        ) > T::zero()
+    {
+        triangles.push(remaining);
+    }
+    triangles
+}
+
+/// 合并共享一条边的两个多边形，合并后必须仍然是凸的
+fn try_merge<T: Real>(points: &[Vector2<T>], p: &[usize], q: &[usize]) -> Option<Vec<usize>> {
+    if p.len() + q.len() - 2 > settings::MAX_POLYGON_VERTICES {
+        return None;
+    }
+
+    for i in 0..p.len() {
+        let (a, b) = (p[i], p[(i + 1) % p.len()]);
+        let j = match (0..q.len()).find(|&j| q[j] == b && q[(j + 1) % q.len()] == a) {
+            Some(j) => j,
+            None => continue,
+        };
+
+        // 从b开始沿p走到a，再沿q从a走回b
+        let mut merged = Vec::with_capacity(p.len() + q.len() - 2);
+        for k in 0..p.len() {
+            merged.push(p[(i + 1 + k) % p.len()]);
+        }
+        for k in 2..q.len() {
+            merged.push(q[(j + k) % q.len()]);
+        }
+
+        let n = merged.len();
+        let convex = (0..n).all(|k| {
+            cross3(
+                points[merged[(k + n - 1) % n]],
+                points[merged[k]],
+                points[merged[(k + 1) % n]],
+            ) >= T::zero()
+        });
+        return if convex { Some(merged) } else { None };
+    }
+    None
+}
+
+fn merge_pieces<T: Real>(points: &[Vector2<T>], pieces: &mut Vec<Vec<usize>>) {
+    let mut i = 0;
+    while i < pieces.len() {
+        let mut merged = false;
+        for j in i + 1..pieces.len() {
+            if let Some(piece) = try_merge(points, &pieces[i], &pieces[j]) {
+                pieces[i] = piece;
+                pieces.remove(j);
+                merged = true;
+                break;
+            }
+        }
+        if !merged {
+            i += 1;
+        }
+    }
+}
@@ -41,6 +41,8 @@ pub struct DistanceInput<'a, T: Real> {
     pub use_radii: bool,
 }
 
+/// 两个形状上的最近点，`iterations`是GJK的迭代次数
+#[derive(Debug, Copy, Clone)]
 pub struct DistanceOutput<T> {
     pub point_a: Vector2<T>,
     pub point_b: Vector2<T>,
@@ -77,6 +79,7 @@ impl<T: Real> Simplex<T> {
     ) {
         assert!(cache.count <= 3);
 
+        self.count = cache.count;
         for (i, v) in unsafe {
             std::slice::from_raw_parts_mut(&mut self.v1 as *mut SimplexVertex<T>, self.count)
         }
@@ -241,7 +244,7 @@ impl<T: Real> Simplex<T> {
         }
 
         if d13_1 > T::zero() && d13_2 > T::zero() && d123_2 <= T::zero() {
-            let inv_d13 = T::zero() / (d13_1 + d13_2);
+            let inv_d13 = T::one() / (d13_1 + d13_2);
             self.v1.a = d13_1 * inv_d13;
             self.v3.a = d13_2 * inv_d13;
             self.count = 2;
@@ -4,6 +4,43 @@ use xmath::{CrossTrait, DotTrait, Real, Vector2, AABB};
 
 const QUERY_STACK_INIT_SIZE: usize = 256;
 
+/// 查询用的栈，和Box2D的`b2GrowableStack`相同，不超过`QUERY_STACK_INIT_SIZE`时不分配内存
+struct GrowableStack {
+    array: [usize; QUERY_STACK_INIT_SIZE],
+    count: usize,
+    overflow: Vec<usize>,
+}
+
+impl GrowableStack {
+    fn new() -> GrowableStack {
+        GrowableStack {
+            array: [0; QUERY_STACK_INIT_SIZE],
+            count: 0,
+            overflow: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, value: usize) {
+        if self.count < QUERY_STACK_INIT_SIZE {
+            self.array[self.count] = value;
+            self.count += 1;
+        } else {
+            self.overflow.push(value);
+        }
+    }
+
+    fn pop(&mut self) -> Option<usize> {
+        if let Some(value) = self.overflow.pop() {
+            return Some(value);
+        }
+        if self.count == 0 {
+            return None;
+        }
+        self.count -= 1;
+        Some(self.array[self.count])
+    }
+}
+
 #[derive(Debug)]
 struct Node<T, D> {
     aabb: AABB<T>,
@@ -33,6 +70,12 @@ impl<T: Real, D> DynamicTree<T, D> {
         }
     }
 
+    /// 删除所有的代理，保留已经分配的节点空间
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.nodes.clear();
+    }
+
     fn allocate_node(&mut self, aabb: AABB<T>, data: Option<D>, height: i32) -> usize {
         self.nodes.insert(Node {
             aabb,
@@ -338,8 +381,30 @@ impl<T: Real, D> DynamicTree<T, D> {
         self.nodes.get(proxy_id).and_then(|d| d.data.as_ref())
     }
 
+    pub fn get_data_mut(&mut self, proxy_id: usize) -> Option<&mut D> {
+        self.nodes.get_mut(proxy_id).and_then(|d| d.data.as_mut())
+    }
+
+    /// 树的高度，空树为0
+    pub fn height(&self) -> i32 {
+        self.root.map(|root| self.nodes[root].height).unwrap_or(0)
+    }
+
+    /// 叶子节点的数量
+    pub fn proxy_count(&self) -> usize {
+        self.nodes.iter().filter(|(_, node)| node.is_leaf()).count()
+    }
+
+    /// 所有内部节点的包围盒和高度，叶子节点的高度为0，用于调试绘制
+    pub fn internal_nodes(&self) -> impl Iterator<Item = (&AABB<T>, i32)> {
+        self.nodes
+            .iter()
+            .filter(|(_, node)| !node.is_leaf())
+            .map(|(_, node)| (&node.aabb, node.height))
+    }
+
     pub fn query(&self, aabb: AABB<T>) -> QueryIter<T, D> {
-        let mut stack = Vec::with_capacity(QUERY_STACK_INIT_SIZE);
+        let mut stack = GrowableStack::new();
         if let Some(root) = self.root {
             stack.push(root);
         }
@@ -350,6 +415,30 @@ impl<T: Real, D> DynamicTree<T, D> {
         }
     }
 
+    /// 和`query`相同，使用调用者提供的栈，步进中反复查询时不用每次都分配内存
+    pub(crate) fn query_with<F: FnMut(usize)>(
+        &self,
+        aabb: &AABB<T>,
+        stack: &mut Vec<usize>,
+        mut f: F,
+    ) {
+        stack.clear();
+        if let Some(root) = self.root {
+            stack.push(root);
+        }
+        while let Some(idx) = stack.pop() {
+            let node = &self.nodes[idx];
+            if node.aabb.is_overlap(aabb) {
+                if node.is_leaf() {
+                    f(idx);
+                } else {
+                    stack.push(node.child1.unwrap());
+                    stack.push(node.child2.unwrap());
+                }
+            }
+        }
+    }
+
     pub fn ray_cast(&self, input: RayCastInput<T>) -> RayCastIter<T, D> {
         let r = (input.p2 - input.p1).normalize();
         assert!(r.length_squared() > T::zero());
@@ -365,7 +454,7 @@ impl<T: Real, D> DynamicTree<T, D> {
             }
         };
 
-        let mut stack = Vec::with_capacity(QUERY_STACK_INIT_SIZE);
+        let mut stack = GrowableStack::new();
         if let Some(root) = self.root {
             stack.push(root);
         }
@@ -391,7 +480,7 @@ impl<T: Real, D> DynamicTree<T, D> {
 
 pub struct QueryIter<'a, T, D> {
     tree: &'a DynamicTree<T, D>,
-    stack: Vec<usize>,
+    stack: GrowableStack,
     aabb: AABB<T>,
 }
 
@@ -415,7 +504,7 @@ impl<'a, T: Real, D> Iterator for QueryIter<'a, T, D> {
 
 pub struct RayCastIter<'a, T, D> {
     tree: &'a DynamicTree<T, D>,
-    stack: Vec<usize>,
+    stack: GrowableStack,
     segment_aabb: AABB<T>,
     v: Vector2<T>,
     v_abs: Vector2<T>,
@@ -504,4 +593,16 @@ mod tests {
             .collect::<Vec<_>>();
         assert!(!res.is_empty());
     }
+
+    #[test]
+    fn test_growable_stack() {
+        let mut stack = GrowableStack::new();
+        for i in 0..QUERY_STACK_INIT_SIZE + 10 {
+            stack.push(i);
+        }
+        for i in (0..QUERY_STACK_INIT_SIZE + 10).rev() {
+            assert_eq!(stack.pop(), Some(i));
+        }
+        assert_eq!(stack.pop(), None);
+    }
 }
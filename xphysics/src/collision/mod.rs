@@ -6,7 +6,8 @@ mod broad_phase;
 mod collide_circle;
 mod collide_edge;
 mod collide_polygon;
-mod distance;
+mod decompose;
+pub(crate) mod distance;
 pub(crate) mod dynamic_tree;
 mod shapes;
 pub(crate) mod time_of_impact;
@@ -15,6 +16,8 @@ pub(crate) use broad_phase::BroadPhase;
 pub(crate) use collide_circle::{collide_circles, collide_polygon_and_circle};
 pub(crate) use collide_edge::{collide_edge_and_circle, collide_edge_and_polygon};
 pub(crate) use collide_polygon::collide_polygons;
+pub use decompose::decompose_polygon;
+pub use distance::DistanceOutput;
 pub use shapes::*;
 
 pub struct MassData<T> {
@@ -248,7 +251,8 @@ pub(crate) fn clip_segment_to_line<T: Real>(
     num_out
 }
 
-pub(crate) fn test_overlap<T: Real>(
+/// 两个形状的一对子形状是否重叠
+pub(crate) fn test_child_overlap<T: Real>(
     shape_a: &dyn Shape<T>,
     index_a: usize,
     shape_b: &dyn Shape<T>,
@@ -268,3 +272,48 @@ pub(crate) fn test_overlap<T: Real>(
     let output = distance::distance(&input, &mut cache);
     output.distance < T::ten() * T::epsilon()
 }
+
+/// 两个形状是否重叠，链形状只要有一个子形状重叠就返回`true`
+pub fn test_overlap<T: Real>(
+    shape_a: &dyn Shape<T>,
+    xf_a: Transform<T>,
+    shape_b: &dyn Shape<T>,
+    xf_b: Transform<T>,
+) -> bool {
+    (0..shape_a.child_count()).any(|index_a| {
+        (0..shape_b.child_count())
+            .any(|index_b| test_child_overlap(shape_a, index_a, shape_b, index_b, xf_a, xf_b))
+    })
+}
+
+/// 计算两个形状的最近点和距离，形状重叠时距离为0
+///
+/// 距离已经减去了两个形状的半径，多边形和边带有很薄的外皮。链形状取所有子形状中最近的一对。
+pub fn distance<T: Real>(
+    shape_a: &dyn Shape<T>,
+    xf_a: Transform<T>,
+    shape_b: &dyn Shape<T>,
+    xf_b: Transform<T>,
+) -> DistanceOutput<T> {
+    let mut best: Option<DistanceOutput<T>> = None;
+    for index_a in 0..shape_a.child_count() {
+        for index_b in 0..shape_b.child_count() {
+            let input = DistanceInput {
+                proxy_a: &shape_a.distance_proxy(index_a),
+                proxy_b: &shape_b.distance_proxy(index_b),
+                transform_a: xf_a,
+                transform_b: xf_b,
+                use_radii: true,
+            };
+            let output = distance::distance(&input, &mut SimpleCache::default());
+            let closer = match &best {
+                Some(best) => output.distance < best.distance,
+                None => true,
+            };
+            if closer {
+                best = Some(output);
+            }
+        }
+    }
+    best.expect("shape has no children")
+}
@@ -4,6 +4,7 @@ use crate::{MassData, RayCastInput, RayCastOutput, Shape, ShapeEdge, ShapeType};
 use std::borrow::Cow;
 use xmath::{Multiply, Real, Transform, Vector2, AABB};
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ShapeChain<T> {
     pub(crate) vertices: Vec<Vector2<T>>,
     pub(crate) prev_vertex: Option<Vector2<T>>,
@@ -138,6 +139,15 @@ impl<T: Real> Shape<T> for ShapeChain<T> {
         }
     }
 
+    fn compute_distance(
+        &self,
+        xf: &Transform<T>,
+        p: &Vector2<T>,
+        child_index: usize,
+    ) -> (T, Vector2<T>) {
+        self.get_child_edge(child_index).compute_distance(xf, p, 0)
+    }
+
     fn distance_proxy(&self, index: usize) -> DistanceProxy<'_, T> {
         assert!(index < self.vertices.len());
 
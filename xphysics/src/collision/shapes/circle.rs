@@ -3,6 +3,7 @@ use crate::{MassData, RayCastInput, RayCastOutput, Shape, ShapeType};
 use std::borrow::Cow;
 use xmath::{DotTrait, Multiply, Real, Transform, Vector2, AABB};
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ShapeCircle<T> {
     pub(crate) radius: T,
     pub(crate) position: Vector2<T>,
@@ -42,7 +43,7 @@ impl<T: Real> Shape<T> for ShapeCircle<T> {
     }
 
     fn test_point(&self, xf: &Transform<T>, p: &Vector2<T>) -> bool {
-        let center = xf.p + xf.q.multiply(*p);
+        let center = xf.p + xf.q.multiply(self.position);
         let d = *p - center;
         d.dot(d) <= self.radius * self.radius
     }
@@ -94,6 +95,17 @@ impl<T: Real> Shape<T> for ShapeCircle<T> {
         MassData { mass, center, i }
     }
 
+    fn compute_distance(
+        &self,
+        xf: &Transform<T>,
+        p: &Vector2<T>,
+        _child_index: usize,
+    ) -> (T, Vector2<T>) {
+        let center = xf.p + xf.q.multiply(self.position);
+        let d = *p - center;
+        (d.length() - self.radius, d.normalize())
+    }
+
     fn distance_proxy(&self, _index: usize) -> DistanceProxy<'_, T> {
         DistanceProxy {
             vertices: Cow::Borrowed(std::slice::from_ref(&self.position)),
@@ -0,0 +1,136 @@
+use crate::collision::distance::DistanceProxy;
+use crate::{
+    settings, MassData, RayCastInput, RayCastOutput, Shape, ShapeCircle, ShapePolygon, ShapeType,
+};
+use xmath::{Multiply, Real, Transform, Vector2, AABB};
+
+/// 复合形状的子形状，只能是凸形状
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CompoundChild<T> {
+    Circle(ShapeCircle<T>),
+    Polygon(ShapePolygon<T>),
+}
+
+impl<T: Real> CompoundChild<T> {
+    pub fn shape(&self) -> &dyn Shape<T> {
+        match self {
+            CompoundChild::Circle(circle) => circle,
+            CompoundChild::Polygon(polygon) => polygon,
+        }
+    }
+}
+
+/// 由多个凸形状组成的形状，一个夹具就可以表示复杂的刚体
+///
+/// 子形状加入时就换算到复合形状的坐标系，每个子形状在broad-phase中有自己的代理。
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShapeCompound<T> {
+    pub(crate) children: Vec<CompoundChild<T>>,
+}
+
+impl<T: Real> Default for ShapeCompound<T> {
+    fn default() -> Self {
+        ShapeCompound {
+            children: Vec::new(),
+        }
+    }
+}
+
+impl<T: Real> ShapeCompound<T> {
+    pub fn new() -> ShapeCompound<T> {
+        Default::default()
+    }
+
+    pub fn add_circle(&mut self, circle: ShapeCircle<T>, xf: Transform<T>) {
+        self.children.push(CompoundChild::Circle(ShapeCircle {
+            position: xf.multiply(circle.position),
+            radius: circle.radius,
+        }));
+    }
+
+    pub fn add_polygon(&mut self, mut polygon: ShapePolygon<T>, xf: Transform<T>) {
+        for i in 0..polygon.count {
+            polygon.vertices[i] = xf.multiply(polygon.vertices[i]);
+            polygon.normals[i] = xf.q.multiply(polygon.normals[i]);
+        }
+        polygon.centroid = xf.multiply(polygon.centroid);
+        self.children.push(CompoundChild::Polygon(polygon));
+    }
+
+    pub fn children(&self) -> &[CompoundChild<T>] {
+        &self.children
+    }
+}
+
+impl<T: Real> Shape<T> for ShapeCompound<T> {
+    fn shape_type(&self) -> ShapeType {
+        ShapeType::Compound
+    }
+
+    /// 子形状中最大的半径
+    fn radius(&self) -> T {
+        self.children
+            .iter()
+            .map(|child| child.shape().radius())
+            .fold(settings::polygon_radius(), T::max)
+    }
+
+    fn child_radius(&self, index: usize) -> T {
+        self.children[index].shape().radius()
+    }
+
+    fn child_count(&self) -> usize {
+        self.children.len()
+    }
+
+    fn test_point(&self, xf: &Transform<T>, p: &Vector2<T>) -> bool {
+        self.children
+            .iter()
+            .any(|child| child.shape().test_point(xf, p))
+    }
+
+    fn ray_cast(
+        &self,
+        input: &RayCastInput<T>,
+        xf: &Transform<T>,
+        child_index: usize,
+    ) -> Option<RayCastOutput<T>> {
+        self.children[child_index].shape().ray_cast(input, xf, 0)
+    }
+
+    fn compute_aabb(&self, xf: &Transform<T>, child_index: usize) -> AABB<T> {
+        self.children[child_index].shape().compute_aabb(xf, 0)
+    }
+
+    /// 子形状的质量都是相对形状原点计算的，可以直接相加
+    fn compute_mass(&self, density: T) -> MassData<T> {
+        let mut mass = T::zero();
+        let mut center = Vector2::zero();
+        let mut i = T::zero();
+        for child in &self.children {
+            let data = child.shape().compute_mass(density);
+            mass += data.mass;
+            center += data.center * data.mass;
+            i += data.i;
+        }
+        if mass > T::zero() {
+            center *= T::one() / mass;
+        }
+        MassData { mass, center, i }
+    }
+
+    fn compute_distance(
+        &self,
+        xf: &Transform<T>,
+        p: &Vector2<T>,
+        child_index: usize,
+    ) -> (T, Vector2<T>) {
+        self.children[child_index]
+            .shape()
+            .compute_distance(xf, p, 0)
+    }
+
+    fn distance_proxy(&self, index: usize) -> DistanceProxy<'_, T> {
+        self.children[index].shape().distance_proxy(0)
+    }
+}
@@ -3,6 +3,7 @@ use crate::{settings, MassData, RayCastInput, RayCastOutput, Shape, ShapeType};
 use std::borrow::Cow;
 use xmath::{DotTrait, Multiply, Real, Transform, TransposeMultiply, Vector2, AABB};
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ShapeEdge<T> {
     pub(crate) vertex1: Vector2<T>,
     pub(crate) vertex2: Vector2<T>,
@@ -129,6 +130,18 @@ impl<T: Real> Shape<T> for ShapeEdge<T> {
         }
     }
 
+    fn compute_distance(
+        &self,
+        xf: &Transform<T>,
+        p: &Vector2<T>,
+        _child_index: usize,
+    ) -> (T, Vector2<T>) {
+        let v1 = xf.multiply(self.vertex1);
+        let v2 = xf.multiply(self.vertex2);
+        let d = *p - closest_point_on_segment(*p, v1, v2);
+        (d.length() - self.radius(), d.normalize())
+    }
+
     fn distance_proxy(&self, _index: usize) -> DistanceProxy<'_, T> {
         DistanceProxy {
             vertices: Cow::Borrowed(unsafe {
@@ -138,3 +151,17 @@ impl<T: Real> Shape<T> for ShapeEdge<T> {
         }
     }
 }
+
+/// 线段`v1`-`v2`上离`p`最近的点
+pub(crate) fn closest_point_on_segment<T: Real>(
+    p: Vector2<T>,
+    v1: Vector2<T>,
+    v2: Vector2<T>,
+) -> Vector2<T> {
+    let e = v2 - v1;
+    let ee = e.dot(e);
+    if ee <= T::zero() {
+        return v1;
+    }
+    v1 + e * ((p - v1).dot(e) / ee).clamp(T::zero(), T::one())
+}
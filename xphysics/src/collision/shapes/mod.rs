@@ -3,20 +3,24 @@ use xmath::{Real, Transform, Vector2, AABB};
 
 mod chain;
 mod circle;
+mod compound;
 mod edge;
 mod polygon;
 
 use crate::collision::distance::DistanceProxy;
 pub use chain::ShapeChain;
 pub use circle::ShapeCircle;
+pub use compound::{CompoundChild, ShapeCompound};
 pub use edge::ShapeEdge;
 pub use polygon::ShapePolygon;
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum ShapeType {
     Circle,
     Edge,
     Polygon,
     Chain,
+    Compound,
 }
 
 pub trait Shape<T: Real> {
@@ -24,6 +28,11 @@ pub trait Shape<T: Real> {
 
     fn radius(&self) -> T;
 
+    /// 子形状的半径，只有复合形状的子形状半径会不同
+    fn child_radius(&self, _index: usize) -> T {
+        self.radius()
+    }
+
     fn child_count(&self) -> usize;
 
     fn test_point(&self, xf: &Transform<T>, p: &Vector2<T>) -> bool;
@@ -39,6 +48,14 @@ pub trait Shape<T: Real> {
 
     fn compute_mass(&self, density: T) -> MassData<T>;
 
+    /// 点到子形状表面的有符号距离和表面的外法线，点在形状内部时距离为负
+    fn compute_distance(
+        &self,
+        xf: &Transform<T>,
+        p: &Vector2<T>,
+        child_index: usize,
+    ) -> (T, Vector2<T>);
+
     fn distance_proxy(&self, index: usize) -> DistanceProxy<'_, T>;
 }
 
@@ -1,10 +1,12 @@
 use crate::collision::distance::DistanceProxy;
+use crate::collision::shapes::edge::closest_point_on_segment;
 use crate::{settings, MassData, RayCastInput, RayCastOutput, Shape, ShapeType};
 use std::borrow::Cow;
 use xmath::{
     CrossTrait, DotTrait, Multiply, Real, Rotation, Transform, TransposeMultiply, Vector2, AABB,
 };
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ShapePolygon<T> {
     pub(crate) centroid: Vector2<T>,
     pub(crate) vertices: [Vector2<T>; settings::MAX_POLYGON_VERTICES],
@@ -345,6 +347,41 @@ impl<T: Real> Shape<T> for ShapePolygon<T> {
         }
     }
 
+    fn compute_distance(
+        &self,
+        xf: &Transform<T>,
+        p: &Vector2<T>,
+        _child_index: usize,
+    ) -> (T, Vector2<T>) {
+        let local = xf.q.transpose_multiply(*p - xf.p);
+
+        let mut max_distance = T::min_value();
+        let mut normal = Vector2::zero();
+        for i in 0..self.count {
+            let dot = self.normals[i].dot(local - self.vertices[i]);
+            if dot > max_distance {
+                max_distance = dot;
+                normal = self.normals[i];
+            }
+        }
+
+        // 在外部时取到每条边的最近距离
+        if max_distance > T::zero() {
+            let mut min_d = local - self.vertices[0];
+            for i in 0..self.count {
+                let j = if i + 1 < self.count { i + 1 } else { 0 };
+                let d = local - closest_point_on_segment(local, self.vertices[i], self.vertices[j]);
+                if d.length_squared() < min_d.length_squared() {
+                    min_d = d;
+                }
+            }
+            max_distance = min_d.length();
+            normal = min_d.normalize();
+        }
+
+        (max_distance - self.radius(), xf.q.multiply(normal))
+    }
+
     fn distance_proxy(&self, _index: usize) -> DistanceProxy<'_, T> {
         DistanceProxy {
             vertices: Cow::Borrowed(&self.vertices[..self.count]),
@@ -1,14 +1,16 @@
 use crate::dynamic::contacts::ContactEdge;
-use crate::dynamic::fixture::FixtureDef;
-use crate::dynamic::world::{WorldFlags, WorldInner};
+use crate::dynamic::fixture::{FixtureDef, FixtureKey};
+use crate::dynamic::joints::{Joint, JointId};
+use crate::dynamic::world::BodyId;
 use crate::math::Sweep;
-use crate::Shape;
-use crate::{Fixture, MassData};
+use crate::{BroadPhase, Fixture, MassData};
+use slab::Slab;
 use xmath::{
     CrossTrait, DotTrait, Multiply, Real, Rotation, Transform, TransposeMultiply, Vector2,
 };
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BodyType {
     Static,
     Kinematic,
@@ -30,6 +32,9 @@ pub struct BodyDef<T, D> {
     pub active: bool,
     pub data: Option<D>,
     pub gravity_scale: T,
+
+    /// 支配等级，两个动态刚体接触时等级高的不会被等级低的推动
+    pub dominance: i8,
 }
 
 impl<T: Real, D> Default for BodyDef<T, D> {
@@ -49,6 +54,7 @@ impl<T: Real, D> Default for BodyDef<T, D> {
             active: true,
             data: None,
             gravity_scale: T::one(),
+            dominance: 0,
         }
     }
 }
@@ -66,11 +72,12 @@ bitflags! {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FixtureId(usize);
 
 pub struct Body<T, D> {
-    pub(crate) world_ptr: *mut WorldInner<T, D>,
+    pub(crate) id: BodyId,
     pub(crate) type_: BodyType,
     pub(crate) flags: BodyFlags,
     pub(crate) island_index: usize,
@@ -80,11 +87,10 @@ pub struct Body<T, D> {
     pub(crate) angular_velocity_: T,
     pub(crate) force: Vector2<T>,
     pub(crate) torque: T,
-    pub(crate) prev_ptr: *mut Body<T, D>,
-    pub(crate) next_ptr: *mut Body<T, D>,
     pub(crate) fixture_inc_id: usize,
     pub(crate) fixture_list: Vec<(FixtureId, Box<Fixture<T, D>>)>,
-    pub(crate) contact_list_ptr: *mut ContactEdge<T, D>,
+    pub(crate) contact_edges: Vec<ContactEdge>,
+    pub(crate) joint_list: Vec<JointId>,
     pub(crate) mass: T,
     pub(crate) inv_mass: T,
     pub(crate) i: T,
@@ -92,12 +98,14 @@ pub struct Body<T, D> {
     pub(crate) linear_damping: T,
     pub(crate) angular_damping: T,
     pub(crate) gravity_scale: T,
+    pub(crate) dominance: i8,
+    pub(crate) island: Option<usize>,
     pub(crate) sleep_time: T,
     pub(crate) data: Option<D>,
 }
 
 impl<T: Real, D> Body<T, D> {
-    pub(crate) fn new(world_ptr: *mut WorldInner<T, D>, def: BodyDef<T, D>) -> Body<T, D> {
+    pub(crate) fn new(id: BodyId, def: BodyDef<T, D>) -> Body<T, D> {
         assert!(def.position.is_valid());
         assert!(def.linear_velocity.is_valid());
         assert!(def.angle.is_valid());
@@ -134,7 +142,7 @@ impl<T: Real, D> Body<T, D> {
         };
 
         Body {
-            world_ptr,
+            id,
             type_: def.type_,
             flags,
             island_index: 0,
@@ -151,11 +159,10 @@ impl<T: Real, D> Body<T, D> {
             angular_velocity_: def.angular_velocity,
             force: Vector2::zero(),
             torque: T::zero(),
-            prev_ptr: std::ptr::null_mut(),
-            next_ptr: std::ptr::null_mut(),
             fixture_inc_id: 0,
             fixture_list: Vec::new(),
-            contact_list_ptr: std::ptr::null_mut(),
+            contact_edges: Vec::new(),
+            joint_list: Vec::new(),
             mass,
             inv_mass,
             i: T::zero(),
@@ -163,11 +170,17 @@ impl<T: Real, D> Body<T, D> {
             linear_damping: def.linear_damping,
             angular_damping: def.angular_damping,
             gravity_scale: def.gravity_scale,
+            dominance: def.dominance,
+            island: None,
             sleep_time: T::zero(),
             data: def.data,
         }
     }
 
+    pub fn id(&self) -> BodyId {
+        self.id
+    }
+
     pub fn body_type(&self) -> BodyType {
         self.type_
     }
@@ -192,6 +205,26 @@ impl<T: Real, D> Body<T, D> {
         &self.sweep.local_center
     }
 
+    pub(crate) fn set_transform(
+        &mut self,
+        broad_phase: &mut BroadPhase<T, FixtureKey>,
+        position: Vector2<T>,
+        angle: T,
+    ) {
+        self.xf.q = Rotation::new(angle);
+        self.xf.p = position;
+
+        self.sweep.c = self.xf.multiply(self.sweep.local_center);
+        self.sweep.a = angle;
+
+        self.sweep.c0 = self.sweep.c;
+        self.sweep.a0 = angle;
+
+        for (_, f) in &mut self.fixture_list {
+            f.synchronize(broad_phase, &self.xf, &self.xf);
+        }
+    }
+
     pub fn set_linear_velocity(&mut self, v: Vector2<T>) {
         if self.type_ == BodyType::Static {
             return;
@@ -325,18 +358,18 @@ impl<T: Real, D> Body<T, D> {
         self.xf.p = self.sweep.c - self.xf.q.multiply(self.sweep.local_center);
     }
 
-    pub(crate) fn synchronize_fixtures(&mut self) {
+    pub(crate) fn synchronize_fixtures(
+        &mut self,
+        broad_phase: &mut BroadPhase<T, FixtureKey>,
+    ) {
         let r = Rotation::new(self.sweep.a0);
         let xf1 = Transform {
             q: r,
             p: self.sweep.c0 - r.multiply(self.sweep.local_center),
         };
 
-        unsafe {
-            let broad_phase = &mut (*self.world_ptr).contact_manager.broad_phase;
-            for (_, f) in &mut self.fixture_list {
-                f.synchronize(broad_phase, &xf1, &self.xf);
-            }
+        for (_, f) in &mut self.fixture_list {
+            f.synchronize(broad_phase, &xf1, &self.xf);
         }
     }
 
@@ -404,53 +437,25 @@ impl<T: Real, D> Body<T, D> {
         self.gravity_scale = scale;
     }
 
-    pub fn set_debug_draw(&mut self, flag: bool) {
-        self.flags.set(BodyFlags::DEBUG_DRAW, flag);
+    /// 上一步中所在的岛在`World::islands`中的索引，静态和睡眠的刚体不在任何岛中
+    pub fn island(&self) -> Option<usize> {
+        self.island
     }
 
-    pub fn is_debug_draw(&self) -> bool {
-        self.flags.contains(BodyFlags::DEBUG_DRAW)
+    pub fn dominance(&self) -> i8 {
+        self.dominance
     }
 
-    pub fn set_body_type(&mut self, type_: BodyType) {
-        unsafe {
-            assert!(!(*self.world_ptr).flags.contains(WorldFlags::LOCKED));
-            if self.type_ == type_ {
-                return;
-            }
-            self.type_ = type_;
-            self.reset_mass();
-
-            if self.type_ == BodyType::Static {
-                self.linear_velocity_ = Vector2::zero();
-                self.angular_velocity_ = T::zero();
-                self.sweep.a0 = self.sweep.a;
-                self.sweep.c0 = self.sweep.c;
-                self.synchronize_fixtures();
-            }
-
-            self.set_awake(true);
-
-            self.force = Vector2::zero();
-            self.torque = T::zero();
+    pub fn set_dominance(&mut self, dominance: i8) {
+        self.dominance = dominance;
+    }
 
-            let mut ce = self.contact_list_ptr;
-            while !ce.is_null() {
-                let ce0 = ce;
-                ce = (*ce).next_ptr;
-                (*self.world_ptr)
-                    .contact_manager
-                    .destroy((*ce0).contact_ptr);
-            }
-            self.contact_list_ptr = std::ptr::null_mut();
+    pub fn set_debug_draw(&mut self, flag: bool) {
+        self.flags.set(BodyFlags::DEBUG_DRAW, flag);
+    }
 
-            let broad_phase = &mut (*self.world_ptr).contact_manager.broad_phase;
-            for (_, f) in &mut self.fixture_list {
-                for proxy in &f.proxies {
-                    broad_phase.touch_proxy(proxy.proxy_id);
-                }
-            }
-        }
+    pub fn is_debug_draw(&self) -> bool {
+        self.flags.contains(BodyFlags::DEBUG_DRAW)
     }
 
     pub fn set_bullet(&mut self, flag: bool) {
@@ -487,38 +492,6 @@ impl<T: Real, D> Body<T, D> {
         self.flags.contains(BodyFlags::ACTIVE)
     }
 
-    pub fn set_active(&mut self, flag: bool) {
-        unsafe {
-            if flag == self.is_active() {
-                return;
-            }
-
-            if flag {
-                self.flags.insert(BodyFlags::ACTIVE);
-                let broad_phase = &mut (*self.world_ptr).contact_manager.broad_phase;
-                for (_, f) in &mut self.fixture_list {
-                    f.create_proxies(broad_phase, self.xf);
-                }
-            } else {
-                self.flags.remove(BodyFlags::ACTIVE);
-                let broad_phase = &mut (*self.world_ptr).contact_manager.broad_phase;
-                for (_, f) in &mut self.fixture_list {
-                    f.destroy_proxies(broad_phase);
-                }
-
-                let mut ce = self.contact_list_ptr;
-                while !ce.is_null() {
-                    let ce0 = ce;
-                    ce = (*ce).next_ptr;
-                    (*self.world_ptr)
-                        .contact_manager
-                        .destroy((*ce0).contact_ptr);
-                }
-                self.contact_list_ptr = std::ptr::null_mut();
-            }
-        }
-    }
-
     pub fn set_fixed_rotation(&mut self, flag: bool) {
         self.flags.set(BodyFlags::FIXED_ROTATION, flag);
     }
@@ -566,7 +539,37 @@ impl<T: Real, D> Body<T, D> {
             .map(|item| item.1.as_mut())
     }
 
-    fn reset_mass(&mut self) {
+    pub(crate) fn set_mass_data(&mut self, mass_data: &MassData<T>) {
+        if self.type_ != BodyType::Dynamic {
+            return;
+        }
+
+        self.inv_mass = T::zero();
+        self.i = T::zero();
+        self.inv_i = T::zero();
+
+        self.mass = mass_data.mass;
+        if self.mass <= T::zero() {
+            self.mass = T::one();
+        }
+        self.inv_mass = T::one() / self.mass;
+
+        if mass_data.i > T::zero() && !self.flags.contains(BodyFlags::FIXED_ROTATION) {
+            self.i = mass_data.i - self.mass * mass_data.center.dot(mass_data.center);
+            assert!(self.i > T::zero());
+            self.inv_i = T::one() / self.i;
+        }
+
+        let old_center = self.sweep.c;
+        self.sweep.local_center = mass_data.center;
+        self.sweep.c0 = self.xf.multiply(self.sweep.local_center);
+        self.sweep.c = self.sweep.c0;
+
+        self.linear_velocity_ += self.angular_velocity_.cross(self.sweep.c - old_center);
+    }
+
+    /// 根据夹具的密度重新计算质量数据，会覆盖`set_mass_data`设置的值
+    pub fn reset_mass_data(&mut self) {
         self.mass = T::zero();
         self.inv_mass = T::zero();
         self.i = T::zero();
@@ -619,8 +622,28 @@ impl<T: Real, D> Body<T, D> {
         self.linear_velocity_ += self.angular_velocity_.cross(self.sweep.c - old_center);
     }
 
-    pub(crate) fn should_collide(&self, other: &Body<T, D>) -> bool {
-        self.type_ == BodyType::Dynamic || other.type_ == BodyType::Dynamic
+    pub(crate) fn should_collide(
+        &self,
+        other: &Body<T, D>,
+        joints: &Slab<Box<Joint<T>>>,
+    ) -> bool {
+        if self.type_ != BodyType::Dynamic && other.type_ != BodyType::Dynamic {
+            return false;
+        }
+
+        // 关节连接的刚体默认不碰撞
+        for id in &self.joint_list {
+            let base = joints[id.0].base();
+            if !base.collide_connected && (base.body_a == other.id || base.body_b == other.id) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// 连接到这个刚体的关节
+    pub fn joint_list(&self) -> &[JointId] {
+        &self.joint_list
     }
 
     pub fn data(&self) -> Option<&D> {
@@ -635,77 +658,39 @@ impl<T: Real, D> Body<T, D> {
         self.data = Some(data);
     }
 
-    pub fn create_fixture(&mut self, def: FixtureDef<T, D>) -> FixtureId {
-        unsafe {
-            let child_count = def.shape.child_count();
-            let fixture_id = FixtureId(self.fixture_inc_id);
-            self.fixture_inc_id += 1;
-            let fixture = Box::new(Fixture {
-                density: def.density,
-                body_ptr: self as *mut Body<T, D>,
-                shape: def.shape,
-                friction: def.friction,
-                restitution: def.restitution,
-                proxies: Vec::with_capacity(child_count),
-                filter: def.filter,
-                is_sensor: def.is_sensor,
-                data: def.data,
-            });
-            self.fixture_list.push((fixture_id, fixture));
-
-            let fixture = &mut self.fixture_list.last_mut().unwrap().1;
-            if self.flags.contains(BodyFlags::ACTIVE) {
-                fixture.create_proxies(&mut (*self.world_ptr).contact_manager.broad_phase, self.xf);
-            }
-
-            if fixture.density > T::zero() {
-                self.reset_mass();
-            }
+    pub(crate) fn create_fixture(
+        &mut self,
+        broad_phase: &mut BroadPhase<T, FixtureKey>,
+        def: FixtureDef<T, D>,
+    ) -> FixtureId {
+        let child_count = def.shape.child_count();
+        let fixture_id = FixtureId(self.fixture_inc_id);
+        self.fixture_inc_id += 1;
+        let fixture = Box::new(Fixture {
+            id: fixture_id,
+            density: def.density,
+            body: self.id,
+            shape: def.shape,
+            friction: def.friction,
+            restitution: def.restitution,
+            proxies: Vec::with_capacity(child_count),
+            filter: def.filter,
+            is_sensor: def.is_sensor,
+            one_way_normal: def.one_way_normal.map(|n| n.normalize()),
+            adhesion: def.adhesion,
+            data: def.data,
+        });
+        self.fixture_list.push((fixture_id, fixture));
 
-            (*self.world_ptr).flags.insert(WorldFlags::NEW_FIXTURE);
-            fixture_id
+        let index = self.fixture_list.len() - 1;
+        let fixture = &mut self.fixture_list[index].1;
+        if self.flags.contains(BodyFlags::ACTIVE) {
+            fixture.create_proxies(broad_phase, self.xf, index);
         }
-    }
 
-    pub fn create_fixture_with_shape(&mut self, shape: Box<dyn Shape<T>>, density: T) -> FixtureId {
-        self.create_fixture(FixtureDef::new(shape, density))
-    }
-
-    pub fn destroy_fixture(&mut self, id: FixtureId) {
-        if let Some(idx) = self
-            .fixture_list
-            .iter()
-            .enumerate()
-            .find(|(_, item)| item.0 == id)
-            .map(|(idx, _)| idx)
-        {
-            unsafe {
-                assert!(!(*self.world_ptr).flags.contains(WorldFlags::LOCKED));
-
-                let mut edge = self.contact_list_ptr;
-                while !edge.is_null() {
-                    let c = (*edge).contact_ptr;
-                    edge = (*edge).next_ptr;
-
-                    let fixture_a = (*c).fixture_a_ptr;
-                    let fixture_b = (*c).fixture_b_ptr;
-
-                    if self.fixture_list[idx].1.as_mut() as *mut Fixture<T, D> == fixture_a
-                        || self.fixture_list[idx].1.as_mut() as *mut Fixture<T, D> == fixture_b
-                    {
-                        (*self.world_ptr).contact_manager.destroy(c);
-                    }
-                }
-
-                if self.flags.contains(BodyFlags::ACTIVE) {
-                    self.fixture_list[idx]
-                        .1
-                        .destroy_proxies(&mut (*self.world_ptr).contact_manager.broad_phase);
-                }
-
-                self.fixture_list.remove(idx);
-                self.reset_mass();
-            }
+        if fixture.density > T::zero() {
+            self.reset_mass_data();
         }
+        fixture_id
     }
 }
@@ -0,0 +1,263 @@
+//! 运动学角色控制器
+//!
+//! 角色不是刚体，每次移动时用形状扫描世界，碰到表面后沿表面滑动。
+//! 胶囊形状可以用`ShapeCompound`组合两个圆和一个矩形得到。
+
+use crate::{settings, QueryFilter, Shape, World};
+use xmath::{DotTrait, Real, Rotation, Transform, Vector2};
+
+bitflags! {
+    /// 最近一次移动中角色碰到的表面
+    pub struct CharacterFlags: u32 {
+        /// 站在坡度不超过`max_slope`的地面上
+        const GROUNDED = 0x0001;
+        const CEILING = 0x0002;
+        /// 碰到墙壁或者太陡的坡
+        const WALL = 0x0004;
+    }
+}
+
+pub struct CharacterController<T> {
+    /// 角色的上方，重力的反方向
+    pub up: Vector2<T>,
+
+    /// 能站立和走上去的最大坡度（弧度）
+    pub max_slope: T,
+
+    /// 能直接走上去的台阶高度
+    pub step_height: T,
+
+    /// 下坡和走下台阶时贴住地面的最大距离
+    pub snap_distance: T,
+
+    /// 一次移动中最多沿表面滑动的次数
+    pub max_iterations: usize,
+    pub filter: QueryFilter,
+
+    shape: Box<dyn Shape<T>>,
+    position: Vector2<T>,
+    flags: CharacterFlags,
+    ground_normal: Option<Vector2<T>>,
+}
+
+impl<T: Real> CharacterController<T> {
+    pub fn new(shape: Box<dyn Shape<T>>, position: Vector2<T>) -> CharacterController<T> {
+        CharacterController {
+            up: Vector2::new(T::zero(), T::one()),
+            max_slope: T::pi() / T::i32(4),
+            step_height: T::two() * T::en1(),
+            snap_distance: T::two() * T::en1(),
+            max_iterations: 4,
+            filter: QueryFilter {
+                ignore_sensors: true,
+                ..QueryFilter::default()
+            },
+            shape,
+            position,
+            flags: CharacterFlags::empty(),
+            ground_normal: None,
+        }
+    }
+
+    pub fn shape(&self) -> &dyn Shape<T> {
+        self.shape.as_ref()
+    }
+
+    pub fn position(&self) -> Vector2<T> {
+        self.position
+    }
+
+    /// 直接移动到`position`，不做碰撞检测，会清除所有的标志
+    pub fn set_position(&mut self, position: Vector2<T>) {
+        self.position = position;
+        self.flags = CharacterFlags::empty();
+        self.ground_normal = None;
+    }
+
+    pub fn flags(&self) -> CharacterFlags {
+        self.flags
+    }
+
+    pub fn is_grounded(&self) -> bool {
+        self.flags.contains(CharacterFlags::GROUNDED)
+    }
+
+    /// 站立的地面的法线
+    pub fn ground_normal(&self) -> Option<Vector2<T>> {
+        self.ground_normal
+    }
+
+    /// 移动`delta`，碰到表面时沿表面滑动，返回实际的位移
+    ///
+    /// 站在地面上时会走上不超过`step_height`的台阶，没有向上移动时会贴住`snap_distance`以内的地面。
+    pub fn move_by<D>(&mut self, world: &World<T, D>, delta: Vector2<T>) -> Vector2<T> {
+        let was_grounded = self.is_grounded();
+        let start = self.position;
+        let up = self.up;
+        let mut state = SlideState::default();
+
+        let mut position = self.slide(world, start, delta, &mut state);
+
+        // 被墙挡住时试着先抬高再水平移动，最后落到台阶上
+        let horizontal = delta - up * delta.dot(up);
+        if was_grounded
+            && state.flags.contains(CharacterFlags::WALL)
+            && self.step_height > T::zero()
+            && horizontal.length_squared() > T::epsilon()
+        {
+            if let Some((stepped, step_state)) = self.step_up(world, start, horizontal) {
+                let direction = horizontal.normalize();
+                if (stepped - start).dot(direction)
+                    > (position - start).dot(direction) + settings::linear_slop::<T>()
+                {
+                    position = stepped;
+                    state = step_state;
+                }
+            }
+        }
+
+        // 贴住地面，刚停在地面上时也用很短的距离检测是否着地
+        if !state.flags.contains(CharacterFlags::GROUNDED) && delta.dot(up) <= T::zero() {
+            let probe = T::two() * settings::linear_slop::<T>();
+            let distance = if was_grounded {
+                self.snap_distance.max(probe)
+            } else {
+                probe
+            };
+            if let Some((fraction, normal)) = self.cast(world, position, -up * distance) {
+                if self.classify(normal) == CharacterFlags::GROUNDED {
+                    position -= up * (distance * fraction);
+                    state.flags |= CharacterFlags::GROUNDED;
+                    state.ground_normal = Some(normal);
+                }
+            }
+        }
+
+        self.position = position;
+        self.flags = state.flags;
+        self.ground_normal = state.ground_normal;
+        position - start
+    }
+
+    fn cast<D>(
+        &self,
+        world: &World<T, D>,
+        from: Vector2<T>,
+        translation: Vector2<T>,
+    ) -> Option<(T, Vector2<T>)> {
+        let filter = self.filter;
+        world
+            .shape_cast(
+                self.shape.as_ref(),
+                Transform::new(from, Rotation::identity()),
+                translation,
+                |fixture| filter.test(fixture),
+            )
+            .map(|hit| (hit.fraction, hit.normal))
+    }
+
+    fn classify(&self, normal: Vector2<T>) -> CharacterFlags {
+        let min_cos = self.max_slope.cos();
+        let d = normal.dot(self.up);
+        if d >= min_cos {
+            CharacterFlags::GROUNDED
+        } else if d <= -min_cos {
+            CharacterFlags::CEILING
+        } else {
+            CharacterFlags::WALL
+        }
+    }
+
+    fn slide<D>(
+        &self,
+        world: &World<T, D>,
+        from: Vector2<T>,
+        delta: Vector2<T>,
+        state: &mut SlideState<T>,
+    ) -> Vector2<T> {
+        let mut position = from;
+        let mut remaining = delta;
+        let mut prev_normal: Option<Vector2<T>> = None;
+
+        for _ in 0..self.max_iterations {
+            if remaining.length_squared() < T::epsilon() * T::epsilon() {
+                break;
+            }
+
+            let (fraction, normal) = match self.cast(world, position, remaining) {
+                Some(hit) => hit,
+                None => {
+                    position += remaining;
+                    break;
+                }
+            };
+
+            position += remaining * fraction;
+            remaining *= T::one() - fraction;
+            remaining -= normal * normal.dot(remaining);
+
+            let kind = self.classify(normal);
+            state.flags |= kind;
+            if kind == CharacterFlags::GROUNDED {
+                state.ground_normal = Some(normal);
+            }
+
+            // 太陡的坡当作墙，不能沿着它走上去
+            if kind == CharacterFlags::WALL && delta.dot(self.up) <= T::zero() {
+                let rise = remaining.dot(self.up);
+                if rise > T::zero() {
+                    remaining -= self.up * rise;
+                }
+            }
+
+            // 夹在两个表面之间时停下
+            if let Some(prev_normal) = prev_normal {
+                if prev_normal.dot(remaining) < T::zero() {
+                    break;
+                }
+            }
+            prev_normal = Some(normal);
+        }
+
+        position
+    }
+
+    fn step_up<D>(
+        &self,
+        world: &World<T, D>,
+        from: Vector2<T>,
+        horizontal: Vector2<T>,
+    ) -> Option<(Vector2<T>, SlideState<T>)> {
+        let rise = self.up * self.step_height;
+        let height = match self.cast(world, from, rise) {
+            Some((fraction, _)) => self.step_height * fraction,
+            None => self.step_height,
+        };
+        let raised = from + self.up * height;
+
+        let mut state = SlideState::default();
+        let moved = self.slide(world, raised, horizontal, &mut state);
+
+        let (fraction, normal) = self.cast(world, moved, -self.up * height)?;
+        if self.classify(normal) != CharacterFlags::GROUNDED {
+            return None;
+        }
+        state.flags |= CharacterFlags::GROUNDED;
+        state.ground_normal = Some(normal);
+        Some((moved - self.up * (height * fraction), state))
+    }
+}
+
+struct SlideState<T> {
+    flags: CharacterFlags,
+    ground_normal: Option<Vector2<T>>,
+}
+
+impl<T> Default for SlideState<T> {
+    fn default() -> Self {
+        SlideState {
+            flags: CharacterFlags::empty(),
+            ground_normal: None,
+        }
+    }
+}
@@ -1,226 +1,192 @@
 use crate::dynamic::contacts::{
-    Contact, ContactEdge, ContactFilter, ContactFlags, ContactListener, DefaultContactFilter,
+    Contact, ContactEdge, ContactEvent, ContactFilter, ContactFlags, ContactKey, ContactListener,
+    ContactMut, DefaultContactFilter, DefaultMaterialMixer, MaterialMixer,
 };
-use crate::dynamic::fixture::FixtureProxy;
-use crate::{BodyType, BroadPhase};
+use crate::dynamic::fixture::FixtureKey;
+use crate::{Body, BodyId, BodyType, BroadPhase, Joint};
+use slab::Slab;
+use std::rc::Rc;
 use xmath::Real;
 
 pub(crate) struct ContactManager<T, D> {
-    pub(crate) broad_phase: BroadPhase<T, *mut FixtureProxy<T, D>>,
-    pub(crate) contact_list: *mut Contact<T, D>,
-    pub(crate) contact_count: usize,
+    pub(crate) broad_phase: BroadPhase<T, FixtureKey>,
+    pub(crate) contacts: Slab<Box<Contact<T, D>>>,
     pub(crate) contact_filter: Box<dyn ContactFilter<T, D>>,
     pub(crate) contact_listener: Option<Box<dyn ContactListener<T, D>>>,
+    pub(crate) material_mixer: Rc<dyn MaterialMixer<T, D>>,
+    pub(crate) contact_events: Option<Vec<ContactEvent<T>>>,
+
+    /// 这一步中开始接触的粘性夹具所在的刚体和连接能承受的最大力
+    pub(crate) pending_bonds: Vec<(BodyId, BodyId, T)>,
 }
 
 impl<T: Real, D> ContactManager<T, D> {
     pub fn new() -> ContactManager<T, D> {
         ContactManager {
             broad_phase: BroadPhase::new(),
-            contact_list: std::ptr::null_mut(),
-            contact_count: 0,
+            contacts: Slab::new(),
             contact_filter: Box::new(DefaultContactFilter),
             contact_listener: None,
+            material_mixer: Rc::new(DefaultMaterialMixer),
+            contact_events: None,
+            pending_bonds: Vec::new(),
+        }
+    }
+
+    /// 删除所有的接触和代理，不会调用监听器
+    pub fn clear(&mut self) {
+        self.contacts.clear();
+        self.broad_phase.clear();
+        self.pending_bonds.clear();
+        if let Some(events) = &mut self.contact_events {
+            events.clear();
         }
     }
 
-    pub fn destroy(&mut self, c: *mut Contact<T, D>) {
-        unsafe {
-            let fixture_a = (*c).fixture_a_ptr;
-            let fixture_b = (*c).fixture_b_ptr;
-            let body_a = (*fixture_a).body_ptr;
-            let body_b = (*fixture_b).body_ptr;
+    pub fn destroy(&mut self, id: ContactKey, bodies: &mut Slab<Box<Body<T, D>>>) {
+        let mut c = self.contacts.remove(id.0);
 
+        if c.is_touching() {
             if let Some(listener) = &mut self.contact_listener {
-                if (*c).is_touching() {
-                    listener.end_contact(c.as_mut().unwrap());
-                }
+                listener.end_contact(&mut ContactMut::new(&mut c, bodies));
             }
-
-            if !(*c).prev_ptr.is_null() {
-                (*(*c).prev_ptr).next_ptr = (*c).next_ptr;
+            if let Some(events) = &mut self.contact_events {
+                events.push(ContactEvent::EndContact(c.info(bodies)));
             }
+        }
 
-            if !(*c).next_ptr.is_null() {
-                (*(*c).next_ptr).prev_ptr = (*c).prev_ptr;
-            }
+        for key in &[c.fixture_a, c.fixture_b] {
+            bodies[key.body.index]
+                .contact_edges
+                .retain(|edge| edge.contact != id);
+        }
+    }
 
-            if c == self.contact_list {
-                self.contact_list = (*c).next_ptr;
+    pub fn find_new_contacts(
+        &mut self,
+        bodies: &mut Slab<Box<Body<T, D>>>,
+        joints: &Slab<Box<Joint<T>>>,
+    ) {
+        let ContactManager {
+            broad_phase,
+            contacts,
+            contact_filter,
+            material_mixer,
+            ..
+        } = self;
+        broad_phase.update_pairs(|key_a, key_b| {
+            let (key_a, key_b) = (*key_a, *key_b);
+            if key_a.body == key_b.body {
+                return;
             }
 
-            if !(*c).node_a.prev_ptr.is_null() {
-                (*(*c).node_a.prev_ptr).next_ptr = (*c).node_a.next_ptr;
-            }
+            let body_a = &bodies[key_a.body.index];
+            let body_b = &bodies[key_b.body.index];
+            for edge in &body_b.contact_edges {
+                if edge.other == key_a.body {
+                    let contact = &contacts[edge.contact.0];
+                    let (fa, fb) = (contact.fixture_a, contact.fixture_b);
 
-            if !(*c).node_a.next_ptr.is_null() {
-                (*(*c).node_a.next_ptr).prev_ptr = (*c).node_a.prev_ptr;
+                    if (fa == key_a && fb == key_b) || (fa == key_b && fb == key_a) {
+                        return;
+                    }
+                }
             }
 
-            if &mut (*c).node_a as *mut ContactEdge<T, D> == (*body_a).contact_list_ptr {
-                (*body_a).contact_list_ptr = (*c).node_a.next_ptr;
+            if !body_b.should_collide(body_a, joints) {
+                return;
             }
 
-            if !(*c).node_b.prev_ptr.is_null() {
-                (*(*c).node_b.prev_ptr).next_ptr = (*c).node_b.next_ptr;
+            if !contact_filter.should_collide(key_a.get(bodies), key_b.get(bodies)) {
+                return;
             }
 
-            if !(*c).node_b.next_ptr.is_null() {
-                (*(*c).node_b.next_ptr).prev_ptr = (*c).node_b.prev_ptr;
-            }
+            let c = match Contact::new(material_mixer, bodies, key_a, key_b) {
+                Some(c) => c,
+                None => return,
+            };
+            let (key_a, key_b) = (c.fixture_a, c.fixture_b);
+            let sensor = key_a.get(bodies).is_sensor() || key_b.get(bodies).is_sensor();
+
+            let id = ContactKey(contacts.insert(Box::new(c)));
+            contacts[id.0].id = id;
+            let (body_a, body_b) = bodies
+                .get2_mut(key_a.body.index, key_b.body.index)
+                .unwrap();
+            body_a.contact_edges.push(ContactEdge {
+                other: body_b.id,
+                contact: id,
+            });
+            body_b.contact_edges.push(ContactEdge {
+                other: body_a.id,
+                contact: id,
+            });
 
-            if &mut (*c).node_b as *mut ContactEdge<T, D> == (*body_b).contact_list_ptr {
-                (*body_b).contact_list_ptr = (*c).node_b.next_ptr;
+            if !sensor {
+                body_a.set_awake(true);
+                body_b.set_awake(true);
             }
-
-            Contact::destroy(c);
-            self.contact_count -= 1;
-        }
+        });
     }
 
-    pub fn find_new_contacts(&mut self) {
-        unsafe {
-            let broad_phase = &mut self.broad_phase as *mut BroadPhase<T, *mut FixtureProxy<T, D>>;
-            (*broad_phase).update_pairs(|proxy_a, proxy_b| {
-                let fixture_a = (*(*proxy_a)).fixture_ptr;
-                let fixture_b = (*(*proxy_b)).fixture_ptr;
-                let index_a = (*(*proxy_a)).child_index;
-                let index_b = (*(*proxy_b)).child_index;
-                let body_a = (*fixture_a).body_ptr;
-                let body_b = (*fixture_b).body_ptr;
-
-                if body_a == body_b {
-                    return;
-                }
-
-                let mut edge = (*body_b).contact_list_ptr;
-                while !edge.is_null() {
-                    if (*edge).other_ptr == body_a {
-                        let fa = (*(*edge).contact_ptr).fixture_a_ptr;
-                        let fb = (*(*edge).contact_ptr).fixture_b_ptr;
-                        let ia = (*(*edge).contact_ptr).child_index_a();
-                        let ib = (*(*edge).contact_ptr).child_index_b();
-
-                        if fa == fixture_a && fb == fixture_b && ia == index_a && ib == index_b {
-                            return;
-                        }
-
-                        if fa == fixture_b && fb == fixture_a && ia == index_b && ib == index_a {
-                            return;
-                        }
-                    }
-
-                    edge = (*edge).next_ptr;
-                }
-
-                if !(*body_b).should_collide(body_a.as_ref().unwrap()) {
-                    return;
+    pub fn collide(&mut self, bodies: &mut Slab<Box<Body<T, D>>>, joints: &Slab<Box<Joint<T>>>) {
+        // 按下标遍历，循环中会销毁当前的接触
+        for index in 0..self.contacts.capacity() {
+            let c = match self.contacts.get_mut(index) {
+                Some(c) => c,
+                None => continue,
+            };
+            let id = ContactKey(index);
+            let (key_a, key_b) = (c.fixture_a, c.fixture_b);
+            let body_a = &bodies[key_a.body.index];
+            let body_b = &bodies[key_b.body.index];
+
+            if c.flags.contains(ContactFlags::FILTER) {
+                if !body_b.should_collide(body_a, joints) {
+                    self.destroy(id, bodies);
+                    continue;
                 }
 
                 if !self
                     .contact_filter
-                    .should_collide(fixture_a.as_ref().unwrap(), fixture_b.as_ref().unwrap())
+                    .should_collide(key_a.get(bodies), key_b.get(bodies))
                 {
-                    return;
-                }
-
-                let c = Contact::new(fixture_a, index_a, fixture_b, index_b);
-                let fixture_a = (*c).fixture_a_ptr;
-                let fixture_b = (*c).fixture_b_ptr;
-                let body_a = (*fixture_a).body_ptr;
-                let body_b = (*fixture_b).body_ptr;
-
-                (*c).prev_ptr = std::ptr::null_mut();
-                (*c).next_ptr = self.contact_list;
-                if !self.contact_list.is_null() {
-                    (*self.contact_list).prev_ptr = c;
+                    self.destroy(id, bodies);
+                    continue;
                 }
-                self.contact_list = c;
 
-                (*c).node_a.contact_ptr = c;
-                (*c).node_a.other_ptr = body_b;
+                c.flags.remove(ContactFlags::FILTER);
+            }
 
-                (*c).node_a.prev_ptr = std::ptr::null_mut();
-                (*c).node_a.next_ptr = (*body_a).contact_list_ptr;
-                if !(*body_a).contact_list_ptr.is_null() {
-                    (*(*body_a).contact_list_ptr).prev_ptr = &mut (*c).node_a;
-                }
-                (*body_a).contact_list_ptr = &mut (*c).node_a;
+            let active_a = body_a.is_awake() && body_a.body_type() != BodyType::Static;
+            let active_b = body_b.is_awake() && body_b.body_type() != BodyType::Static;
 
-                (*c).node_b.contact_ptr = c;
-                (*c).node_b.other_ptr = body_a;
+            if !active_a && !active_b {
+                continue;
+            }
 
-                (*c).node_b.prev_ptr = std::ptr::null_mut();
-                (*c).node_b.next_ptr = (*body_b).contact_list_ptr;
-                if !(*body_b).contact_list_ptr.is_null() {
-                    (*(*body_b).contact_list_ptr).prev_ptr = &mut (*c).node_b;
-                }
-                (*body_b).contact_list_ptr = &mut (*c).node_b;
+            let proxy_id_a = key_a.get(bodies).proxies[key_a.child_index].proxy_id;
+            let proxy_id_b = key_b.get(bodies).proxies[key_b.child_index].proxy_id;
+            let overlap = self.broad_phase.test_overlap(proxy_id_a, proxy_id_b);
 
-                if !(*fixture_a).is_sensor() && !(*fixture_b).is_sensor() {
-                    (*body_a).set_awake(true);
-                    (*body_b).set_awake(true);
-                }
+            if !overlap {
+                self.destroy(id, bodies);
+                continue;
+            }
 
-                self.contact_count += 1;
-            });
+            self.update(id, bodies);
         }
     }
 
-    pub fn collide(&mut self) {
-        unsafe {
-            let mut c = self.contact_list;
-            while !c.is_null() {
-                let fixture_a = (*c).fixture_a_ptr;
-                let fixture_b = (*c).fixture_b_ptr;
-                let index_a = (*c).child_index_a();
-                let index_b = (*c).child_index_b();
-                let body_a = (*fixture_a).body_ptr;
-                let body_b = (*fixture_b).body_ptr;
-
-                if (*c).flags.contains(ContactFlags::FILTER) {
-                    if !(*body_b).should_collide(body_a.as_ref().unwrap()) {
-                        let cnuke = c;
-                        c = (*cnuke).next_ptr;
-                        self.destroy(cnuke);
-                        continue;
-                    }
-
-                    if !self
-                        .contact_filter
-                        .should_collide(fixture_a.as_ref().unwrap(), fixture_b.as_ref().unwrap())
-                    {
-                        let cnuke = c;
-                        c = (*cnuke).next_ptr;
-                        self.destroy(cnuke);
-                        continue;
-                    }
-
-                    (*c).flags.remove(ContactFlags::FILTER);
-                }
-
-                let active_a = (*body_a).is_awake() && (*body_a).body_type() != BodyType::Static;
-                let active_b = (*body_b).is_awake() && (*body_b).body_type() != BodyType::Static;
-
-                if !active_a && !active_b {
-                    c = (*c).next_ptr;
-                    continue;
-                }
-
-                let proxy_id_a = (*fixture_a).proxies[index_a].proxy_id;
-                let proxy_id_b = (*fixture_b).proxies[index_b].proxy_id;
-                let overlap = self.broad_phase.test_overlap(proxy_id_a, proxy_id_b);
-
-                if !overlap {
-                    let cnuke = c;
-                    c = (*cnuke).next_ptr;
-                    self.destroy(cnuke);
-                    continue;
-                }
-
-                (*c).update(&self.contact_listener);
-                c = (*c).next_ptr;
-            }
-        }
+    /// 更新接触的流形，通知监听器并记录事件
+    pub fn update(&mut self, id: ContactKey, bodies: &mut Slab<Box<Body<T, D>>>) {
+        let ContactManager {
+            contacts,
+            contact_listener,
+            contact_events,
+            pending_bonds,
+            ..
+        } = self;
+        contacts[id.0].update(bodies, contact_listener, contact_events, pending_bonds);
     }
 }
@@ -1,9 +1,12 @@
+use crate::dynamic::fixture::FixtureKey;
 use crate::{
-    collision, settings, test_overlap, Body, Fixture, Manifold, Shape, ShapeChain, ShapeCircle,
-    ShapeEdge, ShapePolygon, ShapeType, WorldManifold,
+    collision, settings, Body, BodyId, CompoundChild, Fixture, FixtureId, Manifold, Shape,
+    ShapeChain, ShapeCircle, ShapeCompound, ShapeEdge, ShapePolygon, ShapeType, WorldManifold,
 };
-use std::alloc::Layout;
-use xmath::{Real, Transform};
+use slab::Slab;
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+use xmath::{DotTrait, Multiply, Real, Transform};
 
 #[inline]
 fn mix_friction<T: Real>(friction1: T, friction2: T) -> T {
@@ -15,11 +18,15 @@ fn mix_restitution<T: Real>(restitution1: T, restitution2: T) -> T {
     restitution1.max(restitution2)
 }
 
-pub struct ContactEdge<T, D> {
-    pub other_ptr: *mut Body<T, D>,
-    pub contact_ptr: *mut Contact<T, D>,
-    pub prev_ptr: *mut ContactEdge<T, D>,
-    pub next_ptr: *mut ContactEdge<T, D>,
+/// 接触在`ContactManager`中的索引，接触删除后索引会被新的接触复用
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ContactKey(pub(crate) usize);
+
+/// 刚体上的接触，`other`是接触的另一个刚体
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ContactEdge {
+    pub(crate) other: BodyId,
+    pub(crate) contact: ContactKey,
 }
 
 bitflags! {
@@ -30,9 +37,11 @@ bitflags! {
         const FILTER = 0x0008;
         const BULLET_HIT = 0x0010;
         const TOI = 0x0020;
+        const ONE_WAY_PASS = 0x0040;
     }
 }
 
+#[derive(Debug, Copy, Clone)]
 pub struct ContactImpulse<T> {
     pub normal_impulses: [T; settings::MAX_MANIFOLD_POINTS],
     pub tangent_impulses: [T; settings::MAX_MANIFOLD_POINTS],
@@ -40,10 +49,39 @@ pub struct ContactImpulse<T> {
 }
 
 pub trait ContactListener<T, D> {
-    fn begin_contact(&self, _contact: &mut Contact<T, D>) {}
-    fn end_contact(&self, _contact: &mut Contact<T, D>) {}
-    fn pre_solve(&self, _contact: &mut Contact<T, D>, _old_manifold: &Manifold<T>) {}
-    fn post_solve(&self, _contact: &mut Contact<T, D>, _impulse: &ContactImpulse<T>) {}
+    fn begin_contact(&self, _contact: &mut ContactMut<T, D>) {}
+    fn end_contact(&self, _contact: &mut ContactMut<T, D>) {}
+    fn pre_solve(&self, _contact: &mut ContactMut<T, D>, _old_manifold: &Manifold<T>) {}
+    fn post_solve(&self, _contact: &mut ContactMut<T, D>, _impulse: &ContactImpulse<T>) {}
+}
+
+/// 事件发生时接触的两个夹具和流形
+#[derive(Debug, Copy, Clone)]
+pub struct ContactInfo<T> {
+    pub body_a: BodyId,
+    pub fixture_a: FixtureId,
+    pub child_index_a: usize,
+    pub body_b: BodyId,
+    pub fixture_b: FixtureId,
+    pub child_index_b: usize,
+    pub manifold: Manifold<T>,
+}
+
+/// 步进时记录的接触事件，见`World::drain_contact_events`
+///
+/// 事件只能读取，要在求解前禁用接触仍然需要`ContactListener`。
+#[derive(Debug, Copy, Clone)]
+pub enum ContactEvent<T> {
+    BeginContact(ContactInfo<T>),
+    EndContact(ContactInfo<T>),
+    PreSolve {
+        contact: ContactInfo<T>,
+        old_manifold: Manifold<T>,
+    },
+    PostSolve {
+        contact: ContactInfo<T>,
+        impulse: ContactImpulse<T>,
+    },
 }
 
 pub trait ContactFilter<T, D> {
@@ -66,22 +104,35 @@ impl<T: Real, D> ContactFilter<T, D> for DefaultContactFilter {
     }
 }
 
+/// 计算接触的摩擦系数和恢复系数，可以根据夹具的数据查找材质表
+///
+/// 默认摩擦系数取几何平均值，恢复系数取较大值。
+pub trait MaterialMixer<T: Real, D> {
+    fn mix_friction(&self, fixture_a: &Fixture<T, D>, fixture_b: &Fixture<T, D>) -> T {
+        mix_friction(fixture_a.friction(), fixture_b.friction())
+    }
+
+    fn mix_restitution(&self, fixture_a: &Fixture<T, D>, fixture_b: &Fixture<T, D>) -> T {
+        mix_restitution(fixture_a.restitution(), fixture_b.restitution())
+    }
+}
+
+pub struct DefaultMaterialMixer;
+
+impl<T: Real, D> MaterialMixer<T, D> for DefaultMaterialMixer {}
+
 pub struct Contact<T, D> {
+    pub(crate) id: ContactKey,
     pub(crate) flags: ContactFlags,
-    pub(crate) prev_ptr: *mut Contact<T, D>,
-    pub(crate) next_ptr: *mut Contact<T, D>,
-    pub(crate) node_a: ContactEdge<T, D>,
-    pub(crate) node_b: ContactEdge<T, D>,
-    pub(crate) fixture_a_ptr: *mut Fixture<T, D>,
-    pub(crate) fixture_b_ptr: *mut Fixture<T, D>,
-    pub(crate) index_a: usize,
-    pub(crate) index_b: usize,
+    pub(crate) fixture_a: FixtureKey,
+    pub(crate) fixture_b: FixtureKey,
     pub(crate) manifold: Manifold<T>,
     pub(crate) toi_count: usize,
     pub(crate) toi: T,
     pub(crate) friction: T,
     pub(crate) restitution: T,
     pub(crate) tangent_speed: T,
+    pub(crate) mixer: Rc<dyn MaterialMixer<T, D>>,
     pub(crate) evaluate_fn: fn(
         manifold: &mut Manifold<T>,
         shape_a: &dyn Shape<T>,
@@ -93,46 +144,136 @@ pub struct Contact<T, D> {
     ),
 }
 
+/// 参与碰撞的凸形状，链形状的子形状是边，复合形状的子形状是圆或多边形
+enum ChildShape<'a, T> {
+    Circle(&'a ShapeCircle<T>),
+    Edge(&'a ShapeEdge<T>),
+    Polygon(&'a ShapePolygon<T>),
+}
+
+impl<'a, T: Real> ChildShape<'a, T> {
+    unsafe fn new(
+        shape: &'a dyn Shape<T>,
+        index: usize,
+        chain_edge: &'a mut Option<ShapeEdge<T>>,
+    ) -> Self {
+        let ptr = shape as *const dyn Shape<T>;
+        match shape.shape_type() {
+            ShapeType::Circle => ChildShape::Circle(&*(ptr as *const ShapeCircle<T>)),
+            ShapeType::Edge => ChildShape::Edge(&*(ptr as *const ShapeEdge<T>)),
+            ShapeType::Polygon => ChildShape::Polygon(&*(ptr as *const ShapePolygon<T>)),
+            ShapeType::Chain => {
+                let chain = &*(ptr as *const ShapeChain<T>);
+                ChildShape::Edge(chain_edge.get_or_insert(chain.get_child_edge(index)))
+            }
+            ShapeType::Compound => {
+                let compound = &*(ptr as *const ShapeCompound<T>);
+                match &compound.children[index] {
+                    CompoundChild::Circle(circle) => ChildShape::Circle(circle),
+                    CompoundChild::Polygon(polygon) => ChildShape::Polygon(polygon),
+                }
+            }
+        }
+    }
+}
+
+/// 子形状的类型，链形状的子形状当作边
+fn child_type<T: Real>(shape: &dyn Shape<T>, index: usize) -> ShapeType {
+    match shape.shape_type() {
+        ShapeType::Chain => ShapeType::Edge,
+        ShapeType::Compound => {
+            let compound = unsafe { &*(shape as *const dyn Shape<T> as *const ShapeCompound<T>) };
+            match &compound.children[index] {
+                CompoundChild::Circle(_) => ShapeType::Circle,
+                CompoundChild::Polygon(_) => ShapeType::Polygon,
+            }
+        }
+        shape_type => shape_type,
+    }
+}
+
+/// 碰撞函数要求形状A是边或多边形，形状B是圆或多边形
+fn is_ordered(type_a: ShapeType, type_b: ShapeType) -> bool {
+    matches!(
+        (type_a, type_b),
+        (ShapeType::Circle, ShapeType::Circle)
+            | (ShapeType::Polygon, ShapeType::Circle)
+            | (ShapeType::Polygon, ShapeType::Polygon)
+            | (ShapeType::Edge, ShapeType::Circle)
+            | (ShapeType::Edge, ShapeType::Polygon)
+    )
+}
+
+/// 包含复合形状的接触，每次求解时按子形状的类型选择碰撞函数
+fn evaluate_children<T: Real>(
+    manifold: &mut Manifold<T>,
+    shape_a: &dyn Shape<T>,
+    shape_b: &dyn Shape<T>,
+    xf_a: &Transform<T>,
+    xf_b: &Transform<T>,
+    index_a: usize,
+    index_b: usize,
+) {
+    let mut chain_edge = None;
+    let mut unused = None;
+    unsafe {
+        match (
+            ChildShape::new(shape_a, index_a, &mut chain_edge),
+            ChildShape::new(shape_b, index_b, &mut unused),
+        ) {
+            (ChildShape::Circle(a), ChildShape::Circle(b)) => {
+                collision::collide_circles(manifold, a, xf_a, b, xf_b)
+            }
+            (ChildShape::Polygon(a), ChildShape::Circle(b)) => {
+                collision::collide_polygon_and_circle(manifold, a, xf_a, b, xf_b)
+            }
+            (ChildShape::Polygon(a), ChildShape::Polygon(b)) => {
+                collision::collide_polygons(manifold, a, xf_a, b, xf_b)
+            }
+            (ChildShape::Edge(a), ChildShape::Circle(b)) => {
+                collision::collide_edge_and_circle(manifold, a, xf_a, b, xf_b)
+            }
+            (ChildShape::Edge(a), ChildShape::Polygon(b)) => {
+                collision::collide_edge_and_polygon(manifold, a, xf_a, b, xf_b)
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
 impl<T: Real, D> Contact<T, D> {
     pub(crate) fn new(
-        fixture_a: *mut Fixture<T, D>,
-        index_a: usize,
-        fixture_b: *mut Fixture<T, D>,
-        index_b: usize,
-    ) -> *mut Contact<T, D> {
-        let contact = Contact {
+        mixer: &Rc<dyn MaterialMixer<T, D>>,
+        bodies: &Slab<Box<Body<T, D>>>,
+        mut key_a: FixtureKey,
+        mut key_b: FixtureKey,
+    ) -> Option<Contact<T, D>> {
+        let type_a = child_type(key_a.get(bodies).shape(), key_a.child_index);
+        let type_b = child_type(key_b.get(bodies).shape(), key_b.child_index);
+        if !is_ordered(type_a, type_b) {
+            if !is_ordered(type_b, type_a) {
+                return None;
+            }
+            std::mem::swap(&mut key_a, &mut key_b);
+        }
+        let fixture_a = key_a.get(bodies);
+        let fixture_b = key_b.get(bodies);
+
+        Some(Contact {
+            id: ContactKey(0),
             flags: ContactFlags::ENABLED,
-            prev_ptr: std::ptr::null_mut(),
-            next_ptr: std::ptr::null_mut(),
-            node_a: ContactEdge {
-                other_ptr: std::ptr::null_mut(),
-                contact_ptr: std::ptr::null_mut(),
-                prev_ptr: std::ptr::null_mut(),
-                next_ptr: std::ptr::null_mut(),
-            },
-            node_b: ContactEdge {
-                other_ptr: std::ptr::null_mut(),
-                contact_ptr: std::ptr::null_mut(),
-                prev_ptr: std::ptr::null_mut(),
-                next_ptr: std::ptr::null_mut(),
-            },
-            fixture_a_ptr: fixture_a,
-            fixture_b_ptr: fixture_b,
-            index_a,
-            index_b,
+            fixture_a: key_a,
+            fixture_b: key_b,
             manifold: unsafe { std::mem::zeroed() },
             toi_count: 0,
             toi: T::zero(),
-            friction: unsafe { mix_friction((*fixture_a).friction(), (*fixture_b).friction()) },
-            restitution: unsafe {
-                mix_restitution((*fixture_a).restitution(), (*fixture_b).restitution())
-            },
+            friction: mixer.mix_friction(fixture_a, fixture_b),
+            restitution: mixer.mix_restitution(fixture_a, fixture_b),
             tangent_speed: T::zero(),
+            mixer: mixer.clone(),
             evaluate_fn: unsafe {
-                match (
-                    (*fixture_a).shape().shape_type(),
-                    (*fixture_b).shape().shape_type(),
-                ) {
+                match (fixture_a.shape().shape_type(), fixture_b.shape().shape_type()) {
+                    (ShapeType::Compound, _) | (_, ShapeType::Compound) => evaluate_children,
                     (ShapeType::Circle, ShapeType::Circle) => {
                         |manifold, shape_a, shape_b, xf_a, xf_b, _index_a, _index_b| {
                             collision::collide_circles(
@@ -240,27 +381,14 @@ impl<T: Real, D> Contact<T, D> {
                             )
                         }
                     }
-                    _ => return std::ptr::null_mut(),
+                    _ => return None,
                 }
             },
-        };
-
-        unsafe {
-            let data = std::alloc::alloc(Layout::new::<Contact<T, D>>());
-            std::ptr::copy_nonoverlapping(
-                &contact as *const Contact<T, D>,
-                data as *mut Contact<T, D>,
-                1,
-            );
-            data as *mut Contact<T, D>
-        }
+        })
     }
 
-    pub(crate) fn destroy(contact: *mut Contact<T, D>) {
-        unsafe {
-            std::ptr::drop_in_place::<Contact<T, D>>(contact);
-            std::alloc::dealloc(contact as *mut u8, Layout::new::<Contact<T, D>>());
-        }
+    pub fn id(&self) -> ContactKey {
+        self.id
     }
 
     pub fn manifold(&self) -> &Manifold<T> {
@@ -271,56 +399,75 @@ impl<T: Real, D> Contact<T, D> {
         &mut self.manifold
     }
 
-    pub fn world_manifold(&self) -> WorldManifold<T> {
-        unsafe {
-            let body_a = (*self.fixture_a_ptr).body_ptr;
-            let body_b = (*self.fixture_b_ptr).body_ptr;
-            let shape_a = (*self.fixture_a_ptr).shape();
-            let shape_b = (*self.fixture_b_ptr).shape();
-            WorldManifold::new(
-                &self.manifold,
-                (*body_a).transform(),
-                shape_a.radius(),
-                (*body_b).transform(),
-                shape_b.radius(),
-            )
-        }
+    /// 接触的两个刚体
+    pub fn body_a(&self) -> BodyId {
+        self.fixture_a.body
     }
 
-    pub fn is_touching(&self) -> bool {
-        self.flags.contains(ContactFlags::TOUCHING)
+    pub fn body_b(&self) -> BodyId {
+        self.fixture_b.body
     }
 
-    pub fn set_enable(&mut self, flag: bool) {
-        self.flags.set(ContactFlags::ENABLED, flag);
+    pub(crate) fn world_manifold_with(
+        &self,
+        body_a: &Body<T, D>,
+        body_b: &Body<T, D>,
+    ) -> WorldManifold<T> {
+        let shape_a = body_a.fixture_list[self.fixture_a.fixture].1.shape();
+        let shape_b = body_b.fixture_list[self.fixture_b.fixture].1.shape();
+        WorldManifold::new(
+            &self.manifold,
+            body_a.transform(),
+            shape_a.child_radius(self.fixture_a.child_index),
+            body_b.transform(),
+            shape_b.child_radius(self.fixture_b.child_index),
+        )
     }
 
-    pub fn is_enable(&self) -> bool {
-        self.flags.contains(ContactFlags::ENABLED)
+    pub(crate) fn info(&self, bodies: &Slab<Box<Body<T, D>>>) -> ContactInfo<T> {
+        ContactInfo {
+            body_a: self.fixture_a.body,
+            fixture_a: self.fixture_a.get(bodies).id(),
+            child_index_a: self.fixture_a.child_index,
+            body_b: self.fixture_b.body,
+            fixture_b: self.fixture_b.get(bodies).id(),
+            child_index_b: self.fixture_b.child_index,
+            manifold: self.manifold,
+        }
     }
 
-    pub fn fixture_a(&self) -> &Fixture<T, D> {
-        unsafe { self.fixture_a_ptr.as_ref().unwrap() }
+    /// 世界开启了事件队列时记录事件
+    pub(crate) fn record_event<F>(
+        &self,
+        bodies: &Slab<Box<Body<T, D>>>,
+        events: &mut Option<Vec<ContactEvent<T>>>,
+        f: F,
+    ) where
+        F: FnOnce(ContactInfo<T>) -> ContactEvent<T>,
+    {
+        if let Some(events) = events {
+            events.push(f(self.info(bodies)));
+        }
     }
 
-    pub fn fixture_a_mut(&self) -> &mut Fixture<T, D> {
-        unsafe { self.fixture_a_ptr.as_mut().unwrap() }
+    pub fn is_touching(&self) -> bool {
+        self.flags.contains(ContactFlags::TOUCHING)
     }
 
-    pub fn child_index_a(&self) -> usize {
-        self.index_a
+    pub fn set_enable(&mut self, flag: bool) {
+        self.flags.set(ContactFlags::ENABLED, flag);
     }
 
-    pub fn fixture_b(&self) -> &Fixture<T, D> {
-        unsafe { self.fixture_b_ptr.as_ref().unwrap() }
+    pub fn is_enable(&self) -> bool {
+        self.flags.contains(ContactFlags::ENABLED)
     }
 
-    pub fn fixture_b_mut(&self) -> &mut Fixture<T, D> {
-        unsafe { self.fixture_b_ptr.as_mut().unwrap() }
+    pub fn child_index_a(&self) -> usize {
+        self.fixture_a.child_index
     }
 
     pub fn child_index_b(&self) -> usize {
-        self.index_b
+        self.fixture_b.child_index
     }
 
     pub fn set_friction(&mut self, friction: T) {
@@ -331,15 +478,6 @@ impl<T: Real, D> Contact<T, D> {
         self.friction
     }
 
-    pub fn reset_friction(&mut self) {
-        self.friction = unsafe {
-            mix_friction(
-                (*self.fixture_a_ptr).friction(),
-                (*self.fixture_b_ptr).friction(),
-            )
-        };
-    }
-
     pub fn set_restitution(&mut self, restitution: T) {
         self.restitution = restitution;
     }
@@ -352,15 +490,6 @@ impl<T: Real, D> Contact<T, D> {
         self.flags.insert(ContactFlags::FILTER)
     }
 
-    pub fn reset_restitution(&mut self) {
-        self.friction = unsafe {
-            mix_restitution(
-                (*self.fixture_a_ptr).restitution(),
-                (*self.fixture_b_ptr).restitution(),
-            )
-        };
-    }
-
     pub fn set_tangent_speed(&mut self, speed: T) {
         self.tangent_speed = speed;
     }
@@ -369,45 +498,59 @@ impl<T: Real, D> Contact<T, D> {
         self.tangent_speed
     }
 
-    pub(crate) fn evaluate(&mut self, xf_a: &Transform<T>, xf_b: &Transform<T>) {
-        unsafe {
-            (self.evaluate_fn)(
-                &mut self.manifold,
-                (*self.fixture_a_ptr).shape(),
-                (*self.fixture_b_ptr).shape(),
-                xf_a,
-                xf_b,
-                self.index_a,
-                self.index_b,
-            )
-        }
+    pub(crate) fn evaluate(
+        &mut self,
+        fixture_a: &Fixture<T, D>,
+        fixture_b: &Fixture<T, D>,
+        xf_a: &Transform<T>,
+        xf_b: &Transform<T>,
+    ) {
+        (self.evaluate_fn)(
+            &mut self.manifold,
+            fixture_a.shape(),
+            fixture_b.shape(),
+            xf_a,
+            xf_b,
+            self.fixture_a.child_index,
+            self.fixture_b.child_index,
+        )
     }
 
-    pub(crate) fn update(&mut self, mut listener: &Option<Box<dyn ContactListener<T, D>>>) {
-        unsafe {
-            let old_manifold = self.manifold.clone();
-
-            self.flags.insert(ContactFlags::ENABLED);
-
-            let touching;
-            let was_touching = self.flags.contains(ContactFlags::TOUCHING);
-
-            let sensor_a = (*self.fixture_a_ptr).is_sensor();
-            let sensor_b = (*self.fixture_b_ptr).is_sensor();
-            let sensor = sensor_a || sensor_b;
-
-            let body_a = (*self.fixture_a_ptr).body_ptr;
-            let body_b = (*self.fixture_b_ptr).body_ptr;
-            let xf_a = (*body_a).transform();
-            let xf_b = (*body_b).transform();
+    pub(crate) fn update(
+        &mut self,
+        bodies: &mut Slab<Box<Body<T, D>>>,
+        mut listener: &Option<Box<dyn ContactListener<T, D>>>,
+        events: &mut Option<Vec<ContactEvent<T>>>,
+        pending_bonds: &mut Vec<(BodyId, BodyId, T)>,
+    ) {
+        let old_manifold = self.manifold;
+
+        self.flags.insert(ContactFlags::ENABLED);
+
+        let touching;
+        let was_touching = self.flags.contains(ContactFlags::TOUCHING);
+
+        let sensor = {
+            let body_a = &bodies[self.fixture_a.body.index];
+            let body_b = &bodies[self.fixture_b.body.index];
+            let fixture_a = &body_a.fixture_list[self.fixture_a.fixture].1;
+            let fixture_b = &body_b.fixture_list[self.fixture_b.fixture].1;
+            let sensor = fixture_a.is_sensor() || fixture_b.is_sensor();
+            let xf_a = body_a.transform();
+            let xf_b = body_b.transform();
 
             if sensor {
-                let shape_a = (*self.fixture_a_ptr).shape();
-                let shape_b = (*self.fixture_b_ptr).shape();
-                touching = test_overlap(shape_a, self.index_a, shape_b, self.index_b, *xf_a, *xf_b);
+                touching = collision::test_child_overlap(
+                    fixture_a.shape(),
+                    self.fixture_a.child_index,
+                    fixture_b.shape(),
+                    self.fixture_b.child_index,
+                    *xf_a,
+                    *xf_b,
+                );
                 self.manifold.point_count = 0;
             } else {
-                self.evaluate(xf_a, xf_b);
+                self.evaluate(fixture_a, fixture_b, xf_a, xf_b);
                 touching = self.manifold.point_count > 0;
 
                 for i in 0..self.manifold.point_count {
@@ -426,36 +569,175 @@ impl<T: Real, D> Contact<T, D> {
                         }
                     }
                 }
-
-                if touching != was_touching {
-                    (*body_a).set_awake(true);
-                    (*body_b).set_awake(true);
-                }
             }
+            sensor
+        };
 
-            if touching {
-                self.flags.insert(ContactFlags::TOUCHING);
-            } else {
-                self.flags.remove(ContactFlags::TOUCHING);
+        if !sensor && touching != was_touching {
+            bodies[self.fixture_a.body.index].set_awake(true);
+            bodies[self.fixture_b.body.index].set_awake(true);
+        }
+
+        if touching {
+            self.flags.insert(ContactFlags::TOUCHING);
+        } else {
+            self.flags.remove(ContactFlags::TOUCHING);
+        }
+
+        let bodies = &*bodies;
+        let mut contact = ContactMut::new(self, bodies);
+
+        if !was_touching && touching {
+            if let Some(l) = &mut listener {
+                l.begin_contact(&mut contact);
             }
+            contact.record_event(bodies, events, ContactEvent::BeginContact);
+        }
 
-            if !was_touching && touching {
-                if let Some(l) = &mut listener {
-                    l.begin_contact(self);
-                }
+        if was_touching && !touching {
+            if let Some(l) = &mut listener {
+                l.end_contact(&mut contact);
             }
+            contact.record_event(bodies, events, ContactEvent::EndContact);
+        }
 
-            if was_touching && !touching {
-                if let Some(l) = &mut listener {
-                    l.end_contact(self);
-                }
+        if !touching {
+            contact.flags.remove(ContactFlags::ONE_WAY_PASS);
+        } else if !sensor {
+            if !was_touching {
+                contact.check_one_way();
+            }
+            if contact.flags.contains(ContactFlags::ONE_WAY_PASS) {
+                contact.flags.remove(ContactFlags::ENABLED);
+            } else if !was_touching {
+                contact.check_adhesion(pending_bonds);
             }
+        }
 
-            if !sensor && touching {
-                if let Some(l) = &mut listener {
-                    l.pre_solve(self, &old_manifold);
-                }
+        if !sensor && touching {
+            if let Some(l) = &mut listener {
+                l.pre_solve(&mut contact, &old_manifold);
             }
+            contact.record_event(bodies, events, |contact| ContactEvent::PreSolve {
+                contact,
+                old_manifold,
+            });
+        }
+    }
+}
+
+/// 监听器回调中的接触，可以读取接触的两个夹具
+pub struct ContactMut<'a, T, D> {
+    contact: &'a mut Contact<T, D>,
+    body_a: &'a Body<T, D>,
+    body_b: &'a Body<T, D>,
+}
+
+impl<'a, T: Real, D> ContactMut<'a, T, D> {
+    pub(crate) fn new(
+        contact: &'a mut Contact<T, D>,
+        bodies: &'a Slab<Box<Body<T, D>>>,
+    ) -> ContactMut<'a, T, D> {
+        let body_a = &bodies[contact.fixture_a.body.index];
+        let body_b = &bodies[contact.fixture_b.body.index];
+        ContactMut {
+            contact,
+            body_a,
+            body_b,
         }
     }
+
+    pub fn fixture_a(&self) -> &Fixture<T, D> {
+        &self.body_a.fixture_list[self.contact.fixture_a.fixture].1
+    }
+
+    pub fn fixture_b(&self) -> &Fixture<T, D> {
+        &self.body_b.fixture_list[self.contact.fixture_b.fixture].1
+    }
+
+    pub fn world_manifold(&self) -> WorldManifold<T> {
+        self.contact.world_manifold_with(self.body_a, self.body_b)
+    }
+
+    /// 用世界的`MaterialMixer`重新计算摩擦系数
+    pub fn reset_friction(&mut self) {
+        self.contact.friction = self
+            .contact
+            .mixer
+            .mix_friction(self.fixture_a(), self.fixture_b());
+    }
+
+    /// 用世界的`MaterialMixer`重新计算恢复系数
+    pub fn reset_restitution(&mut self) {
+        self.contact.restitution = self
+            .contact
+            .mixer
+            .mix_restitution(self.fixture_a(), self.fixture_b());
+    }
+
+    /// 刚开始接触时判断是否从单向平台的非实心一侧进入，是的话直到接触结束都禁用
+    fn check_one_way(&mut self) {
+        let normal_a = self.fixture_a().one_way_normal;
+        let normal_b = self.fixture_b().one_way_normal;
+        if normal_a.is_none() && normal_b.is_none() {
+            return;
+        }
+
+        let world_manifold = self.world_manifold();
+        let point = world_manifold.points[0];
+        let body_a = self.body_a;
+        let body_b = self.body_b;
+        let relative_velocity = body_b.linear_velocity_from_world_point(point)
+            - body_a.linear_velocity_from_world_point(point);
+
+        // 接触法线从A指向B。从侧面或下面进入时法线和平台法线夹角超过60度，
+        // 从下往上穿过时另一个刚体沿平台法线远离平台
+        let mut pass = false;
+        if let Some(n) = normal_a {
+            let up = body_a.transform().q.multiply(n);
+            pass |=
+                up.dot(world_manifold.normal) < T::half() || up.dot(relative_velocity) > T::zero();
+        }
+        if let Some(n) = normal_b {
+            let up = body_b.transform().q.multiply(n);
+            pass |= up.dot(-world_manifold.normal) < T::half()
+                || up.dot(-relative_velocity) > T::zero();
+        }
+        self.contact.flags.set(ContactFlags::ONE_WAY_PASS, pass);
+    }
+
+    /// 有粘性夹具的两个刚体撞在一起时，记下需要粘在一起的两个刚体，步进结束后再创建连接
+    ///
+    /// 连接断开后重新生成的接触通常是在分离的，不会马上又粘上。
+    fn check_adhesion(&self, pending_bonds: &mut Vec<(BodyId, BodyId, T)>) {
+        let break_force = match (self.fixture_a().adhesion, self.fixture_b().adhesion) {
+            (Some(a), Some(b)) => a.min(b),
+            (Some(force), None) | (None, Some(force)) => force,
+            (None, None) => return,
+        };
+
+        let world_manifold = self.world_manifold();
+        let point = world_manifold.points[0];
+        let relative_velocity = self.body_b.linear_velocity_from_world_point(point)
+            - self.body_a.linear_velocity_from_world_point(point);
+        if relative_velocity.dot(world_manifold.normal) >= T::zero() {
+            return;
+        }
+
+        pending_bonds.push((self.body_a.id, self.body_b.id, break_force));
+    }
+}
+
+impl<'a, T, D> Deref for ContactMut<'a, T, D> {
+    type Target = Contact<T, D>;
+
+    fn deref(&self) -> &Self::Target {
+        self.contact
+    }
+}
+
+impl<'a, T, D> DerefMut for ContactMut<'a, T, D> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.contact
+    }
 }
@@ -1,7 +1,10 @@
-use crate::dynamic::contacts::Contact;
-use crate::dynamic::time_step::{Position, TimeStep, Velocity};
+use crate::dynamic::contacts::{Contact, ContactKey};
+use crate::dynamic::stack_allocator::StackAllocator;
+use crate::dynamic::time_step::{SolverData, TimeStep};
 use crate::math::Matrix22;
-use crate::{settings, ManifoldType, WorldManifold};
+use crate::{settings, Body, BodyType, ManifoldType, WorldManifold};
+use slab::Slab;
+use std::cmp::Ordering;
 use xmath::{CrossTrait, DotTrait, Multiply, Real, Rotation, Transform, Vector2};
 
 const BLOCK_SOLVE: bool = true;
@@ -52,118 +55,135 @@ pub struct ContactPositionConstraint<T> {
     point_count: usize,
 }
 
-pub struct ContactSolverDef<'a, T, D> {
+pub struct ContactSolverDef<'a, T> {
     pub step: TimeStep<T>,
-    pub contacts: &'a [*mut Contact<T, D>],
-    pub positions: &'a mut [Position<T>],
-    pub velocities: &'a mut [Velocity<T>],
+    pub contacts: &'a [ContactKey],
+    pub allocator: &'a mut StackAllocator<T>,
 }
 
-pub struct ContactSolver<'a, T, D> {
+pub struct ContactSolver<'a, T> {
     pub step: TimeStep<T>,
-    pub positions: &'a mut [Position<T>],
-    pub velocities: &'a mut [Velocity<T>],
-    pub position_constraints: Vec<ContactPositionConstraint<T>>,
-    pub velocity_constraints: Vec<ContactVelocityConstraint<T>>,
-    pub contacts: &'a [*mut Contact<T, D>],
+    pub position_constraints: &'a mut Vec<ContactPositionConstraint<T>>,
+    pub velocity_constraints: &'a mut Vec<ContactVelocityConstraint<T>>,
+    pub contacts: &'a [ContactKey],
 }
 
-impl<'a, T: Real, D> ContactSolver<'a, T, D> {
-    pub fn new(def: ContactSolverDef<'a, T, D>) -> ContactSolver<'a, T, D> {
-        let mut position_constraints =
-            Vec::<ContactPositionConstraint<T>>::with_capacity(def.contacts.len());
+impl<'a, T: Real> ContactSolver<'a, T> {
+    pub fn new<D>(
+        def: ContactSolverDef<'a, T>,
+        contacts: &Slab<Box<Contact<T, D>>>,
+        bodies: &Slab<Box<Body<T, D>>>,
+    ) -> ContactSolver<'a, T> {
+        let allocator = def.allocator;
+        let position_constraints = &mut allocator.position_constraints;
+        position_constraints.clear();
         position_constraints.resize_with(def.contacts.len(), || unsafe { std::mem::zeroed() });
-        let mut velocity_constraints =
-            Vec::<ContactVelocityConstraint<T>>::with_capacity(def.contacts.len());
+        let velocity_constraints = &mut allocator.velocity_constraints;
+        velocity_constraints.clear();
         velocity_constraints.resize_with(def.contacts.len(), || unsafe { std::mem::zeroed() });
 
-        unsafe {
-            for i in 0..def.contacts.len() {
-                let contact = def.contacts[i].as_ref().unwrap();
-                let fixture_a = contact.fixture_a();
-                let fixture_b = contact.fixture_b();
-                let shape_a = fixture_a.shape();
-                let shape_b = fixture_b.shape();
-                let radius_a = shape_a.radius();
-                let radius_b = shape_b.radius();
-                let body_a = fixture_a.body_ptr;
-                let body_b = fixture_b.body_ptr;
-                let manifold = contact.manifold();
-                let point_count = manifold.point_count;
-                assert!(point_count > 0);
-
-                let vc = &mut velocity_constraints[i];
-                vc.friction = contact.friction();
-                vc.restitution = contact.restitution();
-                vc.tangent_speed = contact.tangent_speed();
-                vc.index_a = (*body_a).island_index;
-                vc.index_b = (*body_b).island_index;
-                vc.inv_mass_a = (*body_a).inv_mass;
-                vc.inv_mass_b = (*body_b).inv_mass;
-                vc.inv_i_a = (*body_a).inv_i;
-                vc.inv_i_b = (*body_b).inv_i;
-                vc.contact_index = i;
-                vc.point_count = point_count;
-                vc.k = Matrix22::zero();
-                vc.normal_mass = Matrix22::zero();
-
-                let pc = &mut position_constraints[i];
-                pc.index_a = (*body_a).island_index;
-                pc.index_b = (*body_b).island_index;
-                pc.inv_mass_a = (*body_a).inv_mass;
-                pc.inv_mass_b = (*body_b).inv_mass;
-                pc.local_center_a = (*body_a).sweep.local_center;
-                pc.local_center_b = (*body_b).sweep.local_center;
-                pc.inv_i_a = (*body_a).inv_i;
-                pc.inv_i_b = (*body_b).inv_i;
-                pc.local_normal = manifold.local_normal;
-                pc.local_point = manifold.local_point;
-                pc.point_count = point_count;
-                pc.radius_a = radius_a;
-                pc.radius_b = radius_b;
-                pc.type_ = manifold.type_;
-
-                for j in 0..point_count {
-                    let cp = &manifold.points[j];
-                    let vcp = &mut vc.points[j];
-
-                    if def.step.warm_starting {
-                        vcp.normal_impulse = def.step.dt_ratio * cp.normal_impulse;
-                        vcp.tangent_impulse = def.step.dt_ratio * cp.tangent_impulse;
-                    } else {
-                        vcp.normal_impulse = T::zero();
-                        vcp.tangent_impulse = T::zero();
+        for i in 0..def.contacts.len() {
+            let contact = &contacts[def.contacts[i].0];
+            let shape_a = contact.fixture_a.get(bodies).shape();
+            let shape_b = contact.fixture_b.get(bodies).shape();
+            let radius_a = shape_a.child_radius(contact.child_index_a());
+            let radius_b = shape_b.child_radius(contact.child_index_b());
+            let body_a = &bodies[contact.fixture_a.body.index];
+            let body_b = &bodies[contact.fixture_b.body.index];
+            let manifold = contact.manifold();
+            let point_count = manifold.point_count;
+            assert!(point_count > 0);
+
+            // 支配等级高的动态刚体在这个接触中当作质量无穷大
+            let (mut inv_mass_a, mut inv_i_a) = (body_a.inv_mass, body_a.inv_i);
+            let (mut inv_mass_b, mut inv_i_b) = (body_b.inv_mass, body_b.inv_i);
+            if body_a.type_ == BodyType::Dynamic && body_b.type_ == BodyType::Dynamic {
+                match body_a.dominance.cmp(&body_b.dominance) {
+                    Ordering::Greater => {
+                        inv_mass_a = T::zero();
+                        inv_i_a = T::zero();
+                    }
+                    Ordering::Less => {
+                        inv_mass_b = T::zero();
+                        inv_i_b = T::zero();
                     }
+                    Ordering::Equal => {}
+                }
+            }
 
-                    vcp.ra = Vector2::zero();
-                    vcp.rb = Vector2::zero();
-                    vcp.normal_mass = T::zero();
-                    vcp.tangent_mass = T::zero();
-                    vcp.velocity_bias = T::zero();
+            let vc = &mut velocity_constraints[i];
+            vc.friction = contact.friction();
+            vc.restitution = contact.restitution();
+            vc.tangent_speed = contact.tangent_speed();
+            vc.index_a = body_a.island_index;
+            vc.index_b = body_b.island_index;
+            vc.inv_mass_a = inv_mass_a;
+            vc.inv_mass_b = inv_mass_b;
+            vc.inv_i_a = inv_i_a;
+            vc.inv_i_b = inv_i_b;
+            vc.contact_index = i;
+            vc.point_count = point_count;
+            vc.k = Matrix22::zero();
+            vc.normal_mass = Matrix22::zero();
+
+            let pc = &mut position_constraints[i];
+            pc.index_a = body_a.island_index;
+            pc.index_b = body_b.island_index;
+            pc.inv_mass_a = inv_mass_a;
+            pc.inv_mass_b = inv_mass_b;
+            pc.local_center_a = body_a.sweep.local_center;
+            pc.local_center_b = body_b.sweep.local_center;
+            pc.inv_i_a = inv_i_a;
+            pc.inv_i_b = inv_i_b;
+            pc.local_normal = manifold.local_normal;
+            pc.local_point = manifold.local_point;
+            pc.point_count = point_count;
+            pc.radius_a = radius_a;
+            pc.radius_b = radius_b;
+            pc.type_ = manifold.type_;
+
+            for j in 0..point_count {
+                let cp = &manifold.points[j];
+                let vcp = &mut vc.points[j];
 
-                    pc.local_points[j] = cp.local_point;
+                if def.step.warm_starting {
+                    vcp.normal_impulse = def.step.dt_ratio * cp.normal_impulse;
+                    vcp.tangent_impulse = def.step.dt_ratio * cp.tangent_impulse;
+                } else {
+                    vcp.normal_impulse = T::zero();
+                    vcp.tangent_impulse = T::zero();
                 }
-            }
 
-            ContactSolver {
-                step: def.step,
-                positions: def.positions,
-                velocities: def.velocities,
-                position_constraints,
-                velocity_constraints,
-                contacts: def.contacts,
+                vcp.ra = Vector2::zero();
+                vcp.rb = Vector2::zero();
+                vcp.normal_mass = T::zero();
+                vcp.tangent_mass = T::zero();
+                vcp.velocity_bias = T::zero();
+
+                pc.local_points[j] = cp.local_point;
             }
         }
+
+        ContactSolver {
+            step: def.step,
+            position_constraints,
+            velocity_constraints,
+            contacts: def.contacts,
+        }
     }
 
-    pub fn initialize_velocity_constraints(&mut self) {
+    pub fn initialize_velocity_constraints<D>(
+        &mut self,
+        contacts: &Slab<Box<Contact<T, D>>>,
+        data: &SolverData<T>,
+    ) {
         for i in 0..self.contacts.len() {
             let vc = &mut self.velocity_constraints[i];
             let pc = &mut self.position_constraints[i];
 
             let radius_a = pc.radius_a;
             let radius_b = pc.radius_b;
-            let manifold = unsafe { self.contacts[vc.contact_index].as_ref().unwrap().manifold() };
+            let manifold = contacts[self.contacts[vc.contact_index].0].manifold();
 
             let index_a = vc.index_a;
             let index_b = vc.index_b;
@@ -175,15 +195,15 @@ impl<'a, T: Real, D> ContactSolver<'a, T, D> {
             let local_center_a = pc.local_center_a;
             let local_center_b = pc.local_center_b;
 
-            let ca = self.positions[index_a].c;
-            let aa = self.positions[index_a].a;
-            let va = self.velocities[index_a].v;
-            let wa = self.velocities[index_a].w;
+            let ca = data.positions[index_a].c;
+            let aa = data.positions[index_a].a;
+            let va = data.velocities[index_a].v;
+            let wa = data.velocities[index_a].w;
 
-            let cb = self.positions[index_b].c;
-            let ab = self.positions[index_b].a;
-            let vb = self.velocities[index_b].v;
-            let wb = self.velocities[index_b].w;
+            let cb = data.positions[index_b].c;
+            let ab = data.positions[index_b].a;
+            let vb = data.velocities[index_b].v;
+            let wb = data.velocities[index_b].w;
 
             assert!(manifold.point_count > 0);
 
@@ -267,7 +287,7 @@ impl<'a, T: Real, D> ContactSolver<'a, T, D> {
         }
     }
 
-    pub fn warm_start(&mut self) {
+    pub fn warm_start(&mut self, data: &mut SolverData<T>) {
         for i in 0..self.contacts.len() {
             let vc = &self.velocity_constraints[i];
 
@@ -279,10 +299,10 @@ impl<'a, T: Real, D> ContactSolver<'a, T, D> {
             let ib = vc.inv_i_b;
             let point_count = vc.point_count;
 
-            let mut va = self.velocities[index_a].v;
-            let mut wa = self.velocities[index_a].w;
-            let mut vb = self.velocities[index_b].v;
-            let mut wb = self.velocities[index_b].w;
+            let mut va = data.velocities[index_a].v;
+            let mut wa = data.velocities[index_a].w;
+            let mut vb = data.velocities[index_b].v;
+            let mut wb = data.velocities[index_b].w;
 
             let normal = vc.normal;
             let tangent = normal.cross(T::one());
@@ -296,14 +316,14 @@ impl<'a, T: Real, D> ContactSolver<'a, T, D> {
                 vb += p * mb;
             }
 
-            self.velocities[index_a].v = va;
-            self.velocities[index_a].w = wa;
-            self.velocities[index_b].v = vb;
-            self.velocities[index_b].w = wb;
+            data.velocities[index_a].v = va;
+            data.velocities[index_a].w = wa;
+            data.velocities[index_b].v = vb;
+            data.velocities[index_b].w = wb;
         }
     }
 
-    pub fn solve_velocity_constraints(&mut self) {
+    pub fn solve_velocity_constraints(&mut self, data: &mut SolverData<T>) {
         for i in 0..self.contacts.len() {
             let vc = &mut self.velocity_constraints[i];
 
@@ -315,10 +335,10 @@ impl<'a, T: Real, D> ContactSolver<'a, T, D> {
             let ib = vc.inv_i_b;
             let point_count = vc.point_count;
 
-            let mut va = self.velocities[index_a].v;
-            let mut wa = self.velocities[index_a].w;
-            let mut vb = self.velocities[index_b].v;
-            let mut wb = self.velocities[index_b].w;
+            let mut va = data.velocities[index_a].v;
+            let mut wa = data.velocities[index_a].w;
+            let mut vb = data.velocities[index_b].v;
+            let mut wb = data.velocities[index_b].w;
 
             let normal = vc.normal;
             let tangent = normal.cross(T::one());
@@ -369,8 +389,8 @@ impl<'a, T: Real, D> ContactSolver<'a, T, D> {
                     wb += ib * vcp.rb.cross(p);
                 }
             } else {
-                let cp1 = unsafe { vc.points.as_mut_ptr().as_mut().unwrap() };
-                let cp2 = unsafe { vc.points.as_mut_ptr().add(1).as_mut().unwrap() };
+                let (cp1, cp2) = vc.points.split_at_mut(1);
+                let (cp1, cp2) = (&mut cp1[0], &mut cp2[0]);
 
                 let a = Vector2::new(cp1.normal_impulse, cp2.normal_impulse);
                 assert!(a.x >= T::zero() && a.y >= T::zero());
@@ -471,22 +491,17 @@ impl<'a, T: Real, D> ContactSolver<'a, T, D> {
                 }
             }
 
-            self.velocities[index_a].v = va;
-            self.velocities[index_a].w = wa;
-            self.velocities[index_b].v = vb;
-            self.velocities[index_b].w = wb;
+            data.velocities[index_a].v = va;
+            data.velocities[index_a].w = wa;
+            data.velocities[index_b].v = vb;
+            data.velocities[index_b].w = wb;
         }
     }
 
-    pub fn store_impulses(&mut self) {
+    pub fn store_impulses<D>(&self, contacts: &mut Slab<Box<Contact<T, D>>>) {
         for i in 0..self.contacts.len() {
             let vc = &self.velocity_constraints[i];
-            let manifold = unsafe {
-                self.contacts[vc.contact_index]
-                    .as_mut()
-                    .unwrap()
-                    .manifold_mut()
-            };
+            let manifold = contacts[self.contacts[vc.contact_index].0].manifold_mut();
 
             for j in 0..vc.point_count {
                 manifold.points[j].normal_impulse = vc.points[j].normal_impulse;
@@ -495,7 +510,7 @@ impl<'a, T: Real, D> ContactSolver<'a, T, D> {
         }
     }
 
-    pub fn solve_position_constraints(&mut self) -> bool {
+    pub fn solve_position_constraints(&mut self, data: &mut SolverData<T>) -> bool {
         let mut min_separation = T::zero();
 
         for i in 0..self.contacts.len() {
@@ -511,11 +526,11 @@ impl<'a, T: Real, D> ContactSolver<'a, T, D> {
             let ib = pc.inv_i_b;
             let point_count = pc.point_count;
 
-            let mut ca = self.positions[index_a].c;
-            let mut aa = self.positions[index_a].a;
+            let mut ca = data.positions[index_a].c;
+            let mut aa = data.positions[index_a].a;
 
-            let mut cb = self.positions[index_b].c;
-            let mut ab = self.positions[index_b].a;
+            let mut cb = data.positions[index_b].c;
+            let mut ab = data.positions[index_b].a;
 
             for j in 0..point_count {
                 let xf_a = {
@@ -562,11 +577,11 @@ impl<'a, T: Real, D> ContactSolver<'a, T, D> {
                 ab += ib * rb.cross(p);
             }
 
-            self.positions[index_a].c = ca;
-            self.positions[index_a].a = aa;
+            data.positions[index_a].c = ca;
+            data.positions[index_a].a = aa;
 
-            self.positions[index_b].c = cb;
-            self.positions[index_b].a = ab;
+            data.positions[index_b].c = cb;
+            data.positions[index_b].a = ab;
         }
 
         min_separation >= -T::i32(3) * settings::linear_slop()
@@ -574,6 +589,7 @@ impl<'a, T: Real, D> ContactSolver<'a, T, D> {
 
     pub fn solve_toi_position_constraints(
         &mut self,
+        data: &mut SolverData<T>,
         toi_index_a: usize,
         toi_index_b: usize,
     ) -> bool {
@@ -602,11 +618,11 @@ impl<'a, T: Real, D> ContactSolver<'a, T, D> {
                 ib = pc.inv_i_b;
             }
 
-            let mut ca = self.positions[index_a].c;
-            let mut aa = self.positions[index_a].a;
+            let mut ca = data.positions[index_a].c;
+            let mut aa = data.positions[index_a].a;
 
-            let mut cb = self.positions[index_b].c;
-            let mut ab = self.positions[index_b].a;
+            let mut cb = data.positions[index_b].c;
+            let mut ab = data.positions[index_b].a;
 
             for j in 0..point_count {
                 let xf_a = {
@@ -654,11 +670,11 @@ impl<'a, T: Real, D> ContactSolver<'a, T, D> {
                 ab += ib * rb.cross(p);
             }
 
-            self.positions[index_a].c = ca;
-            self.positions[index_a].a = aa;
+            data.positions[index_a].c = ca;
+            data.positions[index_a].a = aa;
 
-            self.positions[index_b].c = cb;
-            self.positions[index_b].a = ab;
+            data.positions[index_b].c = cb;
+            data.positions[index_b].a = ab;
         }
 
         min_separation >= -T::f32(1.5) * settings::linear_slop::<T>()
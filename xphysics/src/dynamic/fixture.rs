@@ -1,7 +1,9 @@
-use crate::{Body, BroadPhase, MassData, RayCastInput, RayCastOutput, Shape};
+use crate::{Body, BodyId, BroadPhase, FixtureId, MassData, RayCastInput, RayCastOutput, Shape};
+use slab::Slab;
 use xmath::{Real, Transform, Vector2, AABB};
 
 #[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Filter {
     pub category_bits: u16,
     pub mask_bits: u16,
@@ -18,6 +20,41 @@ impl Default for Filter {
     }
 }
 
+/// 查询和射线检测常用的过滤条件，默认不过滤任何夹具，见`QueryFilter::test`
+#[derive(Debug, Copy, Clone)]
+pub struct QueryFilter {
+    /// 和夹具的`Filter`一样，双方的类别和掩码都匹配时才返回
+    pub category_bits: u16,
+    pub mask_bits: u16,
+    pub ignore_sensors: bool,
+    pub ignore_body: Option<BodyId>,
+}
+
+impl Default for QueryFilter {
+    fn default() -> Self {
+        QueryFilter {
+            category_bits: 0xFFFF,
+            mask_bits: 0xFFFF,
+            ignore_sensors: false,
+            ignore_body: None,
+        }
+    }
+}
+
+impl QueryFilter {
+    /// 夹具是否满足条件，可以在查询的闭包中使用：`world.query_aabb_filtered(aabb, |f| filter.test(f))`
+    pub fn test<T: Real, D>(&self, fixture: &Fixture<T, D>) -> bool {
+        if self.ignore_sensors && fixture.is_sensor() {
+            return false;
+        }
+        if self.ignore_body == Some(fixture.body()) {
+            return false;
+        }
+        let filter = fixture.filter();
+        (self.mask_bits & filter.category_bits) != 0 && (self.category_bits & filter.mask_bits) != 0
+    }
+}
+
 pub struct FixtureDef<T, D> {
     pub shape: Box<dyn Shape<T>>,
     pub data: Option<D>,
@@ -26,6 +63,12 @@ pub struct FixtureDef<T, D> {
     pub density: T,
     pub is_sensor: bool,
     pub filter: Filter,
+
+    /// 单向平台的实心一侧在刚体坐标系中的法线，其他刚体只能从这一侧碰撞
+    pub one_way_normal: Option<Vector2<T>>,
+
+    /// 粘性夹具开始接触时会和对方的刚体粘在一起，值是连接能承受的最大力
+    pub adhesion: Option<T>,
 }
 
 impl<T: Real, D> FixtureDef<T, D> {
@@ -38,87 +81,81 @@ impl<T: Real, D> FixtureDef<T, D> {
             density,
             is_sensor: false,
             filter: Default::default(),
+            one_way_normal: None,
+            adhesion: None,
         }
     }
 }
 
-pub(crate) struct FixtureProxy<T, D> {
+/// 夹具的一个子形状，broad-phase中代理的数据，`fixture`是夹具在刚体的`fixture_list`中的下标
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct FixtureKey {
+    pub(crate) body: BodyId,
+    pub(crate) fixture: usize,
+    pub(crate) child_index: usize,
+}
+
+impl FixtureKey {
+    pub(crate) fn get<T, D>(self, bodies: &Slab<Box<Body<T, D>>>) -> &Fixture<T, D> {
+        &bodies[self.body.index].fixture_list[self.fixture].1
+    }
+}
+
+pub(crate) struct FixtureProxy<T> {
     pub(crate) aabb: AABB<T>,
-    pub(crate) fixture_ptr: *mut Fixture<T, D>,
     pub(crate) child_index: usize,
     pub(crate) proxy_id: usize,
 }
 
 pub struct Fixture<T, D> {
+    pub(crate) id: FixtureId,
     pub(crate) density: T,
-    pub(crate) body_ptr: *mut Body<T, D>,
+    pub(crate) body: BodyId,
     pub(crate) shape: Box<dyn Shape<T> + 'static>,
     pub(crate) friction: T,
     pub(crate) restitution: T,
-    pub(crate) proxies: Vec<Box<FixtureProxy<T, D>>>,
+    pub(crate) proxies: Vec<FixtureProxy<T>>,
     pub(crate) filter: Filter,
     pub(crate) is_sensor: bool,
+    pub(crate) one_way_normal: Option<Vector2<T>>,
+    pub(crate) adhesion: Option<T>,
     pub(crate) data: Option<D>,
 }
 
 impl<T: Real, D> Fixture<T, D> {
-    pub fn body_mut(&mut self) -> &mut Body<T, D> {
-        unsafe { self.body_ptr.as_mut().unwrap() }
+    pub fn id(&self) -> FixtureId {
+        self.id
     }
 
-    pub fn body(&self) -> &Body<T, D> {
-        unsafe { self.body_ptr.as_ref().unwrap() }
+    /// 夹具所在的刚体，用`World::body`得到刚体
+    pub fn body(&self) -> BodyId {
+        self.body
     }
 
     pub fn shape(&self) -> &dyn Shape<T> {
         self.shape.as_ref()
     }
 
-    pub fn set_sensor(&mut self, sensor: bool) {
-        if self.is_sensor != sensor {
-            self.body_mut().set_awake(true);
-            self.is_sensor = sensor;
-        }
-    }
-
     pub fn is_sensor(&self) -> bool {
         self.is_sensor
     }
 
-    pub fn set_filter(&mut self, filter: Filter) {
-        if self.filter != filter {
-            self.filter = filter;
-            self.re_filter();
-        }
+    pub fn one_way_normal(&self) -> Option<Vector2<T>> {
+        self.one_way_normal
     }
 
-    fn re_filter(&mut self) {
-        unsafe {
-            if self.body_ptr.is_null() {
-                return;
-            }
-
-            let mut edge = (*self.body_ptr).contact_list_ptr;
-            while !edge.is_null() {
-                let contact = (*edge).contact_ptr;
-                let fixture_a = (*contact).fixture_a_ptr;
-                let fixture_b = (*contact).fixture_b_ptr;
-                if fixture_a == self || fixture_b == self {
-                    (*contact).flag_for_filtering();
-                }
-                edge = (*edge).next_ptr;
-            }
+    /// 已经在穿过平台的接触不受影响
+    pub fn set_one_way_normal(&mut self, normal: Option<Vector2<T>>) {
+        self.one_way_normal = normal.map(|n| n.normalize());
+    }
 
-            let world = (*self.body_ptr).world_ptr;
-            if world.is_null() {
-                return;
-            }
+    pub fn adhesion(&self) -> Option<T> {
+        self.adhesion
+    }
 
-            let broad_phase = &mut (*world).contact_manager.broad_phase;
-            for i in 0..self.proxies.len() {
-                broad_phase.touch_proxy(self.proxies[i].proxy_id);
-            }
-        }
+    /// 只影响之后开始的接触，已经粘上的连接不变
+    pub fn set_adhesion(&mut self, adhesion: Option<T>) {
+        self.adhesion = adhesion;
     }
 
     pub fn filter(&self) -> &Filter {
@@ -162,17 +199,19 @@ impl<T: Real, D> Fixture<T, D> {
         self.restitution = restitution;
     }
 
-    pub fn test_point(&self, pt: &Vector2<T>) -> bool {
-        self.shape.test_point(self.body().transform(), pt)
+    /// `xf`是夹具所在刚体的变换
+    pub fn test_point(&self, xf: &Transform<T>, pt: &Vector2<T>) -> bool {
+        self.shape.test_point(xf, pt)
     }
 
+    /// `xf`是夹具所在刚体的变换
     pub fn ray_cast(
         &self,
         input: &RayCastInput<T>,
+        xf: &Transform<T>,
         child_index: usize,
     ) -> Option<RayCastOutput<T>> {
-        self.shape
-            .ray_cast(input, self.body().transform(), child_index)
+        self.shape.ray_cast(input, xf, child_index)
     }
 
     pub fn mass_data(&self) -> MassData<T> {
@@ -183,39 +222,47 @@ impl<T: Real, D> Fixture<T, D> {
         &self.proxies[child_index].aabb
     }
 
+    /// `index`是夹具在刚体的`fixture_list`中的下标
     pub(crate) fn create_proxies(
         &mut self,
-        broad_phase: &mut BroadPhase<T, *mut FixtureProxy<T, D>>,
+        broad_phase: &mut BroadPhase<T, FixtureKey>,
         xf: Transform<T>,
+        index: usize,
     ) {
         self.proxies.clear();
         for i in 0..self.shape.child_count() {
             let aabb = self.shape.compute_aabb(&xf, i);
-            let fixture_ptr = self as *mut Fixture<T, D>;
-            let mut proxy = Box::new(FixtureProxy {
+            let key = FixtureKey {
+                body: self.body,
+                fixture: index,
+                child_index: i,
+            };
+            self.proxies.push(FixtureProxy {
                 aabb,
-                fixture_ptr,
                 child_index: i,
-                proxy_id: 0,
+                proxy_id: broad_phase.create_proxy(aabb, key),
             });
-            proxy.proxy_id =
-                broad_phase.create_proxy(aabb, proxy.as_mut() as *mut FixtureProxy<T, D>);
-            self.proxies.push(proxy);
         }
     }
 
-    pub(crate) fn destroy_proxies(
-        &mut self,
-        broad_phase: &mut BroadPhase<T, *mut FixtureProxy<T, D>>,
-    ) {
+    pub(crate) fn destroy_proxies(&mut self, broad_phase: &mut BroadPhase<T, FixtureKey>) {
         for proxy in self.proxies.drain(..) {
             broad_phase.destroy_proxy(proxy.proxy_id);
         }
     }
 
+    /// 前面的夹具被删除后，更新代理中记录的下标
+    pub(crate) fn set_index(&mut self, broad_phase: &mut BroadPhase<T, FixtureKey>, index: usize) {
+        for proxy in &self.proxies {
+            if let Some(key) = broad_phase.tree.get_data_mut(proxy.proxy_id) {
+                key.fixture = index;
+            }
+        }
+    }
+
     pub(crate) fn synchronize(
         &mut self,
-        broad_phase: &mut BroadPhase<T, *mut FixtureProxy<T, D>>,
+        broad_phase: &mut BroadPhase<T, FixtureKey>,
         xf1: &Transform<T>,
         xf2: &Transform<T>,
     ) {
@@ -0,0 +1,12 @@
+use crate::Body;
+
+/// 力场在`World`中的索引
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ForceGeneratorId(pub(crate) usize);
+
+/// 每次步进求解之前对动态刚体施加力，例如风、引力源和阻力区域
+///
+/// 对睡眠的刚体施力时可以用`wake = false`，避免唤醒整个世界。
+pub trait ForceGenerator<T, D> {
+    fn apply(&mut self, body: &mut Body<T, D>, dt: T);
+}
@@ -1,318 +1,399 @@
 use crate::dynamic::body::BodyFlags;
+use crate::dynamic::contact_manager::ContactManager;
 use crate::dynamic::contacts::{
-    Contact, ContactImpulse, ContactListener, ContactSolver, ContactSolverDef,
-    ContactVelocityConstraint,
+    ContactEvent, ContactImpulse, ContactKey, ContactMut, ContactSolver, ContactSolverDef,
 };
-use crate::dynamic::time_step::{Position, Profile, TimeStep, Velocity};
+use crate::dynamic::joints::JointId;
+use crate::dynamic::time_step::{Position, Profile, SolverData, TimeStep, Velocity};
+use crate::dynamic::world::WorldInner;
 use crate::timer::Timer;
-use crate::{settings, Body, BodyType};
-use xmath::{DotTrait, Real, Vector2};
+use crate::{settings, Body, BodyId, BodyType};
+use std::ops::Range;
+use xmath::{DotTrait, Real};
+
+/// 上一步中求解的一个岛，见`World::islands`
+#[derive(Debug, Clone)]
+pub struct IslandInfo {
+    /// 岛中的刚体在世界的`island_bodies`数组中的范围
+    pub(crate) bodies: Range<usize>,
+    pub contact_count: usize,
+    pub joint_count: usize,
+
+    /// 求解之后整个岛进入了睡眠
+    pub sleeping: bool,
+}
 
-pub struct Island<'a, T, D> {
-    contact_listener: &'a Option<Box<dyn ContactListener<T, D>>>,
-    pub(crate) bodies: Vec<*mut Body<T, D>>,
-    pub(crate) contacts: Vec<*mut Contact<T, D>>,
+impl IslandInfo {
+    /// 岛中非静态刚体的数量，刚体列表用`World::island_bodies`得到
+    pub fn body_count(&self) -> usize {
+        self.bodies.len()
+    }
+}
+
+/// 求解用的岛，数组保存在世界中，每次求解前用`prepare`清空后复用
+pub struct Island<T> {
+    pub(crate) bodies: Vec<BodyId>,
+    pub(crate) contacts: Vec<ContactKey>,
+    pub(crate) joints: Vec<JointId>,
+    pub(crate) body_capacity: usize,
+    pub(crate) contact_capacity: usize,
     positions: Vec<Position<T>>,
     velocities: Vec<Velocity<T>>,
 }
 
-impl<'a, T: Real, D> Island<'a, T, D> {
-    pub fn new(
+impl<T> Default for Island<T> {
+    fn default() -> Self {
+        Island {
+            bodies: Vec::new(),
+            contacts: Vec::new(),
+            joints: Vec::new(),
+            body_capacity: 0,
+            contact_capacity: 0,
+            positions: Vec::new(),
+            velocities: Vec::new(),
+        }
+    }
+}
+
+impl<T: Real> Island<T> {
+    /// 清空岛，最多可以加入`body_capacity`个刚体和`contact_capacity`个接触
+    pub fn prepare(
+        &mut self,
         body_capacity: usize,
         contact_capacity: usize,
-        listener: &Option<Box<dyn ContactListener<T, D>>>,
-    ) -> Island<T, D> {
-        let mut bodies = Vec::with_capacity(body_capacity);
-        bodies.resize(body_capacity, std::ptr::null_mut());
-
-        let mut contacts = Vec::with_capacity(contact_capacity);
-        contacts.resize(contact_capacity, std::ptr::null_mut());
-
-        let mut positions = Vec::with_capacity(body_capacity);
-        positions.resize(body_capacity, unsafe { std::mem::zeroed() });
-
-        let mut velocities = Vec::with_capacity(body_capacity);
-        velocities.resize(body_capacity, unsafe { std::mem::zeroed() });
+        joint_capacity: usize,
+    ) {
+        self.clear();
+        self.bodies.reserve(body_capacity);
+        self.contacts.reserve(contact_capacity);
+        self.joints.reserve(joint_capacity);
+        self.positions
+            .resize(body_capacity, unsafe { std::mem::zeroed() });
+        self.velocities
+            .resize(body_capacity, unsafe { std::mem::zeroed() });
+        self.body_capacity = body_capacity;
+        self.contact_capacity = contact_capacity;
+    }
 
-        Island {
-            contact_listener: listener,
-            bodies,
-            contacts,
-            positions,
-            velocities,
-        }
+    pub fn reserve(&mut self, bodies: usize, contacts: usize) {
+        self.bodies.reserve(bodies);
+        self.contacts.reserve(contacts);
+        self.positions.reserve(bodies);
+        self.velocities.reserve(bodies);
     }
 
-    pub fn solve(
+    /// 岛本身要先从世界中取出来
+    pub(crate) fn solve<D>(
         &mut self,
         profile: &mut Profile,
         step: &TimeStep<T>,
-        gravity: Vector2<T>,
-        allow_sleep: bool,
+        world: &mut WorldInner<T, D>,
     ) {
-        unsafe {
-            let mut timer = Timer::new();
-            let h = step.dt;
-
-            for i in 0..self.bodies.len() {
-                let b = self.bodies[i];
+        let mut timer = Timer::new();
+        let h = step.dt;
+        let gravity = world.gravity;
 
-                let c = (*b).sweep.c;
-                let a = (*b).sweep.a;
-                let mut v = (*b).linear_velocity_;
-                let mut w = (*b).angular_velocity_;
+        for (i, id) in self.bodies.iter().enumerate() {
+            let b = &mut world.bodies_slab[id.index];
 
-                (*b).sweep.c0 = (*b).sweep.c;
-                (*b).sweep.a0 = (*b).sweep.a;
+            let c = b.sweep.c;
+            let a = b.sweep.a;
+            let mut v = b.linear_velocity_;
+            let mut w = b.angular_velocity_;
 
-                if (*b).body_type() == BodyType::Dynamic {
-                    v += ((*b).force * (*b).inv_mass + gravity * (*b).gravity_scale()) * h;
-                    w += (*b).inv_i * (*b).torque * h;
+            b.sweep.c0 = b.sweep.c;
+            b.sweep.a0 = b.sweep.a;
 
-                    v *= T::one() / (T::one() + h * (*b).linear_damping());
-                    w *= T::one() / (T::one() + h * (*b).angular_damping());
-                }
+            if b.body_type() == BodyType::Dynamic {
+                v += (b.force * b.inv_mass + gravity * b.gravity_scale()) * h;
+                w += b.inv_i * b.torque * h;
 
-                self.positions[i].c = c;
-                self.positions[i].a = a;
-                self.velocities[i].v = v;
-                self.velocities[i].w = w;
+                v *= T::one() / (T::one() + h * b.linear_damping());
+                w *= T::one() / (T::one() + h * b.angular_damping());
             }
 
-            timer.reset();
+            self.positions[i].c = c;
+            self.positions[i].a = a;
+            self.velocities[i].v = v;
+            self.velocities[i].w = w;
+        }
 
-            // TODO: joints
-            //            let solver_data = SolverData {
-            //                step: *step,
-            //                positions: &self.positions,
-            //                velocities: &self.velocities,
-            //            };
+        timer.reset();
 
-            let self_positions = self.positions.as_mut_ptr();
-            let self_velocities = self.velocities.as_mut_ptr();
-            let self_contacts = self.contacts.as_ptr();
-            let mut contact_solver = ContactSolver::new(ContactSolverDef {
+        let mut contact_solver = ContactSolver::new(
+            ContactSolverDef {
                 step: *step,
-                contacts: std::slice::from_raw_parts(self_contacts, self.contacts.len()),
-                positions: std::slice::from_raw_parts_mut(self_positions, self.positions.len()),
-                velocities: std::slice::from_raw_parts_mut(self_velocities, self.velocities.len()),
-            });
-            contact_solver.initialize_velocity_constraints();
-            if step.warm_starting {
-                contact_solver.warm_start();
-            }
+                contacts: &self.contacts,
+                allocator: &mut world.stack_allocator,
+            },
+            &world.contact_manager.contacts,
+            &world.bodies_slab,
+        );
+        let mut solver_data = SolverData {
+            step: *step,
+            positions: &mut self.positions,
+            velocities: &mut self.velocities,
+        };
+
+        contact_solver
+            .initialize_velocity_constraints(&world.contact_manager.contacts, &solver_data);
+        if step.warm_starting {
+            contact_solver.warm_start(&mut solver_data);
+        }
+
+        for id in &self.joints {
+            world.joints_slab[id.0].init_velocity_constraints(&world.bodies_slab, &mut solver_data);
+        }
 
-            profile.solve_init = timer.get_duration();
+        profile.solve_init = timer.get_duration();
 
-            timer.reset();
-            for _ in 0..step.velocity_iterations {
-                contact_solver.solve_velocity_constraints();
+        timer.reset();
+        for _ in 0..step.velocity_iterations {
+            for id in &self.joints {
+                world.joints_slab[id.0].solve_velocity_constraints(&mut solver_data);
             }
+            contact_solver.solve_velocity_constraints(&mut solver_data);
+        }
 
-            contact_solver.store_impulses();
-            profile.solve_velocity = timer.get_duration();
+        contact_solver.store_impulses(&mut world.contact_manager.contacts);
+        profile.solve_velocity = timer.get_duration();
 
-            for i in 0..self.bodies.len() {
-                let mut c = (*self_positions.add(i)).c;
-                let mut a = (*self_positions.add(i)).a;
-                let mut v = (*self_velocities.add(i)).v;
-                let mut w = (*self_velocities.add(i)).w;
+        for i in 0..self.bodies.len() {
+            let mut c = solver_data.positions[i].c;
+            let mut a = solver_data.positions[i].a;
+            let mut v = solver_data.velocities[i].v;
+            let mut w = solver_data.velocities[i].w;
 
-                let translation = v * h;
-                if translation.dot(translation) > settings::max_translation_squared() {
-                    let ratio = settings::max_translation::<T>() / translation.length();
-                    v *= ratio;
-                }
+            let translation = v * h;
+            if translation.dot(translation) > settings::max_translation_squared() {
+                let ratio = settings::max_translation::<T>() / translation.length();
+                v *= ratio;
+            }
 
-                let rotation = w * h;
-                if rotation * rotation > settings::max_rotation_squared::<T>() {
-                    let ratio = settings::max_rotation::<T>() / rotation.abs();
-                    w *= ratio;
-                }
+            let rotation = w * h;
+            if rotation * rotation > settings::max_rotation_squared::<T>() {
+                let ratio = settings::max_rotation::<T>() / rotation.abs();
+                w *= ratio;
+            }
 
-                c += v * h;
-                a += w * h;
+            c += v * h;
+            a += w * h;
 
-                (*self_positions.add(i)).c = c;
-                (*self_positions.add(i)).a = a;
-                (*self_velocities.add(i)).v = v;
-                (*self_velocities.add(i)).w = w;
-            }
+            solver_data.positions[i].c = c;
+            solver_data.positions[i].a = a;
+            solver_data.velocities[i].v = v;
+            solver_data.velocities[i].w = w;
+        }
 
-            timer.reset();
-            let mut position_solved = false;
-            for _ in 0..step.position_iterations {
-                let contacts_okay = contact_solver.solve_position_constraints();
-                if contacts_okay {
-                    position_solved = true;
-                    break;
-                }
+        timer.reset();
+        let mut position_solved = false;
+        for _ in 0..step.position_iterations {
+            let contacts_okay = contact_solver.solve_position_constraints(&mut solver_data);
+
+            let mut joints_okay = true;
+            for id in &self.joints {
+                let joint_okay =
+                    world.joints_slab[id.0].solve_position_constraints(&mut solver_data);
+                joints_okay = joints_okay && joint_okay;
             }
 
-            for i in 0..self.bodies.len() {
-                let body = self.bodies[i];
-                (*body).sweep.c = (*self_positions.add(i)).c;
-                (*body).sweep.a = (*self_positions.add(i)).a;
-                (*body).linear_velocity_ = (*self_velocities.add(i)).v;
-                (*body).angular_velocity_ = (*self_velocities.add(i)).w;
-                (*body).synchronize_transform();
+            if contacts_okay && joints_okay {
+                position_solved = true;
+                break;
             }
+        }
+
+        for (i, id) in self.bodies.iter().enumerate() {
+            let body = &mut world.bodies_slab[id.index];
+            body.sweep.c = self.positions[i].c;
+            body.sweep.a = self.positions[i].a;
+            body.linear_velocity_ = self.velocities[i].v;
+            body.angular_velocity_ = self.velocities[i].w;
+            body.synchronize_transform();
+        }
+
+        profile.solve_position = timer.get_duration();
+        self.report(world);
 
-            profile.solve_position = timer.get_duration();
-            self.report(contact_solver.velocity_constraints.as_ptr());
-
-            if allow_sleep {
-                let mut min_sleep_time = T::max_value();
-                let lin_tol_sqr = settings::linear_sleep_tolerance::<T>()
-                    * settings::linear_sleep_tolerance::<T>();
-                let ang_tol_sqr = settings::angular_sleep_tolerance::<T>()
-                    * settings::angular_sleep_tolerance::<T>();
-
-                for i in 0..self.bodies.len() {
-                    let b = self.bodies[i];
-                    if (*b).body_type() == BodyType::Static {
-                        continue;
-                    }
-
-                    if !(*b).flags.contains(BodyFlags::AUTO_SLEEP)
-                        || (*b).angular_velocity_ * (*b).angular_velocity_ > ang_tol_sqr
-                        || (*b).linear_velocity_.dot((*b).linear_velocity_) > lin_tol_sqr
-                    {
-                        (*b).sleep_time = T::zero();
-                        min_sleep_time = T::zero();
-                    } else {
-                        (*b).sleep_time += h;
-                        min_sleep_time = min_sleep_time.min((*b).sleep_time);
-                    }
+        if world.allow_sleep {
+            let mut min_sleep_time = T::max_value();
+            let lin_tol_sqr =
+                settings::linear_sleep_tolerance::<T>() * settings::linear_sleep_tolerance::<T>();
+            let ang_tol_sqr =
+                settings::angular_sleep_tolerance::<T>() * settings::angular_sleep_tolerance::<T>();
+
+            for id in &self.bodies {
+                let b = &mut world.bodies_slab[id.index];
+                if b.body_type() == BodyType::Static {
+                    continue;
+                }
+
+                if !b.flags.contains(BodyFlags::AUTO_SLEEP)
+                    || b.angular_velocity_ * b.angular_velocity_ > ang_tol_sqr
+                    || b.linear_velocity_.dot(b.linear_velocity_) > lin_tol_sqr
+                {
+                    b.sleep_time = T::zero();
+                    min_sleep_time = T::zero();
+                } else {
+                    b.sleep_time += h;
+                    min_sleep_time = min_sleep_time.min(b.sleep_time);
                 }
+            }
 
-                if min_sleep_time >= settings::time_to_sleep() && position_solved {
-                    for i in 0..self.bodies.len() {
-                        let b = self.bodies[i];
-                        (*b).set_awake(false);
-                    }
+            if min_sleep_time >= settings::time_to_sleep() && position_solved {
+                for id in &self.bodies {
+                    world.bodies_slab[id.index].set_awake(false);
                 }
             }
         }
     }
 
-    unsafe fn report(&mut self, constraints: *const ContactVelocityConstraint<T>) {
-        if let Some(listener) = &self.contact_listener {
-            for i in 0..self.contacts.len() {
-                let c = self.contacts[i];
-                let vc = constraints.add(i);
-                let mut impulse = ContactImpulse {
-                    normal_impulses: Default::default(),
-                    tangent_impulses: Default::default(),
-                    count: (*vc).point_count,
-                };
-                for j in 0..(*vc).point_count {
-                    impulse.normal_impulses[j] = (*vc).points[j].normal_impulse;
-                    impulse.tangent_impulses[j] = (*vc).points[j].tangent_impulse;
-                }
-                listener.post_solve(c.as_mut().unwrap(), &impulse);
+    /// 求解器的速度约束还保存在`stack_allocator`中，从中取出冲量通知监听器
+    fn report<D>(&self, world: &mut WorldInner<T, D>) {
+        let ContactManager {
+            contacts,
+            contact_listener,
+            contact_events,
+            ..
+        } = &mut world.contact_manager;
+        let bodies = &world.bodies_slab;
+        let constraints = &world.stack_allocator.velocity_constraints;
+
+        for (i, key) in self.contacts.iter().enumerate() {
+            let vc = &constraints[i];
+            let mut impulse = ContactImpulse {
+                normal_impulses: Default::default(),
+                tangent_impulses: Default::default(),
+                count: vc.point_count,
+            };
+            for j in 0..vc.point_count {
+                impulse.normal_impulses[j] = vc.points[j].normal_impulse;
+                impulse.tangent_impulses[j] = vc.points[j].tangent_impulse;
             }
+            let mut contact = ContactMut::new(&mut contacts[key.0], bodies);
+            if let Some(listener) = contact_listener {
+                listener.post_solve(&mut contact, &impulse);
+            }
+            contact.record_event(bodies, contact_events, |contact| ContactEvent::PostSolve {
+                contact,
+                impulse,
+            });
         }
     }
 
-    pub fn solve_toi(&mut self, sub_step: &TimeStep<T>, toi_index_a: usize, toi_index_b: usize) {
+    /// `toi_index_a`和`toi_index_b`是发生碰撞的两个刚体在岛中的下标
+    pub(crate) fn solve_toi<D>(
+        &mut self,
+        sub_step: &TimeStep<T>,
+        toi_index_a: usize,
+        toi_index_b: usize,
+        world: &mut WorldInner<T, D>,
+    ) {
         assert!(toi_index_a < self.bodies.len());
         assert!(toi_index_b < self.bodies.len());
 
-        unsafe {
-            for i in 0..self.bodies.len() {
-                let b = self.bodies[i];
-                self.positions[i].c = (*b).sweep.c;
-                self.positions[i].a = (*b).sweep.a;
-                self.velocities[i].v = (*b).linear_velocity_;
-                self.velocities[i].w = (*b).angular_velocity_;
-            }
+        for (i, id) in self.bodies.iter().enumerate() {
+            let b = &world.bodies_slab[id.index];
+            self.positions[i].c = b.sweep.c;
+            self.positions[i].a = b.sweep.a;
+            self.velocities[i].v = b.linear_velocity_;
+            self.velocities[i].w = b.angular_velocity_;
+        }
 
-            let mut contact_solver = ContactSolver::new(ContactSolverDef {
+        let mut contact_solver = ContactSolver::new(
+            ContactSolverDef {
                 step: *sub_step,
-                contacts: std::slice::from_raw_parts(self.contacts.as_ptr(), self.contacts.len()),
-                positions: std::slice::from_raw_parts_mut(
-                    self.positions.as_mut_ptr(),
-                    self.positions.len(),
-                ),
-                velocities: std::slice::from_raw_parts_mut(
-                    self.velocities.as_mut_ptr(),
-                    self.velocities.len(),
-                ),
-            });
-
-            for _ in 0..sub_step.position_iterations {
-                let contacts_okay =
-                    contact_solver.solve_toi_position_constraints(toi_index_a, toi_index_b);
-                if contacts_okay {
-                    break;
-                }
+                contacts: &self.contacts,
+                allocator: &mut world.stack_allocator,
+            },
+            &world.contact_manager.contacts,
+            &world.bodies_slab,
+        );
+        let mut solver_data = SolverData {
+            step: *sub_step,
+            positions: &mut self.positions,
+            velocities: &mut self.velocities,
+        };
+
+        for _ in 0..sub_step.position_iterations {
+            let contacts_okay = contact_solver.solve_toi_position_constraints(
+                &mut solver_data,
+                toi_index_a,
+                toi_index_b,
+            );
+            if contacts_okay {
+                break;
             }
+        }
 
-            (*self.bodies[toi_index_a]).sweep.c0 = self.positions[toi_index_a].c;
-            (*self.bodies[toi_index_a]).sweep.a0 = self.positions[toi_index_a].a;
-            (*self.bodies[toi_index_b]).sweep.c0 = self.positions[toi_index_b].c;
-            (*self.bodies[toi_index_b]).sweep.a0 = self.positions[toi_index_b].a;
-
-            contact_solver.initialize_velocity_constraints();
+        for &index in &[toi_index_a, toi_index_b] {
+            let body = &mut world.bodies_slab[self.bodies[index].index];
+            body.sweep.c0 = solver_data.positions[index].c;
+            body.sweep.a0 = solver_data.positions[index].a;
+        }
 
-            for _ in 0..sub_step.velocity_iterations {
-                contact_solver.solve_velocity_constraints();
-            }
+        contact_solver
+            .initialize_velocity_constraints(&world.contact_manager.contacts, &solver_data);
 
-            let h = sub_step.dt;
+        for _ in 0..sub_step.velocity_iterations {
+            contact_solver.solve_velocity_constraints(&mut solver_data);
+        }
 
-            for i in 0..self.bodies.len() {
-                let mut c = self.positions[i].c;
-                let mut a = self.positions[i].a;
-                let mut v = self.velocities[i].v;
-                let mut w = self.velocities[i].w;
+        let h = sub_step.dt;
 
-                let translation = v * h;
-                if translation.dot(translation) > settings::max_translation_squared() {
-                    let ratio = settings::max_translation::<T>() / translation.length();
-                    v *= ratio;
-                }
+        for (i, id) in self.bodies.iter().enumerate() {
+            let mut c = solver_data.positions[i].c;
+            let mut a = solver_data.positions[i].a;
+            let mut v = solver_data.velocities[i].v;
+            let mut w = solver_data.velocities[i].w;
 
-                let rotation = w * h;
-                if rotation * rotation > settings::max_rotation_squared::<T>() {
-                    let ratio = settings::max_rotation::<T>() / rotation.abs();
-                    w *= ratio;
-                }
+            let translation = v * h;
+            if translation.dot(translation) > settings::max_translation_squared() {
+                let ratio = settings::max_translation::<T>() / translation.length();
+                v *= ratio;
+            }
 
-                c += v * h;
-                a += w * h;
+            let rotation = w * h;
+            if rotation * rotation > settings::max_rotation_squared::<T>() {
+                let ratio = settings::max_rotation::<T>() / rotation.abs();
+                w *= ratio;
+            }
 
-                self.positions[i].c = c;
-                self.positions[i].a = a;
-                self.velocities[i].v = v;
-                self.velocities[i].w = w;
+            c += v * h;
+            a += w * h;
 
-                let body = self.bodies[i];
-                (*body).sweep.c = c;
-                (*body).sweep.a = a;
-                (*body).linear_velocity_ = v;
-                (*body).angular_velocity_ = w;
-                (*body).synchronize_transform();
-            }
+            solver_data.positions[i].c = c;
+            solver_data.positions[i].a = a;
+            solver_data.velocities[i].v = v;
+            solver_data.velocities[i].w = w;
 
-            self.report(contact_solver.velocity_constraints.as_ptr());
+            let body = &mut world.bodies_slab[id.index];
+            body.sweep.c = c;
+            body.sweep.a = a;
+            body.linear_velocity_ = v;
+            body.angular_velocity_ = w;
+            body.synchronize_transform();
         }
+
+        self.report(world);
     }
 
-    pub fn add_body(&mut self, body: *mut Body<T, D>) {
-        self.bodies.push(body);
-        unsafe {
-            (*body).island_index = self.bodies.len() - 1;
-        }
+    pub fn add_body<D>(&mut self, body: &mut Body<T, D>) {
+        body.island_index = self.bodies.len();
+        self.bodies.push(body.id);
     }
 
-    pub fn add_contact(&mut self, contact: *mut Contact<T, D>) {
+    pub fn add_contact(&mut self, contact: ContactKey) {
         self.contacts.push(contact);
     }
 
+    pub fn add_joint(&mut self, joint: JointId) {
+        self.joints.push(joint);
+    }
+
     pub fn clear(&mut self) {
         self.bodies.clear();
         self.contacts.clear();
+        self.joints.clear();
     }
 }
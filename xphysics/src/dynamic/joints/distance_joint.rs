@@ -0,0 +1,339 @@
+use crate::dynamic::joints::JointBase;
+use crate::dynamic::time_step::SolverData;
+use crate::{settings, Body, BodyId, World};
+use slab::Slab;
+use xmath::{CrossTrait, DotTrait, Multiply, Real, Rotation, Vector2};
+
+/// 距离关节的参数，保持两个锚点之间的距离，频率大于0时像弹簧一样有弹性
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DistanceJointDef<T> {
+    pub body_a: BodyId,
+    pub body_b: BodyId,
+    pub collide_connected: bool,
+    pub local_anchor_a: Vector2<T>,
+    pub local_anchor_b: Vector2<T>,
+
+    /// 两个锚点之间的距离
+    pub length: T,
+
+    /// 弹簧的频率(Hz)，为0时是刚性的
+    pub frequency_hz: T,
+
+    /// 阻尼比，0表示没有阻尼，1表示临界阻尼
+    pub damping_ratio: T,
+}
+
+impl<T: Real> DistanceJointDef<T> {
+    pub fn new(body_a: BodyId, body_b: BodyId) -> DistanceJointDef<T> {
+        DistanceJointDef {
+            body_a,
+            body_b,
+            collide_connected: false,
+            local_anchor_a: Vector2::zero(),
+            local_anchor_b: Vector2::zero(),
+            length: T::one(),
+            frequency_hz: T::zero(),
+            damping_ratio: T::zero(),
+        }
+    }
+
+    /// 使用世界坐标中的两个锚点，距离为锚点当前的距离
+    pub fn with_anchors<D>(
+        world: &World<T, D>,
+        body_a: BodyId,
+        body_b: BodyId,
+        anchor_a: Vector2<T>,
+        anchor_b: Vector2<T>,
+    ) -> DistanceJointDef<T> {
+        let a = world.body(body_a).expect("invalid body a");
+        let b = world.body(body_b).expect("invalid body b");
+        DistanceJointDef {
+            local_anchor_a: a.local_point(anchor_a),
+            local_anchor_b: b.local_point(anchor_b),
+            length: (anchor_b - anchor_a).length(),
+            ..DistanceJointDef::new(body_a, body_b)
+        }
+    }
+}
+
+pub struct DistanceJoint<T> {
+    pub(crate) base: JointBase<T>,
+    local_anchor_a: Vector2<T>,
+    local_anchor_b: Vector2<T>,
+    length: T,
+    frequency_hz: T,
+    damping_ratio: T,
+    impulse: T,
+
+    // 求解时的临时数据
+    index_a: usize,
+    index_b: usize,
+    u: Vector2<T>,
+    r_a: Vector2<T>,
+    r_b: Vector2<T>,
+    local_center_a: Vector2<T>,
+    local_center_b: Vector2<T>,
+    inv_mass_a: T,
+    inv_mass_b: T,
+    inv_i_a: T,
+    inv_i_b: T,
+    gamma: T,
+    bias: T,
+    mass: T,
+}
+
+impl<T: Real> DistanceJoint<T> {
+    pub(crate) fn new(base: JointBase<T>, def: DistanceJointDef<T>) -> DistanceJoint<T> {
+        DistanceJoint {
+            base,
+            local_anchor_a: def.local_anchor_a,
+            local_anchor_b: def.local_anchor_b,
+            length: def.length,
+            frequency_hz: def.frequency_hz,
+            damping_ratio: def.damping_ratio,
+            impulse: T::zero(),
+            index_a: 0,
+            index_b: 0,
+            u: Vector2::zero(),
+            r_a: Vector2::zero(),
+            r_b: Vector2::zero(),
+            local_center_a: Vector2::zero(),
+            local_center_b: Vector2::zero(),
+            inv_mass_a: T::zero(),
+            inv_mass_b: T::zero(),
+            inv_i_a: T::zero(),
+            inv_i_b: T::zero(),
+            gamma: T::zero(),
+            bias: T::zero(),
+            mass: T::zero(),
+        }
+    }
+
+    pub fn local_anchor_a(&self) -> &Vector2<T> {
+        &self.local_anchor_a
+    }
+
+    pub fn local_anchor_b(&self) -> &Vector2<T> {
+        &self.local_anchor_b
+    }
+
+    /// 用关节当前的参数生成定义
+    pub fn def(&self) -> DistanceJointDef<T> {
+        DistanceJointDef {
+            body_a: self.base.body_a,
+            body_b: self.base.body_b,
+            collide_connected: self.base.collide_connected,
+            local_anchor_a: self.local_anchor_a,
+            local_anchor_b: self.local_anchor_b,
+            length: self.length,
+            frequency_hz: self.frequency_hz,
+            damping_ratio: self.damping_ratio,
+        }
+    }
+
+    pub(crate) fn anchor_a<D>(&self, bodies: &Slab<Box<Body<T, D>>>) -> Vector2<T> {
+        bodies[self.base.body_a.index].world_point(self.local_anchor_a)
+    }
+
+    pub(crate) fn anchor_b<D>(&self, bodies: &Slab<Box<Body<T, D>>>) -> Vector2<T> {
+        bodies[self.base.body_b.index].world_point(self.local_anchor_b)
+    }
+
+    pub fn reaction_force(&self, inv_dt: T) -> Vector2<T> {
+        self.u * (self.impulse * inv_dt)
+    }
+
+    pub fn reaction_torque(&self, _inv_dt: T) -> T {
+        T::zero()
+    }
+
+    pub fn length(&self) -> T {
+        self.length
+    }
+
+    pub fn set_length(&mut self, length: T) {
+        self.length = length;
+    }
+
+    pub fn frequency(&self) -> T {
+        self.frequency_hz
+    }
+
+    pub fn set_frequency(&mut self, hz: T) {
+        self.frequency_hz = hz;
+    }
+
+    pub fn damping_ratio(&self) -> T {
+        self.damping_ratio
+    }
+
+    pub fn set_damping_ratio(&mut self, ratio: T) {
+        self.damping_ratio = ratio;
+    }
+
+    pub(crate) fn init_velocity_constraints<D>(
+        &mut self,
+        bodies: &Slab<Box<Body<T, D>>>,
+        data: &mut SolverData<T>,
+    ) {
+        let body_a = &bodies[self.base.body_a.index];
+        let body_b = &bodies[self.base.body_b.index];
+        self.index_a = body_a.island_index;
+        self.index_b = body_b.island_index;
+        self.local_center_a = body_a.sweep.local_center;
+        self.local_center_b = body_b.sweep.local_center;
+        self.inv_mass_a = body_a.inv_mass;
+        self.inv_mass_b = body_b.inv_mass;
+        self.inv_i_a = body_a.inv_i;
+        self.inv_i_b = body_b.inv_i;
+
+        let c_a = data.positions[self.index_a].c;
+        let a_a = data.positions[self.index_a].a;
+        let mut v_a = data.velocities[self.index_a].v;
+        let mut w_a = data.velocities[self.index_a].w;
+
+        let c_b = data.positions[self.index_b].c;
+        let a_b = data.positions[self.index_b].a;
+        let mut v_b = data.velocities[self.index_b].v;
+        let mut w_b = data.velocities[self.index_b].w;
+
+        let q_a = Rotation::new(a_a);
+        let q_b = Rotation::new(a_b);
+
+        self.r_a = q_a.multiply(self.local_anchor_a - self.local_center_a);
+        self.r_b = q_b.multiply(self.local_anchor_b - self.local_center_b);
+        self.u = c_b + self.r_b - c_a - self.r_a;
+
+        let length = self.u.length();
+        if length > settings::linear_slop() {
+            self.u *= T::one() / length;
+        } else {
+            self.u = Vector2::zero();
+        }
+
+        let (m_a, m_b) = (self.inv_mass_a, self.inv_mass_b);
+        let (i_a, i_b) = (self.inv_i_a, self.inv_i_b);
+
+        let cr_au = self.r_a.cross(self.u);
+        let cr_bu = self.r_b.cross(self.u);
+        let mut inv_mass = m_a + i_a * cr_au * cr_au + m_b + i_b * cr_bu * cr_bu;
+        self.mass = if inv_mass != T::zero() {
+            T::one() / inv_mass
+        } else {
+            T::zero()
+        };
+
+        if self.frequency_hz > T::zero() {
+            let c = length - self.length;
+
+            let omega = T::pi_times_2() * self.frequency_hz;
+            let d = T::two() * self.mass * self.damping_ratio * omega;
+            let k = self.mass * omega * omega;
+
+            // 软约束
+            let h = data.step.dt;
+            self.gamma = h * (d + h * k);
+            self.gamma = if self.gamma != T::zero() {
+                T::one() / self.gamma
+            } else {
+                T::zero()
+            };
+            self.bias = c * h * k * self.gamma;
+
+            inv_mass += self.gamma;
+            self.mass = if inv_mass != T::zero() {
+                T::one() / inv_mass
+            } else {
+                T::zero()
+            };
+        } else {
+            self.gamma = T::zero();
+            self.bias = T::zero();
+        }
+
+        if data.step.warm_starting {
+            self.impulse *= data.step.dt_ratio;
+
+            let p = self.u * self.impulse;
+            v_a -= p * m_a;
+            w_a -= i_a * self.r_a.cross(p);
+            v_b += p * m_b;
+            w_b += i_b * self.r_b.cross(p);
+        } else {
+            self.impulse = T::zero();
+        }
+
+        data.velocities[self.index_a].v = v_a;
+        data.velocities[self.index_a].w = w_a;
+        data.velocities[self.index_b].v = v_b;
+        data.velocities[self.index_b].w = w_b;
+    }
+
+    pub(crate) fn solve_velocity_constraints(&mut self, data: &mut SolverData<T>) {
+        let mut v_a = data.velocities[self.index_a].v;
+        let mut w_a = data.velocities[self.index_a].w;
+        let mut v_b = data.velocities[self.index_b].v;
+        let mut w_b = data.velocities[self.index_b].w;
+
+        let vp_a = v_a + w_a.cross(self.r_a);
+        let vp_b = v_b + w_b.cross(self.r_b);
+        let cdot = self.u.dot(vp_b - vp_a);
+
+        let impulse = -self.mass * (cdot + self.bias + self.gamma * self.impulse);
+        self.impulse += impulse;
+
+        let p = self.u * impulse;
+        v_a -= p * self.inv_mass_a;
+        w_a -= self.inv_i_a * self.r_a.cross(p);
+        v_b += p * self.inv_mass_b;
+        w_b += self.inv_i_b * self.r_b.cross(p);
+
+        data.velocities[self.index_a].v = v_a;
+        data.velocities[self.index_a].w = w_a;
+        data.velocities[self.index_b].v = v_b;
+        data.velocities[self.index_b].w = w_b;
+    }
+
+    pub(crate) fn solve_position_constraints(&mut self, data: &mut SolverData<T>) -> bool {
+        if self.frequency_hz > T::zero() {
+            // 弹簧不修正位置
+            return true;
+        }
+
+        let mut c_a = data.positions[self.index_a].c;
+        let mut a_a = data.positions[self.index_a].a;
+        let mut c_b = data.positions[self.index_b].c;
+        let mut a_b = data.positions[self.index_b].a;
+
+        let q_a = Rotation::new(a_a);
+        let q_b = Rotation::new(a_b);
+
+        let r_a = q_a.multiply(self.local_anchor_a - self.local_center_a);
+        let r_b = q_b.multiply(self.local_anchor_b - self.local_center_b);
+        let d = c_b + r_b - c_a - r_a;
+
+        let length = d.length();
+        let u = if length > T::epsilon() {
+            d * (T::one() / length)
+        } else {
+            Vector2::zero()
+        };
+        let max_correction = settings::max_linear_correction::<T>();
+        let c = (length - self.length).clamp(-max_correction, max_correction);
+
+        let impulse = -self.mass * c;
+        let p = u * impulse;
+
+        c_a -= p * self.inv_mass_a;
+        a_a -= self.inv_i_a * r_a.cross(p);
+        c_b += p * self.inv_mass_b;
+        a_b += self.inv_i_b * r_b.cross(p);
+
+        data.positions[self.index_a].c = c_a;
+        data.positions[self.index_a].a = a_a;
+        data.positions[self.index_b].c = c_b;
+        data.positions[self.index_b].a = a_b;
+
+        c.abs() < settings::linear_slop()
+    }
+}
@@ -0,0 +1,431 @@
+use crate::dynamic::joints::{Joint, JointBase, JointId, JointType};
+use crate::dynamic::time_step::SolverData;
+use crate::{Body, BodyId};
+use slab::Slab;
+use xmath::{CrossTrait, DotTrait, Multiply, Real, Rotation, TransposeMultiply, Vector2};
+
+/// 齿轮关节的参数，按比例联动两个旋转关节或者平移关节
+///
+/// `坐标1 + ratio * 坐标2 = 常数`，坐标是旋转关节的角度或者平移关节的位移。
+/// 两个关节的刚体A通常是静态的，齿轮关节连接两个关节的刚体B。
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GearJointDef<T> {
+    pub joint1: JointId,
+    pub joint2: JointId,
+    pub ratio: T,
+    pub collide_connected: bool,
+}
+
+impl<T: Real> GearJointDef<T> {
+    pub fn new(joint1: JointId, joint2: JointId, ratio: T) -> GearJointDef<T> {
+        GearJointDef {
+            joint1,
+            joint2,
+            ratio,
+            collide_connected: false,
+        }
+    }
+}
+
+/// 被联动的关节一侧的数据
+struct Side<T> {
+    type_: JointType,
+
+    /// 关节的刚体A
+    body: BodyId,
+    local_anchor: Vector2<T>,
+    local_axis: Vector2<T>,
+    reference_angle: T,
+
+    /// 关节的刚体B上的锚点
+    local_anchor_joint: Vector2<T>,
+}
+
+impl<T: Real> Side<T> {
+    fn new(joint: &Joint<T>) -> Side<T> {
+        let body = joint.base().body_a;
+        match joint {
+            Joint::Revolute(joint) => Side {
+                type_: JointType::Revolute,
+                body,
+                local_anchor: *joint.local_anchor_a(),
+                local_axis: Vector2::zero(),
+                reference_angle: joint.reference_angle(),
+                local_anchor_joint: *joint.local_anchor_b(),
+            },
+            Joint::Prismatic(joint) => Side {
+                type_: JointType::Prismatic,
+                body,
+                local_anchor: *joint.local_anchor_a(),
+                local_axis: *joint.local_axis_a(),
+                reference_angle: joint.reference_angle(),
+                local_anchor_joint: *joint.local_anchor_b(),
+            },
+            _ => panic!("gear joints only couple revolute and prismatic joints"),
+        }
+    }
+
+    /// 关节的坐标，`c`、`a`是刚体B的位置和角度，`c0`、`a0`是刚体A的
+    fn coordinate(&self, c: Vector2<T>, a: T, c0: Vector2<T>, a0: T, center0: Vector2<T>) -> T {
+        match self.type_ {
+            JointType::Revolute => a - a0 - self.reference_angle,
+            _ => {
+                let q = Rotation::new(a);
+                let q0 = Rotation::new(a0);
+                let p0 = self.local_anchor - center0;
+                let p = q0.transpose_multiply(q.multiply(self.local_anchor_joint) + (c - c0));
+                (p - p0).dot(self.local_axis)
+            }
+        }
+    }
+}
+
+pub struct GearJoint<T> {
+    pub(crate) base: JointBase<T>,
+    joint1: JointId,
+    joint2: JointId,
+    side_a: Side<T>,
+    side_b: Side<T>,
+    ratio: T,
+    constant: T,
+    impulse: T,
+
+    // 求解时的临时数据，C、D是两个关节的刚体A
+    index_a: usize,
+    index_b: usize,
+    index_c: usize,
+    index_d: usize,
+    lc_a: Vector2<T>,
+    lc_b: Vector2<T>,
+    lc_c: Vector2<T>,
+    lc_d: Vector2<T>,
+    m_a: T,
+    m_b: T,
+    m_c: T,
+    m_d: T,
+    i_a: T,
+    i_b: T,
+    i_c: T,
+    i_d: T,
+    jv_ac: Vector2<T>,
+    jv_bd: Vector2<T>,
+    jw_a: T,
+    jw_b: T,
+    jw_c: T,
+    jw_d: T,
+    mass: T,
+}
+
+/// 一侧关节的雅可比和有效质量的倒数
+struct Jacobian<T> {
+    jv: Vector2<T>,
+    jw: T,
+    jw0: T,
+    mass: T,
+}
+
+impl<T: Real> GearJoint<T> {
+    pub(crate) fn new<D>(
+        base: JointBase<T>,
+        def: GearJointDef<T>,
+        joint1: &Joint<T>,
+        joint2: &Joint<T>,
+        bodies: &Slab<Box<Body<T, D>>>,
+    ) -> GearJoint<T> {
+        let side_a = Side::new(joint1);
+        let side_b = Side::new(joint2);
+
+        let coordinate = |side: &Side<T>, body: BodyId| {
+            let body = &bodies[body.index];
+            let body0 = &bodies[side.body.index];
+            match side.type_ {
+                JointType::Revolute => side.coordinate(
+                    Vector2::zero(),
+                    body.sweep.a,
+                    Vector2::zero(),
+                    body0.sweep.a,
+                    Vector2::zero(),
+                ),
+                _ => side.coordinate(
+                    body.xf.p,
+                    body.sweep.a,
+                    body0.xf.p,
+                    body0.sweep.a,
+                    Vector2::zero(),
+                ),
+            }
+        };
+        let coordinate_a = coordinate(&side_a, base.body_a);
+        let coordinate_b = coordinate(&side_b, base.body_b);
+
+        GearJoint {
+            joint1: def.joint1,
+            joint2: def.joint2,
+            constant: coordinate_a + def.ratio * coordinate_b,
+            base,
+            side_a,
+            side_b,
+            ratio: def.ratio,
+            impulse: T::zero(),
+            index_a: 0,
+            index_b: 0,
+            index_c: 0,
+            index_d: 0,
+            lc_a: Vector2::zero(),
+            lc_b: Vector2::zero(),
+            lc_c: Vector2::zero(),
+            lc_d: Vector2::zero(),
+            m_a: T::zero(),
+            m_b: T::zero(),
+            m_c: T::zero(),
+            m_d: T::zero(),
+            i_a: T::zero(),
+            i_b: T::zero(),
+            i_c: T::zero(),
+            i_d: T::zero(),
+            jv_ac: Vector2::zero(),
+            jv_bd: Vector2::zero(),
+            jw_a: T::zero(),
+            jw_b: T::zero(),
+            jw_c: T::zero(),
+            jw_d: T::zero(),
+            mass: T::zero(),
+        }
+    }
+
+    pub fn joint1(&self) -> JointId {
+        self.joint1
+    }
+
+    pub fn joint2(&self) -> JointId {
+        self.joint2
+    }
+
+    pub fn ratio(&self) -> T {
+        self.ratio
+    }
+
+    pub fn set_ratio(&mut self, ratio: T) {
+        assert!(ratio.is_valid());
+        self.ratio = ratio;
+    }
+
+    /// 用关节当前的参数生成定义
+    pub fn def(&self) -> GearJointDef<T> {
+        GearJointDef {
+            joint1: self.joint1,
+            joint2: self.joint2,
+            ratio: self.ratio,
+            collide_connected: self.base.collide_connected,
+        }
+    }
+
+    pub(crate) fn anchor_a<D>(&self, bodies: &Slab<Box<Body<T, D>>>) -> Vector2<T> {
+        bodies[self.base.body_a.index].world_point(self.side_a.local_anchor_joint)
+    }
+
+    pub(crate) fn anchor_b<D>(&self, bodies: &Slab<Box<Body<T, D>>>) -> Vector2<T> {
+        bodies[self.base.body_b.index].world_point(self.side_b.local_anchor_joint)
+    }
+
+    pub fn reaction_force(&self, inv_dt: T) -> Vector2<T> {
+        self.jv_ac * (self.impulse * inv_dt)
+    }
+
+    pub fn reaction_torque(&self, inv_dt: T) -> T {
+        inv_dt * self.impulse * self.jw_a
+    }
+
+    /// 一侧关节的雅可比，`q`、`lc`是刚体B的，`q0`、`lc0`是刚体A的
+    #[allow(clippy::too_many_arguments)]
+    fn jacobian(
+        side: &Side<T>,
+        q: Rotation<T>,
+        lc: Vector2<T>,
+        m: T,
+        i: T,
+        q0: Rotation<T>,
+        lc0: Vector2<T>,
+        m0: T,
+        i0: T,
+    ) -> Jacobian<T> {
+        match side.type_ {
+            JointType::Revolute => Jacobian {
+                jv: Vector2::zero(),
+                jw: T::one(),
+                jw0: T::one(),
+                mass: i + i0,
+            },
+            _ => {
+                let u = q0.multiply(side.local_axis);
+                let r0 = q0.multiply(side.local_anchor - lc0);
+                let r = q.multiply(side.local_anchor_joint - lc);
+                let jw0 = r0.cross(u);
+                let jw = r.cross(u);
+                Jacobian {
+                    jv: u,
+                    jw,
+                    jw0,
+                    mass: m0 + m + i0 * jw0 * jw0 + i * jw * jw,
+                }
+            }
+        }
+    }
+
+    fn solve_jacobian(&mut self, a_a: T, a_b: T, a_c: T, a_d: T) {
+        let ja = Self::jacobian(
+            &self.side_a,
+            Rotation::new(a_a),
+            self.lc_a,
+            self.m_a,
+            self.i_a,
+            Rotation::new(a_c),
+            self.lc_c,
+            self.m_c,
+            self.i_c,
+        );
+        let jb = Self::jacobian(
+            &self.side_b,
+            Rotation::new(a_b),
+            self.lc_b,
+            self.m_b,
+            self.i_b,
+            Rotation::new(a_d),
+            self.lc_d,
+            self.m_d,
+            self.i_d,
+        );
+
+        self.jv_ac = ja.jv;
+        self.jw_a = ja.jw;
+        self.jw_c = ja.jw0;
+
+        self.jv_bd = jb.jv * self.ratio;
+        self.jw_b = self.ratio * jb.jw;
+        self.jw_d = self.ratio * jb.jw0;
+
+        let mass = ja.mass + self.ratio * self.ratio * jb.mass;
+        self.mass = if mass > T::zero() {
+            T::one() / mass
+        } else {
+            T::zero()
+        };
+    }
+
+    pub(crate) fn init_velocity_constraints<D>(
+        &mut self,
+        bodies: &Slab<Box<Body<T, D>>>,
+        data: &mut SolverData<T>,
+    ) {
+        let [a, b, c, d] = [
+            self.base.body_a,
+            self.base.body_b,
+            self.side_a.body,
+            self.side_b.body,
+        ]
+        .map(|id| &bodies[id.index]);
+        self.index_a = a.island_index;
+        self.index_b = b.island_index;
+        self.index_c = c.island_index;
+        self.index_d = d.island_index;
+        self.lc_a = a.sweep.local_center;
+        self.lc_b = b.sweep.local_center;
+        self.lc_c = c.sweep.local_center;
+        self.lc_d = d.sweep.local_center;
+        self.m_a = a.inv_mass;
+        self.m_b = b.inv_mass;
+        self.m_c = c.inv_mass;
+        self.m_d = d.inv_mass;
+        self.i_a = a.inv_i;
+        self.i_b = b.inv_i;
+        self.i_c = c.inv_i;
+        self.i_d = d.inv_i;
+
+        self.solve_jacobian(
+            data.positions[self.index_a].a,
+            data.positions[self.index_b].a,
+            data.positions[self.index_c].a,
+            data.positions[self.index_d].a,
+        );
+
+        if data.step.warm_starting {
+            self.apply_impulse(data, self.impulse);
+        } else {
+            self.impulse = T::zero();
+        }
+    }
+
+    fn apply_impulse(&self, data: &mut SolverData<T>, impulse: T) {
+        let v = &mut data.velocities;
+        v[self.index_a].v += self.jv_ac * (self.m_a * impulse);
+        v[self.index_a].w += self.i_a * impulse * self.jw_a;
+        v[self.index_b].v += self.jv_bd * (self.m_b * impulse);
+        v[self.index_b].w += self.i_b * impulse * self.jw_b;
+        v[self.index_c].v -= self.jv_ac * (self.m_c * impulse);
+        v[self.index_c].w -= self.i_c * impulse * self.jw_c;
+        v[self.index_d].v -= self.jv_bd * (self.m_d * impulse);
+        v[self.index_d].w -= self.i_d * impulse * self.jw_d;
+    }
+
+    pub(crate) fn solve_velocity_constraints(&mut self, data: &mut SolverData<T>) {
+        let v = &data.velocities;
+        let (v_a, w_a) = (v[self.index_a].v, v[self.index_a].w);
+        let (v_b, w_b) = (v[self.index_b].v, v[self.index_b].w);
+        let (v_c, w_c) = (v[self.index_c].v, v[self.index_c].w);
+        let (v_d, w_d) = (v[self.index_d].v, v[self.index_d].w);
+
+        let cdot = self.jv_ac.dot(v_a - v_c)
+            + self.jv_bd.dot(v_b - v_d)
+            + (self.jw_a * w_a - self.jw_c * w_c)
+            + (self.jw_b * w_b - self.jw_d * w_d);
+
+        let impulse = -self.mass * cdot;
+        self.impulse += impulse;
+        self.apply_impulse(data, impulse);
+    }
+
+    pub(crate) fn solve_position_constraints(&mut self, data: &mut SolverData<T>) -> bool {
+        let p = &data.positions;
+        let (c_a, a_a) = (p[self.index_a].c, p[self.index_a].a);
+        let (c_b, a_b) = (p[self.index_b].c, p[self.index_b].a);
+        let (c_c, a_c) = (p[self.index_c].c, p[self.index_c].a);
+        let (c_d, a_d) = (p[self.index_d].c, p[self.index_d].a);
+
+        let coordinate_a = self.side_a.coordinate(
+            c_a - Rotation::new(a_a).multiply(self.lc_a),
+            a_a,
+            c_c,
+            a_c,
+            self.lc_c,
+        );
+        let coordinate_b = self.side_b.coordinate(
+            c_b - Rotation::new(a_b).multiply(self.lc_b),
+            a_b,
+            c_d,
+            a_d,
+            self.lc_d,
+        );
+
+        self.solve_jacobian(a_a, a_b, a_c, a_d);
+
+        let c = (coordinate_a + self.ratio * coordinate_b) - self.constant;
+        let impulse = if self.mass > T::zero() {
+            -c * self.mass
+        } else {
+            T::zero()
+        };
+
+        let p = &mut data.positions;
+        p[self.index_a].c += self.jv_ac * (self.m_a * impulse);
+        p[self.index_a].a += self.i_a * impulse * self.jw_a;
+        p[self.index_b].c += self.jv_bd * (self.m_b * impulse);
+        p[self.index_b].a += self.i_b * impulse * self.jw_b;
+        p[self.index_c].c -= self.jv_ac * (self.m_c * impulse);
+        p[self.index_c].a -= self.i_c * impulse * self.jw_c;
+        p[self.index_d].c -= self.jv_bd * (self.m_d * impulse);
+        p[self.index_d].a -= self.i_d * impulse * self.jw_d;
+
+        // 和Box2D一样，齿轮关节不参与位置收敛的判断
+        true
+    }
+}
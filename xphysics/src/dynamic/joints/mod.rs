@@ -0,0 +1,303 @@
+mod distance_joint;
+mod gear_joint;
+mod motor_joint;
+mod prismatic_joint;
+mod revolute_joint;
+mod wheel_joint;
+
+pub use distance_joint::{DistanceJoint, DistanceJointDef};
+pub use gear_joint::{GearJoint, GearJointDef};
+pub use motor_joint::{MotorJoint, MotorJointDef};
+pub use prismatic_joint::{PrismaticJoint, PrismaticJointDef};
+pub use revolute_joint::{RevoluteJoint, RevoluteJointDef};
+pub use wheel_joint::{WheelJoint, WheelJointDef};
+
+use crate::dynamic::time_step::SolverData;
+use crate::{Body, BodyId, World};
+use slab::Slab;
+use xmath::{Real, Vector2};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JointId(pub(crate) usize);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum JointType {
+    Revolute,
+    Prismatic,
+    Distance,
+    Gear,
+    Motor,
+    Wheel,
+}
+
+/// 关节受力超过阈值被破坏，见`DestructionListener::joint_broken`
+#[derive(Debug, Copy, Clone)]
+pub struct JointBroken<T> {
+    pub joint: JointId,
+    pub joint_type: JointType,
+    pub body_a: BodyId,
+    pub body_b: BodyId,
+    pub reaction_force: Vector2<T>,
+    pub reaction_torque: T,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum LimitState {
+    Inactive,
+    AtLower,
+    AtUpper,
+    Equal,
+}
+
+/// 创建关节的参数，由各种关节的定义转换而来
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum JointDef<T> {
+    Revolute(RevoluteJointDef<T>),
+    Prismatic(PrismaticJointDef<T>),
+    Distance(DistanceJointDef<T>),
+    Gear(GearJointDef<T>),
+    Motor(MotorJointDef<T>),
+    Wheel(WheelJointDef<T>),
+}
+
+impl<T> From<RevoluteJointDef<T>> for JointDef<T> {
+    fn from(def: RevoluteJointDef<T>) -> Self {
+        JointDef::Revolute(def)
+    }
+}
+
+impl<T> From<PrismaticJointDef<T>> for JointDef<T> {
+    fn from(def: PrismaticJointDef<T>) -> Self {
+        JointDef::Prismatic(def)
+    }
+}
+
+impl<T> From<DistanceJointDef<T>> for JointDef<T> {
+    fn from(def: DistanceJointDef<T>) -> Self {
+        JointDef::Distance(def)
+    }
+}
+
+impl<T> From<GearJointDef<T>> for JointDef<T> {
+    fn from(def: GearJointDef<T>) -> Self {
+        JointDef::Gear(def)
+    }
+}
+
+impl<T> From<MotorJointDef<T>> for JointDef<T> {
+    fn from(def: MotorJointDef<T>) -> Self {
+        JointDef::Motor(def)
+    }
+}
+
+impl<T> From<WheelJointDef<T>> for JointDef<T> {
+    fn from(def: WheelJointDef<T>) -> Self {
+        JointDef::Wheel(def)
+    }
+}
+
+impl<T: Real> JointDef<T> {
+    pub(crate) fn bodies(&self, joints: &Slab<Box<Joint<T>>>) -> (BodyId, BodyId) {
+        match self {
+            JointDef::Revolute(def) => (def.body_a, def.body_b),
+            JointDef::Prismatic(def) => (def.body_a, def.body_b),
+            JointDef::Distance(def) => (def.body_a, def.body_b),
+            JointDef::Gear(def) => {
+                let joint1 = joints.get(def.joint1.0).expect("invalid joint 1");
+                let joint2 = joints.get(def.joint2.0).expect("invalid joint 2");
+                (joint1.body_b(), joint2.body_b())
+            }
+            JointDef::Motor(def) => (def.body_a, def.body_b),
+            JointDef::Wheel(def) => (def.body_a, def.body_b),
+        }
+    }
+
+    pub(crate) fn collide_connected(&self) -> bool {
+        match self {
+            JointDef::Revolute(def) => def.collide_connected,
+            JointDef::Prismatic(def) => def.collide_connected,
+            JointDef::Distance(def) => def.collide_connected,
+            JointDef::Gear(def) => def.collide_connected,
+            JointDef::Motor(def) => def.collide_connected,
+            JointDef::Wheel(def) => def.collide_connected,
+        }
+    }
+}
+
+pub(crate) struct JointBase<T> {
+    pub(crate) body_a: BodyId,
+    pub(crate) body_b: BodyId,
+    pub(crate) collide_connected: bool,
+    pub(crate) island: bool,
+    pub(crate) break_force: Option<T>,
+    pub(crate) break_torque: Option<T>,
+
+    /// 参数修改后要唤醒两个刚体，在下一次步进开始时处理
+    pub(crate) wake: bool,
+}
+
+impl<T: Real> JointBase<T> {
+    pub(crate) fn wake_bodies(&mut self) {
+        self.wake = true;
+    }
+}
+
+pub enum Joint<T> {
+    Revolute(RevoluteJoint<T>),
+    Prismatic(PrismaticJoint<T>),
+    Distance(DistanceJoint<T>),
+    Gear(GearJoint<T>),
+    Motor(MotorJoint<T>),
+    Wheel(WheelJoint<T>),
+}
+
+macro_rules! dispatch {
+    ($joint:expr, $j:ident => $e:expr) => {
+        match $joint {
+            Joint::Revolute($j) => $e,
+            Joint::Prismatic($j) => $e,
+            Joint::Distance($j) => $e,
+            Joint::Gear($j) => $e,
+            Joint::Motor($j) => $e,
+            Joint::Wheel($j) => $e,
+        }
+    };
+}
+
+impl<T: Real> Joint<T> {
+    pub(crate) fn new<D>(
+        base: JointBase<T>,
+        def: JointDef<T>,
+        joints: &Slab<Box<Joint<T>>>,
+        bodies: &Slab<Box<Body<T, D>>>,
+    ) -> Joint<T> {
+        match def {
+            JointDef::Revolute(def) => Joint::Revolute(RevoluteJoint::new(base, def)),
+            JointDef::Prismatic(def) => Joint::Prismatic(PrismaticJoint::new(base, def)),
+            JointDef::Distance(def) => Joint::Distance(DistanceJoint::new(base, def)),
+            JointDef::Gear(def) => {
+                let joint1 = &joints[def.joint1.0];
+                let joint2 = &joints[def.joint2.0];
+                Joint::Gear(GearJoint::new(base, def, joint1, joint2, bodies))
+            }
+            JointDef::Motor(def) => Joint::Motor(MotorJoint::new(base, def)),
+            JointDef::Wheel(def) => Joint::Wheel(WheelJoint::new(base, def)),
+        }
+    }
+
+    pub(crate) fn base(&self) -> &JointBase<T> {
+        dispatch!(self, j => &j.base)
+    }
+
+    pub(crate) fn base_mut(&mut self) -> &mut JointBase<T> {
+        dispatch!(self, j => &mut j.base)
+    }
+
+    pub fn joint_type(&self) -> JointType {
+        match self {
+            Joint::Revolute(_) => JointType::Revolute,
+            Joint::Prismatic(_) => JointType::Prismatic,
+            Joint::Distance(_) => JointType::Distance,
+            Joint::Gear(_) => JointType::Gear,
+            Joint::Motor(_) => JointType::Motor,
+            Joint::Wheel(_) => JointType::Wheel,
+        }
+    }
+
+    pub fn body_a(&self) -> BodyId {
+        self.base().body_a
+    }
+
+    pub fn body_b(&self) -> BodyId {
+        self.base().body_b
+    }
+
+    /// 连接的两个刚体之间是否碰撞
+    /// 用关节当前的参数生成定义，可以用来重新创建这个关节
+    pub fn def(&self) -> JointDef<T> {
+        dispatch!(self, j => j.def().into())
+    }
+
+    pub fn collide_connected(&self) -> bool {
+        self.base().collide_connected
+    }
+
+    /// 破坏关节的反作用力阈值，`None`表示不会被破坏
+    pub fn break_force(&self) -> Option<T> {
+        self.base().break_force
+    }
+
+    pub fn set_break_force(&mut self, force: Option<T>) {
+        self.base_mut().break_force = force;
+    }
+
+    /// 破坏关节的反作用扭矩阈值，`None`表示不会被破坏
+    pub fn break_torque(&self) -> Option<T> {
+        self.base().break_torque
+    }
+
+    pub fn set_break_torque(&mut self, torque: Option<T>) {
+        self.base_mut().break_torque = torque;
+    }
+
+    /// 刚体A上锚点的世界坐标
+    pub fn anchor_a<D>(&self, world: &World<T, D>) -> Vector2<T> {
+        self.anchor_a_in(&world.0.bodies_slab)
+    }
+
+    /// 刚体B上锚点的世界坐标
+    pub fn anchor_b<D>(&self, world: &World<T, D>) -> Vector2<T> {
+        self.anchor_b_in(&world.0.bodies_slab)
+    }
+
+    pub(crate) fn anchor_a_in<D>(&self, bodies: &Slab<Box<Body<T, D>>>) -> Vector2<T> {
+        dispatch!(self, j => j.anchor_a(bodies))
+    }
+
+    pub(crate) fn anchor_b_in<D>(&self, bodies: &Slab<Box<Body<T, D>>>) -> Vector2<T> {
+        dispatch!(self, j => j.anchor_b(bodies))
+    }
+
+    /// 上一步中关节作用在刚体B的锚点上的力，`inv_dt`是时间步长的倒数
+    pub fn reaction_force(&self, inv_dt: T) -> Vector2<T> {
+        dispatch!(self, j => j.reaction_force(inv_dt))
+    }
+
+    /// 上一步中关节作用在刚体B上的扭矩，`inv_dt`是时间步长的倒数
+    pub fn reaction_torque(&self, inv_dt: T) -> T {
+        dispatch!(self, j => j.reaction_torque(inv_dt))
+    }
+
+    /// 反作用力或者扭矩超过阈值时返回`true`
+    pub(crate) fn should_break(&self, inv_dt: T) -> bool {
+        let base = self.base();
+        if let Some(force) = base.break_force {
+            if self.reaction_force(inv_dt).length_squared() > force * force {
+                return true;
+            }
+        }
+        if let Some(torque) = base.break_torque {
+            if self.reaction_torque(inv_dt).abs() > torque {
+                return true;
+            }
+        }
+        false
+    }
+
+    pub(crate) fn init_velocity_constraints<D>(
+        &mut self,
+        bodies: &Slab<Box<Body<T, D>>>,
+        data: &mut SolverData<T>,
+    ) {
+        dispatch!(self, j => j.init_velocity_constraints(bodies, data))
+    }
+
+    pub(crate) fn solve_velocity_constraints(&mut self, data: &mut SolverData<T>) {
+        dispatch!(self, j => j.solve_velocity_constraints(data))
+    }
+
+    pub(crate) fn solve_position_constraints(&mut self, data: &mut SolverData<T>) -> bool {
+        dispatch!(self, j => j.solve_position_constraints(data))
+    }
+}
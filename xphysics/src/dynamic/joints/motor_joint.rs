@@ -0,0 +1,316 @@
+use crate::dynamic::joints::JointBase;
+use crate::dynamic::time_step::SolverData;
+use crate::math::Matrix22;
+use crate::{Body, BodyId, World};
+use slab::Slab;
+use xmath::{CrossTrait, Multiply, Real, Rotation, Vector2};
+
+/// 马达关节的参数，用有限的力和扭矩把刚体B驱动到相对刚体A的目标位置和角度
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MotorJointDef<T> {
+    pub body_a: BodyId,
+    pub body_b: BodyId,
+    pub collide_connected: bool,
+
+    /// 刚体B的位置在刚体A坐标系中的目标值
+    pub linear_offset: Vector2<T>,
+
+    /// 刚体B相对刚体A的目标角度
+    pub angular_offset: T,
+    pub max_force: T,
+    pub max_torque: T,
+
+    /// 位置修正系数，范围是[0,1]
+    pub correction_factor: T,
+}
+
+impl<T: Real> MotorJointDef<T> {
+    pub fn new(body_a: BodyId, body_b: BodyId) -> MotorJointDef<T> {
+        MotorJointDef {
+            body_a,
+            body_b,
+            collide_connected: false,
+            linear_offset: Vector2::zero(),
+            angular_offset: T::zero(),
+            max_force: T::one(),
+            max_torque: T::one(),
+            correction_factor: T::f32(0.3),
+        }
+    }
+
+    /// 目标位置和角度为两个刚体当前的相对位置和角度
+    pub fn with_bodies<D>(world: &World<T, D>, body_a: BodyId, body_b: BodyId) -> MotorJointDef<T> {
+        let a = world.body(body_a).expect("invalid body a");
+        let b = world.body(body_b).expect("invalid body b");
+        MotorJointDef {
+            linear_offset: a.local_point(*b.position()),
+            angular_offset: b.angle() - a.angle(),
+            ..MotorJointDef::new(body_a, body_b)
+        }
+    }
+}
+
+pub struct MotorJoint<T> {
+    pub(crate) base: JointBase<T>,
+    linear_offset: Vector2<T>,
+    angular_offset: T,
+    linear_impulse: Vector2<T>,
+    angular_impulse: T,
+    max_force: T,
+    max_torque: T,
+    correction_factor: T,
+
+    // 求解时的临时数据
+    index_a: usize,
+    index_b: usize,
+    r_a: Vector2<T>,
+    r_b: Vector2<T>,
+    local_center_a: Vector2<T>,
+    local_center_b: Vector2<T>,
+    linear_error: Vector2<T>,
+    angular_error: T,
+    inv_mass_a: T,
+    inv_mass_b: T,
+    inv_i_a: T,
+    inv_i_b: T,
+    linear_mass: Matrix22<T>,
+    angular_mass: T,
+}
+
+impl<T: Real> MotorJoint<T> {
+    pub(crate) fn new(base: JointBase<T>, def: MotorJointDef<T>) -> MotorJoint<T> {
+        MotorJoint {
+            base,
+            linear_offset: def.linear_offset,
+            angular_offset: def.angular_offset,
+            linear_impulse: Vector2::zero(),
+            angular_impulse: T::zero(),
+            max_force: def.max_force,
+            max_torque: def.max_torque,
+            correction_factor: def.correction_factor,
+            index_a: 0,
+            index_b: 0,
+            r_a: Vector2::zero(),
+            r_b: Vector2::zero(),
+            local_center_a: Vector2::zero(),
+            local_center_b: Vector2::zero(),
+            linear_error: Vector2::zero(),
+            angular_error: T::zero(),
+            inv_mass_a: T::zero(),
+            inv_mass_b: T::zero(),
+            inv_i_a: T::zero(),
+            inv_i_b: T::zero(),
+            linear_mass: Matrix22::zero(),
+            angular_mass: T::zero(),
+        }
+    }
+
+    /// 用关节当前的参数生成定义
+    pub fn def(&self) -> MotorJointDef<T> {
+        MotorJointDef {
+            body_a: self.base.body_a,
+            body_b: self.base.body_b,
+            collide_connected: self.base.collide_connected,
+            linear_offset: self.linear_offset,
+            angular_offset: self.angular_offset,
+            max_force: self.max_force,
+            max_torque: self.max_torque,
+            correction_factor: self.correction_factor,
+        }
+    }
+
+    pub(crate) fn anchor_a<D>(&self, bodies: &Slab<Box<Body<T, D>>>) -> Vector2<T> {
+        *bodies[self.base.body_a.index].position()
+    }
+
+    pub(crate) fn anchor_b<D>(&self, bodies: &Slab<Box<Body<T, D>>>) -> Vector2<T> {
+        *bodies[self.base.body_b.index].position()
+    }
+
+    pub fn reaction_force(&self, inv_dt: T) -> Vector2<T> {
+        self.linear_impulse * inv_dt
+    }
+
+    pub fn reaction_torque(&self, inv_dt: T) -> T {
+        inv_dt * self.angular_impulse
+    }
+
+    pub fn linear_offset(&self) -> &Vector2<T> {
+        &self.linear_offset
+    }
+
+    pub fn set_linear_offset(&mut self, offset: Vector2<T>) {
+        if offset != self.linear_offset {
+            self.base.wake_bodies();
+            self.linear_offset = offset;
+        }
+    }
+
+    pub fn angular_offset(&self) -> T {
+        self.angular_offset
+    }
+
+    pub fn set_angular_offset(&mut self, offset: T) {
+        if offset != self.angular_offset {
+            self.base.wake_bodies();
+            self.angular_offset = offset;
+        }
+    }
+
+    pub fn max_force(&self) -> T {
+        self.max_force
+    }
+
+    pub fn set_max_force(&mut self, force: T) {
+        assert!(force >= T::zero());
+        self.max_force = force;
+    }
+
+    pub fn max_torque(&self) -> T {
+        self.max_torque
+    }
+
+    pub fn set_max_torque(&mut self, torque: T) {
+        assert!(torque >= T::zero());
+        self.max_torque = torque;
+    }
+
+    pub fn correction_factor(&self) -> T {
+        self.correction_factor
+    }
+
+    pub fn set_correction_factor(&mut self, factor: T) {
+        assert!(factor >= T::zero() && factor <= T::one());
+        self.correction_factor = factor;
+    }
+
+    pub(crate) fn init_velocity_constraints<D>(
+        &mut self,
+        bodies: &Slab<Box<Body<T, D>>>,
+        data: &mut SolverData<T>,
+    ) {
+        let body_a = &bodies[self.base.body_a.index];
+        let body_b = &bodies[self.base.body_b.index];
+        self.index_a = body_a.island_index;
+        self.index_b = body_b.island_index;
+        self.local_center_a = body_a.sweep.local_center;
+        self.local_center_b = body_b.sweep.local_center;
+        self.inv_mass_a = body_a.inv_mass;
+        self.inv_mass_b = body_b.inv_mass;
+        self.inv_i_a = body_a.inv_i;
+        self.inv_i_b = body_b.inv_i;
+
+        let c_a = data.positions[self.index_a].c;
+        let a_a = data.positions[self.index_a].a;
+        let mut v_a = data.velocities[self.index_a].v;
+        let mut w_a = data.velocities[self.index_a].w;
+
+        let c_b = data.positions[self.index_b].c;
+        let a_b = data.positions[self.index_b].a;
+        let mut v_b = data.velocities[self.index_b].v;
+        let mut w_b = data.velocities[self.index_b].w;
+
+        let q_a = Rotation::new(a_a);
+        let q_b = Rotation::new(a_b);
+
+        // 锚点是刚体的原点
+        self.r_a = q_a.multiply(-self.local_center_a);
+        self.r_b = q_b.multiply(-self.local_center_b);
+
+        let (m_a, m_b) = (self.inv_mass_a, self.inv_mass_b);
+        let (i_a, i_b) = (self.inv_i_a, self.inv_i_b);
+        let (r_a, r_b) = (self.r_a, self.r_b);
+
+        let mut k = Matrix22::zero();
+        k.ex.x = m_a + m_b + i_a * r_a.y * r_a.y + i_b * r_b.y * r_b.y;
+        k.ex.y = -i_a * r_a.x * r_a.y - i_b * r_b.x * r_b.y;
+        k.ey.x = k.ex.y;
+        k.ey.y = m_a + m_b + i_a * r_a.x * r_a.x + i_b * r_b.x * r_b.x;
+        self.linear_mass = k.inverse();
+
+        self.angular_mass = i_a + i_b;
+        if self.angular_mass > T::zero() {
+            self.angular_mass = T::one() / self.angular_mass;
+        }
+
+        self.linear_error = c_b + r_b - c_a - r_a - q_a.multiply(self.linear_offset);
+        self.angular_error = a_b - a_a - self.angular_offset;
+
+        if data.step.warm_starting {
+            self.linear_impulse *= data.step.dt_ratio;
+            self.angular_impulse *= data.step.dt_ratio;
+
+            let p = self.linear_impulse;
+            v_a -= p * m_a;
+            w_a -= i_a * (r_a.cross(p) + self.angular_impulse);
+            v_b += p * m_b;
+            w_b += i_b * (r_b.cross(p) + self.angular_impulse);
+        } else {
+            self.linear_impulse = Vector2::zero();
+            self.angular_impulse = T::zero();
+        }
+
+        data.velocities[self.index_a].v = v_a;
+        data.velocities[self.index_a].w = w_a;
+        data.velocities[self.index_b].v = v_b;
+        data.velocities[self.index_b].w = w_b;
+    }
+
+    pub(crate) fn solve_velocity_constraints(&mut self, data: &mut SolverData<T>) {
+        let mut v_a = data.velocities[self.index_a].v;
+        let mut w_a = data.velocities[self.index_a].w;
+        let mut v_b = data.velocities[self.index_b].v;
+        let mut w_b = data.velocities[self.index_b].w;
+
+        let (m_a, m_b) = (self.inv_mass_a, self.inv_mass_b);
+        let (i_a, i_b) = (self.inv_i_a, self.inv_i_b);
+        let (r_a, r_b) = (self.r_a, self.r_b);
+
+        let h = data.step.dt;
+        let inv_h = data.step.inv_dt;
+
+        // 角度
+        {
+            let cdot = w_b - w_a + inv_h * self.correction_factor * self.angular_error;
+            let mut impulse = -self.angular_mass * cdot;
+
+            let old_impulse = self.angular_impulse;
+            let max_impulse = h * self.max_torque;
+            self.angular_impulse = (old_impulse + impulse).clamp(-max_impulse, max_impulse);
+            impulse = self.angular_impulse - old_impulse;
+
+            w_a -= i_a * impulse;
+            w_b += i_b * impulse;
+        }
+
+        // 位置
+        {
+            let cdot = v_b + w_b.cross(r_b) - v_a - w_a.cross(r_a)
+                + self.linear_error * (inv_h * self.correction_factor);
+            let mut impulse = -self.linear_mass.multiply(cdot);
+
+            let old_impulse = self.linear_impulse;
+            self.linear_impulse += impulse;
+
+            let max_impulse = h * self.max_force;
+            if self.linear_impulse.length_squared() > max_impulse * max_impulse {
+                self.linear_impulse = self.linear_impulse.normalize() * max_impulse;
+            }
+            impulse = self.linear_impulse - old_impulse;
+
+            v_a -= impulse * m_a;
+            w_a -= i_a * r_a.cross(impulse);
+            v_b += impulse * m_b;
+            w_b += i_b * r_b.cross(impulse);
+        }
+
+        data.velocities[self.index_a].v = v_a;
+        data.velocities[self.index_a].w = w_a;
+        data.velocities[self.index_b].v = v_b;
+        data.velocities[self.index_b].w = w_b;
+    }
+
+    pub(crate) fn solve_position_constraints(&mut self, _data: &mut SolverData<T>) -> bool {
+        true
+    }
+}
@@ -0,0 +1,581 @@
+use crate::dynamic::joints::{JointBase, LimitState};
+use crate::dynamic::time_step::SolverData;
+use crate::math::{Matrix22, Matrix33};
+use crate::{settings, Body, BodyId, World};
+use slab::Slab;
+use xmath::{CrossTrait, DotTrait, Multiply, Real, Rotation, Vector2, Vector3};
+
+/// 平移关节的参数，刚体B只能沿着刚体A上的一个轴移动，不能相对旋转，可以限制距离以及用马达驱动
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrismaticJointDef<T> {
+    pub body_a: BodyId,
+    pub body_b: BodyId,
+    pub collide_connected: bool,
+    pub local_anchor_a: Vector2<T>,
+    pub local_anchor_b: Vector2<T>,
+
+    /// 刚体A局部坐标中的移动方向
+    pub local_axis_a: Vector2<T>,
+
+    /// 刚体B相对刚体A的初始角度
+    pub reference_angle: T,
+    pub enable_limit: bool,
+    pub lower_translation: T,
+    pub upper_translation: T,
+    pub enable_motor: bool,
+    pub max_motor_force: T,
+    pub motor_speed: T,
+}
+
+impl<T: Real> PrismaticJointDef<T> {
+    pub fn new(body_a: BodyId, body_b: BodyId) -> PrismaticJointDef<T> {
+        PrismaticJointDef {
+            body_a,
+            body_b,
+            collide_connected: false,
+            local_anchor_a: Vector2::zero(),
+            local_anchor_b: Vector2::zero(),
+            local_axis_a: Vector2::new(T::one(), T::zero()),
+            reference_angle: T::zero(),
+            enable_limit: false,
+            lower_translation: T::zero(),
+            upper_translation: T::zero(),
+            enable_motor: false,
+            max_motor_force: T::zero(),
+            motor_speed: T::zero(),
+        }
+    }
+
+    /// 使用世界坐标中的锚点和移动方向，参考角度为两个刚体当前的角度差
+    pub fn with_anchor<D>(
+        world: &World<T, D>,
+        body_a: BodyId,
+        body_b: BodyId,
+        anchor: Vector2<T>,
+        axis: Vector2<T>,
+    ) -> PrismaticJointDef<T> {
+        let a = world.body(body_a).expect("invalid body a");
+        let b = world.body(body_b).expect("invalid body b");
+        PrismaticJointDef {
+            local_anchor_a: a.local_point(anchor),
+            local_anchor_b: b.local_point(anchor),
+            local_axis_a: a.local_vector(axis),
+            reference_angle: b.angle() - a.angle(),
+            ..PrismaticJointDef::new(body_a, body_b)
+        }
+    }
+}
+
+pub struct PrismaticJoint<T> {
+    pub(crate) base: JointBase<T>,
+    local_anchor_a: Vector2<T>,
+    local_anchor_b: Vector2<T>,
+    local_x_axis_a: Vector2<T>,
+    local_y_axis_a: Vector2<T>,
+    reference_angle: T,
+    impulse: Vector3<T>,
+    motor_impulse: T,
+    lower_translation: T,
+    upper_translation: T,
+    max_motor_force: T,
+    motor_speed: T,
+    enable_limit: bool,
+    enable_motor: bool,
+    limit_state: LimitState,
+
+    // 求解时的临时数据
+    index_a: usize,
+    index_b: usize,
+    local_center_a: Vector2<T>,
+    local_center_b: Vector2<T>,
+    inv_mass_a: T,
+    inv_mass_b: T,
+    inv_i_a: T,
+    inv_i_b: T,
+    axis: Vector2<T>,
+    perp: Vector2<T>,
+    s1: T,
+    s2: T,
+    a1: T,
+    a2: T,
+    k: Matrix33<T>,
+    motor_mass: T,
+}
+
+impl<T: Real> PrismaticJoint<T> {
+    pub(crate) fn new(base: JointBase<T>, def: PrismaticJointDef<T>) -> PrismaticJoint<T> {
+        let local_x_axis_a = def.local_axis_a.normalize();
+        PrismaticJoint {
+            base,
+            local_anchor_a: def.local_anchor_a,
+            local_anchor_b: def.local_anchor_b,
+            local_x_axis_a,
+            local_y_axis_a: T::one().cross(local_x_axis_a),
+            reference_angle: def.reference_angle,
+            impulse: Vector3::zero(),
+            motor_impulse: T::zero(),
+            lower_translation: def.lower_translation,
+            upper_translation: def.upper_translation,
+            max_motor_force: def.max_motor_force,
+            motor_speed: def.motor_speed,
+            enable_limit: def.enable_limit,
+            enable_motor: def.enable_motor,
+            limit_state: LimitState::Inactive,
+            index_a: 0,
+            index_b: 0,
+            local_center_a: Vector2::zero(),
+            local_center_b: Vector2::zero(),
+            inv_mass_a: T::zero(),
+            inv_mass_b: T::zero(),
+            inv_i_a: T::zero(),
+            inv_i_b: T::zero(),
+            axis: Vector2::zero(),
+            perp: Vector2::zero(),
+            s1: T::zero(),
+            s2: T::zero(),
+            a1: T::zero(),
+            a2: T::zero(),
+            k: Matrix33::zero(),
+            motor_mass: T::zero(),
+        }
+    }
+
+    pub fn local_anchor_a(&self) -> &Vector2<T> {
+        &self.local_anchor_a
+    }
+
+    pub fn local_anchor_b(&self) -> &Vector2<T> {
+        &self.local_anchor_b
+    }
+
+    pub fn local_axis_a(&self) -> &Vector2<T> {
+        &self.local_x_axis_a
+    }
+
+    pub fn reference_angle(&self) -> T {
+        self.reference_angle
+    }
+
+    /// 用关节当前的参数生成定义
+    pub fn def(&self) -> PrismaticJointDef<T> {
+        PrismaticJointDef {
+            body_a: self.base.body_a,
+            body_b: self.base.body_b,
+            collide_connected: self.base.collide_connected,
+            local_anchor_a: self.local_anchor_a,
+            local_anchor_b: self.local_anchor_b,
+            local_axis_a: self.local_x_axis_a,
+            reference_angle: self.reference_angle,
+            enable_limit: self.enable_limit,
+            lower_translation: self.lower_translation,
+            upper_translation: self.upper_translation,
+            enable_motor: self.enable_motor,
+            max_motor_force: self.max_motor_force,
+            motor_speed: self.motor_speed,
+        }
+    }
+
+    pub(crate) fn anchor_a<D>(&self, bodies: &Slab<Box<Body<T, D>>>) -> Vector2<T> {
+        bodies[self.base.body_a.index].world_point(self.local_anchor_a)
+    }
+
+    pub(crate) fn anchor_b<D>(&self, bodies: &Slab<Box<Body<T, D>>>) -> Vector2<T> {
+        bodies[self.base.body_b.index].world_point(self.local_anchor_b)
+    }
+
+    pub fn reaction_force(&self, inv_dt: T) -> Vector2<T> {
+        (self.perp * self.impulse.x + self.axis * (self.motor_impulse + self.impulse.z)) * inv_dt
+    }
+
+    pub fn reaction_torque(&self, inv_dt: T) -> T {
+        inv_dt * self.impulse.y
+    }
+
+    /// 两个锚点沿移动方向的距离
+    pub fn joint_translation<D>(&self, world: &World<T, D>) -> T {
+        let bodies = &world.0.bodies_slab;
+        let d = self.anchor_b(bodies) - self.anchor_a(bodies);
+        let axis = bodies[self.base.body_a.index].world_vector(self.local_x_axis_a);
+        d.dot(axis)
+    }
+
+    pub fn joint_speed<D>(&self, world: &World<T, D>) -> T {
+        let b_a = &world.0.bodies_slab[self.base.body_a.index];
+        let b_b = &world.0.bodies_slab[self.base.body_b.index];
+
+        let r_a = b_a
+            .xf
+            .q
+            .multiply(self.local_anchor_a - b_a.sweep.local_center);
+        let r_b = b_b
+            .xf
+            .q
+            .multiply(self.local_anchor_b - b_b.sweep.local_center);
+        let p1 = b_a.sweep.c + r_a;
+        let p2 = b_b.sweep.c + r_b;
+        let d = p2 - p1;
+        let axis = b_a.xf.q.multiply(self.local_x_axis_a);
+
+        let v_a = b_a.linear_velocity_;
+        let v_b = b_b.linear_velocity_;
+        let w_a = b_a.angular_velocity_;
+        let w_b = b_b.angular_velocity_;
+
+        d.dot(w_a.cross(axis)) + axis.dot(v_b + w_b.cross(r_b) - v_a - w_a.cross(r_a))
+    }
+
+    pub fn is_limit_enabled(&self) -> bool {
+        self.enable_limit
+    }
+
+    pub fn enable_limit(&mut self, flag: bool) {
+        if flag != self.enable_limit {
+            self.base.wake_bodies();
+            self.enable_limit = flag;
+            self.impulse.z = T::zero();
+        }
+    }
+
+    pub fn lower_limit(&self) -> T {
+        self.lower_translation
+    }
+
+    pub fn upper_limit(&self) -> T {
+        self.upper_translation
+    }
+
+    pub fn set_limits(&mut self, lower: T, upper: T) {
+        assert!(lower <= upper);
+        if lower != self.lower_translation || upper != self.upper_translation {
+            self.base.wake_bodies();
+            self.lower_translation = lower;
+            self.upper_translation = upper;
+            self.impulse.z = T::zero();
+        }
+    }
+
+    pub fn is_motor_enabled(&self) -> bool {
+        self.enable_motor
+    }
+
+    pub fn enable_motor(&mut self, flag: bool) {
+        if flag != self.enable_motor {
+            self.base.wake_bodies();
+            self.enable_motor = flag;
+        }
+    }
+
+    pub fn motor_speed(&self) -> T {
+        self.motor_speed
+    }
+
+    pub fn set_motor_speed(&mut self, speed: T) {
+        if speed != self.motor_speed {
+            self.base.wake_bodies();
+            self.motor_speed = speed;
+        }
+    }
+
+    pub fn max_motor_force(&self) -> T {
+        self.max_motor_force
+    }
+
+    pub fn set_max_motor_force(&mut self, force: T) {
+        if force != self.max_motor_force {
+            self.base.wake_bodies();
+            self.max_motor_force = force;
+        }
+    }
+
+    /// 上一步中马达的推力
+    pub fn motor_force(&self, inv_dt: T) -> T {
+        inv_dt * self.motor_impulse
+    }
+
+    fn mass_matrix(&self, s1: T, s2: T, a1: T, a2: T) -> Matrix33<T> {
+        let (m_a, m_b) = (self.inv_mass_a, self.inv_mass_b);
+        let (i_a, i_b) = (self.inv_i_a, self.inv_i_b);
+
+        let k11 = m_a + m_b + i_a * s1 * s1 + i_b * s2 * s2;
+        let k12 = i_a * s1 + i_b * s2;
+        let k13 = i_a * s1 * a1 + i_b * s2 * a2;
+        let mut k22 = i_a + i_b;
+        if k22 == T::zero() {
+            // 两个刚体都不能旋转
+            k22 = T::one();
+        }
+        let k23 = i_a * a1 + i_b * a2;
+        let k33 = m_a + m_b + i_a * a1 * a1 + i_b * a2 * a2;
+
+        Matrix33::new(
+            Vector3::new(k11, k12, k13),
+            Vector3::new(k12, k22, k23),
+            Vector3::new(k13, k23, k33),
+        )
+    }
+
+    pub(crate) fn init_velocity_constraints<D>(
+        &mut self,
+        bodies: &Slab<Box<Body<T, D>>>,
+        data: &mut SolverData<T>,
+    ) {
+        let body_a = &bodies[self.base.body_a.index];
+        let body_b = &bodies[self.base.body_b.index];
+        self.index_a = body_a.island_index;
+        self.index_b = body_b.island_index;
+        self.local_center_a = body_a.sweep.local_center;
+        self.local_center_b = body_b.sweep.local_center;
+        self.inv_mass_a = body_a.inv_mass;
+        self.inv_mass_b = body_b.inv_mass;
+        self.inv_i_a = body_a.inv_i;
+        self.inv_i_b = body_b.inv_i;
+
+        let c_a = data.positions[self.index_a].c;
+        let a_a = data.positions[self.index_a].a;
+        let mut v_a = data.velocities[self.index_a].v;
+        let mut w_a = data.velocities[self.index_a].w;
+
+        let c_b = data.positions[self.index_b].c;
+        let a_b = data.positions[self.index_b].a;
+        let mut v_b = data.velocities[self.index_b].v;
+        let mut w_b = data.velocities[self.index_b].w;
+
+        let q_a = Rotation::new(a_a);
+        let q_b = Rotation::new(a_b);
+
+        let r_a = q_a.multiply(self.local_anchor_a - self.local_center_a);
+        let r_b = q_b.multiply(self.local_anchor_b - self.local_center_b);
+        let d = (c_b - c_a) + r_b - r_a;
+
+        let (m_a, m_b) = (self.inv_mass_a, self.inv_mass_b);
+        let (i_a, i_b) = (self.inv_i_a, self.inv_i_b);
+
+        self.axis = q_a.multiply(self.local_x_axis_a);
+        self.a1 = (d + r_a).cross(self.axis);
+        self.a2 = r_b.cross(self.axis);
+
+        self.motor_mass = m_a + m_b + i_a * self.a1 * self.a1 + i_b * self.a2 * self.a2;
+        if self.motor_mass > T::zero() {
+            self.motor_mass = T::one() / self.motor_mass;
+        }
+
+        self.perp = q_a.multiply(self.local_y_axis_a);
+        self.s1 = (d + r_a).cross(self.perp);
+        self.s2 = r_b.cross(self.perp);
+
+        self.k = self.mass_matrix(self.s1, self.s2, self.a1, self.a2);
+
+        if self.enable_limit {
+            let joint_translation = self.axis.dot(d);
+            if (self.upper_translation - self.lower_translation).abs()
+                < T::two() * settings::linear_slop::<T>()
+            {
+                self.limit_state = LimitState::Equal;
+            } else if joint_translation <= self.lower_translation {
+                if self.limit_state != LimitState::AtLower {
+                    self.limit_state = LimitState::AtLower;
+                    self.impulse.z = T::zero();
+                }
+            } else if joint_translation >= self.upper_translation {
+                if self.limit_state != LimitState::AtUpper {
+                    self.limit_state = LimitState::AtUpper;
+                    self.impulse.z = T::zero();
+                }
+            } else {
+                self.limit_state = LimitState::Inactive;
+                self.impulse.z = T::zero();
+            }
+        } else {
+            self.limit_state = LimitState::Inactive;
+            self.impulse.z = T::zero();
+        }
+
+        if !self.enable_motor {
+            self.motor_impulse = T::zero();
+        }
+
+        if data.step.warm_starting {
+            self.impulse *= data.step.dt_ratio;
+            self.motor_impulse *= data.step.dt_ratio;
+
+            let axial = self.motor_impulse + self.impulse.z;
+            let p = self.perp * self.impulse.x + self.axis * axial;
+            let l_a = self.impulse.x * self.s1 + self.impulse.y + axial * self.a1;
+            let l_b = self.impulse.x * self.s2 + self.impulse.y + axial * self.a2;
+
+            v_a -= p * m_a;
+            w_a -= i_a * l_a;
+
+            v_b += p * m_b;
+            w_b += i_b * l_b;
+        } else {
+            self.impulse = Vector3::zero();
+            self.motor_impulse = T::zero();
+        }
+
+        data.velocities[self.index_a].v = v_a;
+        data.velocities[self.index_a].w = w_a;
+        data.velocities[self.index_b].v = v_b;
+        data.velocities[self.index_b].w = w_b;
+    }
+
+    pub(crate) fn solve_velocity_constraints(&mut self, data: &mut SolverData<T>) {
+        let mut v_a = data.velocities[self.index_a].v;
+        let mut w_a = data.velocities[self.index_a].w;
+        let mut v_b = data.velocities[self.index_b].v;
+        let mut w_b = data.velocities[self.index_b].w;
+
+        let (m_a, m_b) = (self.inv_mass_a, self.inv_mass_b);
+        let (i_a, i_b) = (self.inv_i_a, self.inv_i_b);
+
+        if self.enable_motor && self.limit_state != LimitState::Equal {
+            let cdot = self.axis.dot(v_b - v_a) + self.a2 * w_b - self.a1 * w_a;
+            let mut impulse = self.motor_mass * (self.motor_speed - cdot);
+            let old_impulse = self.motor_impulse;
+            let max_impulse = data.step.dt * self.max_motor_force;
+            self.motor_impulse = (old_impulse + impulse).clamp(-max_impulse, max_impulse);
+            impulse = self.motor_impulse - old_impulse;
+
+            let p = self.axis * impulse;
+            let l_a = impulse * self.a1;
+            let l_b = impulse * self.a2;
+
+            v_a -= p * m_a;
+            w_a -= i_a * l_a;
+
+            v_b += p * m_b;
+            w_b += i_b * l_b;
+        }
+
+        let cdot1 = Vector2::new(
+            self.perp.dot(v_b - v_a) + self.s2 * w_b - self.s1 * w_a,
+            w_b - w_a,
+        );
+
+        let df = if self.enable_limit && self.limit_state != LimitState::Inactive {
+            let cdot2 = self.axis.dot(v_b - v_a) + self.a2 * w_b - self.a1 * w_a;
+            let cdot = Vector3::new(cdot1.x, cdot1.y, cdot2);
+
+            let f1 = self.impulse;
+            let df = self.k.solve33(-cdot);
+            self.impulse += df;
+
+            if self.limit_state == LimitState::AtLower {
+                self.impulse.z = self.impulse.z.max(T::zero());
+            } else if self.limit_state == LimitState::AtUpper {
+                self.impulse.z = self.impulse.z.min(T::zero());
+            }
+
+            // f2(1:2) = invK(1:2,1:2) * (-Cdot(1:2) - K(1:2,3) * (f2(3) - f1(3))) + f1(1:2)
+            let b = -cdot1 - Vector2::new(self.k.ez.x, self.k.ez.y) * (self.impulse.z - f1.z);
+            let f2r = self.k.solve22(b) + Vector2::new(f1.x, f1.y);
+            self.impulse.x = f2r.x;
+            self.impulse.y = f2r.y;
+
+            self.impulse - f1
+        } else {
+            let df = self.k.solve22(-cdot1);
+            self.impulse.x += df.x;
+            self.impulse.y += df.y;
+            Vector3::new(df.x, df.y, T::zero())
+        };
+
+        let p = self.perp * df.x + self.axis * df.z;
+        let l_a = df.x * self.s1 + df.y + df.z * self.a1;
+        let l_b = df.x * self.s2 + df.y + df.z * self.a2;
+
+        v_a -= p * m_a;
+        w_a -= i_a * l_a;
+
+        v_b += p * m_b;
+        w_b += i_b * l_b;
+
+        data.velocities[self.index_a].v = v_a;
+        data.velocities[self.index_a].w = w_a;
+        data.velocities[self.index_b].v = v_b;
+        data.velocities[self.index_b].w = w_b;
+    }
+
+    pub(crate) fn solve_position_constraints(&mut self, data: &mut SolverData<T>) -> bool {
+        let mut c_a = data.positions[self.index_a].c;
+        let mut a_a = data.positions[self.index_a].a;
+        let mut c_b = data.positions[self.index_b].c;
+        let mut a_b = data.positions[self.index_b].a;
+
+        let q_a = Rotation::new(a_a);
+        let q_b = Rotation::new(a_b);
+
+        let (m_a, m_b) = (self.inv_mass_a, self.inv_mass_b);
+        let (i_a, i_b) = (self.inv_i_a, self.inv_i_b);
+
+        let r_a = q_a.multiply(self.local_anchor_a - self.local_center_a);
+        let r_b = q_b.multiply(self.local_anchor_b - self.local_center_b);
+        let d = c_b + r_b - c_a - r_a;
+
+        let axis = q_a.multiply(self.local_x_axis_a);
+        let a1 = (d + r_a).cross(axis);
+        let a2 = r_b.cross(axis);
+        let perp = q_a.multiply(self.local_y_axis_a);
+
+        let s1 = (d + r_a).cross(perp);
+        let s2 = r_b.cross(perp);
+
+        let c1 = Vector2::new(perp.dot(d), a_b - a_a - self.reference_angle);
+
+        let mut linear_error = c1.x.abs();
+        let angular_error = c1.y.abs();
+
+        let max_correction = settings::max_linear_correction::<T>();
+        let mut c2 = None;
+        if self.enable_limit {
+            let translation = axis.dot(d);
+            if (self.upper_translation - self.lower_translation).abs()
+                < T::two() * settings::linear_slop::<T>()
+            {
+                let c = translation - self.lower_translation;
+                c2 = Some(c.clamp(-max_correction, max_correction));
+                linear_error = linear_error.max(c.abs());
+            } else if translation <= self.lower_translation {
+                let c = translation - self.lower_translation + settings::linear_slop();
+                c2 = Some(c.clamp(-max_correction, T::zero()));
+                linear_error = linear_error.max(self.lower_translation - translation);
+            } else if translation >= self.upper_translation {
+                let c = translation - self.upper_translation - settings::linear_slop();
+                c2 = Some(c.clamp(T::zero(), max_correction));
+                linear_error = linear_error.max(translation - self.upper_translation);
+            }
+        }
+
+        let impulse = match c2 {
+            Some(c2) => {
+                let k = self.mass_matrix(s1, s2, a1, a2);
+                k.solve33(-Vector3::new(c1.x, c1.y, c2))
+            }
+            None => {
+                let k3 = self.mass_matrix(s1, s2, a1, a2);
+                let k = Matrix22::new(
+                    Vector2::new(k3.ex.x, k3.ex.y),
+                    Vector2::new(k3.ey.x, k3.ey.y),
+                );
+                let impulse1 = k.solve(-c1);
+                Vector3::new(impulse1.x, impulse1.y, T::zero())
+            }
+        };
+
+        let p = perp * impulse.x + axis * impulse.z;
+        let l_a = impulse.x * s1 + impulse.y + impulse.z * a1;
+        let l_b = impulse.x * s2 + impulse.y + impulse.z * a2;
+
+        c_a -= p * m_a;
+        a_a -= i_a * l_a;
+        c_b += p * m_b;
+        a_b += i_b * l_b;
+
+        data.positions[self.index_a].c = c_a;
+        data.positions[self.index_a].a = a_a;
+        data.positions[self.index_b].c = c_b;
+        data.positions[self.index_b].a = a_b;
+
+        linear_error <= settings::linear_slop() && angular_error <= settings::angular_slop()
+    }
+}
@@ -0,0 +1,505 @@
+use crate::dynamic::joints::{JointBase, LimitState};
+use crate::dynamic::time_step::SolverData;
+use crate::math::{Matrix22, Matrix33};
+use crate::{settings, Body, BodyId, World};
+use slab::Slab;
+use xmath::{CrossTrait, Multiply, Real, Rotation, Vector2, Vector3};
+
+/// 旋转关节的参数，两个刚体绕同一个锚点旋转，可以限制角度以及用马达驱动
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RevoluteJointDef<T> {
+    pub body_a: BodyId,
+    pub body_b: BodyId,
+    pub collide_connected: bool,
+    pub local_anchor_a: Vector2<T>,
+    pub local_anchor_b: Vector2<T>,
+
+    /// 刚体B相对刚体A的初始角度
+    pub reference_angle: T,
+    pub enable_limit: bool,
+    pub lower_angle: T,
+    pub upper_angle: T,
+    pub enable_motor: bool,
+    pub motor_speed: T,
+    pub max_motor_torque: T,
+}
+
+impl<T: Real> RevoluteJointDef<T> {
+    pub fn new(body_a: BodyId, body_b: BodyId) -> RevoluteJointDef<T> {
+        RevoluteJointDef {
+            body_a,
+            body_b,
+            collide_connected: false,
+            local_anchor_a: Vector2::zero(),
+            local_anchor_b: Vector2::zero(),
+            reference_angle: T::zero(),
+            enable_limit: false,
+            lower_angle: T::zero(),
+            upper_angle: T::zero(),
+            enable_motor: false,
+            motor_speed: T::zero(),
+            max_motor_torque: T::zero(),
+        }
+    }
+
+    /// 使用世界坐标中的锚点，参考角度为两个刚体当前的角度差
+    pub fn with_anchor<D>(
+        world: &World<T, D>,
+        body_a: BodyId,
+        body_b: BodyId,
+        anchor: Vector2<T>,
+    ) -> RevoluteJointDef<T> {
+        let a = world.body(body_a).expect("invalid body a");
+        let b = world.body(body_b).expect("invalid body b");
+        RevoluteJointDef {
+            local_anchor_a: a.local_point(anchor),
+            local_anchor_b: b.local_point(anchor),
+            reference_angle: b.angle() - a.angle(),
+            ..RevoluteJointDef::new(body_a, body_b)
+        }
+    }
+}
+
+pub struct RevoluteJoint<T> {
+    pub(crate) base: JointBase<T>,
+    local_anchor_a: Vector2<T>,
+    local_anchor_b: Vector2<T>,
+    reference_angle: T,
+    impulse: Vector3<T>,
+    motor_impulse: T,
+    enable_limit: bool,
+    lower_angle: T,
+    upper_angle: T,
+    enable_motor: bool,
+    motor_speed: T,
+    max_motor_torque: T,
+    limit_state: LimitState,
+
+    // 求解时的临时数据
+    index_a: usize,
+    index_b: usize,
+    r_a: Vector2<T>,
+    r_b: Vector2<T>,
+    local_center_a: Vector2<T>,
+    local_center_b: Vector2<T>,
+    inv_mass_a: T,
+    inv_mass_b: T,
+    inv_i_a: T,
+    inv_i_b: T,
+    mass: Matrix33<T>,
+    motor_mass: T,
+}
+
+impl<T: Real> RevoluteJoint<T> {
+    pub(crate) fn new(base: JointBase<T>, def: RevoluteJointDef<T>) -> RevoluteJoint<T> {
+        RevoluteJoint {
+            base,
+            local_anchor_a: def.local_anchor_a,
+            local_anchor_b: def.local_anchor_b,
+            reference_angle: def.reference_angle,
+            impulse: Vector3::zero(),
+            motor_impulse: T::zero(),
+            enable_limit: def.enable_limit,
+            lower_angle: def.lower_angle,
+            upper_angle: def.upper_angle,
+            enable_motor: def.enable_motor,
+            motor_speed: def.motor_speed,
+            max_motor_torque: def.max_motor_torque,
+            limit_state: LimitState::Inactive,
+            index_a: 0,
+            index_b: 0,
+            r_a: Vector2::zero(),
+            r_b: Vector2::zero(),
+            local_center_a: Vector2::zero(),
+            local_center_b: Vector2::zero(),
+            inv_mass_a: T::zero(),
+            inv_mass_b: T::zero(),
+            inv_i_a: T::zero(),
+            inv_i_b: T::zero(),
+            mass: Matrix33::zero(),
+            motor_mass: T::zero(),
+        }
+    }
+
+    pub fn local_anchor_a(&self) -> &Vector2<T> {
+        &self.local_anchor_a
+    }
+
+    pub fn local_anchor_b(&self) -> &Vector2<T> {
+        &self.local_anchor_b
+    }
+
+    pub fn reference_angle(&self) -> T {
+        self.reference_angle
+    }
+
+    /// 用关节当前的参数生成定义
+    pub fn def(&self) -> RevoluteJointDef<T> {
+        RevoluteJointDef {
+            body_a: self.base.body_a,
+            body_b: self.base.body_b,
+            collide_connected: self.base.collide_connected,
+            local_anchor_a: self.local_anchor_a,
+            local_anchor_b: self.local_anchor_b,
+            reference_angle: self.reference_angle,
+            enable_limit: self.enable_limit,
+            lower_angle: self.lower_angle,
+            upper_angle: self.upper_angle,
+            enable_motor: self.enable_motor,
+            motor_speed: self.motor_speed,
+            max_motor_torque: self.max_motor_torque,
+        }
+    }
+
+    pub(crate) fn anchor_a<D>(&self, bodies: &Slab<Box<Body<T, D>>>) -> Vector2<T> {
+        bodies[self.base.body_a.index].world_point(self.local_anchor_a)
+    }
+
+    pub(crate) fn anchor_b<D>(&self, bodies: &Slab<Box<Body<T, D>>>) -> Vector2<T> {
+        bodies[self.base.body_b.index].world_point(self.local_anchor_b)
+    }
+
+    pub fn reaction_force(&self, inv_dt: T) -> Vector2<T> {
+        Vector2::new(self.impulse.x, self.impulse.y) * inv_dt
+    }
+
+    pub fn reaction_torque(&self, inv_dt: T) -> T {
+        inv_dt * self.impulse.z
+    }
+
+    /// 刚体B相对刚体A转过的角度
+    pub fn joint_angle<D>(&self, world: &World<T, D>) -> T {
+        let bodies = &world.0.bodies_slab;
+        bodies[self.base.body_b.index].sweep.a
+            - bodies[self.base.body_a.index].sweep.a
+            - self.reference_angle
+    }
+
+    pub fn joint_speed<D>(&self, world: &World<T, D>) -> T {
+        let bodies = &world.0.bodies_slab;
+        bodies[self.base.body_b.index].angular_velocity_
+            - bodies[self.base.body_a.index].angular_velocity_
+    }
+
+    pub fn is_limit_enabled(&self) -> bool {
+        self.enable_limit
+    }
+
+    pub fn enable_limit(&mut self, flag: bool) {
+        if flag != self.enable_limit {
+            self.base.wake_bodies();
+            self.enable_limit = flag;
+            self.impulse.z = T::zero();
+        }
+    }
+
+    pub fn lower_limit(&self) -> T {
+        self.lower_angle
+    }
+
+    pub fn upper_limit(&self) -> T {
+        self.upper_angle
+    }
+
+    pub fn set_limits(&mut self, lower: T, upper: T) {
+        assert!(lower <= upper);
+        if lower != self.lower_angle || upper != self.upper_angle {
+            self.base.wake_bodies();
+            self.impulse.z = T::zero();
+            self.lower_angle = lower;
+            self.upper_angle = upper;
+        }
+    }
+
+    pub fn is_motor_enabled(&self) -> bool {
+        self.enable_motor
+    }
+
+    pub fn enable_motor(&mut self, flag: bool) {
+        if flag != self.enable_motor {
+            self.base.wake_bodies();
+            self.enable_motor = flag;
+        }
+    }
+
+    pub fn motor_speed(&self) -> T {
+        self.motor_speed
+    }
+
+    pub fn set_motor_speed(&mut self, speed: T) {
+        if speed != self.motor_speed {
+            self.base.wake_bodies();
+            self.motor_speed = speed;
+        }
+    }
+
+    pub fn max_motor_torque(&self) -> T {
+        self.max_motor_torque
+    }
+
+    pub fn set_max_motor_torque(&mut self, torque: T) {
+        if torque != self.max_motor_torque {
+            self.base.wake_bodies();
+            self.max_motor_torque = torque;
+        }
+    }
+
+    /// 上一步中马达的扭矩
+    pub fn motor_torque(&self, inv_dt: T) -> T {
+        inv_dt * self.motor_impulse
+    }
+
+    pub(crate) fn init_velocity_constraints<D>(
+        &mut self,
+        bodies: &Slab<Box<Body<T, D>>>,
+        data: &mut SolverData<T>,
+    ) {
+        let body_a = &bodies[self.base.body_a.index];
+        let body_b = &bodies[self.base.body_b.index];
+        self.index_a = body_a.island_index;
+        self.index_b = body_b.island_index;
+        self.local_center_a = body_a.sweep.local_center;
+        self.local_center_b = body_b.sweep.local_center;
+        self.inv_mass_a = body_a.inv_mass;
+        self.inv_mass_b = body_b.inv_mass;
+        self.inv_i_a = body_a.inv_i;
+        self.inv_i_b = body_b.inv_i;
+
+        let a_a = data.positions[self.index_a].a;
+        let mut v_a = data.velocities[self.index_a].v;
+        let mut w_a = data.velocities[self.index_a].w;
+
+        let a_b = data.positions[self.index_b].a;
+        let mut v_b = data.velocities[self.index_b].v;
+        let mut w_b = data.velocities[self.index_b].w;
+
+        let q_a = Rotation::new(a_a);
+        let q_b = Rotation::new(a_b);
+
+        self.r_a = q_a.multiply(self.local_anchor_a - self.local_center_a);
+        self.r_b = q_b.multiply(self.local_anchor_b - self.local_center_b);
+
+        let (m_a, m_b) = (self.inv_mass_a, self.inv_mass_b);
+        let (i_a, i_b) = (self.inv_i_a, self.inv_i_b);
+        let (r_a, r_b) = (self.r_a, self.r_b);
+
+        let fixed_rotation = i_a + i_b == T::zero();
+
+        self.mass.ex.x = m_a + m_b + r_a.y * r_a.y * i_a + r_b.y * r_b.y * i_b;
+        self.mass.ey.x = -r_a.y * r_a.x * i_a - r_b.y * r_b.x * i_b;
+        self.mass.ez.x = -r_a.y * i_a - r_b.y * i_b;
+        self.mass.ex.y = self.mass.ey.x;
+        self.mass.ey.y = m_a + m_b + r_a.x * r_a.x * i_a + r_b.x * r_b.x * i_b;
+        self.mass.ez.y = r_a.x * i_a + r_b.x * i_b;
+        self.mass.ex.z = self.mass.ez.x;
+        self.mass.ey.z = self.mass.ez.y;
+        self.mass.ez.z = i_a + i_b;
+
+        self.motor_mass = i_a + i_b;
+        if self.motor_mass > T::zero() {
+            self.motor_mass = T::one() / self.motor_mass;
+        }
+
+        if !self.enable_motor || fixed_rotation {
+            self.motor_impulse = T::zero();
+        }
+
+        if self.enable_limit && !fixed_rotation {
+            let joint_angle = a_b - a_a - self.reference_angle;
+            if (self.upper_angle - self.lower_angle).abs()
+                < T::two() * settings::angular_slop::<T>()
+            {
+                self.limit_state = LimitState::Equal;
+            } else if joint_angle <= self.lower_angle {
+                if self.limit_state != LimitState::AtLower {
+                    self.impulse.z = T::zero();
+                }
+                self.limit_state = LimitState::AtLower;
+            } else if joint_angle >= self.upper_angle {
+                if self.limit_state != LimitState::AtUpper {
+                    self.impulse.z = T::zero();
+                }
+                self.limit_state = LimitState::AtUpper;
+            } else {
+                self.limit_state = LimitState::Inactive;
+                self.impulse.z = T::zero();
+            }
+        } else {
+            self.limit_state = LimitState::Inactive;
+        }
+
+        if data.step.warm_starting {
+            self.impulse *= data.step.dt_ratio;
+            self.motor_impulse *= data.step.dt_ratio;
+
+            let p = Vector2::new(self.impulse.x, self.impulse.y);
+
+            v_a -= p * m_a;
+            w_a -= i_a * (r_a.cross(p) + self.motor_impulse + self.impulse.z);
+
+            v_b += p * m_b;
+            w_b += i_b * (r_b.cross(p) + self.motor_impulse + self.impulse.z);
+        } else {
+            self.impulse = Vector3::zero();
+            self.motor_impulse = T::zero();
+        }
+
+        data.velocities[self.index_a].v = v_a;
+        data.velocities[self.index_a].w = w_a;
+        data.velocities[self.index_b].v = v_b;
+        data.velocities[self.index_b].w = w_b;
+    }
+
+    pub(crate) fn solve_velocity_constraints(&mut self, data: &mut SolverData<T>) {
+        let mut v_a = data.velocities[self.index_a].v;
+        let mut w_a = data.velocities[self.index_a].w;
+        let mut v_b = data.velocities[self.index_b].v;
+        let mut w_b = data.velocities[self.index_b].w;
+
+        let (m_a, m_b) = (self.inv_mass_a, self.inv_mass_b);
+        let (i_a, i_b) = (self.inv_i_a, self.inv_i_b);
+        let (r_a, r_b) = (self.r_a, self.r_b);
+
+        let fixed_rotation = i_a + i_b == T::zero();
+
+        if self.enable_motor && self.limit_state != LimitState::Equal && !fixed_rotation {
+            let cdot = w_b - w_a - self.motor_speed;
+            let mut impulse = -self.motor_mass * cdot;
+            let old_impulse = self.motor_impulse;
+            let max_impulse = data.step.dt * self.max_motor_torque;
+            self.motor_impulse = (old_impulse + impulse).clamp(-max_impulse, max_impulse);
+            impulse = self.motor_impulse - old_impulse;
+
+            w_a -= i_a * impulse;
+            w_b += i_b * impulse;
+        }
+
+        if self.enable_limit && self.limit_state != LimitState::Inactive && !fixed_rotation {
+            let cdot1 = v_b + w_b.cross(r_b) - v_a - w_a.cross(r_a);
+            let cdot2 = w_b - w_a;
+            let cdot = Vector3::new(cdot1.x, cdot1.y, cdot2);
+
+            let mut impulse = -self.mass.solve33(cdot);
+
+            match self.limit_state {
+                LimitState::Equal => self.impulse += impulse,
+                LimitState::AtLower | LimitState::AtUpper => {
+                    let new_impulse = self.impulse.z + impulse.z;
+                    let reduce = if self.limit_state == LimitState::AtLower {
+                        new_impulse < T::zero()
+                    } else {
+                        new_impulse > T::zero()
+                    };
+                    if reduce {
+                        let rhs =
+                            -cdot1 + Vector2::new(self.mass.ez.x, self.mass.ez.y) * self.impulse.z;
+                        let reduced = self.mass.solve22(rhs);
+                        impulse.x = reduced.x;
+                        impulse.y = reduced.y;
+                        impulse.z = -self.impulse.z;
+                        self.impulse.x += reduced.x;
+                        self.impulse.y += reduced.y;
+                        self.impulse.z = T::zero();
+                    } else {
+                        self.impulse += impulse;
+                    }
+                }
+                LimitState::Inactive => unreachable!(),
+            }
+
+            let p = Vector2::new(impulse.x, impulse.y);
+
+            v_a -= p * m_a;
+            w_a -= i_a * (r_a.cross(p) + impulse.z);
+
+            v_b += p * m_b;
+            w_b += i_b * (r_b.cross(p) + impulse.z);
+        } else {
+            let cdot = v_b + w_b.cross(r_b) - v_a - w_a.cross(r_a);
+            let impulse = self.mass.solve22(-cdot);
+
+            self.impulse.x += impulse.x;
+            self.impulse.y += impulse.y;
+
+            v_a -= impulse * m_a;
+            w_a -= i_a * r_a.cross(impulse);
+
+            v_b += impulse * m_b;
+            w_b += i_b * r_b.cross(impulse);
+        }
+
+        data.velocities[self.index_a].v = v_a;
+        data.velocities[self.index_a].w = w_a;
+        data.velocities[self.index_b].v = v_b;
+        data.velocities[self.index_b].w = w_b;
+    }
+
+    pub(crate) fn solve_position_constraints(&mut self, data: &mut SolverData<T>) -> bool {
+        let mut c_a = data.positions[self.index_a].c;
+        let mut a_a = data.positions[self.index_a].a;
+        let mut c_b = data.positions[self.index_b].c;
+        let mut a_b = data.positions[self.index_b].a;
+
+        let mut angular_error = T::zero();
+        let fixed_rotation = self.inv_i_a + self.inv_i_b == T::zero();
+
+        if self.enable_limit && self.limit_state != LimitState::Inactive && !fixed_rotation {
+            let angle = a_b - a_a - self.reference_angle;
+            let max_correction = settings::max_angular_correction::<T>();
+            let c = match self.limit_state {
+                LimitState::Equal => {
+                    let c = (angle - self.lower_angle).clamp(-max_correction, max_correction);
+                    angular_error = c.abs();
+                    c
+                }
+                LimitState::AtLower => {
+                    let c = angle - self.lower_angle;
+                    angular_error = -c;
+                    (c + settings::angular_slop()).clamp(-max_correction, T::zero())
+                }
+                LimitState::AtUpper => {
+                    let c = angle - self.upper_angle;
+                    angular_error = c;
+                    (c - settings::angular_slop()).clamp(T::zero(), max_correction)
+                }
+                LimitState::Inactive => unreachable!(),
+            };
+            let limit_impulse = -self.motor_mass * c;
+
+            a_a -= self.inv_i_a * limit_impulse;
+            a_b += self.inv_i_b * limit_impulse;
+        }
+
+        let q_a = Rotation::new(a_a);
+        let q_b = Rotation::new(a_b);
+        let r_a = q_a.multiply(self.local_anchor_a - self.local_center_a);
+        let r_b = q_b.multiply(self.local_anchor_b - self.local_center_b);
+
+        let c = c_b + r_b - c_a - r_a;
+        let position_error = c.length();
+
+        let (m_a, m_b) = (self.inv_mass_a, self.inv_mass_b);
+        let (i_a, i_b) = (self.inv_i_a, self.inv_i_b);
+
+        let mut k = Matrix22::zero();
+        k.ex.x = m_a + m_b + i_a * r_a.y * r_a.y + i_b * r_b.y * r_b.y;
+        k.ex.y = -i_a * r_a.x * r_a.y - i_b * r_b.x * r_b.y;
+        k.ey.x = k.ex.y;
+        k.ey.y = m_a + m_b + i_a * r_a.x * r_a.x + i_b * r_b.x * r_b.x;
+
+        let impulse = -k.solve(c);
+
+        c_a -= impulse * m_a;
+        a_a -= i_a * r_a.cross(impulse);
+
+        c_b += impulse * m_b;
+        a_b += i_b * r_b.cross(impulse);
+
+        data.positions[self.index_a].c = c_a;
+        data.positions[self.index_a].a = a_a;
+        data.positions[self.index_b].c = c_b;
+        data.positions[self.index_b].a = a_b;
+
+        position_error <= settings::linear_slop() && angular_error <= settings::angular_slop::<T>()
+    }
+}
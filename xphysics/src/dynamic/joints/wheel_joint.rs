@@ -0,0 +1,464 @@
+use crate::dynamic::joints::JointBase;
+use crate::dynamic::time_step::SolverData;
+use crate::{settings, Body, BodyId, World};
+use slab::Slab;
+use xmath::{CrossTrait, DotTrait, Multiply, Real, Rotation, Vector2};
+
+/// 轮子关节的参数，刚体B可以绕锚点自由旋转，并且沿着刚体A上的一个轴像悬挂弹簧一样移动，可以用马达驱动旋转
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WheelJointDef<T> {
+    pub body_a: BodyId,
+    pub body_b: BodyId,
+    pub collide_connected: bool,
+    pub local_anchor_a: Vector2<T>,
+    pub local_anchor_b: Vector2<T>,
+
+    /// 刚体A局部坐标中悬挂的方向
+    pub local_axis_a: Vector2<T>,
+    pub enable_motor: bool,
+    pub max_motor_torque: T,
+    pub motor_speed: T,
+
+    /// 悬挂弹簧的频率(Hz)，为0时没有悬挂
+    pub frequency_hz: T,
+
+    /// 阻尼比，0表示没有阻尼，1表示临界阻尼
+    pub damping_ratio: T,
+}
+
+impl<T: Real> WheelJointDef<T> {
+    pub fn new(body_a: BodyId, body_b: BodyId) -> WheelJointDef<T> {
+        WheelJointDef {
+            body_a,
+            body_b,
+            collide_connected: false,
+            local_anchor_a: Vector2::zero(),
+            local_anchor_b: Vector2::zero(),
+            local_axis_a: Vector2::new(T::one(), T::zero()),
+            enable_motor: false,
+            max_motor_torque: T::zero(),
+            motor_speed: T::zero(),
+            frequency_hz: T::two(),
+            damping_ratio: T::f32(0.7),
+        }
+    }
+
+    /// 使用世界坐标中的锚点和悬挂方向，锚点一般是轮子的中心
+    pub fn with_anchor<D>(
+        world: &World<T, D>,
+        body_a: BodyId,
+        body_b: BodyId,
+        anchor: Vector2<T>,
+        axis: Vector2<T>,
+    ) -> WheelJointDef<T> {
+        let a = world.body(body_a).expect("invalid body a");
+        let b = world.body(body_b).expect("invalid body b");
+        WheelJointDef {
+            local_anchor_a: a.local_point(anchor),
+            local_anchor_b: b.local_point(anchor),
+            local_axis_a: a.local_vector(axis),
+            ..WheelJointDef::new(body_a, body_b)
+        }
+    }
+}
+
+pub struct WheelJoint<T> {
+    pub(crate) base: JointBase<T>,
+    local_anchor_a: Vector2<T>,
+    local_anchor_b: Vector2<T>,
+    local_x_axis_a: Vector2<T>,
+    local_y_axis_a: Vector2<T>,
+    impulse: T,
+    motor_impulse: T,
+    spring_impulse: T,
+    max_motor_torque: T,
+    motor_speed: T,
+    enable_motor: bool,
+    frequency_hz: T,
+    damping_ratio: T,
+
+    // 求解时的临时数据
+    index_a: usize,
+    index_b: usize,
+    local_center_a: Vector2<T>,
+    local_center_b: Vector2<T>,
+    inv_mass_a: T,
+    inv_mass_b: T,
+    inv_i_a: T,
+    inv_i_b: T,
+    ax: Vector2<T>,
+    ay: Vector2<T>,
+    s_ax: T,
+    s_bx: T,
+    s_ay: T,
+    s_by: T,
+    mass: T,
+    motor_mass: T,
+    spring_mass: T,
+    bias: T,
+    gamma: T,
+}
+
+impl<T: Real> WheelJoint<T> {
+    pub(crate) fn new(base: JointBase<T>, def: WheelJointDef<T>) -> WheelJoint<T> {
+        let local_x_axis_a = def.local_axis_a.normalize();
+        WheelJoint {
+            base,
+            local_anchor_a: def.local_anchor_a,
+            local_anchor_b: def.local_anchor_b,
+            local_x_axis_a,
+            local_y_axis_a: T::one().cross(local_x_axis_a),
+            impulse: T::zero(),
+            motor_impulse: T::zero(),
+            spring_impulse: T::zero(),
+            max_motor_torque: def.max_motor_torque,
+            motor_speed: def.motor_speed,
+            enable_motor: def.enable_motor,
+            frequency_hz: def.frequency_hz,
+            damping_ratio: def.damping_ratio,
+            index_a: 0,
+            index_b: 0,
+            local_center_a: Vector2::zero(),
+            local_center_b: Vector2::zero(),
+            inv_mass_a: T::zero(),
+            inv_mass_b: T::zero(),
+            inv_i_a: T::zero(),
+            inv_i_b: T::zero(),
+            ax: Vector2::zero(),
+            ay: Vector2::zero(),
+            s_ax: T::zero(),
+            s_bx: T::zero(),
+            s_ay: T::zero(),
+            s_by: T::zero(),
+            mass: T::zero(),
+            motor_mass: T::zero(),
+            spring_mass: T::zero(),
+            bias: T::zero(),
+            gamma: T::zero(),
+        }
+    }
+
+    pub fn local_anchor_a(&self) -> &Vector2<T> {
+        &self.local_anchor_a
+    }
+
+    pub fn local_anchor_b(&self) -> &Vector2<T> {
+        &self.local_anchor_b
+    }
+
+    pub fn local_axis_a(&self) -> &Vector2<T> {
+        &self.local_x_axis_a
+    }
+
+    /// 用关节当前的参数生成定义
+    pub fn def(&self) -> WheelJointDef<T> {
+        WheelJointDef {
+            body_a: self.base.body_a,
+            body_b: self.base.body_b,
+            collide_connected: self.base.collide_connected,
+            local_anchor_a: self.local_anchor_a,
+            local_anchor_b: self.local_anchor_b,
+            local_axis_a: self.local_x_axis_a,
+            enable_motor: self.enable_motor,
+            max_motor_torque: self.max_motor_torque,
+            motor_speed: self.motor_speed,
+            frequency_hz: self.frequency_hz,
+            damping_ratio: self.damping_ratio,
+        }
+    }
+
+    pub(crate) fn anchor_a<D>(&self, bodies: &Slab<Box<Body<T, D>>>) -> Vector2<T> {
+        bodies[self.base.body_a.index].world_point(self.local_anchor_a)
+    }
+
+    pub(crate) fn anchor_b<D>(&self, bodies: &Slab<Box<Body<T, D>>>) -> Vector2<T> {
+        bodies[self.base.body_b.index].world_point(self.local_anchor_b)
+    }
+
+    pub fn reaction_force(&self, inv_dt: T) -> Vector2<T> {
+        (self.ay * self.impulse + self.ax * self.spring_impulse) * inv_dt
+    }
+
+    pub fn reaction_torque(&self, inv_dt: T) -> T {
+        inv_dt * self.motor_impulse
+    }
+
+    /// 两个锚点沿悬挂方向的距离
+    pub fn joint_translation<D>(&self, world: &World<T, D>) -> T {
+        let bodies = &world.0.bodies_slab;
+        let d = self.anchor_b(bodies) - self.anchor_a(bodies);
+        let axis = bodies[self.base.body_a.index].world_vector(self.local_x_axis_a);
+        d.dot(axis)
+    }
+
+    /// 轮子相对刚体A的角速度
+    pub fn joint_speed<D>(&self, world: &World<T, D>) -> T {
+        let bodies = &world.0.bodies_slab;
+        bodies[self.base.body_b.index].angular_velocity_
+            - bodies[self.base.body_a.index].angular_velocity_
+    }
+
+    pub fn is_motor_enabled(&self) -> bool {
+        self.enable_motor
+    }
+
+    pub fn enable_motor(&mut self, flag: bool) {
+        if flag != self.enable_motor {
+            self.base.wake_bodies();
+            self.enable_motor = flag;
+        }
+    }
+
+    pub fn motor_speed(&self) -> T {
+        self.motor_speed
+    }
+
+    pub fn set_motor_speed(&mut self, speed: T) {
+        if speed != self.motor_speed {
+            self.base.wake_bodies();
+            self.motor_speed = speed;
+        }
+    }
+
+    pub fn max_motor_torque(&self) -> T {
+        self.max_motor_torque
+    }
+
+    pub fn set_max_motor_torque(&mut self, torque: T) {
+        if torque != self.max_motor_torque {
+            self.base.wake_bodies();
+            self.max_motor_torque = torque;
+        }
+    }
+
+    /// 上一步中马达的扭矩
+    pub fn motor_torque(&self, inv_dt: T) -> T {
+        inv_dt * self.motor_impulse
+    }
+
+    pub fn frequency(&self) -> T {
+        self.frequency_hz
+    }
+
+    pub fn set_frequency(&mut self, hz: T) {
+        self.frequency_hz = hz;
+    }
+
+    pub fn damping_ratio(&self) -> T {
+        self.damping_ratio
+    }
+
+    pub fn set_damping_ratio(&mut self, ratio: T) {
+        self.damping_ratio = ratio;
+    }
+
+    pub(crate) fn init_velocity_constraints<D>(
+        &mut self,
+        bodies: &Slab<Box<Body<T, D>>>,
+        data: &mut SolverData<T>,
+    ) {
+        let body_a = &bodies[self.base.body_a.index];
+        let body_b = &bodies[self.base.body_b.index];
+        self.index_a = body_a.island_index;
+        self.index_b = body_b.island_index;
+        self.local_center_a = body_a.sweep.local_center;
+        self.local_center_b = body_b.sweep.local_center;
+        self.inv_mass_a = body_a.inv_mass;
+        self.inv_mass_b = body_b.inv_mass;
+        self.inv_i_a = body_a.inv_i;
+        self.inv_i_b = body_b.inv_i;
+
+        let c_a = data.positions[self.index_a].c;
+        let a_a = data.positions[self.index_a].a;
+        let mut v_a = data.velocities[self.index_a].v;
+        let mut w_a = data.velocities[self.index_a].w;
+
+        let c_b = data.positions[self.index_b].c;
+        let a_b = data.positions[self.index_b].a;
+        let mut v_b = data.velocities[self.index_b].v;
+        let mut w_b = data.velocities[self.index_b].w;
+
+        let q_a = Rotation::new(a_a);
+        let q_b = Rotation::new(a_b);
+
+        let r_a = q_a.multiply(self.local_anchor_a - self.local_center_a);
+        let r_b = q_b.multiply(self.local_anchor_b - self.local_center_b);
+        let d = c_b + r_b - c_a - r_a;
+
+        let (m_a, m_b) = (self.inv_mass_a, self.inv_mass_b);
+        let (i_a, i_b) = (self.inv_i_a, self.inv_i_b);
+
+        // 点到直线的约束
+        self.ay = q_a.multiply(self.local_y_axis_a);
+        self.s_ay = (d + r_a).cross(self.ay);
+        self.s_by = r_b.cross(self.ay);
+        self.mass = m_a + m_b + i_a * self.s_ay * self.s_ay + i_b * self.s_by * self.s_by;
+        if self.mass > T::zero() {
+            self.mass = T::one() / self.mass;
+        }
+
+        // 悬挂弹簧
+        self.ax = q_a.multiply(self.local_x_axis_a);
+        self.s_ax = (d + r_a).cross(self.ax);
+        self.s_bx = r_b.cross(self.ax);
+        self.spring_mass = T::zero();
+        self.bias = T::zero();
+        self.gamma = T::zero();
+        if self.frequency_hz > T::zero() {
+            let inv_mass = m_a + m_b + i_a * self.s_ax * self.s_ax + i_b * self.s_bx * self.s_bx;
+            if inv_mass > T::zero() {
+                self.spring_mass = T::one() / inv_mass;
+
+                let c = d.dot(self.ax);
+                let omega = T::pi_times_2() * self.frequency_hz;
+                let damp = T::two() * self.spring_mass * self.damping_ratio * omega;
+                let k = self.spring_mass * omega * omega;
+
+                // 软约束
+                let h = data.step.dt;
+                self.gamma = h * (damp + h * k);
+                if self.gamma > T::zero() {
+                    self.gamma = T::one() / self.gamma;
+                }
+                self.bias = c * h * k * self.gamma;
+
+                self.spring_mass = inv_mass + self.gamma;
+                if self.spring_mass > T::zero() {
+                    self.spring_mass = T::one() / self.spring_mass;
+                }
+            }
+        } else {
+            self.spring_impulse = T::zero();
+        }
+
+        // 旋转马达
+        if self.enable_motor {
+            self.motor_mass = i_a + i_b;
+            if self.motor_mass > T::zero() {
+                self.motor_mass = T::one() / self.motor_mass;
+            }
+        } else {
+            self.motor_mass = T::zero();
+            self.motor_impulse = T::zero();
+        }
+
+        if data.step.warm_starting {
+            self.impulse *= data.step.dt_ratio;
+            self.spring_impulse *= data.step.dt_ratio;
+            self.motor_impulse *= data.step.dt_ratio;
+
+            let p = self.ay * self.impulse + self.ax * self.spring_impulse;
+            let l_a =
+                self.impulse * self.s_ay + self.spring_impulse * self.s_ax + self.motor_impulse;
+            let l_b =
+                self.impulse * self.s_by + self.spring_impulse * self.s_bx + self.motor_impulse;
+
+            v_a -= p * m_a;
+            w_a -= i_a * l_a;
+
+            v_b += p * m_b;
+            w_b += i_b * l_b;
+        } else {
+            self.impulse = T::zero();
+            self.spring_impulse = T::zero();
+            self.motor_impulse = T::zero();
+        }
+
+        data.velocities[self.index_a].v = v_a;
+        data.velocities[self.index_a].w = w_a;
+        data.velocities[self.index_b].v = v_b;
+        data.velocities[self.index_b].w = w_b;
+    }
+
+    pub(crate) fn solve_velocity_constraints(&mut self, data: &mut SolverData<T>) {
+        let mut v_a = data.velocities[self.index_a].v;
+        let mut w_a = data.velocities[self.index_a].w;
+        let mut v_b = data.velocities[self.index_b].v;
+        let mut w_b = data.velocities[self.index_b].w;
+
+        let (m_a, m_b) = (self.inv_mass_a, self.inv_mass_b);
+        let (i_a, i_b) = (self.inv_i_a, self.inv_i_b);
+
+        // 悬挂弹簧
+        {
+            let cdot = self.ax.dot(v_b - v_a) + self.s_bx * w_b - self.s_ax * w_a;
+            let impulse = -self.spring_mass * (cdot + self.bias + self.gamma * self.spring_impulse);
+            self.spring_impulse += impulse;
+
+            let p = self.ax * impulse;
+            v_a -= p * m_a;
+            w_a -= i_a * impulse * self.s_ax;
+            v_b += p * m_b;
+            w_b += i_b * impulse * self.s_bx;
+        }
+
+        // 旋转马达
+        {
+            let cdot = w_b - w_a - self.motor_speed;
+            let mut impulse = -self.motor_mass * cdot;
+            let old_impulse = self.motor_impulse;
+            let max_impulse = data.step.dt * self.max_motor_torque;
+            self.motor_impulse = (old_impulse + impulse).clamp(-max_impulse, max_impulse);
+            impulse = self.motor_impulse - old_impulse;
+
+            w_a -= i_a * impulse;
+            w_b += i_b * impulse;
+        }
+
+        // 点到直线的约束
+        {
+            let cdot = self.ay.dot(v_b - v_a) + self.s_by * w_b - self.s_ay * w_a;
+            let impulse = -self.mass * cdot;
+            self.impulse += impulse;
+
+            let p = self.ay * impulse;
+            v_a -= p * m_a;
+            w_a -= i_a * impulse * self.s_ay;
+            v_b += p * m_b;
+            w_b += i_b * impulse * self.s_by;
+        }
+
+        data.velocities[self.index_a].v = v_a;
+        data.velocities[self.index_a].w = w_a;
+        data.velocities[self.index_b].v = v_b;
+        data.velocities[self.index_b].w = w_b;
+    }
+
+    pub(crate) fn solve_position_constraints(&mut self, data: &mut SolverData<T>) -> bool {
+        let mut c_a = data.positions[self.index_a].c;
+        let mut a_a = data.positions[self.index_a].a;
+        let mut c_b = data.positions[self.index_b].c;
+        let mut a_b = data.positions[self.index_b].a;
+
+        let q_a = Rotation::new(a_a);
+        let q_b = Rotation::new(a_b);
+
+        let r_a = q_a.multiply(self.local_anchor_a - self.local_center_a);
+        let r_b = q_b.multiply(self.local_anchor_b - self.local_center_b);
+        let d = c_b + r_b - c_a - r_a;
+
+        let ay = q_a.multiply(self.local_y_axis_a);
+        let s_ay = (d + r_a).cross(ay);
+        let s_by = r_b.cross(ay);
+
+        let c = d.dot(ay);
+        let k = self.inv_mass_a
+            + self.inv_mass_b
+            + self.inv_i_a * s_ay * s_ay
+            + self.inv_i_b * s_by * s_by;
+        let impulse = if k != T::zero() { -c / k } else { T::zero() };
+
+        let p = ay * impulse;
+        c_a -= p * self.inv_mass_a;
+        a_a -= self.inv_i_a * impulse * s_ay;
+        c_b += p * self.inv_mass_b;
+        a_b += self.inv_i_b * impulse * s_by;
+
+        data.positions[self.index_a].c = c_a;
+        data.positions[self.index_a].a = a_a;
+        data.positions[self.index_b].c = c_b;
+        data.positions[self.index_b].a = a_b;
+
+        c.abs() <= settings::linear_slop()
+    }
+}
@@ -1,15 +1,42 @@
 mod body;
+mod character_controller;
 mod contact_manager;
 mod contacts;
 mod fixture;
+mod force_generator;
 mod island;
+mod joints;
+mod particle_system;
+mod rope;
+#[cfg(feature = "serde")]
+mod serialize;
+mod stack_allocator;
 mod time_step;
+mod top_down_controller;
 mod world;
 
 pub use body::{Body, BodyDef, BodyType, FixtureId};
-pub use contacts::{Contact, ContactFilter, ContactListener};
-pub use fixture::{Filter, Fixture, FixtureDef};
+pub use character_controller::{CharacterController, CharacterFlags};
+pub use contacts::{
+    Contact, ContactEvent, ContactFilter, ContactImpulse, ContactInfo, ContactKey, ContactListener,
+    ContactMut, DefaultContactFilter, DefaultMaterialMixer, MaterialMixer,
+};
+pub use fixture::{Filter, Fixture, FixtureDef, QueryFilter};
+pub use force_generator::{ForceGenerator, ForceGeneratorId};
+pub use island::IslandInfo;
+pub use joints::{
+    DistanceJoint, DistanceJointDef, GearJoint, GearJointDef, Joint, JointBroken, JointDef,
+    JointId, JointType, MotorJoint, MotorJointDef, PrismaticJoint, PrismaticJointDef,
+    RevoluteJoint, RevoluteJointDef, WheelJoint, WheelJointDef,
+};
+pub use particle_system::{
+    ParticleFlags, ParticleGroupDef, ParticleGroupId, ParticleSystem, ParticleSystemDef,
+    ParticleSystemId,
+};
+pub use rope::{Rope, RopeDef, RopeId};
 pub use time_step::Profile;
+pub use top_down_controller::TopDownController;
 pub use world::{
-    BodyId, Color, DebugDraw, DebugDrawFlags, DestructionListener, RayCastIter, World,
+    BodyId, Color, DebugDraw, DebugDrawFlags, DestructionListener, OutOfBoundsAction, RayCastHit,
+    RayCastIter, ShapeCastHit, World,
 };
@@ -0,0 +1,666 @@
+//! LiquidFun风格的粒子系统
+//!
+//! 粒子没有形状，只在和相邻粒子、刚体夹具的距离小于粒子直径时相互作用，可以模拟水、沙子和软体。
+
+use crate::dynamic::fixture::FixtureKey;
+use crate::dynamic::time_step::TimeStep;
+use crate::{Body, BodyId, BroadPhase, Shape};
+use slab::Slab;
+use std::collections::HashMap;
+use xmath::{CrossTrait, DotTrait, Real, Rotation, Transform, Vector2, AABB};
+
+/// 填充粒子组时粒子间距和直径的比例
+const PARTICLE_STRIDE: f32 = 0.75;
+
+/// 计算压力时粒子权重的范围
+const MIN_PARTICLE_WEIGHT: f32 = 1.0;
+const MAX_PARTICLE_WEIGHT: f32 = 5.0;
+
+/// 一步之内粒子间的力造成的最大速度变化，和临界速度的比例
+const MAX_PARTICLE_FORCE: f32 = 0.5;
+
+/// 每一步修正的刚体穿透比例
+const PARTICLE_BAUMGARTE: f32 = 0.2;
+
+bitflags! {
+    /// 粒子的行为，没有标志的粒子是普通的水
+    pub struct ParticleFlags: u32 {
+        /// 同一个粒子组中相邻的粒子用弹簧连接，保持组的初始形状
+        const ELASTIC = 0x0001;
+        /// 相邻粒子的速度趋于一致，例如油和泥浆
+        const VISCOUS = 0x0002;
+        /// 表面张力，粒子会聚成液滴
+        const TENSILE = 0x0004;
+    }
+}
+
+/// 粒子系统在`World`中的索引
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ParticleSystemId(pub(crate) usize);
+
+/// 粒子组在`ParticleSystem`中的索引
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ParticleGroupId(pub(crate) usize);
+
+/// 粒子系统的参数，系统中所有粒子的大小和密度相同
+pub struct ParticleSystemDef<T> {
+    pub radius: T,
+    pub density: T,
+    pub gravity_scale: T,
+
+    /// 粒子相互靠近时的阻尼
+    pub damping_strength: T,
+
+    /// 压力系数，越大越难压缩
+    pub pressure_strength: T,
+
+    /// `VISCOUS`粒子的粘性系数
+    pub viscous_strength: T,
+
+    /// `ELASTIC`粒子之间弹簧的系数
+    pub elastic_strength: T,
+
+    /// `TENSILE`粒子的表面张力系数
+    pub surface_tension_pressure_strength: T,
+    pub surface_tension_normal_strength: T,
+}
+
+impl<T: Real> ParticleSystemDef<T> {
+    pub fn new(radius: T) -> ParticleSystemDef<T> {
+        ParticleSystemDef {
+            radius,
+            density: T::one(),
+            gravity_scale: T::one(),
+            damping_strength: T::one(),
+            pressure_strength: T::f32(0.05),
+            viscous_strength: T::f32(0.25),
+            elastic_strength: T::f32(0.25),
+            surface_tension_pressure_strength: T::f32(0.2),
+            surface_tension_normal_strength: T::f32(0.2),
+        }
+    }
+}
+
+/// 粒子组的参数，用粒子填满放在`position`处的形状
+pub struct ParticleGroupDef<T> {
+    pub flags: ParticleFlags,
+    pub shape: Box<dyn Shape<T>>,
+    pub position: Vector2<T>,
+    pub angle: T,
+    pub linear_velocity: Vector2<T>,
+    pub angular_velocity: T,
+}
+
+impl<T: Real> ParticleGroupDef<T> {
+    pub fn new(shape: Box<dyn Shape<T>>, position: Vector2<T>) -> ParticleGroupDef<T> {
+        ParticleGroupDef {
+            flags: ParticleFlags::empty(),
+            shape,
+            position,
+            angle: T::zero(),
+            linear_velocity: Vector2::zero(),
+            angular_velocity: T::zero(),
+        }
+    }
+}
+
+/// 距离小于直径的两个粒子，`normal`从`a`指向`b`
+struct ParticleContact<T> {
+    a: usize,
+    b: usize,
+    flags: ParticleFlags,
+    weight: T,
+    normal: Vector2<T>,
+}
+
+/// 距离小于直径的粒子和夹具，`normal`是夹具表面的外法线
+struct ParticleBodyContact<T> {
+    index: usize,
+    body: BodyId,
+    distance: T,
+    weight: T,
+    normal: Vector2<T>,
+    mass: T,
+}
+
+/// `ELASTIC`粒子之间的弹簧
+struct ParticleSpring<T> {
+    a: usize,
+    b: usize,
+    length: T,
+}
+
+pub struct ParticleSystem<T> {
+    def: ParticleSystemDef<T>,
+    positions: Vec<Vector2<T>>,
+    velocities: Vec<Vector2<T>>,
+    flags: Vec<ParticleFlags>,
+    groups: Vec<ParticleGroupId>,
+    group_slab: Slab<ParticleFlags>,
+    springs: Vec<ParticleSpring<T>>,
+
+    // 求解时的临时数据
+    contacts: Vec<ParticleContact<T>>,
+    weights: Vec<T>,
+    pressures: Vec<T>,
+    surface_normals: Vec<Vector2<T>>,
+}
+
+impl<T: Real> ParticleSystem<T> {
+    pub(crate) fn new(def: ParticleSystemDef<T>) -> ParticleSystem<T> {
+        assert!(def.radius > T::zero());
+        assert!(def.density > T::zero());
+        ParticleSystem {
+            def,
+            positions: Vec::new(),
+            velocities: Vec::new(),
+            flags: Vec::new(),
+            groups: Vec::new(),
+            group_slab: Slab::new(),
+            springs: Vec::new(),
+            contacts: Vec::new(),
+            weights: Vec::new(),
+            pressures: Vec::new(),
+            surface_normals: Vec::new(),
+        }
+    }
+
+    pub fn radius(&self) -> T {
+        self.def.radius
+    }
+
+    fn diameter(&self) -> T {
+        self.def.radius * T::two()
+    }
+
+    /// 每个粒子代表的质量
+    pub fn particle_mass(&self) -> T {
+        let stride = self.diameter() * T::f32(PARTICLE_STRIDE);
+        self.def.density * stride * stride
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn group_count(&self) -> usize {
+        self.group_slab.len()
+    }
+
+    pub fn positions(&self) -> &[Vector2<T>] {
+        &self.positions
+    }
+
+    pub fn velocities(&self) -> &[Vector2<T>] {
+        &self.velocities
+    }
+
+    pub fn velocities_mut(&mut self) -> &mut [Vector2<T>] {
+        &mut self.velocities
+    }
+
+    pub fn particle_flags(&self) -> &[ParticleFlags] {
+        &self.flags
+    }
+
+    /// 粒子所属的组，删除粒子组后其它粒子的索引会改变
+    pub fn particle_group(&self, index: usize) -> ParticleGroupId {
+        self.groups[index]
+    }
+
+    pub fn create_particle_group(&mut self, def: ParticleGroupDef<T>) -> ParticleGroupId {
+        let id = ParticleGroupId(self.group_slab.insert(def.flags));
+        let xf = Transform::new(def.position, Rotation::new(def.angle));
+        let stride = self.diameter() * T::f32(PARTICLE_STRIDE);
+        let first = self.positions.len();
+
+        if def.shape.child_count() > 0 {
+            let aabb = (1..def.shape.child_count())
+                .fold(def.shape.compute_aabb(&xf, 0), |aabb, i| {
+                    aabb.combine(&def.shape.compute_aabb(&xf, i))
+                });
+            let mut y = (aabb.lower_bound.y / stride).floor() * stride;
+            while y < aabb.upper_bound.y {
+                let mut x = (aabb.lower_bound.x / stride).floor() * stride;
+                while x < aabb.upper_bound.x {
+                    let p = Vector2::new(x, y);
+                    if def.shape.test_point(&xf, &p) {
+                        let velocity =
+                            def.linear_velocity + def.angular_velocity.cross(p - def.position);
+                        self.positions.push(p);
+                        self.velocities.push(velocity);
+                        self.flags.push(def.flags);
+                        self.groups.push(id);
+                    }
+                    x += stride;
+                }
+                y += stride;
+            }
+        }
+
+        if def.flags.contains(ParticleFlags::ELASTIC) {
+            let diameter = self.diameter();
+            let positions = &self.positions;
+            let springs = &mut self.springs;
+            for_each_pair(positions, first, diameter, |a, b| {
+                springs.push(ParticleSpring {
+                    a,
+                    b,
+                    length: (positions[b] - positions[a]).length(),
+                });
+            });
+        }
+
+        id
+    }
+
+    /// 删除粒子组和组里的所有粒子
+    pub fn destroy_particle_group(&mut self, id: ParticleGroupId) {
+        if !self.group_slab.contains(id.0) {
+            return;
+        }
+        self.group_slab.remove(id.0);
+
+        let mut remap = Vec::with_capacity(self.positions.len());
+        let mut count = 0;
+        for group in &self.groups {
+            if *group == id {
+                remap.push(None);
+            } else {
+                remap.push(Some(count));
+                count += 1;
+            }
+        }
+
+        let mut index = 0;
+        self.positions.retain(|_| {
+            index += 1;
+            remap[index - 1].is_some()
+        });
+        let mut index = 0;
+        self.velocities.retain(|_| {
+            index += 1;
+            remap[index - 1].is_some()
+        });
+        let mut index = 0;
+        self.flags.retain(|_| {
+            index += 1;
+            remap[index - 1].is_some()
+        });
+        self.groups.retain(|group| *group != id);
+        self.springs
+            .retain(|spring| remap[spring.a].is_some() && remap[spring.b].is_some());
+        for spring in &mut self.springs {
+            spring.a = remap[spring.a].unwrap();
+            spring.b = remap[spring.b].unwrap();
+        }
+    }
+
+    pub(crate) fn shift_origin(&mut self, new_origin: Vector2<T>) {
+        for p in &mut self.positions {
+            *p -= new_origin;
+        }
+    }
+
+    /// 粒子一步最多移动一个直径
+    fn critical_velocity(&self, step: &TimeStep<T>) -> T {
+        self.diameter() * step.inv_dt
+    }
+
+    /// 在刚体求解之前调用，粒子对刚体的作用用冲量直接加到刚体的速度上
+    pub(crate) fn solve<D>(
+        &mut self,
+        step: &TimeStep<T>,
+        gravity: Vector2<T>,
+        bodies: &mut Slab<Box<Body<T, D>>>,
+        broad_phase: &BroadPhase<T, FixtureKey>,
+    ) {
+        if self.positions.is_empty() {
+            return;
+        }
+
+        let gravity = gravity * (step.dt * self.def.gravity_scale);
+        for v in &mut self.velocities {
+            *v += gravity;
+        }
+
+        self.update_contacts();
+        let body_contacts = self.update_body_contacts(bodies, broad_phase);
+        self.compute_weights(&body_contacts);
+
+        let all_flags = self
+            .flags
+            .iter()
+            .fold(ParticleFlags::empty(), |acc, flags| acc | *flags);
+
+        self.solve_pressure(step, bodies, &body_contacts);
+        self.solve_damping(step, bodies, &body_contacts);
+        if all_flags.contains(ParticleFlags::ELASTIC) {
+            self.solve_elastic(step);
+        }
+        if all_flags.contains(ParticleFlags::VISCOUS) {
+            self.solve_viscous(bodies, &body_contacts);
+        }
+        if all_flags.contains(ParticleFlags::TENSILE) {
+            self.solve_tensile(step);
+        }
+        self.limit_velocity(step);
+        self.solve_collision(step, bodies, &body_contacts);
+
+        for i in 0..self.positions.len() {
+            self.positions[i] += self.velocities[i] * step.dt;
+        }
+    }
+
+    fn update_contacts(&mut self) {
+        let diameter = self.diameter();
+        let positions = &self.positions;
+        let flags = &self.flags;
+        let contacts = &mut self.contacts;
+        contacts.clear();
+        for_each_pair(positions, 0, diameter, |a, b| {
+            let d = positions[b] - positions[a];
+            let distance = d.length();
+            contacts.push(ParticleContact {
+                a,
+                b,
+                flags: flags[a] | flags[b],
+                weight: T::one() - distance / diameter,
+                normal: d.normalize(),
+            });
+        });
+    }
+
+    fn update_body_contacts<D>(
+        &self,
+        bodies: &Slab<Box<Body<T, D>>>,
+        broad_phase: &BroadPhase<T, FixtureKey>,
+    ) -> Vec<ParticleBodyContact<T>> {
+        let diameter = self.diameter();
+        let inv_mass = T::one() / self.particle_mass();
+        let mut body_contacts = Vec::new();
+
+        for (index, p) in self.positions.iter().enumerate() {
+            let aabb = AABB {
+                lower_bound: *p - Vector2::new(diameter, diameter),
+                upper_bound: *p + Vector2::new(diameter, diameter),
+            };
+            for (_, _, key) in broad_phase.tree.query(aabb) {
+                let fixture = key.get(bodies);
+                if fixture.is_sensor() {
+                    continue;
+                }
+
+                let body = &bodies[key.body.index];
+                let (distance, normal) =
+                    fixture
+                        .shape()
+                        .compute_distance(body.transform(), p, key.child_index);
+                if distance >= diameter {
+                    continue;
+                }
+
+                let rp = *p - body.sweep.c;
+                let rpn = rp.cross(normal);
+                let inv_m = inv_mass + body.inv_mass + body.inv_i * rpn * rpn;
+                body_contacts.push(ParticleBodyContact {
+                    index,
+                    body: key.body,
+                    distance,
+                    weight: T::one() - distance / diameter,
+                    normal,
+                    mass: if inv_m > T::zero() {
+                        T::one() / inv_m
+                    } else {
+                        T::zero()
+                    },
+                });
+            }
+        }
+        body_contacts
+    }
+
+    fn compute_weights(&mut self, body_contacts: &[ParticleBodyContact<T>]) {
+        self.weights.clear();
+        self.weights.resize(self.positions.len(), T::zero());
+        for contact in body_contacts {
+            self.weights[contact.index] += contact.weight;
+        }
+        for contact in &self.contacts {
+            self.weights[contact.a] += contact.weight;
+            self.weights[contact.b] += contact.weight;
+        }
+    }
+
+    /// 根据粒子周围的密集程度产生压力，把粒子推开
+    fn solve_pressure<D>(
+        &mut self,
+        step: &TimeStep<T>,
+        bodies: &mut Slab<Box<Body<T, D>>>,
+        body_contacts: &[ParticleBodyContact<T>],
+    ) {
+        let critical_velocity = self.critical_velocity(step);
+        let critical_pressure = self.def.density * critical_velocity * critical_velocity;
+        let pressure_per_weight = self.def.pressure_strength * critical_pressure;
+        let velocity_per_pressure = step.dt / (self.def.density * self.diameter());
+        let inv_mass = T::one() / self.particle_mass();
+
+        self.pressures.clear();
+        for w in &self.weights {
+            let h = pressure_per_weight
+                * (w.min(T::f32(MAX_PARTICLE_WEIGHT)) - T::f32(MIN_PARTICLE_WEIGHT)).max(T::zero());
+            self.pressures.push(h);
+        }
+
+        for contact in body_contacts {
+            let a = contact.index;
+            let h = self.pressures[a] + pressure_per_weight * contact.weight;
+            let f = contact.normal * (velocity_per_pressure * contact.weight * contact.mass * h);
+            self.velocities[a] += f * inv_mass;
+            bodies[contact.body.index].apply_linear_impulse(-f, self.positions[a], true);
+        }
+
+        for contact in &self.contacts {
+            let h = self.pressures[contact.a] + self.pressures[contact.b];
+            let f = contact.normal * (velocity_per_pressure * contact.weight * h);
+            self.velocities[contact.a] -= f;
+            self.velocities[contact.b] += f;
+        }
+    }
+
+    /// 减小相互靠近的速度
+    fn solve_damping<D>(
+        &mut self,
+        step: &TimeStep<T>,
+        bodies: &mut Slab<Box<Body<T, D>>>,
+        body_contacts: &[ParticleBodyContact<T>],
+    ) {
+        let linear_damping = self.def.damping_strength;
+        let quadratic_damping = T::one() / self.critical_velocity(step);
+        let inv_mass = T::one() / self.particle_mass();
+
+        for contact in body_contacts {
+            let a = contact.index;
+            let p = self.positions[a];
+            let v =
+                self.velocities[a] - bodies[contact.body.index].linear_velocity_from_world_point(p);
+            let vn = v.dot(contact.normal);
+            if vn < T::zero() {
+                let damping =
+                    (linear_damping * contact.weight).max((-quadratic_damping * vn).min(T::half()));
+                let f = contact.normal * (-damping * contact.mass * vn);
+                self.velocities[a] += f * inv_mass;
+                bodies[contact.body.index].apply_linear_impulse(-f, p, true);
+            }
+        }
+
+        for contact in &self.contacts {
+            let v = self.velocities[contact.b] - self.velocities[contact.a];
+            let vn = v.dot(contact.normal);
+            if vn < T::zero() {
+                let damping =
+                    (linear_damping * contact.weight).max((-quadratic_damping * vn).min(T::half()));
+                let f = contact.normal * (damping * vn);
+                self.velocities[contact.a] += f;
+                self.velocities[contact.b] -= f;
+            }
+        }
+    }
+
+    fn solve_elastic(&mut self, step: &TimeStep<T>) {
+        let strength = step.inv_dt * self.def.elastic_strength;
+        for spring in &self.springs {
+            let pa = self.positions[spring.a] + self.velocities[spring.a] * step.dt;
+            let pb = self.positions[spring.b] + self.velocities[spring.b] * step.dt;
+            let d = pb - pa;
+            let length = d.length();
+            if length < T::epsilon() {
+                continue;
+            }
+            let f = d * (strength * (spring.length - length) / length);
+            self.velocities[spring.a] -= f;
+            self.velocities[spring.b] += f;
+        }
+    }
+
+    fn solve_viscous<D>(
+        &mut self,
+        bodies: &mut Slab<Box<Body<T, D>>>,
+        body_contacts: &[ParticleBodyContact<T>],
+    ) {
+        let viscous_strength = self.def.viscous_strength;
+        let inv_mass = T::one() / self.particle_mass();
+
+        for contact in body_contacts {
+            let a = contact.index;
+            if !self.flags[a].contains(ParticleFlags::VISCOUS) {
+                continue;
+            }
+            let p = self.positions[a];
+            let v =
+                bodies[contact.body.index].linear_velocity_from_world_point(p) - self.velocities[a];
+            let f = v * (viscous_strength * contact.mass * contact.weight);
+            self.velocities[a] += f * inv_mass;
+            bodies[contact.body.index].apply_linear_impulse(-f, p, true);
+        }
+
+        for contact in &self.contacts {
+            if !contact.flags.contains(ParticleFlags::VISCOUS) {
+                continue;
+            }
+            let v = self.velocities[contact.b] - self.velocities[contact.a];
+            let f = v * (viscous_strength * contact.weight);
+            self.velocities[contact.a] += f;
+            self.velocities[contact.b] -= f;
+        }
+    }
+
+    /// 表面上的粒子受到指向内部的力
+    fn solve_tensile(&mut self, step: &TimeStep<T>) {
+        self.surface_normals.clear();
+        self.surface_normals
+            .resize(self.positions.len(), Vector2::zero());
+        for contact in &self.contacts {
+            if !contact.flags.contains(ParticleFlags::TENSILE) {
+                continue;
+            }
+            let w = contact.weight;
+            let weighted_normal = contact.normal * ((T::one() - w) * w);
+            self.surface_normals[contact.a] -= weighted_normal;
+            self.surface_normals[contact.b] += weighted_normal;
+        }
+
+        let critical_velocity = self.critical_velocity(step);
+        let pressure_strength = self.def.surface_tension_pressure_strength * critical_velocity;
+        let normal_strength = self.def.surface_tension_normal_strength * critical_velocity;
+        let max_velocity_variation = T::f32(MAX_PARTICLE_FORCE) * critical_velocity;
+        for contact in &self.contacts {
+            if !contact.flags.contains(ParticleFlags::TENSILE) {
+                continue;
+            }
+            let h = self.weights[contact.a] + self.weights[contact.b];
+            let s = self.surface_normals[contact.b] - self.surface_normals[contact.a];
+            let fn_ = (pressure_strength * (h - T::two())
+                + normal_strength * s.dot(contact.normal))
+            .min(max_velocity_variation)
+                * contact.weight;
+            let f = contact.normal * fn_;
+            self.velocities[contact.a] -= f;
+            self.velocities[contact.b] += f;
+        }
+    }
+
+    fn limit_velocity(&mut self, step: &TimeStep<T>) {
+        let critical_velocity = self.critical_velocity(step);
+        let max_velocity_squared = critical_velocity * critical_velocity;
+        for v in &mut self.velocities {
+            let v2 = v.length_squared();
+            if v2 > max_velocity_squared {
+                *v *= (max_velocity_squared / v2).sqrt();
+            }
+        }
+    }
+
+    /// 粒子中心不能进入夹具表面一个半径以内
+    fn solve_collision<D>(
+        &mut self,
+        step: &TimeStep<T>,
+        bodies: &mut Slab<Box<Body<T, D>>>,
+        body_contacts: &[ParticleBodyContact<T>],
+    ) {
+        let radius = self.def.radius;
+        let inv_mass = T::one() / self.particle_mass();
+
+        for contact in body_contacts {
+            let a = contact.index;
+            let p = self.positions[a];
+            let vp = bodies[contact.body.index].linear_velocity_from_world_point(p);
+            let vn = (self.velocities[a] - vp).dot(contact.normal);
+
+            // 最多靠近到一个半径处，已经穿透的用位置修正推出去
+            let target = ((radius - contact.distance) * step.inv_dt).min(T::zero());
+            if vn < target {
+                let impulse = contact.normal * ((target - vn) * contact.mass);
+                self.velocities[a] += impulse * inv_mass;
+                bodies[contact.body.index].apply_linear_impulse(-impulse, p, true);
+            }
+            if contact.distance < radius {
+                self.positions[a] +=
+                    contact.normal * ((radius - contact.distance) * T::f32(PARTICLE_BAUMGARTE));
+            }
+        }
+    }
+}
+
+/// 用网格找出索引大于等于`first`并且距离小于`diameter`的所有粒子对
+fn for_each_pair<T: Real, F>(positions: &[Vector2<T>], first: usize, diameter: T, mut f: F)
+where
+    F: FnMut(usize, usize),
+{
+    let cell = |p: &Vector2<T>| {
+        (
+            (p.x / diameter).floor().to_i32(),
+            (p.y / diameter).floor().to_i32(),
+        )
+    };
+    let mut grid: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+    for (i, p) in positions.iter().enumerate().skip(first) {
+        grid.entry(cell(p)).or_default().push(i);
+    }
+
+    let diameter_squared = diameter * diameter;
+    for (a, p) in positions.iter().enumerate().skip(first) {
+        let (x, y) = cell(p);
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if let Some(indices) = grid.get(&(x + dx, y + dy)) {
+                    for &b in indices {
+                        if b > a && p.distance_squared(&positions[b]) < diameter_squared {
+                            f(a, b);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,398 @@
+//! 基于位置的绳子，类似Box2D的`b2Rope`
+//!
+//! 绳子不是刚体，由一串质点和它们之间的距离、弯曲约束组成，可以表示电缆、藤蔓和头发。
+//! 顶点会和世界中的夹具碰撞，也可以挂在刚体上，碰撞和拉力会作用到刚体。
+
+use crate::dynamic::fixture::FixtureKey;
+use crate::dynamic::time_step::TimeStep;
+use crate::{Body, BodyId, BroadPhase};
+use slab::Slab;
+use xmath::{CrossTrait, DotTrait, Real, Vector2, AABB};
+
+/// 绳子在`World`中的索引
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct RopeId(pub(crate) usize);
+
+pub struct RopeDef<T> {
+    pub vertices: Vec<Vector2<T>>,
+
+    /// 每个顶点的质量，质量为0的顶点固定不动
+    pub masses: Vec<T>,
+    pub gravity_scale: T,
+    pub damping: T,
+
+    /// 拉伸和弯曲的刚度，范围是[0,1]
+    pub stretch_stiffness: T,
+    pub bend_stiffness: T,
+
+    /// 顶点和夹具碰撞的半径，为0时不碰撞
+    pub radius: T,
+    pub iterations: usize,
+}
+
+impl<T: Real> RopeDef<T> {
+    /// 所有顶点的质量相同
+    pub fn new(vertices: Vec<Vector2<T>>, mass: T) -> RopeDef<T> {
+        let masses = vec![mass; vertices.len()];
+        RopeDef {
+            vertices,
+            masses,
+            gravity_scale: T::one(),
+            damping: T::en1(),
+            stretch_stiffness: T::one(),
+            bend_stiffness: T::half(),
+            radius: T::f32(0.05),
+            iterations: 8,
+        }
+    }
+}
+
+/// 相邻两个顶点的距离约束
+struct Stretch<T> {
+    i1: usize,
+    i2: usize,
+    length: T,
+}
+
+/// 相邻三个顶点的角度约束，保持初始的弯曲角度
+struct Bend<T> {
+    i1: usize,
+    i2: usize,
+    i3: usize,
+    angle: T,
+}
+
+/// 挂在刚体上的顶点，跟随刚体上的锚点移动
+struct Attachment<T> {
+    index: usize,
+    body: BodyId,
+    local_anchor: Vector2<T>,
+
+    // 每一步开始时从刚体取得
+    inv_mass: T,
+    inv_i: T,
+    r: Vector2<T>,
+}
+
+pub struct Rope<T> {
+    positions: Vec<Vector2<T>>,
+    prev_positions: Vec<Vector2<T>>,
+    velocities: Vec<Vector2<T>>,
+    inv_masses: Vec<T>,
+    masses: Vec<T>,
+    stretches: Vec<Stretch<T>>,
+    bends: Vec<Bend<T>>,
+    attachments: Vec<Attachment<T>>,
+    gravity_scale: T,
+    damping: T,
+    stretch_stiffness: T,
+    bend_stiffness: T,
+    radius: T,
+    iterations: usize,
+
+    // 求解时挂点受到的冲量
+    reactions: Vec<Vector2<T>>,
+}
+
+fn angle_between<T: Real>(d1: Vector2<T>, d2: Vector2<T>) -> T {
+    d1.cross(d2).atan2(d1.dot(d2))
+}
+
+impl<T: Real> Rope<T> {
+    pub(crate) fn new(def: RopeDef<T>) -> Rope<T> {
+        let count = def.vertices.len();
+        assert!(count >= 3);
+        assert_eq!(def.masses.len(), count);
+
+        let positions = def.vertices;
+        let stretches = (0..count - 1)
+            .map(|i| Stretch {
+                i1: i,
+                i2: i + 1,
+                length: (positions[i + 1] - positions[i]).length(),
+            })
+            .collect();
+        let bends = (0..count - 2)
+            .map(|i| Bend {
+                i1: i,
+                i2: i + 1,
+                i3: i + 2,
+                angle: angle_between(
+                    positions[i + 1] - positions[i],
+                    positions[i + 2] - positions[i + 1],
+                ),
+            })
+            .collect();
+        let inv_masses = def
+            .masses
+            .iter()
+            .map(|m| {
+                if *m > T::zero() {
+                    T::one() / *m
+                } else {
+                    T::zero()
+                }
+            })
+            .collect();
+
+        Rope {
+            prev_positions: positions.clone(),
+            positions,
+            velocities: vec![Vector2::zero(); count],
+            inv_masses,
+            masses: def.masses,
+            stretches,
+            bends,
+            attachments: Vec::new(),
+            gravity_scale: def.gravity_scale,
+            damping: def.damping,
+            stretch_stiffness: def.stretch_stiffness,
+            bend_stiffness: def.bend_stiffness,
+            radius: def.radius,
+            iterations: def.iterations,
+            reactions: vec![Vector2::zero(); count],
+        }
+    }
+
+    pub fn vertices(&self) -> &[Vector2<T>] {
+        &self.positions
+    }
+
+    pub fn velocities(&self) -> &[Vector2<T>] {
+        &self.velocities
+    }
+
+    pub fn radius(&self) -> T {
+        self.radius
+    }
+
+    /// 移动顶点，通常用来移动固定的顶点
+    pub fn set_vertex(&mut self, index: usize, position: Vector2<T>) {
+        self.positions[index] = position;
+        self.prev_positions[index] = position;
+        self.velocities[index] = Vector2::zero();
+    }
+
+    /// 把顶点挂在刚体的局部坐标`local_anchor`处，绳子的拉力会作用到刚体
+    pub fn attach(&mut self, index: usize, body: BodyId, local_anchor: Vector2<T>) {
+        self.detach(index);
+        self.attachments.push(Attachment {
+            index,
+            body,
+            local_anchor,
+            inv_mass: T::zero(),
+            inv_i: T::zero(),
+            r: Vector2::zero(),
+        });
+    }
+
+    pub fn detach(&mut self, index: usize) {
+        self.attachments
+            .retain(|attachment| attachment.index != index);
+    }
+
+    pub fn set_stretch_stiffness(&mut self, stiffness: T) {
+        self.stretch_stiffness = stiffness;
+    }
+
+    pub fn set_bend_stiffness(&mut self, stiffness: T) {
+        self.bend_stiffness = stiffness;
+    }
+
+    pub(crate) fn shift_origin(&mut self, new_origin: Vector2<T>) {
+        for p in self.positions.iter_mut().chain(&mut self.prev_positions) {
+            *p -= new_origin;
+        }
+    }
+
+    fn inv_mass(&self, index: usize) -> T {
+        if self.attachments.iter().any(|a| a.index == index) {
+            T::zero()
+        } else {
+            self.inv_masses[index]
+        }
+    }
+
+    /// 沿`n`方向的有效质量的倒数，挂点使用刚体在锚点处的有效质量
+    fn effective_inv_mass(&self, index: usize, n: Vector2<T>) -> (T, bool) {
+        match self.attachments.iter().find(|a| a.index == index) {
+            Some(a) => {
+                let rn = a.r.cross(n);
+                (a.inv_mass + a.inv_i * rn * rn, true)
+            }
+            None => (self.inv_masses[index], false),
+        }
+    }
+
+    /// 在刚体求解之后调用，刚体受到的冲量在下一步生效
+    pub(crate) fn step<D>(
+        &mut self,
+        step: &TimeStep<T>,
+        gravity: Vector2<T>,
+        bodies: &mut Slab<Box<Body<T, D>>>,
+        broad_phase: &BroadPhase<T, FixtureKey>,
+    ) {
+        let dt = step.dt;
+        self.attachments.retain(|attachment| {
+            bodies
+                .get(attachment.body.index)
+                .is_some_and(|body| body.id == attachment.body)
+        });
+
+        let gravity = gravity * self.gravity_scale;
+        let damping = T::one() / (T::one() + dt * self.damping);
+        for i in 0..self.positions.len() {
+            self.prev_positions[i] = self.positions[i];
+            if self.inv_mass(i) > T::zero() {
+                self.velocities[i] = (self.velocities[i] + gravity * dt) * damping;
+                self.positions[i] += self.velocities[i] * dt;
+            }
+            self.reactions[i] = Vector2::zero();
+        }
+        for attachment in &mut self.attachments {
+            let body = &bodies[attachment.body.index];
+            let p = body.world_point(attachment.local_anchor);
+            self.positions[attachment.index] = p;
+            attachment.inv_mass = body.inv_mass;
+            attachment.inv_i = body.inv_i;
+            attachment.r = p - *body.world_center();
+        }
+
+        for _ in 0..self.iterations {
+            self.solve_stretch();
+            self.solve_bend();
+            if self.radius > T::zero() {
+                self.solve_collision(step, bodies, broad_phase);
+            }
+        }
+
+        for i in 0..self.positions.len() {
+            self.velocities[i] = (self.positions[i] - self.prev_positions[i]) * step.inv_dt;
+        }
+
+        // 挂点的拉力作用到刚体上
+        for attachment in &self.attachments {
+            let body = &mut bodies[attachment.body.index];
+            let p = self.positions[attachment.index];
+            body.apply_linear_impulse(self.reactions[attachment.index] * step.inv_dt, p, true);
+        }
+    }
+
+    fn solve_stretch(&mut self) {
+        for c in &self.stretches {
+            let d = self.positions[c.i2] - self.positions[c.i1];
+            let length = d.length();
+            if length < T::epsilon() {
+                continue;
+            }
+
+            let n = d * (T::one() / length);
+            let (w1, attached1) = self.effective_inv_mass(c.i1, n);
+            let (w2, attached2) = self.effective_inv_mass(c.i2, n);
+            let sum = w1 + w2;
+            if sum == T::zero() {
+                continue;
+            }
+
+            // correction是位置修正对应的冲量乘以dt
+            let correction = n * (self.stretch_stiffness * (length - c.length) / sum);
+            self.positions[c.i1] += correction * w1;
+            self.positions[c.i2] -= correction * w2;
+            if attached1 {
+                self.reactions[c.i1] += correction;
+            }
+            if attached2 {
+                self.reactions[c.i2] -= correction;
+            }
+        }
+    }
+
+    fn solve_bend(&mut self) {
+        if self.bend_stiffness == T::zero() {
+            return;
+        }
+
+        for c in &self.bends {
+            let (p1, p2, p3) = (
+                self.positions[c.i1],
+                self.positions[c.i2],
+                self.positions[c.i3],
+            );
+            let (w1, w2, w3) = (
+                self.inv_mass(c.i1),
+                self.inv_mass(c.i2),
+                self.inv_mass(c.i3),
+            );
+            let d1 = p2 - p1;
+            let d2 = p3 - p2;
+            let l1 = d1.length_squared();
+            let l2 = d2.length_squared();
+            if l1 * l2 == T::zero() {
+                continue;
+            }
+
+            let mut angle = angle_between(d1, d2) - c.angle;
+            if angle > T::pi() {
+                angle -= T::pi_times_2();
+            } else if angle < -T::pi() {
+                angle += T::pi_times_2();
+            }
+
+            let jd1 = Vector2::new(-d1.y, d1.x) * (-T::one() / l1);
+            let jd2 = Vector2::new(-d2.y, d2.x) * (T::one() / l2);
+            let j1 = -jd1;
+            let j2 = jd1 - jd2;
+            let j3 = jd2;
+            let sum = w1 * j1.dot(j1) + w2 * j2.dot(j2) + w3 * j3.dot(j3);
+            if sum == T::zero() {
+                continue;
+            }
+
+            let impulse = -self.bend_stiffness * angle / sum;
+            self.positions[c.i1] += j1 * (w1 * impulse);
+            self.positions[c.i2] += j2 * (w2 * impulse);
+            self.positions[c.i3] += j3 * (w3 * impulse);
+        }
+    }
+
+    /// 把顶点推出夹具，推开的距离按动量作用到刚体
+    fn solve_collision<D>(
+        &mut self,
+        step: &TimeStep<T>,
+        bodies: &mut Slab<Box<Body<T, D>>>,
+        broad_phase: &BroadPhase<T, FixtureKey>,
+    ) {
+        let radius = self.radius;
+        for i in 0..self.positions.len() {
+            if self.inv_mass(i) == T::zero() {
+                continue;
+            }
+
+            let p = self.positions[i];
+            let aabb = AABB {
+                lower_bound: p - Vector2::new(radius, radius),
+                upper_bound: p + Vector2::new(radius, radius),
+            };
+            for (_, _, key) in broad_phase.tree.query(aabb) {
+                let fixture = key.get(bodies);
+                if fixture.is_sensor() {
+                    continue;
+                }
+
+                let (distance, normal) = fixture.shape().compute_distance(
+                    bodies[key.body.index].transform(),
+                    &self.positions[i],
+                    key.child_index,
+                );
+                if distance >= radius {
+                    continue;
+                }
+
+                let correction = normal * (radius - distance);
+                self.positions[i] += correction;
+                let impulse = correction * (self.masses[i] * step.inv_dt);
+                bodies[key.body.index].apply_linear_impulse(-impulse, self.positions[i], true);
+            }
+        }
+    }
+}
@@ -0,0 +1,375 @@
+//! 世界的序列化
+//!
+//! 接触和关节的冲量不会保存，反序列化后的第一次`step`会重新生成接触。
+
+use crate::dynamic::body::BodyFlags;
+use crate::dynamic::fixture::FixtureKey;
+use crate::dynamic::joints::{Joint, JointBase, JointDef, JointId};
+use crate::dynamic::world::{WorldFlags, WorldInner};
+use crate::math::Sweep;
+use crate::{
+    Body, BodyDef, BodyId, BodyType, BroadPhase, Filter, FixtureDef, FixtureId, IntoBoxedShape,
+    Shape, ShapeChain, ShapeCircle, ShapeCompound, ShapeEdge, ShapePolygon, ShapeType, World,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use slab::Slab;
+use xmath::{Real, Vector2};
+
+#[derive(Serialize)]
+enum ShapeRef<'a, T> {
+    Circle(&'a ShapeCircle<T>),
+    Edge(&'a ShapeEdge<T>),
+    Polygon(&'a ShapePolygon<T>),
+    Chain(&'a ShapeChain<T>),
+    Compound(&'a ShapeCompound<T>),
+}
+
+impl<'a, T: Real> ShapeRef<'a, T> {
+    fn new(shape: &'a dyn Shape<T>) -> ShapeRef<'a, T> {
+        let ptr = shape as *const dyn Shape<T>;
+        unsafe {
+            match shape.shape_type() {
+                ShapeType::Circle => ShapeRef::Circle(&*(ptr as *const ShapeCircle<T>)),
+                ShapeType::Edge => ShapeRef::Edge(&*(ptr as *const ShapeEdge<T>)),
+                ShapeType::Polygon => ShapeRef::Polygon(&*(ptr as *const ShapePolygon<T>)),
+                ShapeType::Chain => ShapeRef::Chain(&*(ptr as *const ShapeChain<T>)),
+                ShapeType::Compound => ShapeRef::Compound(&*(ptr as *const ShapeCompound<T>)),
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+enum ShapeData<T> {
+    Circle(ShapeCircle<T>),
+    Edge(ShapeEdge<T>),
+    Polygon(ShapePolygon<T>),
+    Chain(ShapeChain<T>),
+    Compound(ShapeCompound<T>),
+}
+
+impl<T: Real> ShapeData<T> {
+    fn into_boxed(self) -> Box<dyn Shape<T>> {
+        match self {
+            ShapeData::Circle(shape) => shape.into_boxed(),
+            ShapeData::Edge(shape) => shape.into_boxed(),
+            ShapeData::Polygon(shape) => shape.into_boxed(),
+            ShapeData::Chain(shape) => shape.into_boxed(),
+            ShapeData::Compound(shape) => shape.into_boxed(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct FixtureRef<'a, T, D> {
+    id: FixtureId,
+    shape: ShapeRef<'a, T>,
+    density: T,
+    friction: T,
+    restitution: T,
+    filter: Filter,
+    is_sensor: bool,
+    one_way_normal: Option<Vector2<T>>,
+    adhesion: Option<T>,
+    data: &'a Option<D>,
+}
+
+#[derive(Deserialize)]
+struct FixtureData<T, D> {
+    id: FixtureId,
+    shape: ShapeData<T>,
+    density: T,
+    friction: T,
+    restitution: T,
+    filter: Filter,
+    is_sensor: bool,
+    one_way_normal: Option<Vector2<T>>,
+    adhesion: Option<T>,
+    data: Option<D>,
+}
+
+#[derive(Serialize)]
+struct BodyRef<'a, T, D> {
+    id: BodyId,
+    type_: BodyType,
+    flags: u32,
+    position: Vector2<T>,
+    sweep: Sweep<T>,
+    linear_velocity: Vector2<T>,
+    angular_velocity: T,
+    force: Vector2<T>,
+    torque: T,
+    mass: T,
+    inv_mass: T,
+    i: T,
+    inv_i: T,
+    linear_damping: T,
+    angular_damping: T,
+    gravity_scale: T,
+    dominance: i8,
+    sleep_time: T,
+    fixture_inc_id: usize,
+    fixtures: Vec<FixtureRef<'a, T, D>>,
+    joint_list: &'a [JointId],
+    data: &'a Option<D>,
+}
+
+impl<'a, T: Real, D> BodyRef<'a, T, D> {
+    fn new(body: &'a Body<T, D>) -> BodyRef<'a, T, D> {
+        BodyRef {
+            id: body.id,
+            type_: body.type_,
+            flags: (body.flags - (BodyFlags::ISLAND | BodyFlags::TOI)).bits(),
+            position: body.xf.p,
+            sweep: body.sweep,
+            linear_velocity: body.linear_velocity_,
+            angular_velocity: body.angular_velocity_,
+            force: body.force,
+            torque: body.torque,
+            mass: body.mass,
+            inv_mass: body.inv_mass,
+            i: body.i,
+            inv_i: body.inv_i,
+            linear_damping: body.linear_damping,
+            angular_damping: body.angular_damping,
+            gravity_scale: body.gravity_scale,
+            dominance: body.dominance,
+            sleep_time: body.sleep_time,
+            fixture_inc_id: body.fixture_inc_id,
+            fixtures: body
+                .fixture_list
+                .iter()
+                .map(|(id, f)| FixtureRef {
+                    id: *id,
+                    shape: ShapeRef::new(f.shape.as_ref()),
+                    density: f.density,
+                    friction: f.friction,
+                    restitution: f.restitution,
+                    filter: f.filter,
+                    is_sensor: f.is_sensor,
+                    one_way_normal: f.one_way_normal,
+                    adhesion: f.adhesion,
+                    data: &f.data,
+                })
+                .collect(),
+            joint_list: &body.joint_list,
+            data: &body.data,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct BodyData<T, D> {
+    id: BodyId,
+    type_: BodyType,
+    flags: u32,
+    position: Vector2<T>,
+    sweep: Sweep<T>,
+    linear_velocity: Vector2<T>,
+    angular_velocity: T,
+    force: Vector2<T>,
+    torque: T,
+    mass: T,
+    inv_mass: T,
+    i: T,
+    inv_i: T,
+    linear_damping: T,
+    angular_damping: T,
+    gravity_scale: T,
+    dominance: i8,
+    sleep_time: T,
+    fixture_inc_id: usize,
+    fixtures: Vec<FixtureData<T, D>>,
+    joint_list: Vec<JointId>,
+    data: Option<D>,
+}
+
+impl<T: Real, D> BodyData<T, D> {
+    /// 恢复刚体，夹具重新加入广义阶段
+    fn into_body(self, broad_phase: &mut BroadPhase<T, FixtureKey>) -> Box<Body<T, D>> {
+        let def = BodyDef {
+            type_: self.type_,
+            position: self.position,
+            angle: self.sweep.a,
+            ..BodyDef::default()
+        };
+        let mut body = Box::new(Body::new(self.id, def));
+        body.flags = BodyFlags::from_bits_truncate(self.flags);
+
+        for f in self.fixtures {
+            body.create_fixture(
+                broad_phase,
+                FixtureDef {
+                    shape: f.shape.into_boxed(),
+                    data: f.data,
+                    friction: f.friction,
+                    restitution: f.restitution,
+                    density: f.density,
+                    is_sensor: f.is_sensor,
+                    filter: f.filter,
+                    one_way_normal: f.one_way_normal,
+                    adhesion: f.adhesion,
+                },
+            );
+            let (id, fixture) = body.fixture_list.last_mut().unwrap();
+            *id = f.id;
+            fixture.id = f.id;
+        }
+        body.fixture_inc_id = self.fixture_inc_id;
+
+        body.mass = self.mass;
+        body.inv_mass = self.inv_mass;
+        body.i = self.i;
+        body.inv_i = self.inv_i;
+        body.sweep = self.sweep;
+        body.linear_velocity_ = self.linear_velocity;
+        body.angular_velocity_ = self.angular_velocity;
+        body.force = self.force;
+        body.torque = self.torque;
+        body.linear_damping = self.linear_damping;
+        body.angular_damping = self.angular_damping;
+        body.gravity_scale = self.gravity_scale;
+        body.dominance = self.dominance;
+        body.sleep_time = self.sleep_time;
+        body.joint_list = self.joint_list;
+        body.data = self.data;
+        body
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct JointData<T> {
+    id: JointId,
+    def: JointDef<T>,
+    break_force: Option<T>,
+    break_torque: Option<T>,
+}
+
+impl<T: Real> JointData<T> {
+    fn into_joint<D>(
+        self,
+        world: &WorldInner<T, D>,
+        joints: &Slab<Box<Joint<T>>>,
+    ) -> Box<Joint<T>> {
+        let (body_a, body_b) = self.def.bodies(joints);
+        let base = JointBase {
+            body_a,
+            body_b,
+            collide_connected: self.def.collide_connected(),
+            island: false,
+            break_force: self.break_force,
+            break_torque: self.break_torque,
+            wake: false,
+        };
+        Box::new(Joint::new(base, self.def, joints, &world.bodies_slab))
+    }
+}
+
+#[derive(Serialize)]
+struct WorldRef<'a, T, D> {
+    gravity: Vector2<T>,
+    allow_sleep: bool,
+    warm_starting: bool,
+    continuous_physics: bool,
+    sub_stepping: bool,
+    auto_clear_forces: bool,
+    inv_dt0: T,
+    next_generation: u32,
+    bodies: Vec<BodyRef<'a, T, D>>,
+    joints: Vec<JointData<T>>,
+}
+
+#[derive(Deserialize)]
+struct WorldData<T, D> {
+    gravity: Vector2<T>,
+    allow_sleep: bool,
+    warm_starting: bool,
+    continuous_physics: bool,
+    sub_stepping: bool,
+    auto_clear_forces: bool,
+    inv_dt0: T,
+    next_generation: u32,
+    bodies: Vec<BodyData<T, D>>,
+    joints: Vec<JointData<T>>,
+}
+
+impl<T: Real + Serialize, D: Serialize> Serialize for World<T, D> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let world = &self.0;
+        WorldRef {
+            gravity: world.gravity,
+            allow_sleep: world.allow_sleep,
+            warm_starting: world.warm_starting,
+            continuous_physics: world.continuous_physics,
+            sub_stepping: world.sub_stepping,
+            auto_clear_forces: world.flags.contains(WorldFlags::CLEAR_FORCES),
+            inv_dt0: world.inv_dt0,
+            next_generation: world.next_generation,
+            bodies: world
+                .bodies_slab
+                .iter()
+                .map(|(_, body)| BodyRef::new(body))
+                .collect(),
+            joints: world
+                .joints_slab
+                .iter()
+                .map(|(index, joint)| JointData {
+                    id: JointId(index),
+                    def: joint.def(),
+                    break_force: joint.break_force(),
+                    break_torque: joint.break_torque(),
+                })
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, T, D> Deserialize<'de> for World<T, D>
+where
+    T: Real + Deserialize<'de>,
+    D: Deserialize<'de>,
+{
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let data = WorldData::<T, D>::deserialize(deserializer)?;
+
+        let mut world = World::new(data.gravity);
+        let inner = world.0.as_mut();
+        inner.allow_sleep = data.allow_sleep;
+        inner.warm_starting = data.warm_starting;
+        inner.continuous_physics = data.continuous_physics;
+        inner.sub_stepping = data.sub_stepping;
+        inner
+            .flags
+            .set(WorldFlags::CLEAR_FORCES, data.auto_clear_forces);
+        inner.inv_dt0 = data.inv_dt0;
+        inner.next_generation = data.next_generation;
+
+        // 保持句柄不变，刚体和关节要放回原来的槽位
+        let broad_phase = &mut inner.contact_manager.broad_phase;
+        let bodies = data
+            .bodies
+            .into_iter()
+            .map(|body| (body.id.index, body.into_body(broad_phase)))
+            .collect();
+        inner.bodies_slab = bodies;
+
+        // 齿轮关节依赖它连接的关节，最后创建
+        let (gears, joints): (Vec<_>, Vec<_>) = data
+            .joints
+            .into_iter()
+            .partition(|joint| matches!(joint.def, JointDef::Gear(_)));
+        let empty = Slab::new();
+        let joints: Slab<_> = joints
+            .into_iter()
+            .map(|joint| (joint.id.0, joint.into_joint(inner, &empty)))
+            .collect();
+        let gears = gears
+            .into_iter()
+            .map(|joint| (joint.id.0, joint.into_joint(inner, &joints)))
+            .collect::<Vec<_>>();
+        inner.joints_slab = joints.into_iter().chain(gears).collect();
+
+        Ok(world)
+    }
+}
@@ -0,0 +1,27 @@
+//! 步进中使用的临时数组
+//!
+//! 作用和Box2D的`b2StackAllocator`相同，数组保存在世界中，每次求解时清空后复用，
+//! 场景稳定之后步进就不再分配内存。
+
+use crate::dynamic::contacts::{ContactPositionConstraint, ContactVelocityConstraint};
+
+pub(crate) struct StackAllocator<T> {
+    pub(crate) position_constraints: Vec<ContactPositionConstraint<T>>,
+    pub(crate) velocity_constraints: Vec<ContactVelocityConstraint<T>>,
+}
+
+impl<T> Default for StackAllocator<T> {
+    fn default() -> Self {
+        StackAllocator {
+            position_constraints: Vec::new(),
+            velocity_constraints: Vec::new(),
+        }
+    }
+}
+
+impl<T> StackAllocator<T> {
+    pub(crate) fn reserve(&mut self, contacts: usize) {
+        self.position_constraints.reserve(contacts);
+        self.velocity_constraints.reserve(contacts);
+    }
+}
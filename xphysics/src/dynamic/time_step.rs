@@ -1,7 +1,7 @@
 use std::time::Duration;
 use xmath::Vector2;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Copy, Clone)]
 pub struct Profile {
     pub step: Duration,
     pub collide: Duration,
@@ -11,6 +11,18 @@ pub struct Profile {
     pub solve_position: Duration,
     pub broad_phase: Duration,
     pub solve_toi: Duration,
+
+    /// 在岛中求解的非静态刚体数量
+    pub body_count: usize,
+
+    /// 接触中的流形点数量
+    pub contact_point_count: usize,
+
+    /// 求解的TOI事件数量
+    pub toi_count: usize,
+
+    /// broad-phase查找新的配对时查询动态树的次数
+    pub tree_query_count: usize,
 }
 
 #[derive(Copy, Clone)]
@@ -35,8 +47,8 @@ pub struct Velocity<T> {
     pub w: T,
 }
 
-//pub struct SolverData<'a, T> {
-//    pub step: TimeStep<T>,
-//    pub positions: &'a [Position<T>],
-//    pub velocities: &'a [Velocity<T>],
-//}
+pub struct SolverData<'a, T> {
+    pub step: TimeStep<T>,
+    pub positions: &'a mut [Position<T>],
+    pub velocities: &'a mut [Velocity<T>],
+}
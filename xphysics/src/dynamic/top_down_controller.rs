@@ -0,0 +1,132 @@
+//! 俯视角游戏的角色移动
+//!
+//! 只用世界的查询接口移动一个形状，不需要创建刚体，也不会推动世界中的刚体。
+
+use crate::collision::distance::{distance, DistanceInput, SimpleCache};
+use crate::{settings, BodyType, Fixture, Shape, World};
+use xmath::{DotTrait, Real, Rotation, Transform, Vector2};
+
+pub struct TopDownController {
+    /// 一次移动中最多沿表面滑动的次数
+    pub max_iterations: usize,
+
+    /// 推出重叠的迭代次数
+    pub recover_iterations: usize,
+}
+
+impl Default for TopDownController {
+    fn default() -> Self {
+        TopDownController {
+            max_iterations: 4,
+            recover_iterations: 4,
+        }
+    }
+}
+
+fn is_obstacle<T: Real, D>(world: &World<T, D>, fixture: &Fixture<T, D>) -> bool {
+    !fixture.is_sensor()
+        && world.0.bodies_slab[fixture.body().index].body_type() == BodyType::Static
+}
+
+impl TopDownController {
+    pub fn new() -> TopDownController {
+        Default::default()
+    }
+
+    /// 把放在`from`处的形状移动`delta`，返回最终的位置
+    ///
+    /// 开始时和静态夹具重叠的形状会先被推出来，移动时碰到静态夹具会沿着表面滑动。
+    pub fn move_shape<T: Real, D>(
+        &self,
+        world: &World<T, D>,
+        shape: &dyn Shape<T>,
+        from: Vector2<T>,
+        delta: Vector2<T>,
+    ) -> Vector2<T> {
+        let mut position = self.recover(world, shape, from);
+        let mut remaining = delta;
+        let mut prev_normal: Option<Vector2<T>> = None;
+
+        for _ in 0..self.max_iterations {
+            if remaining.length_squared() < T::epsilon() * T::epsilon() {
+                break;
+            }
+
+            let xf = Transform::new(position, Rotation::identity());
+            let hit = match world
+                .shape_cast(shape, xf, remaining, |fixture| is_obstacle(world, fixture))
+            {
+                Some(hit) => hit,
+                None => {
+                    position += remaining;
+                    break;
+                }
+            };
+
+            position += remaining * hit.fraction;
+            remaining *= T::one() - hit.fraction;
+            remaining -= hit.normal * hit.normal.dot(remaining);
+
+            // 夹在两个表面之间时停下
+            if let Some(prev_normal) = prev_normal {
+                if prev_normal.dot(remaining) < T::zero() {
+                    break;
+                }
+            }
+            prev_normal = Some(hit.normal);
+        }
+
+        position
+    }
+
+    /// 把形状推出和它重叠的静态夹具，核心重叠的形状无法确定方向，不会被推出
+    fn recover<T: Real, D>(
+        &self,
+        world: &World<T, D>,
+        shape: &dyn Shape<T>,
+        mut position: Vector2<T>,
+    ) -> Vector2<T> {
+        let slop = settings::linear_slop::<T>();
+
+        for _ in 0..self.recover_iterations {
+            let xf = Transform::new(position, Rotation::identity());
+            let mut push = Vector2::zero();
+
+            for fixture in world.overlap_shape(shape, xf, |fixture| is_obstacle(world, fixture)) {
+                let xf_b = *world.0.bodies_slab[fixture.body().index].transform();
+                for index_b in 0..fixture.shape().child_count() {
+                    let proxy_b = fixture.shape().distance_proxy(index_b);
+                    for index_a in 0..shape.child_count() {
+                        let proxy_a = shape.distance_proxy(index_a);
+                        let output = distance(
+                            &DistanceInput {
+                                proxy_a: &proxy_a,
+                                proxy_b: &proxy_b,
+                                transform_a: xf,
+                                transform_b: xf_b,
+                                use_radii: false,
+                            },
+                            &mut SimpleCache::default(),
+                        );
+
+                        // 和TOI的目标距离一致，允许很薄的重叠
+                        let target = slop.max(proxy_a.radius + proxy_b.radius - T::i32(3) * slop);
+                        if output.distance < T::epsilon() || output.distance >= target {
+                            continue;
+                        }
+                        let normal =
+                            (output.point_a - output.point_b) * (T::one() / output.distance);
+                        push += normal * (target - output.distance);
+                    }
+                }
+            }
+
+            if push.length_squared() < T::epsilon() * T::epsilon() {
+                break;
+            }
+            position += push;
+        }
+
+        position
+    }
+}
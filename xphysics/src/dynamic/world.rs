@@ -1,21 +1,37 @@
+use crate::collision::distance::{DistanceInput, SimpleCache};
 use crate::collision::dynamic_tree;
 use crate::collision::time_of_impact::{time_of_impact, TOIInput, TOIOutputState};
 use crate::dynamic::body::{BodyDef, BodyFlags};
 use crate::dynamic::contact_manager::ContactManager;
-use crate::dynamic::contacts::{ContactFilter, ContactFlags, ContactListener};
-use crate::dynamic::fixture::FixtureProxy;
-use crate::dynamic::island::Island;
+use crate::dynamic::contacts::{
+    Contact, ContactEvent, ContactFilter, ContactFlags, ContactKey, ContactListener, MaterialMixer,
+};
+use crate::dynamic::fixture::FixtureKey;
+use crate::dynamic::force_generator::{ForceGenerator, ForceGeneratorId};
+use crate::dynamic::island::{Island, IslandInfo};
+use crate::dynamic::joints::{Joint, JointBase, JointBroken, JointDef, JointId, MotorJointDef};
+use crate::dynamic::particle_system::{ParticleSystem, ParticleSystemDef, ParticleSystemId};
+use crate::dynamic::rope::{Rope, RopeDef, RopeId};
+use crate::dynamic::stack_allocator::StackAllocator;
 use crate::dynamic::time_step::{Profile, TimeStep};
+use crate::math::Sweep;
 use crate::timer::Timer;
 use crate::{
-    settings, Body, BodyType, Fixture, FixtureDef, RayCastInput, Shape, ShapeChain, ShapeCircle,
-    ShapeEdge, ShapePolygon, ShapeType,
+    collision, settings, Body, BodyType, Filter, Fixture, FixtureDef, FixtureId, MassData,
+    RayCastInput, Shape, ShapeChain, ShapeCircle, ShapeCompound, ShapeEdge, ShapePolygon,
+    ShapeType,
 };
 use slab::Slab;
-use xmath::{Multiply, Real, Rotation, Transform, Vector2, AABB};
-
-#[derive(Debug, Copy, Clone)]
-pub struct BodyId(usize);
+use std::rc::Rc;
+use xmath::{DotTrait, Multiply, Real, Rotation, Transform, Vector2, AABB};
+
+/// 刚体句柄，刚体删除后旧的句柄不会指向新创建的刚体
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BodyId {
+    pub(crate) index: usize,
+    pub(crate) generation: u32,
+}
 
 bitflags! {
     pub struct WorldFlags: u32 {
@@ -27,6 +43,22 @@ bitflags! {
 
 pub trait DestructionListener<T, D> {
     fn fixture_destroyed(&self, fixture: &Fixture<T, D>);
+
+    /// 刚体被删除时，连接到它的关节也会被删除
+    fn joint_destroyed(&self, _joint: &Joint<T>) {}
+
+    /// 关节受力超过阈值，在步进结束时被删除
+    fn joint_broken(&self, _event: &JointBroken<T>) {}
+
+    /// 刚体离开了世界边界，在步进结束时按`OutOfBoundsAction`停用或删除之前调用
+    fn body_out_of_bounds(&self, _body: &Body<T, D>) {}
+}
+
+/// 刚体离开世界边界时的处理方式，见`World::set_bounds`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum OutOfBoundsAction {
+    Deactivate,
+    Destroy,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -52,6 +84,17 @@ bitflags! {
         const SHAPE = 0x0001;
         const AABB = 0x0002;
         const CENTER_OF_MASS = 0x0004;
+        const JOINT = 0x0008;
+        const PARTICLE = 0x0010;
+        const ROPE = 0x0020;
+        const CONTACT_POINT = 0x0040;
+        const CONTACT_NORMAL = 0x0080;
+        const CONTACT_IMPULSE = 0x0100;
+        const FRICTION_IMPULSE = 0x0200;
+        /// 动态树的内部节点，颜色按高度从绿色变到红色
+        const TREE = 0x0400;
+        /// broad-phase中配对的代理之间的连线
+        const PAIRS = 0x0800;
     }
 }
 
@@ -71,15 +114,20 @@ pub trait DebugDraw {
     fn draw_point(&mut self, p: &Vector2<f32>, color: Color);
 }
 
+type StepHook<T, D> = Box<dyn FnMut(&mut World<T, D>, T)>;
+
 pub(crate) struct WorldInner<T, D> {
     pub(crate) bodies_slab: Slab<Box<Body<T, D>>>,
+    pub(crate) joints_slab: Slab<Box<Joint<T>>>,
     pub(crate) flags: WorldFlags,
     pub(crate) contact_manager: ContactManager<T, D>,
-    pub(crate) body_list: *mut Body<T, D>,
-    pub(crate) body_count: usize,
+    pub(crate) next_generation: u32,
     pub(crate) gravity: Vector2<T>,
     pub(crate) allow_sleep: bool,
     pub(crate) destruction_listener: Option<Box<dyn DestructionListener<T, D>>>,
+    pub(crate) force_generators: Slab<Box<dyn ForceGenerator<T, D>>>,
+    pub(crate) particle_systems: Slab<ParticleSystem<T>>,
+    pub(crate) ropes: Slab<Rope<T>>,
     pub(crate) debug_draw_flags: DebugDrawFlags,
     pub(crate) debug_draw: Option<Box<dyn DebugDraw>>,
     pub(crate) inv_dt0: T,
@@ -88,22 +136,39 @@ pub(crate) struct WorldInner<T, D> {
     pub(crate) sub_stepping: bool,
     pub(crate) step_complete: bool,
     pub(crate) profile: Profile,
+    pub(crate) islands: Vec<IslandInfo>,
+    pub(crate) island_bodies: Vec<BodyId>,
+    pub(crate) bounds: Option<(AABB<T>, OutOfBoundsAction)>,
+    pub(crate) pre_step: Option<StepHook<T, D>>,
+    pub(crate) post_step: Option<StepHook<T, D>>,
+    pub(crate) stack_allocator: StackAllocator<T>,
+    pub(crate) island: Island<T>,
+    pub(crate) island_stack: Vec<BodyId>,
 }
 
-pub struct World<T, D>(Box<WorldInner<T, D>>);
+pub struct World<T, D>(pub(crate) Box<WorldInner<T, D>>);
 
 impl<T: Real, D> World<T, D> {
     pub fn new(gravity: Vector2<T>) -> World<T, D> {
         World(Box::new(WorldInner {
             bodies_slab: Default::default(),
+            joints_slab: Default::default(),
             flags: WorldFlags::CLEAR_FORCES,
             contact_manager: ContactManager::new(),
-            body_list: std::ptr::null_mut(),
-            body_count: 0,
+            next_generation: 0,
             gravity,
             allow_sleep: true,
             destruction_listener: None,
-            debug_draw_flags: DebugDrawFlags::all(),
+            force_generators: Default::default(),
+            particle_systems: Default::default(),
+            ropes: Default::default(),
+            debug_draw_flags: DebugDrawFlags::all()
+                - DebugDrawFlags::CONTACT_POINT
+                - DebugDrawFlags::CONTACT_NORMAL
+                - DebugDrawFlags::CONTACT_IMPULSE
+                - DebugDrawFlags::FRICTION_IMPULSE
+                - DebugDrawFlags::TREE
+                - DebugDrawFlags::PAIRS,
             debug_draw: None,
             inv_dt0: T::zero(),
             warm_starting: true,
@@ -111,6 +176,14 @@ impl<T: Real, D> World<T, D> {
             sub_stepping: false,
             step_complete: true,
             profile: Profile::default(),
+            islands: Vec::new(),
+            island_bodies: Vec::new(),
+            bounds: None,
+            pre_step: None,
+            post_step: None,
+            stack_allocator: StackAllocator::default(),
+            island: Island::default(),
+            island_stack: Vec::new(),
         }))
     }
 
@@ -141,6 +214,126 @@ impl<T: Real, D> World<T, D> {
         self.0.contact_manager.contact_listener = Some(Box::new(listener));
     }
 
+    /// 设置新接触的摩擦系数和恢复系数的计算方式，已有的接触要调用`Contact::reset_friction`等方法更新
+    pub fn set_material_mixer<M: MaterialMixer<T, D> + 'static>(&mut self, mixer: M) {
+        let mixer: Rc<dyn MaterialMixer<T, D>> = Rc::new(mixer);
+        for (_, c) in &mut self.0.contact_manager.contacts {
+            c.mixer = mixer.clone();
+        }
+        self.0.contact_manager.material_mixer = mixer;
+    }
+
+    pub fn add_force_generator<G: ForceGenerator<T, D> + 'static>(
+        &mut self,
+        generator: G,
+    ) -> ForceGeneratorId {
+        ForceGeneratorId(self.0.force_generators.insert(Box::new(generator)))
+    }
+
+    pub fn remove_force_generator(
+        &mut self,
+        id: ForceGeneratorId,
+    ) -> Option<Box<dyn ForceGenerator<T, D>>> {
+        self.0.force_generators.try_remove(id.0)
+    }
+
+    /// 设置每次步进开始时调用的函数，参数是世界和时间步长
+    ///
+    /// 调用时世界没有锁定，可以施加力或者创建和删除刚体，新的夹具在这一步就会产生接触。
+    pub fn set_pre_step<F: FnMut(&mut World<T, D>, T) + 'static>(&mut self, f: F) {
+        self.0.pre_step = Some(Box::new(f));
+    }
+
+    /// 设置每次步进结束时调用的函数，这时积分和边界检查都已经完成，世界没有锁定
+    pub fn set_post_step<F: FnMut(&mut World<T, D>, T) + 'static>(&mut self, f: F) {
+        self.0.post_step = Some(Box::new(f));
+    }
+
+    pub fn clear_step_hooks(&mut self) {
+        self.0.pre_step = None;
+        self.0.post_step = None;
+    }
+
+    /// 粒子系统不会随世界一起序列化
+    pub fn create_particle_system(&mut self, def: ParticleSystemDef<T>) -> ParticleSystemId {
+        ParticleSystemId(self.0.particle_systems.insert(ParticleSystem::new(def)))
+    }
+
+    pub fn destroy_particle_system(&mut self, id: ParticleSystemId) {
+        assert!(!self.is_locked());
+        self.0.particle_systems.try_remove(id.0);
+    }
+
+    pub fn particle_system(&self, id: ParticleSystemId) -> Option<&ParticleSystem<T>> {
+        self.0.particle_systems.get(id.0)
+    }
+
+    pub fn particle_system_mut(&mut self, id: ParticleSystemId) -> Option<&mut ParticleSystem<T>> {
+        self.0.particle_systems.get_mut(id.0)
+    }
+
+    /// 绳子不会随世界一起序列化
+    pub fn create_rope(&mut self, def: RopeDef<T>) -> RopeId {
+        RopeId(self.0.ropes.insert(Rope::new(def)))
+    }
+
+    pub fn destroy_rope(&mut self, id: RopeId) {
+        assert!(!self.is_locked());
+        self.0.ropes.try_remove(id.0);
+    }
+
+    pub fn rope(&self, id: RopeId) -> Option<&Rope<T>> {
+        self.0.ropes.get(id.0)
+    }
+
+    pub fn rope_mut(&mut self, id: RopeId) -> Option<&mut Rope<T>> {
+        self.0.ropes.get_mut(id.0)
+    }
+
+    /// 开启后步进时的接触事件会保存下来，用`drain_contact_events`取出，关闭时丢弃未取出的事件
+    pub fn set_contact_events_enabled(&mut self, flag: bool) {
+        let events = &mut self.0.contact_manager.contact_events;
+        if flag {
+            events.get_or_insert_with(Vec::new);
+        } else {
+            *events = None;
+        }
+    }
+
+    pub fn contact_events_enabled(&self) -> bool {
+        self.0.contact_manager.contact_events.is_some()
+    }
+
+    /// 取出上次调用以来记录的接触事件
+    pub fn drain_contact_events(&mut self) -> impl Iterator<Item = ContactEvent<T>> + '_ {
+        self.0
+            .contact_manager
+            .contact_events
+            .iter_mut()
+            .flat_map(|events| events.drain(..))
+    }
+
+    /// 设置世界边界，`None`表示没有边界
+    ///
+    /// 步进结束时，包围盒不完全在边界内的非静态刚体会按`action`停用或删除，避免穿透的刚体飞向无穷远。
+    pub fn set_bounds(&mut self, bounds: Option<AABB<T>>, action: OutOfBoundsAction) {
+        self.0.bounds = bounds.map(|bounds| (bounds, action));
+    }
+
+    pub fn bounds(&self) -> Option<AABB<T>> {
+        self.0.bounds.map(|(bounds, _)| bounds)
+    }
+
+    /// 为`bodies`个刚体和`contacts`个接触预留空间，避免大场景在前几步中反复扩容
+    pub fn reserve(&mut self, bodies: usize, contacts: usize) {
+        let world = self.0.as_mut();
+        world.bodies_slab.reserve(bodies);
+        world.contact_manager.contacts.reserve(contacts);
+        world.island.reserve(bodies, contacts);
+        world.island_stack.reserve(bodies);
+        world.stack_allocator.reserve(contacts);
+    }
+
     pub fn is_locked(&self) -> bool {
         self.0.flags.contains(WorldFlags::LOCKED)
     }
@@ -156,55 +349,56 @@ impl<T: Real, D> World<T, D> {
     pub fn shift_origin(&mut self, new_origin: Vector2<T>) {
         assert!(!self.0.flags.contains(WorldFlags::LOCKED));
 
-        unsafe {
-            let mut b = self.0.body_list;
-            while b.is_null() {
-                (*b).xf.p -= new_origin;
-                (*b).sweep.c0 -= new_origin;
-                (*b).sweep.c -= new_origin;
-                b = (*b).next_ptr;
-            }
-            self.0.contact_manager.broad_phase.shift_origin(new_origin);
+        for (_, b) in &mut self.0.bodies_slab {
+            b.xf.p -= new_origin;
+            b.sweep.c0 -= new_origin;
+            b.sweep.c -= new_origin;
+        }
+        for (_, system) in &mut self.0.particle_systems {
+            system.shift_origin(new_origin);
         }
+        for (_, rope) in &mut self.0.ropes {
+            rope.shift_origin(new_origin);
+        }
+        self.0.contact_manager.broad_phase.shift_origin(new_origin);
     }
 
     pub fn clear_forces(&mut self) {
-        unsafe {
-            let mut b = self.0.body_list;
-            while !b.is_null() {
-                (*b).force = Vector2::zero();
-                (*b).torque = T::zero();
-                b = (*b).next_ptr;
-            }
+        for (_, b) in &mut self.0.bodies_slab {
+            b.force = Vector2::zero();
+            b.torque = T::zero();
         }
     }
 
     pub fn body(&self, id: BodyId) -> Option<&Body<T, D>> {
-        self.0.bodies_slab.get(id.0).map(|body| body.as_ref())
+        self.0
+            .bodies_slab
+            .get(id.index)
+            .filter(|body| body.id == id)
+            .map(|body| body.as_ref())
     }
 
     pub fn body_mut(&mut self, id: BodyId) -> Option<&mut Body<T, D>> {
-        self.0.bodies_slab.get_mut(id.0).map(|body| body.as_mut())
+        self.0
+            .bodies_slab
+            .get_mut(id.index)
+            .filter(|body| body.id == id)
+            .map(|body| body.as_mut())
+    }
+
+    fn insert_body(&mut self, def: BodyDef<T, D>) -> BodyId {
+        let entry = self.0.bodies_slab.vacant_entry();
+        let id = BodyId {
+            index: entry.key(),
+            generation: self.0.next_generation,
+        };
+        self.0.next_generation = self.0.next_generation.wrapping_add(1);
+        entry.insert(Box::new(Body::new(id, def)));
+        id
     }
 
     pub fn create_body(&mut self, def: BodyDef<T, D>) -> BodyId {
-        unsafe {
-            let world_ptr = self.0.as_mut() as *mut WorldInner<T, D>;
-            let id = self
-                .0
-                .bodies_slab
-                .insert(Box::new(Body::new(world_ptr, def)));
-            let body = self.0.bodies_slab.get_unchecked_mut(id);
-
-            body.prev_ptr = std::ptr::null_mut();
-            body.next_ptr = self.0.body_list;
-            if !self.0.body_list.is_null() {
-                (*self.0.body_list).prev_ptr = body.as_mut();
-            }
-            self.0.body_list = body.as_mut();
-            self.0.body_count += 1;
-            BodyId(id)
-        }
+        self.insert_body(def)
     }
 
     pub fn create_body_with_fixture(
@@ -212,87 +406,514 @@ impl<T: Real, D> World<T, D> {
         def: BodyDef<T, D>,
         fixture_def: FixtureDef<T, D>,
     ) -> BodyId {
-        unsafe {
-            let world_ptr = self.0.as_mut() as *mut WorldInner<T, D>;
-            let id = self
-                .0
-                .bodies_slab
-                .insert(Box::new(Body::new(world_ptr, def)));
-            let body = self.0.bodies_slab.get_unchecked_mut(id);
-
-            body.prev_ptr = std::ptr::null_mut();
-            body.next_ptr = self.0.body_list;
-            if !self.0.body_list.is_null() {
-                (*self.0.body_list).prev_ptr = body.as_mut();
-            }
-            self.0.body_list = body.as_mut();
-            self.0.body_count += 1;
-
-            body.create_fixture(fixture_def);
-
-            BodyId(id)
-        }
+        let id = self.insert_body(def);
+        self.create_fixture(id, fixture_def);
+        id
     }
 
     pub fn create_body_with_fixtures<IT>(&mut self, def: BodyDef<T, D>, fixtures_def: IT) -> BodyId
     where
         IT: IntoIterator<Item = FixtureDef<T, D>>,
     {
-        unsafe {
-            let world_ptr = self.0.as_mut() as *mut WorldInner<T, D>;
-            let id = self
-                .0
-                .bodies_slab
-                .insert(Box::new(Body::new(world_ptr, def)));
-            let body = self.0.bodies_slab.get_unchecked_mut(id);
-
-            body.prev_ptr = std::ptr::null_mut();
-            body.next_ptr = self.0.body_list;
-            if !self.0.body_list.is_null() {
-                (*self.0.body_list).prev_ptr = body.as_mut();
+        let id = self.insert_body(def);
+        for fixture_def in fixtures_def {
+            self.create_fixture(id, fixture_def);
+        }
+        id
+    }
+
+    /// 删除刚体以及连接它的关节和接触，无效的句柄会被忽略
+    pub fn destroy_body(&mut self, id: BodyId) {
+        assert!(!self.0.flags.contains(WorldFlags::LOCKED));
+
+        let joint_list = match self.body(id) {
+            Some(body) => body.joint_list.clone(),
+            None => return,
+        };
+        for joint_id in joint_list {
+            // 齿轮关节可能已经随着它连接的关节一起删除了
+            let joint = match self.0.joints_slab.get(joint_id.0) {
+                Some(joint) => joint,
+                None => continue,
+            };
+            if let Some(l) = &self.0.destruction_listener {
+                l.joint_destroyed(joint);
             }
-            self.0.body_list = body.as_mut();
-            self.0.body_count += 1;
+            self.remove_joint(joint_id);
+        }
+
+        let world = self.0.as_mut();
+        let edges = std::mem::take(&mut world.bodies_slab[id.index].contact_edges);
+        for edge in edges {
+            world
+                .contact_manager
+                .destroy(edge.contact, &mut world.bodies_slab);
+        }
 
-            for fixture_def in fixtures_def {
-                body.create_fixture(fixture_def);
+        let mut body = self.0.bodies_slab.remove(id.index);
+        for (_, f) in &mut body.fixture_list {
+            if let Some(l) = &self.0.destruction_listener {
+                l.fixture_destroyed(f.as_ref());
             }
+            f.destroy_proxies(&mut self.0.contact_manager.broad_phase);
+        }
+    }
 
-            BodyId(id)
+    /// 直接设置刚体原点的位置和角度，会破坏接触，最好不要在每一帧都调用
+    pub fn set_body_transform(&mut self, id: BodyId, position: Vector2<T>, angle: T) {
+        assert!(!self.0.flags.contains(WorldFlags::LOCKED));
+        let world = self.0.as_mut();
+        if let Some(body) = world.bodies_slab.get_mut(id.index).filter(|b| b.id == id) {
+            body.set_transform(&mut world.contact_manager.broad_phase, position, angle);
         }
     }
 
-    pub fn destroy_body(&mut self, id: BodyId) {
-        unsafe {
-            assert!(self.0.flags.contains(WorldFlags::LOCKED));
-            let body = self.0.bodies_slab.get_unchecked_mut(id.0);
-
-            let mut ce = body.contact_list_ptr;
-            while !ce.is_null() {
-                let ce0 = ce;
-                ce = (*ce).next_ptr;
-                self.0.contact_manager.destroy((*ce0).contact_ptr);
+    /// 修改刚体类型，会删除刚体已有的接触
+    pub fn set_body_type(&mut self, id: BodyId, type_: BodyType) {
+        assert!(!self.0.flags.contains(WorldFlags::LOCKED));
+
+        let world = self.0.as_mut();
+        let body = match world.bodies_slab.get_mut(id.index).filter(|b| b.id == id) {
+            Some(body) => body,
+            None => return,
+        };
+        let contact_manager = &mut world.contact_manager;
+        let broad_phase = &mut contact_manager.broad_phase;
+        if body.type_ == type_ {
+            return;
+        }
+        body.type_ = type_;
+        body.reset_mass_data();
+
+        if body.type_ == BodyType::Static {
+            body.linear_velocity_ = Vector2::zero();
+            body.angular_velocity_ = T::zero();
+            body.sweep.a0 = body.sweep.a;
+            body.sweep.c0 = body.sweep.c;
+            body.synchronize_fixtures(broad_phase);
+        }
+
+        body.set_awake(true);
+
+        body.force = Vector2::zero();
+        body.torque = T::zero();
+
+        for (_, f) in &body.fixture_list {
+            for proxy in &f.proxies {
+                broad_phase.touch_proxy(proxy.proxy_id);
             }
+        }
+
+        let edges = std::mem::take(&mut body.contact_edges);
+        for edge in edges {
+            contact_manager.destroy(edge.contact, &mut world.bodies_slab);
+        }
+    }
+
+    /// 停用的刚体不参与碰撞和求解，停用时会删除它的代理和接触
+    pub fn set_body_active(&mut self, id: BodyId, flag: bool) {
+        let world = self.0.as_mut();
+        let body = match world.bodies_slab.get_mut(id.index).filter(|b| b.id == id) {
+            Some(body) => body,
+            None => return,
+        };
+        let contact_manager = &mut world.contact_manager;
+        let broad_phase = &mut contact_manager.broad_phase;
+        if flag == body.is_active() {
+            return;
+        }
 
+        if flag {
+            body.flags.insert(BodyFlags::ACTIVE);
+            let xf = body.xf;
+            for (index, (_, f)) in body.fixture_list.iter_mut().enumerate() {
+                f.create_proxies(broad_phase, xf, index);
+            }
+        } else {
+            body.flags.remove(BodyFlags::ACTIVE);
             for (_, f) in &mut body.fixture_list {
-                if let Some(l) = &(*body.world_ptr).destruction_listener {
-                    l.fixture_destroyed(f.as_ref());
+                f.destroy_proxies(broad_phase);
+            }
+
+            let edges = std::mem::take(&mut body.contact_edges);
+            for edge in edges {
+                contact_manager.destroy(edge.contact, &mut world.bodies_slab);
+            }
+        }
+    }
+
+    /// 用自定义的质量、质心和转动惯量覆盖由夹具密度计算的质量数据，只对动态刚体有效
+    pub fn set_body_mass_data(&mut self, id: BodyId, mass_data: &MassData<T>) {
+        assert!(!self.0.flags.contains(WorldFlags::LOCKED));
+        if let Some(body) = self.body_mut(id) {
+            body.set_mass_data(mass_data);
+        }
+    }
+
+    /// 刚体参与的接触，包括还没有接触上的(AABB重叠)
+    pub fn body_contacts(&self, id: BodyId) -> impl Iterator<Item = &Contact<T, D>> {
+        let contacts = &self.0.contact_manager.contacts;
+        self.body(id).into_iter().flat_map(move |body| {
+            body.contact_edges
+                .iter()
+                .map(move |edge| contacts[edge.contact.0].as_ref())
+        })
+    }
+
+    /// 给刚体添加夹具，无效的刚体句柄会panic
+    pub fn create_fixture(&mut self, body: BodyId, def: FixtureDef<T, D>) -> FixtureId {
+        let world = self.0.as_mut();
+        let body = world
+            .bodies_slab
+            .get_mut(body.index)
+            .filter(|b| b.id == body)
+            .expect("invalid body");
+        let id = body.create_fixture(&mut world.contact_manager.broad_phase, def);
+        self.0.flags.insert(WorldFlags::NEW_FIXTURE);
+        id
+    }
+
+    pub fn create_fixture_with_shape(
+        &mut self,
+        body: BodyId,
+        shape: Box<dyn Shape<T>>,
+        density: T,
+    ) -> FixtureId {
+        self.create_fixture(body, FixtureDef::new(shape, density))
+    }
+
+    /// 删除夹具和它参与的接触，无效的句柄会被忽略
+    pub fn destroy_fixture(&mut self, body: BodyId, fixture: FixtureId) {
+        assert!(!self.0.flags.contains(WorldFlags::LOCKED));
+
+        let world = self.0.as_mut();
+        let body = match world
+            .bodies_slab
+            .get_mut(body.index)
+            .filter(|b| b.id == body)
+        {
+            Some(body) => body,
+            None => return,
+        };
+        let index = match body.fixture_list.iter().position(|item| item.0 == fixture) {
+            Some(index) => index,
+            None => return,
+        };
+        let body_id = body.id;
+        let is_fixture = |key: &FixtureKey| key.body == body_id && key.fixture == index;
+
+        let mut edges = std::mem::take(&mut body.contact_edges);
+        let contact_manager = &mut world.contact_manager;
+        let bodies = &mut world.bodies_slab;
+        edges.retain(|edge| {
+            let c = &contact_manager.contacts[edge.contact.0];
+            if is_fixture(&c.fixture_a) || is_fixture(&c.fixture_b) {
+                contact_manager.destroy(edge.contact, bodies);
+                return false;
+            }
+            true
+        });
+
+        let body = &mut world.bodies_slab[body_id.index];
+        let broad_phase = &mut world.contact_manager.broad_phase;
+        let (_, mut fixture) = body.fixture_list.remove(index);
+        if body.flags.contains(BodyFlags::ACTIVE) {
+            fixture.destroy_proxies(broad_phase);
+        }
+
+        // 后面的夹具在列表中前移了一位
+        for (i, (_, f)) in body.fixture_list.iter_mut().enumerate().skip(index) {
+            f.set_index(broad_phase, i);
+        }
+        for edge in &edges {
+            let c = &mut world.contact_manager.contacts[edge.contact.0];
+            for key in [&mut c.fixture_a, &mut c.fixture_b] {
+                if key.body == body_id && key.fixture > index {
+                    key.fixture -= 1;
                 }
-                f.destroy_proxies(&mut (*body.world_ptr).contact_manager.broad_phase);
             }
+        }
+        body.contact_edges = edges;
+        body.reset_mass_data();
+    }
 
-            if !body.prev_ptr.is_null() {
-                (*body.prev_ptr).next_ptr = body.next_ptr;
+    /// 切换夹具是否是传感器，会唤醒刚体
+    pub fn set_fixture_sensor(&mut self, body: BodyId, fixture: FixtureId, sensor: bool) {
+        let body = match self.body_mut(body) {
+            Some(body) => body,
+            None => return,
+        };
+        let changed = match body.fixture_mut(fixture) {
+            Some(fixture) if fixture.is_sensor != sensor => {
+                fixture.is_sensor = sensor;
+                true
             }
-            if !body.next_ptr.is_null() {
-                (*body.next_ptr).prev_ptr = body.prev_ptr;
+            _ => false,
+        };
+        if changed {
+            body.set_awake(true);
+        }
+    }
+
+    /// 修改夹具的碰撞过滤，已有的接触会在下一步重新检查
+    pub fn set_fixture_filter(&mut self, body: BodyId, fixture: FixtureId, filter: Filter) {
+        let world = self.0.as_mut();
+        let body = match world
+            .bodies_slab
+            .get_mut(body.index)
+            .filter(|b| b.id == body)
+        {
+            Some(body) => body,
+            None => return,
+        };
+        let index = match body.fixture_list.iter().position(|item| item.0 == fixture) {
+            Some(index) => index,
+            None => return,
+        };
+        let fixture = &mut body.fixture_list[index].1;
+        if fixture.filter == filter {
+            return;
+        }
+        fixture.filter = filter;
+
+        for proxy in &fixture.proxies {
+            world
+                .contact_manager
+                .broad_phase
+                .touch_proxy(proxy.proxy_id);
+        }
+        let is_fixture = |key: FixtureKey| key.body == body.id && key.fixture == index;
+        for edge in &body.contact_edges {
+            let contact = &mut world.contact_manager.contacts[edge.contact.0];
+            if is_fixture(contact.fixture_a) || is_fixture(contact.fixture_b) {
+                contact.flag_for_filtering();
             }
-            if body.as_mut() as *mut Body<T, D> == self.0.body_list {
-                self.0.body_list = body.next_ptr;
+        }
+    }
+
+    /// 删除所有的刚体、关节、接触、粒子系统和绳子，用来重新开始关卡
+    ///
+    /// 已经分配的空间会保留下来，监听器、力场和世界的设置不变。删除时不会调用任何监听器，
+    /// 旧的刚体句柄仍然无效。
+    pub fn clear(&mut self) {
+        assert!(!self.0.flags.contains(WorldFlags::LOCKED));
+
+        let world = self.0.as_mut();
+        world.contact_manager.clear();
+        world.joints_slab.clear();
+        world.bodies_slab.clear();
+        world.particle_systems.clear();
+        world.ropes.clear();
+        world.islands.clear();
+        world.island_bodies.clear();
+        world.flags.remove(WorldFlags::NEW_FIXTURE);
+    }
+
+    pub fn joint(&self, id: JointId) -> Option<&Joint<T>> {
+        self.0.joints_slab.get(id.0).map(|joint| joint.as_ref())
+    }
+
+    pub fn joint_mut(&mut self, id: JointId) -> Option<&mut Joint<T>> {
+        self.0.joints_slab.get_mut(id.0).map(|joint| joint.as_mut())
+    }
+
+    pub fn create_joint<J: Into<JointDef<T>>>(&mut self, def: J) -> JointId {
+        assert!(!self.0.flags.contains(WorldFlags::LOCKED));
+
+        let def = def.into();
+        let (body_a, body_b) = def.bodies(&self.0.joints_slab);
+        let collide_connected = def.collide_connected();
+        assert!(self.body(body_a).is_some(), "invalid body a");
+        assert!(self.body(body_b).is_some(), "invalid body b");
+        assert_ne!(body_a, body_b);
+
+        let base = JointBase {
+            body_a,
+            body_b,
+            collide_connected,
+            island: false,
+            break_force: None,
+            break_torque: None,
+            wake: false,
+        };
+        let joint = Joint::new(base, def, &self.0.joints_slab, &self.0.bodies_slab);
+        let id = JointId(self.0.joints_slab.insert(Box::new(joint)));
+
+        self.0.bodies_slab[body_a.index].joint_list.push(id);
+        self.0.bodies_slab[body_b.index].joint_list.push(id);
+        if !collide_connected {
+            self.flag_contacts_for_filtering(body_a, body_b);
+        }
+        id
+    }
+
+    /// 删除关节，连接这个关节的齿轮关节也会一起删除
+    pub fn destroy_joint(&mut self, id: JointId) {
+        assert!(!self.0.flags.contains(WorldFlags::LOCKED));
+        self.remove_joint(id);
+    }
+
+    fn remove_joint(&mut self, id: JointId) {
+        let joint = match self.0.joints_slab.contains(id.0) {
+            true => self.0.joints_slab.remove(id.0),
+            false => return,
+        };
+        let base = joint.base();
+
+        for body in &[base.body_a, base.body_b] {
+            let body = &mut self.0.bodies_slab[body.index];
+            body.set_awake(true);
+            body.joint_list.retain(|joint_id| *joint_id != id);
+        }
+        if !base.collide_connected {
+            self.flag_contacts_for_filtering(base.body_a, base.body_b);
+
+            // 重叠的两个刚体不移动时也要重新生成接触
+            let world = self.0.as_mut();
+            let broad_phase = &mut world.contact_manager.broad_phase;
+            for (_, fixture) in &world.bodies_slab[base.body_b.index].fixture_list {
+                for proxy in &fixture.proxies {
+                    broad_phase.touch_proxy(proxy.proxy_id);
+                }
             }
+        }
 
-            self.0.bodies_slab.remove(id.0);
-            self.0.body_count -= 1;
+        let gears = self
+            .0
+            .joints_slab
+            .iter()
+            .filter_map(|(index, joint)| match joint.as_ref() {
+                Joint::Gear(gear) if gear.joint1() == id || gear.joint2() == id => {
+                    Some(JointId(index))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for gear in gears {
+            if let Some(l) = &self.0.destruction_listener {
+                l.joint_destroyed(&self.0.joints_slab[gear.0]);
+            }
+            self.remove_joint(gear);
+        }
+    }
+
+    fn check_bounds(&mut self) {
+        let (bounds, action) = match self.0.bounds {
+            Some(bounds) => bounds,
+            None => return,
+        };
+
+        let tree = &self.0.contact_manager.broad_phase.tree;
+        let out_of_bounds = self
+            .0
+            .bodies_slab
+            .iter()
+            .filter(|(_, body)| {
+                body.body_type() != BodyType::Static && body.is_awake() && body.is_active()
+            })
+            .filter(|(_, body)| {
+                let point = AABB::new(body.xf.p, body.xf.p);
+                let aabb = body
+                    .fixture_list
+                    .iter()
+                    .flat_map(|(_, f)| &f.proxies)
+                    .fold(point, |aabb, proxy| {
+                        aabb.combine(tree.get_fat_aabb(proxy.proxy_id))
+                    });
+                !bounds.contains(&aabb)
+            })
+            .map(|(_, body)| body.id)
+            .collect::<Vec<_>>();
+
+        for id in out_of_bounds {
+            if let Some(l) = &self.0.destruction_listener {
+                l.body_out_of_bounds(&self.0.bodies_slab[id.index]);
+            }
+            match action {
+                OutOfBoundsAction::Deactivate => self.set_body_active(id, false),
+                OutOfBoundsAction::Destroy => self.destroy_body(id),
+            }
+        }
+    }
+
+    fn break_joints(&mut self, inv_dt: T) {
+        let bodies = &self.0.bodies_slab;
+        let broken = self
+            .0
+            .joints_slab
+            .iter()
+            .filter(|(_, joint)| {
+                let base = joint.base();
+                let awake =
+                    bodies[base.body_a.index].is_awake() || bodies[base.body_b.index].is_awake();
+                awake && joint.should_break(inv_dt)
+            })
+            .map(|(index, joint)| JointBroken {
+                joint: JointId(index),
+                joint_type: joint.joint_type(),
+                body_a: joint.body_a(),
+                body_b: joint.body_b(),
+                reaction_force: joint.reaction_force(inv_dt),
+                reaction_torque: joint.reaction_torque(inv_dt),
+            })
+            .collect::<Vec<_>>();
+
+        for event in broken {
+            if !self.0.joints_slab.contains(event.joint.0) {
+                continue;
+            }
+            self.remove_joint(event.joint);
+            if let Some(l) = &self.0.destruction_listener {
+                l.joint_broken(&event);
+            }
+        }
+    }
+
+    /// 把这一步中开始接触的粘性夹具所在的刚体用马达关节固定在当前的相对位置
+    ///
+    /// 已经有关节连接的两个刚体不会再粘一次。
+    fn create_bonds(&mut self) {
+        let bonds = std::mem::take(&mut self.0.contact_manager.pending_bonds);
+        for (body_a, body_b, break_force) in bonds {
+            let connected = match (self.body(body_a), self.body(body_b)) {
+                (Some(a), Some(_)) => a.joint_list.iter().any(|id| {
+                    let joint = &self.0.joints_slab[id.0];
+                    joint.body_a() == body_b || joint.body_b() == body_b
+                }),
+                _ => continue,
+            };
+            if connected {
+                continue;
+            }
+
+            let id = self.create_joint(MotorJointDef {
+                max_force: T::max_value(),
+                max_torque: T::max_value(),
+                ..MotorJointDef::with_bodies(self, body_a, body_b)
+            });
+            self.0.joints_slab[id.0].set_break_force(Some(break_force));
+        }
+    }
+
+    /// 修改过参数的关节在步进开始时唤醒它连接的刚体
+    fn wake_joint_bodies(&mut self) {
+        let world = self.0.as_mut();
+        for (_, joint) in &mut world.joints_slab {
+            let base = joint.base_mut();
+            if base.wake {
+                base.wake = false;
+                world.bodies_slab[base.body_a.index].set_awake(true);
+                world.bodies_slab[base.body_b.index].set_awake(true);
+            }
+        }
+    }
+
+    fn flag_contacts_for_filtering(&mut self, body_a: BodyId, body_b: BodyId) {
+        let world = self.0.as_mut();
+        for edge in &world.bodies_slab[body_b.index].contact_edges {
+            if edge.other == body_a {
+                world.contact_manager.contacts[edge.contact.0].flag_for_filtering();
+            }
         }
     }
 
@@ -335,412 +956,478 @@ impl<T: Real, D> World<T, D> {
 
         self.0.allow_sleep = flag;
         if !self.0.allow_sleep {
-            unsafe {
-                let mut b = self.0.body_list;
-                while !b.is_null() {
-                    (*b).set_awake(true);
-                    b = (*b).next_ptr;
-                }
+            for (_, b) in &mut self.0.bodies_slab {
+                b.set_awake(true);
             }
         }
     }
 
     fn solve(&mut self, step: &TimeStep<T>) {
-        unsafe {
-            self.0.profile.solve_init = Default::default();
-            self.0.profile.solve_velocity = Default::default();
-            self.0.profile.solve_position = Default::default();
-
-            let mut island = Island::new(
-                self.0.body_count,
-                self.0.contact_manager.contact_count,
-                &self.0.contact_manager.contact_listener,
-            );
+        let world = self.0.as_mut();
+        world.profile.solve_init = Default::default();
+        world.profile.solve_velocity = Default::default();
+        world.profile.solve_position = Default::default();
+
+        let mut island = std::mem::take(&mut world.island);
+        island.prepare(
+            world.bodies_slab.len(),
+            world.contact_manager.contacts.len(),
+            world.joints_slab.len(),
+        );
+
+        for (_, b) in &mut world.bodies_slab {
+            b.flags.remove(BodyFlags::ISLAND);
+            b.island = None;
+        }
+        world.islands.clear();
+        world.island_bodies.clear();
 
-            let mut b = self.0.body_list;
-            while !b.is_null() {
-                (*b).flags.remove(BodyFlags::ISLAND);
-                b = (*b).next_ptr;
-            }
+        for (_, c) in &mut world.contact_manager.contacts {
+            c.flags.remove(ContactFlags::ISLAND);
+        }
 
-            let mut c = self.0.contact_manager.contact_list;
-            while !c.is_null() {
-                (*c).flags.remove(ContactFlags::ISLAND);
-                c = (*c).next_ptr;
+        for (_, joint) in &mut world.joints_slab {
+            joint.base_mut().island = false;
+        }
+
+        let mut stack = std::mem::take(&mut world.island_stack);
+        stack.clear();
+        for index in 0..world.bodies_slab.capacity() {
+            let seed = match world.bodies_slab.get_mut(index) {
+                Some(body) => body,
+                None => continue,
+            };
+            if seed.flags.contains(BodyFlags::ISLAND) {
+                continue;
+            }
+            if !seed.is_awake() || !seed.is_active() {
+                continue;
+            }
+            if seed.body_type() == BodyType::Static {
+                continue;
             }
 
-            let stack_size = self.0.body_count;
-            let mut stack = Vec::with_capacity(stack_size);
-            let mut seed = self.0.body_list;
-            while !seed.is_null() {
-                if (*seed).flags.contains(BodyFlags::ISLAND) {
-                    seed = (*seed).next_ptr;
-                    continue;
-                }
-                if !(*seed).is_awake() || !(*seed).is_active() {
-                    seed = (*seed).next_ptr;
-                    continue;
-                }
-                if (*seed).body_type() == BodyType::Static {
-                    seed = (*seed).next_ptr;
-                    continue;
-                }
+            island.clear();
 
-                island.clear();
+            stack.push(seed.id);
+            seed.flags.insert(BodyFlags::ISLAND);
+            let seed = seed.id;
 
-                stack.push(seed);
-                (*seed).flags.insert(BodyFlags::ISLAND);
+            while let Some(id) = stack.pop() {
+                let b = &mut world.bodies_slab[id.index];
+                assert!(b.is_active());
+                island.add_body(b);
 
-                while let Some(b) = stack.pop() {
-                    assert!((*b).is_active());
-                    island.add_body(b);
+                b.flags.insert(BodyFlags::AWAKE);
+                if b.body_type() == BodyType::Static {
+                    continue;
+                }
 
-                    (*b).flags.insert(BodyFlags::AWAKE);
-                    if (*b).body_type() == BodyType::Static {
+                for i in 0..b.contact_edges.len() {
+                    let edge = world.bodies_slab[id.index].contact_edges[i];
+                    let contact = &mut world.contact_manager.contacts[edge.contact.0];
+                    if contact.flags.contains(ContactFlags::ISLAND) {
+                        continue;
+                    }
+                    if !contact.is_enable() || !contact.is_touching() {
                         continue;
                     }
 
-                    let mut ce = (*b).contact_list_ptr;
-                    while !ce.is_null() {
-                        let contact = (*ce).contact_ptr;
-                        if !(*contact).is_enable() || !(*contact).is_touching() {
-                            ce = (*ce).next_ptr;
-                            continue;
-                        }
+                    let sensor_a = contact.fixture_a.get(&world.bodies_slab).is_sensor;
+                    let sensor_b = contact.fixture_b.get(&world.bodies_slab).is_sensor;
+                    if sensor_a || sensor_b {
+                        continue;
+                    }
 
-                        let sensor_a = (*(*contact).fixture_a_ptr).is_sensor;
-                        let sensor_b = (*(*contact).fixture_b_ptr).is_sensor;
-                        if sensor_a || sensor_b {
-                            ce = (*ce).next_ptr;
-                            continue;
-                        }
+                    island.add_contact(edge.contact);
+                    contact.flags.insert(ContactFlags::ISLAND);
 
-                        island.add_contact(contact);
-                        (*contact).flags.insert(ContactFlags::ISLAND);
+                    let other = &mut world.bodies_slab[edge.other.index];
+                    if other.flags.contains(BodyFlags::ISLAND) {
+                        continue;
+                    }
 
-                        let other = (*ce).other_ptr;
-                        if (*other).flags.contains(BodyFlags::ISLAND) {
-                            ce = (*ce).next_ptr;
-                            continue;
-                        }
+                    stack.push(other.id);
+                    other.flags.insert(BodyFlags::ISLAND);
+                }
 
-                        stack.push(other);
-                        (*other).flags.insert(BodyFlags::ISLAND);
-                        ce = (*ce).next_ptr;
+                for i in 0..world.bodies_slab[id.index].joint_list.len() {
+                    let joint_id = world.bodies_slab[id.index].joint_list[i];
+                    let base = world.joints_slab[joint_id.0].base_mut();
+                    if base.island {
+                        continue;
                     }
-                }
 
-                let mut profile = Profile::default();
-                island.solve(&mut profile, step, self.0.gravity, self.0.allow_sleep);
-                self.0.profile.solve_init += profile.solve_init;
-                self.0.profile.solve_velocity += profile.solve_velocity;
-                self.0.profile.solve_position += profile.solve_position;
+                    let other = if base.body_a == id {
+                        base.body_b
+                    } else {
+                        base.body_a
+                    };
+                    let other = &mut world.bodies_slab[other.index];
+                    if !other.is_active() {
+                        continue;
+                    }
+
+                    island.add_joint(joint_id);
+                    base.island = true;
 
-                for i in 0..island.bodies.len() {
-                    let b = island.bodies[i];
-                    if (*b).body_type() == BodyType::Static {
-                        (*b).flags.remove(BodyFlags::ISLAND);
+                    if other.flags.contains(BodyFlags::ISLAND) {
+                        continue;
                     }
-                }
 
-                seed = (*seed).next_ptr;
+                    stack.push(other.id);
+                    other.flags.insert(BodyFlags::ISLAND);
+                }
             }
 
-            let timer = Timer::new();
-            let mut b = self.0.body_list;
-            while !b.is_null() {
-                if !(*b).flags.contains(BodyFlags::ISLAND) {
-                    b = (*b).next_ptr;
-                    continue;
+            let bodies = &world.bodies_slab;
+            world.profile.body_count += island
+                .bodies
+                .iter()
+                .filter(|id| bodies[id.index].body_type() != BodyType::Static)
+                .count();
+
+            let mut profile = Profile::default();
+            island.solve(&mut profile, step, world);
+            world.profile.solve_init += profile.solve_init;
+            world.profile.solve_velocity += profile.solve_velocity;
+            world.profile.solve_position += profile.solve_position;
+
+            let start = world.island_bodies.len();
+            for id in &island.bodies {
+                let b = &mut world.bodies_slab[id.index];
+                if b.body_type() == BodyType::Static {
+                    b.flags.remove(BodyFlags::ISLAND);
+                } else {
+                    b.island = Some(world.islands.len());
+                    world.island_bodies.push(b.id);
                 }
-                if (*b).body_type() == BodyType::Static {
-                    b = (*b).next_ptr;
-                    continue;
-                }
-                (*b).synchronize_fixtures();
-                b = (*b).next_ptr;
             }
+            world.islands.push(IslandInfo {
+                bodies: start..world.island_bodies.len(),
+                contact_count: island.contacts.len(),
+                joint_count: island.joints.len(),
+                sleeping: !world.bodies_slab[seed.index].is_awake(),
+            });
+        }
+        island.clear();
+        world.island = island;
+        world.island_stack = stack;
 
-            self.0.contact_manager.find_new_contacts();
-            self.0.profile.broad_phase = timer.get_duration();
+        let timer = Timer::new();
+        for (_, b) in &mut world.bodies_slab {
+            if !b.flags.contains(BodyFlags::ISLAND) {
+                continue;
+            }
+            if b.body_type() == BodyType::Static {
+                continue;
+            }
+            b.synchronize_fixtures(&mut world.contact_manager.broad_phase);
         }
+
+        world
+            .contact_manager
+            .find_new_contacts(&mut world.bodies_slab, &world.joints_slab);
+        world.profile.broad_phase = timer.get_duration();
     }
 
     fn solve_toi(&mut self, step: &TimeStep<T>) {
-        unsafe {
-            let mut island = Island::new(
-                2 * settings::MAX_TOI_CONTACTS,
-                settings::MAX_TOI_CONTACTS,
-                (&self.0.contact_manager.contact_listener
-                    as *const Option<Box<dyn ContactListener<T, D>>>)
-                    .as_ref()
-                    .unwrap(),
-            );
+        let world = self.0.as_mut();
+        let mut island = std::mem::take(&mut world.island);
+        island.prepare(
+            2 * settings::MAX_TOI_CONTACTS,
+            settings::MAX_TOI_CONTACTS,
+            0,
+        );
+
+        if world.step_complete {
+            for (_, b) in &mut world.bodies_slab {
+                b.flags.remove(BodyFlags::ISLAND);
+            }
+
+            for (_, c) in &mut world.contact_manager.contacts {
+                c.flags.remove(ContactFlags::TOI | ContactFlags::ISLAND);
+                c.toi_count = 0;
+            }
+        }
 
-            if self.0.step_complete {
-                let mut b = self.0.body_list;
-                while !b.is_null() {
-                    (*b).flags.remove(BodyFlags::ISLAND);
-                    b = (*b).next_ptr;
+        loop {
+            let mut min_contact = None;
+            let mut min_alpha = T::one();
+
+            for (index, c) in world.contact_manager.contacts.iter_mut() {
+                if !c.is_enable() {
+                    continue;
                 }
 
-                let mut c = self.0.contact_manager.contact_list;
-                while !c.is_null() {
-                    (*c).flags.remove(ContactFlags::TOI | ContactFlags::ISLAND);
-                    (*c).toi_count = 0;
-                    c = (*c).next_ptr;
+                if c.toi_count > settings::MAX_SUB_STEPS {
+                    continue;
                 }
-            }
 
-            loop {
-                let mut min_contact = std::ptr::null_mut();
-                let mut min_alpha = T::one();
+                let alpha;
+                if c.flags.contains(ContactFlags::TOI) {
+                    alpha = c.toi;
+                } else {
+                    let (ba, bb) = world
+                        .bodies_slab
+                        .get2_mut(c.fixture_a.body.index, c.fixture_b.body.index)
+                        .unwrap();
+                    let fa = &ba.fixture_list[c.fixture_a.fixture].1;
+                    let fb = &bb.fixture_list[c.fixture_b.fixture].1;
 
-                let mut c = self.0.contact_manager.contact_list;
-                while !c.is_null() {
-                    if !(*c).is_enable() {
-                        c = (*c).next_ptr;
+                    if fa.is_sensor || fb.is_sensor {
                         continue;
                     }
 
-                    if (*c).toi_count > settings::MAX_SUB_STEPS {
-                        c = (*c).next_ptr;
+                    let type_a = ba.type_;
+                    let type_b = bb.type_;
+                    assert!(type_a == BodyType::Dynamic || type_b == BodyType::Dynamic);
+
+                    let active_a = ba.is_awake() && type_a != BodyType::Static;
+                    let active_b = bb.is_awake() && type_b != BodyType::Static;
+
+                    if !active_a && !active_b {
                         continue;
                     }
 
-                    let alpha;
-                    if (*c).flags.contains(ContactFlags::TOI) {
-                        alpha = (*c).toi;
-                    } else {
-                        let fa = (*c).fixture_a_ptr;
-                        let fb = (*c).fixture_b_ptr;
+                    let collide_a = ba.is_bullet() || type_a != BodyType::Dynamic;
+                    let collide_b = bb.is_bullet() || type_b != BodyType::Dynamic;
 
-                        if (*fa).is_sensor || (*fb).is_sensor {
-                            c = (*c).next_ptr;
-                            continue;
-                        }
+                    if !collide_a && !collide_b {
+                        continue;
+                    }
 
-                        let ba = (*fa).body_ptr;
-                        let bb = (*fb).body_ptr;
+                    let mut alpha0 = ba.sweep.alpha0;
 
-                        let type_a = (*ba).type_;
-                        let type_b = (*bb).type_;
-                        assert!(type_a == BodyType::Dynamic || type_b == BodyType::Dynamic);
+                    if ba.sweep.alpha0 < bb.sweep.alpha0 {
+                        alpha0 = bb.sweep.alpha0;
+                        ba.sweep.advance(alpha0);
+                    } else if bb.sweep.alpha0 < ba.sweep.alpha0 {
+                        alpha0 = ba.sweep.alpha0;
+                        bb.sweep.advance(alpha0);
+                    }
 
-                        let active_a = (*ba).is_awake() && type_a != BodyType::Static;
-                        let active_b = (*bb).is_awake() && type_b != BodyType::Static;
+                    assert!(alpha0 < T::one());
 
-                        if !active_a && !active_b {
-                            c = (*c).next_ptr;
-                            continue;
-                        }
+                    let fa = &ba.fixture_list[c.fixture_a.fixture].1;
+                    let fb = &bb.fixture_list[c.fixture_b.fixture].1;
+                    let input = TOIInput {
+                        proxy_a: &fa.shape.distance_proxy(c.child_index_a()),
+                        proxy_b: &fb.shape.distance_proxy(c.child_index_b()),
+                        sweep_a: ba.sweep,
+                        sweep_b: bb.sweep,
+                        max: T::one(),
+                    };
 
-                        let collide_a = (*ba).is_bullet() || type_a != BodyType::Dynamic;
-                        let collide_b = (*bb).is_bullet() || type_b != BodyType::Dynamic;
+                    let output = time_of_impact(input);
 
-                        if !collide_a && !collide_b {
-                            c = (*c).next_ptr;
-                            continue;
-                        }
+                    let beta = output.t;
+                    if output.state == TOIOutputState::Touching {
+                        alpha = T::min(alpha0 + (T::one() - alpha0) * beta, T::one());
+                    } else {
+                        alpha = T::one();
+                    }
 
-                        let mut alpha0 = (*ba).sweep.alpha0;
+                    c.toi = alpha;
+                    c.flags.insert(ContactFlags::TOI);
+                }
 
-                        if (*ba).sweep.alpha0 < (*bb).sweep.alpha0 {
-                            alpha0 = (*bb).sweep.alpha0;
-                            (*ba).sweep.advance(alpha0);
-                        } else if (*bb).sweep.alpha0 < (*ba).sweep.alpha0 {
-                            alpha0 = (*ba).sweep.alpha0;
-                            (*bb).sweep.advance(alpha0);
-                        }
+                if alpha < min_alpha {
+                    min_contact = Some(ContactKey(index));
+                    min_alpha = alpha;
+                }
+            }
 
-                        assert!(alpha0 < T::one());
+            let min_contact = match min_contact {
+                Some(id) if min_alpha <= T::one() - T::ten() * T::epsilon() => id,
+                _ => {
+                    world.step_complete = true;
+                    break;
+                }
+            };
+
+            let c = &world.contact_manager.contacts[min_contact.0];
+            let (id_a, id_b) = (c.fixture_a.body, c.fixture_b.body);
+            let (ba, bb) = world.bodies_slab.get2_mut(id_a.index, id_b.index).unwrap();
+
+            let backup1 = ba.sweep;
+            let backup2 = bb.sweep;
+
+            ba.advance(min_alpha);
+            bb.advance(min_alpha);
+
+            world
+                .contact_manager
+                .update(min_contact, &mut world.bodies_slab);
+            let c = &mut world.contact_manager.contacts[min_contact.0];
+            c.flags.remove(ContactFlags::TOI);
+            c.toi_count += 1;
+
+            let (ba, bb) = world.bodies_slab.get2_mut(id_a.index, id_b.index).unwrap();
+            if !c.is_enable() || !c.is_touching() {
+                c.set_enable(false);
+                ba.sweep = backup1;
+                bb.sweep = backup2;
+                ba.synchronize_transform();
+                bb.synchronize_transform();
+                continue;
+            }
 
-                        let index_a = (*c).child_index_a();
-                        let index_b = (*c).child_index_b();
+            ba.set_awake(true);
+            bb.set_awake(true);
 
-                        let input = TOIInput {
-                            proxy_a: &(*fa).shape.distance_proxy(index_a),
-                            proxy_b: &(*fb).shape.distance_proxy(index_b),
-                            sweep_a: (*ba).sweep,
-                            sweep_b: (*bb).sweep,
-                            max: T::one(),
-                        };
+            island.clear();
+            island.add_body(ba);
+            island.add_body(bb);
+            island.add_contact(min_contact);
 
-                        let output = time_of_impact(input);
+            ba.flags.insert(BodyFlags::ISLAND);
+            bb.flags.insert(BodyFlags::ISLAND);
+            c.flags.insert(ContactFlags::ISLAND);
 
-                        let beta = output.t;
-                        if output.state == TOIOutputState::Touching {
-                            alpha = T::min(alpha0 + (T::one() - alpha0) * beta, T::one());
-                        } else {
-                            alpha = T::one();
-                        }
+            for &id in &[id_a, id_b] {
+                let body = &world.bodies_slab[id.index];
+                if body.type_ != BodyType::Dynamic {
+                    continue;
+                }
+                let bullet = body.is_bullet();
 
-                        (*c).toi = alpha;
-                        (*c).flags.insert(ContactFlags::TOI);
+                for i in 0..body.contact_edges.len() {
+                    if island.bodies.len() == island.body_capacity {
+                        break;
                     }
 
-                    if alpha < min_alpha {
-                        min_contact = c;
-                        min_alpha = alpha;
+                    if island.contacts.len() == island.contact_capacity {
+                        break;
                     }
 
-                    c = (*c).next_ptr;
-                }
-
-                if min_contact.is_null() || T::one() - T::ten() * T::epsilon() < min_alpha {
-                    self.0.step_complete = true;
-                    break;
-                }
-
-                let fa = (*min_contact).fixture_a_ptr;
-                let fb = (*min_contact).fixture_b_ptr;
-                let mut ba = (*fa).body_ptr;
-                let mut bb = (*fb).body_ptr;
+                    let edge = world.bodies_slab[id.index].contact_edges[i];
+                    let contact = &world.contact_manager.contacts[edge.contact.0];
+                    if contact.flags.contains(ContactFlags::ISLAND) {
+                        continue;
+                    }
 
-                let backup1 = (*ba).sweep;
-                let backup2 = (*bb).sweep;
+                    let other = &world.bodies_slab[edge.other.index];
+                    if other.type_ == BodyType::Dynamic && !bullet && !other.is_bullet() {
+                        continue;
+                    }
 
-                (*ba).advance(min_alpha);
-                (*bb).advance(min_alpha);
+                    let sensor_a = contact.fixture_a.get(&world.bodies_slab).is_sensor;
+                    let sensor_b = contact.fixture_b.get(&world.bodies_slab).is_sensor;
+                    if sensor_a || sensor_b {
+                        continue;
+                    }
 
-                (*min_contact).update(&self.0.contact_manager.contact_listener);
-                (*min_contact).flags.remove(ContactFlags::TOI);
-                (*min_contact).toi_count += 1;
+                    let other = &mut world.bodies_slab[edge.other.index];
+                    let backup = other.sweep;
+                    if !other.flags.contains(BodyFlags::ISLAND) {
+                        other.advance(min_alpha);
+                    }
 
-                if !(*min_contact).is_enable() || !(*min_contact).is_touching() {
-                    (*min_contact).set_enable(false);
-                    (*ba).sweep = backup1;
-                    (*bb).sweep = backup2;
-                    (*ba).synchronize_transform();
-                    (*bb).synchronize_transform();
-                    continue;
-                }
+                    world
+                        .contact_manager
+                        .update(edge.contact, &mut world.bodies_slab);
 
-                (*ba).set_awake(true);
-                (*bb).set_awake(true);
-
-                island.clear();
-                island.add_body(ba);
-                island.add_body(bb);
-                island.add_contact(min_contact);
-
-                (*ba).flags.insert(BodyFlags::ISLAND);
-                (*bb).flags.insert(BodyFlags::ISLAND);
-                (*min_contact).flags.insert(ContactFlags::ISLAND);
-
-                let bodies = [ba, bb];
-                for i in 0..2 {
-                    let body = bodies[i];
-                    if (*body).type_ == BodyType::Dynamic {
-                        let mut ce = (*body).contact_list_ptr;
-                        while !ce.is_null() {
-                            if island.bodies.len() == island.bodies.capacity() {
-                                break;
-                            }
-
-                            if island.contacts.len() == island.contacts.capacity() {
-                                break;
-                            }
-
-                            let contact = (*ce).contact_ptr;
-                            if (*contact).flags.contains(ContactFlags::ISLAND) {
-                                ce = (*ce).next_ptr;
-                                continue;
-                            }
-
-                            let other = (*ce).other_ptr;
-                            if (*other).type_ == BodyType::Dynamic
-                                && !(*body).is_bullet()
-                                && !(*body).is_bullet()
-                            {
-                                ce = (*ce).next_ptr;
-                                continue;
-                            }
-
-                            let sensor_a = (*(*contact).fixture_a_ptr).is_sensor;
-                            let sensor_b = (*(*contact).fixture_b_ptr).is_sensor;
-                            if sensor_a || sensor_b {
-                                ce = (*ce).next_ptr;
-                                continue;
-                            }
-
-                            let backup = (*other).sweep;
-                            if !(*other).flags.contains(BodyFlags::ISLAND) {
-                                (*other).advance(min_alpha);
-                            }
-
-                            (*contact).update(&self.0.contact_manager.contact_listener);
-
-                            if !(*contact).is_enable() {
-                                (*other).sweep = backup;
-                                (*other).synchronize_transform();
-                                ce = (*ce).next_ptr;
-                                continue;
-                            }
-
-                            (*contact).flags.insert(ContactFlags::ISLAND);
-                            island.add_contact(contact);
-
-                            if (*other).flags.contains(BodyFlags::ISLAND) {
-                                ce = (*ce).next_ptr;
-                                continue;
-                            }
-
-                            (*other).flags.insert(BodyFlags::ISLAND);
-
-                            if (*other).type_ != BodyType::Static {
-                                (*other).set_awake(true);
-                            }
-
-                            island.add_body(other);
-                        }
+                    let contact = &mut world.contact_manager.contacts[edge.contact.0];
+                    let other = &mut world.bodies_slab[edge.other.index];
+                    if !contact.is_enable() || !contact.is_touching() {
+                        other.sweep = backup;
+                        other.synchronize_transform();
+                        continue;
                     }
-                }
 
-                let sub_step_dt = (T::one() - min_alpha) * step.dt;
-                let sub_step = TimeStep {
-                    dt: sub_step_dt,
-                    inv_dt: T::one() / sub_step_dt,
-                    dt_ratio: T::one(),
-                    velocity_iterations: step.velocity_iterations,
-                    position_iterations: 20,
-                    warm_starting: false,
-                };
-                island.solve_toi(&sub_step, (*ba).island_index, (*bb).island_index);
+                    contact.flags.insert(ContactFlags::ISLAND);
+                    island.add_contact(edge.contact);
 
-                for i in 0..island.bodies.len() {
-                    let body = island.bodies[i];
-                    (*body).flags.remove(BodyFlags::ISLAND);
-                    if (*body).type_ != BodyType::Dynamic {
+                    if other.flags.contains(BodyFlags::ISLAND) {
                         continue;
                     }
-                    (*body).synchronize_fixtures();
-
-                    let mut ce = (*body).contact_list_ptr;
-                    while !ce.is_null() {
-                        (*(*ce).contact_ptr)
-                            .flags
-                            .remove(ContactFlags::TOI | ContactFlags::ISLAND);
-                        ce = (*ce).next_ptr;
+
+                    other.flags.insert(BodyFlags::ISLAND);
+
+                    if other.type_ != BodyType::Static {
+                        other.set_awake(true);
                     }
+
+                    island.add_body(other);
                 }
+            }
 
-                self.0.contact_manager.find_new_contacts();
+            let sub_step_dt = (T::one() - min_alpha) * step.dt;
+            let sub_step = TimeStep {
+                dt: sub_step_dt,
+                inv_dt: T::one() / sub_step_dt,
+                dt_ratio: T::one(),
+                velocity_iterations: step.velocity_iterations,
+                position_iterations: 20,
+                warm_starting: false,
+            };
+            let toi_index_a = world.bodies_slab[id_a.index].island_index;
+            let toi_index_b = world.bodies_slab[id_b.index].island_index;
+            island.solve_toi(&sub_step, toi_index_a, toi_index_b, world);
+            world.profile.toi_count += 1;
+
+            for id in &island.bodies {
+                let body = &mut world.bodies_slab[id.index];
+                body.flags.remove(BodyFlags::ISLAND);
+                if body.type_ != BodyType::Dynamic {
+                    continue;
+                }
+                body.synchronize_fixtures(&mut world.contact_manager.broad_phase);
 
-                if self.0.sub_stepping {
-                    self.0.step_complete = false;
-                    break;
+                for edge in &body.contact_edges {
+                    world.contact_manager.contacts[edge.contact.0]
+                        .flags
+                        .remove(ContactFlags::TOI | ContactFlags::ISLAND);
+                }
+            }
+
+            world
+                .contact_manager
+                .find_new_contacts(&mut world.bodies_slab, &world.joints_slab);
+
+            if world.sub_stepping {
+                world.step_complete = false;
+                break;
+            }
+        }
+
+        island.clear();
+        world.island = island;
+    }
+
+    fn apply_force_generators(&mut self, dt: T) {
+        let world = self.0.as_mut();
+        for (_, generator) in &mut world.force_generators {
+            for (_, body) in &mut world.bodies_slab {
+                if body.type_ == BodyType::Dynamic {
+                    generator.apply(body, dt);
                 }
             }
         }
     }
 
     pub fn step(&mut self, dt: T, velocity_iterations: usize, position_iterations: usize) {
+        // 钩子在调用期间被取出，调用中设置了新的钩子时保留新的
+        if let Some(mut hook) = self.0.pre_step.take() {
+            hook(self, dt);
+            if self.0.pre_step.is_none() {
+                self.0.pre_step = Some(hook);
+            }
+        }
+
         let timer = Timer::new();
+        self.0.profile.body_count = 0;
+        self.0.profile.toi_count = 0;
+        self.0.contact_manager.broad_phase.query_count = 0;
+        self.wake_joint_bodies();
 
         if self.0.flags.contains(WorldFlags::NEW_FIXTURE) {
-            self.0.contact_manager.find_new_contacts();
-            self.0.flags.remove(WorldFlags::NEW_FIXTURE);
+            let world = self.0.as_mut();
+            world
+                .contact_manager
+                .find_new_contacts(&mut world.bodies_slab, &world.joints_slab);
+            world.flags.remove(WorldFlags::NEW_FIXTURE);
         }
 
         self.0.flags.insert(WorldFlags::LOCKED);
@@ -760,11 +1447,34 @@ impl<T: Real, D> World<T, D> {
 
         {
             let timer = Timer::new();
-            self.0.contact_manager.collide();
+            let world = self.0.as_mut();
+            world
+                .contact_manager
+                .collide(&mut world.bodies_slab, &world.joints_slab);
             self.0.profile.collide = timer.get_duration();
+            self.0.profile.contact_point_count = self
+                .0
+                .contact_manager
+                .contacts
+                .iter()
+                .filter(|(_, c)| c.is_touching())
+                .map(|(_, c)| c.manifold.point_count)
+                .sum();
         }
 
         if self.0.step_complete && step.dt > T::zero() {
+            self.apply_force_generators(step.dt);
+
+            let world = self.0.as_mut();
+            for (_, system) in &mut world.particle_systems {
+                system.solve(
+                    &step,
+                    world.gravity,
+                    &mut world.bodies_slab,
+                    &world.contact_manager.broad_phase,
+                );
+            }
+
             let timer = Timer::new();
             self.solve(&step);
             self.0.profile.solve = timer.get_duration();
@@ -776,8 +1486,21 @@ impl<T: Real, D> World<T, D> {
             self.0.profile.solve_toi = timer.get_duration();
         }
 
+        if step.dt > T::zero() {
+            let world = self.0.as_mut();
+            for (_, rope) in &mut world.ropes {
+                rope.step(
+                    &step,
+                    world.gravity,
+                    &mut world.bodies_slab,
+                    &world.contact_manager.broad_phase,
+                );
+            }
+        }
+
         if step.dt > T::zero() {
             self.0.inv_dt0 = step.inv_dt;
+            self.break_joints(step.inv_dt);
         }
 
         if self.0.flags.contains(WorldFlags::CLEAR_FORCES) {
@@ -785,119 +1508,253 @@ impl<T: Real, D> World<T, D> {
         }
 
         self.0.flags.remove(WorldFlags::LOCKED);
+        self.create_bonds();
+        self.check_bounds();
+        self.0.profile.tree_query_count = self.0.contact_manager.broad_phase.query_count;
         self.0.profile.step = timer.get_duration();
+
+        if let Some(mut hook) = self.0.post_step.take() {
+            hook(self, dt);
+            if self.0.post_step.is_none() {
+                self.0.post_step = Some(hook);
+            }
+        }
     }
 
     pub fn draw_debug_data(&mut self) {
         if let Some(dd) = &mut self.0.debug_draw {
-            unsafe {
-                if self.0.debug_draw_flags.contains(DebugDrawFlags::SHAPE) {
-                    let mut b = self.0.body_list;
-                    while !b.is_null() {
-                        if !(*b).is_debug_draw() {
-                            b = (*b).next_ptr;
-                            continue;
-                        }
+            if self.0.debug_draw_flags.contains(DebugDrawFlags::SHAPE) {
+                for (_, b) in &self.0.bodies_slab {
+                    if !b.is_debug_draw() {
+                        continue;
+                    }
 
-                        let xf = (*b).transform();
-                        for (_, f) in &(*b).fixture_list {
-                            if !(*b).is_active() {
-                                Self::draw_shape(dd.as_mut(), f, xf, Color::rgb(0.5, 0.5, 0.3));
-                            } else if (*b).body_type() == BodyType::Static {
-                                Self::draw_shape(dd.as_mut(), f, xf, Color::rgb(0.5, 0.9, 0.5));
-                            } else if (*b).body_type() == BodyType::Kinematic {
-                                Self::draw_shape(dd.as_mut(), f, xf, Color::rgb(0.5, 0.5, 0.9));
-                            } else if !(*b).is_awake() {
-                                Self::draw_shape(dd.as_mut(), f, xf, Color::rgb(0.6, 0.6, 0.6));
-                            } else {
-                                Self::draw_shape(dd.as_mut(), f, xf, Color::rgb(0.9, 0.7, 0.7));
-                            }
+                    let xf = b.transform();
+                    for (_, f) in &b.fixture_list {
+                        if !b.is_active() {
+                            Self::draw_shape(dd.as_mut(), f.shape(), xf, Color::rgb(0.5, 0.5, 0.3));
+                        } else if b.body_type() == BodyType::Static {
+                            Self::draw_shape(dd.as_mut(), f.shape(), xf, Color::rgb(0.5, 0.9, 0.5));
+                        } else if b.body_type() == BodyType::Kinematic {
+                            Self::draw_shape(dd.as_mut(), f.shape(), xf, Color::rgb(0.5, 0.5, 0.9));
+                        } else if !b.is_awake() {
+                            Self::draw_shape(dd.as_mut(), f.shape(), xf, Color::rgb(0.6, 0.6, 0.6));
+                        } else {
+                            Self::draw_shape(dd.as_mut(), f.shape(), xf, Color::rgb(0.9, 0.7, 0.7));
                         }
+                    }
+                }
+            }
 
-                        b = (*b).next_ptr;
+            if self.0.debug_draw_flags.contains(DebugDrawFlags::PARTICLE) {
+                let color = Color::rgb(0.3, 0.5, 0.9);
+                for (_, system) in &self.0.particle_systems {
+                    let radius = system.radius().to_f32();
+                    for p in system.positions() {
+                        dd.draw_circle(&Vector2::new(p.x.to_f32(), p.y.to_f32()), radius, color);
                     }
                 }
+            }
 
-                if self.0.debug_draw_flags.contains(DebugDrawFlags::AABB) {
-                    let color = Color::rgb(0.9, 0.3, 0.9);
-                    let bp = &self.0.contact_manager.broad_phase;
+            if self.0.debug_draw_flags.contains(DebugDrawFlags::ROPE) {
+                let color = Color::rgb(0.4, 0.5, 0.7);
+                for (_, rope) in &self.0.ropes {
+                    for segment in rope.vertices().windows(2) {
+                        dd.draw_segment(
+                            &Vector2::new(segment[0].x.to_f32(), segment[0].y.to_f32()),
+                            &Vector2::new(segment[1].x.to_f32(), segment[1].y.to_f32()),
+                            color,
+                        );
+                    }
+                }
+            }
 
-                    let mut b = self.0.body_list;
-                    while !b.is_null() {
-                        if !(*b).is_debug_draw() {
-                            b = (*b).next_ptr;
-                            continue;
-                        }
+            if self.0.debug_draw_flags.contains(DebugDrawFlags::JOINT) {
+                for (_, joint) in &self.0.joints_slab {
+                    Self::draw_joint(dd.as_mut(), joint, &self.0.bodies_slab);
+                }
+            }
 
-                        if !(*b).is_active() {
-                            b = (*b).next_ptr;
-                            continue;
-                        }
+            if self.0.debug_draw_flags.intersects(
+                DebugDrawFlags::CONTACT_POINT
+                    | DebugDrawFlags::CONTACT_NORMAL
+                    | DebugDrawFlags::CONTACT_IMPULSE
+                    | DebugDrawFlags::FRICTION_IMPULSE,
+            ) {
+                Self::draw_contacts(
+                    dd.as_mut(),
+                    self.0.debug_draw_flags,
+                    &self.0.contact_manager.contacts,
+                    &self.0.bodies_slab,
+                );
+            }
 
-                        for (_, f) in &(*b).fixture_list {
-                            for i in 0..f.proxies.len() {
-                                let proxy = &f.proxies[i];
-                                let aabb = bp.tree.get_fat_aabb(proxy.proxy_id);
-                                let vs = [
-                                    Vector2::new(
-                                        aabb.lower_bound.x.to_f32(),
-                                        aabb.lower_bound.y.to_f32(),
-                                    ),
-                                    Vector2::new(
-                                        aabb.upper_bound.x.to_f32(),
-                                        aabb.lower_bound.y.to_f32(),
-                                    ),
-                                    Vector2::new(
-                                        aabb.upper_bound.x.to_f32(),
-                                        aabb.upper_bound.y.to_f32(),
-                                    ),
-                                    Vector2::new(
-                                        aabb.lower_bound.x.to_f32(),
-                                        aabb.upper_bound.y.to_f32(),
-                                    ),
-                                ];
-                                dd.draw_polygon(&vs, color);
-                            }
-                        }
+            if self.0.debug_draw_flags.contains(DebugDrawFlags::AABB) {
+                let color = Color::rgb(0.9, 0.3, 0.9);
+                let bp = &self.0.contact_manager.broad_phase;
 
-                        b = (*b).next_ptr;
+                for (_, b) in &self.0.bodies_slab {
+                    if !b.is_debug_draw() {
+                        continue;
                     }
-                }
 
-                if self
-                    .0
-                    .debug_draw_flags
-                    .contains(DebugDrawFlags::CENTER_OF_MASS)
-                {
-                    let mut b = self.0.body_list;
-                    while !b.is_null() {
-                        if !(*b).is_debug_draw() {
-                            b = (*b).next_ptr;
-                            continue;
+                    if !b.is_active() {
+                        continue;
+                    }
+
+                    for (_, f) in &b.fixture_list {
+                        for i in 0..f.proxies.len() {
+                            let proxy = &f.proxies[i];
+                            let aabb = bp.tree.get_fat_aabb(proxy.proxy_id);
+                            Self::draw_aabb(dd.as_mut(), aabb, color);
                         }
+                    }
+                }
+            }
+
+            if self.0.debug_draw_flags.contains(DebugDrawFlags::TREE) {
+                let tree = &self.0.contact_manager.broad_phase.tree;
+                let max_height = tree.height().max(1) as f32;
+                for (aabb, height) in tree.internal_nodes() {
+                    let t = height as f32 / max_height;
+                    Self::draw_aabb(dd.as_mut(), aabb, Color::rgb(t, 1.0 - t, 0.2));
+                }
+            }
+
+            if self.0.debug_draw_flags.contains(DebugDrawFlags::PAIRS) {
+                let color = Color::rgb(0.3, 0.9, 0.9);
+                let tree = &self.0.contact_manager.broad_phase.tree;
+                for (_, c) in &self.0.contact_manager.contacts {
+                    let fixture_a = c.fixture_a.get(&self.0.bodies_slab);
+                    let fixture_b = c.fixture_b.get(&self.0.bodies_slab);
+                    let proxy_a = fixture_a.proxies[c.child_index_a()].proxy_id;
+                    let proxy_b = fixture_b.proxies[c.child_index_b()].proxy_id;
+                    let ca = tree.get_fat_aabb(proxy_a).center();
+                    let cb = tree.get_fat_aabb(proxy_b).center();
+                    dd.draw_segment(
+                        &Vector2::new(ca.x.to_f32(), ca.y.to_f32()),
+                        &Vector2::new(cb.x.to_f32(), cb.y.to_f32()),
+                        color,
+                    );
+                }
+            }
 
-                        let mut xf = *((*b).transform());
-                        xf.p = *((*b).world_center());
-
-                        let xf_f32 = {
-                            Transform::<f32> {
-                                p: Vector2::new(xf.p.x.to_f32(), xf.p.y.to_f32()),
-                                q: Rotation::new(xf.q.angle().to_f32()),
-                            }
-                        };
-                        dd.draw_transform(&xf_f32);
-                        b = (*b).next_ptr;
+            if self
+                .0
+                .debug_draw_flags
+                .contains(DebugDrawFlags::CENTER_OF_MASS)
+            {
+                for (_, b) in &self.0.bodies_slab {
+                    if !b.is_debug_draw() {
+                        continue;
                     }
+
+                    let mut xf = *(b.transform());
+                    xf.p = *(b.world_center());
+
+                    let xf_f32 = {
+                        Transform::<f32> {
+                            p: Vector2::new(xf.p.x.to_f32(), xf.p.y.to_f32()),
+                            q: Rotation::new(xf.q.angle().to_f32()),
+                        }
+                    };
+                    dd.draw_transform(&xf_f32);
                 }
             }
         }
     }
 
-    fn draw_shape(dd: &mut dyn DebugDraw, f: &Fixture<T, D>, xf: &Transform<T>, color: Color) {
+    fn draw_aabb(dd: &mut dyn DebugDraw, aabb: &AABB<T>, color: Color) {
+        let vs = [
+            Vector2::new(aabb.lower_bound.x.to_f32(), aabb.lower_bound.y.to_f32()),
+            Vector2::new(aabb.upper_bound.x.to_f32(), aabb.lower_bound.y.to_f32()),
+            Vector2::new(aabb.upper_bound.x.to_f32(), aabb.upper_bound.y.to_f32()),
+            Vector2::new(aabb.lower_bound.x.to_f32(), aabb.upper_bound.y.to_f32()),
+        ];
+        dd.draw_polygon(&vs, color);
+    }
+
+    /// 和Box2D的testbed一样，法线长度固定，冲量按0.1的比例画出
+    fn draw_contacts(
+        dd: &mut dyn DebugDraw,
+        flags: DebugDrawFlags,
+        contacts: &Slab<Box<Contact<T, D>>>,
+        bodies: &Slab<Box<Body<T, D>>>,
+    ) {
+        const AXIS_SCALE: f32 = 0.3;
+        const IMPULSE_SCALE: f32 = 0.1;
+
+        for (_, c) in contacts {
+            if !c.is_touching() || !c.is_enable() {
+                continue;
+            }
+
+            let manifold = c.manifold();
+            let world_manifold = c.world_manifold_with(
+                &bodies[c.fixture_a.body.index],
+                &bodies[c.fixture_b.body.index],
+            );
+            let normal = Vector2::new(
+                world_manifold.normal.x.to_f32(),
+                world_manifold.normal.y.to_f32(),
+            );
+            let tangent = Vector2::new(normal.y, -normal.x);
+
+            for i in 0..manifold.point_count {
+                let mp = &manifold.points[i];
+                let p = Vector2::new(
+                    world_manifold.points[i].x.to_f32(),
+                    world_manifold.points[i].y.to_f32(),
+                );
+
+                if flags.contains(DebugDrawFlags::CONTACT_POINT) {
+                    dd.draw_point(&p, Color::rgb(0.3, 0.3, 0.95));
+                }
+
+                if flags.contains(DebugDrawFlags::CONTACT_NORMAL) {
+                    let p2 = p + normal * AXIS_SCALE;
+                    dd.draw_segment(&p, &p2, Color::rgb(0.9, 0.9, 0.9));
+                }
+
+                if flags.contains(DebugDrawFlags::CONTACT_IMPULSE) {
+                    let p2 = p + normal * (IMPULSE_SCALE * mp.normal_impulse.to_f32());
+                    dd.draw_segment(&p, &p2, Color::rgb(0.9, 0.9, 0.3));
+                }
+
+                if flags.contains(DebugDrawFlags::FRICTION_IMPULSE) {
+                    let p2 = p + tangent * (IMPULSE_SCALE * mp.tangent_impulse.to_f32());
+                    dd.draw_segment(&p, &p2, Color::rgb(0.9, 0.9, 0.3));
+                }
+            }
+        }
+    }
+
+    fn draw_joint(dd: &mut dyn DebugDraw, joint: &Joint<T>, bodies: &Slab<Box<Body<T, D>>>) {
+        let to_f32 = |v: Vector2<T>| Vector2::new(v.x.to_f32(), v.y.to_f32());
+        let base = joint.base();
+        let x1 = to_f32(*bodies[base.body_a.index].position());
+        let x2 = to_f32(*bodies[base.body_b.index].position());
+        let p1 = to_f32(joint.anchor_a_in(bodies));
+        let p2 = to_f32(joint.anchor_b_in(bodies));
+        let color = Color::rgb(0.5, 0.8, 0.8);
+
+        match joint {
+            Joint::Distance(_) => dd.draw_segment(&p1, &p2, color),
+            _ => {
+                dd.draw_segment(&x1, &p1, color);
+                dd.draw_segment(&p1, &p2, color);
+                dd.draw_segment(&x2, &p2, color);
+            }
+        }
+        dd.draw_point(&p1, color);
+        dd.draw_point(&p2, color);
+    }
+
+    fn draw_shape(dd: &mut dyn DebugDraw, shape: &dyn Shape<T>, xf: &Transform<T>, color: Color) {
         unsafe {
-            match f.shape.shape_type() {
+            match shape.shape_type() {
                 ShapeType::Circle => {
-                    let circle = (f.shape.as_ref() as *const dyn Shape<T> as *const ShapeCircle<T>)
+                    let circle = (shape as *const dyn Shape<T> as *const ShapeCircle<T>)
                         .as_ref()
                         .unwrap();
                     let center = xf.multiply(circle.position);
@@ -914,7 +1771,7 @@ impl<T: Real, D> World<T, D> {
                     );
                 }
                 ShapeType::Edge => {
-                    let edge = (f.shape.as_ref() as *const dyn Shape<T> as *const ShapeEdge<T>)
+                    let edge = (shape as *const dyn Shape<T> as *const ShapeEdge<T>)
                         .as_ref()
                         .unwrap();
                     let v1 = xf.multiply(edge.vertex1);
@@ -932,8 +1789,7 @@ impl<T: Real, D> World<T, D> {
                     );
                 }
                 ShapeType::Polygon => {
-                    let polygon = (f.shape.as_ref() as *const dyn Shape<T>
-                        as *const ShapePolygon<T>)
+                    let polygon = (shape as *const dyn Shape<T> as *const ShapePolygon<T>)
                         .as_ref()
                         .unwrap();
                     let mut vertices = [Vector2::<f32>::zero(); settings::MAX_POLYGON_VERTICES];
@@ -944,7 +1800,7 @@ impl<T: Real, D> World<T, D> {
                     dd.draw_solid_polygon(&vertices[0..polygon.count], color);
                 }
                 ShapeType::Chain => {
-                    let chain = (f.shape.as_ref() as *const dyn Shape<T> as *const ShapeChain<T>)
+                    let chain = (shape as *const dyn Shape<T> as *const ShapeChain<T>)
                         .as_ref()
                         .unwrap();
                     let count = chain.vertices.len();
@@ -1006,32 +1862,342 @@ impl<T: Real, D> World<T, D> {
                         );
                     }
                 }
+                ShapeType::Compound => {
+                    let compound = (shape as *const dyn Shape<T> as *const ShapeCompound<T>)
+                        .as_ref()
+                        .unwrap();
+                    for child in compound.children() {
+                        Self::draw_shape(dd, child.shape(), xf, color);
+                    }
+                }
             }
         }
     }
 
+    /// 查找刚体上的夹具，刚体或夹具不存在时返回`None`
+    pub fn fixture(&self, body_id: BodyId, fixture_id: FixtureId) -> Option<&Fixture<T, D>> {
+        self.body(body_id).and_then(|body| body.fixture(fixture_id))
+    }
+
+    pub fn fixture_mut(
+        &mut self,
+        body_id: BodyId,
+        fixture_id: FixtureId,
+    ) -> Option<&mut Fixture<T, D>> {
+        self.body_mut(body_id)
+            .and_then(|body| body.fixture_mut(fixture_id))
+    }
+
+    /// 遍历所有刚体，顺序只取决于刚体创建和销毁的顺序
+    pub fn bodies(&self) -> impl Iterator<Item = &Body<T, D>> {
+        self.0.bodies_slab.iter().map(|(_, body)| body.as_ref())
+    }
+
+    /// 遍历所有接触，包括还没有接触上的(AABB重叠)，用`Contact::is_touching`区分
+    pub fn contacts(&self) -> impl Iterator<Item = &Contact<T, D>> {
+        self.0
+            .contact_manager
+            .contacts
+            .iter()
+            .map(|(_, contact)| contact.as_ref())
+    }
+
+    /// 遍历所有刚体上的夹具，可以用`Fixture::body`和`Fixture::id`得到对应的句柄
+    pub fn fixtures(&self) -> impl Iterator<Item = &Fixture<T, D>> {
+        self.0
+            .bodies_slab
+            .iter()
+            .flat_map(|(_, body)| body.fixture_list.iter().map(|(_, f)| f.as_ref()))
+    }
+
     pub fn query_aabb(&self, aabb: AABB<T>) -> impl Iterator<Item = &Fixture<T, D>> {
+        let bodies = &self.0.bodies_slab;
         self.0
             .contact_manager
             .broad_phase
             .tree
             .query(aabb)
-            .map(|item| unsafe { ((*(*item.2)).fixture_ptr).as_ref().unwrap() })
+            .map(move |item| item.2.get(bodies))
     }
 
-    pub fn ray_cast(&self, input: RayCastInput<T>) -> RayCastIter<T, D> {
+    /// 包围盒和`aabb`重叠并且`filter`返回`true`的夹具
+    pub fn query_aabb_filtered<F>(
+        &self,
+        aabb: AABB<T>,
+        mut filter: F,
+    ) -> impl Iterator<Item = &Fixture<T, D>>
+    where
+        F: FnMut(&Fixture<T, D>) -> bool,
+    {
+        self.query_aabb(aabb).filter(move |fixture| filter(fixture))
+    }
+
+    /// 和放在`xf`处的形状重叠并且`filter`返回`true`的夹具
+    pub fn overlap_shape<F>(
+        &self,
+        shape: &dyn Shape<T>,
+        xf: Transform<T>,
+        mut filter: F,
+    ) -> impl Iterator<Item = &Fixture<T, D>>
+    where
+        F: FnMut(&Fixture<T, D>) -> bool,
+    {
+        let aabb = (1..shape.child_count()).fold(shape.compute_aabb(&xf, 0), |aabb, index| {
+            aabb.combine(&shape.compute_aabb(&xf, index))
+        });
+
+        let mut fixtures: Vec<&Fixture<T, D>> = Vec::new();
+        for item in self.0.contact_manager.broad_phase.tree.query(aabb) {
+            let key = *item.2;
+            let fixture = key.get(&self.0.bodies_slab);
+            if fixtures.iter().any(|f| std::ptr::eq(*f, fixture)) || !filter(fixture) {
+                continue;
+            }
+            let overlap = (0..shape.child_count()).any(|index| {
+                collision::test_child_overlap(
+                    shape,
+                    index,
+                    fixture.shape(),
+                    key.child_index,
+                    xf,
+                    *self.0.bodies_slab[key.body.index].transform(),
+                )
+            });
+            if overlap {
+                fixtures.push(fixture);
+            }
+        }
+        fixtures.into_iter()
+    }
+
+    pub fn ray_cast(&self, input: RayCastInput<T>) -> RayCastIter<'_, T, D> {
         RayCastIter {
             iter: self.0.contact_manager.broad_phase.tree.ray_cast(input),
+            bodies: &self.0.bodies_slab,
+        }
+    }
+
+    /// 包围盒和射线相交并且`filter`返回`true`的夹具
+    pub fn ray_cast_filtered<F>(
+        &self,
+        input: RayCastInput<T>,
+        mut filter: F,
+    ) -> impl Iterator<Item = &Fixture<T, D>>
+    where
+        F: FnMut(&Fixture<T, D>) -> bool,
+    {
+        self.ray_cast(input).filter(move |fixture| filter(fixture))
+    }
+
+    /// 从`p1`到`p2`做射线检测，返回最近的击中点，`filter`返回`false`的夹具会被忽略
+    pub fn ray_cast_closest<F>(
+        &self,
+        p1: Vector2<T>,
+        p2: Vector2<T>,
+        mut filter: F,
+    ) -> Option<RayCastHit<'_, T, D>>
+    where
+        F: FnMut(&Fixture<T, D>) -> bool,
+    {
+        let mut input = RayCastInput {
+            p1,
+            p2,
+            max_fraction: T::one(),
+        };
+        let mut hit = None;
+        let mut iter = self.ray_cast(RayCastInput {
+            p1,
+            p2,
+            max_fraction: T::one(),
+        });
+
+        while let Some(fixture) = iter.next() {
+            if !filter(fixture) {
+                continue;
+            }
+            let xf = self.0.bodies_slab[fixture.body.index].transform();
+            for child_index in 0..fixture.shape().child_count() {
+                if let Some(output) = fixture.ray_cast(&input, xf, child_index) {
+                    input.max_fraction = output.fraction;
+                    iter.set_max_fraction(output.fraction);
+                    hit = Some(RayCastHit {
+                        fixture,
+                        point: p1 + (p2 - p1) * output.fraction,
+                        normal: output.normal,
+                        fraction: output.fraction,
+                    });
+                }
+            }
+        }
+
+        hit
+    }
+
+    /// 沿`translation`平移放在`xf`处的形状，返回最先碰到的夹具，`filter`返回`false`的夹具会被忽略
+    ///
+    /// 形状不会旋转。开始时已经接触，但平移方向离开表面的夹具不算击中。
+    pub fn shape_cast<F>(
+        &self,
+        shape: &dyn Shape<T>,
+        xf: Transform<T>,
+        translation: Vector2<T>,
+        mut filter: F,
+    ) -> Option<ShapeCastHit<'_, T, D>>
+    where
+        F: FnMut(&Fixture<T, D>) -> bool,
+    {
+        let target = Transform::new(xf.p + translation, xf.q);
+        let aabb = (0..shape.child_count()).fold(shape.compute_aabb(&xf, 0), |aabb, index| {
+            aabb.combine(&shape.compute_aabb(&xf, index))
+                .combine(&shape.compute_aabb(&target, index))
+        });
+        let angle = xf.q.angle();
+        let sweep_a = Sweep {
+            local_center: Vector2::zero(),
+            c0: xf.p,
+            c: target.p,
+            a0: angle,
+            a: angle,
+            alpha0: T::zero(),
+        };
+
+        let mut hit: Option<ShapeCastHit<'_, T, D>> = None;
+        for item in self.0.contact_manager.broad_phase.tree.query(aabb) {
+            let key = *item.2;
+            let fixture = key.get(&self.0.bodies_slab);
+            if !filter(fixture) {
+                continue;
+            }
+
+            let xf_b = *self.0.bodies_slab[key.body.index].transform();
+            let sweep_b = Sweep {
+                local_center: Vector2::zero(),
+                c0: xf_b.p,
+                c: xf_b.p,
+                a0: xf_b.q.angle(),
+                a: xf_b.q.angle(),
+                alpha0: T::zero(),
+            };
+            let proxy_b = fixture.shape().distance_proxy(key.child_index);
+
+            for index in 0..shape.child_count() {
+                let proxy_a = shape.distance_proxy(index);
+                let output = time_of_impact(TOIInput {
+                    proxy_a: &proxy_a,
+                    proxy_b: &proxy_b,
+                    sweep_a,
+                    sweep_b,
+                    max: T::one(),
+                });
+                if output.state == TOIOutputState::Separated {
+                    continue;
+                }
+                if let Some(hit) = &hit {
+                    if output.t >= hit.fraction {
+                        continue;
+                    }
+                }
+
+                // 击中时两个形状核心的最近点决定法线
+                let distance = collision::distance::distance(
+                    &DistanceInput {
+                        proxy_a: &proxy_a,
+                        proxy_b: &proxy_b,
+                        transform_a: sweep_a.get_transform(output.t),
+                        transform_b: xf_b,
+                        use_radii: false,
+                    },
+                    &mut SimpleCache::default(),
+                );
+                let normal = if distance.distance > T::epsilon() {
+                    (distance.point_a - distance.point_b) * (T::one() / distance.distance)
+                } else {
+                    -translation.normalize()
+                };
+                // 沿表面滑动时法线有误差，几乎平行于表面的平移不算击中
+                if normal.dot(translation) > -settings::linear_slop::<T>() * translation.length() {
+                    continue;
+                }
+
+                hit = Some(ShapeCastHit {
+                    fixture,
+                    point: distance.point_b + normal * proxy_b.radius,
+                    normal,
+                    fraction: output.t,
+                });
+            }
         }
+
+        hit
     }
 
+    /// 上一步中求解的所有岛，用`Body::island`得到刚体所在的岛
+    pub fn islands(&self) -> &[IslandInfo] {
+        &self.0.islands
+    }
+
+    /// 第`index`个岛中的非静态刚体，静态刚体不属于任何岛
+    pub fn island_bodies(&self, index: usize) -> &[BodyId] {
+        &self.0.island_bodies[self.0.islands[index].bodies.clone()]
+    }
+
+    /// 最近一次步进的耗时和计数
     pub fn profile(&self) -> &Profile {
         &self.0.profile
     }
+
+    pub fn body_count(&self) -> usize {
+        self.0.bodies_slab.len()
+    }
+
+    pub fn joint_count(&self) -> usize {
+        self.0.joints_slab.len()
+    }
+
+    pub fn contact_count(&self) -> usize {
+        self.0.contact_manager.contacts.len()
+    }
+
+    /// broad-phase中的代理数量
+    pub fn proxy_count(&self) -> usize {
+        self.0.contact_manager.broad_phase.tree.proxy_count()
+    }
+
+    /// broad-phase动态树的高度
+    pub fn tree_height(&self) -> i32 {
+        self.0.contact_manager.broad_phase.tree.height()
+    }
+}
+
+/// `World::ray_cast_closest`的结果
+pub struct RayCastHit<'a, T, D> {
+    pub fixture: &'a Fixture<T, D>,
+
+    /// 击中点的世界坐标
+    pub point: Vector2<T>,
+
+    /// 击中点的表面法线
+    pub normal: Vector2<T>,
+    pub fraction: T,
+}
+
+/// `World::shape_cast`的结果
+pub struct ShapeCastHit<'a, T, D> {
+    pub fixture: &'a Fixture<T, D>,
+
+    /// 接触点的世界坐标
+    pub point: Vector2<T>,
+
+    /// 击中的表面法线，指向移动的形状
+    pub normal: Vector2<T>,
+
+    /// 形状在`xf.p + translation * fraction`处碰到夹具
+    pub fraction: T,
 }
 
 pub struct RayCastIter<'a, T, D> {
-    iter: dynamic_tree::RayCastIter<'a, T, *mut FixtureProxy<T, D>>,
+    iter: dynamic_tree::RayCastIter<'a, T, FixtureKey>,
+    bodies: &'a Slab<Box<Body<T, D>>>,
 }
 
 impl<'a, T: Real, D> RayCastIter<'a, T, D> {
@@ -1044,7 +2210,7 @@ impl<'a, T: Real, D> Iterator for RayCastIter<'a, T, D> {
     type Item = &'a Fixture<T, D>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        Iterator::next(&mut self.iter)
-            .map(|item| unsafe { ((*(*item.2)).fixture_ptr).as_ref().unwrap() })
+        let bodies = self.bodies;
+        Iterator::next(&mut self.iter).map(|item| item.2.get(bodies))
     }
 }
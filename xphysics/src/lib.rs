@@ -21,11 +21,10 @@ mod tests {
             type_: BodyType::Dynamic,
             ..BodyDef::default()
         });
-        let body = world.body_mut(id).unwrap();
-        body.create_fixture(FixtureDef::new(
-            ShapeCircle::new(position, 50.0).into_boxed(),
-            1.0,
-        ));
+        world.create_fixture(
+            id,
+            FixtureDef::new(ShapeCircle::new(position, 50.0).into_boxed(), 1.0),
+        );
         id
     }
 
@@ -39,4 +38,2098 @@ mod tests {
             println!("{:?}", world.body(body).unwrap().position());
         }
     }
+
+    #[test]
+    fn test_toi_island_with_kinematic_body() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        world.create_body_with_fixtures(
+            BodyDef {
+                type_: BodyType::Kinematic,
+                position: (0.0, 10.0).into(),
+                angular_velocity: 0.05 * std::f32::consts::PI,
+                ..BodyDef::default()
+            },
+            vec![
+                FixtureDef::new(
+                    ShapePolygon::new_box(0.5, 10.0, (10.0, 0.0), 0.0).into_boxed(),
+                    5.0,
+                ),
+                FixtureDef::new(
+                    ShapePolygon::new_box(0.5, 10.0, (-10.0, 0.0), 0.0).into_boxed(),
+                    5.0,
+                ),
+                FixtureDef::new(
+                    ShapePolygon::new_box(10.0, 0.5, (0.0, 10.0), 0.0).into_boxed(),
+                    5.0,
+                ),
+                FixtureDef::new(
+                    ShapePolygon::new_box(10.0, 0.5, (0.0, -10.0), 0.0).into_boxed(),
+                    5.0,
+                ),
+            ],
+        );
+
+        for _ in 0..300 {
+            world.step(1.0 / 60.0, 8, 3);
+            world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: (0.0, 10.0).into(),
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(ShapePolygon::new_box_center(0.125, 0.125).into_boxed(), 1.0),
+            );
+        }
+        assert_eq!(world.body_count(), 301);
+    }
+
+    #[test]
+    fn test_toi_rotated_polygon() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(ShapeEdge::new((-40.0, 0.0), (40.0, 0.0)).into_boxed(), 0.0),
+        );
+        // 旋转过的薄板高速下落，一步的位移远大于厚度，需要TOI才不会穿过地面
+        let body = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (0.0, 2.0).into(),
+                angle: 0.3,
+                linear_velocity: (0.0, -100.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapePolygon::new_box_center(0.6, 0.125).into_boxed(), 20.0),
+        );
+
+        for _ in 0..60 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        assert!(world.body(body).unwrap().position().y > 0.0);
+    }
+
+    #[test]
+    fn test_revolute_joint() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        let ground = world.create_body(BodyDef::default());
+        let create_box = |world: &mut World<f32, ()>, x: f32| {
+            world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: (x, 10.0).into(),
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(ShapePolygon::new_box_center(0.5, 0.125).into_boxed(), 1.0),
+            )
+        };
+
+        // 钟摆，锚点在左端
+        let pendulum = create_box(&mut world, 0.0);
+        let anchor = Vector2::new(-0.5, 10.0);
+        let joint = world.create_joint(RevoluteJointDef::with_anchor(
+            &world, ground, pendulum, anchor,
+        ));
+
+        // 限制角度的门
+        let door = create_box(&mut world, 5.0);
+        let door_joint = world.create_joint(RevoluteJointDef {
+            enable_limit: true,
+            lower_angle: -0.25 * std::f32::consts::PI,
+            upper_angle: 0.0,
+            ..RevoluteJointDef::with_anchor(&world, ground, door, Vector2::new(4.5, 10.0))
+        });
+
+        // 马达驱动的轮子
+        let wheel = create_box(&mut world, 10.0);
+        world.create_joint(RevoluteJointDef {
+            enable_motor: true,
+            motor_speed: 2.0,
+            max_motor_torque: 1000.0,
+            ..RevoluteJointDef::with_anchor(&world, ground, wheel, Vector2::new(10.0, 10.0))
+        });
+        assert_eq!(world.joint_count(), 3);
+
+        for _ in 0..120 {
+            world.step(1.0 / 60.0, 8, 3);
+            let joint = world.joint(joint).unwrap();
+            assert!((joint.anchor_b(&world) - anchor).length() < 0.01);
+        }
+
+        match world.joint(door_joint).unwrap() {
+            Joint::Revolute(joint) => {
+                let angle = joint.joint_angle(&world);
+                assert!(angle >= joint.lower_limit() - 0.05, "{}", angle);
+                assert!(angle < joint.lower_limit() + 0.05, "{}", angle);
+            }
+            _ => unreachable!(),
+        }
+        assert!(world.body(pendulum).unwrap().angle() < -0.1);
+        assert!((world.body(wheel).unwrap().angular_velocity() - 2.0).abs() < 0.01);
+        assert_eq!(
+            world.body(wheel).unwrap().position(),
+            &Vector2::new(10.0, 10.0)
+        );
+
+        world.destroy_joint(joint);
+        assert_eq!(world.joint_count(), 2);
+        assert!(world.body(pendulum).unwrap().joint_list().is_empty());
+    }
+
+    #[test]
+    fn test_prismatic_joint() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        let ground = world.create_body(BodyDef::default());
+        let platform = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (0.0, 1.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapePolygon::new_box_center(1.0, 0.125).into_boxed(), 1.0),
+        );
+
+        // 向上移动的升降台，最多升高2米
+        let joint = world.create_joint(PrismaticJointDef {
+            enable_limit: true,
+            lower_translation: 0.0,
+            upper_translation: 2.0,
+            enable_motor: true,
+            motor_speed: 1.0,
+            max_motor_force: 1000.0,
+            ..PrismaticJointDef::with_anchor(
+                &world,
+                ground,
+                platform,
+                Vector2::new(0.0, 1.0),
+                Vector2::new(0.0, 1.0),
+            )
+        });
+
+        for _ in 0..60 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        let body = world.body(platform).unwrap();
+        assert!((body.position().y - 2.0).abs() < 0.05);
+        assert!(body.position().x.abs() < 0.01);
+        assert!(body.angle().abs() < 0.01);
+
+        for _ in 0..120 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        match world.joint(joint).unwrap() {
+            Joint::Prismatic(joint) => {
+                let translation = joint.joint_translation(&world);
+                assert!((translation - 2.0).abs() < 0.02, "{}", translation);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_wheel_joint() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        let ground = world.create_body(BodyDef::default());
+        let wheel = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (0.0, 1.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapeCircle::new(Vector2::zero(), 0.4).into_boxed(), 1.0),
+        );
+
+        // 挂在空中的轮子，悬挂弹簧被重力拉长一点，马达带动轮子旋转
+        let joint = world.create_joint(WheelJointDef {
+            enable_motor: true,
+            motor_speed: 5.0,
+            max_motor_torque: 100.0,
+            frequency_hz: 4.0,
+            ..WheelJointDef::with_anchor(
+                &world,
+                ground,
+                wheel,
+                Vector2::new(0.0, 1.0),
+                Vector2::new(0.0, 1.0),
+            )
+        });
+
+        for _ in 0..120 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        let body = world.body(wheel).unwrap();
+        assert!(body.position().x.abs() < 0.001);
+        match world.joint(joint).unwrap() {
+            Joint::Wheel(joint) => {
+                // 平衡位置是 -g / (2πf)^2
+                let translation = joint.joint_translation(&world);
+                assert!((translation + 0.0158).abs() < 0.002, "{}", translation);
+                assert!((joint.joint_speed(&world) - 5.0).abs() < 0.01);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_distance_joint() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        let ground = world.create_body(BodyDef::default());
+        let create_ball = |world: &mut World<f32, ()>, x: f32| {
+            world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: (x, 8.0).into(),
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(ShapeCircle::new(Vector2::zero(), 0.25).into_boxed(), 1.0),
+            )
+        };
+
+        // 刚性的绳子
+        let ball = create_ball(&mut world, 0.0);
+        world.create_joint(DistanceJointDef::with_anchors(
+            &world,
+            ground,
+            ball,
+            Vector2::new(0.0, 10.0),
+            Vector2::new(0.0, 8.0),
+        ));
+
+        // 弹簧，长度比当前距离短，会把球拉上去
+        let spring = create_ball(&mut world, 5.0);
+        let anchor = Vector2::new(5.0, 10.0);
+        world.create_joint(DistanceJointDef {
+            length: 1.0,
+            frequency_hz: 2.0,
+            damping_ratio: 1.0,
+            ..DistanceJointDef::with_anchors(&world, ground, spring, anchor, Vector2::new(5.0, 8.0))
+        });
+
+        for _ in 0..180 {
+            world.step(1.0 / 60.0, 8, 3);
+            let distance =
+                (*world.body(ball).unwrap().position() - Vector2::new(0.0, 10.0)).length();
+            assert!((distance - 2.0).abs() < 0.01, "{}", distance);
+        }
+
+        // 静止时弹簧的伸长量 = mg / k
+        let body = world.body(spring).unwrap();
+        let k = body.mass() * (2.0 * std::f32::consts::PI * 2.0).powi(2);
+        let stretch = body.mass() * 10.0 / k;
+        let distance = (*body.position() - anchor).length();
+        assert!((distance - 1.0 - stretch).abs() < 0.02, "{}", distance);
+        assert!(body.linear_velocity().length() < 0.05);
+    }
+
+    #[test]
+    fn test_gear_joint() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        let ground = world.create_body(BodyDef::default());
+        let create_wheel = |world: &mut World<f32, ()>, x: f32| {
+            world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: (x, 10.0).into(),
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(ShapeCircle::new(Vector2::zero(), 0.5).into_boxed(), 1.0),
+            )
+        };
+
+        let wheel1 = create_wheel(&mut world, 0.0);
+        let joint1 = world.create_joint(RevoluteJointDef {
+            enable_motor: true,
+            motor_speed: 2.0,
+            max_motor_torque: 1000.0,
+            ..RevoluteJointDef::with_anchor(&world, ground, wheel1, Vector2::new(0.0, 10.0))
+        });
+        let wheel2 = create_wheel(&mut world, 5.0);
+        let joint2 = world.create_joint(RevoluteJointDef::with_anchor(
+            &world,
+            ground,
+            wheel2,
+            Vector2::new(5.0, 10.0),
+        ));
+        let gear = world.create_joint(GearJointDef::new(joint1, joint2, 2.0));
+        assert_eq!(world.joint(gear).unwrap().joint_type(), JointType::Gear);
+
+        for _ in 0..60 {
+            world.step(1.0 / 60.0, 8, 3);
+            let angle1 = world.body(wheel1).unwrap().angle();
+            let angle2 = world.body(wheel2).unwrap().angle();
+            assert!(
+                (angle1 + 2.0 * angle2).abs() < 0.01,
+                "{} {}",
+                angle1,
+                angle2
+            );
+        }
+        assert!(world.body(wheel1).unwrap().angle() > 0.5);
+        assert!((world.body(wheel2).unwrap().angular_velocity() + 1.0).abs() < 0.05);
+
+        // 删除连接的关节时齿轮关节也一起删除
+        world.destroy_joint(joint1);
+        assert_eq!(world.joint_count(), 1);
+        assert!(world.joint(gear).is_none());
+        assert_eq!(world.body(wheel2).unwrap().joint_list(), &[joint2]);
+    }
+
+    #[test]
+    fn test_motor_joint() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        let ground = world.create_body(BodyDef::default());
+        let create_platform = |world: &mut World<f32, ()>, x: f32| {
+            world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: (x, 5.0).into(),
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(ShapePolygon::new_box_center(1.0, 0.25).into_boxed(), 1.0),
+            )
+        };
+
+        // 移动平台，跟随目标位置
+        let platform = create_platform(&mut world, 0.0);
+        let joint = world.create_joint(MotorJointDef {
+            max_force: 1000.0,
+            max_torque: 1000.0,
+            ..MotorJointDef::with_bodies(&world, ground, platform)
+        });
+
+        // 最大力小于重力，托不住
+        let weak = create_platform(&mut world, 10.0);
+        world.create_joint(MotorJointDef {
+            max_force: 1.0,
+            ..MotorJointDef::with_bodies(&world, ground, weak)
+        });
+
+        match world.joint_mut(joint).unwrap() {
+            Joint::Motor(joint) => joint.set_linear_offset(Vector2::new(3.0, 6.0)),
+            _ => unreachable!(),
+        }
+        for _ in 0..180 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+
+        let body = world.body(platform).unwrap();
+        assert!((*body.position() - Vector2::new(3.0, 6.0)).length() < 0.05);
+        assert!(body.angle().abs() < 0.01);
+        assert!(world.body(weak).unwrap().position().y < 4.0);
+    }
+
+    #[test]
+    fn test_joint_breaking() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        struct Listener(Rc<RefCell<Vec<JointBroken<f32>>>>);
+
+        impl DestructionListener<f32, ()> for Listener {
+            fn fixture_destroyed(&self, _fixture: &Fixture<f32, ()>) {}
+
+            fn joint_broken(&self, event: &JointBroken<f32>) {
+                self.0.borrow_mut().push(*event);
+            }
+        }
+
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        let broken = Rc::new(RefCell::new(Vec::new()));
+        world.set_destruction_listener(Listener(broken.clone()));
+        let ground = world.create_body(BodyDef::default());
+        let create_box = |world: &mut World<f32, ()>, x: f32| {
+            world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: (x, 10.0).into(),
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(ShapePolygon::new_box_center(0.5, 0.5).into_boxed(), 1.0),
+            )
+        };
+
+        // 重量是10N
+        let strong = create_box(&mut world, 0.0);
+        let strong_joint = world.create_joint(RevoluteJointDef::with_anchor(
+            &world,
+            ground,
+            strong,
+            Vector2::new(0.0, 10.5),
+        ));
+        world
+            .joint_mut(strong_joint)
+            .unwrap()
+            .set_break_force(Some(100.0));
+
+        let weak = create_box(&mut world, 5.0);
+        let weak_joint = world.create_joint(RevoluteJointDef::with_anchor(
+            &world,
+            ground,
+            weak,
+            Vector2::new(5.0, 10.5),
+        ));
+        world
+            .joint_mut(weak_joint)
+            .unwrap()
+            .set_break_force(Some(5.0));
+
+        for _ in 0..60 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+
+        assert_eq!(world.joint_count(), 1);
+        let joint = world.joint(strong_joint).unwrap();
+        let force = joint.reaction_force(60.0);
+        assert!(
+            (force - Vector2::new(0.0, 10.0)).length() < 0.1,
+            "{:?}",
+            force
+        );
+        assert!(joint.reaction_torque(60.0).abs() < 0.01);
+        assert!(world.body(weak).unwrap().position().y < 9.0);
+
+        let broken = broken.borrow();
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].joint, weak_joint);
+        assert!(broken[0].reaction_force.length() > 5.0);
+    }
+
+    #[test]
+    fn test_debug_draw_joints() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        use xmath::Transform;
+
+        type Segments = Rc<RefCell<Vec<(Vector2<f32>, Vector2<f32>)>>>;
+
+        struct Collector(Segments);
+
+        impl DebugDraw for Collector {
+            fn draw_polygon(&mut self, _vertices: &[Vector2<f32>], _color: Color) {}
+            fn draw_solid_polygon(&mut self, _vertices: &[Vector2<f32>], _color: Color) {}
+            fn draw_circle(&mut self, _center: &Vector2<f32>, _radius: f32, _color: Color) {}
+            fn draw_solid_circle(
+                &mut self,
+                _center: &Vector2<f32>,
+                _radius: f32,
+                _axis: &Vector2<f32>,
+                _color: Color,
+            ) {
+            }
+            fn draw_segment(&mut self, p1: &Vector2<f32>, p2: &Vector2<f32>, _color: Color) {
+                self.0.borrow_mut().push((*p1, *p2));
+            }
+            fn draw_transform(&mut self, _xf: &Transform<f32>) {}
+            fn draw_point(&mut self, _p: &Vector2<f32>, _color: Color) {}
+        }
+
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        let segments = Rc::new(RefCell::new(Vec::new()));
+        world.set_debug_draw(Collector(segments.clone()));
+        world.set_debug_draw_flags(DebugDrawFlags::JOINT);
+
+        let ground = world.create_body(BodyDef::default());
+        let body = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (2.0, 0.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapeCircle::new(Vector2::zero(), 0.5).into_boxed(), 1.0),
+        );
+        world.create_joint(RevoluteJointDef::with_anchor(
+            &world,
+            ground,
+            body,
+            Vector2::new(1.0, 0.0),
+        ));
+        world.draw_debug_data();
+
+        // 刚体A原点到锚点、两个锚点之间、刚体B原点到锚点
+        assert_eq!(
+            &*segments.borrow(),
+            &[
+                (Vector2::new(0.0, 0.0), Vector2::new(1.0, 0.0)),
+                (Vector2::new(1.0, 0.0), Vector2::new(1.0, 0.0)),
+                (Vector2::new(2.0, 0.0), Vector2::new(1.0, 0.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_destroy_body() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(ShapePolygon::new_box_center(10.0, 0.5).into_boxed(), 0.0),
+        );
+        let create_box = |world: &mut World<f32, ()>, x: f32| {
+            world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: (x, 1.0).into(),
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(ShapePolygon::new_box_center(0.5, 0.5).into_boxed(), 1.0),
+            )
+        };
+        let box1 = create_box(&mut world, -2.0);
+        let box2 = create_box(&mut world, 2.0);
+
+        for _ in 0..60 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        assert_eq!(world.contact_count(), 2);
+
+        world.destroy_body(box1);
+        assert!(world.body(box1).is_none());
+        assert_eq!(world.body_count(), 2);
+        assert_eq!(world.contact_count(), 1);
+
+        // 新刚体复用了槽位，旧的句柄仍然无效
+        let box3 = create_box(&mut world, -2.0);
+        assert_ne!(box1, box3);
+        assert!(world.body(box1).is_none());
+        world.destroy_body(box1);
+        assert_eq!(world.body_count(), 3);
+
+        for _ in 0..60 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        assert_eq!(world.contact_count(), 2);
+        assert!(world.body(box2).is_some());
+        assert!(world.body(box3).is_some());
+    }
+
+    #[test]
+    fn test_set_mass_data() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        let id = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapePolygon::new_box_center(0.5, 0.5).into_boxed(), 1.0),
+        );
+        let default_mass = world.body(id).unwrap().mass_data();
+        assert!((default_mass.mass - 1.0).abs() < 1e-5);
+
+        world.set_body_mass_data(
+            id,
+            &MassData {
+                mass: 4.0,
+                center: Vector2::new(0.5, 0.0),
+                i: 3.0,
+            },
+        );
+        let body = world.body_mut(id).unwrap();
+        let mass_data = body.mass_data();
+        assert!((mass_data.mass - 4.0).abs() < 1e-5);
+        assert_eq!(mass_data.center, Vector2::new(0.5, 0.0));
+        assert!((mass_data.i - 3.0).abs() < 1e-5);
+        assert_eq!(*body.world_center(), Vector2::new(0.5, 0.0));
+
+        body.reset_mass_data();
+        let mass_data = body.mass_data();
+        assert!((mass_data.mass - default_mass.mass).abs() < 1e-5);
+        assert_eq!(mass_data.center, default_mass.center);
+        assert!((mass_data.i - default_mass.i).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_world_fixtures() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        let body_a = world.create_body(BodyDef::default());
+        let fixture_a = world.create_fixture(
+            body_a,
+            FixtureDef::new(ShapePolygon::new_box_center(0.5, 0.5).into_boxed(), 1.0),
+        );
+        let body_b = world.create_body(BodyDef::default());
+        let fixture_b = world.create_fixture(
+            body_b,
+            FixtureDef::new(ShapeCircle::new(Vector2::zero(), 0.5).into_boxed(), 1.0),
+        );
+
+        let fixture = world.fixture(body_b, fixture_b).unwrap();
+        assert_eq!(fixture.id(), fixture_b);
+        assert_eq!(fixture.body(), body_b);
+
+        let mut ids = world
+            .fixtures()
+            .map(|f| (f.body(), f.id()))
+            .collect::<Vec<_>>();
+        ids.sort_by_key(|(body, _)| body.index);
+        assert_eq!(ids, vec![(body_a, fixture_a), (body_b, fixture_b)]);
+
+        world.destroy_body(body_a);
+        assert!(world.fixture(body_a, fixture_a).is_none());
+        assert_eq!(world.fixtures().count(), 1);
+    }
+
+    #[test]
+    fn test_contacts() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        let ground = world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(ShapePolygon::new_box_center(10.0, 0.5).into_boxed(), 0.0),
+        );
+        let body = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (0.0, 1.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapePolygon::new_box_center(0.5, 0.5).into_boxed(), 1.0),
+        );
+        let other = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (5.0, 5.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapeCircle::new(Vector2::zero(), 0.5).into_boxed(), 1.0),
+        );
+        assert_eq!(world.contacts().count(), 0);
+
+        world.step(1.0 / 60.0, 8, 3);
+        world.step(1.0 / 60.0, 8, 3);
+
+        let contacts = world.contacts().collect::<Vec<_>>();
+        assert_eq!(contacts.len(), 1);
+        let contact = contacts[0];
+        assert!(contact.is_touching());
+        assert_eq!(contact.manifold().point_count, 2);
+        let mut bodies = [
+            contact.body_a(),
+            contact.body_b(),
+        ];
+        bodies.sort_by_key(|id| id.index);
+        assert_eq!(bodies, [ground, body]);
+
+        assert_eq!(world.body_contacts(body).count(), 1);
+        assert_eq!(world.body_contacts(ground).count(), 1);
+        assert_eq!(world.body_contacts(other).count(), 0);
+    }
+
+    #[test]
+    fn test_decompose_polygon() {
+        // 顺时针的U形，面积是5
+        let outline = [
+            Vector2::new(0.0, 0.0),
+            Vector2::new(0.0, 2.0),
+            Vector2::new(1.0, 2.0),
+            Vector2::new(1.0, 1.0),
+            Vector2::new(2.0, 1.0),
+            Vector2::new(2.0, 2.0),
+            Vector2::new(3.0, 2.0),
+            Vector2::new(3.0, 0.0),
+            Vector2::new(1.5, 0.0),
+        ];
+        let pieces = decompose_polygon(&outline);
+        assert!(pieces.len() >= 3 && pieces.len() < 7, "{}", pieces.len());
+        let area: f32 = pieces.iter().map(|p| p.compute_mass(1.0).mass).sum();
+        assert!((area - 5.0).abs() < 1e-4, "{}", area);
+
+        // 凸多边形不需要分解
+        let pieces = decompose_polygon(&outline[..3]);
+        assert_eq!(pieces.len(), 1);
+        assert!(decompose_polygon(&outline[..2]).is_empty());
+    }
+
+    #[test]
+    fn test_distance() {
+        use xmath::{Rotation, Transform};
+
+        let circle = ShapeCircle::new(Vector2::zero(), 0.5);
+        let square = ShapePolygon::new_box_center(1.0, 1.0);
+        let output = distance(
+            &circle,
+            Transform::identity(),
+            &square,
+            Transform::new(Vector2::new(3.0, 0.0), Rotation::new(0.0)),
+        );
+        // 多边形有0.01的外皮
+        assert!((output.distance - 1.49).abs() < 1e-5);
+        assert!((output.point_a - Vector2::new(0.5, 0.0)).length() < 1e-5);
+        assert!((output.point_b - Vector2::new(1.99, 0.0)).length() < 1e-5);
+
+        let output = distance(
+            &circle,
+            Transform::identity(),
+            &square,
+            Transform::identity(),
+        );
+        assert_eq!(output.distance, 0.0);
+
+        let chain = ShapeChain::create_chain(vec![(-5.0, 3.0), (-5.0, -2.0), (5.0, -2.0)]);
+        let output = distance(
+            &circle,
+            Transform::identity(),
+            &chain,
+            Transform::identity(),
+        );
+        assert!((output.distance - 1.49).abs() < 1e-5);
+        assert!((output.point_b - Vector2::new(0.0, -1.99)).length() < 1e-5);
+    }
+
+    #[test]
+    fn test_overlap_shape() {
+        use xmath::Transform;
+
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        let ground = world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(
+                ShapeChain::create_chain(vec![(-5.0, 0.0), (0.0, 0.0), (5.0, 0.0)]).into_boxed(),
+                0.0,
+            ),
+        );
+        let body = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (0.0, 1.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapePolygon::new_box_center(0.5, 0.5).into_boxed(), 1.0),
+        );
+        world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (3.0, 1.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapeCircle::new(Vector2::zero(), 0.5).into_boxed(), 1.0),
+        );
+
+        let circle = ShapeCircle::new(Vector2::zero(), 0.3);
+        let xf = Transform::new(Vector2::new(0.0, 0.2), xmath::Rotation::new(0.0));
+        assert!(test_overlap(
+            &circle,
+            xf,
+            world.body(ground).unwrap().fixture_list()[0].1.shape(),
+            Transform::identity()
+        ));
+
+        let mut bodies = world
+            .overlap_shape(&circle, xf, |_| true)
+            .map(|f| f.body())
+            .collect::<Vec<_>>();
+        bodies.sort_by_key(|id| id.index);
+        assert_eq!(bodies, [ground, body]);
+
+        let xf = Transform::new(Vector2::new(1.5, 1.0), xmath::Rotation::new(0.0));
+        assert_eq!(world.overlap_shape(&circle, xf, |_| true).count(), 0);
+    }
+
+    #[test]
+    fn test_ray_cast_closest() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        let ground = world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(ShapeEdge::new((-10.0, 0.0), (10.0, 0.0)).into_boxed(), 0.0),
+        );
+        let body = world.create_body_with_fixture(
+            BodyDef {
+                position: (0.0, 2.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapeCircle::new(Vector2::zero(), 0.5).into_boxed(), 1.0),
+        );
+        world.step(1.0 / 60.0, 8, 3);
+
+        let hit = world
+            .ray_cast_closest((0.0, 5.0).into(), (0.0, -5.0).into(), |_| true)
+            .unwrap();
+        assert_eq!(hit.fixture.body(), body);
+        assert!((hit.point - Vector2::new(0.0, 2.5)).length() < 1e-4);
+        assert!((hit.normal - Vector2::new(0.0, 1.0)).length() < 1e-4);
+        assert!((hit.fraction - 0.25).abs() < 1e-4);
+
+        let hit = world
+            .ray_cast_closest((0.0, 5.0).into(), (0.0, -5.0).into(), |f| {
+                f.body() != body
+            })
+            .unwrap();
+        assert_eq!(hit.fixture.body(), ground);
+        assert!((hit.point - Vector2::new(0.0, 0.0)).length() < 1e-4);
+
+        assert!(world
+            .ray_cast_closest((5.0, 5.0).into(), (5.0, 1.0).into(), |_| true)
+            .is_none());
+    }
+
+    #[test]
+    fn test_query_filter() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        let ground = world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(ShapeEdge::new((-10.0, 0.0), (10.0, 0.0)).into_boxed(), 0.0),
+        );
+        let sensor = world.create_body_with_fixture(
+            BodyDef {
+                position: (0.0, 2.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef {
+                is_sensor: true,
+                ..FixtureDef::new(ShapeCircle::new(Vector2::zero(), 0.5).into_boxed(), 1.0)
+            },
+        );
+        let body = world.create_body_with_fixture(
+            BodyDef {
+                position: (0.0, 4.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef {
+                filter: Filter {
+                    category_bits: 0x0002,
+                    ..Filter::default()
+                },
+                ..FixtureDef::new(ShapeCircle::new(Vector2::zero(), 0.5).into_boxed(), 1.0)
+            },
+        );
+        world.step(1.0 / 60.0, 8, 3);
+
+        let p1 = Vector2::new(0.0, 5.0);
+        let p2 = Vector2::new(0.0, -5.0);
+        let hit_body = |filter: QueryFilter| {
+            world
+                .ray_cast_closest(p1, p2, |f| filter.test(f))
+                .map(|hit| hit.fixture.body())
+        };
+        assert_eq!(hit_body(QueryFilter::default()), Some(body));
+        assert_eq!(
+            hit_body(QueryFilter {
+                mask_bits: !0x0002,
+                ..QueryFilter::default()
+            }),
+            Some(sensor)
+        );
+        assert_eq!(
+            hit_body(QueryFilter {
+                mask_bits: !0x0002,
+                ignore_sensors: true,
+                ..QueryFilter::default()
+            }),
+            Some(ground)
+        );
+        assert_eq!(
+            hit_body(QueryFilter {
+                ignore_body: Some(body),
+                ignore_sensors: true,
+                ..QueryFilter::default()
+            }),
+            Some(ground)
+        );
+
+        let aabb = xmath::AABB::new(Vector2::new(-1.0, -1.0), Vector2::new(1.0, 5.0));
+        assert_eq!(world.query_aabb(aabb).count(), 3);
+        assert_eq!(
+            world.query_aabb_filtered(aabb, |f| !f.is_sensor()).count(),
+            2
+        );
+        let input = || RayCastInput {
+            p1,
+            p2,
+            max_fraction: 1.0,
+        };
+        assert_eq!(world.ray_cast(input()).count(), 3);
+        assert_eq!(
+            world
+                .ray_cast_filtered(input(), |f| f.body() != ground)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_compound_shape() {
+        use xmath::{Rotation, Transform};
+
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+
+        // 圆先于地面创建，接触的形状顺序需要交换
+        let ball = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (5.0, 2.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapeCircle::new(Vector2::zero(), 0.5).into_boxed(), 1.0),
+        );
+        world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(
+                ShapePolygon::new_box(10.0, 0.5, (0.0, -0.5), 0.0).into_boxed(),
+                0.0,
+            ),
+        );
+
+        let mut compound = ShapeCompound::new();
+        compound.add_polygon(
+            ShapePolygon::new_box_center(0.5, 0.5),
+            Transform::new(Vector2::new(-1.0, 0.0), Rotation::new(0.0)),
+        );
+        compound.add_circle(
+            ShapeCircle::new(Vector2::zero(), 0.5),
+            Transform::new(Vector2::new(1.0, 0.0), Rotation::new(0.0)),
+        );
+        let body = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (0.0, 3.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(compound.into_boxed(), 1.0),
+        );
+        assert_eq!(world.proxy_count(), 4);
+
+        let circle_mass = std::f32::consts::PI * 0.25;
+        let b = world.body(body).unwrap();
+        assert!((b.mass() - (1.0 + circle_mass)).abs() < 1e-4);
+        let center_x = (circle_mass - 1.0) / (1.0 + circle_mass);
+        assert!((b.local_center().x - center_x).abs() < 1e-4);
+
+        for _ in 0..180 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        let b = world.body(body).unwrap();
+        assert!((b.position().y - 0.5).abs() < 0.05);
+        assert!(b.angle().abs() < 0.05);
+        assert_eq!(world.body_contacts(body).count(), 2);
+        assert!((world.body(ball).unwrap().position().y - 0.5).abs() < 0.05);
+
+        let hit = world
+            .ray_cast_closest((1.0, 5.0).into(), (1.0, -5.0).into(), |_| true)
+            .unwrap();
+        assert_eq!(hit.fixture.body(), body);
+        assert!((hit.point.y - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_contact_events() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(ShapePolygon::new_box_center(10.0, 0.5).into_boxed(), 0.0),
+        );
+        let body = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (0.0, 1.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapePolygon::new_box_center(0.5, 0.5).into_boxed(), 1.0),
+        );
+
+        world.set_contact_events_enabled(true);
+        world.step(1.0 / 60.0, 8, 3);
+        let events = world.drain_contact_events().collect::<Vec<_>>();
+        assert!(matches!(events[0], ContactEvent::BeginContact(_)));
+        assert!(matches!(events[1], ContactEvent::PreSolve { .. }));
+        match events[2] {
+            ContactEvent::PostSolve { contact, impulse } => {
+                assert!(contact.body_a == body || contact.body_b == body);
+                assert_eq!(impulse.count, 2);
+                assert!(impulse.normal_impulses[0] > 0.0);
+            }
+            _ => panic!(),
+        }
+        assert_eq!(events.len(), 3);
+        assert_eq!(world.drain_contact_events().count(), 0);
+
+        world.step(1.0 / 60.0, 8, 3);
+        assert!(world.drain_contact_events().all(|event| matches!(
+            event,
+            ContactEvent::PreSolve { .. } | ContactEvent::PostSolve { .. }
+        )));
+
+        world.destroy_body(body);
+        let events = world.drain_contact_events().collect::<Vec<_>>();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], ContactEvent::EndContact(_)));
+    }
+
+    #[test]
+    fn test_material_mixer() {
+        struct MinMixer;
+
+        impl MaterialMixer<f32, ()> for MinMixer {
+            fn mix_friction(&self, a: &Fixture<f32, ()>, b: &Fixture<f32, ()>) -> f32 {
+                a.friction().min(b.friction())
+            }
+
+            fn mix_restitution(&self, a: &Fixture<f32, ()>, b: &Fixture<f32, ()>) -> f32 {
+                a.restitution().min(b.restitution())
+            }
+        }
+
+        struct ResetListener;
+
+        impl ContactListener<f32, ()> for ResetListener {
+            fn pre_solve(&self, contact: &mut ContactMut<f32, ()>, _old_manifold: &Manifold<f32>) {
+                contact.set_friction(1.0);
+                contact.set_restitution(1.0);
+                contact.reset_friction();
+                contact.reset_restitution();
+            }
+        }
+
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        world.set_material_mixer(MinMixer);
+        world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef {
+                friction: 0.6,
+                restitution: 0.3,
+                ..FixtureDef::new(ShapePolygon::new_box_center(10.0, 0.5).into_boxed(), 0.0)
+            },
+        );
+        world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (0.0, 1.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef {
+                friction: 0.2,
+                restitution: 0.5,
+                ..FixtureDef::new(ShapePolygon::new_box_center(0.5, 0.5).into_boxed(), 1.0)
+            },
+        );
+
+        world.step(1.0 / 60.0, 8, 3);
+        let contact = world.contacts().next().unwrap();
+        assert_eq!(contact.friction(), 0.2);
+        assert_eq!(contact.restitution(), 0.3);
+
+        world.set_contact_listener(ResetListener);
+        world.step(1.0 / 60.0, 8, 3);
+        let contact = world.contacts().next().unwrap();
+        assert_eq!(contact.friction(), 0.2);
+        assert_eq!(contact.restitution(), 0.3);
+    }
+
+    #[test]
+    fn test_force_generator() {
+        // 把刚体吸向原点
+        struct Attractor(f32);
+
+        impl ForceGenerator<f32, ()> for Attractor {
+            fn apply(&mut self, body: &mut Body<f32, ()>, _dt: f32) {
+                let force = -*body.world_center() * self.0;
+                body.apply_force_to_center(force, true);
+            }
+        }
+
+        let mut world = World::<f32, ()>::new(Vector2::zero());
+        let ground = world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(
+                ShapeCircle::new(Vector2::new(10.0, 0.0), 0.5).into_boxed(),
+                0.0,
+            ),
+        );
+        let ball = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (0.0, 5.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapeCircle::new(Vector2::zero(), 0.5).into_boxed(), 1.0),
+        );
+
+        let id = world.add_force_generator(Attractor(10.0));
+        for _ in 0..10 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        assert!(world.body(ball).unwrap().linear_velocity().y < 0.0);
+        assert_eq!(*world.body(ground).unwrap().position(), Vector2::zero());
+
+        assert!(world.remove_force_generator(id).is_some());
+        assert!(world.remove_force_generator(id).is_none());
+        let velocity = *world.body(ball).unwrap().linear_velocity();
+        world.step(1.0 / 60.0, 8, 3);
+        assert_eq!(*world.body(ball).unwrap().linear_velocity(), velocity);
+    }
+
+    #[test]
+    fn test_one_way_platform() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef {
+                one_way_normal: Some(Vector2::new(0.0, 1.0)),
+                ..FixtureDef::new(ShapePolygon::new_box_center(5.0, 0.25).into_boxed(), 0.0)
+            },
+        );
+        let create_ball = |world: &mut World<f32, ()>, x: f32, y: f32, vy: f32| {
+            let id = world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: (x, y).into(),
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(ShapeCircle::new(Vector2::zero(), 0.5).into_boxed(), 1.0),
+            );
+            world
+                .body_mut(id)
+                .unwrap()
+                .set_linear_velocity(Vector2::new(0.0, vy));
+            id
+        };
+
+        // 从下面跳上平台，从上面落下的停在平台上
+        let jumper = create_ball(&mut world, -2.0, -2.0, 12.0);
+        let faller = create_ball(&mut world, 2.0, 3.0, 0.0);
+        for _ in 0..180 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        for id in [jumper, faller] {
+            let y = world.body(id).unwrap().position().y;
+            assert!((y - 0.75).abs() < 0.02, "{}", y);
+        }
+    }
+
+    #[test]
+    fn test_particle_system() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        world.create_body_with_fixtures(
+            BodyDef::default(),
+            vec![
+                FixtureDef::new(ShapeEdge::new((-2.0, 0.0), (2.0, 0.0)).into_boxed(), 0.0),
+                FixtureDef::new(ShapeEdge::new((-2.0, 0.0), (-2.0, 4.0)).into_boxed(), 0.0),
+                FixtureDef::new(ShapeEdge::new((2.0, 0.0), (2.0, 4.0)).into_boxed(), 0.0),
+            ],
+        );
+        let system = world.create_particle_system(ParticleSystemDef::new(0.05));
+        world
+            .particle_system_mut(system)
+            .unwrap()
+            .create_particle_group(ParticleGroupDef::new(
+                ShapePolygon::new_box_center(0.5, 0.5).into_boxed(),
+                Vector2::new(-1.0, 1.0),
+            ));
+        let count = world.particle_system(system).unwrap().particle_count();
+        assert!(count > 100);
+
+        // 水在容器里散开
+        for _ in 0..240 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        let system = world.particle_system(system).unwrap();
+        assert_eq!(system.particle_count(), count);
+        let max_y = system.positions().iter().map(|p| p.y).fold(0.0, f32::max);
+        assert!(system
+            .positions()
+            .iter()
+            .all(|p| p.y > 0.0 && p.x.abs() < 2.0));
+        assert!(max_y < 0.5, "{}", max_y);
+
+        // 粒子撞到刚体会把它推开
+        let mut world = World::<f32, ()>::new(Vector2::zero());
+        let body = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (1.0, 0.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapePolygon::new_box_center(0.25, 0.5).into_boxed(), 1.0),
+        );
+        let system = world.create_particle_system(ParticleSystemDef::new(0.05));
+        world
+            .particle_system_mut(system)
+            .unwrap()
+            .create_particle_group(ParticleGroupDef {
+                linear_velocity: Vector2::new(4.0, 0.0),
+                ..ParticleGroupDef::new(
+                    ShapePolygon::new_box_center(0.25, 0.25).into_boxed(),
+                    Vector2::new(0.0, 0.0),
+                )
+            });
+        for _ in 0..30 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        let body = world.body(body).unwrap();
+        assert!(body.linear_velocity().x > 0.1);
+        let left = body.position().x - 0.25;
+        let system = world.particle_system(system).unwrap();
+        assert!(system.positions().iter().all(|p| p.x < left));
+    }
+
+    #[test]
+    fn test_rope() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        world.create_body_with_fixture(
+            BodyDef {
+                position: (0.0, 2.5).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapePolygon::new_box_center(4.0, 0.5).into_boxed(), 0.0),
+        );
+
+        // 一端固定的绳子落到地面上
+        let vertices = (0..11)
+            .map(|i| Vector2::new(i as f32 * 0.2, 4.0))
+            .collect::<Vec<_>>();
+        let mut def = RopeDef::new(vertices, 0.1);
+        def.masses[0] = 0.0;
+        let rope = world.create_rope(def);
+        for _ in 0..180 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        let rope = world.rope(rope).unwrap();
+        let vertices = rope.vertices();
+        assert_eq!(vertices[0], Vector2::new(0.0, 4.0));
+        assert!(vertices.iter().all(|p| p.y > 3.0));
+        assert!(vertices[10].y < 3.2, "{:?}", vertices[10]);
+        assert!(vertices
+            .windows(2)
+            .all(|s| ((s[1] - s[0]).length() - 0.2).abs() < 0.02));
+
+        // 挂在绳子上的刚体
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        let body = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (2.0, 4.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapeCircle::new((0.0, 0.0), 0.1).into_boxed(), 1.0),
+        );
+        let vertices = (0..11)
+            .map(|i| Vector2::new(i as f32 * 0.2, 4.0))
+            .collect::<Vec<_>>();
+        let mut def = RopeDef::new(vertices, 0.1);
+        def.masses[0] = 0.0;
+        let rope = world.create_rope(def);
+        world
+            .rope_mut(rope)
+            .unwrap()
+            .attach(10, body, Vector2::zero());
+        for _ in 0..600 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        let body = world.body(body).unwrap();
+        let distance = (*body.position() - Vector2::new(0.0, 4.0)).length();
+        assert!(distance > 1.8 && distance < 2.2, "{:?}", body.position());
+        let end = world.rope(rope).unwrap().vertices()[10];
+        assert!((end - *body.position()).length() < 0.01);
+    }
+
+    #[test]
+    fn test_top_down_controller() {
+        let mut world = World::<f32, ()>::new(Vector2::zero());
+        world.create_body_with_fixture(
+            BodyDef {
+                position: (2.0, 0.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapePolygon::new_box_center(0.5, 10.0).into_boxed(), 0.0),
+        );
+        world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (1.0, -3.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapePolygon::new_box_center(0.5, 0.5).into_boxed(), 1.0),
+        );
+        world.step(1.0 / 60.0, 8, 3);
+
+        // 斜着撞墙后沿墙滑动，动态刚体不会挡住角色
+        let controller = TopDownController::new();
+        let shape = ShapeCircle::new((0.0, 0.0), 0.5);
+        let mut position = Vector2::new(0.0, 0.0);
+        for _ in 0..10 {
+            position = controller.move_shape(&world, &shape, position, Vector2::new(0.5, -0.5));
+        }
+        assert!(position.x > 0.99 && position.x < 1.01, "{:?}", position);
+        assert!((position.y + 5.0).abs() < 0.01, "{:?}", position);
+
+        // 离开墙壁
+        let next = controller.move_shape(&world, &shape, position, Vector2::new(-0.5, 0.0));
+        assert!((next.x - position.x + 0.5).abs() < 0.01, "{:?}", next);
+
+        // 重叠时先被推出来
+        let position =
+            controller.move_shape(&world, &shape, Vector2::new(1.3, 0.0), Vector2::zero());
+        assert!(position.x > 0.99 && position.x < 1.01, "{:?}", position);
+    }
+
+    #[test]
+    fn test_character_controller() {
+        let mut world = World::<f32, ()>::new(Vector2::zero());
+        let mut create_box = |hx: f32, hy: f32, position: Vector2<f32>| {
+            world.create_body_with_fixture(
+                BodyDef {
+                    position,
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(ShapePolygon::new_box_center(hx, hy).into_boxed(), 0.0),
+            );
+        };
+        create_box(10.0, 0.5, Vector2::new(0.0, -0.5));
+        create_box(1.0, 0.05, Vector2::new(3.0, 0.05));
+        create_box(0.5, 5.0, Vector2::new(6.5, 5.0));
+
+        // 落到地面上
+        let mut controller = CharacterController::new(
+            ShapePolygon::new_box_center(0.25, 0.5).into_boxed(),
+            Vector2::new(0.0, 2.0),
+        );
+        for _ in 0..20 {
+            controller.move_by(&world, Vector2::new(0.0, -0.2));
+        }
+        assert!(controller.is_grounded());
+        assert!((controller.position().y - 0.5).abs() < 0.02);
+
+        // 走上台阶，再被墙挡住
+        let mut max_y = 0.0f32;
+        for _ in 0..40 {
+            controller.move_by(&world, Vector2::new(0.2, -0.05));
+            max_y = max_y.max(controller.position().y);
+            assert!(controller.is_grounded(), "{:?}", controller.position());
+        }
+        assert!((max_y - 0.6).abs() < 0.02, "{}", max_y);
+        assert!(controller.flags().contains(CharacterFlags::WALL));
+        assert!((controller.position().x - 5.75).abs() < 0.02);
+        assert!((controller.position().y - 0.5).abs() < 0.02);
+
+        // 跳起来离开地面
+        controller.move_by(&world, Vector2::new(0.0, 0.5));
+        assert!(!controller.is_grounded());
+    }
+
+    #[test]
+    fn test_adhesive_fixture() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        world.create_body_with_fixture(
+            BodyDef {
+                position: (5.0, 5.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapePolygon::new_box_center(0.5, 5.0).into_boxed(), 0.0),
+        );
+
+        // 射到墙上的粘性弹丸不会掉下来
+        let projectile = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (0.0, 5.0).into(),
+                linear_velocity: (20.0, 0.0).into(),
+                bullet: true,
+                ..BodyDef::default()
+            },
+            FixtureDef {
+                adhesion: Some(10.0),
+                ..FixtureDef::new(ShapeCircle::new((0.0, 0.0), 0.1).into_boxed(), 1.0)
+            },
+        );
+        for _ in 0..60 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        assert_eq!(world.joint_count(), 1);
+        let position = *world.body(projectile).unwrap().position();
+        assert!((position.x - 4.4).abs() < 0.05, "{:?}", position);
+        assert!(position.y > 4.5, "{:?}", position);
+
+        // 拉力超过阈值时脱落
+        for _ in 0..30 {
+            world
+                .body_mut(projectile)
+                .unwrap()
+                .apply_force_to_center((-20.0, 0.0).into(), true);
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        assert_eq!(world.joint_count(), 0);
+        assert!(world.body(projectile).unwrap().position().x < 4.0);
+    }
+
+    #[test]
+    fn test_dominance() {
+        let mut world = World::<f32, ()>::new(Vector2::zero());
+        let mut create_box = |position: Vector2<f32>, velocity: Vector2<f32>, dominance: i8| {
+            world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position,
+                    linear_velocity: velocity,
+                    dominance,
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(ShapePolygon::new_box_center(0.5, 0.5).into_boxed(), 1.0),
+            )
+        };
+        let boss = create_box(Vector2::new(0.0, 0.0), Vector2::new(2.0, 0.0), 1);
+        let debris = create_box(Vector2::new(2.0, 0.0), Vector2::new(-2.0, 0.0), 0);
+
+        // 等级高的刚体不受影响，等级低的被推开
+        for _ in 0..60 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        let boss = world.body(boss).unwrap();
+        let debris = world.body(debris).unwrap();
+        assert!((boss.linear_velocity().x - 2.0).abs() < 1e-3);
+        assert!(debris.linear_velocity().x >= 2.0 - 1e-3);
+        assert!(debris.position().x - boss.position().x > 0.99);
+    }
+
+    #[test]
+    fn test_islands() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(ShapeEdge::new((-10.0, 0.0), (10.0, 0.0)).into_boxed(), 0.0),
+        );
+        let mut create_box = |x: f32, y: f32| {
+            world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: (x, y).into(),
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(ShapePolygon::new_box_center(0.5, 0.5).into_boxed(), 1.0),
+            )
+        };
+        let a = create_box(-5.0, 0.5);
+        let b = create_box(-5.0, 1.5);
+        let c = create_box(5.0, 0.5);
+
+        // 通过地面接触的刚体不在同一个岛中
+        world.step(1.0 / 60.0, 8, 3);
+        world.step(1.0 / 60.0, 8, 3);
+        assert_eq!(world.islands().len(), 2);
+        let island_a = world.body(a).unwrap().island().unwrap();
+        assert_eq!(world.body(b).unwrap().island(), Some(island_a));
+        assert_ne!(world.body(c).unwrap().island(), Some(island_a));
+        let info = &world.islands()[island_a];
+        assert_eq!(info.body_count(), 2);
+        let mut bodies = world.island_bodies(island_a).to_vec();
+        bodies.sort_by_key(|id| id.index);
+        assert_eq!(bodies, vec![a, b]);
+        assert_eq!(info.contact_count, 2);
+        assert!(!info.sleeping);
+
+        let mut slept = false;
+        for _ in 0..120 {
+            world.step(1.0 / 60.0, 8, 3);
+            slept |= world.islands().iter().any(|info| info.sleeping);
+        }
+        assert!(slept);
+        assert!(world.islands().is_empty());
+        assert_eq!(world.body(a).unwrap().island(), None);
+    }
+
+    #[test]
+    fn test_world_clear() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        world.set_contact_events_enabled(true);
+        let ground = world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(ShapeEdge::new((-10.0, 0.0), (10.0, 0.0)).into_boxed(), 0.0),
+        );
+        let body = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (0.0, 0.5).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapePolygon::new_box_center(0.5, 0.5).into_boxed(), 1.0),
+        );
+        world.create_joint(RevoluteJointDef {
+            collide_connected: true,
+            ..RevoluteJointDef::with_anchor(&world, ground, body, Vector2::new(0.0, 0.0))
+        });
+        world.create_rope(RopeDef::new(
+            vec![
+                Vector2::zero(),
+                Vector2::new(1.0, 0.0),
+                Vector2::new(2.0, 0.0),
+            ],
+            1.0,
+        ));
+        world.step(1.0 / 60.0, 8, 3);
+        assert_eq!(world.contact_count(), 1);
+
+        world.clear();
+        assert_eq!(world.body_count(), 0);
+        assert_eq!(world.joint_count(), 0);
+        assert_eq!(world.contact_count(), 0);
+        assert_eq!(world.proxy_count(), 0);
+        assert_eq!(world.drain_contact_events().count(), 0);
+        assert!(world.rope(RopeId(0)).is_none());
+        assert!(world.contact_events_enabled());
+
+        // 旧的句柄不会指向新的刚体，世界可以继续使用
+        let new_body = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (0.0, 2.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapeCircle::new((0.0, 0.0), 0.5).into_boxed(), 1.0),
+        );
+        assert!(world.body(ground).is_none());
+        assert!(world.body(body).is_none());
+        world.step(1.0 / 60.0, 8, 3);
+        assert!(world.body(new_body).unwrap().position().y < 2.0);
+        assert_eq!(world.proxy_count(), 1);
+    }
+
+    #[test]
+    fn test_world_bounds() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        struct Listener(Rc<RefCell<Vec<BodyId>>>);
+
+        impl DestructionListener<f32, ()> for Listener {
+            fn fixture_destroyed(&self, _fixture: &Fixture<f32, ()>) {}
+
+            fn body_out_of_bounds(&self, body: &Body<f32, ()>) {
+                self.0.borrow_mut().push(body.id());
+            }
+        }
+
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, 0.0));
+        let reported = Rc::new(RefCell::new(Vec::new()));
+        world.set_destruction_listener(Listener(reported.clone()));
+        world.set_bounds(
+            Some(xmath::AABB::new(
+                Vector2::new(-10.0, -10.0),
+                Vector2::new(10.0, 10.0),
+            )),
+            OutOfBoundsAction::Deactivate,
+        );
+        let create_ball = |world: &mut World<f32, ()>, y: f32| {
+            world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: (0.0, y).into(),
+                    linear_velocity: (60.0, 0.0).into(),
+                    bullet: true,
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(ShapeCircle::new((0.0, 0.0), 0.5).into_boxed(), 1.0),
+            )
+        };
+        let fast = create_ball(&mut world, 0.0);
+        let resting = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (0.0, 5.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapeCircle::new((0.0, 0.0), 0.5).into_boxed(), 1.0),
+        );
+
+        for _ in 0..20 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        assert_eq!(*reported.borrow(), vec![fast]);
+        assert!(!world.body(fast).unwrap().is_active());
+        assert!(world.body(fast).unwrap().position().x < 12.0);
+        assert!(world.body(resting).unwrap().is_active());
+
+        // 删除模式下刚体在报告之后被删除
+        world.set_bounds(world.bounds(), OutOfBoundsAction::Destroy);
+        let fast = create_ball(&mut world, -5.0);
+        for _ in 0..20 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        assert_eq!(reported.borrow().len(), 2);
+        assert!(world.body(fast).is_none());
+        assert_eq!(world.body_count(), 2);
+    }
+
+    #[test]
+    fn test_step_hooks() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        let body = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (0.0, 5.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapeCircle::new((0.0, 0.0), 0.5).into_boxed(), 1.0),
+        );
+
+        // 每一步抵消重力，步进结束后记录位置
+        world.set_pre_step(move |world, _dt| {
+            let body = world.body_mut(body).unwrap();
+            let force = Vector2::new(0.0, 10.0 * body.mass());
+            body.apply_force_to_center(force, true);
+        });
+        let positions = Rc::new(RefCell::new(Vec::new()));
+        world.set_post_step({
+            let positions = positions.clone();
+            move |world, dt| {
+                assert!(!world.is_locked());
+                assert!((dt - 1.0 / 60.0).abs() < f32::EPSILON);
+                positions
+                    .borrow_mut()
+                    .push(world.body(body).unwrap().position().y);
+            }
+        });
+
+        for _ in 0..10 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        assert_eq!(positions.borrow().len(), 10);
+        assert!(positions.borrow().iter().all(|y| (y - 5.0).abs() < 1e-4));
+
+        world.clear_step_hooks();
+        world.step(1.0 / 60.0, 8, 3);
+        assert_eq!(positions.borrow().len(), 10);
+        assert!(world.body(body).unwrap().position().y < 5.0);
+    }
+
+    #[test]
+    fn test_debug_draw_contacts() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        use xmath::Transform;
+
+        #[derive(Default)]
+        struct Collected {
+            points: Vec<Vector2<f32>>,
+            segments: Vec<(Vector2<f32>, Vector2<f32>)>,
+        }
+
+        struct Collector(Rc<RefCell<Collected>>);
+
+        impl DebugDraw for Collector {
+            fn draw_polygon(&mut self, _vertices: &[Vector2<f32>], _color: Color) {}
+            fn draw_solid_polygon(&mut self, _vertices: &[Vector2<f32>], _color: Color) {}
+            fn draw_circle(&mut self, _center: &Vector2<f32>, _radius: f32, _color: Color) {}
+            fn draw_solid_circle(
+                &mut self,
+                _center: &Vector2<f32>,
+                _radius: f32,
+                _axis: &Vector2<f32>,
+                _color: Color,
+            ) {
+            }
+            fn draw_segment(&mut self, p1: &Vector2<f32>, p2: &Vector2<f32>, _color: Color) {
+                self.0.borrow_mut().segments.push((*p1, *p2));
+            }
+            fn draw_transform(&mut self, _xf: &Transform<f32>) {}
+            fn draw_point(&mut self, p: &Vector2<f32>, _color: Color) {
+                self.0.borrow_mut().points.push(*p);
+            }
+        }
+
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        let collected = Rc::new(RefCell::new(Collected::default()));
+        world.set_debug_draw(Collector(collected.clone()));
+        world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(ShapeEdge::new((-10.0, 0.0), (10.0, 0.0)).into_boxed(), 0.0),
+        );
+        world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (0.0, 0.5).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapePolygon::new_box_center(0.5, 0.5).into_boxed(), 1.0),
+        );
+        for _ in 0..30 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+
+        // 默认不画接触
+        assert!(!world
+            .debug_draw_flags()
+            .intersects(DebugDrawFlags::CONTACT_POINT | DebugDrawFlags::CONTACT_IMPULSE));
+
+        world.set_debug_draw_flags(DebugDrawFlags::CONTACT_POINT | DebugDrawFlags::CONTACT_IMPULSE);
+        world.draw_debug_data();
+
+        let collected = collected.borrow();
+        assert_eq!(collected.points.len(), 2);
+        assert!(collected.points.iter().all(|p| p.y.abs() < 0.02));
+        assert_eq!(collected.segments.len(), 2);
+        for (p1, p2) in &collected.segments {
+            assert!(p2.y > p1.y);
+            assert!((p2.x - p1.x).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_debug_draw_tree_and_pairs() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        use xmath::Transform;
+
+        #[derive(Default)]
+        struct Collected {
+            polygons: usize,
+            segments: usize,
+        }
+
+        struct Collector(Rc<RefCell<Collected>>);
+
+        impl DebugDraw for Collector {
+            fn draw_polygon(&mut self, _vertices: &[Vector2<f32>], _color: Color) {
+                self.0.borrow_mut().polygons += 1;
+            }
+            fn draw_solid_polygon(&mut self, _vertices: &[Vector2<f32>], _color: Color) {}
+            fn draw_circle(&mut self, _center: &Vector2<f32>, _radius: f32, _color: Color) {}
+            fn draw_solid_circle(
+                &mut self,
+                _center: &Vector2<f32>,
+                _radius: f32,
+                _axis: &Vector2<f32>,
+                _color: Color,
+            ) {
+            }
+            fn draw_segment(&mut self, _p1: &Vector2<f32>, _p2: &Vector2<f32>, _color: Color) {
+                self.0.borrow_mut().segments += 1;
+            }
+            fn draw_transform(&mut self, _xf: &Transform<f32>) {}
+            fn draw_point(&mut self, _p: &Vector2<f32>, _color: Color) {}
+        }
+
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        let collected = Rc::new(RefCell::new(Collected::default()));
+        world.set_debug_draw(Collector(collected.clone()));
+        world.set_debug_draw_flags(DebugDrawFlags::TREE | DebugDrawFlags::PAIRS);
+        world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(ShapeEdge::new((-10.0, 0.0), (10.0, 0.0)).into_boxed(), 0.0),
+        );
+        for i in 0..3 {
+            world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: (0.0, 0.5 + i as f32).into(),
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(ShapePolygon::new_box_center(0.5, 0.5).into_boxed(), 1.0),
+            );
+        }
+        world.step(1.0 / 60.0, 8, 3);
+        world.draw_debug_data();
+
+        // 4个代理的平衡树有3个内部节点，每个接触画一条线
+        let collected = collected.borrow();
+        assert_eq!(collected.polygons, world.proxy_count() - 1);
+        assert_eq!(collected.segments, world.contact_count());
+        assert!(world.contact_count() >= 3);
+    }
+
+    #[test]
+    fn test_profile_counters() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(ShapeEdge::new((-10.0, 0.0), (10.0, 0.0)).into_boxed(), 0.0),
+        );
+        for i in 0..2 {
+            world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: (0.0, 0.5 + i as f32).into(),
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(ShapePolygon::new_box_center(0.5, 0.5).into_boxed(), 1.0),
+            );
+        }
+        world.step(1.0 / 60.0, 8, 3);
+        let profile = *world.profile();
+        assert_eq!(profile.body_count, 2);
+        assert!(profile.tree_query_count >= 3);
+        world.step(1.0 / 60.0, 8, 3);
+        assert_eq!(world.profile().contact_point_count, 4);
+
+        // 快速的子弹穿过薄墙之前会产生TOI事件
+        world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (-1.5, 5.0).into(),
+                linear_velocity: (500.0, 0.0).into(),
+                bullet: true,
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapeCircle::new((0.0, 0.0), 0.1).into_boxed(), 1.0),
+        );
+        world.create_body_with_fixture(
+            BodyDef {
+                position: (0.0, 5.0).into(),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapePolygon::new_box_center(0.05, 2.0).into_boxed(), 0.0),
+        );
+        world.step(1.0 / 60.0, 8, 3);
+        assert!(world.profile().toi_count >= 1);
+    }
+
+    #[test]
+    fn test_stack_allocator_reuse() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(ShapeEdge::new((-10.0, 0.0), (10.0, 0.0)).into_boxed(), 0.0),
+        );
+        for i in 0..5 {
+            world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: (0.0, 0.5 + i as f32).into(),
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(ShapePolygon::new_box_center(0.5, 0.5).into_boxed(), 1.0),
+            );
+        }
+        world.step(1.0 / 60.0, 8, 3);
+        world.step(1.0 / 60.0, 8, 3);
+
+        // 接触数量不变时约束数组不会重新分配
+        let allocator = &world.0.stack_allocator;
+        let velocity_ptr = allocator.velocity_constraints.as_ptr();
+        let position_ptr = allocator.position_constraints.as_ptr();
+        assert!(allocator.velocity_constraints.capacity() >= 5);
+        for _ in 0..10 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+        let allocator = &world.0.stack_allocator;
+        assert_eq!(allocator.velocity_constraints.as_ptr(), velocity_ptr);
+        assert_eq!(allocator.position_constraints.as_ptr(), position_ptr);
+    }
+
+    #[test]
+    fn test_world_reserve() {
+        let mut world = World::<f32, ()>::new(Vector2::new(0.0, -10.0));
+        world.reserve(64, 128);
+        assert!(world.0.island.bodies.capacity() >= 64);
+        assert!(world.0.island.contacts.capacity() >= 128);
+        assert!(world.0.island_stack.capacity() >= 64);
+        let bodies_ptr = world.0.island.bodies.as_ptr();
+        let stack_ptr = world.0.island_stack.as_ptr();
+
+        world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(ShapeEdge::new((-20.0, 0.0), (20.0, 0.0)).into_boxed(), 0.0),
+        );
+        for i in 0..40 {
+            world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: ((i % 10) as f32 - 5.0, 0.5 + (i / 10) as f32).into(),
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(ShapePolygon::new_box_center(0.45, 0.45).into_boxed(), 1.0),
+            );
+        }
+        for _ in 0..30 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+
+        // 岛的数组在步进之间复用，预留的空间足够时不会重新分配
+        assert_eq!(world.0.island.bodies.as_ptr(), bodies_ptr);
+        assert_eq!(world.0.island_stack.as_ptr(), stack_ptr);
+        assert!(world.0.island.bodies.is_empty());
+        assert_eq!(
+            world
+                .islands()
+                .iter()
+                .map(|island| island.body_count())
+                .sum::<usize>(),
+            40
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_world() {
+        let mut world = World::<f32, i32>::new(Vector2::new(0.0, -10.0));
+        world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(ShapeEdge::new((-20.0, 0.0), (20.0, 0.0)).into_boxed(), 0.0),
+        );
+        let ground = world.create_body(BodyDef::default());
+        let removed = world.create_body(BodyDef::default());
+        let pendulum = world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: (5.0, 10.0).into(),
+                data: Some(7),
+                ..BodyDef::default()
+            },
+            FixtureDef::new(ShapeCircle::new(Vector2::zero(), 0.5).into_boxed(), 1.0),
+        );
+        let joint = world.create_joint(RevoluteJointDef::with_anchor(
+            &world,
+            ground,
+            pendulum,
+            Vector2::new(0.0, 10.0),
+        ));
+        world
+            .joint_mut(joint)
+            .unwrap()
+            .set_break_force(Some(1000.0));
+        let boxes = (0..3)
+            .map(|i| {
+                world.create_body_with_fixture(
+                    BodyDef {
+                        type_: BodyType::Dynamic,
+                        position: (-5.0, 0.5 + i as f32).into(),
+                        ..BodyDef::default()
+                    },
+                    FixtureDef::new(ShapePolygon::new_box_center(0.5, 0.5).into_boxed(), 1.0),
+                )
+            })
+            .collect::<Vec<_>>();
+        world.destroy_body(removed);
+
+        for _ in 0..30 {
+            world.step(1.0 / 60.0, 8, 3);
+        }
+
+        let json = serde_json::to_string(&world).unwrap();
+        let mut loaded: World<f32, i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.body_count(), world.body_count());
+        assert_eq!(loaded.joint_count(), 1);
+        assert!(loaded.body(removed).is_none());
+        assert_eq!(loaded.body(pendulum).unwrap().data(), Some(&7));
+        assert_eq!(loaded.joint(joint).unwrap().break_force(), Some(1000.0));
+        assert_eq!(
+            loaded.body(pendulum).unwrap().position(),
+            world.body(pendulum).unwrap().position()
+        );
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+
+        // 新创建的刚体不会复用旧句柄
+        let new_body = loaded.create_body(BodyDef::default());
+        assert!(boxes.iter().all(|id| *id != new_body));
+
+        for _ in 0..60 {
+            world.step(1.0 / 60.0, 8, 3);
+            loaded.step(1.0 / 60.0, 8, 3);
+        }
+        for id in boxes.iter().chain(Some(&pendulum)) {
+            let p1 = world.body(*id).unwrap().position();
+            let p2 = loaded.body(*id).unwrap().position();
+            assert!((*p1 - *p2).length() < 0.05, "{:?} {:?}", p1, p2);
+        }
+    }
+
+    /// 金字塔、关节链和子弹组成的场景，返回每一帧后所有刚体的位置和角度
+    fn run_scene<T: xmath::Real>(steps: usize) -> Vec<(Vector2<T>, T)> {
+        let mut world = World::<T, ()>::new(Vector2::new(T::zero(), T::i32(-10)));
+        world.create_body_with_fixture(
+            BodyDef::default(),
+            FixtureDef::new(
+                ShapeEdge::new((T::i32(-40), T::zero()), (T::i32(40), T::zero())).into_boxed(),
+                T::zero(),
+            ),
+        );
+
+        let half = T::half();
+        let mut bodies = Vec::new();
+        for row in 0..4 {
+            for col in 0..(4 - row) {
+                let x = T::i32(col * 2 - (3 - row)) * T::f32(0.55);
+                let y = half + T::i32(row) * T::f32(1.05);
+                bodies.push(world.create_body_with_fixture(
+                    BodyDef {
+                        type_: BodyType::Dynamic,
+                        position: Vector2::new(x, y),
+                        ..BodyDef::default()
+                    },
+                    FixtureDef::new(
+                        ShapePolygon::new_box_center(half, half).into_boxed(),
+                        T::one(),
+                    ),
+                ));
+            }
+        }
+
+        let mut prev = world.create_body(BodyDef {
+            position: Vector2::new(T::i32(8), T::i32(10)),
+            ..BodyDef::default()
+        });
+        for i in 0..3 {
+            let link = world.create_body_with_fixture(
+                BodyDef {
+                    type_: BodyType::Dynamic,
+                    position: Vector2::new(T::i32(9 + i), T::i32(10)),
+                    angle: T::f32(0.3),
+                    ..BodyDef::default()
+                },
+                FixtureDef::new(
+                    ShapePolygon::new_box_center(half, T::f32(0.125)).into_boxed(),
+                    T::one(),
+                ),
+            );
+            world.create_joint(RevoluteJointDef::with_anchor(
+                &world,
+                prev,
+                link,
+                Vector2::new(T::i32(8 + i), T::i32(10)),
+            ));
+            bodies.push(link);
+            prev = link;
+        }
+
+        bodies.push(world.create_body_with_fixture(
+            BodyDef {
+                type_: BodyType::Dynamic,
+                position: Vector2::new(T::i32(-15), T::i32(1)),
+                linear_velocity: Vector2::new(T::i32(60), T::zero()),
+                bullet: true,
+                ..BodyDef::default()
+            },
+            FixtureDef::new(
+                ShapeCircle::new(Vector2::zero(), T::f32(0.25)).into_boxed(),
+                T::i32(4),
+            ),
+        ));
+
+        let dt = T::one() / T::i32(60);
+        let mut states = Vec::new();
+        for _ in 0..steps {
+            world.step(dt, 8, 3);
+            for id in &bodies {
+                let body = world.body(*id).unwrap();
+                states.push((*body.position(), body.angle()));
+            }
+        }
+        states
+    }
+
+    fn scene_checksum(states: &[(Vector2<xfixedpoint::FP>, xfixedpoint::FP)]) -> u64 {
+        let mut hash = 0xcbf29ce484222325u64;
+        for (p, a) in states {
+            for v in [p.x, p.y, *a].iter() {
+                for b in v.to_bits().to_le_bytes().iter() {
+                    hash = (hash ^ *b as u64).wrapping_mul(0x100000001b3);
+                }
+            }
+        }
+        hash
+    }
+
+    #[test]
+    fn test_fixed_point_world() {
+        use xmath::RealConverter;
+
+        let a = run_scene::<xfixedpoint::FP>(240);
+        let b = run_scene::<xfixedpoint::FP>(240);
+        assert!(a.iter().zip(&b).all(|(x, y)| x == y));
+        // 定点数不依赖平台的浮点实现，校验和在所有平台和编译模式下都一样
+        assert_eq!(scene_checksum(&a), 0x0f1725861f53cff3);
+
+        // 关节链不受碰撞影响，定点数的结果应该和浮点数接近
+        let c = run_scene::<f32>(240);
+        for (x, y) in a.iter().zip(&c).skip(c.len() - 14).skip(10).take(3) {
+            assert!((x.0.x.to_f32() - y.0.x).abs() < 0.01);
+            assert!((x.0.y.to_f32() - y.0.y).abs() < 0.01);
+            assert!((x.1.to_f32() - y.1).abs() < 0.01);
+        }
+    }
 }
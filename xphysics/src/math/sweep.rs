@@ -1,6 +1,7 @@
 use xmath::{Multiply, Real, Rotation, Transform, Vector2};
 
 #[derive(Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sweep<T> {
     pub local_center: Vector2<T>,
     pub c0: Vector2<T>,